@@ -18,6 +18,7 @@ use rand::thread_rng;
 use rug::rand::RandState;
 use rug::Integer;
 use std::cell::RefCell;
+use std::sync::Arc;
 
 const LARGE_PRIMES: [u64; 3] = [
     12_702_637_924_034_044_211,
@@ -75,23 +76,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     assert_eq!(Rsa2048::exp(&w, &value), acc);
 
     let proof_transcript = RefCell::new(Transcript::new(b"membership"));
-    crs.crs_hash_to_prime.hash_to_prime_parameters.transcript = Some(proof_transcript.clone());
+    Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
+        Some(proof_transcript.clone());
     let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
-    let statement = Statement {
-        c_e_q: commitment,
-        c_p: acc.clone(),
-    };
+    let statement = Statement::new(acc.clone(), commitment).unwrap();
     protocol
         .prove(
             &mut verifier_channel,
             &mut rng1,
             &mut rng2,
             &statement,
-            &Witness {
-                e: value.clone(),
-                r_q: randomness.clone(),
-                w: w.clone(),
-            },
+            &Witness::new(value.clone(), randomness.clone(), w.clone()).unwrap(),
         )
         .unwrap();
     let proof = verifier_channel.proof().unwrap();
@@ -100,7 +95,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         proof.proof_hash_to_prime.serialized_size()
     );
     let verification_transcript = RefCell::new(Transcript::new(b"membership"));
-    crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+    Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
         Some(verification_transcript.clone());
     let mut prover_channel = TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
     protocol.verify(&mut prover_channel, &statement).unwrap();
@@ -108,24 +103,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("membership_bp_60 protocol proving", |b| {
         b.iter(|| {
             let proof_transcript = RefCell::new(Transcript::new(b"membership"));
-            crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+            Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
                 Some(proof_transcript.clone());
             let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
-            let statement = Statement {
-                c_e_q: commitment,
-                c_p: acc.clone(),
-            };
+            let statement = Statement::new(acc.clone(), commitment).unwrap();
             protocol
                 .prove(
                     &mut verifier_channel,
                     &mut rng1,
                     &mut rng2,
                     &statement,
-                    &Witness {
-                        e: value.clone(),
-                        r_q: randomness.clone(),
-                        w: w.clone(),
-                    },
+                    &Witness::new(value.clone(), randomness.clone(), w.clone()).unwrap(),
                 )
                 .unwrap();
         })
@@ -133,7 +121,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("membership_bp_60 protocol verification", |b| {
         b.iter(|| {
             let verification_transcript = RefCell::new(Transcript::new(b"membership"));
-            crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+            Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
                 Some(verification_transcript.clone());
             let mut prover_channel =
                 TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
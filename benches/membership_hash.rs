@@ -92,21 +92,14 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     let proof_transcript = RefCell::new(Transcript::new(b"membership"));
     let mut verifier_channel = TranscriptVerifierChannel::new(&protocol.crs, &proof_transcript);
-    let statement = Statement {
-        c_e_q: commitment,
-        c_p: acc.clone(),
-    };
+    let statement = Statement::new(acc.clone(), commitment).unwrap();
     protocol
         .prove(
             &mut verifier_channel,
             &mut rng1,
             &mut rng2,
             &statement,
-            &Witness {
-                e: value.clone(),
-                r_q: randomness.clone(),
-                w: w.clone(),
-            },
+            &Witness::new(value.clone(), randomness.clone(), w.clone()).unwrap(),
         )
         .unwrap();
     let proof = verifier_channel.proof().unwrap();
@@ -121,21 +114,14 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             let proof_transcript = RefCell::new(Transcript::new(b"membership"));
             let mut verifier_channel =
                 TranscriptVerifierChannel::new(&protocol.crs, &proof_transcript);
-            let statement = Statement {
-                c_e_q: commitment,
-                c_p: acc.clone(),
-            };
+            let statement = Statement::new(acc.clone(), commitment).unwrap();
             protocol
                 .prove(
                     &mut verifier_channel,
                     &mut rng1,
                     &mut rng2,
                     &statement,
-                    &Witness {
-                        e: value.clone(),
-                        r_q: randomness.clone(),
-                        w: w.clone(),
-                    },
+                    &Witness::new(value.clone(), randomness.clone(), w.clone()).unwrap(),
                 )
                 .unwrap();
         })
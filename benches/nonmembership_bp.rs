@@ -18,6 +18,7 @@ use rand::thread_rng;
 use rug::rand::RandState;
 use rug::Integer;
 use std::cell::RefCell;
+use std::sync::Arc;
 
 const LARGE_PRIMES: [u64; 3] = [
     12_702_637_924_034_044_211,
@@ -79,24 +80,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     );
 
     let proof_transcript = RefCell::new(Transcript::new(b"nonmembership"));
-    crs.crs_hash_to_prime.hash_to_prime_parameters.transcript = Some(proof_transcript.clone());
+    Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
+        Some(proof_transcript.clone());
     let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
-    let statement = Statement {
-        c_e_q: commitment,
-        c_p: acc.clone(),
-    };
+    let statement = Statement::new(acc.clone(), commitment).unwrap();
     protocol
         .prove(
             &mut verifier_channel,
             &mut rng1,
             &mut rng2,
             &statement,
-            &Witness {
-                e: value.clone(),
-                r_q: randomness.clone(),
-                d: d.clone(),
-                b: b.clone(),
-            },
+            &Witness::new(value.clone(), randomness.clone(), d.clone(), b.clone()).unwrap(),
         )
         .unwrap();
     let proof = verifier_channel.proof().unwrap();
@@ -105,7 +99,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         proof.proof_hash_to_prime.serialized_size()
     );
     let verification_transcript = RefCell::new(Transcript::new(b"nonmembership"));
-    crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+    Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
         Some(verification_transcript.clone());
     let mut prover_channel = TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
     protocol.verify(&mut prover_channel, &statement).unwrap();
@@ -113,25 +107,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("nonmembership_bp protocol proving", |be| {
         be.iter(|| {
             let proof_transcript = RefCell::new(Transcript::new(b"nonmembership"));
-            crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+            Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
                 Some(proof_transcript.clone());
             let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
-            let statement = Statement {
-                c_e_q: commitment,
-                c_p: acc.clone(),
-            };
+            let statement = Statement::new(acc.clone(), commitment).unwrap();
             protocol
                 .prove(
                     &mut verifier_channel,
                     &mut rng1,
                     &mut rng2,
                     &statement,
-                    &Witness {
-                        e: value.clone(),
-                        r_q: randomness.clone(),
-                        d: d.clone(),
-                        b: b.clone(),
-                    },
+                    &Witness::new(value.clone(), randomness.clone(), d.clone(), b.clone()).unwrap(),
                 )
                 .unwrap();
         })
@@ -139,7 +125,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("nonmembership_bp protocol verification", |be| {
         be.iter(|| {
             let verification_transcript = RefCell::new(Transcript::new(b"nonmembership"));
-            crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+            Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
                 Some(verification_transcript.clone());
             let mut prover_channel =
                 TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
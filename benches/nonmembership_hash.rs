@@ -97,22 +97,14 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     let proof_transcript = RefCell::new(Transcript::new(b"nonmembership"));
     let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
-    let statement = Statement {
-        c_e_q: commitment,
-        c_p: acc.clone(),
-    };
+    let statement = Statement::new(acc.clone(), commitment).unwrap();
     protocol
         .prove(
             &mut verifier_channel,
             &mut rng1,
             &mut rng2,
             &statement,
-            &Witness {
-                e: value.clone(),
-                r_q: randomness.clone(),
-                d: d.clone(),
-                b: b.clone(),
-            },
+            &Witness::new(value.clone(), randomness.clone(), d.clone(), b.clone()).unwrap(),
         )
         .unwrap();
     let proof = verifier_channel.proof().unwrap();
@@ -126,22 +118,14 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         be.iter(|| {
             let proof_transcript = RefCell::new(Transcript::new(b"nonmembership"));
             let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
-            let statement = Statement {
-                c_e_q: commitment,
-                c_p: acc.clone(),
-            };
+            let statement = Statement::new(acc.clone(), commitment).unwrap();
             protocol
                 .prove(
                     &mut verifier_channel,
                     &mut rng1,
                     &mut rng2,
                     &statement,
-                    &Witness {
-                        e: value.clone(),
-                        r_q: randomness.clone(),
-                        d: d.clone(),
-                        b: b.clone(),
-                    },
+                    &Witness::new(value.clone(), randomness.clone(), d.clone(), b.clone()).unwrap(),
                 )
                 .unwrap();
         })
@@ -0,0 +1,36 @@
+//! Accumulator state management, producing the witnesses consumed by the
+//! protocols.
+use rug::Integer;
+use thiserror::Error;
+
+#[cfg(feature = "arkworks")]
+pub mod pairing;
+pub mod universal;
+
+#[derive(Debug, Error)]
+pub enum AccumulatorError {
+    #[error("the element is already in the accumulator")]
+    ElementAlreadyPresent,
+    #[error("the element is not in the accumulator")]
+    ElementNotFound,
+    #[error("the element's witness is not tracked")]
+    ElementNotTracked,
+    #[error("the element is not coprime to the accumulated product")]
+    ElementNotCoprime,
+    #[error("the accumulator is full")]
+    CapacityExceeded,
+    #[error("could not convert {0}")]
+    IntegerError(Integer),
+}
+
+impl From<Integer> for AccumulatorError {
+    fn from(err: Integer) -> Self {
+        AccumulatorError::IntegerError(err)
+    }
+}
+
+pub(crate) fn product(elements: &[Integer]) -> Integer {
+    elements
+        .iter()
+        .fold(Integer::from(1), |product, e| product * e)
+}
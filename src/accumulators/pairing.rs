@@ -0,0 +1,87 @@
+//! A bilinear-map (Nguyen) accumulator, computed from the powers of the secret
+//! in the CRS, so it can hold at most as many elements as there are powers
+//! beyond the first.
+use crate::{
+    accumulators::AccumulatorError,
+    protocols::pairing::{commit_product, CRSPairing, Witness},
+    utils::integer_to_bigint_mod_q,
+};
+use ark_ec::PairingEngine;
+use rug::Integer;
+
+#[derive(Clone)]
+pub struct PairingAccumulator<E: PairingEngine> {
+    pub value: E::G1Projective,
+    powers: Vec<E::G1Projective>,
+    elements: Vec<Integer>,
+}
+
+impl<E: PairingEngine> PairingAccumulator<E> {
+    pub fn new(crs: &CRSPairing<E>) -> PairingAccumulator<E> {
+        PairingAccumulator {
+            value: crs.powers[0],
+            powers: crs.powers.clone(),
+            elements: vec![],
+        }
+    }
+
+    pub fn elements(&self) -> &[Integer] {
+        &self.elements
+    }
+
+    pub fn contains(&self, element: &Integer) -> bool {
+        self.elements.contains(element)
+    }
+
+    pub fn add(&mut self, element: &Integer) -> Result<(), AccumulatorError> {
+        if self.contains(element) {
+            return Err(AccumulatorError::ElementAlreadyPresent);
+        }
+        if self.elements.len() + 1 >= self.powers.len() {
+            return Err(AccumulatorError::CapacityExceeded);
+        }
+        self.elements.push(element.clone());
+        self.value = self.evaluate(&self.elements)?;
+        Ok(())
+    }
+
+    pub fn delete(&mut self, element: &Integer) -> Result<(), AccumulatorError> {
+        if !self.contains(element) {
+            return Err(AccumulatorError::ElementNotFound);
+        }
+        self.elements.retain(|e| e != element);
+        self.value = self.evaluate(&self.elements)?;
+        Ok(())
+    }
+
+    /// Returns the witness for the pairing membership protocol, given the
+    /// randomness of the Pedersen commitment to the element.
+    pub fn membership_witness(
+        &self,
+        element: &Integer,
+        r_q: &Integer,
+    ) -> Result<Witness<E>, AccumulatorError> {
+        if !self.contains(element) {
+            return Err(AccumulatorError::ElementNotFound);
+        }
+        let others = self
+            .elements
+            .iter()
+            .filter(|e| *e != element)
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(Witness {
+            e: element.clone(),
+            r_q: r_q.clone(),
+            w: self.evaluate(&others)?,
+        })
+    }
+
+    fn evaluate(&self, elements: &[Integer]) -> Result<E::G1Projective, AccumulatorError> {
+        let elements = elements
+            .iter()
+            .map(integer_to_bigint_mod_q::<E::G1Projective>)
+            .collect::<Result<Vec<_>, _>>()?;
+        commit_product(&self.powers, &elements).ok_or(AccumulatorError::CapacityExceeded)
+    }
+}
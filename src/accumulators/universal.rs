@@ -0,0 +1,256 @@
+//! A universal accumulator, maintaining both membership and non-membership
+//! witnesses for tracked elements.
+//!
+//! The accumulated elements are expected to be primes, i.e., the output of the
+//! hash-to-prime in case it's not the identity. Witnesses are recomputed from
+//! the accumulated set whenever it changes, which is the accumulator manager's
+//! view of the set.
+use crate::{
+    accumulators::{product, AccumulatorError},
+    protocols::{
+        membership::Witness as MembershipWitness, nonmembership::Witness as NonMembershipWitness,
+    },
+    utils::ConvertibleUnknownOrderGroup,
+};
+use rug::Integer;
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub enum TrackedWitness<G: ConvertibleUnknownOrderGroup> {
+    Membership { w: G::Elem },
+    NonMembership { d: G::Elem, b: Integer },
+}
+
+#[derive(Clone)]
+pub struct UniversalAccumulator<G: ConvertibleUnknownOrderGroup> {
+    pub value: G::Elem,
+    elements: Vec<Integer>,
+    tracked: HashMap<Integer, TrackedWitness<G>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> UniversalAccumulator<G> {
+    pub fn empty() -> UniversalAccumulator<G> {
+        UniversalAccumulator {
+            value: G::unknown_order_elem(),
+            elements: vec![],
+            tracked: HashMap::new(),
+        }
+    }
+
+    pub fn elements(&self) -> &[Integer] {
+        &self.elements
+    }
+
+    pub fn contains(&self, element: &Integer) -> bool {
+        self.elements.contains(element)
+    }
+
+    /// Adds the elements and updates the tracked witnesses. The whole batch is
+    /// checked first, so on an error the accumulator is left unchanged.
+    pub fn add(&mut self, elements: &[Integer]) -> Result<(), AccumulatorError> {
+        for (i, e) in elements.iter().enumerate() {
+            if self.contains(e) || elements[..i].contains(e) {
+                return Err(AccumulatorError::ElementAlreadyPresent);
+            }
+        }
+        let mut accumulated = self.elements.clone();
+        accumulated.extend_from_slice(elements);
+        self.update(G::exp(&self.value, &product(elements)), accumulated)
+    }
+
+    /// Deletes the elements and updates the tracked witnesses, leaving the
+    /// accumulator unchanged on an error, as `add` does.
+    pub fn delete(&mut self, elements: &[Integer]) -> Result<(), AccumulatorError> {
+        if elements.iter().any(|e| !self.contains(e)) {
+            return Err(AccumulatorError::ElementNotFound);
+        }
+        let remaining = self
+            .elements
+            .iter()
+            .filter(|e| !elements.contains(e))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.update(
+            G::exp(&G::unknown_order_elem(), &product(&remaining)),
+            remaining,
+        )
+    }
+
+    /// Starts maintaining a witness for the element, which may or may not be in
+    /// the accumulated set.
+    pub fn track(&mut self, element: &Integer) -> Result<(), AccumulatorError> {
+        let witness = self.compute_witness(element)?;
+        self.tracked.insert(element.clone(), witness);
+        Ok(())
+    }
+
+    pub fn untrack(&mut self, element: &Integer) {
+        self.tracked.remove(element);
+    }
+
+    pub fn witness(&self, element: &Integer) -> Option<&TrackedWitness<G>> {
+        self.tracked.get(element)
+    }
+
+    /// Returns the witness for the membership protocol, given the randomness of
+    /// the Pedersen commitment to the element.
+    pub fn membership_witness(
+        &self,
+        element: &Integer,
+        r_q: &Integer,
+    ) -> Result<MembershipWitness<G>, AccumulatorError> {
+        match self.tracked.get(element) {
+            Some(TrackedWitness::Membership { w }) => Ok(MembershipWitness {
+                e: element.clone(),
+                r_q: r_q.clone(),
+                w: w.clone(),
+            }),
+            Some(TrackedWitness::NonMembership { .. }) => Err(AccumulatorError::ElementNotFound),
+            None => Err(AccumulatorError::ElementNotTracked),
+        }
+    }
+
+    /// Returns the witness for the non-membership protocol, given the
+    /// randomness of the Pedersen commitment to the element.
+    pub fn nonmembership_witness(
+        &self,
+        element: &Integer,
+        r_q: &Integer,
+    ) -> Result<NonMembershipWitness<G>, AccumulatorError> {
+        match self.tracked.get(element) {
+            Some(TrackedWitness::NonMembership { d, b }) => Ok(NonMembershipWitness {
+                e: element.clone(),
+                r_q: r_q.clone(),
+                d: d.clone(),
+                b: b.clone(),
+            }),
+            Some(TrackedWitness::Membership { .. }) => Err(AccumulatorError::ElementAlreadyPresent),
+            None => Err(AccumulatorError::ElementNotTracked),
+        }
+    }
+
+    fn compute_witness(&self, element: &Integer) -> Result<TrackedWitness<G>, AccumulatorError> {
+        if self.contains(element) {
+            let others = self
+                .elements
+                .iter()
+                .filter(|e| *e != element)
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(TrackedWitness::Membership {
+                w: G::exp(&G::unknown_order_elem(), &product(&others)),
+            })
+        } else {
+            // a*e + b*x = 1, so (g^a)^e * acc^b = g.
+            let (gcd, a, b) = element
+                .clone()
+                .gcd_cofactors(product(&self.elements), Integer::new());
+            if gcd != 1 {
+                return Err(AccumulatorError::ElementNotCoprime);
+            }
+            Ok(TrackedWitness::NonMembership {
+                d: G::exp(&G::unknown_order_elem(), &a),
+                b,
+            })
+        }
+    }
+
+    /// Recomputes the tracked witnesses against the new set, and replaces the
+    /// state only once all of them succeeded.
+    fn update(&mut self, value: G::Elem, elements: Vec<Integer>) -> Result<(), AccumulatorError> {
+        let mut updated = UniversalAccumulator {
+            value,
+            elements,
+            tracked: HashMap::new(),
+        };
+        for element in self.tracked.keys() {
+            let witness = updated.compute_witness(element)?;
+            updated.tracked.insert(element.clone(), witness);
+        }
+        *self = updated;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TrackedWitness, UniversalAccumulator};
+    use accumulator::group::{Group, Rsa2048, UnknownOrderGroup};
+    use rug::Integer;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    fn assert_witness(accumulator: &UniversalAccumulator<Rsa2048>, element: &Integer) {
+        match accumulator.witness(element).unwrap() {
+            TrackedWitness::Membership { w } => {
+                assert!(accumulator.contains(element));
+                assert_eq!(Rsa2048::exp(w, element), accumulator.value);
+            }
+            TrackedWitness::NonMembership { d, b } => {
+                assert!(!accumulator.contains(element));
+                assert_eq!(
+                    Rsa2048::op(
+                        &Rsa2048::exp(d, element),
+                        &Rsa2048::exp(&accumulator.value, b)
+                    ),
+                    Rsa2048::unknown_order_elem()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tracked_witnesses() {
+        let primes = LARGE_PRIMES
+            .iter()
+            .map(|p| Integer::from(*p))
+            .collect::<Vec<_>>();
+        let mut accumulator = UniversalAccumulator::<Rsa2048>::empty();
+        accumulator.add(&primes[1..3]).unwrap();
+        accumulator.track(&primes[0]).unwrap();
+        accumulator.track(&primes[1]).unwrap();
+        assert_witness(&accumulator, &primes[0]);
+        assert_witness(&accumulator, &primes[1]);
+        accumulator
+            .nonmembership_witness(&primes[0], &Integer::from(5))
+            .unwrap();
+        accumulator
+            .membership_witness(&primes[1], &Integer::from(5))
+            .unwrap();
+
+        accumulator.add(&primes[0..1]).unwrap();
+        accumulator.delete(&primes[1..2]).unwrap();
+        assert_witness(&accumulator, &primes[0]);
+        assert_witness(&accumulator, &primes[1]);
+        accumulator
+            .membership_witness(&primes[0], &Integer::from(5))
+            .unwrap();
+        accumulator
+            .nonmembership_witness(&primes[1], &Integer::from(5))
+            .unwrap();
+        accumulator.add(&primes[0..1]).unwrap_err();
+
+        // A batch failing partway leaves the set and the witnesses unchanged.
+        let value = accumulator.value.clone();
+        accumulator
+            .add(&[primes[1].clone(), primes[1].clone()])
+            .unwrap_err();
+        let composite = primes[3].clone() * 3;
+        accumulator.track(&composite).unwrap();
+        accumulator
+            .add(&[primes[1].clone(), primes[3].clone()])
+            .unwrap_err();
+        assert_eq!(accumulator.value, value);
+        assert!(!accumulator.contains(&primes[1]));
+        assert_witness(&accumulator, &primes[1]);
+        assert!(matches!(
+            accumulator.witness(&composite),
+            Some(TrackedWitness::NonMembership { .. })
+        ));
+    }
+}
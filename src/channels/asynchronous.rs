@@ -0,0 +1,550 @@
+//! Async channels, so interactive deployments can run the protocols on tokio.
+//!
+//! `TokioVerifierChannel` and `TokioProverChannel` are the two ends of a
+//! connection carrying the membership messages, where the verifier samples the
+//! challenges. The protocols themselves are synchronous, so `BlockingChannel`
+//! adapts any async channel to the synchronous channel traits. It should be
+//! used from `tokio::task::spawn_blocking`, keeping the runtime threads free.
+use crate::{
+    channels::{random_challenge, wire::MembershipMessage, ChannelError},
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{
+            channel::{
+                AsyncHashToPrimeProverChannel, AsyncHashToPrimeVerifierChannel,
+                HashToPrimeProverChannel, HashToPrimeVerifierChannel,
+            },
+            HashToPrimeProtocol,
+        },
+        membership::channel::{
+            AsyncMembershipProverChannel, AsyncMembershipVerifierChannel, MembershipProverChannel,
+            MembershipVerifierChannel,
+        },
+        modeq::{
+            channel::{
+                AsyncModEqProverChannel, AsyncModEqVerifierChannel, ModEqProverChannel,
+                ModEqVerifierChannel,
+            },
+            Message1 as ModEqMessage1, Message2 as ModEqMessage2,
+        },
+        root::{
+            channel::{
+                AsyncRootProverChannel, AsyncRootVerifierChannel, RootProverChannel,
+                RootVerifierChannel,
+            },
+            Message1 as RootMessage1, Message2 as RootMessage2, Message3 as RootMessage3,
+            Message4 as RootMessage4,
+        },
+    },
+    utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
+};
+use async_trait::async_trait;
+use rug::Integer;
+use tokio::{
+    runtime::Handle,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+type Sender<G, P, HP> = UnboundedSender<MembershipMessage<G, P, HP>>;
+type Receiver<G, P, HP> = UnboundedReceiver<MembershipMessage<G, P, HP>>;
+
+/// The prover's end of the connection.
+pub struct TokioVerifierChannel<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    sender: Sender<G, P, HP>,
+    receiver: Receiver<G, P, HP>,
+}
+
+/// The verifier's end of the connection.
+pub struct TokioProverChannel<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    parameters: Parameters,
+    sender: Sender<G, P, HP>,
+    receiver: Receiver<G, P, HP>,
+}
+
+/// Creates a connected pair of channels for the membership protocol.
+pub fn membership_channels<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    parameters: &Parameters,
+) -> (TokioVerifierChannel<G, P, HP>, TokioProverChannel<G, P, HP>) {
+    let (prover_sender, verifier_receiver) = unbounded_channel();
+    let (verifier_sender, prover_receiver) = unbounded_channel();
+    (
+        TokioVerifierChannel {
+            sender: prover_sender,
+            receiver: prover_receiver,
+        },
+        TokioProverChannel {
+            parameters: parameters.clone(),
+            sender: verifier_sender,
+            receiver: verifier_receiver,
+        },
+    )
+}
+
+fn send<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    sender: &Sender<G, P, HP>,
+    message: MembershipMessage<G, P, HP>,
+) -> Result<(), ChannelError> {
+    sender.send(message).map_err(|_| ChannelError::CouldNotSend)
+}
+
+async fn receive<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    receiver: &mut Receiver<G, P, HP>,
+) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+    receiver.recv().await.ok_or(ChannelError::CouldNotReceive)
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    TokioVerifierChannel<G, P, HP>
+{
+    async fn receive_challenge_message(&mut self) -> Result<Integer, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::Challenge(c) => Ok(c),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    TokioProverChannel<G, P, HP>
+{
+    fn sample_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let c = random_challenge(self.parameters.security_soundness);
+        send(&self.sender, MembershipMessage::Challenge(c.clone()))?;
+        Ok(c)
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncMembershipVerifierChannel<G> for TokioVerifierChannel<G, P, HP>
+{
+    async fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        send(&self.sender, MembershipMessage::Epoch(epoch.to_vec()))
+    }
+    async fn send_c_e(&mut self, c_e: &G::Elem) -> Result<(), ChannelError> {
+        send(&self.sender, MembershipMessage::CE(c_e.clone()))
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncRootVerifierChannel<G> for TokioVerifierChannel<G, P, HP>
+{
+    async fn send_message1(&mut self, message: &RootMessage1<G>) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::RootMessage1(message.clone()),
+        )
+    }
+    async fn send_message2(&mut self, message: &RootMessage2<G>) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::RootMessage2(message.clone()),
+        )
+    }
+    async fn send_message3(&mut self, message: &RootMessage3) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::RootMessage3(message.clone()),
+        )
+    }
+    async fn send_message4(&mut self, message: &RootMessage4<G>) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::RootMessage4(message.clone()),
+        )
+    }
+    async fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive_challenge_message().await
+    }
+    async fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive_challenge_message().await
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncModEqVerifierChannel<G, P> for TokioVerifierChannel<G, P, HP>
+{
+    async fn send_message1(&mut self, message: &ModEqMessage1<G, P>) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::ModEqMessage1(message.clone()),
+        )
+    }
+    async fn send_message2(&mut self, message: &ModEqMessage2<P>) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::ModEqMessage2(message.clone()),
+        )
+    }
+    async fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive_challenge_message().await
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncHashToPrimeVerifierChannel<P, HP> for TokioVerifierChannel<G, P, HP>
+{
+    async fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), ChannelError> {
+        send(
+            &self.sender,
+            MembershipMessage::HashToPrimeProof(proof.clone()),
+        )
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncMembershipProverChannel<G> for TokioProverChannel<G, P, HP>
+{
+    /// Fails if the prover bound the proof to a different accumulator state.
+    async fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::Epoch(e) if e == epoch => Ok(()),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn receive_c_e(&mut self) -> Result<G::Elem, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::CE(c_e) => Ok(c_e),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncRootProverChannel<G> for TokioProverChannel<G, P, HP>
+{
+    async fn receive_message1(&mut self) -> Result<RootMessage1<G>, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::RootMessage1(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn receive_message2(&mut self) -> Result<RootMessage2<G>, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::RootMessage2(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn receive_message3(&mut self) -> Result<RootMessage3, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::RootMessage3(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn receive_message4(&mut self) -> Result<RootMessage4<G>, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::RootMessage4(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.sample_and_send_challenge()
+    }
+    async fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.sample_and_send_challenge()
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncModEqProverChannel<G, P> for TokioProverChannel<G, P, HP>
+{
+    async fn receive_message1(&mut self) -> Result<ModEqMessage1<G, P>, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::ModEqMessage1(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn receive_message2(&mut self) -> Result<ModEqMessage2<P>, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::ModEqMessage2(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    async fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.sample_and_send_challenge()
+    }
+}
+
+#[async_trait(?Send)]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    AsyncHashToPrimeProverChannel<P, HP> for TokioProverChannel<G, P, HP>
+{
+    async fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        match receive(&mut self.receiver).await? {
+            MembershipMessage::HashToPrimeProof(proof) => Ok(proof),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+/// Adapts an async channel to the synchronous channel traits, by blocking on
+/// the given runtime. Must not be used from within the runtime's own threads.
+pub struct BlockingChannel<C> {
+    inner: C,
+    handle: Handle,
+}
+
+impl<C> BlockingChannel<C> {
+    pub fn new(inner: C, handle: Handle) -> BlockingChannel<C> {
+        BlockingChannel { inner, handle }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: AsyncMembershipVerifierChannel<G>>
+    MembershipVerifierChannel<G> for BlockingChannel<C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.bind_epoch(epoch))
+    }
+    fn send_c_e(&mut self, c_e: &G::Elem) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_c_e(c_e))
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: AsyncMembershipProverChannel<G>> MembershipProverChannel<G>
+    for BlockingChannel<C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.bind_epoch(epoch))
+    }
+    fn receive_c_e(&mut self) -> Result<G::Elem, ChannelError> {
+        self.handle.block_on(self.inner.receive_c_e())
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: AsyncRootVerifierChannel<G>> RootVerifierChannel<G>
+    for BlockingChannel<C>
+{
+    fn send_message1(&mut self, message: &RootMessage1<G>) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_message1(message))
+    }
+    fn send_message2(&mut self, message: &RootMessage2<G>) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_message2(message))
+    }
+    fn send_message3(&mut self, message: &RootMessage3) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_message3(message))
+    }
+    fn send_message4(&mut self, message: &RootMessage4<G>) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_message4(message))
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.handle.block_on(self.inner.receive_challenge())
+    }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.handle.block_on(self.inner.receive_poe_challenge())
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: AsyncRootProverChannel<G>> RootProverChannel<G>
+    for BlockingChannel<C>
+{
+    fn receive_message1(&mut self) -> Result<RootMessage1<G>, ChannelError> {
+        self.handle.block_on(self.inner.receive_message1())
+    }
+    fn receive_message2(&mut self) -> Result<RootMessage2<G>, ChannelError> {
+        self.handle.block_on(self.inner.receive_message2())
+    }
+    fn receive_message3(&mut self) -> Result<RootMessage3, ChannelError> {
+        self.handle.block_on(self.inner.receive_message3())
+    }
+    fn receive_message4(&mut self) -> Result<RootMessage4<G>, ChannelError> {
+        self.handle.block_on(self.inner.receive_message4())
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.handle
+            .block_on(self.inner.generate_and_send_challenge())
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.handle
+            .block_on(self.inner.generate_and_send_poe_challenge())
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        C: AsyncModEqVerifierChannel<G, P>,
+    > ModEqVerifierChannel<G, P> for BlockingChannel<C>
+{
+    fn send_message1(&mut self, message: &ModEqMessage1<G, P>) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_message1(message))
+    }
+    fn send_message2(&mut self, message: &ModEqMessage2<P>) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_message2(message))
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.handle.block_on(self.inner.receive_challenge())
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        C: AsyncModEqProverChannel<G, P>,
+    > ModEqProverChannel<G, P> for BlockingChannel<C>
+{
+    fn receive_message1(&mut self) -> Result<ModEqMessage1<G, P>, ChannelError> {
+        self.handle.block_on(self.inner.receive_message1())
+    }
+    fn receive_message2(&mut self) -> Result<ModEqMessage2<P>, ChannelError> {
+        self.handle.block_on(self.inner.receive_message2())
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.handle
+            .block_on(self.inner.generate_and_send_challenge())
+    }
+}
+
+impl<
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: AsyncHashToPrimeVerifierChannel<P, HP>,
+    > HashToPrimeVerifierChannel<P, HP> for BlockingChannel<C>
+{
+    fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), ChannelError> {
+        self.handle.block_on(self.inner.send_proof(proof))
+    }
+}
+
+impl<
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: AsyncHashToPrimeProverChannel<P, HP>,
+    > HashToPrimeProverChannel<P, HP> for BlockingChannel<C>
+{
+    fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        self.handle.block_on(self.inner.receive_proof())
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{membership_channels, BlockingChannel};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use tokio::runtime::Handle;
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tokio_membership() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let (verifier_channel, prover_channel) = membership_channels(&crs.parameters);
+        let prover_statement = Statement {
+            c_e_q: commitment,
+            c_p: acc.clone(),
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        let verifier_statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+
+        let handle = Handle::current();
+        let prover = tokio::task::spawn_blocking(move || {
+            let mut rng1 = RandState::new();
+            rng1.seed(&Integer::from(17));
+            let mut verifier_channel = BlockingChannel::new(verifier_channel, handle);
+            protocol
+                .prove(
+                    &mut verifier_channel,
+                    &mut rng1,
+                    &mut thread_rng(),
+                    &prover_statement,
+                    &Witness {
+                        e: value,
+                        r_q: randomness,
+                        w,
+                    },
+                )
+                .unwrap();
+        });
+        let handle = Handle::current();
+        let verifier = tokio::task::spawn_blocking(move || {
+            let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+            let mut prover_channel = BlockingChannel::new(prover_channel, handle);
+            protocol
+                .verify(&mut prover_channel, &verifier_statement)
+                .unwrap();
+        });
+        prover.await.unwrap();
+        verifier.await.unwrap();
+    }
+}
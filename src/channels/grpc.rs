@@ -0,0 +1,421 @@
+//! Runs the membership protocol over a bidirectional gRPC stream.
+//!
+//! The messages are defined in `proto/membership.proto`. The verifier serves
+//! `MembershipChannelService`, which hands every incoming stream to the
+//! application as a `GrpcTransport`, and the prover opens a stream with
+//! `connect`. The transports are used from blocking tasks, as in the
+//! [asynchronous](super::asynchronous) module.
+use crate::{
+    channels::{
+        handshake::Handshake,
+        transport::{MessageTransport, TransportProverChannel, TransportVerifierChannel},
+        wire::{scalar_from_bytes, MembershipMessage},
+        ChannelError,
+    },
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        modeq::{Message1 as ModEqMessage1, Message2 as ModEqMessage2},
+        root::{
+            Message1 as RootMessage1, Message2 as RootMessage2, Message3 as RootMessage3,
+            Message4 as RootMessage4,
+        },
+    },
+    utils::{
+        bigint_to_bytes, bytes_to_integer, curve::CurvePointProjective, group::ElemFromBytes,
+        integer_to_bytes, ConvertibleUnknownOrderGroup,
+    },
+};
+use rug::Integer;
+use std::convert::TryInto;
+use tokio::{runtime::Handle, sync::mpsc};
+use tokio_stream::{wrappers::UnboundedReceiverStream, StreamExt};
+use tonic::{transport::Endpoint, Request, Response, Status, Streaming};
+
+pub mod proto {
+    tonic::include_proto!("cpsnarks_set.membership");
+}
+
+use proto::{
+    frame::Message,
+    membership_channel_client::MembershipChannelClient,
+    membership_channel_server::{MembershipChannel, MembershipChannelServer},
+    Frame,
+};
+
+pub const PROTO_VERSION: u32 = 1;
+
+fn integer_to_proto(integer: &Integer) -> proto::Integer {
+    proto::Integer {
+        negative: *integer < 0,
+        magnitude: integer_to_bytes(&Integer::from(integer.abs_ref())),
+    }
+}
+
+fn integer_from_proto(integer: Option<proto::Integer>) -> Result<Integer, ChannelError> {
+    let integer = integer.ok_or(ChannelError::MalformedMessage)?;
+    let magnitude = bytes_to_integer(&integer.magnitude);
+    Ok(if integer.negative {
+        -magnitude
+    } else {
+        magnitude
+    })
+}
+
+fn elem_from_proto<G: ElemFromBytes>(bytes: &[u8]) -> Result<G::Elem, ChannelError> {
+    G::elem_from_bytes(bytes).ok_or(ChannelError::MalformedMessage)
+}
+
+pub fn message_to_frame<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    message: &MembershipMessage<G, P, HP>,
+) -> Result<Frame, ChannelError> {
+    let message = match message {
+        MembershipMessage::Epoch(epoch) => Message::Epoch(proto::Epoch {
+            epoch: epoch.clone(),
+        }),
+        MembershipMessage::CE(c_e) => Message::ElementCommitment(proto::ElementCommitment {
+            c_e: G::elem_to_bytes(c_e),
+        }),
+        MembershipMessage::RootMessage1(message) => Message::RootMessage1(proto::RootMessage1 {
+            c_w: G::elem_to_bytes(&message.c_w),
+            c_r: G::elem_to_bytes(&message.c_r),
+        }),
+        MembershipMessage::RootMessage2(message) => Message::RootMessage2(proto::RootMessage2 {
+            alpha1: G::elem_to_bytes(&message.alpha1),
+            alpha2: G::elem_to_bytes(&message.alpha2),
+            alpha3: G::elem_to_bytes(&message.alpha3),
+            alpha4: G::elem_to_bytes(&message.alpha4),
+        }),
+        MembershipMessage::RootMessage3(message) => Message::RootMessage3(proto::RootMessage3 {
+            s_e: Some(integer_to_proto(&message.s_e)),
+            s_r: Some(integer_to_proto(&message.s_r)),
+            s_r_2: Some(integer_to_proto(&message.s_r_2)),
+            s_r_3: Some(integer_to_proto(&message.s_r_3)),
+            s_beta: Some(integer_to_proto(&message.s_beta)),
+            s_delta: Some(integer_to_proto(&message.s_delta)),
+        }),
+        MembershipMessage::RootMessage4(message) => Message::RootMessage4(proto::RootMessage4 {
+            q1: G::elem_to_bytes(&message.q1),
+            q2: G::elem_to_bytes(&message.q2),
+            q3: G::elem_to_bytes(&message.q3),
+            q4: G::elem_to_bytes(&message.q4),
+        }),
+        MembershipMessage::ModEqMessage1(message) => Message::ModEqMessage1(proto::ModEqMessage1 {
+            alpha1: G::elem_to_bytes(&message.alpha1),
+            alpha2: message.alpha2.to_affine_bytes()?,
+        }),
+        MembershipMessage::ModEqMessage2(message) => Message::ModEqMessage2(proto::ModEqMessage2 {
+            s_e: Some(integer_to_proto(&message.s_e)),
+            s_r: Some(integer_to_proto(&message.s_r)),
+            s_r_q: bigint_to_bytes::<P>(&message.s_r_q),
+        }),
+        MembershipMessage::HashToPrimeProof(proof) => {
+            Message::HashToPrimeProof(proto::HashToPrimeProof {
+                proof: HP::proof_to_bytes(proof)?,
+            })
+        }
+        MembershipMessage::Challenge(c) => Message::Challenge(proto::Challenge {
+            c: Some(integer_to_proto(c)),
+        }),
+        MembershipMessage::Handshake(handshake) => Message::Handshake(proto::Handshake {
+            version: handshake.version as u32,
+            parameters_fingerprint: handshake.parameters_fingerprint.to_vec(),
+            group: handshake.group.clone(),
+            curve: handshake.curve.clone(),
+            hash_to_prime: handshake.hash_to_prime.clone(),
+        }),
+    };
+    Ok(Frame {
+        version: PROTO_VERSION,
+        message: Some(message),
+    })
+}
+
+pub fn frame_to_message<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    frame: Frame,
+) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+    if frame.version != PROTO_VERSION {
+        return Err(ChannelError::UnsupportedVersion(
+            frame.version.min(u8::MAX as u32) as u8,
+        ));
+    }
+    Ok(match frame.message.ok_or(ChannelError::MalformedMessage)? {
+        Message::Epoch(epoch) => MembershipMessage::Epoch(epoch.epoch),
+        Message::ElementCommitment(c_e) => MembershipMessage::CE(elem_from_proto::<G>(&c_e.c_e)?),
+        Message::RootMessage1(message) => MembershipMessage::RootMessage1(RootMessage1 {
+            c_w: elem_from_proto::<G>(&message.c_w)?,
+            c_r: elem_from_proto::<G>(&message.c_r)?,
+        }),
+        Message::RootMessage2(message) => MembershipMessage::RootMessage2(RootMessage2 {
+            alpha1: elem_from_proto::<G>(&message.alpha1)?,
+            alpha2: elem_from_proto::<G>(&message.alpha2)?,
+            alpha3: elem_from_proto::<G>(&message.alpha3)?,
+            alpha4: elem_from_proto::<G>(&message.alpha4)?,
+        }),
+        Message::RootMessage3(message) => MembershipMessage::RootMessage3(RootMessage3 {
+            s_e: integer_from_proto(message.s_e)?,
+            s_r: integer_from_proto(message.s_r)?,
+            s_r_2: integer_from_proto(message.s_r_2)?,
+            s_r_3: integer_from_proto(message.s_r_3)?,
+            s_beta: integer_from_proto(message.s_beta)?,
+            s_delta: integer_from_proto(message.s_delta)?,
+        }),
+        Message::RootMessage4(message) => MembershipMessage::RootMessage4(RootMessage4 {
+            q1: elem_from_proto::<G>(&message.q1)?,
+            q2: elem_from_proto::<G>(&message.q2)?,
+            q3: elem_from_proto::<G>(&message.q3)?,
+            q4: elem_from_proto::<G>(&message.q4)?,
+        }),
+        Message::ModEqMessage1(message) => MembershipMessage::ModEqMessage1(ModEqMessage1 {
+            alpha1: elem_from_proto::<G>(&message.alpha1)?,
+            alpha2: P::from_affine_bytes(&message.alpha2)?,
+        }),
+        Message::ModEqMessage2(message) => MembershipMessage::ModEqMessage2(ModEqMessage2 {
+            s_e: integer_from_proto(message.s_e)?,
+            s_r: integer_from_proto(message.s_r)?,
+            s_r_q: scalar_from_bytes::<P>(&message.s_r_q)?,
+        }),
+        Message::HashToPrimeProof(proof) => {
+            MembershipMessage::HashToPrimeProof(HP::proof_from_bytes(&proof.proof)?)
+        }
+        Message::Challenge(challenge) => {
+            MembershipMessage::Challenge(integer_from_proto(challenge.c)?)
+        }
+        Message::Handshake(handshake) => MembershipMessage::Handshake(Handshake {
+            version: handshake
+                .version
+                .try_into()
+                .map_err(|_| ChannelError::MalformedMessage)?,
+            parameters_fingerprint: handshake
+                .parameters_fingerprint
+                .as_slice()
+                .try_into()
+                .map_err(|_| ChannelError::MalformedMessage)?,
+            group: handshake.group,
+            curve: handshake.curve,
+            hash_to_prime: handshake.hash_to_prime,
+        }),
+    })
+}
+
+/// One end of an `Exchange` stream. Since the channel traits are synchronous,
+/// it has to be used outside of the runtime, e.g., from
+/// `tokio::task::spawn_blocking`.
+pub struct GrpcTransport {
+    outgoing: mpsc::UnboundedSender<Result<Frame, Status>>,
+    incoming: Streaming<Frame>,
+    handle: Handle,
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MessageTransport<G, P, HP> for GrpcTransport
+{
+    fn send(&mut self, message: MembershipMessage<G, P, HP>) -> Result<(), ChannelError> {
+        self.outgoing
+            .send(Ok(message_to_frame(&message)?))
+            .map_err(|_| ChannelError::CouldNotSend)
+    }
+
+    fn receive(&mut self) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+        let frame = self
+            .handle
+            .block_on(self.incoming.message())
+            .map_err(|_| ChannelError::CouldNotReceive)?
+            .ok_or(ChannelError::CouldNotReceive)?;
+        frame_to_message(frame)
+    }
+}
+
+/// Opens an `Exchange` stream to the verifier, giving the prover's end.
+pub async fn connect(endpoint: Endpoint) -> Result<GrpcTransport, ChannelError> {
+    let channel = endpoint
+        .connect()
+        .await
+        .map_err(|_| ChannelError::CouldNotSend)?;
+    let (outgoing, receiver) = mpsc::unbounded_channel();
+    let requests = UnboundedReceiverStream::new(receiver).filter_map(Result::ok);
+    let incoming = MembershipChannelClient::new(channel)
+        .exchange(requests)
+        .await
+        .map_err(|_| ChannelError::CouldNotReceive)?
+        .into_inner();
+    Ok(GrpcTransport {
+        outgoing,
+        incoming,
+        handle: Handle::current(),
+    })
+}
+
+/// The verifier's service. Each `Exchange` stream is passed on as a
+/// `GrpcTransport`, on which the application runs the verification.
+pub struct MembershipChannelService {
+    sessions: mpsc::UnboundedSender<GrpcTransport>,
+    handle: Handle,
+}
+
+impl MembershipChannelService {
+    /// Must be called from within the runtime serving the service.
+    pub fn new() -> (
+        MembershipChannelServer<MembershipChannelService>,
+        mpsc::UnboundedReceiver<GrpcTransport>,
+    ) {
+        let (sessions, receiver) = mpsc::unbounded_channel();
+        let service = MembershipChannelService {
+            sessions,
+            handle: Handle::current(),
+        };
+        (MembershipChannelServer::new(service), receiver)
+    }
+}
+
+#[tonic::async_trait]
+impl MembershipChannel for MembershipChannelService {
+    type ExchangeStream = UnboundedReceiverStream<Result<Frame, Status>>;
+
+    async fn exchange(
+        &self,
+        request: Request<Streaming<Frame>>,
+    ) -> Result<Response<Self::ExchangeStream>, Status> {
+        let (outgoing, receiver) = mpsc::unbounded_channel();
+        self.sessions
+            .send(GrpcTransport {
+                outgoing,
+                incoming: request.into_inner(),
+                handle: self.handle.clone(),
+            })
+            .map_err(|_| Status::unavailable("the verifier is not accepting sessions"))?;
+        Ok(Response::new(UnboundedReceiverStream::new(receiver)))
+    }
+}
+
+/// The prover's end of a gRPC stream.
+pub type GrpcVerifierChannel<G, P, HP> = TransportVerifierChannel<G, P, HP, GrpcTransport>;
+/// The verifier's end of a gRPC stream.
+pub type GrpcProverChannel<G, P, HP> = TransportProverChannel<G, P, HP, GrpcTransport>;
+
+#[cfg(test)]
+mod test {
+    use super::{connect, GrpcProverChannel, GrpcVerifierChannel, MembershipChannelService};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::{Endpoint, Server};
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_grpc_membership() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let prover_statement = Statement {
+            c_e_q: commitment,
+            c_p: acc.clone(),
+            epoch: None,
+        };
+        let verifier_statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: None,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (service, mut sessions) = MembershipChannelService::new();
+        tokio::spawn(
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let verifier = tokio::spawn(async move {
+            let transport = sessions.recv().await.unwrap();
+            tokio::task::spawn_blocking(move || {
+                let protocol =
+                    Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+                let mut prover_channel = GrpcProverChannel::new(&crs.parameters, transport);
+                protocol
+                    .verify(&mut prover_channel, &verifier_statement)
+                    .unwrap();
+            })
+            .await
+            .unwrap();
+        });
+
+        let endpoint = Endpoint::from_shared(format!("http://{}", address)).unwrap();
+        let transport = connect(endpoint).await.unwrap();
+        tokio::task::spawn_blocking(move || {
+            let mut rng1 = RandState::new();
+            rng1.seed(&Integer::from(17));
+            let mut verifier_channel = GrpcVerifierChannel::new(transport);
+            protocol
+                .prove(
+                    &mut verifier_channel,
+                    &mut rng1,
+                    &mut thread_rng(),
+                    &prover_statement,
+                    &Witness {
+                        e: value,
+                        r_q: randomness,
+                        w,
+                    },
+                )
+                .unwrap();
+        })
+        .await
+        .unwrap();
+        verifier.await.unwrap();
+    }
+}
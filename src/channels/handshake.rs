@@ -0,0 +1,100 @@
+//! A handshake before the first message of the channel protocols.
+//!
+//! The prover sends its `Handshake` and the verifier answers with its own.
+//! Each side checks that the other runs the same protocol version with the
+//! same parameters and backends, so mismatched builds fail with a clear
+//! `ChannelError` instead of a failed verification.
+use crate::{
+    channels::ChannelError, parameters::Parameters, protocols::hash_to_prime::HashToPrimeProtocol,
+    utils::curve::CurvePointProjective,
+};
+use std::any::type_name;
+
+pub const PROTOCOL_VERSION: u16 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Handshake {
+    pub version: u16,
+    pub parameters_fingerprint: [u8; 32],
+    /// The group of unknown order.
+    pub group: String,
+    pub curve: String,
+    pub hash_to_prime: String,
+}
+
+impl Handshake {
+    pub fn new<G, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+        parameters: &Parameters,
+    ) -> Handshake {
+        Handshake {
+            version: PROTOCOL_VERSION,
+            parameters_fingerprint: parameters.fingerprint(),
+            group: type_name::<G>().to_string(),
+            curve: type_name::<P>().to_string(),
+            hash_to_prime: type_name::<HP>().to_string(),
+        }
+    }
+
+    /// Checks the handshake received from the other side against ours.
+    pub fn check(&self, theirs: &Handshake) -> Result<(), ChannelError> {
+        if self.version != theirs.version {
+            return Err(ChannelError::VersionMismatch(self.version, theirs.version));
+        }
+        if self.parameters_fingerprint != theirs.parameters_fingerprint {
+            return Err(ChannelError::ParametersMismatch);
+        }
+        for (ours, theirs) in [
+            (&self.group, &theirs.group),
+            (&self.curve, &theirs.curve),
+            (&self.hash_to_prime, &theirs.hash_to_prime),
+        ]
+        .iter()
+        {
+            if ours != theirs {
+                return Err(ChannelError::BackendMismatch(
+                    ours.to_string(),
+                    theirs.to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Handshake;
+    use crate::{
+        channels::ChannelError, parameters::Parameters,
+        protocols::hash_to_prime::snark_range::Protocol as HPProtocol,
+    };
+    use accumulator::group::{ClassGroup, Rsa2048};
+    use ark_bls12_381::{Bls12_381, G1Projective};
+
+    #[test]
+    fn test_check() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let ours = Handshake::new::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(&params);
+        ours.check(&ours.clone()).unwrap();
+
+        let mut theirs = ours.clone();
+        theirs.version += 1;
+        assert!(matches!(
+            ours.check(&theirs),
+            Err(ChannelError::VersionMismatch(1, 2))
+        ));
+
+        let other_params = Parameters::from_security_level(80).unwrap();
+        let theirs = Handshake::new::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(&other_params);
+        assert!(matches!(
+            ours.check(&theirs),
+            Err(ChannelError::ParametersMismatch)
+        ));
+
+        let theirs = Handshake::new::<ClassGroup, G1Projective, HPProtocol<Bls12_381>>(&params);
+        assert!(matches!(
+            ours.check(&theirs),
+            Err(ChannelError::BackendMismatch(_, _))
+        ));
+    }
+}
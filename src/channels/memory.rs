@@ -0,0 +1,196 @@
+//! Runs the membership protocol interactively between two threads.
+//!
+//! The verifier samples its challenges from a `ChallengeSource` and sends them
+//! to the prover as the protocol goes, instead of both sides deriving them
+//! from a transcript. This is useful to compare the interactive protocol with
+//! its Fiat-Shamir transform, and for designated-verifier settings where the
+//! proof shouldn't convince anyone else.
+use crate::{
+    channels::{
+        transport::{MessageTransport, TransportProverChannel, TransportVerifierChannel},
+        wire::MembershipMessage,
+        ChallengeSource, ChannelError, RandomChallenges,
+    },
+    parameters::Parameters,
+    protocols::hash_to_prime::HashToPrimeProtocol,
+    utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
+};
+use rand::rngs::OsRng;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+pub struct MemoryTransport<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    outgoing: Sender<MembershipMessage<G, P, HP>>,
+    incoming: Receiver<MembershipMessage<G, P, HP>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MessageTransport<G, P, HP> for MemoryTransport<G, P, HP>
+{
+    fn send(&mut self, message: MembershipMessage<G, P, HP>) -> Result<(), ChannelError> {
+        self.outgoing
+            .send(message)
+            .map_err(|_| ChannelError::CouldNotSend)
+    }
+
+    fn receive(&mut self) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+        self.incoming
+            .recv()
+            .map_err(|_| ChannelError::CouldNotReceive)
+    }
+}
+
+/// Returns two connected transports.
+pub fn memory_transports<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>() -> (MemoryTransport<G, P, HP>, MemoryTransport<G, P, HP>) {
+    let (sender1, receiver1) = channel();
+    let (sender2, receiver2) = channel();
+    (
+        MemoryTransport {
+            outgoing: sender1,
+            incoming: receiver2,
+        },
+        MemoryTransport {
+            outgoing: sender2,
+            incoming: receiver1,
+        },
+    )
+}
+
+/// The prover's end of an in-process channel.
+pub type MemoryVerifierChannel<G, P, HP> =
+    TransportVerifierChannel<G, P, HP, MemoryTransport<G, P, HP>>;
+/// The verifier's end of an in-process channel.
+pub type MemoryProverChannel<G, P, HP, S = RandomChallenges<OsRng>> =
+    TransportProverChannel<G, P, HP, MemoryTransport<G, P, HP>, S>;
+
+/// Returns the prover's and the verifier's ends of an interactive run, with
+/// the verifier drawing its challenges from `challenges`.
+pub fn interactive_channels<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    S: ChallengeSource,
+>(
+    parameters: &Parameters,
+    challenges: S,
+) -> (
+    MemoryVerifierChannel<G, P, HP>,
+    MemoryProverChannel<G, P, HP, S>,
+) {
+    let (prover_transport, verifier_transport) = memory_transports();
+    (
+        TransportVerifierChannel::new(prover_transport),
+        TransportProverChannel::with_challenge_source(parameters, verifier_transport, challenges),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::interactive_channels;
+    use crate::{
+        channels::RandomChallenges,
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::{rngs::StdRng, thread_rng, SeedableRng};
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_interactive_membership() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc.clone(),
+            epoch: None,
+        };
+        let verifier_statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: None,
+        };
+
+        let (mut verifier_channel, mut prover_channel) =
+            interactive_channels::<Rsa2048, G1Projective, HPProtocol<Bls12_381>, _>(
+                &crs.parameters,
+                RandomChallenges::new(StdRng::seed_from_u64(7)),
+            );
+        let verifier = std::thread::spawn(move || {
+            let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+            prover_channel.handshake().unwrap();
+            protocol
+                .verify(&mut prover_channel, &verifier_statement)
+                .unwrap();
+        });
+
+        verifier_channel.handshake(&params).unwrap();
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        verifier.join().unwrap();
+    }
+}
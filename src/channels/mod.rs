@@ -0,0 +1,112 @@
+//! Channels provide a way for protocol to act as interactive protocol.
+//!
+//! Each protocol defines the messages the prover and verifiers send, such that
+//! the prover receives a verifier channel and the prover receives a verifier
+//! channel.
+use crate::{transcript::TranscriptProtocolChallenge, utils::curve::CurveError};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use rug::integer::Order;
+use rug::Integer;
+use std::cell::{BorrowError, BorrowMutError};
+use thiserror::Error;
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod handshake;
+pub mod memory;
+pub mod stats;
+pub mod streaming;
+pub mod tcp;
+pub mod trace;
+pub mod transport;
+pub mod wire;
+
+#[derive(Debug, Error)]
+pub enum ChannelError {
+    #[error("could not send a message")]
+    CouldNotSend,
+    #[error("could not receive a message")]
+    CouldNotReceive,
+    #[error("received an unexpected message")]
+    UnexpectedMessage,
+    #[error("received a malformed message")]
+    MalformedMessage,
+    #[error("unsupported message version {0}")]
+    UnsupportedVersion(u8),
+    #[error("protocol version mismatch: ours is {0}, theirs is {1}")]
+    VersionMismatch(u16, u16),
+    #[error("the parties use different parameters")]
+    ParametersMismatch,
+    #[error("backend mismatch: ours is {0}, theirs is {1}")]
+    BackendMismatch(String, String),
+    #[error("I/O error on the channel")]
+    IoError(#[from] std::io::Error),
+    #[error("could not borrow the channel")]
+    CouldNotBorrow(#[from] BorrowError),
+    #[error("could not borrow the channel mutably")]
+    CouldNotBorrowMut(#[from] BorrowMutError),
+    #[error("could not encode or decode a curve point")]
+    CurveError(#[from] CurveError),
+}
+
+/// Samples a challenge in the verifier, for the channels that run the
+/// protocols interactively.
+pub(crate) fn random_challenge(length_in_bits: u16) -> Integer {
+    let mut buf = vec![0u8; (length_in_bits / 8) as usize];
+    OsRng.fill_bytes(&mut buf);
+    Integer::from_digits(&buf[..], Order::MsfBe)
+}
+
+/// The source of the verifier's challenges. When the protocols run
+/// interactively the verifier samples them at random, while under the
+/// Fiat-Shamir transform they are derived from the transcript, so every
+/// transcript is a challenge source as well.
+pub trait ChallengeSource {
+    fn challenge(
+        &mut self,
+        label: &'static [u8],
+        length_in_bits: u16,
+    ) -> Result<Integer, ChannelError>;
+}
+
+/// Samples the challenges from a cryptographic RNG, e.g., for a designated
+/// verifier that doesn't want the proof to be transferable.
+pub struct RandomChallenges<R: RngCore + CryptoRng> {
+    rng: R,
+}
+
+impl<R: RngCore + CryptoRng> RandomChallenges<R> {
+    pub fn new(rng: R) -> RandomChallenges<R> {
+        RandomChallenges { rng }
+    }
+}
+
+impl Default for RandomChallenges<OsRng> {
+    fn default() -> Self {
+        RandomChallenges { rng: OsRng }
+    }
+}
+
+impl<R: RngCore + CryptoRng> ChallengeSource for RandomChallenges<R> {
+    fn challenge(
+        &mut self,
+        _: &'static [u8],
+        length_in_bits: u16,
+    ) -> Result<Integer, ChannelError> {
+        let mut buf = vec![0u8; (length_in_bits / 8) as usize];
+        self.rng.fill_bytes(&mut buf);
+        Ok(Integer::from_digits(&buf[..], Order::MsfBe))
+    }
+}
+
+impl<T: TranscriptProtocolChallenge> ChallengeSource for T {
+    fn challenge(
+        &mut self,
+        label: &'static [u8],
+        length_in_bits: u16,
+    ) -> Result<Integer, ChannelError> {
+        Ok(self.challenge_scalar(label, length_in_bits))
+    }
+}
@@ -0,0 +1,738 @@
+//! A channel wrapper measuring the communication of the protocols.
+//!
+//! `InstrumentedChannel` forwards to any membership or nonmembership channel
+//! and records, for each message, its size and the time the inner channel took
+//! to send or receive it. The sizes are those of the [wire](super::wire)
+//! encoding, without the message tags and length prefixes.
+use crate::{
+    channels::ChannelError,
+    commitments::{integer::IntegerCommitment, Commitment},
+    protocols::{
+        coprime::{
+            channel::{CoprimeProverChannel, CoprimeVerifierChannel},
+            Message1 as CoprimeMessage1, Message2 as CoprimeMessage2, Message3 as CoprimeMessage3,
+            Message4 as CoprimeMessage4,
+        },
+        hash_to_prime::{
+            channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
+            HashToPrimeProtocol,
+        },
+        membership::channel::{MembershipProverChannel, MembershipVerifierChannel},
+        modeq::{
+            channel::{ModEqProverChannel, ModEqVerifierChannel},
+            Message1 as ModEqMessage1, Message2 as ModEqMessage2,
+        },
+        nonmembership::channel::{NonMembershipProverChannel, NonMembershipVerifierChannel},
+        root::{
+            channel::{RootProverChannel, RootVerifierChannel},
+            Message1 as RootMessage1, Message2 as RootMessage2, Message3 as RootMessage3,
+            Message4 as RootMessage4,
+        },
+    },
+    utils::{bigint_to_bytes, curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
+};
+use rug::Integer;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Clone, Debug)]
+pub struct MessageStats {
+    pub sub_protocol: &'static str,
+    pub message: &'static str,
+    pub direction: Direction,
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ChannelStats {
+    pub messages: Vec<MessageStats>,
+}
+
+impl ChannelStats {
+    pub fn bytes_sent(&self) -> usize {
+        self.bytes(|m| m.direction == Direction::Sent)
+    }
+
+    pub fn bytes_received(&self) -> usize {
+        self.bytes(|m| m.direction == Direction::Received)
+    }
+
+    /// The bytes exchanged in both directions by one of the sub-protocols,
+    /// e.g., "root" or "modeq".
+    pub fn sub_protocol_bytes(&self, sub_protocol: &str) -> usize {
+        self.bytes(|m| m.sub_protocol == sub_protocol)
+    }
+
+    pub fn sub_protocol_elapsed(&self, sub_protocol: &str) -> Duration {
+        self.messages
+            .iter()
+            .filter(|m| m.sub_protocol == sub_protocol)
+            .map(|m| m.elapsed)
+            .sum()
+    }
+
+    fn bytes<F: Fn(&MessageStats) -> bool>(&self, filter: F) -> usize {
+        self.messages
+            .iter()
+            .filter(|m| filter(m))
+            .map(|m| m.bytes)
+            .sum()
+    }
+}
+
+fn elem_size<G: ConvertibleUnknownOrderGroup>(elem: &G::Elem) -> usize {
+    G::elem_to_bytes(elem).len()
+}
+
+fn integer_size(integer: &Integer) -> usize {
+    // The sign byte and the magnitude.
+    1 + (integer.significant_bits() as usize + 7) / 8
+}
+
+pub struct InstrumentedChannel<C> {
+    inner: C,
+    stats: ChannelStats,
+}
+
+impl<C> InstrumentedChannel<C> {
+    pub fn new(inner: C) -> InstrumentedChannel<C> {
+        InstrumentedChannel {
+            inner,
+            stats: ChannelStats::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn stats(&self) -> &ChannelStats {
+        &self.stats
+    }
+
+    pub fn into_inner(self) -> (C, ChannelStats) {
+        (self.inner, self.stats)
+    }
+
+    fn send<F: FnOnce(&mut C) -> Result<(), ChannelError>>(
+        &mut self,
+        sub_protocol: &'static str,
+        message: &'static str,
+        bytes: usize,
+        send: F,
+    ) -> Result<(), ChannelError> {
+        let start = Instant::now();
+        send(&mut self.inner)?;
+        self.stats.messages.push(MessageStats {
+            sub_protocol,
+            message,
+            direction: Direction::Sent,
+            bytes,
+            elapsed: start.elapsed(),
+        });
+        Ok(())
+    }
+
+    fn receive<T, F: FnOnce(&mut C) -> Result<T, ChannelError>, S: FnOnce(&T) -> usize>(
+        &mut self,
+        sub_protocol: &'static str,
+        message: &'static str,
+        receive: F,
+        size: S,
+    ) -> Result<T, ChannelError> {
+        let start = Instant::now();
+        let received = receive(&mut self.inner)?;
+        self.stats.messages.push(MessageStats {
+            sub_protocol,
+            message,
+            direction: Direction::Received,
+            bytes: size(&received),
+            elapsed: start.elapsed(),
+        });
+        Ok(received)
+    }
+
+    /// Challenges are sampled by the verifier's end, so they count as sent.
+    fn send_challenge<F: FnOnce(&mut C) -> Result<Integer, ChannelError>>(
+        &mut self,
+        sub_protocol: &'static str,
+        message: &'static str,
+        generate_and_send: F,
+    ) -> Result<Integer, ChannelError> {
+        let start = Instant::now();
+        let c = generate_and_send(&mut self.inner)?;
+        self.stats.messages.push(MessageStats {
+            sub_protocol,
+            message,
+            direction: Direction::Sent,
+            bytes: integer_size(&c),
+            elapsed: start.elapsed(),
+        });
+        Ok(c)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: MembershipVerifierChannel<G>> MembershipVerifierChannel<G>
+    for InstrumentedChannel<C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        self.send("membership", "epoch", epoch.len(), |c| {
+            MembershipVerifierChannel::<G>::bind_epoch(c, epoch)
+        })
+    }
+    fn send_c_e(
+        &mut self,
+        c_e: &<IntegerCommitment<G> as Commitment>::Instance,
+    ) -> Result<(), ChannelError> {
+        self.send("membership", "c_e", elem_size::<G>(c_e), |c| {
+            MembershipVerifierChannel::<G>::send_c_e(c, c_e)
+        })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: MembershipProverChannel<G>> MembershipProverChannel<G>
+    for InstrumentedChannel<C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let bytes = epoch.len();
+        self.receive(
+            "membership",
+            "epoch",
+            |c| MembershipProverChannel::<G>::bind_epoch(c, epoch),
+            |_| bytes,
+        )
+    }
+    fn receive_c_e(
+        &mut self,
+    ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError> {
+        self.receive(
+            "membership",
+            "c_e",
+            <C as MembershipProverChannel<G>>::receive_c_e,
+            elem_size::<G>,
+        )
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: NonMembershipVerifierChannel<G>>
+    NonMembershipVerifierChannel<G> for InstrumentedChannel<C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        self.send("nonmembership", "epoch", epoch.len(), |c| {
+            NonMembershipVerifierChannel::<G>::bind_epoch(c, epoch)
+        })
+    }
+    fn send_c_e(
+        &mut self,
+        c_e: &<IntegerCommitment<G> as Commitment>::Instance,
+    ) -> Result<(), ChannelError> {
+        self.send("nonmembership", "c_e", elem_size::<G>(c_e), |c| {
+            NonMembershipVerifierChannel::<G>::send_c_e(c, c_e)
+        })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: NonMembershipProverChannel<G>>
+    NonMembershipProverChannel<G> for InstrumentedChannel<C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let bytes = epoch.len();
+        self.receive(
+            "nonmembership",
+            "epoch",
+            |c| NonMembershipProverChannel::<G>::bind_epoch(c, epoch),
+            |_| bytes,
+        )
+    }
+    fn receive_c_e(
+        &mut self,
+    ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError> {
+        self.receive(
+            "nonmembership",
+            "c_e",
+            <C as NonMembershipProverChannel<G>>::receive_c_e,
+            elem_size::<G>,
+        )
+    }
+}
+
+fn root_message1_size<G: ConvertibleUnknownOrderGroup>(message: &RootMessage1<G>) -> usize {
+    elem_size::<G>(&message.c_w) + elem_size::<G>(&message.c_r)
+}
+
+fn root_message2_size<G: ConvertibleUnknownOrderGroup>(message: &RootMessage2<G>) -> usize {
+    elem_size::<G>(&message.alpha1)
+        + elem_size::<G>(&message.alpha2)
+        + elem_size::<G>(&message.alpha3)
+        + elem_size::<G>(&message.alpha4)
+}
+
+fn root_message3_size(message: &RootMessage3) -> usize {
+    [
+        &message.s_e,
+        &message.s_r,
+        &message.s_r_2,
+        &message.s_r_3,
+        &message.s_beta,
+        &message.s_delta,
+    ]
+    .iter()
+    .map(|s| integer_size(s))
+    .sum()
+}
+
+fn root_message4_size<G: ConvertibleUnknownOrderGroup>(message: &RootMessage4<G>) -> usize {
+    elem_size::<G>(&message.q1)
+        + elem_size::<G>(&message.q2)
+        + elem_size::<G>(&message.q3)
+        + elem_size::<G>(&message.q4)
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: RootVerifierChannel<G>> RootVerifierChannel<G>
+    for InstrumentedChannel<C>
+{
+    fn send_message1(&mut self, message: &RootMessage1<G>) -> Result<(), ChannelError> {
+        self.send("root", "message1", root_message1_size(message), |c| {
+            RootVerifierChannel::<G>::send_message1(c, message)
+        })
+    }
+    fn send_message2(&mut self, message: &RootMessage2<G>) -> Result<(), ChannelError> {
+        self.send("root", "message2", root_message2_size(message), |c| {
+            RootVerifierChannel::<G>::send_message2(c, message)
+        })
+    }
+    fn send_message3(&mut self, message: &RootMessage3) -> Result<(), ChannelError> {
+        self.send("root", "message3", root_message3_size(message), |c| {
+            RootVerifierChannel::<G>::send_message3(c, message)
+        })
+    }
+    fn send_message4(&mut self, message: &RootMessage4<G>) -> Result<(), ChannelError> {
+        self.send("root", "message4", root_message4_size(message), |c| {
+            RootVerifierChannel::<G>::send_message4(c, message)
+        })
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive(
+            "root",
+            "challenge",
+            <C as RootVerifierChannel<G>>::receive_challenge,
+            integer_size,
+        )
+    }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive(
+            "root",
+            "poe_challenge",
+            <C as RootVerifierChannel<G>>::receive_poe_challenge,
+            integer_size,
+        )
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: RootProverChannel<G>> RootProverChannel<G>
+    for InstrumentedChannel<C>
+{
+    fn receive_message1(&mut self) -> Result<RootMessage1<G>, ChannelError> {
+        self.receive(
+            "root",
+            "message1",
+            <C as RootProverChannel<G>>::receive_message1,
+            root_message1_size,
+        )
+    }
+    fn receive_message2(&mut self) -> Result<RootMessage2<G>, ChannelError> {
+        self.receive(
+            "root",
+            "message2",
+            <C as RootProverChannel<G>>::receive_message2,
+            root_message2_size,
+        )
+    }
+    fn receive_message3(&mut self) -> Result<RootMessage3, ChannelError> {
+        self.receive(
+            "root",
+            "message3",
+            <C as RootProverChannel<G>>::receive_message3,
+            root_message3_size,
+        )
+    }
+    fn receive_message4(&mut self) -> Result<RootMessage4<G>, ChannelError> {
+        self.receive(
+            "root",
+            "message4",
+            <C as RootProverChannel<G>>::receive_message4,
+            root_message4_size,
+        )
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.send_challenge(
+            "root",
+            "challenge",
+            <C as RootProverChannel<G>>::generate_and_send_challenge,
+        )
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.send_challenge(
+            "root",
+            "poe_challenge",
+            <C as RootProverChannel<G>>::generate_and_send_poe_challenge,
+        )
+    }
+}
+
+fn modeq_message1_size<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective>(
+    message: &ModEqMessage1<G, P>,
+) -> usize {
+    elem_size::<G>(&message.alpha1)
+        + message
+            .alpha2
+            .to_affine_bytes()
+            .map(|bytes| bytes.len())
+            .unwrap_or(0)
+}
+
+fn modeq_message2_size<P: CurvePointProjective>(message: &ModEqMessage2<P>) -> usize {
+    integer_size(&message.s_e)
+        + integer_size(&message.s_r)
+        + bigint_to_bytes::<P>(&message.s_r_q).len()
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, C: ModEqVerifierChannel<G, P>>
+    ModEqVerifierChannel<G, P> for InstrumentedChannel<C>
+{
+    fn send_message1(&mut self, message: &ModEqMessage1<G, P>) -> Result<(), ChannelError> {
+        self.send("modeq", "message1", modeq_message1_size(message), |c| {
+            ModEqVerifierChannel::<G, P>::send_message1(c, message)
+        })
+    }
+    fn send_message2(&mut self, message: &ModEqMessage2<P>) -> Result<(), ChannelError> {
+        self.send("modeq", "message2", modeq_message2_size(message), |c| {
+            ModEqVerifierChannel::<G, P>::send_message2(c, message)
+        })
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive(
+            "modeq",
+            "challenge",
+            <C as ModEqVerifierChannel<G, P>>::receive_challenge,
+            integer_size,
+        )
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, C: ModEqProverChannel<G, P>>
+    ModEqProverChannel<G, P> for InstrumentedChannel<C>
+{
+    fn receive_message1(&mut self) -> Result<ModEqMessage1<G, P>, ChannelError> {
+        self.receive(
+            "modeq",
+            "message1",
+            <C as ModEqProverChannel<G, P>>::receive_message1,
+            modeq_message1_size,
+        )
+    }
+    fn receive_message2(&mut self) -> Result<ModEqMessage2<P>, ChannelError> {
+        self.receive(
+            "modeq",
+            "message2",
+            <C as ModEqProverChannel<G, P>>::receive_message2,
+            modeq_message2_size,
+        )
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.send_challenge(
+            "modeq",
+            "challenge",
+            <C as ModEqProverChannel<G, P>>::generate_and_send_challenge,
+        )
+    }
+}
+
+fn coprime_message1_size<G: ConvertibleUnknownOrderGroup>(message: &CoprimeMessage1<G>) -> usize {
+    elem_size::<G>(&message.c_a)
+        + elem_size::<G>(&message.c_r_a)
+        + elem_size::<G>(&message.c_b_cap)
+        + elem_size::<G>(&message.c_rho_b_cap)
+}
+
+fn coprime_message2_size<G: ConvertibleUnknownOrderGroup>(message: &CoprimeMessage2<G>) -> usize {
+    [
+        &message.alpha2,
+        &message.alpha3,
+        &message.alpha4,
+        &message.alpha5,
+        &message.alpha6,
+        &message.alpha7,
+    ]
+    .iter()
+    .map(|alpha| elem_size::<G>(alpha))
+    .sum()
+}
+
+fn coprime_message3_size(message: &CoprimeMessage3) -> usize {
+    [
+        &message.s_b,
+        &message.s_e,
+        &message.s_rho_b_cap,
+        &message.s_r,
+        &message.s_r_a,
+        &message.s_r_a_prime,
+        &message.s_rho_b_cap_prime,
+        &message.s_beta,
+        &message.s_delta,
+    ]
+    .iter()
+    .map(|s| integer_size(s))
+    .sum()
+}
+
+fn coprime_message4_size<G: ConvertibleUnknownOrderGroup>(message: &CoprimeMessage4<G>) -> usize {
+    [
+        &message.q2,
+        &message.q3,
+        &message.q4,
+        &message.q5,
+        &message.q6,
+        &message.q7,
+    ]
+    .iter()
+    .map(|q| elem_size::<G>(q))
+    .sum()
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: CoprimeVerifierChannel<G>> CoprimeVerifierChannel<G>
+    for InstrumentedChannel<C>
+{
+    fn send_message1(&mut self, message: &CoprimeMessage1<G>) -> Result<(), ChannelError> {
+        self.send("coprime", "message1", coprime_message1_size(message), |c| {
+            CoprimeVerifierChannel::<G>::send_message1(c, message)
+        })
+    }
+    fn send_message2(&mut self, message: &CoprimeMessage2<G>) -> Result<(), ChannelError> {
+        self.send("coprime", "message2", coprime_message2_size(message), |c| {
+            CoprimeVerifierChannel::<G>::send_message2(c, message)
+        })
+    }
+    fn send_message3(&mut self, message: &CoprimeMessage3) -> Result<(), ChannelError> {
+        self.send("coprime", "message3", coprime_message3_size(message), |c| {
+            CoprimeVerifierChannel::<G>::send_message3(c, message)
+        })
+    }
+    fn send_message4(&mut self, message: &CoprimeMessage4<G>) -> Result<(), ChannelError> {
+        self.send("coprime", "message4", coprime_message4_size(message), |c| {
+            CoprimeVerifierChannel::<G>::send_message4(c, message)
+        })
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive(
+            "coprime",
+            "challenge",
+            <C as CoprimeVerifierChannel<G>>::receive_challenge,
+            integer_size,
+        )
+    }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive(
+            "coprime",
+            "poe_challenge",
+            <C as CoprimeVerifierChannel<G>>::receive_poe_challenge,
+            integer_size,
+        )
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: CoprimeProverChannel<G>> CoprimeProverChannel<G>
+    for InstrumentedChannel<C>
+{
+    fn receive_message1(&mut self) -> Result<CoprimeMessage1<G>, ChannelError> {
+        self.receive(
+            "coprime",
+            "message1",
+            <C as CoprimeProverChannel<G>>::receive_message1,
+            coprime_message1_size,
+        )
+    }
+    fn receive_message2(&mut self) -> Result<CoprimeMessage2<G>, ChannelError> {
+        self.receive(
+            "coprime",
+            "message2",
+            <C as CoprimeProverChannel<G>>::receive_message2,
+            coprime_message2_size,
+        )
+    }
+    fn receive_message3(&mut self) -> Result<CoprimeMessage3, ChannelError> {
+        self.receive(
+            "coprime",
+            "message3",
+            <C as CoprimeProverChannel<G>>::receive_message3,
+            coprime_message3_size,
+        )
+    }
+    fn receive_message4(&mut self) -> Result<CoprimeMessage4<G>, ChannelError> {
+        self.receive(
+            "coprime",
+            "message4",
+            <C as CoprimeProverChannel<G>>::receive_message4,
+            coprime_message4_size,
+        )
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.send_challenge(
+            "coprime",
+            "challenge",
+            <C as CoprimeProverChannel<G>>::generate_and_send_challenge,
+        )
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.send_challenge(
+            "coprime",
+            "poe_challenge",
+            <C as CoprimeProverChannel<G>>::generate_and_send_poe_challenge,
+        )
+    }
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>, C: HashToPrimeVerifierChannel<P, HP>>
+    HashToPrimeVerifierChannel<P, HP> for InstrumentedChannel<C>
+{
+    fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), ChannelError> {
+        let bytes = HP::proof_to_bytes(proof)?.len();
+        self.send("hash_to_prime", "proof", bytes, |c| c.send_proof(proof))
+    }
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>, C: HashToPrimeProverChannel<P, HP>>
+    HashToPrimeProverChannel<P, HP> for InstrumentedChannel<C>
+{
+    fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        self.receive(
+            "hash_to_prime",
+            "proof",
+            |c| c.receive_proof(),
+            |proof| {
+                HP::proof_to_bytes(proof)
+                    .map(|bytes| bytes.len())
+                    .unwrap_or(0)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Direction, InstrumentedChannel};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{
+                transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+                Protocol, Statement, Witness,
+            },
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_instrumented_membership() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: None,
+        };
+        let proof_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut verifier_channel =
+            InstrumentedChannel::new(TranscriptVerifierChannel::new(&crs, &proof_transcript));
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        let (verifier_channel, prover_stats) = verifier_channel.into_inner();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut prover_channel = InstrumentedChannel::new(TranscriptProverChannel::new(
+            &crs,
+            &verification_transcript,
+            &proof,
+        ));
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+        let verifier_stats = prover_channel.stats();
+
+        // What the prover sends is what the verifier receives.
+        assert!(prover_stats.bytes_sent() > 0);
+        assert_eq!(prover_stats.bytes_sent(), verifier_stats.bytes_received());
+        assert_eq!(prover_stats.bytes_received(), verifier_stats.bytes_sent());
+        for sub_protocol in &["membership", "root", "modeq", "hash_to_prime"] {
+            assert!(prover_stats.sub_protocol_bytes(sub_protocol) > 0);
+        }
+        assert!(prover_stats
+            .messages
+            .iter()
+            .any(|m| m.message == "challenge" && m.direction == Direction::Received));
+    }
+}
@@ -0,0 +1,198 @@
+//! Sends large messages over a byte stream in bounded chunks.
+//!
+//! A message is sent as the wire version followed by chunks holding their
+//! length as a big-endian u32 and at most `chunk_size` bytes, and ends with an
+//! empty chunk. The receiver can process each chunk as it arrives, e.g.,
+//! absorbing it into the transcript with `read_absorbing`, so a constrained
+//! verifier doesn't need to hold a whole batched proof in memory to bind it.
+use crate::{
+    channels::{
+        tcp::WIRE_VERSION,
+        transport::{MessageTransport, TransportProverChannel, TransportVerifierChannel},
+        wire::{decode_message, encode_message, MembershipMessage},
+        ChannelError,
+    },
+    protocols::hash_to_prime::HashToPrimeProtocol,
+    transcript::FiatShamir,
+    utils::{curve::CurvePointProjective, group::ElemFromBytes},
+};
+use std::io::{Read, Write};
+
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 16;
+/// Messages longer than this are rejected when they're buffered whole.
+pub const MAX_MESSAGE_LENGTH: usize = 1 << 30;
+
+/// Absorbs a payload into a transcript chunk by chunk, followed by its total
+/// length, the same way `read_absorbing` absorbs it on the receiving end.
+pub fn append_chunked<T: FiatShamir>(
+    transcript: &mut T,
+    label: &'static [u8],
+    payload: &[u8],
+    chunk_size: usize,
+) {
+    for chunk in payload.chunks(chunk_size) {
+        transcript.append_message(label, chunk);
+    }
+    transcript.append_message(b"length", &(payload.len() as u64).to_be_bytes());
+}
+
+pub struct ChunkedStream<S: Read + Write> {
+    stream: S,
+    chunk_size: usize,
+}
+
+impl<S: Read + Write> ChunkedStream<S> {
+    pub fn new(stream: S) -> ChunkedStream<S> {
+        ChunkedStream::with_chunk_size(stream, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Sends chunks of at most `chunk_size` bytes, and rejects larger ones.
+    pub fn with_chunk_size(stream: S, chunk_size: usize) -> ChunkedStream<S> {
+        assert!(chunk_size > 0 && chunk_size <= u32::MAX as usize);
+        ChunkedStream { stream, chunk_size }
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    pub fn write_chunked(&mut self, payload: &[u8]) -> Result<(), ChannelError> {
+        self.stream.write_all(&[WIRE_VERSION])?;
+        for chunk in payload.chunks(self.chunk_size) {
+            self.stream.write_all(&(chunk.len() as u32).to_be_bytes())?;
+            self.stream.write_all(chunk)?;
+        }
+        self.stream.write_all(&0u32.to_be_bytes())?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// Calls `on_chunk` with each chunk of the next message as it's read,
+    /// reusing a single buffer of at most `chunk_size` bytes, and returns the
+    /// length of the message.
+    pub fn read_chunked<F: FnMut(&[u8]) -> Result<(), ChannelError>>(
+        &mut self,
+        mut on_chunk: F,
+    ) -> Result<usize, ChannelError> {
+        let mut version = [0u8; 1];
+        self.stream.read_exact(&mut version)?;
+        if version[0] != WIRE_VERSION {
+            return Err(ChannelError::UnsupportedVersion(version[0]));
+        }
+        let mut buffer = vec![0u8; self.chunk_size];
+        let mut total = 0;
+        loop {
+            let mut length = [0u8; 4];
+            self.stream.read_exact(&mut length)?;
+            let length = u32::from_be_bytes(length) as usize;
+            if length == 0 {
+                return Ok(total);
+            }
+            if length > self.chunk_size {
+                return Err(ChannelError::MalformedMessage);
+            }
+            self.stream.read_exact(&mut buffer[..length])?;
+            on_chunk(&buffer[..length])?;
+            total += length;
+        }
+    }
+
+    /// Reads the next message, absorbing its chunks into `transcript` as
+    /// they arrive, and passes them on to `on_chunk`.
+    pub fn read_absorbing<T: FiatShamir, F: FnMut(&[u8]) -> Result<(), ChannelError>>(
+        &mut self,
+        transcript: &mut T,
+        label: &'static [u8],
+        mut on_chunk: F,
+    ) -> Result<usize, ChannelError> {
+        let total = self.read_chunked(|chunk| {
+            transcript.append_message(label, chunk);
+            on_chunk(chunk)
+        })?;
+        transcript.append_message(b"length", &(total as u64).to_be_bytes());
+        Ok(total)
+    }
+
+    pub fn read_message(&mut self) -> Result<Vec<u8>, ChannelError> {
+        let mut message = vec![];
+        self.read_chunked(|chunk| {
+            if message.len() + chunk.len() > MAX_MESSAGE_LENGTH {
+                return Err(ChannelError::MalformedMessage);
+            }
+            message.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+impl<S: Read + Write, G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MessageTransport<G, P, HP> for ChunkedStream<S>
+{
+    fn send(&mut self, message: MembershipMessage<G, P, HP>) -> Result<(), ChannelError> {
+        self.write_chunked(&encode_message(&message)?)
+    }
+
+    fn receive(&mut self) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+        decode_message(&self.read_message()?)
+    }
+}
+
+/// The prover's end of a chunked stream.
+pub type StreamingVerifierChannel<G, P, HP, S> =
+    TransportVerifierChannel<G, P, HP, ChunkedStream<S>>;
+/// The verifier's end of a chunked stream.
+pub type StreamingProverChannel<G, P, HP, S> = TransportProverChannel<G, P, HP, ChunkedStream<S>>;
+
+#[cfg(test)]
+mod test {
+    use super::{append_chunked, ChunkedStream};
+    use crate::{
+        channels::ChannelError,
+        transcript::{shake::ShakeTranscript, FiatShamir},
+    };
+    use std::io::Cursor;
+
+    #[test]
+    fn test_chunks() {
+        let payload = (0..1000u32).map(|i| i as u8).collect::<Vec<_>>();
+        let mut stream = ChunkedStream::with_chunk_size(Cursor::new(vec![]), 64);
+        stream.write_chunked(&payload).unwrap();
+        let bytes = stream.into_inner().into_inner();
+
+        let mut stream = ChunkedStream::with_chunk_size(Cursor::new(bytes.clone()), 64);
+        let mut chunks = 0;
+        let mut receiver_transcript = ShakeTranscript::new(b"test");
+        let total = stream
+            .read_absorbing(&mut receiver_transcript, b"proof", |chunk| {
+                assert!(chunk.len() <= 64);
+                chunks += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total, payload.len());
+        assert_eq!(chunks, 16);
+
+        let mut sender_transcript = ShakeTranscript::new(b"test");
+        append_chunked(&mut sender_transcript, b"proof", &payload, 64);
+        let mut c1 = [0u8; 16];
+        let mut c2 = [0u8; 16];
+        sender_transcript.challenge_bytes(b"c", &mut c1);
+        receiver_transcript.challenge_bytes(b"c", &mut c2);
+        assert_eq!(c1, c2);
+
+        let mut stream = ChunkedStream::with_chunk_size(Cursor::new(bytes.clone()), 64);
+        assert_eq!(stream.read_message().unwrap(), payload);
+
+        // Chunks larger than the receiver accepts are rejected.
+        let mut stream = ChunkedStream::with_chunk_size(Cursor::new(bytes), 32);
+        assert!(matches!(
+            stream.read_message(),
+            Err(ChannelError::MalformedMessage)
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+//! Runs the membership protocol between two processes over a byte stream.
+//!
+//! Each message is sent as a frame holding the wire version, the length of the
+//! encoded message as a big-endian u32 and the encoded message itself.
+use crate::{
+    channels::{
+        transport::{MessageTransport, TransportProverChannel, TransportVerifierChannel},
+        wire::{decode_message, encode_message, MembershipMessage},
+        ChannelError,
+    },
+    parameters::Parameters,
+    protocols::hash_to_prime::HashToPrimeProtocol,
+    utils::{curve::CurvePointProjective, group::ElemFromBytes},
+};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+pub const WIRE_VERSION: u8 = 1;
+/// Frames longer than this are rejected before allocating a buffer for them.
+pub const MAX_FRAME_LENGTH: usize = 1 << 26;
+
+pub struct FramedTransport<S: Read + Write> {
+    stream: S,
+}
+
+impl<S: Read + Write> FramedTransport<S> {
+    pub fn new(stream: S) -> FramedTransport<S> {
+        FramedTransport { stream }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    pub fn write_frame(&mut self, payload: &[u8]) -> Result<(), ChannelError> {
+        if payload.len() > MAX_FRAME_LENGTH {
+            return Err(ChannelError::CouldNotSend);
+        }
+        let mut header = [0u8; 5];
+        header[0] = WIRE_VERSION;
+        header[1..].copy_from_slice(&(payload.len() as u32).to_be_bytes());
+        self.stream.write_all(&header)?;
+        self.stream.write_all(payload)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    pub fn read_frame(&mut self) -> Result<Vec<u8>, ChannelError> {
+        let mut header = [0u8; 5];
+        self.stream.read_exact(&mut header)?;
+        if header[0] != WIRE_VERSION {
+            return Err(ChannelError::UnsupportedVersion(header[0]));
+        }
+        let mut length = [0u8; 4];
+        length.copy_from_slice(&header[1..]);
+        let length = u32::from_be_bytes(length) as usize;
+        if length > MAX_FRAME_LENGTH {
+            return Err(ChannelError::MalformedMessage);
+        }
+        let mut payload = vec![0u8; length];
+        self.stream.read_exact(&mut payload)?;
+        Ok(payload)
+    }
+}
+
+impl<S: Read + Write, G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MessageTransport<G, P, HP> for FramedTransport<S>
+{
+    fn send(&mut self, message: MembershipMessage<G, P, HP>) -> Result<(), ChannelError> {
+        self.write_frame(&encode_message(&message)?)
+    }
+
+    fn receive(&mut self) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+        decode_message(&self.read_frame()?)
+    }
+}
+
+/// The prover's end of a TCP connection.
+pub type TcpVerifierChannel<G, P, HP> =
+    TransportVerifierChannel<G, P, HP, FramedTransport<TcpStream>>;
+/// The verifier's end of a TCP connection.
+pub type TcpProverChannel<G, P, HP> = TransportProverChannel<G, P, HP, FramedTransport<TcpStream>>;
+
+pub fn tcp_verifier_channel<
+    G: ElemFromBytes,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    stream: TcpStream,
+) -> Result<TcpVerifierChannel<G, P, HP>, ChannelError> {
+    stream.set_nodelay(true)?;
+    Ok(TransportVerifierChannel::new(FramedTransport::new(stream)))
+}
+
+pub fn tcp_prover_channel<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    parameters: &Parameters,
+    stream: TcpStream,
+) -> Result<TcpProverChannel<G, P, HP>, ChannelError> {
+    stream.set_nodelay(true)?;
+    Ok(TransportProverChannel::new(
+        parameters,
+        FramedTransport::new(stream),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{tcp_prover_channel, tcp_verifier_channel, FramedTransport};
+    use crate::{
+        channels::ChannelError,
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::io::Cursor;
+    use std::net::{TcpListener, TcpStream};
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_frames() {
+        let mut transport = FramedTransport::new(Cursor::new(vec![]));
+        transport.write_frame(b"message").unwrap();
+        let mut bytes = transport.into_inner().into_inner();
+        let mut transport = FramedTransport::new(Cursor::new(bytes.clone()));
+        assert_eq!(transport.read_frame().unwrap(), b"message".to_vec());
+
+        bytes[0] = 2;
+        let mut transport = FramedTransport::new(Cursor::new(bytes));
+        assert!(matches!(
+            transport.read_frame(),
+            Err(ChannelError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_tcp_membership() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc.clone(),
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        let verifier_statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let verifier = std::thread::spawn(move || {
+            let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+            let (stream, _) = listener.accept().unwrap();
+            let mut prover_channel = tcp_prover_channel(&crs.parameters, stream).unwrap();
+            protocol
+                .verify(&mut prover_channel, &verifier_statement)
+                .unwrap();
+        });
+
+        let mut verifier_channel =
+            tcp_verifier_channel(TcpStream::connect(address).unwrap()).unwrap();
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        verifier.join().unwrap();
+    }
+}
@@ -0,0 +1,450 @@
+//! Recording and replaying the verifier's side of the membership protocol.
+//!
+//! `RecordingChannel` wraps the channel a verifier runs on and keeps every
+//! message it receives and every challenge it issues in a `Trace`. A trace is
+//! serialized as the [wire](super::wire) encoding of its messages, each
+//! prefixed by its length as a big-endian u32. `ReplayChannel` serves a trace
+//! back to a verifier, so failed verifications can be rerun offline and real
+//! runs can be kept as regression fixtures.
+use crate::{
+    channels::{
+        wire::{decode_message, encode_message, MembershipMessage},
+        ChannelError,
+    },
+    protocols::{
+        hash_to_prime::{channel::HashToPrimeProverChannel, HashToPrimeProtocol},
+        membership::channel::MembershipProverChannel,
+        modeq::{
+            channel::ModEqProverChannel, Message1 as ModEqMessage1, Message2 as ModEqMessage2,
+        },
+        root::{
+            channel::RootProverChannel, Message1 as RootMessage1, Message2 as RootMessage2,
+            Message3 as RootMessage3, Message4 as RootMessage4,
+        },
+    },
+    utils::{curve::CurvePointProjective, group::ElemFromBytes, ConvertibleUnknownOrderGroup},
+};
+use rug::Integer;
+use std::convert::TryInto;
+
+pub struct Trace<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub messages: Vec<MembershipMessage<G, P, HP>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Trace<G, P, HP>
+{
+    pub fn new() -> Trace<G, P, HP> {
+        Trace { messages: vec![] }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ChannelError> {
+        let mut out = vec![];
+        for message in &self.messages {
+            let encoded = encode_message(message)?;
+            out.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+            out.extend_from_slice(&encoded);
+        }
+        Ok(out)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Trace<G, P, HP> {
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Trace<G, P, HP>, ChannelError> {
+        let mut messages = vec![];
+        while !bytes.is_empty() {
+            if bytes.len() < 4 {
+                return Err(ChannelError::MalformedMessage);
+            }
+            let (length, rest) = bytes.split_at(4);
+            let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+            if rest.len() < length {
+                return Err(ChannelError::MalformedMessage);
+            }
+            let (message, rest) = rest.split_at(length);
+            messages.push(decode_message(message)?);
+            bytes = rest;
+        }
+        Ok(Trace { messages })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Default
+    for Trace<G, P, HP>
+{
+    fn default() -> Self {
+        Trace::new()
+    }
+}
+
+pub struct RecordingChannel<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    C,
+> {
+    inner: C,
+    trace: Trace<G, P, HP>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>, C>
+    RecordingChannel<G, P, HP, C>
+{
+    pub fn new(inner: C) -> RecordingChannel<G, P, HP, C> {
+        RecordingChannel {
+            inner,
+            trace: Trace::new(),
+        }
+    }
+
+    pub fn trace(&self) -> &Trace<G, P, HP> {
+        &self.trace
+    }
+
+    pub fn into_inner(self) -> (C, Trace<G, P, HP>) {
+        (self.inner, self.trace)
+    }
+
+    fn record<T, F: FnOnce(&T) -> MembershipMessage<G, P, HP>>(
+        &mut self,
+        result: Result<T, ChannelError>,
+        message: F,
+    ) -> Result<T, ChannelError> {
+        let value = result?;
+        self.trace.messages.push(message(&value));
+        Ok(value)
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: MembershipProverChannel<G>,
+    > MembershipProverChannel<G> for RecordingChannel<G, P, HP, C>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let result = self.inner.bind_epoch(epoch);
+        self.record(result, |_| MembershipMessage::Epoch(epoch.to_vec()))
+    }
+    fn receive_c_e(&mut self) -> Result<G::Elem, ChannelError> {
+        let result = self.inner.receive_c_e();
+        self.record(result, |c_e| MembershipMessage::CE(c_e.clone()))
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: RootProverChannel<G>,
+    > RootProverChannel<G> for RecordingChannel<G, P, HP, C>
+{
+    fn receive_message1(&mut self) -> Result<RootMessage1<G>, ChannelError> {
+        let result = self.inner.receive_message1();
+        self.record(result, |m| MembershipMessage::RootMessage1(m.clone()))
+    }
+    fn receive_message2(&mut self) -> Result<RootMessage2<G>, ChannelError> {
+        let result = self.inner.receive_message2();
+        self.record(result, |m| MembershipMessage::RootMessage2(m.clone()))
+    }
+    fn receive_message3(&mut self) -> Result<RootMessage3, ChannelError> {
+        let result = self.inner.receive_message3();
+        self.record(result, |m| MembershipMessage::RootMessage3(m.clone()))
+    }
+    fn receive_message4(&mut self) -> Result<RootMessage4<G>, ChannelError> {
+        let result = self.inner.receive_message4();
+        self.record(result, |m| MembershipMessage::RootMessage4(m.clone()))
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let result = self.inner.generate_and_send_challenge();
+        self.record(result, |c| MembershipMessage::Challenge(c.clone()))
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let result = self.inner.generate_and_send_poe_challenge();
+        self.record(result, |c| MembershipMessage::Challenge(c.clone()))
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: ModEqProverChannel<G, P>,
+    > ModEqProverChannel<G, P> for RecordingChannel<G, P, HP, C>
+{
+    fn receive_message1(&mut self) -> Result<ModEqMessage1<G, P>, ChannelError> {
+        let result = self.inner.receive_message1();
+        self.record(result, |m| MembershipMessage::ModEqMessage1(m.clone()))
+    }
+    fn receive_message2(&mut self) -> Result<ModEqMessage2<P>, ChannelError> {
+        let result = self.inner.receive_message2();
+        self.record(result, |m| MembershipMessage::ModEqMessage2(m.clone()))
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let result = self.inner.generate_and_send_challenge();
+        self.record(result, |c| MembershipMessage::Challenge(c.clone()))
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: HashToPrimeProverChannel<P, HP>,
+    > HashToPrimeProverChannel<P, HP> for RecordingChannel<G, P, HP, C>
+{
+    fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        let result = self.inner.receive_proof();
+        self.record(result, |proof| {
+            MembershipMessage::HashToPrimeProof(proof.clone())
+        })
+    }
+}
+
+/// Plays a recorded trace back to a verifier, returning the recorded
+/// challenges instead of generating new ones.
+pub struct ReplayChannel<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    messages: std::vec::IntoIter<MembershipMessage<G, P, HP>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    ReplayChannel<G, P, HP>
+{
+    pub fn new(trace: Trace<G, P, HP>) -> ReplayChannel<G, P, HP> {
+        ReplayChannel {
+            messages: trace.messages.into_iter(),
+        }
+    }
+
+    /// The number of recorded messages the verifier hasn't asked for yet.
+    pub fn remaining(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn next_message(&mut self) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+        self.messages.next().ok_or(ChannelError::CouldNotReceive)
+    }
+
+    fn next_challenge(&mut self) -> Result<Integer, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::Challenge(c) => Ok(c),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MembershipProverChannel<G> for ReplayChannel<G, P, HP>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::Epoch(e) if e == epoch => Ok(()),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_c_e(&mut self) -> Result<G::Elem, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::CE(c_e) => Ok(c_e),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    RootProverChannel<G> for ReplayChannel<G, P, HP>
+{
+    fn receive_message1(&mut self) -> Result<RootMessage1<G>, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::RootMessage1(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message2(&mut self) -> Result<RootMessage2<G>, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::RootMessage2(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message3(&mut self) -> Result<RootMessage3, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::RootMessage3(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message4(&mut self) -> Result<RootMessage4<G>, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::RootMessage4(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.next_challenge()
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.next_challenge()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    ModEqProverChannel<G, P> for ReplayChannel<G, P, HP>
+{
+    fn receive_message1(&mut self) -> Result<ModEqMessage1<G, P>, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::ModEqMessage1(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message2(&mut self) -> Result<ModEqMessage2<P>, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::ModEqMessage2(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.next_challenge()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    HashToPrimeProverChannel<P, HP> for ReplayChannel<G, P, HP>
+{
+    fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        match self.next_message()? {
+            MembershipMessage::HashToPrimeProof(proof) => Ok(proof),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RecordingChannel, ReplayChannel, Trace};
+    use crate::{
+        channels::wire::MembershipMessage,
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{
+                transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+                Protocol, Statement, Witness,
+            },
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_record_and_replay() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        let proof_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut prover_channel =
+            RecordingChannel::<Rsa2048, G1Projective, HPProtocol<Bls12_381>, _>::new(
+                TranscriptProverChannel::new(&crs, &verification_transcript, &proof),
+            );
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+        let bytes = prover_channel.trace().to_bytes().unwrap();
+
+        let trace =
+            Trace::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_bytes(&bytes).unwrap();
+        let mut replay_channel = ReplayChannel::new(trace);
+        protocol.verify(&mut replay_channel, &statement).unwrap();
+        assert_eq!(replay_channel.remaining(), 0);
+
+        let mut trace =
+            Trace::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_bytes(&bytes).unwrap();
+        for message in trace.messages.iter_mut() {
+            if let MembershipMessage::Challenge(c) = message {
+                *c += 1;
+                break;
+            }
+        }
+        assert!(protocol
+            .verify(&mut ReplayChannel::new(trace), &statement)
+            .is_err());
+
+        assert!(
+            Trace::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_bytes(
+                &bytes[..bytes.len() - 1]
+            )
+            .is_err()
+        );
+    }
+}
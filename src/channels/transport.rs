@@ -0,0 +1,358 @@
+//! Channels for the membership protocol on top of any message transport.
+//!
+//! `TransportVerifierChannel` is the prover's end and `TransportProverChannel`
+//! is the verifier's end, which draws the challenges from a `ChallengeSource`
+//! and sends them to the prover. By default the challenges are sampled at
+//! random, so the protocol runs interactively without Fiat-Shamir. Transports
+//! only need to move `MembershipMessage`s, e.g., by framing their encoding over
+//! a stream.
+use crate::{
+    channels::{
+        handshake::Handshake, wire::MembershipMessage, ChallengeSource, ChannelError,
+        RandomChallenges,
+    },
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{
+            channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
+            HashToPrimeProtocol,
+        },
+        membership::channel::{MembershipProverChannel, MembershipVerifierChannel},
+        modeq::{
+            channel::{ModEqProverChannel, ModEqVerifierChannel},
+            Message1 as ModEqMessage1, Message2 as ModEqMessage2,
+        },
+        root::{
+            channel::{RootProverChannel, RootVerifierChannel},
+            Message1 as RootMessage1, Message2 as RootMessage2, Message3 as RootMessage3,
+            Message4 as RootMessage4,
+        },
+    },
+    utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
+};
+use rand::rngs::OsRng;
+use rug::Integer;
+use std::marker::PhantomData;
+
+pub trait MessageTransport<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>
+{
+    fn send(&mut self, message: MembershipMessage<G, P, HP>) -> Result<(), ChannelError>;
+    fn receive(&mut self) -> Result<MembershipMessage<G, P, HP>, ChannelError>;
+}
+
+pub struct TransportVerifierChannel<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    T: MessageTransport<G, P, HP>,
+> {
+    transport: T,
+    message_types: PhantomData<(G, P, HP)>,
+}
+
+pub struct TransportProverChannel<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    T: MessageTransport<G, P, HP>,
+    S: ChallengeSource = RandomChallenges<OsRng>,
+> {
+    parameters: Parameters,
+    transport: T,
+    challenges: S,
+    message_types: PhantomData<(G, P, HP)>,
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+    > TransportVerifierChannel<G, P, HP, T>
+{
+    pub fn new(transport: T) -> TransportVerifierChannel<G, P, HP, T> {
+        TransportVerifierChannel {
+            transport,
+            message_types: PhantomData,
+        }
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    /// Sends our handshake and checks the verifier's answer. Should be called
+    /// before proving.
+    pub fn handshake(&mut self, parameters: &Parameters) -> Result<(), ChannelError> {
+        let ours = Handshake::new::<G, P, HP>(parameters);
+        self.transport
+            .send(MembershipMessage::Handshake(ours.clone()))?;
+        match self.transport.receive()? {
+            MembershipMessage::Handshake(theirs) => ours.check(&theirs),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+
+    fn receive_challenge_message(&mut self) -> Result<Integer, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::Challenge(c) => Ok(c),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+    > TransportProverChannel<G, P, HP, T>
+{
+    pub fn new(parameters: &Parameters, transport: T) -> TransportProverChannel<G, P, HP, T> {
+        TransportProverChannel::with_challenge_source(
+            parameters,
+            transport,
+            RandomChallenges::default(),
+        )
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+        S: ChallengeSource,
+    > TransportProverChannel<G, P, HP, T, S>
+{
+    /// Draws the challenges from `challenges` instead of sampling them with
+    /// the OS RNG, e.g., from a seeded RNG to reproduce a run.
+    pub fn with_challenge_source(
+        parameters: &Parameters,
+        transport: T,
+        challenges: S,
+    ) -> TransportProverChannel<G, P, HP, T, S> {
+        TransportProverChannel {
+            parameters: parameters.clone(),
+            transport,
+            challenges,
+            message_types: PhantomData,
+        }
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    /// Answers the prover's handshake with ours and checks it. Should be
+    /// called before verifying.
+    pub fn handshake(&mut self) -> Result<(), ChannelError> {
+        let ours = Handshake::new::<G, P, HP>(&self.parameters);
+        let theirs = match self.transport.receive()? {
+            MembershipMessage::Handshake(theirs) => theirs,
+            _ => return Err(ChannelError::UnexpectedMessage),
+        };
+        // The prover gets our handshake even on a mismatch, so that it fails
+        // fast as well.
+        self.transport
+            .send(MembershipMessage::Handshake(ours.clone()))?;
+        ours.check(&theirs)
+    }
+
+    fn sample_and_send_challenge(&mut self, label: &'static [u8]) -> Result<Integer, ChannelError> {
+        let c = self
+            .challenges
+            .challenge(label, self.parameters.security_soundness)?;
+        self.transport
+            .send(MembershipMessage::Challenge(c.clone()))?;
+        Ok(c)
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+    > MembershipVerifierChannel<G> for TransportVerifierChannel<G, P, HP, T>
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::Epoch(epoch.to_vec()))
+    }
+    fn send_c_e(&mut self, c_e: &G::Elem) -> Result<(), ChannelError> {
+        self.transport.send(MembershipMessage::CE(c_e.clone()))
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+    > RootVerifierChannel<G> for TransportVerifierChannel<G, P, HP, T>
+{
+    fn send_message1(&mut self, message: &RootMessage1<G>) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::RootMessage1(message.clone()))
+    }
+    fn send_message2(&mut self, message: &RootMessage2<G>) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::RootMessage2(message.clone()))
+    }
+    fn send_message3(&mut self, message: &RootMessage3) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::RootMessage3(message.clone()))
+    }
+    fn send_message4(&mut self, message: &RootMessage4<G>) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::RootMessage4(message.clone()))
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive_challenge_message()
+    }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive_challenge_message()
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+    > ModEqVerifierChannel<G, P> for TransportVerifierChannel<G, P, HP, T>
+{
+    fn send_message1(&mut self, message: &ModEqMessage1<G, P>) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::ModEqMessage1(message.clone()))
+    }
+    fn send_message2(&mut self, message: &ModEqMessage2<P>) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::ModEqMessage2(message.clone()))
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.receive_challenge_message()
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+    > HashToPrimeVerifierChannel<P, HP> for TransportVerifierChannel<G, P, HP, T>
+{
+    fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), ChannelError> {
+        self.transport
+            .send(MembershipMessage::HashToPrimeProof(proof.clone()))
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+        S: ChallengeSource,
+    > MembershipProverChannel<G> for TransportProverChannel<G, P, HP, T, S>
+{
+    /// Fails if the prover bound the proof to a different accumulator state.
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::Epoch(e) if e == epoch => Ok(()),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_c_e(&mut self) -> Result<G::Elem, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::CE(c_e) => Ok(c_e),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+        S: ChallengeSource,
+    > RootProverChannel<G> for TransportProverChannel<G, P, HP, T, S>
+{
+    fn receive_message1(&mut self) -> Result<RootMessage1<G>, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::RootMessage1(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message2(&mut self) -> Result<RootMessage2<G>, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::RootMessage2(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message3(&mut self) -> Result<RootMessage3, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::RootMessage3(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message4(&mut self) -> Result<RootMessage4<G>, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::RootMessage4(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.sample_and_send_challenge(b"c")
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.sample_and_send_challenge(b"l")
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+        S: ChallengeSource,
+    > ModEqProverChannel<G, P> for TransportProverChannel<G, P, HP, T, S>
+{
+    fn receive_message1(&mut self) -> Result<ModEqMessage1<G, P>, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::ModEqMessage1(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn receive_message2(&mut self) -> Result<ModEqMessage2<P>, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::ModEqMessage2(message) => Ok(message),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.sample_and_send_challenge(b"c")
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        T: MessageTransport<G, P, HP>,
+        S: ChallengeSource,
+    > HashToPrimeProverChannel<P, HP> for TransportProverChannel<G, P, HP, T, S>
+{
+    fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        match self.transport.receive()? {
+            MembershipMessage::HashToPrimeProof(proof) => Ok(proof),
+            _ => Err(ChannelError::UnexpectedMessage),
+        }
+    }
+}
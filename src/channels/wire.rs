@@ -0,0 +1,270 @@
+//! The messages of the membership protocol and their byte encoding.
+//!
+//! Each message starts with a tag byte, followed by its fields. Group
+//! elements, curve points, proofs and byte strings are prefixed by their
+//! length as a big-endian u32, and integers additionally carry a sign byte.
+use crate::{
+    channels::{handshake::Handshake, ChannelError},
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        modeq::{Message1 as ModEqMessage1, Message2 as ModEqMessage2},
+        root::{
+            Message1 as RootMessage1, Message2 as RootMessage2, Message3 as RootMessage3,
+            Message4 as RootMessage4,
+        },
+    },
+    utils::{
+        bigint_to_bytes, bytes_big_endian_to_bits_big_endian, bytes_to_integer,
+        curve::{CurveError, CurvePointProjective, Field},
+        group::ElemFromBytes,
+        integer_to_bytes, ConvertibleUnknownOrderGroup,
+    },
+};
+use rug::Integer;
+use std::convert::TryInto;
+
+pub enum MembershipMessage<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    Epoch(Vec<u8>),
+    CE(G::Elem),
+    RootMessage1(RootMessage1<G>),
+    RootMessage2(RootMessage2<G>),
+    RootMessage3(RootMessage3),
+    RootMessage4(RootMessage4<G>),
+    ModEqMessage1(ModEqMessage1<G, P>),
+    ModEqMessage2(ModEqMessage2<P>),
+    HashToPrimeProof(HP::Proof),
+    Challenge(Integer),
+    Handshake(Handshake),
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn write_integer(out: &mut Vec<u8>, integer: &Integer) {
+    out.push((*integer < 0) as u8);
+    write_bytes(out, &integer_to_bytes(&Integer::from(integer.abs_ref())));
+}
+
+fn write_elem<G: ConvertibleUnknownOrderGroup>(out: &mut Vec<u8>, elem: &G::Elem) {
+    write_bytes(out, &G::elem_to_bytes(elem));
+}
+
+fn write_point<P: CurvePointProjective>(out: &mut Vec<u8>, point: &P) -> Result<(), CurveError> {
+    write_bytes(out, &point.to_affine_bytes()?);
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read_u8(&mut self) -> Result<u8, ChannelError> {
+        let (first, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(ChannelError::MalformedMessage)?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], ChannelError> {
+        if self.bytes.len() < 4 {
+            return Err(ChannelError::MalformedMessage);
+        }
+        let (length, rest) = self.bytes.split_at(4);
+        let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+        if rest.len() < length {
+            return Err(ChannelError::MalformedMessage);
+        }
+        let (bytes, rest) = rest.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    fn read_string(&mut self) -> Result<String, ChannelError> {
+        String::from_utf8(self.read_bytes()?.to_vec()).map_err(|_| ChannelError::MalformedMessage)
+    }
+
+    fn read_integer(&mut self) -> Result<Integer, ChannelError> {
+        let is_negative = match self.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(ChannelError::MalformedMessage),
+        };
+        let magnitude = bytes_to_integer(self.read_bytes()?);
+        Ok(if is_negative { -magnitude } else { magnitude })
+    }
+
+    fn read_elem<G: ElemFromBytes>(&mut self) -> Result<G::Elem, ChannelError> {
+        G::elem_from_bytes(self.read_bytes()?).ok_or(ChannelError::MalformedMessage)
+    }
+
+    fn read_point<P: CurvePointProjective>(&mut self) -> Result<P, ChannelError> {
+        Ok(P::from_affine_bytes(self.read_bytes()?)?)
+    }
+
+    fn read_scalar<P: CurvePointProjective>(&mut self) -> Result<P::ScalarField, ChannelError> {
+        scalar_from_bytes::<P>(self.read_bytes()?)
+    }
+}
+
+/// Reads a scalar encoded by `bigint_to_bytes`.
+pub(crate) fn scalar_from_bytes<P: CurvePointProjective>(
+    bytes: &[u8],
+) -> Result<P::ScalarField, ChannelError> {
+    if bytes.len() * 8 < P::ScalarField::size_in_bits() {
+        return Err(ChannelError::MalformedMessage);
+    }
+    Ok(P::ScalarField::from_bits(
+        &bytes_big_endian_to_bits_big_endian(bytes),
+    ))
+}
+
+pub fn encode_message<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    message: &MembershipMessage<G, P, HP>,
+) -> Result<Vec<u8>, ChannelError> {
+    let mut out = vec![];
+    match message {
+        MembershipMessage::Epoch(epoch) => {
+            out.push(0);
+            write_bytes(&mut out, epoch);
+        }
+        MembershipMessage::CE(c_e) => {
+            out.push(1);
+            write_elem::<G>(&mut out, c_e);
+        }
+        MembershipMessage::RootMessage1(message) => {
+            out.push(2);
+            write_elem::<G>(&mut out, &message.c_w);
+            write_elem::<G>(&mut out, &message.c_r);
+        }
+        MembershipMessage::RootMessage2(message) => {
+            out.push(3);
+            write_elem::<G>(&mut out, &message.alpha1);
+            write_elem::<G>(&mut out, &message.alpha2);
+            write_elem::<G>(&mut out, &message.alpha3);
+            write_elem::<G>(&mut out, &message.alpha4);
+        }
+        MembershipMessage::RootMessage3(message) => {
+            out.push(4);
+            write_integer(&mut out, &message.s_e);
+            write_integer(&mut out, &message.s_r);
+            write_integer(&mut out, &message.s_r_2);
+            write_integer(&mut out, &message.s_r_3);
+            write_integer(&mut out, &message.s_beta);
+            write_integer(&mut out, &message.s_delta);
+        }
+        MembershipMessage::RootMessage4(message) => {
+            out.push(5);
+            write_elem::<G>(&mut out, &message.q1);
+            write_elem::<G>(&mut out, &message.q2);
+            write_elem::<G>(&mut out, &message.q3);
+            write_elem::<G>(&mut out, &message.q4);
+        }
+        MembershipMessage::ModEqMessage1(message) => {
+            out.push(6);
+            write_elem::<G>(&mut out, &message.alpha1);
+            write_point(&mut out, &message.alpha2)?;
+        }
+        MembershipMessage::ModEqMessage2(message) => {
+            out.push(7);
+            write_integer(&mut out, &message.s_e);
+            write_integer(&mut out, &message.s_r);
+            write_bytes(&mut out, &bigint_to_bytes::<P>(&message.s_r_q));
+        }
+        MembershipMessage::HashToPrimeProof(proof) => {
+            out.push(8);
+            write_bytes(&mut out, &HP::proof_to_bytes(proof)?);
+        }
+        MembershipMessage::Challenge(c) => {
+            out.push(9);
+            write_integer(&mut out, c);
+        }
+        MembershipMessage::Handshake(handshake) => {
+            out.push(10);
+            write_bytes(&mut out, &handshake.version.to_be_bytes());
+            write_bytes(&mut out, &handshake.parameters_fingerprint);
+            write_bytes(&mut out, handshake.group.as_bytes());
+            write_bytes(&mut out, handshake.curve.as_bytes());
+            write_bytes(&mut out, handshake.hash_to_prime.as_bytes());
+        }
+    }
+    Ok(out)
+}
+
+/// Decodes a message, rejecting unknown tags and trailing bytes.
+pub fn decode_message<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    bytes: &[u8],
+) -> Result<MembershipMessage<G, P, HP>, ChannelError> {
+    let mut reader = Reader { bytes };
+    let message = match reader.read_u8()? {
+        0 => MembershipMessage::Epoch(reader.read_bytes()?.to_vec()),
+        1 => MembershipMessage::CE(reader.read_elem::<G>()?),
+        2 => MembershipMessage::RootMessage1(RootMessage1 {
+            c_w: reader.read_elem::<G>()?,
+            c_r: reader.read_elem::<G>()?,
+        }),
+        3 => MembershipMessage::RootMessage2(RootMessage2 {
+            alpha1: reader.read_elem::<G>()?,
+            alpha2: reader.read_elem::<G>()?,
+            alpha3: reader.read_elem::<G>()?,
+            alpha4: reader.read_elem::<G>()?,
+        }),
+        4 => MembershipMessage::RootMessage3(RootMessage3 {
+            s_e: reader.read_integer()?,
+            s_r: reader.read_integer()?,
+            s_r_2: reader.read_integer()?,
+            s_r_3: reader.read_integer()?,
+            s_beta: reader.read_integer()?,
+            s_delta: reader.read_integer()?,
+        }),
+        5 => MembershipMessage::RootMessage4(RootMessage4 {
+            q1: reader.read_elem::<G>()?,
+            q2: reader.read_elem::<G>()?,
+            q3: reader.read_elem::<G>()?,
+            q4: reader.read_elem::<G>()?,
+        }),
+        6 => MembershipMessage::ModEqMessage1(ModEqMessage1 {
+            alpha1: reader.read_elem::<G>()?,
+            alpha2: reader.read_point::<P>()?,
+        }),
+        7 => MembershipMessage::ModEqMessage2(ModEqMessage2 {
+            s_e: reader.read_integer()?,
+            s_r: reader.read_integer()?,
+            s_r_q: reader.read_scalar::<P>()?,
+        }),
+        8 => MembershipMessage::HashToPrimeProof(HP::proof_from_bytes(reader.read_bytes()?)?),
+        9 => MembershipMessage::Challenge(reader.read_integer()?),
+        10 => MembershipMessage::Handshake(Handshake {
+            version: u16::from_be_bytes(
+                reader
+                    .read_bytes()?
+                    .try_into()
+                    .map_err(|_| ChannelError::MalformedMessage)?,
+            ),
+            parameters_fingerprint: reader
+                .read_bytes()?
+                .try_into()
+                .map_err(|_| ChannelError::MalformedMessage)?,
+            group: reader.read_string()?,
+            curve: reader.read_string()?,
+            hash_to_prime: reader.read_string()?,
+        }),
+        _ => return Err(ChannelError::MalformedMessage),
+    };
+    if !reader.bytes.is_empty() {
+        return Err(ChannelError::MalformedMessage);
+    }
+    Ok(message)
+}
@@ -0,0 +1,246 @@
+//! Pedersen commitments over a curve embedded in the scalar field of a
+//! pairing curve, e.g., Jubjub over BLS12-381 or Edwards BLS12-377 over
+//! BLS12-377.
+//!
+//! The coordinates of the embedded curve are elements of the pairing curve's
+//! scalar field, so a commitment `c_e_q` over it can be opened again in a
+//! circuit over that field for a few thousand constraints, e.g., to prove more
+//! about the committed element in another SNARK. The protocols run over
+//! these curves with a pairing-free hash-to-prime backend such as
+//! `sigma_range`.
+use crate::{
+    commitments::{pedersen::PedersenCommitment, CommitmentError},
+    utils::integer_to_bigint_mod_q,
+};
+use ark_ec::ProjectiveCurve;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, eq::EqGadget, groups::CurveVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use rug::Integer;
+use std::marker::PhantomData;
+
+/// Enforces that the commitment opens to the little-endian bits of the value
+/// and randomness, with the bases as constants.
+pub fn enforce_opening<C, F, GG>(
+    parameters: &PedersenCommitment<C>,
+    commitment: &GG,
+    value: &[Boolean<F>],
+    randomness: &[Boolean<F>],
+) -> Result<(), SynthesisError>
+where
+    C: ProjectiveCurve<BaseField = F>,
+    F: PrimeField,
+    GG: CurveVar<C, F>,
+{
+    let g = GG::constant(parameters.g);
+    let h = GG::constant(parameters.h);
+    let opened = g.scalar_mul_le(value.iter())? + h.scalar_mul_le(randomness.iter())?;
+    opened.enforce_equal(commitment)
+}
+
+/// Allocates the little-endian bits of a scalar of the embedded curve.
+fn alloc_scalar_bits<C, F>(
+    cs: ConstraintSystemRef<F>,
+    scalar: Option<C::ScalarField>,
+) -> Result<Vec<Boolean<F>>, SynthesisError>
+where
+    C: ProjectiveCurve<BaseField = F>,
+    F: PrimeField,
+{
+    let size = C::ScalarField::size_in_bits();
+    let bits = match scalar {
+        Some(scalar) => scalar
+            .into_repr()
+            .to_bits_le()
+            .into_iter()
+            .take(size)
+            .map(Some)
+            .collect(),
+        None => vec![None; size],
+    };
+    bits.into_iter()
+        .map(|bit| {
+            Boolean::new_witness(cs.clone(), || bit.ok_or(SynthesisError::AssignmentMissing))
+        })
+        .collect()
+}
+
+/// Proves knowledge of an opening of a public commitment over the embedded
+/// curve, in a circuit over its base field.
+pub struct OpeningCircuit<C: ProjectiveCurve, GG> {
+    parameters: PedersenCommitment<C>,
+    commitment: C,
+    value: Option<C::ScalarField>,
+    randomness: Option<C::ScalarField>,
+    _curve_var: PhantomData<GG>,
+}
+
+impl<C: ProjectiveCurve, GG> OpeningCircuit<C, GG> {
+    /// The circuit for the commitment, with its opening or none when only
+    /// the constraints are needed.
+    pub fn new(
+        parameters: &PedersenCommitment<C>,
+        commitment: &C,
+        opening: Option<(&Integer, &Integer)>,
+    ) -> Result<OpeningCircuit<C, GG>, CommitmentError> {
+        let (value, randomness) = match opening {
+            Some((value, randomness)) => (
+                Some(
+                    integer_to_bigint_mod_q::<C>(value)
+                        .map_err(|_| CommitmentError::IntegerTooBig)?,
+                ),
+                Some(
+                    integer_to_bigint_mod_q::<C>(randomness)
+                        .map_err(|_| CommitmentError::IntegerTooBig)?,
+                ),
+            ),
+            None => (None, None),
+        };
+        Ok(OpeningCircuit {
+            parameters: parameters.clone(),
+            commitment: *commitment,
+            value,
+            randomness,
+            _curve_var: PhantomData,
+        })
+    }
+}
+
+impl<C, F, GG> ConstraintSynthesizer<F> for OpeningCircuit<C, GG>
+where
+    C: ProjectiveCurve<BaseField = F>,
+    F: PrimeField,
+    GG: CurveVar<C, F>,
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let commitment =
+            GG::new_input(ark_relations::ns!(cs, "commitment"), || Ok(self.commitment))?;
+        let value = alloc_scalar_bits::<C, F>(cs.clone(), self.value)?;
+        let randomness = alloc_scalar_bits::<C, F>(cs, self.randomness)?;
+        enforce_opening(&self.parameters, &commitment, &value, &randomness)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OpeningCircuit;
+    use crate::{
+        commitments::{pedersen::PedersenCommitment, Commitment},
+        parameters::Parameters,
+        protocols::{hash_to_prime::sigma_range, membership},
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_ec::ProjectiveCurve;
+    use ark_ed_on_bls12_377::{
+        constraints::EdwardsVar as EdBls12_377Var, EdwardsProjective as EdBls12_377,
+    };
+    use ark_ed_on_bls12_381::{constraints::EdwardsVar as JubjubVar, EdwardsProjective as Jubjub};
+    use ark_r1cs_std::groups::CurveVar;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    fn opens<C: ProjectiveCurve, GG: CurveVar<C, C::BaseField>>(
+        parameters: &PedersenCommitment<C>,
+        commitment: &C,
+        value: &Integer,
+        randomness: &Integer,
+    ) -> bool
+    where
+        C::BaseField: ark_ff::PrimeField,
+    {
+        let cs = ConstraintSystem::new_ref();
+        OpeningCircuit::<C, GG>::new(parameters, commitment, Some((value, randomness)))
+            .unwrap()
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    fn test_curve<C: ProjectiveCurve, GG: CurveVar<C, C::BaseField>>()
+    where
+        C::BaseField: ark_ff::PrimeField,
+    {
+        let mut rng = thread_rng();
+        let parameters = PedersenCommitment::<C>::setup(&mut rng);
+        let value = Integer::from(Integer::u_pow_u(2, 240)) - 245;
+        let randomness = Integer::from(9);
+        let commitment = parameters.commit(&value, &randomness).unwrap();
+        assert!(opens::<C, GG>(
+            &parameters,
+            &commitment,
+            &value,
+            &randomness
+        ));
+        assert!(!opens::<C, GG>(
+            &parameters,
+            &commitment,
+            &value,
+            &Integer::from(10)
+        ));
+    }
+
+    #[test]
+    fn test_opening_jubjub() {
+        test_curve::<Jubjub, JubjubVar>();
+    }
+
+    #[test]
+    fn test_opening_ed_on_bls12_377() {
+        test_curve::<EdBls12_377, EdBls12_377Var>();
+    }
+
+    /// The commitment of a membership proof over Jubjub opens in a circuit
+    /// over the scalar field of BLS12-381.
+    #[test]
+    fn test_membership_jubjub() {
+        let (params, _) = Parameters::from_curve::<ark_ed_on_bls12_381::Fr>().unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol =
+            membership::Protocol::<Rsa2048, Jubjub, sigma_range::Protocol<Jubjub>>::setup(
+                &params, &mut rng1, &mut rng2,
+            )
+            .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        let commitment = pedersen.commit(&value, &randomness).unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = membership::Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &membership::Witness {
+                    e: value.clone(),
+                    r_q: randomness.clone(),
+                    w: accum.1.witness.0.value,
+                },
+                b"context",
+            )
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+
+        assert!(opens::<Jubjub, JubjubVar>(
+            pedersen,
+            &statement.c_e_q,
+            &value,
+            &randomness
+        ));
+    }
+}
@@ -2,8 +2,9 @@
 
 use crate::{
     commitments::{Commitment, CommitmentError},
-    utils::ConvertibleUnknownOrderGroup,
+    utils::{group::FixedBaseTable, join, rng::fork_rng, ConvertibleUnknownOrderGroup},
 };
+use rand::{CryptoRng, RngCore};
 use rug::rand::MutRandState;
 use rug::Integer;
 
@@ -27,6 +28,25 @@ impl<G: ConvertibleUnknownOrderGroup> IntegerCommitment<G> {
             h: h.clone(),
         }
     }
+
+    /// Commits to a secret value with secret randomness through
+    /// `G::exp_secret`, failing if a base isn't invertible.
+    pub fn commit_secret<R: RngCore + CryptoRng>(
+        &self,
+        value: &Integer,
+        randomness: &Integer,
+        rng: &mut R,
+    ) -> Result<G::Elem, CommitmentError> {
+        let (mut rng_g, mut rng_h) = (fork_rng(rng), fork_rng(rng));
+        let (g_value, h_randomness) = join(
+            || G::exp_secret(&self.g, value, &mut rng_g),
+            || G::exp_secret(&self.h, randomness, &mut rng_h),
+        );
+        match (g_value, h_randomness) {
+            (Some(g_value), Some(h_randomness)) => Ok(G::op(&g_value, &h_randomness)),
+            _ => Err(CommitmentError::NonInvertibleBase),
+        }
+    }
 }
 
 impl<G: ConvertibleUnknownOrderGroup> Commitment for IntegerCommitment<G> {
@@ -37,7 +57,9 @@ impl<G: ConvertibleUnknownOrderGroup> Commitment for IntegerCommitment<G> {
         value: &Integer,
         randomness: &Integer,
     ) -> Result<Self::Instance, CommitmentError> {
-        Ok(G::op(&G::exp(&self.g, value), &G::exp(&self.h, randomness)))
+        let (g_value, h_randomness) =
+            join(|| G::exp(&self.g, value), || G::exp(&self.h, randomness));
+        Ok(G::op(&g_value, &h_randomness))
     }
 
     fn open(
@@ -46,8 +68,7 @@ impl<G: ConvertibleUnknownOrderGroup> Commitment for IntegerCommitment<G> {
         value: &Integer,
         randomness: &Integer,
     ) -> Result<(), CommitmentError> {
-        let expected = G::op(&G::exp(&self.g, value), &G::exp(&self.h, randomness));
-        if expected == *commitment {
+        if self.commit(value, randomness)? == *commitment {
             Ok(())
         } else {
             Err(CommitmentError::WrongOpening)
@@ -55,11 +76,52 @@ impl<G: ConvertibleUnknownOrderGroup> Commitment for IntegerCommitment<G> {
     }
 }
 
+/// Fixed-base tables for the bases of an integer commitment, to be stored
+/// alongside a CRS.
+#[derive(Clone)]
+pub struct IntegerCommitmentTables<G: ConvertibleUnknownOrderGroup> {
+    pub g: FixedBaseTable<G>,
+    pub h: FixedBaseTable<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> IntegerCommitmentTables<G> {
+    pub fn new(parameters: &IntegerCommitment<G>, max_bits: u32) -> IntegerCommitmentTables<G> {
+        IntegerCommitmentTables {
+            g: FixedBaseTable::new(&parameters.g, max_bits),
+            h: FixedBaseTable::new(&parameters.h, max_bits),
+        }
+    }
+
+    pub fn commit(&self, value: &Integer, randomness: &Integer) -> G::Elem {
+        let (g_value, h_randomness) = join(|| self.g.exp(value), || self.h.exp(randomness));
+        G::op(&g_value, &h_randomness)
+    }
+
+    /// Commits through `G::exp_secret` on the bases, without the tables.
+    pub fn commit_secret<R: RngCore + CryptoRng>(
+        &self,
+        value: &Integer,
+        randomness: &Integer,
+        rng: &mut R,
+    ) -> Result<G::Elem, CommitmentError> {
+        let (mut rng_g, mut rng_h) = (fork_rng(rng), fork_rng(rng));
+        let (g_value, h_randomness) = join(
+            || self.g.exp_secret(value, &mut rng_g),
+            || self.h.exp_secret(randomness, &mut rng_h),
+        );
+        match (g_value, h_randomness) {
+            (Some(g_value), Some(h_randomness)) => Ok(G::op(&g_value, &h_randomness)),
+            _ => Err(CommitmentError::NonInvertibleBase),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::IntegerCommitment;
+    use super::{IntegerCommitment, IntegerCommitmentTables};
     use crate::commitments::Commitment;
     use accumulator::group::Rsa2048;
+    use rand::thread_rng;
     use rug::rand::RandState;
     use rug::Integer;
 
@@ -85,4 +147,31 @@ mod test {
             .open(&commitment, &wrong_value, &wrong_randomness)
             .unwrap_err();
     }
+
+    #[test]
+    fn test_commitment_tables() {
+        let mut rng = RandState::new();
+        rng.seed(&Integer::from(13));
+
+        let integer = IntegerCommitment::<Rsa2048>::setup(&mut rng);
+        let tables = IntegerCommitmentTables::new(&integer, 128);
+        for (value, randomness) in &[
+            (Integer::from(2), Integer::from(5)),
+            (Integer::from(-7), Integer::from(Integer::u_pow_u(2, 127))),
+            (Integer::from(Integer::u_pow_u(2, 200)), Integer::from(-3)),
+        ] {
+            assert_eq!(
+                tables.commit(value, randomness),
+                integer.commit(value, randomness).unwrap()
+            );
+            assert_eq!(
+                tables
+                    .commit_secret(value, randomness, &mut thread_rng())
+                    .unwrap(),
+                integer
+                    .commit_secret(value, randomness, &mut thread_rng())
+                    .unwrap()
+            );
+        }
+    }
 }
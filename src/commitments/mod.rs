@@ -1,19 +1,23 @@
 //! Implements integer and Pedersen commitments.
 
 use rug::Integer;
+use thiserror::Error;
 
+#[cfg(feature = "arkworks")]
+pub mod embedded;
 pub mod integer;
 pub mod pedersen;
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum CommitmentError {
-        WrongOpening {}
-        IntegerTooBig {}
-        ConversionError(err: std::io::Error) {
-            from()
-        }
-    }
+#[derive(Debug, Error)]
+pub enum CommitmentError {
+    #[error("the opening doesn't match the commitment")]
+    WrongOpening,
+    #[error("the integer is too big to commit to")]
+    IntegerTooBig,
+    #[error("the base is not invertible")]
+    NonInvertibleBase,
+    #[error("could not convert the integer to a scalar")]
+    ConversionError(#[from] std::io::Error),
 }
 
 pub trait Commitment {
@@ -36,7 +36,7 @@ impl<P: CurvePointProjective> Commitment for PedersenCommitment<P> {
     ) -> Result<Self::Instance, CommitmentError> {
         let v = integer_to_bigint::<P>(value);
         let r = integer_to_bigint::<P>(randomness);
-        Ok(self.g.mul(&v).add(&self.h.mul(&r)))
+        Ok(P::msm(&[self.g.clone(), self.h.clone()], &[v, r]))
     }
 
     fn open(
@@ -45,10 +45,7 @@ impl<P: CurvePointProjective> Commitment for PedersenCommitment<P> {
         value: &Integer,
         randomness: &Integer,
     ) -> Result<(), CommitmentError> {
-        let expected = self
-            .g
-            .mul(&integer_to_bigint::<P>(value))
-            .add(&self.h.mul(&integer_to_bigint::<P>(randomness)));
+        let expected = self.commit(value, randomness)?;
         if expected == *commitment {
             Ok(())
         } else {
@@ -86,4 +83,33 @@ mod test {
             .open(&commitment, &wrong_value, &wrong_randomness)
             .unwrap_err();
     }
+
+    #[test]
+    fn test_secp256k1_commitment() {
+        use ark_ec::{AffineCurve, ProjectiveCurve};
+        use ark_ff::UniformRand;
+        use ark_secp256k1::{Affine, Fr, Projective};
+
+        let mut rng = thread_rng();
+
+        // With the standard generator as g, the commitment to a secret key
+        // with no randomness is its public key.
+        let secret_key = Integer::from(0xdeadbeefu32);
+        let public_key = Affine::prime_subgroup_generator().mul(Fr::from(0xdeadbeefu64));
+        let pedersen = PedersenCommitment::new(
+            &Projective::prime_subgroup_generator(),
+            &Projective::rand(&mut rng),
+        );
+        let commitment = pedersen.commit(&secret_key, &Integer::from(0)).unwrap();
+        assert_eq!(commitment, public_key);
+
+        let randomness = Integer::from(5);
+        let commitment = pedersen.commit(&secret_key, &randomness).unwrap();
+        pedersen
+            .open(&commitment, &secret_key, &randomness)
+            .unwrap();
+        pedersen
+            .open(&commitment, &secret_key, &Integer::from(7))
+            .unwrap_err();
+    }
 }
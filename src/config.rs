@@ -0,0 +1,172 @@
+//! Instantiates a membership protocol from a configuration file, so that a
+//! service picks the curve, the backend and the CRS at deployment rather than
+//! at compile time:
+//!
+//! ```toml
+//! curve = "bn254"
+//! backend = "snark_range"
+//! group = "rsa2048"
+//! security_level = 128
+//! crs_path = "/etc/cpsnarks/crs.bin"
+//! ```
+//!
+//! The same fields can be given in JSON. The CRS file holds the encoding
+//! written by `AnyMembershipProtocol::crs_bytes`, and `security_level`, when
+//! set, has to match the one the CRS was generated for.
+use crate::protocols::membership::any::{AnyMembershipError, AnyMembershipProtocol, Instantiation};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("couldn't read {}", .0.display())]
+    IoError(PathBuf, #[source] std::io::Error),
+    #[error("invalid TOML configuration")]
+    TomlError(#[from] toml::de::Error),
+    #[error("invalid JSON configuration")]
+    JsonError(#[from] serde_json::Error),
+    #[error("unknown configuration format of {}", .0.display())]
+    UnknownFormat(PathBuf),
+    #[error("unsupported group {0}")]
+    UnsupportedGroup(String),
+    #[error("configured security level {0} but the CRS is for {1}")]
+    SecurityLevelMismatch(u16, u16),
+    #[error("could not instantiate the protocol")]
+    AnyMembershipError(#[from] AnyMembershipError),
+}
+
+fn default_group() -> String {
+    "rsa2048".to_string()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The curve of the Pedersen commitments, e.g., `bls12_381`.
+    pub curve: String,
+    /// The hash-to-prime backend, e.g., `snark_range`.
+    pub backend: String,
+    /// The group of unknown order. Only `rsa2048` is supported.
+    #[serde(default = "default_group")]
+    pub group: String,
+    pub security_level: Option<u16>,
+    pub crs_path: PathBuf,
+}
+
+impl Config {
+    pub fn from_toml(s: &str) -> Result<Config, ConfigError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Config, ConfigError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Reads a configuration file, in TOML or JSON by its extension. A
+    /// relative `crs_path` is resolved from the directory of the file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let contents =
+            fs::read_to_string(path).map_err(|err| ConfigError::IoError(path.to_owned(), err))?;
+        let mut config = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Config::from_toml(&contents)?,
+            Some("json") => Config::from_json(&contents)?,
+            _ => return Err(ConfigError::UnknownFormat(path.to_owned())),
+        };
+        if config.crs_path.is_relative() {
+            if let Some(dir) = path.parent() {
+                config.crs_path = dir.join(&config.crs_path);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The instantiation named by the curve and the backend.
+    pub fn instantiation(&self) -> Result<Instantiation, ConfigError> {
+        if self.group != "rsa2048" {
+            return Err(ConfigError::UnsupportedGroup(self.group.clone()));
+        }
+        Ok(format!("{}/{}", self.curve, self.backend).parse()?)
+    }
+
+    /// Loads the CRS and creates the protocol from it.
+    pub fn build(&self) -> Result<AnyMembershipProtocol, ConfigError> {
+        let instantiation = self.instantiation()?;
+        let crs_bytes = fs::read(&self.crs_path)
+            .map_err(|err| ConfigError::IoError(self.crs_path.clone(), err))?;
+        let protocol = AnyMembershipProtocol::from_crs_bytes(instantiation, &crs_bytes)?;
+        if let Some(security_level) = self.security_level {
+            let crs_level = protocol.parameters().security_level;
+            if crs_level != security_level {
+                return Err(ConfigError::SecurityLevelMismatch(
+                    security_level,
+                    crs_level,
+                ));
+            }
+        }
+        Ok(protocol)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, ConfigError};
+    use crate::protocols::membership::any::{AnyMembershipProtocol, Instantiation};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::fs;
+
+    #[test]
+    fn test_build() {
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+        let instantiation = Instantiation::JubjubSigmaRange;
+        let params = instantiation.default_parameters().unwrap();
+        let protocol =
+            AnyMembershipProtocol::setup(instantiation, &params, &mut rng1, &mut rng2).unwrap();
+
+        let dir = std::env::temp_dir().join("cpsnarks-set-config-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("crs.bin"), protocol.crs_bytes().unwrap()).unwrap();
+        fs::write(
+            dir.join("config.toml"),
+            format!(
+                "curve = \"jubjub\"\nbackend = \"sigma_range\"\nsecurity_level = {}\ncrs_path = \"crs.bin\"\n",
+                params.security_level
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("config.json"),
+            r#"{"curve": "jubjub", "backend": "sigma_range", "group": "rsa2048", "crs_path": "crs.bin"}"#,
+        )
+        .unwrap();
+
+        for file in &["config.toml", "config.json"] {
+            let config = Config::from_file(dir.join(file)).unwrap();
+            let loaded = config.build().unwrap();
+            assert_eq!(loaded.instantiation(), instantiation);
+            assert_eq!(
+                loaded.parameters().fingerprint(),
+                protocol.parameters().fingerprint()
+            );
+        }
+
+        let mut config = Config::from_file(dir.join("config.toml")).unwrap();
+        config.security_level = Some(params.security_level + 1);
+        assert!(matches!(
+            config.build(),
+            Err(ConfigError::SecurityLevelMismatch(_, _))
+        ));
+        config.group = "class".to_string();
+        assert!(matches!(
+            config.build(),
+            Err(ConfigError::UnsupportedGroup(_))
+        ));
+        assert!(Config::from_toml("curve = \"jubjub\"").is_err());
+    }
+}
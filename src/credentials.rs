@@ -0,0 +1,364 @@
+//! Anonymous credentials from membership proofs. The issuer accumulates the
+//! serial numbers of the credentials it issues, and the holder of a
+//! credential shows it by proving that its committed serial is in the
+//! accumulator, without revealing which one:
+//!
+//! ```ignore
+//! let mut issuer = Issuer::new(&crs);
+//! let mut credential = issuer.issue(&mut rng1, &mut rng2)?;
+//! // ... more credentials are issued, so the holder refreshes the witness
+//! credential.update_witness(issuer.witness(credential.serial())?);
+//!
+//! let presentation = credential.show(&protocol, issuer.accumulator(), b"login", &mut rng1, &mut rng2)?;
+//! presentation.verify(&verifier_key, issuer.accumulator(), b"login")?;
+//! ```
+//!
+//! Each presentation commits to the serial with fresh randomness, so two
+//! presentations of a credential can't be linked to each other or to the
+//! commitment given at issuance. The presentation context, e.g., a verifier
+//! nonce, is bound to the proof, so a presentation can't be replayed in
+//! another context.
+use crate::{
+    accumulators::{universal::UniversalAccumulator, AccumulatorError},
+    commitments::{pedersen::PedersenCommitment, Commitment, CommitmentError},
+    protocols::{
+        hash_to_prime::{HashToPrimeError, HashToPrimeProtocol},
+        membership::{MembershipProof, Protocol, Statement, VerifierKey, Witness, CRS},
+        ProofError, ValidationError, VerificationError,
+    },
+    utils::{
+        bigint_to_integer,
+        curve::{CurvePointProjective, Field},
+        group::ElemFromBytes,
+        serialization::{read_bytes, write_bytes},
+        zeroize_integer, ConvertibleUnknownOrderGroup, Redacted, RedactedInteger,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+use rand::{CryptoRng, RngCore};
+use rug::integer::IsPrime;
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::fmt;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+#[derive(Debug, Error)]
+pub enum CredentialError {
+    #[error("could not update the accumulator")]
+    AccumulatorError(#[from] AccumulatorError),
+    #[error("could not hash the serial to a prime")]
+    HashToPrimeError(#[from] HashToPrimeError),
+    #[error("could not commit to the serial")]
+    CommitmentError(#[from] CommitmentError),
+    #[error("the statement or witness is malformed")]
+    ValidationError(#[from] ValidationError),
+    #[error("could not prove possession of the credential")]
+    ProofError(#[from] ProofError),
+    #[error("the presentation is invalid")]
+    VerificationError(#[from] VerificationError),
+    #[error("the presentation is for another accumulator state")]
+    StaleAccumulator,
+}
+
+/// Binds the presentations to their context, apart from other membership
+/// proofs created with the same context.
+fn presentation_context(context: &[u8]) -> Vec<u8> {
+    let mut bytes = b"cpsnarks-set credential presentation/".to_vec();
+    bytes.extend_from_slice(context);
+    bytes
+}
+
+/// Issues credentials and maintains the accumulator of their serials.
+pub struct Issuer<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    protocol: Protocol<G, P, HP>,
+    accumulator: UniversalAccumulator<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Issuer<G, P, HP>
+{
+    pub fn new(crs: &CRS<G, P, HP>) -> Self {
+        Issuer {
+            protocol: Protocol::from_crs(crs),
+            accumulator: UniversalAccumulator::empty(),
+        }
+    }
+
+    /// The current value of the accumulator, which the presentations are
+    /// verified against.
+    pub fn accumulator(&self) -> &G::Elem {
+        &self.accumulator.value
+    }
+
+    /// Issues a credential with a fresh serial, of `hash_to_prime_bits` bits
+    /// and hashing to a prime that isn't accumulated yet, and accumulates it.
+    /// The witnesses of the other credentials change, see `witness`.
+    pub fn issue<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &mut self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<Credential<G, P>, CredentialError> {
+        let bits = self.protocol.crs.parameters.hash_to_prime_bits as u32;
+        let (serial, prime) = loop {
+            let serial = Integer::from(Integer::random_bits(bits - 1, rng1))
+                + Integer::from(Integer::u_pow_u(2, bits - 1));
+            let (prime, _) = self.protocol.hash_to_prime(&serial)?;
+            if prime.is_probably_prime(30) != IsPrime::No && !self.accumulator.contains(&prime) {
+                break (serial, prime);
+            }
+        };
+        self.accumulator.add(&[prime.clone()])?;
+        self.accumulator.track(&prime)?;
+
+        let pedersen = &self.protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        let randomness = bigint_to_integer::<P>(&P::ScalarField::rand(rng2));
+        let commitment = pedersen.commit(&serial, &randomness)?;
+        let witness = self.tracked_witness(&prime)?;
+        Ok(Credential {
+            serial,
+            randomness,
+            commitment,
+            witness,
+        })
+    }
+
+    /// The current witness of the credential with the serial.
+    pub fn witness(&self, serial: &Integer) -> Result<G::Elem, CredentialError> {
+        let (prime, _) = self.protocol.hash_to_prime(serial)?;
+        self.tracked_witness(&prime)
+    }
+
+    /// Removes the credential with the serial from the accumulator, after
+    /// which it can't be shown.
+    pub fn revoke(&mut self, serial: &Integer) -> Result<(), CredentialError> {
+        let (prime, _) = self.protocol.hash_to_prime(serial)?;
+        self.accumulator.untrack(&prime);
+        self.accumulator.delete(&[prime])?;
+        Ok(())
+    }
+
+    fn tracked_witness(&self, prime: &Integer) -> Result<G::Elem, CredentialError> {
+        Ok(self
+            .accumulator
+            .membership_witness(prime, &Integer::from(0))?
+            .w()
+            .clone())
+    }
+}
+
+/// A credential, held secretly: its serial, the commitment to it given at
+/// issuance with its randomness, and the membership witness of the serial.
+pub struct Credential<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    serial: Integer,
+    randomness: Integer,
+    commitment: <PedersenCommitment<P> as Commitment>::Instance,
+    witness: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Credential<G, P> {
+    pub fn serial(&self) -> &Integer {
+        &self.serial
+    }
+
+    /// The commitment to the serial given at issuance, e.g., for the holder
+    /// to check the credential. The presentations commit anew.
+    pub fn commitment(&self) -> &<PedersenCommitment<P> as Commitment>::Instance {
+        &self.commitment
+    }
+
+    pub fn randomness(&self) -> &Integer {
+        &self.randomness
+    }
+
+    pub fn witness(&self) -> &G::Elem {
+        &self.witness
+    }
+
+    /// Replaces the witness after the accumulator changed.
+    pub fn update_witness(&mut self, witness: G::Elem) {
+        self.witness = witness;
+    }
+
+    /// Proves possession of a credential in the accumulator, bound to the
+    /// context.
+    pub fn show<HP: HashToPrimeProtocol<P>, R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        protocol: &Protocol<G, P, HP>,
+        accumulator: &G::Elem,
+        context: &[u8],
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<Presentation<G, P, HP>, CredentialError> {
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        let randomness = bigint_to_integer::<P>(&P::ScalarField::rand(rng2));
+        let statement = Statement::new(
+            accumulator.clone(),
+            pedersen.commit(&self.serial, &randomness)?,
+        )?;
+        let witness = Witness::new(self.serial.clone(), randomness, self.witness.clone())?;
+        let proof = protocol.prove_noninteractive(
+            rng1,
+            rng2,
+            &statement,
+            &witness,
+            &presentation_context(context),
+        )?;
+        Ok(Presentation { statement, proof })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Zeroize for Credential<G, P> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.serial);
+        zeroize_integer(&mut self.randomness);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> fmt::Debug for Credential<G, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credential")
+            .field("serial", &RedactedInteger(&self.serial))
+            .field("randomness", &RedactedInteger(&self.randomness))
+            .field("commitment", &Redacted)
+            .field("witness", &Redacted)
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Drop for Credential<G, P> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// A shown credential: the statement, with the accumulator and a fresh
+/// commitment to the serial, and the membership proof.
+pub struct Presentation<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub statement: Statement<G, P>,
+    pub proof: MembershipProof<G, P, HP>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Presentation<G, P, HP>
+{
+    /// Verifies the presentation for the current accumulator and the
+    /// context.
+    pub fn verify(
+        &self,
+        key: &VerifierKey<G, P, HP>,
+        accumulator: &G::Elem,
+        context: &[u8],
+    ) -> Result<(), CredentialError> {
+        if self.statement.c_p() != accumulator {
+            return Err(CredentialError::StaleAccumulator);
+        }
+        key.verify_noninteractive(&self.statement, &self.proof, &presentation_context(context))?;
+        Ok(())
+    }
+
+    /// The canonical statement followed by the compressed proof, each
+    /// prefixed by its length.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        write_bytes(&mut bytes, &self.statement.to_canonical_bytes()?)?;
+        write_bytes(&mut bytes, &self.proof.to_canonical_bytes()?)?;
+        Ok(bytes)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Presentation<G, P, HP> {
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, SerializationError> {
+        let statement = Statement::from_canonical_bytes(&read_bytes(&mut bytes)?)?;
+        let proof = MembershipProof::deserialize(&read_bytes(&mut bytes)?[..])?;
+        if !bytes.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Presentation { statement, proof })
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{CredentialError, Issuer, Presentation};
+    use crate::{
+        parameters::Parameters,
+        protocols::{hash_to_prime::snark_range::Protocol as HPProtocol, membership::Protocol},
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::Rsa2048;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+
+    #[test]
+    fn test_issue_and_show() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [21u8; 32],
+            )
+            .unwrap();
+        let key = protocol.crs.verifier_key();
+        let (mut rng1, mut rng2) = seeded_rngs([22u8; 32]);
+
+        let mut issuer = Issuer::new(&protocol.crs);
+        let mut credential = issuer.issue(&mut rng1, &mut rng2).unwrap();
+        let other = issuer.issue(&mut rng1, &mut rng2).unwrap();
+        credential.update_witness(issuer.witness(credential.serial()).unwrap());
+
+        let presentation = credential
+            .show(
+                &protocol,
+                issuer.accumulator(),
+                b"login",
+                &mut rng1,
+                &mut rng2,
+            )
+            .unwrap();
+        let presentation = Presentation::from_bytes(&presentation.to_bytes().unwrap()).unwrap();
+        presentation
+            .verify(&key, issuer.accumulator(), b"login")
+            .unwrap();
+        assert!(presentation
+            .verify(&key, issuer.accumulator(), b"payment")
+            .is_err());
+
+        let again = credential
+            .show(
+                &protocol,
+                issuer.accumulator(),
+                b"login",
+                &mut rng1,
+                &mut rng2,
+            )
+            .unwrap();
+        assert!(again.statement.c_e_q() != presentation.statement.c_e_q());
+        assert!(again.statement.c_e_q() != credential.commitment());
+
+        issuer.revoke(other.serial()).unwrap();
+        assert!(matches!(
+            presentation.verify(&key, issuer.accumulator(), b"login"),
+            Err(CredentialError::StaleAccumulator)
+        ));
+        assert!(issuer.witness(other.serial()).is_err());
+        credential.update_witness(issuer.witness(credential.serial()).unwrap());
+        credential
+            .show(
+                &protocol,
+                issuer.accumulator(),
+                b"login",
+                &mut rng1,
+                &mut rng2,
+            )
+            .unwrap()
+            .verify(&key, issuer.accumulator(), b"login")
+            .unwrap();
+    }
+}
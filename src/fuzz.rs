@@ -0,0 +1,186 @@
+//! Harnesses for the cargo-fuzz targets in `fuzz/`, which feed arbitrary
+//! bytes to the deserializers of the membership CRS, statements and proofs,
+//! and to the verifier. The harnesses return on any input, so a panic, an
+//! abort or a timeout is a bug, as is a mutated proof that verifies.
+//!
+//! The inputs are decoded against the CRS of `test_utils` and one of its
+//! valid statements and proofs, built once per thread. `seeds` returns the
+//! encodings of the fixture to start the corpora from.
+use crate::{
+    protocols::{
+        format,
+        hash_to_prime::snark_range::Protocol as HPProtocol,
+        membership::{verify_membership, CRS},
+    },
+    test_utils::{protocol, valid_proofs, MembershipProtocol, MembershipStatement, Proof, CONTEXT},
+    utils::rng::seeded_rngs,
+};
+use accumulator::group::Rsa2048;
+use ark_bls12_381::{Bls12_381, G1Projective};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+struct Fixture {
+    protocol: MembershipProtocol,
+    statement: MembershipStatement,
+    proof: Proof,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let protocol = protocol();
+        let (statement, proof) = valid_proofs(&protocol).remove(0);
+        Fixture {
+            protocol,
+            statement,
+            proof,
+        }
+    }
+
+    fn crs_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.protocol.crs.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn vk_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.protocol
+            .crs
+            .verifier_key()
+            .serialize(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    fn proof_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.proof.serialize(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+thread_local! {
+    static FIXTURE: Fixture = Fixture::new();
+}
+
+/// The valid encodings of the fixture, named after the targets they seed.
+pub fn seeds() -> Vec<(&'static str, Vec<u8>)> {
+    FIXTURE.with(|fixture| {
+        let statement_bytes = fixture.statement.to_canonical_bytes().unwrap();
+        let proof_bytes = fixture.proof_bytes();
+        let versioned =
+            format::encode(&fixture.proof, &fixture.protocol.crs.parameters, true).unwrap();
+        vec![
+            ("crs", fixture.crs_bytes()),
+            ("statement", statement_bytes.clone()),
+            ("proof", proof_bytes.clone()),
+            ("versioned_proof", versioned),
+            ("verify", split_input(&statement_bytes, &proof_bytes)),
+        ]
+    })
+}
+
+/// Decodes a CRS and runs its validation.
+pub fn crs(data: &[u8]) {
+    let mut reader = data;
+    if let Ok(crs) = CRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(&mut reader) {
+        let (_, mut rng) = seeded_rngs([2u8; 32]);
+        let _ = crs.validate(&mut rng);
+    }
+}
+
+/// Decodes a statement and validates it.
+pub fn statement(data: &[u8]) {
+    if let Ok(statement) = MembershipStatement::from_canonical_bytes(data) {
+        let _ = statement.validate();
+    }
+}
+
+/// Decodes a proof and verifies it against the fixture's statement.
+pub fn proof(data: &[u8]) {
+    let mut reader = data;
+    if let Ok(proof) = Proof::deserialize(&mut reader) {
+        FIXTURE.with(|fixture| {
+            if proof.validate(&fixture.protocol.crs.parameters).is_ok() {
+                let verified =
+                    fixture
+                        .protocol
+                        .verify_noninteractive(&fixture.statement, &proof, CONTEXT);
+                if verified.is_ok() {
+                    let mut bytes = vec![];
+                    proof.serialize(&mut bytes).unwrap();
+                    assert!(bytes == fixture.proof_bytes(), "a mutated proof verified");
+                }
+            }
+        });
+    }
+}
+
+/// Decodes a proof in the versioned format and verifies it against the
+/// fixture's statement.
+pub fn versioned_proof(data: &[u8]) {
+    FIXTURE.with(|fixture| {
+        if let Ok(proof) = format::decode::<Proof>(data, &fixture.protocol.crs.parameters) {
+            let _ = fixture
+                .protocol
+                .verify_noninteractive(&fixture.statement, &proof, CONTEXT);
+        }
+    });
+}
+
+/// Verifies a statement and proof, split by a 2-byte big-endian length
+/// prefix of the statement, from bytes under the fixture's verifier key.
+pub fn verify(data: &[u8]) {
+    if data.len() < 2 {
+        return;
+    }
+    let statement_len = u16::from_be_bytes([data[0], data[1]]) as usize;
+    let rest = &data[2..];
+    if statement_len > rest.len() {
+        return;
+    }
+    let (statement_bytes, proof_bytes) = rest.split_at(statement_len);
+    FIXTURE.with(|fixture| {
+        let _ = verify_membership::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+            &fixture.vk_bytes(),
+            statement_bytes,
+            proof_bytes,
+            CONTEXT,
+        );
+    });
+}
+
+fn split_input(statement_bytes: &[u8], proof_bytes: &[u8]) -> Vec<u8> {
+    let mut input = (statement_bytes.len() as u16).to_be_bytes().to_vec();
+    input.extend_from_slice(statement_bytes);
+    input.extend_from_slice(proof_bytes);
+    input
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crs, proof, seeds, statement, verify, versioned_proof};
+
+    /// Runs every harness on the seeds and on truncated versions and a few
+    /// bit flips of them.
+    #[test]
+    fn test_harnesses() {
+        let harnesses: [(&str, fn(&[u8])); 5] = [
+            ("crs", crs),
+            ("statement", statement),
+            ("proof", proof),
+            ("versioned_proof", versioned_proof),
+            ("verify", verify),
+        ];
+        for (name, seed) in seeds() {
+            let harness = harnesses.iter().find(|(n, _)| *n == name).unwrap().1;
+            harness(&seed);
+            harness(&seed[..seed.len() / 2]);
+            for i in (0..seed.len()).step_by(seed.len() / 16 + 1) {
+                let mut flipped = seed.clone();
+                flipped[i] ^= 1;
+                harness(&flipped);
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@
 //!
 //! `CPMemRSA`, `CPMemRSAPrm`, `CPNonMemRSA`, `CPNonMemRSAPrm`, with RSA and
 //! class groups available as groups of unknown order to be used for the root
-//! protocol, RSA available for the coprime protocol and LegoGroth16 and
+//! and coprime protocols and LegoGroth16 and
 //! Bulletproofs available for the hash-to-prime and range proof protocols. The
 //! hash-to-prime uses Blake2s.
 //!
@@ -18,12 +18,32 @@
 //! and verify functions and compose the subprotocols into end-to-end protocols
 //! ready to use.
 
-#[macro_use]
-extern crate quick_error;
+// The integers are GMP's, through rug, which doesn't build for wasm32, so
+// fail early with the reason rather than in the build script of gmp-mpfr-sys.
+#[cfg(target_arch = "wasm32")]
+compile_error!("cpsnarks-set doesn't support wasm32: its integers use GMP through rug");
 
+pub mod accumulators;
 pub mod channels;
 pub mod commitments;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod credentials;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
+#[cfg(feature = "mobile")]
+pub mod mobile;
 pub mod parameters;
+pub mod prelude;
 pub mod protocols;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod revocation;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 pub mod transcript;
+pub mod transparency;
 pub mod utils;
+
+#[cfg(feature = "mobile")]
+uniffi::setup_scaffolding!();
@@ -0,0 +1,220 @@
+//! UniFFI bindings for wallets proving membership of a credential, over the
+//! RSA-2048 group and BLS12-381 with the LegoGroth16 range proof.
+//!
+//! The wallet loads the CRS from a file, as written by `CanonicalSerialize`,
+//! e.g., one bundled with the app, and receives the accumulator and its
+//! membership witness from the issuer. Integers are passed as decimal strings,
+//! and accumulators, witnesses, commitments and proofs as bytes in the
+//! encodings used by the rest of the crate.
+use crate::{
+    commitments::Commitment,
+    protocols::{
+        hash_to_prime::snark_range::Protocol as HPProtocol,
+        membership::{MembershipProof, Protocol, Statement, Witness, CRS},
+    },
+    utils::{
+        curve::{CurvePointProjective, Field},
+        group::ElemFromBytes,
+        random_between,
+        rng::secure_rngs,
+    },
+};
+use accumulator::group::Rsa2048;
+use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::rngs::OsRng;
+use rug::Integer;
+use std::{fs::File, io::BufReader, sync::Arc};
+use thiserror::Error;
+
+type MembershipProtocol = Protocol<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+type MembershipCRS = CRS<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+type Proof = MembershipProof<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+
+#[derive(Debug, Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum WalletError {
+    #[error("could not load the CRS: {0}")]
+    CrsError(String),
+    #[error("invalid {0}")]
+    InvalidInput(&'static str),
+    #[error("could not create the proof: {0}")]
+    ProofError(String),
+}
+
+fn to_integer(field: &'static str, value: &str) -> Result<Integer, WalletError> {
+    Integer::from_str_radix(value, 10).map_err(|_| WalletError::InvalidInput(field))
+}
+
+/// A commitment to an element with the randomness that opens it, which the
+/// wallet keeps to prove membership of the element.
+#[derive(uniffi::Record)]
+pub struct ElementCommitment {
+    pub commitment: Vec<u8>,
+    pub randomness: String,
+}
+
+#[derive(uniffi::Object)]
+pub struct MembershipWallet {
+    protocol: MembershipProtocol,
+}
+
+#[uniffi::export]
+impl MembershipWallet {
+    /// Loads the CRS from a file and precomputes its fixed-base tables.
+    #[uniffi::constructor]
+    pub fn from_crs_file(path: String) -> Result<Arc<Self>, WalletError> {
+        let file = File::open(&path).map_err(|e| WalletError::CrsError(e.to_string()))?;
+        let mut crs = MembershipCRS::deserialize(BufReader::new(file))
+            .map_err(|e| WalletError::CrsError(format!("{:?}", e)))?;
+        crs.precompute_fixed_base_tables();
+        Ok(Arc::new(MembershipWallet {
+            protocol: MembershipProtocol::from_crs(&crs),
+        }))
+    }
+
+    /// Commits to the element with fresh randomness.
+    pub fn commit(&self, element: String) -> Result<ElementCommitment, WalletError> {
+        let element = to_integer("element", &element)?;
+        let (mut rng, _) = secure_rngs(&mut OsRng);
+        let randomness = random_between(&mut rng, &Integer::new(), &Fr::modulus());
+        let commitment = self
+            .protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&element, &randomness)
+            .map_err(|_| WalletError::InvalidInput("element"))?;
+        Ok(ElementCommitment {
+            commitment: commitment
+                .to_affine_bytes()
+                .map_err(|_| WalletError::InvalidInput("element"))?,
+            randomness: randomness.to_string(),
+        })
+    }
+
+    /// Proves that the committed element is accumulated, given the membership
+    /// witness from the issuer, and returns the serialized proof.
+    pub fn prove(
+        &self,
+        accumulator: Vec<u8>,
+        witness: Vec<u8>,
+        element: String,
+        commitment: ElementCommitment,
+        context: Vec<u8>,
+    ) -> Result<Vec<u8>, WalletError> {
+        let statement = Statement {
+            c_p: Rsa2048::elem_from_bytes(&accumulator)
+                .ok_or(WalletError::InvalidInput("accumulator"))?,
+            c_e_q: G1Projective::from_affine_bytes(&commitment.commitment)
+                .map_err(|_| WalletError::InvalidInput("commitment"))?,
+            epoch: None,
+        };
+        let witness = Witness {
+            e: to_integer("element", &element)?,
+            r_q: to_integer("randomness", &commitment.randomness)?,
+            w: Rsa2048::elem_from_bytes(&witness).ok_or(WalletError::InvalidInput("witness"))?,
+        };
+        let (mut rng1, mut rng2) = secure_rngs(&mut OsRng);
+        let proof = self
+            .protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, &context)
+            .map_err(|e| WalletError::ProofError(format!("{:?}", e)))?;
+        let mut bytes = vec![];
+        proof
+            .serialize(&mut bytes)
+            .map_err(|e| WalletError::ProofError(format!("{:?}", e)))?;
+        Ok(bytes)
+    }
+
+    /// Verifies a serialized proof, returning false for malformed inputs.
+    pub fn verify(
+        &self,
+        accumulator: Vec<u8>,
+        commitment: Vec<u8>,
+        proof: Vec<u8>,
+        context: Vec<u8>,
+    ) -> bool {
+        let c_p = match Rsa2048::elem_from_bytes(&accumulator) {
+            Some(c_p) => c_p,
+            None => return false,
+        };
+        let c_e_q = match G1Projective::from_affine_bytes(&commitment) {
+            Ok(c_e_q) => c_e_q,
+            Err(_) => return false,
+        };
+        let proof = match Proof::deserialize(&proof[..]) {
+            Ok(proof) => proof,
+            Err(_) => return false,
+        };
+        let statement = Statement {
+            c_p,
+            c_e_q,
+            epoch: None,
+        };
+        self.protocol
+            .verify_noninteractive(&statement, &proof, &context)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MembershipProtocol, MembershipWallet};
+    use crate::{parameters::Parameters, utils::group::ElemFromBytes};
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_serialize::CanonicalSerialize;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::fs::File;
+
+    #[test]
+    fn test_wallet() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+        let crs = MembershipProtocol::setup(&params, &mut rng1, &mut rng2)
+            .unwrap()
+            .crs;
+        let path = std::env::temp_dir().join("cpsnarks-set-test-wallet.crs");
+        crs.serialize(File::create(&path).unwrap()).unwrap();
+        let wallet = MembershipWallet::from_crs_file(path.to_string_lossy().into_owned()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let element = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[element.clone()]);
+        let accumulator = Rsa2048::elem_to_bytes(&accum.0.value);
+        let witness = Rsa2048::elem_to_bytes(&accum.1.witness.0.value);
+
+        let commitment = wallet.commit(element.to_string()).unwrap();
+        let commitment_bytes = commitment.commitment.clone();
+        let proof = wallet
+            .prove(
+                accumulator.clone(),
+                witness,
+                element.to_string(),
+                commitment,
+                b"session".to_vec(),
+            )
+            .unwrap();
+        assert!(wallet.verify(
+            accumulator.clone(),
+            commitment_bytes.clone(),
+            proof.clone(),
+            b"session".to_vec()
+        ));
+        assert!(!wallet.verify(
+            accumulator,
+            commitment_bytes,
+            proof,
+            b"other session".to_vec()
+        ));
+        assert!(MembershipWallet::from_crs_file("/nonexistent.crs".to_string()).is_err());
+    }
+}
@@ -1,7 +1,10 @@
 //! Derives secure parameters given a desired security level or curve parameters.
 
 use crate::utils::curve::Field;
-use std::fmt;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use blake2::{Blake2s, Digest};
+use core::fmt;
+use thiserror::Error;
 #[derive(Clone, Debug)]
 pub struct Parameters {
     /// Desired security level. It's an upper bound rather than the final
@@ -30,14 +33,16 @@ impl fmt::Display for Parameters {
     }
 }
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum ParametersError {
-        InvalidParameters {}
-    }
+#[derive(Debug, Error)]
+pub enum ParametersError {
+    #[error("invalid parameters")]
+    InvalidParameters,
 }
 
 impl Parameters {
+    /// The size of the five fields, written as big-endian u16s.
+    pub const SERIALIZED_SIZE: usize = 10;
+
     /// Derive parameters for a desired security level.
     pub fn from_security_level(security_level: u16) -> Result<Parameters, ParametersError> {
         let parameters = Parameters {
@@ -104,6 +109,53 @@ impl Parameters {
         Ok((parameters, security_level))
     }
 
+    /// Minimal discriminant size in bits for a class group to be used as the
+    /// group of unknown order at this security level, checked by the setup and
+    /// the validation of the CRS. The sizes are the estimates of Biasse,
+    /// Jacobson and Silvester for computing class group orders, which are
+    /// smaller than the RSA moduli of the same level since the best known
+    /// algorithms are slower than factoring.
+    pub fn class_group_discriminant_bits(&self) -> u32 {
+        match self.security_level {
+            0..=80 => 1024,
+            81..=112 => 1348,
+            113..=128 => 1827,
+            129..=192 => 3598,
+            _ => 5971,
+        }
+    }
+
+    /// Bit size of the largest response of the root and modeq protocols, for a
+    /// group whose order upper bound has `order_bits` bits. Used to size the
+    /// fixed-base tables.
+    pub fn max_response_bits(&self, order_bits: u32) -> u32 {
+        order_bits
+            + (self.security_zk + self.security_soundness + self.hash_to_prime_bits) as u32
+            + 1
+    }
+
+    fn fields(&self) -> [u16; 5] {
+        [
+            self.security_level,
+            self.security_zk,
+            self.security_soundness,
+            self.hash_to_prime_bits,
+            self.field_size_bits,
+        ]
+    }
+
+    /// A hash of the parameters, to check that two parties or a stored proof
+    /// use the same ones.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = Blake2s::default();
+        for value in self.fields().iter() {
+            hasher.update(&value.to_be_bytes());
+        }
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&hasher.finalize());
+        fingerprint
+    }
+
     /// Check the parameters are valid according to section 4.5 of
     /// the paper.
     pub fn is_valid(&self) -> Result<(), ParametersError> {
@@ -116,9 +168,46 @@ impl Parameters {
     }
 }
 
+impl CanonicalSerialize for Parameters {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        for value in self.fields().iter() {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        Self::SERIALIZED_SIZE
+    }
+}
+
+/// Rejects parameters that aren't valid.
+impl CanonicalDeserialize for Parameters {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut fields = [0u16; 5];
+        for field in fields.iter_mut() {
+            let mut bytes = [0u8; 2];
+            reader.read_exact(&mut bytes)?;
+            *field = u16::from_be_bytes(bytes);
+        }
+        let parameters = Parameters {
+            security_level: fields[0],
+            security_zk: fields[1],
+            security_soundness: fields[2],
+            hash_to_prime_bits: fields[3],
+            field_size_bits: fields[4],
+        };
+        if parameters.hash_to_prime_bits == 0 || parameters.is_valid().is_err() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(parameters)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Parameters;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 
     #[test]
     fn test_valid_for_128() {
@@ -126,6 +215,39 @@ mod test {
         params.is_valid().unwrap();
     }
 
+    #[test]
+    fn test_class_group_discriminant_bits() {
+        let params = Parameters::from_security_level(128).unwrap();
+        assert_eq!(params.class_group_discriminant_bits(), 1827);
+        let params = Parameters::from_security_level(80).unwrap();
+        assert_eq!(params.class_group_discriminant_bits(), 1024);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let params = Parameters::from_security_level(128).unwrap();
+        assert_eq!(
+            params.fingerprint(),
+            Parameters::from_security_level(128).unwrap().fingerprint()
+        );
+        let mut other = params.clone();
+        other.security_zk -= 1;
+        assert_ne!(params.fingerprint(), other.fingerprint());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut bytes = vec![];
+        params.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), Parameters::SERIALIZED_SIZE);
+        let deserialized = Parameters::deserialize(&bytes[..]).unwrap();
+        assert_eq!(deserialized.fingerprint(), params.fingerprint());
+
+        bytes[7] = 0;
+        assert!(Parameters::deserialize(&bytes[..]).is_err());
+    }
+
     #[cfg(all(test, feature = "arkworks"))]
     #[test]
     fn test_valid_for_some_fields() {
@@ -136,4 +258,40 @@ mod test {
         );
         params_with_security_level.0.is_valid().unwrap();
     }
+
+    #[cfg(all(test, feature = "arkworks"))]
+    #[test]
+    fn test_bn254() {
+        // The scalar field of BN254 has 254 bits, so the elements have 252
+        // bits instead of 254.
+        let (params, security_level) = Parameters::from_curve::<ark_bn254::Fr>().unwrap();
+        assert_eq!(security_level, 127);
+        assert_eq!(params.hash_to_prime_bits, 252);
+        assert!(params.hash_to_prime_bits < params.field_size_bits);
+    }
+
+    #[cfg(all(test, feature = "arkworks"))]
+    #[test]
+    fn test_bls12_377() {
+        let (params, security_level) = Parameters::from_curve::<ark_bls12_377::Fr>().unwrap();
+        assert_eq!(params.field_size_bits, 253);
+        assert_eq!(security_level, 126);
+        assert_eq!(params.hash_to_prime_bits, 250);
+        let (params, _) =
+            Parameters::from_curve_and_small_prime_size::<ark_bls12_377::Fr>(60, 80).unwrap();
+        params.is_valid().unwrap();
+    }
+
+    #[cfg(all(test, feature = "arkworks"))]
+    #[test]
+    fn test_bw6_761() {
+        // The scalar field is the 377-bit base field of BLS12-377, so the
+        // derived level is above the usual 128 bits, which fit as well.
+        let (params, security_level) = Parameters::from_curve::<ark_bw6_761::Fr>().unwrap();
+        assert_eq!(params.field_size_bits, 377);
+        assert_eq!(security_level, 188);
+        assert_eq!(params.hash_to_prime_bits, 374);
+        let params = Parameters::from_security_level(128).unwrap();
+        assert!((params.hash_to_prime_bits as usize) < 377);
+    }
 }
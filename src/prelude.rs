@@ -0,0 +1,105 @@
+//! The types and traits most uses of the crate need, under stable names, so
+//! that one `use cpsnarks_set::prelude::*;` replaces the paths into the
+//! protocol modules:
+//!
+//! ```ignore
+//! use cpsnarks_set::prelude::*;
+//!
+//! let params = Parameters::from_security_level(128)?;
+//! let protocol = MembershipProtocol::<Rsa2048, G1Projective, SnarkRange<Bls12_381>>::setup(
+//!     &params, &mut rng1, &mut rng2,
+//! )?;
+//! ```
+//!
+//! The protocols, statements and witnesses of membership and non-membership
+//! share their names in their modules, so they're prefixed here.
+pub use crate::{
+    channels::{
+        memory::{interactive_channels, memory_transports},
+        ChannelError, RandomChallenges,
+    },
+    commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment, Commitment},
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{sigma_range::Protocol as SigmaRange, HashToPrimeProtocol},
+        membership::{
+            facade::{MembershipProver, MembershipVerifier},
+            transcript::{
+                TranscriptProverChannel as MembershipTranscriptProverChannel,
+                TranscriptVerifierChannel as MembershipTranscriptVerifierChannel,
+            },
+            MembershipProof, Protocol as MembershipProtocol, Statement as MembershipStatement,
+            VerifierKey as MembershipVerifierKey, Witness as MembershipWitness,
+            CRS as MembershipCRS,
+        },
+        nonmembership::{
+            transcript::{
+                TranscriptProverChannel as NonMembershipTranscriptProverChannel,
+                TranscriptVerifierChannel as NonMembershipTranscriptVerifierChannel,
+            },
+            NonMembershipProof, Protocol as NonMembershipProtocol,
+            Statement as NonMembershipStatement, VerifierKey as NonMembershipVerifierKey,
+            Witness as NonMembershipWitness, CRS as NonMembershipCRS,
+        },
+        ProofError, SetupError, VerificationError,
+    },
+    transcript::FiatShamir,
+    utils::{
+        curve::CurvePointProjective,
+        group::{ElemFromBytes, UnknownOrderGroup},
+        rng::{secure_rngs, seeded_rngs},
+        ConvertibleUnknownOrderGroup,
+    },
+};
+pub use accumulator::group::Rsa2048;
+pub use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+#[cfg(feature = "arkworks")]
+pub use crate::protocols::hash_to_prime::{
+    snark_hash::Protocol as SnarkHash, snark_range::Protocol as SnarkRange,
+};
+
+#[cfg(feature = "dalek")]
+pub use crate::protocols::hash_to_prime::bp::Protocol as Bulletproofs;
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::*;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rug::Integer;
+
+    #[test]
+    fn test_prove_and_verify_with_prelude() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            MembershipProtocol::<Rsa2048, G1Projective, SnarkRange<Bls12_381>>::setup_deterministic(
+                &params, [5u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([6u8; 32]);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 245;
+        let randomness = Integer::from(5);
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = MembershipStatement::new(
+            accum.0.value,
+            protocol
+                .crs
+                .crs_modeq
+                .pedersen_commitment_parameters
+                .commit(&value, &randomness)
+                .unwrap(),
+        )
+        .unwrap();
+        let witness = MembershipWitness::new(value, randomness, accum.1.witness.0.value).unwrap();
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"prelude")
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"prelude")
+            .unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+//! Equations deferred by the verifiers, to check those of many proofs at once
+//! by random linear combination.
+//!
+//! The equations in the group of unknown order are raised to random exponents
+//! and multiplied, and the curve equations multiplied by random scalars and
+//! added, so that a single multi-exponentiation and a single MSM check them
+//! all. Repeated bases, e.g., the commitment bases and an accumulator shared by
+//! the statements, are exponentiated once. A false equation passes with
+//! probability about 2^{-security_soundness}, under the same assumptions on the
+//! group as the protocols, since finding elements of small order would break
+//! them.
+use crate::{
+    commitments::integer::IntegerCommitmentTables,
+    utils::{
+        curve::{CurvePointProjective, Field},
+        random_between, ConvertibleUnknownOrderGroup,
+    },
+};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::collections::HashMap;
+
+/// prod_i base_i^{exponent_i} = target in a group of unknown order.
+#[derive(Clone, Debug)]
+pub struct GroupEquation<G: ConvertibleUnknownOrderGroup> {
+    pub terms: Vec<(G::Elem, Integer)>,
+    pub target: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> GroupEquation<G> {
+    /// Checks the equation alone, with the fixed-base tables for the bases
+    /// they cover.
+    pub fn holds(&self, tables: Option<&IntegerCommitmentTables<G>>) -> bool {
+        let result = self.terms.iter().fold(G::id(), |result, (base, exponent)| {
+            let power = match tables {
+                Some(tables) if base == tables.g.base() => tables.g.exp(exponent),
+                Some(tables) if base == tables.h.base() => tables.h.exp(exponent),
+                _ => G::exp(base, exponent),
+            };
+            G::op(&result, &power)
+        });
+        result == self.target
+    }
+}
+
+/// sum_i scalar_i * base_i = target on a curve.
+#[derive(Clone)]
+pub struct CurveEquation<P: CurvePointProjective> {
+    pub bases: Vec<P>,
+    pub scalars: Vec<P::ScalarField>,
+    pub target: P,
+}
+
+impl<P: CurvePointProjective> CurveEquation<P> {
+    pub fn holds(&self) -> bool {
+        P::msm(&self.bases, &self.scalars) == self.target
+    }
+}
+
+pub struct BatchEquations<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    pub group: Vec<GroupEquation<G>>,
+    pub curve: Vec<CurveEquation<P>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> BatchEquations<G, P> {
+    pub fn new() -> BatchEquations<G, P> {
+        BatchEquations {
+            group: vec![],
+            curve: vec![],
+        }
+    }
+
+    /// Checks all the equations, combined with random exponents of
+    /// `security_soundness` bits and random scalars.
+    pub fn check<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        security_soundness: u16,
+    ) -> bool {
+        self.check_group(rng1, security_soundness) && self.check_curve(rng2)
+    }
+
+    fn check_group<R: MutRandState>(&self, rng: &mut R, security_soundness: u16) -> bool {
+        let bound = Integer::from(Integer::u_pow_u(2, security_soundness as u32));
+        let mut exponents = HashMap::<G::Elem, Integer>::new();
+        for equation in &self.group {
+            let rho = random_between(rng, &Integer::from(1), &bound);
+            for (base, exponent) in &equation.terms {
+                *exponents.entry(base.clone()).or_default() += Integer::from(exponent * &rho);
+            }
+            *exponents.entry(equation.target.clone()).or_default() -= &rho;
+        }
+        let result = exponents
+            .iter()
+            .filter(|(_, exponent)| **exponent != 0)
+            .fold(G::id(), |result, (base, exponent)| {
+                G::op(&result, &G::exp(base, exponent))
+            });
+        result == G::id()
+    }
+
+    fn check_curve<R: RngCore + CryptoRng>(&self, rng: &mut R) -> bool {
+        let mut bases = vec![];
+        let mut scalars = vec![];
+        let mut targets = vec![];
+        let mut rhos = vec![];
+        for equation in &self.curve {
+            let rho = P::ScalarField::rand(rng);
+            bases.extend_from_slice(&equation.bases);
+            scalars.extend(equation.scalars.iter().map(|scalar| scalar.mul(&rho)));
+            targets.push(equation.target.clone());
+            rhos.push(rho);
+        }
+        P::msm(&bases, &scalars) == P::msm(&targets, &rhos)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Default for BatchEquations<G, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,237 @@
+//! Phase-2 contributions to a LegoGroth16 proving key, so that the
+//! circuit-specific part of the setup of the hash-to-prime and Merkle SNARKs
+//! can run as a multi-party ceremony.
+//!
+//! Starting from a key created by `setup`, each participant multiplies delta
+//! by a secret factor, dividing the elements of the key that are divided by
+//! delta, and publishes the new key with a proof of knowledge of the factor.
+//! The key is secure as long as one of the participants erased their factor.
+//! The other elements of the key, including the link bases committing to the
+//! Pedersen bases, don't depend on delta and are left unchanged. As in the
+//! Groth16 ceremonies, the elements the first key was created from still have
+//! to come from a trustworthy phase-1 or coordinator.
+use crate::utils::curve::CurvePointProjective;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalSerialize, SerializationError};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CeremonyError {
+    #[error("invalid proof of knowledge of the contribution")]
+    InvalidProofOfKnowledge,
+    #[error("delta doesn't match the contribution")]
+    InvalidDelta,
+    #[error("the contribution changed more than delta")]
+    UnexpectedChange,
+    #[error("could not serialize the key")]
+    SerializationError(#[from] SerializationError),
+}
+
+/// Published by a participant with the key they produced.
+#[derive(Clone)]
+pub struct Contribution<E: PairingEngine> {
+    /// delta in G1 after the contribution.
+    pub delta_g1: E::G1Affine,
+    /// The Schnorr proof that the participant knows the factor relating
+    /// delta before and after the contribution.
+    pub r: E::G1Affine,
+    pub z: E::Fr,
+}
+
+fn challenge<E: PairingEngine>(
+    before: &E::G1Affine,
+    after: &E::G1Affine,
+    r: &E::G1Affine,
+) -> Result<E::Fr, SerializationError> {
+    let mut transcript = Transcript::new(b"legogro16-phase2");
+    let mut append = |label: &'static [u8], point: &E::G1Affine| {
+        let mut bytes = vec![];
+        point.serialize(&mut bytes)?;
+        transcript.append_message(label, &bytes);
+        Ok::<_, SerializationError>(())
+    };
+    append(b"before", before)?;
+    append(b"after", after)?;
+    append(b"r", r)?;
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(b"c", &mut bytes);
+    Ok(E::Fr::from_le_bytes_mod_order(&bytes))
+}
+
+fn scale<G: AffineCurve>(points: &[G], factor: &G::ScalarField) -> Vec<G> {
+    let factor = factor.into_repr();
+    let scaled = points.iter().map(|p| p.mul(factor)).collect::<Vec<_>>();
+    G::Projective::batch_normalization_into_affine(&scaled)
+}
+
+/// Contributes fresh randomness to the key in place. The factor is dropped
+/// when the function returns.
+pub fn contribute<E: PairingEngine, R: RngCore + CryptoRng>(
+    pk: &mut legogro16::ProvingKey<E>,
+    rng: &mut R,
+) -> Result<Contribution<E>, CeremonyError> {
+    let mut delta = E::Fr::rand(rng);
+    while delta.is_zero() {
+        delta = E::Fr::rand(rng);
+    }
+    let delta_inverse = delta.inverse().ok_or(CeremonyError::InvalidDelta)?;
+
+    let delta_g1_before = pk.delta_g1;
+    pk.delta_g1 = pk.delta_g1.mul(delta.into_repr()).into_affine();
+    pk.vk.delta_g2 = pk.vk.delta_g2.mul(delta.into_repr()).into_affine();
+    pk.eta_delta_inv_g1 = pk
+        .eta_delta_inv_g1
+        .mul(delta_inverse.into_repr())
+        .into_affine();
+    pk.h_query = scale(&pk.h_query, &delta_inverse);
+    pk.l_query = scale(&pk.l_query, &delta_inverse);
+
+    let k = E::Fr::rand(rng);
+    let r = delta_g1_before.mul(k.into_repr()).into_affine();
+    let c = challenge::<E>(&delta_g1_before, &pk.delta_g1, &r)?;
+    Ok(Contribution {
+        delta_g1: pk.delta_g1,
+        r,
+        z: k + c * delta,
+    })
+}
+
+/// Checks that `after` was produced from `before` by the contribution: that
+/// the participant knows the factor, that delta and the elements divided by
+/// it changed consistently, and that nothing else changed. The elements
+/// divided by delta are checked together, combined with random scalars.
+pub fn verify_contribution<E: PairingEngine, R: RngCore + CryptoRng>(
+    before: &legogro16::ProvingKey<E>,
+    after: &legogro16::ProvingKey<E>,
+    contribution: &Contribution<E>,
+    rng: &mut R,
+) -> Result<(), CeremonyError> {
+    if contribution.delta_g1 != after.delta_g1 || after.delta_g1.is_zero() {
+        return Err(CeremonyError::InvalidDelta);
+    }
+    let c = challenge::<E>(&before.delta_g1, &after.delta_g1, &contribution.r)?;
+    if before.delta_g1.mul(contribution.z.into_repr())
+        != contribution.r.into_projective() + after.delta_g1.mul(c.into_repr())
+    {
+        return Err(CeremonyError::InvalidProofOfKnowledge);
+    }
+
+    if after.h_query.len() != before.h_query.len() || after.l_query.len() != before.l_query.len() {
+        return Err(CeremonyError::UnexpectedChange);
+    }
+    let mut unchanged = after.clone();
+    unchanged.delta_g1 = before.delta_g1;
+    unchanged.vk.delta_g2 = before.vk.delta_g2;
+    unchanged.eta_delta_inv_g1 = before.eta_delta_inv_g1;
+    unchanged.h_query = before.h_query.clone();
+    unchanged.l_query = before.l_query.clone();
+    let (mut unchanged_bytes, mut before_bytes) = (vec![], vec![]);
+    unchanged.serialize_uncompressed(&mut unchanged_bytes)?;
+    before.serialize_uncompressed(&mut before_bytes)?;
+    if unchanged_bytes != before_bytes {
+        return Err(CeremonyError::UnexpectedChange);
+    }
+
+    if E::pairing(after.delta_g1, before.vk.delta_g2)
+        != E::pairing(before.delta_g1, after.vk.delta_g2)
+    {
+        return Err(CeremonyError::InvalidDelta);
+    }
+    let divided = |pk: &legogro16::ProvingKey<E>| {
+        std::iter::once(&pk.eta_delta_inv_g1)
+            .chain(pk.h_query.iter())
+            .chain(pk.l_query.iter())
+            .map(|p| p.into_projective())
+            .collect::<Vec<_>>()
+    };
+    let scalars = (0..1 + before.h_query.len() + before.l_query.len())
+        .map(|_| E::Fr::rand(rng))
+        .collect::<Vec<_>>();
+    let combined_before = E::G1Projective::msm(&divided(before), &scalars);
+    let combined_after = E::G1Projective::msm(&divided(after), &scalars);
+    if E::pairing(combined_after.into_affine(), after.vk.delta_g2)
+        != E::pairing(combined_before.into_affine(), before.vk.delta_g2)
+    {
+        return Err(CeremonyError::UnexpectedChange);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{contribute, verify_contribution, CeremonyError};
+    use crate::{
+        commitments::{pedersen::PedersenCommitment, Commitment},
+        parameters::Parameters,
+        protocols::hash_to_prime::{
+            snark_range::Protocol as HPProtocol, transcript::TranscriptProverChannel,
+            transcript::TranscriptVerifierChannel, CRSHashToPrime, HashToPrimeProtocol, Statement,
+            Witness,
+        },
+    };
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::Integer;
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_contributions() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng = thread_rng();
+        let pedersen = PedersenCommitment::<G1Projective>::setup(&mut rng);
+        let pk = HPProtocol::<Bls12_381>::setup(&mut rng, &pedersen, &params).unwrap();
+
+        let mut first = pk.clone();
+        let contribution1 = contribute(&mut first, &mut rng).unwrap();
+        verify_contribution(&pk, &first, &contribution1, &mut rng).unwrap();
+        let mut second = first.clone();
+        let contribution2 = contribute(&mut second, &mut rng).unwrap();
+        verify_contribution(&first, &second, &contribution2, &mut rng).unwrap();
+        assert!(verify_contribution(&pk, &second, &contribution2, &mut rng).is_err());
+
+        let mut tampered = second.clone();
+        tampered.h_query[0] = tampered.h_query[0].mul(2u64).into_affine();
+        assert!(matches!(
+            verify_contribution(&first, &tampered, &contribution2, &mut rng),
+            Err(CeremonyError::UnexpectedChange)
+        ));
+
+        let crs = CRSHashToPrime::<G1Projective, HPProtocol<Bls12_381>> {
+            parameters: params.clone(),
+            pedersen_commitment_parameters: pedersen.clone(),
+            hash_to_prime_parameters: Arc::new(second),
+        };
+        crs.validate(&mut rng).unwrap();
+        let protocol = HPProtocol::<Bls12_381>::from_crs(&crs);
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(9);
+        let commitment = pedersen.commit(&value, &randomness).unwrap();
+        let proof_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let statement = Statement { c_e_q: commitment };
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+        let verification_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+    }
+}
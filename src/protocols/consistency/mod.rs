@@ -0,0 +1,279 @@
+//! Implements consistency proofs between accumulator epochs.
+//!
+//! Shows that the accumulator at epoch t+1 extends the accumulator at epoch t,
+//! optionally after removing an explicit list of deleted elements. The deletions
+//! are shown with a proof of exponentiation (PoE) and the additions with a proof
+//! of knowledge of exponent (PoKE2), so the proof size doesn't depend on the
+//! number of added elements. The challenges are derived after the statement,
+//! so a proof can't be moved to other accumulators or deletions.
+use crate::{
+    accumulators::product,
+    parameters::Parameters,
+    protocols::{check_elem, ProofError, ValidationError, VerificationError},
+    utils::zeroize_integer,
+    utils::ConvertibleUnknownOrderGroup,
+    utils::{Redacted, RedactedIntegers},
+};
+use channel::{ConsistencyProverChannel, ConsistencyVerifierChannel};
+use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
+
+pub mod channel;
+pub mod transcript;
+
+#[derive(Clone)]
+pub struct CRSConsistency<G: ConvertibleUnknownOrderGroup> {
+    // G contains the information about Z^*_N
+    pub parameters: Parameters,
+    pub g: G::Elem,
+}
+
+pub struct Statement<G: ConvertibleUnknownOrderGroup> {
+    pub acc_old: G::Elem,
+    pub acc_new: G::Elem,
+    pub deleted: Vec<Integer>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Statement<G> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("acc_old", &self.acc_old)?;
+        check_elem::<G>("acc_new", &self.acc_new)
+    }
+}
+
+pub struct Witness<G: ConvertibleUnknownOrderGroup> {
+    /// The accumulator at the old epoch after removing the deleted elements.
+    pub acc_mid: G::Elem,
+    pub added: Vec<Integer>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for Witness<G> {
+    fn zeroize(&mut self) {
+        self.added.iter_mut().for_each(zeroize_integer);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for Witness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("acc_mid", &Redacted)
+            .field("added", &RedactedIntegers(&self.added))
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for Witness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone)]
+pub struct Message1<G: ConvertibleUnknownOrderGroup> {
+    pub acc_mid: G::Elem,
+    pub z: G::Elem,
+}
+
+#[derive(Clone)]
+pub struct Message2<G: ConvertibleUnknownOrderGroup> {
+    pub q_deleted: G::Elem,
+    pub q_added: G::Elem,
+    pub r_added: Integer,
+}
+
+#[derive(Clone)]
+pub struct Proof<G: ConvertibleUnknownOrderGroup> {
+    pub message1: Message1<G>,
+    pub message2: Message2<G>,
+}
+
+pub struct Protocol<G: ConvertibleUnknownOrderGroup> {
+    pub crs: CRSConsistency<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
+    pub fn setup(parameters: &Parameters) -> Protocol<G> {
+        Protocol {
+            crs: CRSConsistency::<G> {
+                parameters: parameters.clone(),
+                g: G::unknown_order_elem(),
+            },
+        }
+    }
+
+    pub fn from_crs(crs: &CRSConsistency<G>) -> Protocol<G> {
+        Protocol { crs: crs.clone() }
+    }
+
+    pub fn prove<C: ConsistencyVerifierChannel<G>>(
+        &self,
+        verifier_channel: &mut C,
+        statement: &Statement<G>,
+        witness: &Witness<G>,
+    ) -> Result<(), ProofError> {
+        verifier_channel.send_statement(statement)?;
+        let x = product(&witness.added);
+        let z = G::exp(&self.crs.g, &x);
+        let message1 = Message1::<G> {
+            acc_mid: witness.acc_mid.clone(),
+            z,
+        };
+        verifier_channel.send_message1(&message1)?;
+
+        let (c_l, alpha) = verifier_channel.receive_challenge()?;
+        let l = c_l.next_prime();
+        let (q_d, _) = product(&statement.deleted).div_rem_floor(l.clone());
+        let q_deleted = G::exp(&witness.acc_mid, &q_d);
+        let base = G::op(&witness.acc_mid, &G::exp(&self.crs.g, &alpha));
+        let (q_x, r_added) = x.div_rem_floor(l);
+        let q_added = G::exp(&base, &q_x);
+        let message2 = Message2::<G> {
+            q_deleted,
+            q_added,
+            r_added,
+        };
+        verifier_channel.send_message2(&message2)?;
+
+        Ok(())
+    }
+
+    pub fn verify<C: ConsistencyProverChannel<G>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G>,
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        prover_channel.receive_statement(statement)?;
+        let message1 = prover_channel.receive_message1()?;
+        let (c_l, alpha) = prover_channel.generate_and_send_challenge()?;
+        let message2 = prover_channel.receive_message2()?;
+        let l = c_l.next_prime();
+
+        let r_deleted = product(&statement.deleted) % &l;
+        let expected_acc_old = G::op(
+            &G::exp(&message2.q_deleted, &l),
+            &G::exp(&message1.acc_mid, &r_deleted),
+        );
+
+        let base = G::op(&message1.acc_mid, &G::exp(&self.crs.g, &alpha));
+        let expected_acc_new_z = G::op(
+            &G::exp(&message2.q_added, &l),
+            &G::exp(&base, &message2.r_added),
+        );
+        let acc_new_z = G::op(&statement.acc_new, &G::exp(&message1.z, &alpha));
+        let is_r_in_range = message2.r_added >= 0 && message2.r_added < l;
+
+        if expected_acc_old == statement.acc_old && expected_acc_new_z == acc_new_z && is_r_in_range
+        {
+            Ok(())
+        } else {
+            Err(VerificationError::ConsistencyCheckFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Protocol, Statement, Witness};
+    use crate::{
+        parameters::Parameters,
+        protocols::consistency::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+    };
+    use accumulator::{group::Rsa2048, AccumulatorWithoutHashToPrime};
+    use merlin::Transcript;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    fn primes(indices: &[usize]) -> Vec<Integer> {
+        indices
+            .iter()
+            .map(|i| Integer::from(LARGE_PRIMES[*i]))
+            .collect()
+    }
+
+    fn prove_and_verify(
+        protocol: &Protocol<Rsa2048>,
+        statement: &Statement<Rsa2048>,
+        witness: &Witness<Rsa2048>,
+    ) -> bool {
+        let proof_transcript = RefCell::new(Transcript::new(b"consistency"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&protocol.crs, &proof_transcript);
+        protocol
+            .prove(&mut verifier_channel, statement, witness)
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"consistency"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&protocol.crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, statement).is_ok()
+    }
+
+    #[test]
+    fn test_proof_additions() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol = Protocol::<Rsa2048>::setup(&params);
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum_old = accum.clone().add(&primes(&[0, 1]));
+        let accum_new = accum_old.clone().add(&primes(&[2, 3]));
+
+        let statement = Statement {
+            acc_old: accum_old.value.clone(),
+            acc_new: accum_new.value,
+            deleted: vec![],
+        };
+        let witness = Witness {
+            acc_mid: accum_old.value,
+            added: primes(&[2, 3]),
+        };
+        assert!(prove_and_verify(&protocol, &statement, &witness));
+    }
+
+    #[test]
+    fn test_proof_deletions() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol = Protocol::<Rsa2048>::setup(&params);
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum_old = accum.clone().add(&primes(&[0, 1]));
+        let accum_mid = accum.clone().add(&primes(&[0]));
+        let accum_new = accum_mid.clone().add(&primes(&[2]));
+
+        let statement = Statement {
+            acc_old: accum_old.value.clone(),
+            acc_new: accum_new.value.clone(),
+            deleted: primes(&[1]),
+        };
+        let witness = Witness {
+            acc_mid: accum_mid.value,
+            added: primes(&[2]),
+        };
+        assert!(prove_and_verify(&protocol, &statement, &witness));
+
+        let statement_hiding_deletion = Statement {
+            acc_old: accum_old.value.clone(),
+            acc_new: accum_new.value,
+            deleted: vec![],
+        };
+        let witness_hiding_deletion = Witness {
+            acc_mid: accum_old.value,
+            added: primes(&[2]),
+        };
+        assert!(!prove_and_verify(
+            &protocol,
+            &statement_hiding_deletion,
+            &witness_hiding_deletion
+        ));
+    }
+}
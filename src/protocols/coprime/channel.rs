@@ -1,6 +1,6 @@
 use crate::{
     channels::ChannelError,
-    protocols::coprime::{Message1, Message2, Message3},
+    protocols::coprime::{Message1, Message2, Message3, Message4},
     utils::ConvertibleUnknownOrderGroup,
 };
 use rug::Integer;
@@ -9,12 +9,16 @@ pub trait CoprimeVerifierChannel<G: ConvertibleUnknownOrderGroup> {
     fn send_message1(&mut self, message: &Message1<G>) -> Result<(), ChannelError>;
     fn send_message2(&mut self, message: &Message2<G>) -> Result<(), ChannelError>;
     fn send_message3(&mut self, message: &Message3) -> Result<(), ChannelError>;
+    fn send_message4(&mut self, message: &Message4<G>) -> Result<(), ChannelError>;
     fn receive_challenge(&mut self) -> Result<Integer, ChannelError>;
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError>;
 }
 
 pub trait CoprimeProverChannel<G: ConvertibleUnknownOrderGroup> {
     fn receive_message1(&mut self) -> Result<Message1<G>, ChannelError>;
     fn receive_message2(&mut self) -> Result<Message2<G>, ChannelError>;
     fn receive_message3(&mut self) -> Result<Message3, ChannelError>;
+    fn receive_message4(&mut self) -> Result<Message4<G>, ChannelError>;
     fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError>;
 }
@@ -1,13 +1,43 @@
 //! Implements coprime, to be used in the nonmembership protocol.
+//!
+//! The large exponentiations of the verifier are replaced by a Wesolowski proof
+//! of exponentiation (PoE), as in root: once the responses s are absorbed, a
+//! fresh prime challenge l is derived and the prover sends Q = u^floor(s/l) for
+//! each check, so the verifier only exponentiates by l and s mod l. Since l is
+//! sampled after s is fixed, a prover that can pass the checks with a wrong
+//! quotient can compute l-th roots of a group element it didn't choose for a
+//! random prime l, which breaks the adaptive root assumption, so the checks
+//! hold for the full responses with overwhelming probability, and the sigma
+//! protocol's soundness applies to them unchanged.
+//!
+//! This only makes verification cheaper: the full responses are still sent,
+//! since the verifier checks their ranges, so the proof grows by the six
+//! quotients. The responses aren't compressed with PoKE.
 use crate::{
-    commitments::{integer::IntegerCommitment, Commitment},
+    commitments::{integer::IntegerCommitment, Commitment, CommitmentError},
     parameters::Parameters,
-    protocols::{CRSError, ProofError, VerificationError},
-    utils::{random_symmetric_range, ConvertibleUnknownOrderGroup},
+    protocols::{
+        check_challenge, check_discriminant, check_elem, check_masking, check_response,
+        response_bound, s_e_bound, CRSError, ProofError, ValidationError, VerificationError,
+    },
+    utils::zeroize_integer,
+    utils::{
+        group::ElemFromBytes,
+        poe_split, random_symmetric_range,
+        serialization::{
+            elem_size, integer_size, read_elem, read_integer, write_elem, write_integer,
+        },
+        ConvertibleUnknownOrderGroup,
+    },
+    utils::{Redacted, RedactedInteger},
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use channel::{CoprimeProverChannel, CoprimeVerifierChannel};
+use rand::{CryptoRng, RngCore};
 use rug::rand::MutRandState;
 use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
 
 pub mod channel;
 pub mod transcript;
@@ -23,6 +53,13 @@ pub struct Statement<G: ConvertibleUnknownOrderGroup> {
     pub acc: G::Elem,
 }
 
+impl<G: ConvertibleUnknownOrderGroup> Statement<G> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("c_e", &self.c_e)?;
+        check_elem::<G>("acc", &self.acc)
+    }
+}
+
 pub struct Witness<G: ConvertibleUnknownOrderGroup> {
     pub e: Integer,
     pub r: Integer,
@@ -30,6 +67,31 @@ pub struct Witness<G: ConvertibleUnknownOrderGroup> {
     pub b: Integer,
 }
 
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for Witness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r);
+        zeroize_integer(&mut self.b);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for Witness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r", &RedactedInteger(&self.r))
+            .field("d", &Redacted)
+            .field("b", &RedactedInteger(&self.b))
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for Witness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone)]
 pub struct Message1<G: ConvertibleUnknownOrderGroup> {
     pub c_a: G::Elem,
@@ -61,11 +123,175 @@ pub struct Message3 {
     pub s_delta: Integer,
 }
 
+/// PoE quotients for the checks of alpha2 to alpha7.
+#[derive(Clone)]
+pub struct Message4<G: ConvertibleUnknownOrderGroup> {
+    pub q2: G::Elem,
+    pub q3: G::Elem,
+    pub q4: G::Elem,
+    pub q5: G::Elem,
+    pub q6: G::Elem,
+    pub q7: G::Elem,
+}
+
 #[derive(Clone)]
 pub struct Proof<G: ConvertibleUnknownOrderGroup> {
     pub message1: Message1<G>,
     pub message2: Message2<G>,
     pub message3: Message3,
+    pub message4: Message4<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Proof<G> {
+    fn elems(&self) -> [&G::Elem; 16] {
+        [
+            &self.message1.c_a,
+            &self.message1.c_r_a,
+            &self.message1.c_b_cap,
+            &self.message1.c_rho_b_cap,
+            &self.message2.alpha2,
+            &self.message2.alpha3,
+            &self.message2.alpha4,
+            &self.message2.alpha5,
+            &self.message2.alpha6,
+            &self.message2.alpha7,
+            &self.message4.q2,
+            &self.message4.q3,
+            &self.message4.q4,
+            &self.message4.q5,
+            &self.message4.q6,
+            &self.message4.q7,
+        ]
+    }
+
+    fn integers(&self) -> [&Integer; 9] {
+        [
+            &self.message3.s_b,
+            &self.message3.s_e,
+            &self.message3.s_rho_b_cap,
+            &self.message3.s_r,
+            &self.message3.s_r_a,
+            &self.message3.s_r_a_prime,
+            &self.message3.s_rho_b_cap_prime,
+            &self.message3.s_beta,
+            &self.message3.s_delta,
+        ]
+    }
+
+    /// Checks that the responses are within the ranges an honest prover's
+    /// are, to reject a malformed proof before verifying it.
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        check_response("s_e", &self.message3.s_e, &s_e_bound(parameters))?;
+        let bound = response_bound::<G>(parameters);
+        for (field, response) in [
+            ("s_b", &self.message3.s_b),
+            ("s_rho_b_cap", &self.message3.s_rho_b_cap),
+            ("s_r", &self.message3.s_r),
+            ("s_r_a", &self.message3.s_r_a),
+            ("s_r_a_prime", &self.message3.s_r_a_prime),
+            ("s_rho_b_cap_prime", &self.message3.s_rho_b_cap_prime),
+            ("s_beta", &self.message3.s_beta),
+            ("s_delta", &self.message3.s_delta),
+        ]
+        .iter()
+        {
+            check_response(field, response, &bound)?;
+        }
+        Ok(())
+    }
+}
+
+/// The group elements of the messages and the quotients of message 4 are
+/// written first, followed by the responses.
+///
+/// There are no curve points, so the uncompressed encoding is the same.
+impl<G: ConvertibleUnknownOrderGroup> CanonicalSerialize for Proof<G> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        for elem in self.elems().iter() {
+            write_elem::<G, _>(&mut writer, elem)?;
+        }
+        for integer in self.integers().iter() {
+            write_integer(&mut writer, integer)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        let elems: usize = self.elems().iter().map(|e| elem_size::<G>(e)).sum();
+        let integers: usize = self.integers().iter().map(|i| integer_size(i)).sum();
+        elems + integers
+    }
+}
+
+impl<G: ElemFromBytes> CanonicalDeserialize for Proof<G> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let message1 = Message1 {
+            c_a: read_elem::<G, _>(&mut reader)?,
+            c_r_a: read_elem::<G, _>(&mut reader)?,
+            c_b_cap: read_elem::<G, _>(&mut reader)?,
+            c_rho_b_cap: read_elem::<G, _>(&mut reader)?,
+        };
+        let message2 = Message2 {
+            alpha2: read_elem::<G, _>(&mut reader)?,
+            alpha3: read_elem::<G, _>(&mut reader)?,
+            alpha4: read_elem::<G, _>(&mut reader)?,
+            alpha5: read_elem::<G, _>(&mut reader)?,
+            alpha6: read_elem::<G, _>(&mut reader)?,
+            alpha7: read_elem::<G, _>(&mut reader)?,
+        };
+        let q2 = read_elem::<G, _>(&mut reader)?;
+        let q3 = read_elem::<G, _>(&mut reader)?;
+        let q4 = read_elem::<G, _>(&mut reader)?;
+        let q5 = read_elem::<G, _>(&mut reader)?;
+        let q6 = read_elem::<G, _>(&mut reader)?;
+        let q7 = read_elem::<G, _>(&mut reader)?;
+        let message3 = Message3 {
+            s_b: read_integer(&mut reader)?,
+            s_e: read_integer(&mut reader)?,
+            s_rho_b_cap: read_integer(&mut reader)?,
+            s_r: read_integer(&mut reader)?,
+            s_r_a: read_integer(&mut reader)?,
+            s_r_a_prime: read_integer(&mut reader)?,
+            s_rho_b_cap_prime: read_integer(&mut reader)?,
+            s_beta: read_integer(&mut reader)?,
+            s_delta: read_integer(&mut reader)?,
+        };
+        Ok(Proof {
+            message1,
+            message2,
+            message3,
+            message4: Message4 {
+                q2,
+                q3,
+                q4,
+                q5,
+                q6,
+                q7,
+            },
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<G: ConvertibleUnknownOrderGroup> serde::Serialize for Proof<G> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::utils::serialization::serialize_canonical(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, G: ElemFromBytes> serde::Deserialize<'de> for Proof<G> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::utils::serialization::deserialize_canonical(deserializer)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> CRSCoprime<G> {
+    /// The size of the integer commitment bases.
+    pub(crate) fn size(&self) -> usize {
+        elem_size::<G>(&self.integer_commitment_parameters.g)
+            + elem_size::<G>(&self.integer_commitment_parameters.h)
+    }
 }
 
 pub struct Protocol<G: ConvertibleUnknownOrderGroup> {
@@ -74,41 +300,51 @@ pub struct Protocol<G: ConvertibleUnknownOrderGroup> {
 
 impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
     pub fn from_crs(crs: &CRSCoprime<G>) -> Result<Protocol<G>, CRSError> {
-        let modulus = G::rsa_modulus().map_err(|_| CRSError::InvalidParameters)?;
+        // Only relies on the order upper bound, so that both RSA and class groups
+        // can be used.
         if crs.parameters.security_soundness + 1 >= crs.parameters.hash_to_prime_bits
-            || crs.parameters.security_soundness >= modulus / 2
+            || u32::from(crs.parameters.security_soundness)
+                >= G::order_upper_bound().significant_bits()
         {
             return Err(CRSError::InvalidParameters);
         }
+        check_discriminant::<G>(&crs.parameters)?;
         Ok(Protocol { crs: crs.clone() })
     }
 
-    pub fn prove<R: MutRandState, C: CoprimeVerifierChannel<G>>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "coprime::prove", level = "debug", skip_all)
+    )]
+    pub fn prove<R1: MutRandState, R2: RngCore + CryptoRng, C: CoprimeVerifierChannel<G>>(
         &self,
         verifier_channel: &mut C,
-        rng: &mut R,
+        rng1: &mut R1,
+        rng2: &mut R2,
         statement: &Statement<G>,
         witness: &Witness<G>,
     ) -> Result<(), ProofError> {
-        let r_a = random_symmetric_range(rng, &(G::order_upper_bound() / 2));
-        let r_a_prime = random_symmetric_range(rng, &(G::order_upper_bound() / 2));
-        let rho_b_cap = random_symmetric_range(rng, &(G::order_upper_bound() / 2));
-        let rho_b_cap_prime = random_symmetric_range(rng, &(G::order_upper_bound() / 2));
+        let mut r_a = random_symmetric_range(rng1, &(G::order_upper_bound() / 2));
+        let mut r_a_prime = random_symmetric_range(rng1, &(G::order_upper_bound() / 2));
+        let mut rho_b_cap = random_symmetric_range(rng1, &(G::order_upper_bound() / 2));
+        let mut rho_b_cap_prime = random_symmetric_range(rng1, &(G::order_upper_bound() / 2));
         let c_a = G::op(
             &witness.d,
-            &G::exp(&self.crs.integer_commitment_parameters.h, &r_a),
+            &G::exp_secret(&self.crs.integer_commitment_parameters.h, &r_a, rng2)
+                .ok_or(CommitmentError::NonInvertibleBase)?,
         );
         let c_r_a = self
             .crs
             .integer_commitment_parameters
-            .commit(&r_a, &r_a_prime)?;
+            .commit_secret(&r_a, &r_a_prime, rng2)?;
         let integer_commitment_c_b_cap =
             IntegerCommitment::<G>::new(&statement.acc, &self.crs.integer_commitment_parameters.h);
-        let c_b_cap = integer_commitment_c_b_cap.commit(&witness.b, &rho_b_cap)?;
-        let c_rho_b_cap = self
-            .crs
-            .integer_commitment_parameters
-            .commit(&rho_b_cap, &rho_b_cap_prime)?;
+        let c_b_cap = integer_commitment_c_b_cap.commit_secret(&witness.b, &rho_b_cap, rng2)?;
+        let c_rho_b_cap = self.crs.integer_commitment_parameters.commit_secret(
+            &rho_b_cap,
+            &rho_b_cap_prime,
+            rng2,
+        )?;
 
         let message1 = Message1::<G> {
             c_a,
@@ -124,19 +360,22 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
                 + self.crs.parameters.security_soundness
                 + self.crs.parameters.hash_to_prime_bits) as u32,
         ));
-        let r_b = random_symmetric_range(rng, &r_b_e_range);
-        let r_e = random_symmetric_range(rng, &r_b_e_range);
+        check_masking(&self.crs.parameters, "b", &r_b_e_range, &witness.b)?;
+        check_masking(&self.crs.parameters, "e", &r_b_e_range, &witness.e)?;
+        let r_b = random_symmetric_range(rng1, &r_b_e_range);
+        let r_e = random_symmetric_range(rng1, &r_b_e_range);
 
         let r_r_range = G::order_upper_bound() / 2
             * Integer::from(Integer::u_pow_u(
                 2,
                 (self.crs.parameters.security_zk + self.crs.parameters.security_soundness) as u32,
             ));
-        let r_rho_b_cap = random_symmetric_range(rng, &r_r_range);
-        let r_r = random_symmetric_range(rng, &r_r_range);
-        let r_r_a = random_symmetric_range(rng, &r_r_range);
-        let r_r_a_prime = random_symmetric_range(rng, &r_r_range);
-        let r_rho_b_cap_prime = random_symmetric_range(rng, &r_r_range);
+        check_masking(&self.crs.parameters, "r", &r_r_range, &witness.r)?;
+        let r_rho_b_cap = random_symmetric_range(rng1, &r_r_range);
+        let r_r = random_symmetric_range(rng1, &r_r_range);
+        let r_r_a = random_symmetric_range(rng1, &r_r_range);
+        let r_r_a_prime = random_symmetric_range(rng1, &r_r_range);
+        let r_rho_b_cap_prime = random_symmetric_range(rng1, &r_r_range);
 
         let r_beta_delta_range = G::order_upper_bound() / 2
             * Integer::from(Integer::u_pow_u(
@@ -145,31 +384,36 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
                     + self.crs.parameters.security_soundness
                     + self.crs.parameters.hash_to_prime_bits) as u32,
             ));
-        let r_beta = random_symmetric_range(rng, &r_beta_delta_range);
-        let r_delta = random_symmetric_range(rng, &r_beta_delta_range);
+        let r_beta = random_symmetric_range(rng1, &r_beta_delta_range);
+        let r_delta = random_symmetric_range(rng1, &r_beta_delta_range);
 
-        let alpha2 = integer_commitment_c_b_cap.commit(&r_b, &r_rho_b_cap)?;
-        let alpha3 = self.crs.integer_commitment_parameters.commit(&r_e, &r_r)?;
-        let alpha4 = self
+        let alpha2 = integer_commitment_c_b_cap.commit_secret(&r_b, &r_rho_b_cap, rng2)?;
+        let alpha3 = self
             .crs
             .integer_commitment_parameters
-            .commit(&r_r_a, &r_r_a_prime)?;
+            .commit_secret(&r_e, &r_r, rng2)?;
+        let alpha4 =
+            self.crs
+                .integer_commitment_parameters
+                .commit_secret(&r_r_a, &r_r_a_prime, rng2)?;
 
         let integer_commitment_alpha5 =
             IntegerCommitment::<G>::new(&message1.c_a, &self.crs.integer_commitment_parameters.h);
-        let alpha5 = integer_commitment_alpha5.commit(&r_e, &r_beta)?;
+        let alpha5 = integer_commitment_alpha5.commit_secret(&r_e, &r_beta, rng2)?;
 
         let alpha6 = G::op(
-            &G::exp(&message1.c_r_a, &r_e),
+            &G::exp_secret(&message1.c_r_a, &r_e, rng2)
+                .ok_or(CommitmentError::NonInvertibleBase)?,
             &self
                 .crs
                 .integer_commitment_parameters
-                .commit(&r_beta, &r_delta)?,
+                .commit_secret(&r_beta, &r_delta, rng2)?,
         );
-        let alpha7 = self
-            .crs
-            .integer_commitment_parameters
-            .commit(&r_rho_b_cap, &r_rho_b_cap_prime)?;
+        let alpha7 = self.crs.integer_commitment_parameters.commit_secret(
+            &r_rho_b_cap,
+            &r_rho_b_cap_prime,
+            rng2,
+        )?;
 
         let message2 = Message2::<G> {
             alpha2,
@@ -182,15 +426,30 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
         verifier_channel.send_message2(&message2)?;
 
         let c = verifier_channel.receive_challenge()?;
-        let s_b = r_b - c.clone() * witness.b.clone();
-        let s_e = r_e - c.clone() * witness.e.clone();
-        let s_rho_b_cap = r_rho_b_cap - c.clone() * rho_b_cap.clone();
-        let s_r = r_r - c.clone() * witness.r.clone();
-        let s_r_a = r_r_a - c.clone() * r_a.clone();
-        let s_r_a_prime = r_r_a_prime - c.clone() * r_a_prime.clone();
-        let s_rho_b_cap_prime = r_rho_b_cap_prime - c.clone() * rho_b_cap_prime.clone();
-        let s_beta = r_beta + c.clone() * (witness.e.clone() * r_a + rho_b_cap);
-        let s_delta = r_delta + c * (witness.e.clone() * r_a_prime + rho_b_cap_prime);
+        check_challenge(&self.crs.parameters, &c)?;
+        let s_b = r_b - &c * &witness.b;
+        let s_e = r_e - &c * &witness.e;
+        let s_rho_b_cap = r_rho_b_cap - &c * &rho_b_cap;
+        let s_r = r_r - &c * &witness.r;
+        let s_r_a = r_r_a - &c * &r_a;
+        let s_r_a_prime = r_r_a_prime - &c * &r_a_prime;
+        let s_rho_b_cap_prime = r_rho_b_cap_prime - &c * &rho_b_cap_prime;
+        let mut beta = Integer::from(&witness.e * &r_a) + &rho_b_cap;
+        let mut delta = Integer::from(&witness.e * &r_a_prime) + &rho_b_cap_prime;
+        let s_beta = r_beta + &c * &beta;
+        let s_delta = r_delta + &c * &delta;
+        for secret in [
+            &mut r_a,
+            &mut r_a_prime,
+            &mut rho_b_cap,
+            &mut rho_b_cap_prime,
+            &mut beta,
+            &mut delta,
+        ]
+        .iter_mut()
+        {
+            zeroize_integer(secret);
+        }
         let message3 = Message3 {
             s_b,
             s_e,
@@ -204,86 +463,149 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
         };
         verifier_channel.send_message3(&message3)?;
 
+        let l = verifier_channel.receive_poe_challenge()?.next_prime();
+        let (q_rho_b_cap, _) = poe_split(&message3.s_rho_b_cap, &l);
+        let (q_r, _) = poe_split(&message3.s_r, &l);
+        let (q_r_a, _) = poe_split(&message3.s_r_a, &l);
+        let (q_r_a_prime, _) = poe_split(&message3.s_r_a_prime, &l);
+        let (q_rho_b_cap_prime, _) = poe_split(&message3.s_rho_b_cap_prime, &l);
+        let (q_beta, _) = poe_split(&message3.s_beta, &l);
+        let (q_delta, _) = poe_split(&message3.s_delta, &l);
+        let h = &self.crs.integer_commitment_parameters.h;
+        let message4 = Message4::<G> {
+            q2: G::exp(h, &q_rho_b_cap),
+            q3: G::exp(h, &q_r),
+            q4: self
+                .crs
+                .integer_commitment_parameters
+                .commit(&q_r_a, &q_r_a_prime)?,
+            q5: G::exp(h, &q_beta),
+            q6: self
+                .crs
+                .integer_commitment_parameters
+                .commit(&q_beta, &q_delta)?,
+            q7: self
+                .crs
+                .integer_commitment_parameters
+                .commit(&q_rho_b_cap, &q_rho_b_cap_prime)?,
+        };
+        verifier_channel.send_message4(&message4)?;
+
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "coprime::verify", level = "debug", skip_all)
+    )]
     pub fn verify<C: CoprimeProverChannel<G>>(
         &self,
         prover_channel: &mut C,
         statement: &Statement<G>,
     ) -> Result<(), VerificationError> {
+        statement.validate()?;
         let message1 = prover_channel.receive_message1()?;
         let message2 = prover_channel.receive_message2()?;
         let c = prover_channel.generate_and_send_challenge()?;
+        check_challenge(&self.crs.parameters, &c)?;
         let message3 = prover_channel.receive_message3()?;
+        let l = prover_channel
+            .generate_and_send_poe_challenge()?
+            .next_prime();
+        let message4 = prover_channel.receive_message4()?;
+        for (field, elem) in [
+            ("c_a", &message1.c_a),
+            ("c_r_a", &message1.c_r_a),
+            ("c_b_cap", &message1.c_b_cap),
+            ("c_rho_b_cap", &message1.c_rho_b_cap),
+            ("alpha2", &message2.alpha2),
+            ("alpha3", &message2.alpha3),
+            ("alpha4", &message2.alpha4),
+            ("alpha5", &message2.alpha5),
+            ("alpha6", &message2.alpha6),
+            ("alpha7", &message2.alpha7),
+            ("q2", &message4.q2),
+            ("q3", &message4.q3),
+            ("q4", &message4.q4),
+            ("q5", &message4.q5),
+            ("q6", &message4.q6),
+            ("q7", &message4.q7),
+        ]
+        .iter()
+        {
+            check_elem::<G>(field, elem)?;
+        }
+        check_response("s_e", &message3.s_e, &s_e_bound(&self.crs.parameters))?;
+        let (_, s_rho_b_cap) = poe_split(&message3.s_rho_b_cap, &l);
+        let (_, s_r) = poe_split(&message3.s_r, &l);
+        let (_, s_r_a) = poe_split(&message3.s_r_a, &l);
+        let (_, s_r_a_prime) = poe_split(&message3.s_r_a_prime, &l);
+        let (_, s_rho_b_cap_prime) = poe_split(&message3.s_rho_b_cap_prime, &l);
+        let (_, s_beta) = poe_split(&message3.s_beta, &l);
+        let (_, s_delta) = poe_split(&message3.s_delta, &l);
+
         let integer_commitment_alpha2 =
             IntegerCommitment::<G>::new(&statement.acc, &self.crs.integer_commitment_parameters.h);
         let expected_alpha2 = G::op(
-            &G::exp(&message1.c_b_cap, &c),
-            &integer_commitment_alpha2.commit(&message3.s_b, &message3.s_rho_b_cap)?,
+            &G::op(&G::exp(&message1.c_b_cap, &c), &G::exp(&message4.q2, &l)),
+            &integer_commitment_alpha2.commit(&message3.s_b, &s_rho_b_cap)?,
         );
         let expected_alpha3 = G::op(
-            &G::exp(&statement.c_e, &c),
+            &G::op(&G::exp(&statement.c_e, &c), &G::exp(&message4.q3, &l)),
             &self
                 .crs
                 .integer_commitment_parameters
-                .commit(&message3.s_e, &message3.s_r)?,
+                .commit(&message3.s_e, &s_r)?,
         );
         let expected_alpha4 = G::op(
-            &G::exp(&message1.c_r_a, &c),
+            &G::op(&G::exp(&message1.c_r_a, &c), &G::exp(&message4.q4, &l)),
             &self
                 .crs
                 .integer_commitment_parameters
-                .commit(&message3.s_r_a, &message3.s_r_a_prime)?,
+                .commit(&s_r_a, &s_r_a_prime)?,
         );
         let integer_commitment_alpha5 =
             IntegerCommitment::<G>::new(&message1.c_a, &G::inv(&message1.c_b_cap));
         let expected_alpha5 = G::op(
-            &integer_commitment_alpha5.commit(&message3.s_e, &c)?,
-            &self
-                .crs
-                .integer_commitment_parameters
-                .commit(&c, &message3.s_beta)?,
+            &G::op(
+                &integer_commitment_alpha5.commit(&message3.s_e, &c)?,
+                &G::exp(&message4.q5, &l),
+            ),
+            &self.crs.integer_commitment_parameters.commit(&c, &s_beta)?,
         );
         let integer_commitment_alpha6 =
             IntegerCommitment::<G>::new(&message1.c_r_a, &G::inv(&message1.c_rho_b_cap));
         let expected_alpha6 = G::op(
-            &integer_commitment_alpha6.commit(&message3.s_e, &c)?,
+            &G::op(
+                &integer_commitment_alpha6.commit(&message3.s_e, &c)?,
+                &G::exp(&message4.q6, &l),
+            ),
             &self
                 .crs
                 .integer_commitment_parameters
-                .commit(&message3.s_beta, &message3.s_delta)?,
+                .commit(&s_beta, &s_delta)?,
         );
         let expected_alpha7 = G::op(
-            &G::exp(&message1.c_rho_b_cap, &c),
+            &G::op(
+                &G::exp(&message1.c_rho_b_cap, &c),
+                &G::exp(&message4.q7, &l),
+            ),
             &self
                 .crs
                 .integer_commitment_parameters
-                .commit(&message3.s_rho_b_cap, &message3.s_rho_b_cap_prime)?,
+                .commit(&s_rho_b_cap, &s_rho_b_cap_prime)?,
         );
-        let s_e_expected_right = Integer::from(Integer::u_pow_u(
-            2,
-            (self.crs.parameters.security_zk
-                + self.crs.parameters.security_soundness
-                + self.crs.parameters.hash_to_prime_bits
-                + 1) as u32,
-        ));
-
-        let s_e_expected_left: Integer = -s_e_expected_right.clone();
-        let is_s_e_in_range =
-            message3.s_e >= s_e_expected_left && message3.s_e <= s_e_expected_right;
-
-        if expected_alpha2 == message2.alpha2
-            && expected_alpha3 == message2.alpha3
-            && expected_alpha4 == message2.alpha4
-            && expected_alpha5 == message2.alpha5
-            && expected_alpha6 == message2.alpha6
-            && expected_alpha7 == message2.alpha7
-            && is_s_e_in_range
-        {
-            Ok(())
-        } else {
-            Err(VerificationError::VerificationFailed)
+        let checks = [
+            ("alpha2", expected_alpha2 == message2.alpha2),
+            ("alpha3", expected_alpha3 == message2.alpha3),
+            ("alpha4", expected_alpha4 == message2.alpha4),
+            ("alpha5", expected_alpha5 == message2.alpha5),
+            ("alpha6", expected_alpha6 == message2.alpha6),
+            ("alpha7", expected_alpha7 == message2.alpha7),
+        ];
+        match checks.iter().find(|(_, holds)| !holds) {
+            Some((field, _)) => Err(VerificationError::CoprimeCheckFailed(field)),
+            None => Ok(()),
         }
     }
 }
@@ -373,6 +695,7 @@ mod test {
             .prove(
                 &mut verifier_channel,
                 &mut rng1,
+                &mut rng2,
                 &statement,
                 &Witness {
                     e: value,
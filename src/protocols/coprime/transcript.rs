@@ -2,14 +2,15 @@ use crate::{
     channels::ChannelError,
     protocols::coprime::{
         channel::{CoprimeProverChannel, CoprimeVerifierChannel},
-        CRSCoprime, Message1, Message2, Message3, Proof,
+        CRSCoprime, Message1, Message2, Message3, Message4, Proof,
+    },
+    transcript::{
+        FiatShamir, TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolInteger,
     },
-    transcript::{TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolInteger},
     utils::ConvertibleUnknownOrderGroup,
 };
-use merlin::Transcript;
+use core::cell::RefCell;
 use rug::Integer;
-use std::cell::RefCell;
 
 pub trait TranscriptProtocolCoprime<G: ConvertibleUnknownOrderGroup>:
     TranscriptProtocolInteger<G> + TranscriptProtocolChallenge
@@ -17,12 +18,27 @@ pub trait TranscriptProtocolCoprime<G: ConvertibleUnknownOrderGroup>:
     fn coprime_domain_sep(&mut self);
 }
 
-impl<G: ConvertibleUnknownOrderGroup> TranscriptProtocolCoprime<G> for Transcript {
+impl<G: ConvertibleUnknownOrderGroup, T: FiatShamir> TranscriptProtocolCoprime<G> for T {
     fn coprime_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"coprime");
     }
 }
 
+fn append_message3<G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolCoprime<G>>(
+    transcript: &mut T,
+    message: &Message3,
+) {
+    transcript.append_integer_scalar(b"s_b", &message.s_b);
+    transcript.append_integer_scalar(b"s_e", &message.s_e);
+    transcript.append_integer_scalar(b"s_rho_b_cap", &message.s_rho_b_cap);
+    transcript.append_integer_scalar(b"s_r", &message.s_r);
+    transcript.append_integer_scalar(b"s_r_a", &message.s_r_a);
+    transcript.append_integer_scalar(b"s_r_a_prime", &message.s_r_a_prime);
+    transcript.append_integer_scalar(b"s_rho_b_cap_prime", &message.s_rho_b_cap_prime);
+    transcript.append_integer_scalar(b"s_beta", &message.s_beta);
+    transcript.append_integer_scalar(b"s_delta", &message.s_delta);
+}
+
 pub struct TranscriptVerifierChannel<
     'a,
     G: ConvertibleUnknownOrderGroup,
@@ -33,6 +49,7 @@ pub struct TranscriptVerifierChannel<
     message1: Option<Message1<G>>,
     message2: Option<Message2<G>>,
     message3: Option<Message3>,
+    message4: Option<Message4<G>>,
 }
 
 impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolCoprime<G>>
@@ -48,15 +65,21 @@ impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolCoprime<G>>
             message1: None,
             message2: None,
             message3: None,
+            message4: None,
         }
     }
 
     pub fn proof(&self) -> Result<Proof<G>, TranscriptChannelError> {
-        if self.message1.is_some() && self.message2.is_some() && self.message3.is_some() {
+        if self.message1.is_some()
+            && self.message2.is_some()
+            && self.message3.is_some()
+            && self.message4.is_some()
+        {
             Ok(Proof {
                 message1: self.message1.as_ref().unwrap().clone(),
                 message2: self.message2.as_ref().unwrap().clone(),
                 message3: self.message3.as_ref().unwrap().clone(),
+                message4: self.message4.as_ref().unwrap().clone(),
             })
         } else {
             Err(TranscriptChannelError::Incomplete)
@@ -90,14 +113,26 @@ impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolCoprime<G>> Copri
         Ok(())
     }
     fn send_message3(&mut self, message: &Message3) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.coprime_domain_sep();
+        append_message3::<G, T>(&mut *transcript, message);
         self.message3 = Some(message.clone());
         Ok(())
     }
+    fn send_message4(&mut self, message: &Message4<G>) -> Result<(), ChannelError> {
+        self.message4 = Some(message.clone());
+        Ok(())
+    }
     fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
         let mut transcript = self.transcript.try_borrow_mut()?;
         transcript.coprime_domain_sep();
         Ok(transcript.challenge_scalar(b"c", self.crs.parameters.security_soundness))
     }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.coprime_domain_sep();
+        Ok(transcript.challenge_scalar(b"l", self.crs.parameters.security_soundness))
+    }
 }
 
 pub struct TranscriptProverChannel<
@@ -151,11 +186,22 @@ impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolCoprime<G>> Copri
         Ok(self.proof.message2.clone())
     }
     fn receive_message3(&mut self) -> Result<Message3, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.coprime_domain_sep();
+        append_message3::<G, T>(&mut *transcript, &self.proof.message3);
         Ok(self.proof.message3.clone())
     }
+    fn receive_message4(&mut self) -> Result<Message4<G>, ChannelError> {
+        Ok(self.proof.message4.clone())
+    }
     fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
         let mut transcript = self.transcript.try_borrow_mut()?;
         transcript.coprime_domain_sep();
         Ok(transcript.challenge_scalar(b"c", self.crs.parameters.security_soundness))
     }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.coprime_domain_sep();
+        Ok(transcript.challenge_scalar(b"l", self.crs.parameters.security_soundness))
+    }
 }
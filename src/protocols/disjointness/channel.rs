@@ -0,0 +1,24 @@
+use crate::{
+    channels::ChannelError,
+    protocols::disjointness::{Message1, Message2, Statement},
+    utils::ConvertibleUnknownOrderGroup,
+};
+use rug::Integer;
+
+pub trait DisjointnessVerifierChannel<G: ConvertibleUnknownOrderGroup> {
+    /// Binds the statement to the challenges that follow.
+    fn send_statement(&mut self, statement: &Statement<G>) -> Result<(), ChannelError>;
+    fn send_message1(&mut self, message: &Message1<G>) -> Result<(), ChannelError>;
+    fn send_message2(&mut self, message: &Message2<G>) -> Result<(), ChannelError>;
+    /// Receives the challenges (c_l, alpha), c_l is used to derive the prime l.
+    fn receive_challenge(&mut self) -> Result<(Integer, Integer), ChannelError>;
+}
+
+pub trait DisjointnessProverChannel<G: ConvertibleUnknownOrderGroup> {
+    /// Binds the statement to the challenges that follow.
+    fn receive_statement(&mut self, statement: &Statement<G>) -> Result<(), ChannelError>;
+    fn receive_message1(&mut self) -> Result<Message1<G>, ChannelError>;
+    fn receive_message2(&mut self) -> Result<Message2<G>, ChannelError>;
+    /// Generates the challenges (c_l, alpha), c_l is used to derive the prime l.
+    fn generate_and_send_challenge(&mut self) -> Result<(Integer, Integer), ChannelError>;
+}
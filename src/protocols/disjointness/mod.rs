@@ -0,0 +1,279 @@
+//! Implements disjointness proofs between two accumulators.
+//!
+//! Shows that the sets accumulated in acc_a = g^x and acc_b = g^y are disjoint,
+//! i.e. that x and y are coprime, by proving knowledge of Bezout coefficients
+//! (a, b) such that acc_a^a * acc_b^b = g. The prover sends v = acc_a^a and
+//! shows knowledge of both exponents with a proof of knowledge of exponent
+//! (PoKE2), so the proof size doesn't depend on the size of the sets.
+use crate::{
+    accumulators::product,
+    parameters::Parameters,
+    protocols::{check_elem, ProofError, ValidationError, VerificationError},
+    utils::zeroize_integer,
+    utils::ConvertibleUnknownOrderGroup,
+    utils::RedactedIntegers,
+};
+use channel::{DisjointnessProverChannel, DisjointnessVerifierChannel};
+use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
+
+pub mod channel;
+pub mod transcript;
+
+#[derive(Clone)]
+pub struct CRSDisjointness<G: ConvertibleUnknownOrderGroup> {
+    // G contains the information about Z^*_N
+    pub parameters: Parameters,
+    pub g: G::Elem,
+}
+
+pub struct Statement<G: ConvertibleUnknownOrderGroup> {
+    pub acc_a: G::Elem,
+    pub acc_b: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Statement<G> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("acc_a", &self.acc_a)?;
+        check_elem::<G>("acc_b", &self.acc_b)
+    }
+}
+
+pub struct Witness {
+    pub elements_a: Vec<Integer>,
+    pub elements_b: Vec<Integer>,
+}
+
+impl Zeroize for Witness {
+    fn zeroize(&mut self) {
+        self.elements_a.iter_mut().for_each(zeroize_integer);
+        self.elements_b.iter_mut().for_each(zeroize_integer);
+    }
+}
+
+impl fmt::Debug for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("elements_a", &RedactedIntegers(&self.elements_a))
+            .field("elements_b", &RedactedIntegers(&self.elements_b))
+            .finish()
+    }
+}
+
+impl Drop for Witness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone)]
+pub struct Message1<G: ConvertibleUnknownOrderGroup> {
+    pub v: G::Elem,
+    pub z_a: G::Elem,
+    pub z_b: G::Elem,
+}
+
+#[derive(Clone)]
+pub struct Message2<G: ConvertibleUnknownOrderGroup> {
+    pub q_a: G::Elem,
+    pub r_a: Integer,
+    pub q_b: G::Elem,
+    pub r_b: Integer,
+}
+
+#[derive(Clone)]
+pub struct Proof<G: ConvertibleUnknownOrderGroup> {
+    pub message1: Message1<G>,
+    pub message2: Message2<G>,
+}
+
+pub struct Protocol<G: ConvertibleUnknownOrderGroup> {
+    pub crs: CRSDisjointness<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
+    pub fn setup(parameters: &Parameters) -> Protocol<G> {
+        Protocol {
+            crs: CRSDisjointness::<G> {
+                parameters: parameters.clone(),
+                g: G::unknown_order_elem(),
+            },
+        }
+    }
+
+    pub fn from_crs(crs: &CRSDisjointness<G>) -> Protocol<G> {
+        Protocol { crs: crs.clone() }
+    }
+
+    pub fn prove<C: DisjointnessVerifierChannel<G>>(
+        &self,
+        verifier_channel: &mut C,
+        statement: &Statement<G>,
+        witness: &Witness,
+    ) -> Result<(), ProofError> {
+        let x = product(&witness.elements_a);
+        let y = product(&witness.elements_b);
+        let (d, a, b) = x.gcd_cofactors(y, Integer::new());
+        if d != 1 {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+
+        verifier_channel.send_statement(statement)?;
+        let v = G::exp(&statement.acc_a, &a);
+        let message1 = Message1::<G> {
+            v,
+            z_a: G::exp(&self.crs.g, &a),
+            z_b: G::exp(&self.crs.g, &b),
+        };
+        verifier_channel.send_message1(&message1)?;
+
+        let (c_l, alpha) = verifier_channel.receive_challenge()?;
+        let l = c_l.next_prime();
+        let g_alpha = G::exp(&self.crs.g, &alpha);
+        let (q_a, r_a) = a.div_rem_floor(l.clone());
+        let (q_b, r_b) = b.div_rem_floor(l);
+        let message2 = Message2::<G> {
+            q_a: G::exp(&G::op(&statement.acc_a, &g_alpha), &q_a),
+            r_a,
+            q_b: G::exp(&G::op(&statement.acc_b, &g_alpha), &q_b),
+            r_b,
+        };
+        verifier_channel.send_message2(&message2)?;
+
+        Ok(())
+    }
+
+    pub fn verify<C: DisjointnessProverChannel<G>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G>,
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        prover_channel.receive_statement(statement)?;
+        let message1 = prover_channel.receive_message1()?;
+        let (c_l, alpha) = prover_channel.generate_and_send_challenge()?;
+        let message2 = prover_channel.receive_message2()?;
+        let l = c_l.next_prime();
+        let g_alpha = G::exp(&self.crs.g, &alpha);
+
+        let expected_v_z_a = G::op(
+            &G::exp(&message2.q_a, &l),
+            &G::exp(&G::op(&statement.acc_a, &g_alpha), &message2.r_a),
+        );
+        let v_z_a = G::op(&message1.v, &G::exp(&message1.z_a, &alpha));
+
+        let expected_w_z_b = G::op(
+            &G::exp(&message2.q_b, &l),
+            &G::exp(&G::op(&statement.acc_b, &g_alpha), &message2.r_b),
+        );
+        let w = G::op(&self.crs.g, &G::inv(&message1.v));
+        let w_z_b = G::op(&w, &G::exp(&message1.z_b, &alpha));
+
+        let are_r_in_range =
+            message2.r_a >= 0 && message2.r_a < l && message2.r_b >= 0 && message2.r_b < l;
+
+        if expected_v_z_a == v_z_a && expected_w_z_b == w_z_b && are_r_in_range {
+            Ok(())
+        } else {
+            Err(VerificationError::DisjointnessCheckFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Protocol, Statement, Witness};
+    use crate::{
+        parameters::Parameters,
+        protocols::{
+            disjointness::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+            ProofError,
+        },
+    };
+    use accumulator::{group::Rsa2048, AccumulatorWithoutHashToPrime};
+    use merlin::Transcript;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    fn primes(indices: &[usize]) -> Vec<Integer> {
+        indices
+            .iter()
+            .map(|i| Integer::from(LARGE_PRIMES[*i]))
+            .collect()
+    }
+
+    #[test]
+    fn test_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol = Protocol::<Rsa2048>::setup(&params);
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum_a = accum.clone().add(&primes(&[0, 1]));
+        let accum_b = accum.clone().add(&primes(&[2, 3]));
+
+        let statement = Statement {
+            acc_a: accum_a.value,
+            acc_b: accum_b.value,
+        };
+        let witness = Witness {
+            elements_a: primes(&[0, 1]),
+            elements_b: primes(&[2, 3]),
+        };
+
+        let proof_transcript = RefCell::new(Transcript::new(b"disjointness"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&protocol.crs, &proof_transcript);
+        protocol
+            .prove(&mut verifier_channel, &statement, &witness)
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"disjointness"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&protocol.crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        // The proof is bound to the accumulators it was created for.
+        let other = Statement {
+            acc_a: accum.clone().add(&primes(&[0])).value,
+            acc_b: statement.acc_b.clone(),
+        };
+        let verification_transcript = RefCell::new(Transcript::new(b"disjointness"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&protocol.crs, &verification_transcript, &proof);
+        assert!(protocol.verify(&mut prover_channel, &other).is_err());
+    }
+
+    #[test]
+    fn test_proof_overlapping_sets() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol = Protocol::<Rsa2048>::setup(&params);
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum_a = accum.clone().add(&primes(&[0, 1]));
+        let accum_b = accum.add(&primes(&[1, 2]));
+
+        let statement = Statement {
+            acc_a: accum_a.value,
+            acc_b: accum_b.value,
+        };
+        let witness = Witness {
+            elements_a: primes(&[0, 1]),
+            elements_b: primes(&[1, 2]),
+        };
+
+        let proof_transcript = RefCell::new(Transcript::new(b"disjointness"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&protocol.crs, &proof_transcript);
+        let result = protocol.prove(&mut verifier_channel, &statement, &witness);
+        assert!(matches!(result, Err(ProofError::CouldNotCreateProof)));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    channels::ChannelError,
+    protocols::disjointness::{
+        channel::{DisjointnessProverChannel, DisjointnessVerifierChannel},
+        CRSDisjointness, Message1, Message2, Proof, Statement,
+    },
+    transcript::{
+        FiatShamir, TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolInteger,
+    },
+    utils::ConvertibleUnknownOrderGroup,
+};
+use core::cell::RefCell;
+use rug::Integer;
+
+pub trait TranscriptProtocolDisjointness<G: ConvertibleUnknownOrderGroup>:
+    TranscriptProtocolInteger<G> + TranscriptProtocolChallenge
+{
+    fn disjointness_domain_sep(&mut self);
+    fn append_disjointness_statement(&mut self, statement: &Statement<G>);
+}
+
+impl<G: ConvertibleUnknownOrderGroup, T: FiatShamir> TranscriptProtocolDisjointness<G> for T {
+    fn disjointness_domain_sep(&mut self) {
+        self.append_message(b"dom-sep", b"disjointness");
+    }
+
+    fn append_disjointness_statement(&mut self, statement: &Statement<G>) {
+        TranscriptProtocolDisjointness::<G>::disjointness_domain_sep(self);
+        TranscriptProtocolInteger::<G>::append_integer_point(self, b"acc_a", &statement.acc_a);
+        TranscriptProtocolInteger::<G>::append_integer_point(self, b"acc_b", &statement.acc_b);
+    }
+}
+
+pub struct TranscriptVerifierChannel<
+    'a,
+    G: ConvertibleUnknownOrderGroup,
+    T: TranscriptProtocolDisjointness<G>,
+> {
+    crs: CRSDisjointness<G>,
+    transcript: &'a RefCell<T>,
+    message1: Option<Message1<G>>,
+    message2: Option<Message2<G>>,
+}
+
+impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolDisjointness<G>>
+    TranscriptVerifierChannel<'a, G, T>
+{
+    pub fn new(
+        crs: &CRSDisjointness<G>,
+        transcript: &'a RefCell<T>,
+    ) -> TranscriptVerifierChannel<'a, G, T> {
+        TranscriptVerifierChannel {
+            crs: crs.clone(),
+            transcript,
+            message1: None,
+            message2: None,
+        }
+    }
+
+    pub fn proof(&self) -> Result<Proof<G>, TranscriptChannelError> {
+        if self.message1.is_some() && self.message2.is_some() {
+            Ok(Proof {
+                message1: self.message1.as_ref().unwrap().clone(),
+                message2: self.message2.as_ref().unwrap().clone(),
+            })
+        } else {
+            Err(TranscriptChannelError::Incomplete)
+        }
+    }
+}
+
+impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolDisjointness<G>>
+    DisjointnessVerifierChannel<G> for TranscriptVerifierChannel<'a, G, T>
+{
+    fn send_statement(&mut self, statement: &Statement<G>) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.append_disjointness_statement(statement);
+        Ok(())
+    }
+    fn send_message1(&mut self, message: &Message1<G>) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.disjointness_domain_sep();
+        transcript.append_integer_point(b"v", &message.v);
+        transcript.append_integer_point(b"z_a", &message.z_a);
+        transcript.append_integer_point(b"z_b", &message.z_b);
+        self.message1 = Some(message.clone());
+        Ok(())
+    }
+    fn send_message2(&mut self, message: &Message2<G>) -> Result<(), ChannelError> {
+        self.message2 = Some(message.clone());
+        Ok(())
+    }
+    fn receive_challenge(&mut self) -> Result<(Integer, Integer), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.disjointness_domain_sep();
+        Ok((
+            transcript.challenge_scalar(b"l", self.crs.parameters.security_soundness),
+            transcript.challenge_scalar(b"alpha", self.crs.parameters.security_soundness),
+        ))
+    }
+}
+
+pub struct TranscriptProverChannel<
+    'a,
+    G: ConvertibleUnknownOrderGroup,
+    T: TranscriptProtocolDisjointness<G>,
+> {
+    crs: CRSDisjointness<G>,
+    transcript: &'a RefCell<T>,
+    proof: Proof<G>,
+}
+
+impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolDisjointness<G>>
+    TranscriptProverChannel<'a, G, T>
+{
+    pub fn new(
+        crs: &CRSDisjointness<G>,
+        transcript: &'a RefCell<T>,
+        proof: &Proof<G>,
+    ) -> TranscriptProverChannel<'a, G, T> {
+        TranscriptProverChannel {
+            crs: crs.clone(),
+            transcript,
+            proof: proof.clone(),
+        }
+    }
+}
+
+impl<'a, G: ConvertibleUnknownOrderGroup, T: TranscriptProtocolDisjointness<G>>
+    DisjointnessProverChannel<G> for TranscriptProverChannel<'a, G, T>
+{
+    fn receive_statement(&mut self, statement: &Statement<G>) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.append_disjointness_statement(statement);
+        Ok(())
+    }
+    fn receive_message1(&mut self) -> Result<Message1<G>, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.disjointness_domain_sep();
+        transcript.append_integer_point(b"v", &self.proof.message1.v);
+        transcript.append_integer_point(b"z_a", &self.proof.message1.z_a);
+        transcript.append_integer_point(b"z_b", &self.proof.message1.z_b);
+        Ok(self.proof.message1.clone())
+    }
+    fn receive_message2(&mut self) -> Result<Message2<G>, ChannelError> {
+        Ok(self.proof.message2.clone())
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<(Integer, Integer), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.disjointness_domain_sep();
+        Ok((
+            transcript.challenge_scalar(b"l", self.crs.parameters.security_soundness),
+            transcript.challenge_scalar(b"alpha", self.crs.parameters.security_soundness),
+        ))
+    }
+}
@@ -0,0 +1,392 @@
+//! A versioned binary format for storing non-interactive proofs.
+//!
+//! A proof is written after a header holding the magic bytes, the format
+//! version, the kind of proof, the encoding mode, the fingerprint of the
+//! parameters and the identifiers of the group, curve and hash-to-prime
+//! backends. Parsing is strict: a proof written by another version of the
+//! format, for other parameters or backends, or followed by trailing bytes is
+//! rejected with a `FormatError` before any of it is verified, as is a proof
+//! whose responses are out of range.
+use crate::{
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol, membership::MembershipProof,
+        nonmembership::NonMembershipProof, ValidationError,
+    },
+    utils::{
+        curve::CurvePointProjective,
+        group::ElemFromBytes,
+        serialization::{read_bytes, write_bytes},
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError};
+use core::any::type_name;
+use thiserror::Error;
+
+pub const MAGIC: [u8; 4] = *b"CPSP";
+pub const FORMAT_VERSION: u16 = 1;
+/// Backend identifiers longer than this are rejected when parsing.
+pub const MAX_IDENTIFIER_LENGTH: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("invalid magic bytes")]
+    InvalidMagic,
+    #[error("unsupported proof format version {0}")]
+    UnsupportedVersion(u16),
+    #[error("expected a {0:?} proof, found kind {1}")]
+    UnexpectedKind(ProofKind, u8),
+    #[error("invalid encoding {0}")]
+    InvalidEncoding(u8),
+    #[error("the proof is for other parameters")]
+    ParametersMismatch,
+    #[error("backend mismatch: ours is {0}, the proof's is {1}")]
+    BackendMismatch(String, String),
+    #[error("trailing bytes after the proof")]
+    TrailingBytes,
+    #[error("the proof is malformed")]
+    ValidationError(#[from] ValidationError),
+    #[error("could not read the header")]
+    IoError(#[from] std::io::Error),
+    #[error("could not serialize or deserialize the proof")]
+    SerializationError(#[from] SerializationError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofKind {
+    Membership = 1,
+    NonMembership = 2,
+}
+
+/// A proof that can be stored in the versioned format.
+pub trait VersionedProof: CanonicalSerialize + CanonicalDeserialize {
+    const KIND: ProofKind;
+
+    /// The identifiers of the group, curve and hash-to-prime backends.
+    fn backends() -> [String; 3];
+
+    fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError>;
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> VersionedProof
+    for MembershipProof<G, P, HP>
+{
+    const KIND: ProofKind = ProofKind::Membership;
+
+    fn backends() -> [String; 3] {
+        backends::<G, P, HP>()
+    }
+
+    fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        self.validate(parameters)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> VersionedProof
+    for NonMembershipProof<G, P, HP>
+{
+    const KIND: ProofKind = ProofKind::NonMembership;
+
+    fn backends() -> [String; 3] {
+        backends::<G, P, HP>()
+    }
+
+    fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        self.validate(parameters)
+    }
+}
+
+fn backends<G, P, HP>() -> [String; 3] {
+    [
+        type_name::<G>().to_string(),
+        type_name::<P>().to_string(),
+        type_name::<HP>().to_string(),
+    ]
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u16,
+    pub kind: u8,
+    pub compressed: bool,
+    pub parameters_fingerprint: [u8; 32],
+    /// The group, curve and hash-to-prime backends, in that order.
+    pub backends: [String; 3],
+}
+
+impl Header {
+    pub fn new<T: VersionedProof>(parameters: &Parameters, compressed: bool) -> Header {
+        Header {
+            version: FORMAT_VERSION,
+            kind: T::KIND as u8,
+            compressed,
+            parameters_fingerprint: parameters.fingerprint(),
+            backends: T::backends(),
+        }
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), FormatError> {
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&self.version.to_be_bytes());
+        out.push(self.kind);
+        out.push(if self.compressed { 0 } else { 1 });
+        out.extend_from_slice(&self.parameters_fingerprint);
+        for backend in self.backends.iter() {
+            write_bytes(out, backend.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a header, rejecting other versions of the format. The rest of it
+    /// can be inspected without knowing the backends of the proof.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Header, FormatError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(FormatError::InvalidMagic);
+        }
+        let mut version = [0u8; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_be_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+        let mut kind_and_encoding = [0u8; 2];
+        reader.read_exact(&mut kind_and_encoding)?;
+        let compressed = match kind_and_encoding[1] {
+            0 => true,
+            1 => false,
+            encoding => return Err(FormatError::InvalidEncoding(encoding)),
+        };
+        let mut parameters_fingerprint = [0u8; 32];
+        reader.read_exact(&mut parameters_fingerprint)?;
+        Ok(Header {
+            version,
+            kind: kind_and_encoding[0],
+            compressed,
+            parameters_fingerprint,
+            backends: [
+                read_identifier(reader)?,
+                read_identifier(reader)?,
+                read_identifier(reader)?,
+            ],
+        })
+    }
+
+    /// Checks that a proof with this header can be read as a `T` and verified
+    /// with `parameters`.
+    pub fn check<T: VersionedProof>(&self, parameters: &Parameters) -> Result<(), FormatError> {
+        if self.kind != T::KIND as u8 {
+            return Err(FormatError::UnexpectedKind(T::KIND, self.kind));
+        }
+        if self.parameters_fingerprint != parameters.fingerprint() {
+            return Err(FormatError::ParametersMismatch);
+        }
+        for (ours, theirs) in T::backends().iter().zip(self.backends.iter()) {
+            if ours != theirs {
+                return Err(FormatError::BackendMismatch(
+                    ours.to_string(),
+                    theirs.to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_identifier<R: Read>(reader: &mut R) -> Result<String, FormatError> {
+    let bytes = read_bytes(reader)?;
+    if bytes.len() > MAX_IDENTIFIER_LENGTH {
+        return Err(SerializationError::InvalidData.into());
+    }
+    Ok(String::from_utf8(bytes).map_err(|_| SerializationError::InvalidData)?)
+}
+
+/// Writes `proof`, created with `parameters`, in the versioned format.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "format::encode", level = "debug", skip_all)
+)]
+pub fn encode<T: VersionedProof>(
+    proof: &T,
+    parameters: &Parameters,
+    compressed: bool,
+) -> Result<Vec<u8>, FormatError> {
+    let mut out = vec![];
+    Header::new::<T>(parameters, compressed).write(&mut out)?;
+    if compressed {
+        proof.serialize(&mut out)?;
+    } else {
+        proof.serialize_uncompressed(&mut out)?;
+    }
+    Ok(out)
+}
+
+/// Reads a proof written by `encode`, checking its header against `T` and
+/// `parameters`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "format::decode", level = "debug", skip_all)
+)]
+pub fn decode<T: VersionedProof>(bytes: &[u8], parameters: &Parameters) -> Result<T, FormatError> {
+    let mut reader = bytes;
+    let header = Header::read(&mut reader)?;
+    header.check::<T>(parameters)?;
+    let proof = if header.compressed {
+        T::deserialize(&mut reader)?
+    } else {
+        T::deserialize_uncompressed(&mut reader)?
+    };
+    if !reader.is_empty() {
+        return Err(FormatError::TrailingBytes);
+    }
+    VersionedProof::validate(&proof, parameters)?;
+    Ok(proof)
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{decode, encode, FormatError, Header, FORMAT_VERSION};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{MembershipProof, Protocol, Statement, Witness},
+            nonmembership::NonMembershipProof,
+            ValidationError,
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    type Proof = MembershipProof<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+
+    #[test]
+    fn test_versioned_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let mut proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w: accum.1.witness.0.value,
+                },
+                b"session",
+            )
+            .unwrap();
+
+        for compressed in [true, false].iter() {
+            let bytes = encode(&proof, &crs.parameters, *compressed).unwrap();
+            let header = Header::read(&mut &bytes[..]).unwrap();
+            assert_eq!(header.compressed, *compressed);
+            let decoded: Proof = decode(&bytes, &crs.parameters).unwrap();
+            protocol
+                .verify_noninteractive(&statement, &decoded, b"session")
+                .unwrap();
+        }
+
+        let bytes = encode(&proof, &crs.parameters, true).unwrap();
+        let mut tampered = bytes.clone();
+        tampered[0] ^= 1;
+        assert!(matches!(
+            decode::<Proof>(&tampered, &crs.parameters),
+            Err(FormatError::InvalidMagic)
+        ));
+
+        let mut tampered = bytes.clone();
+        tampered[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+        assert!(matches!(
+            decode::<Proof>(&tampered, &crs.parameters),
+            Err(FormatError::UnsupportedVersion(_))
+        ));
+
+        let mut tampered = bytes.clone();
+        tampered.push(0);
+        assert!(matches!(
+            decode::<Proof>(&tampered, &crs.parameters),
+            Err(FormatError::TrailingBytes)
+        ));
+
+        assert!(matches!(
+            decode::<Proof>(&bytes[..bytes.len() - 1], &crs.parameters),
+            Err(FormatError::SerializationError(_))
+        ));
+        assert!(matches!(
+            decode::<Proof>(&bytes, &Parameters::from_security_level(80).unwrap()),
+            Err(FormatError::ParametersMismatch)
+        ));
+        assert!(matches!(
+            decode::<NonMembershipProof<Rsa2048, G1Projective, HPProtocol<Bls12_381>>>(
+                &bytes,
+                &crs.parameters
+            ),
+            Err(FormatError::UnexpectedKind(_, 1))
+        ));
+        let mut header = Header::read(&mut &bytes[..]).unwrap();
+        header.backends[0] = "other".to_string();
+        assert!(matches!(
+            header.check::<Proof>(&crs.parameters),
+            Err(FormatError::BackendMismatch(_, _))
+        ));
+
+        // Oversized responses are rejected before verifying.
+        proof.proof_root.message3.s_r = Integer::from(1) << 100_000;
+        let bytes = encode(&proof, &crs.parameters, true).unwrap();
+        assert!(matches!(
+            decode::<Proof>(&bytes, &crs.parameters),
+            Err(FormatError::ValidationError(
+                ValidationError::ResponseOutOfRange("s_r")
+            ))
+        ));
+    }
+}
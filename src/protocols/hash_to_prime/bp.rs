@@ -6,21 +6,25 @@ use crate::{
     protocols::{
         hash_to_prime::{
             channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
-            CRSHashToPrime, CRSSize, HashToPrimeError, HashToPrimeProtocol, Statement, Witness,
+            CRSHashToPrime, CRSHashToPrimeVerifier, CRSSize, HashToPrimeError, HashToPrimeProtocol,
+            Statement, Witness,
         },
-        ProofError, SetupError, VerificationError,
+        CRSError, ProofError, SetupError, VerificationError,
+    },
+    utils::{
+        curve::{CurveError, Field},
+        integer_to_bigint_mod_q, log2,
     },
-    utils::{curve::Field, integer_to_bigint_mod_q, log2},
 };
 use bulletproofs::{
     r1cs::{ConstraintSystem, LinearCombination, Prover, R1CSError, R1CSProof, Verifier},
     BulletproofGens, PedersenGens,
 };
+use core::cell::RefCell;
 use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar, traits::Identity};
 use merlin::Transcript;
-use rand::Rng;
+use rand::{CryptoRng, Rng};
 use rug::Integer;
-use std::cell::RefCell;
 
 pub fn range_proof<CS: ConstraintSystem>(
     cs: &mut CS,
@@ -89,9 +93,52 @@ impl CRSSize for BPParameters {
     }
 }
 
+fn verify_range<C: HashToPrimeProverChannel<RistrettoPoint, Protocol>>(
+    parameters: &Parameters,
+    pedersen_commitment_parameters: &PedersenCommitment<RistrettoPoint>,
+    bp_parameters: &BPParameters,
+    prover_channel: &mut C,
+    statement: &Statement<RistrettoPoint>,
+) -> Result<(), VerificationError> {
+    statement.validate()?;
+    let pedersen_gens = PedersenGens {
+        B: pedersen_commitment_parameters.g,
+        B_blinding: pedersen_commitment_parameters.h,
+    };
+
+    let default_transcript = RefCell::new(Transcript::new(b"bp_range_proof"));
+    let verifier_transcript = if bp_parameters.transcript.is_some() {
+        bp_parameters.transcript.as_ref().unwrap()
+    } else {
+        &default_transcript
+    };
+
+    let mut verifier_transcript = verifier_transcript
+        .try_borrow_mut()
+        .map_err(|_| VerificationError::HashToPrimeProofInvalid)?;
+    let mut verifier = Verifier::new(&mut *verifier_transcript);
+
+    let var = verifier.commit(statement.c_e_q.compress());
+
+    if range_proof(
+        &mut verifier,
+        var.into(),
+        None,
+        parameters.hash_to_prime_bits as usize,
+    )
+    .is_err()
+    {
+        return Err(VerificationError::HashToPrimeProofInvalid);
+    }
+
+    let proof = prover_channel.receive_proof()?;
+    Ok(verifier.verify(&proof, &pedersen_gens, &bp_parameters.bulletproof_gens)?)
+}
+
 impl HashToPrimeProtocol<RistrettoPoint> for Protocol {
     type Proof = R1CSProof;
     type Parameters = BPParameters;
+    type VerifyingKey = BPParameters;
 
     fn from_crs(crs: &CRSHashToPrime<RistrettoPoint, Self>) -> Protocol {
         Protocol {
@@ -99,7 +146,11 @@ impl HashToPrimeProtocol<RistrettoPoint> for Protocol {
         }
     }
 
-    fn setup<R: Rng>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "bp::setup", level = "debug", skip_all)
+    )]
+    fn setup<R: Rng + CryptoRng>(
         _: &mut R,
         _: &PedersenCommitment<RistrettoPoint>,
         parameters: &Parameters,
@@ -111,7 +162,11 @@ impl HashToPrimeProtocol<RistrettoPoint> for Protocol {
         })
     }
 
-    fn prove<R: Rng, C: HashToPrimeVerifierChannel<RistrettoPoint, Self>>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "bp::prove", level = "debug", skip_all)
+    )]
+    fn prove<R: Rng + CryptoRng, C: HashToPrimeVerifierChannel<RistrettoPoint, Self>>(
         &self,
         verifier_channel: &mut C,
         _: &mut R,
@@ -165,56 +220,80 @@ impl HashToPrimeProtocol<RistrettoPoint> for Protocol {
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "bp::verify", level = "debug", skip_all)
+    )]
     fn verify<C: HashToPrimeProverChannel<RistrettoPoint, Self>>(
         &self,
         prover_channel: &mut C,
         statement: &Statement<RistrettoPoint>,
     ) -> Result<(), VerificationError> {
-        let pedersen_gens = PedersenGens {
-            B: self.crs.pedersen_commitment_parameters.g,
-            B_blinding: self.crs.pedersen_commitment_parameters.h,
-        };
+        verify_range(
+            &self.crs.parameters,
+            &self.crs.pedersen_commitment_parameters,
+            &self.crs.hash_to_prime_parameters,
+            prover_channel,
+            statement,
+        )
+    }
 
-        let default_transcript = RefCell::new(Transcript::new(b"bp_range_proof"));
-        let verifier_transcript = if self.crs.hash_to_prime_parameters.transcript.is_some() {
-            self.crs
-                .hash_to_prime_parameters
-                .transcript
-                .as_ref()
-                .unwrap()
-        } else {
-            &default_transcript
-        };
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "bp::verify", level = "debug", skip_all)
+    )]
+    fn verify_with_key<C: HashToPrimeProverChannel<RistrettoPoint, Self>>(
+        crs: &CRSHashToPrimeVerifier<RistrettoPoint, Self>,
+        prover_channel: &mut C,
+        statement: &Statement<RistrettoPoint>,
+    ) -> Result<(), VerificationError> {
+        verify_range(
+            &crs.parameters,
+            &crs.pedersen_commitment_parameters,
+            &crs.verifying_key,
+            prover_channel,
+            statement,
+        )
+    }
 
-        let mut verifier_transcript = verifier_transcript
-            .try_borrow_mut()
-            .map_err(|_| VerificationError::VerificationFailed)?;
-        let mut verifier = Verifier::new(&mut *verifier_transcript);
+    /// The generators are needed to verify as well.
+    fn verifying_key(parameters: &Self::Parameters) -> Self::VerifyingKey {
+        parameters.clone()
+    }
 
-        let var = verifier.commit(statement.c_e_q.compress());
+    /// The generators are derived from their capacities.
+    fn verifying_key_bytes(verifying_key: &Self::VerifyingKey) -> Vec<u8> {
+        let gens = &verifying_key.bulletproof_gens;
+        let mut bytes = (gens.gens_capacity as u64).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&(gens.party_capacity as u64).to_be_bytes());
+        bytes
+    }
 
-        if range_proof(
-            &mut verifier,
-            var.into(),
-            None,
-            self.crs.parameters.hash_to_prime_bits as usize,
-        )
-        .is_err()
+    /// The generators have to cover the rounded number of bits of the range.
+    fn validate_parameters<R: Rng + CryptoRng>(
+        crs: &CRSHashToPrime<RistrettoPoint, Self>,
+        _: &mut R,
+    ) -> Result<(), CRSError> {
+        let gens = &crs.hash_to_prime_parameters.bulletproof_gens;
+        if gens.gens_capacity < 1 << log2(crs.parameters.hash_to_prime_bits as usize)
+            || gens.party_capacity < 1
         {
-            return Err(VerificationError::VerificationFailed);
+            return Err(CRSError::InvalidKey);
         }
-
-        let proof = prover_channel.receive_proof()?;
-        Ok(verifier.verify(
-            &proof,
-            &pedersen_gens,
-            &self.crs.hash_to_prime_parameters.bulletproof_gens,
-        )?)
+        Ok(())
     }
 
     fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
         Ok((e.clone(), 0))
     }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        Ok(proof.to_bytes())
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        R1CSProof::from_bytes(bytes).map_err(|_| CurveError::CannotRead)
+    }
 }
 
 #[cfg(test)]
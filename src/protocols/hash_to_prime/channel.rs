@@ -9,3 +9,38 @@ pub trait HashToPrimeVerifierChannel<P: CurvePointProjective, HP: HashToPrimePro
 pub trait HashToPrimeProverChannel<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
     fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError>;
 }
+
+/// Lends a channel, e.g., to a wrapper such as `InstrumentedChannel`.
+impl<
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: HashToPrimeVerifierChannel<P, HP> + ?Sized,
+    > HashToPrimeVerifierChannel<P, HP> for &mut C
+{
+    fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), ChannelError> {
+        (**self).send_proof(proof)
+    }
+}
+
+impl<
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        C: HashToPrimeProverChannel<P, HP> + ?Sized,
+    > HashToPrimeProverChannel<P, HP> for &mut C
+{
+    fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError> {
+        (**self).receive_proof()
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncHashToPrimeVerifierChannel<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
+    async fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), ChannelError>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncHashToPrimeProverChannel<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
+    async fn receive_proof(&mut self) -> Result<HP::Proof, ChannelError>;
+}
@@ -0,0 +1,432 @@
+//! Selects the hash-to-prime backend at runtime, e.g., from a configuration
+//! file, instead of as a type parameter.
+//!
+//! `HashToPrimeDyn` is an object-safe view of a backend and its CRS, proving
+//! to and verifying from the bytes of the proofs, and `DynProtocol` is the
+//! `HashToPrimeProtocol` over a `Box<dyn HashToPrimeDyn>`, so that the
+//! membership protocol is instantiated once for all the backends:
+//!
+//! ```ignore
+//! let protocol: Protocol<Rsa2048, G1Projective, DynProtocol<G1Projective>> =
+//!     match config.backend.as_str() {
+//!         "snark_range" => Protocol::<_, _, SnarkRange<Bls12_381>>::from_crs(&crs).into_dyn(),
+//!         "sigma_range" => Protocol::<_, _, SigmaRange<G1Projective>>::from_crs(&crs).into_dyn(),
+//!         ...
+//!     };
+//! ```
+//!
+//! The proofs of `DynProtocol` are the bytes of `proof_to_bytes`, and the
+//! fingerprint of a CRS doesn't change when it's converted, so that the
+//! membership proofs are the same as the ones of the backend's type.
+use crate::{
+    commitments::pedersen::PedersenCommitment,
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{
+            channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
+            transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+            CRSHashToPrime, CRSHashToPrimeVerifier, HashToPrimeError, HashToPrimeProtocol,
+            Statement, Witness,
+        },
+        CRSError, ProofError, SetupError, VerificationError,
+    },
+    utils::curve::{CurveError, CurvePointProjective},
+};
+use ark_serialize::SerializationError;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// A generator for the provers, usable as a trait object.
+pub trait CryptoRngCore: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng + ?Sized> CryptoRngCore for R {}
+
+/// A hash-to-prime backend together with its CRS.
+pub trait HashToPrimeDyn<P: CurvePointProjective> {
+    /// The type name of the backend, as in the handshakes.
+    fn backend(&self) -> &'static str;
+    fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError>;
+    fn num_constraints(&self) -> Option<usize>;
+    fn verifying_key_bytes(&self) -> Vec<u8>;
+    fn validate(&self, rng: &mut dyn CryptoRngCore) -> Result<(), CRSError>;
+    /// Proves the statement and returns the compressed bytes of the proof.
+    fn prove(
+        &self,
+        rng: &mut dyn CryptoRngCore,
+        statement: &Statement<P>,
+        witness: &Witness,
+    ) -> Result<Vec<u8>, ProofError>;
+    fn verify(&self, statement: &Statement<P>, proof: &[u8]) -> Result<(), VerificationError>;
+    #[cfg(feature = "simulator")]
+    fn simulate_proof(
+        &self,
+        rng: &mut dyn CryptoRngCore,
+        statement: &Statement<P>,
+    ) -> Option<Vec<u8>>;
+}
+
+/// Implements `HashToPrimeDyn` for a backend's type.
+pub struct Backend<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
+    crs: CRSHashToPrime<P, HP>,
+    protocol: HP,
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Backend<P, HP> {
+    pub fn new(crs: &CRSHashToPrime<P, HP>) -> Backend<P, HP> {
+        Backend {
+            crs: crs.clone(),
+            protocol: HP::from_crs(crs),
+        }
+    }
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> HashToPrimeDyn<P> for Backend<P, HP> {
+    fn backend(&self) -> &'static str {
+        std::any::type_name::<HP>()
+    }
+
+    fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
+        self.protocol.hash_to_prime(e)
+    }
+
+    fn num_constraints(&self) -> Option<usize> {
+        self.protocol.num_constraints()
+    }
+
+    fn verifying_key_bytes(&self) -> Vec<u8> {
+        HP::verifying_key_bytes(&HP::verifying_key(&self.crs.hash_to_prime_parameters))
+    }
+
+    fn validate(&self, mut rng: &mut dyn CryptoRngCore) -> Result<(), CRSError> {
+        self.crs.validate(&mut rng)
+    }
+
+    fn prove(
+        &self,
+        mut rng: &mut dyn CryptoRngCore,
+        statement: &Statement<P>,
+        witness: &Witness,
+    ) -> Result<Vec<u8>, ProofError> {
+        let transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.protocol
+            .prove(&mut verifier_channel, &mut rng, statement, witness)?;
+        let proof = verifier_channel.proof()?;
+        Ok(HP::proof_to_bytes(&proof).map_err(SerializationError::from)?)
+    }
+
+    fn verify(&self, statement: &Statement<P>, proof: &[u8]) -> Result<(), VerificationError> {
+        let proof = HP::proof_from_bytes(proof).map_err(SerializationError::from)?;
+        let transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, &proof);
+        self.protocol.verify(&mut prover_channel, statement)
+    }
+
+    #[cfg(feature = "simulator")]
+    fn simulate_proof(
+        &self,
+        mut rng: &mut dyn CryptoRngCore,
+        statement: &Statement<P>,
+    ) -> Option<Vec<u8>> {
+        let proof = self.protocol.simulate_proof(&mut rng, statement)?;
+        HP::proof_to_bytes(&proof).ok()
+    }
+}
+
+/// Boxes a backend with its CRS.
+pub fn boxed<P: CurvePointProjective + 'static, HP: HashToPrimeProtocol<P> + 'static>(
+    crs: &CRSHashToPrime<P, HP>,
+) -> Box<dyn HashToPrimeDyn<P>> {
+    Box::new(Backend::new(crs))
+}
+
+/// Generates the parameters of the backend named `snark_range` or
+/// `sigma_range`, as in `membership::any`, and boxes it with them. The
+/// `snark_hash` backend also depends on the size of the hashed messages, so
+/// it's boxed with `boxed` after its setup.
+#[cfg(feature = "arkworks")]
+pub fn setup<E: ark_ec::PairingEngine, R: RngCore + CryptoRng>(
+    backend: &str,
+    rng: &mut R,
+    pedersen_commitment_parameters: &PedersenCommitment<E::G1Projective>,
+    parameters: &Parameters,
+) -> Result<Box<dyn HashToPrimeDyn<E::G1Projective>>, SetupError>
+where
+    E::G1Projective: CurvePointProjective,
+{
+    use crate::protocols::hash_to_prime::{sigma_range, snark_range};
+
+    fn setup_backend<
+        P: CurvePointProjective + 'static,
+        HP: HashToPrimeProtocol<P> + 'static,
+        R: RngCore + CryptoRng,
+    >(
+        rng: &mut R,
+        pedersen_commitment_parameters: &PedersenCommitment<P>,
+        parameters: &Parameters,
+    ) -> Result<Box<dyn HashToPrimeDyn<P>>, SetupError> {
+        let crs = CRSHashToPrime::<P, HP> {
+            parameters: parameters.clone(),
+            pedersen_commitment_parameters: pedersen_commitment_parameters.clone(),
+            hash_to_prime_parameters: Arc::new(HP::setup(
+                rng,
+                pedersen_commitment_parameters,
+                parameters,
+            )?),
+        };
+        Ok(boxed(&crs))
+    }
+
+    match backend {
+        "snark_range" => setup_backend::<_, snark_range::Protocol<E>, _>(
+            rng,
+            pedersen_commitment_parameters,
+            parameters,
+        ),
+        "sigma_range" => setup_backend::<_, sigma_range::Protocol<E::G1Projective>, _>(
+            rng,
+            pedersen_commitment_parameters,
+            parameters,
+        ),
+        _ => Err(SetupError::UnknownBackend(backend.to_string())),
+    }
+}
+
+/// The hash-to-prime protocol over a boxed backend. Its parameters are the
+/// backend, so it can't generate them: the CRS is converted with
+/// `CRSHashToPrime::into_dyn`, or created with `setup`.
+pub struct DynProtocol<P: CurvePointProjective> {
+    backend: Arc<dyn HashToPrimeDyn<P>>,
+}
+
+impl<P: CurvePointProjective> DynProtocol<P> {
+    pub fn backend(&self) -> &dyn HashToPrimeDyn<P> {
+        self.backend.as_ref()
+    }
+}
+
+impl<P: CurvePointProjective + 'static, HP: HashToPrimeProtocol<P> + 'static>
+    CRSHashToPrime<P, HP>
+{
+    pub fn into_dyn(self) -> CRSHashToPrime<P, DynProtocol<P>> {
+        let backend: Arc<dyn HashToPrimeDyn<P>> = Arc::from(boxed(&self));
+        CRSHashToPrime {
+            parameters: self.parameters,
+            pedersen_commitment_parameters: self.pedersen_commitment_parameters,
+            hash_to_prime_parameters: Arc::new(backend),
+        }
+    }
+}
+
+impl<P: CurvePointProjective> CRSHashToPrime<P, DynProtocol<P>> {
+    /// Creates the CRS of a boxed backend, e.g., from `setup`, which has to
+    /// use the same parameters and Pedersen bases.
+    pub fn from_backend(
+        parameters: &Parameters,
+        pedersen_commitment_parameters: &PedersenCommitment<P>,
+        backend: Box<dyn HashToPrimeDyn<P>>,
+    ) -> Self {
+        CRSHashToPrime {
+            parameters: parameters.clone(),
+            pedersen_commitment_parameters: pedersen_commitment_parameters.clone(),
+            hash_to_prime_parameters: Arc::new(Arc::from(backend)),
+        }
+    }
+}
+
+impl<P: CurvePointProjective> HashToPrimeProtocol<P> for DynProtocol<P> {
+    type Proof = Vec<u8>;
+    type Parameters = Arc<dyn HashToPrimeDyn<P>>;
+    type VerifyingKey = Arc<dyn HashToPrimeDyn<P>>;
+
+    fn from_crs(crs: &CRSHashToPrime<P, Self>) -> DynProtocol<P> {
+        DynProtocol {
+            backend: crs.hash_to_prime_parameters.as_ref().clone(),
+        }
+    }
+
+    fn setup<R: RngCore + CryptoRng>(
+        _: &mut R,
+        _: &PedersenCommitment<P>,
+        _: &Parameters,
+    ) -> Result<Self::Parameters, SetupError> {
+        Err(SetupError::CouldNotPerformSetup)
+    }
+
+    fn prove<R: RngCore + CryptoRng, C: HashToPrimeVerifierChannel<P, Self>>(
+        &self,
+        verifier_channel: &mut C,
+        rng: &mut R,
+        statement: &Statement<P>,
+        witness: &Witness,
+    ) -> Result<(), ProofError> {
+        let proof = self.backend.prove(rng, statement, witness)?;
+        verifier_channel.send_proof(&proof)?;
+        Ok(())
+    }
+
+    fn verify<C: HashToPrimeProverChannel<P, Self>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<P>,
+    ) -> Result<(), VerificationError> {
+        let proof = prover_channel.receive_proof()?;
+        self.backend.verify(statement, &proof)
+    }
+
+    fn verify_with_key<C: HashToPrimeProverChannel<P, Self>>(
+        crs: &CRSHashToPrimeVerifier<P, Self>,
+        prover_channel: &mut C,
+        statement: &Statement<P>,
+    ) -> Result<(), VerificationError> {
+        let proof = prover_channel.receive_proof()?;
+        crs.verifying_key.verify(statement, &proof)
+    }
+
+    fn verifying_key(parameters: &Self::Parameters) -> Self::VerifyingKey {
+        parameters.clone()
+    }
+
+    fn verifying_key_bytes(verifying_key: &Self::VerifyingKey) -> Vec<u8> {
+        verifying_key.verifying_key_bytes()
+    }
+
+    fn validate_parameters<R: RngCore + CryptoRng>(
+        crs: &CRSHashToPrime<P, Self>,
+        rng: &mut R,
+    ) -> Result<(), CRSError> {
+        crs.hash_to_prime_parameters.validate(rng)
+    }
+
+    fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
+        self.backend.hash_to_prime(e)
+    }
+
+    fn num_constraints(&self) -> Option<usize> {
+        self.backend.num_constraints()
+    }
+
+    #[cfg(feature = "simulator")]
+    fn simulate_proof<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        statement: &Statement<P>,
+    ) -> Option<Self::Proof> {
+        self.backend.simulate_proof(rng, statement)
+    }
+
+    /// The proofs are kept as bytes and decoded when they're verified.
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        Ok(proof.clone())
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::DynProtocol;
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::{sigma_range, snark_range, HashToPrimeProtocol},
+            membership::{MembershipProof, Protocol, Statement, Witness, CRS},
+        },
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::{group::Rsa2048, AccumulatorWithoutHashToPrime};
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use ark_serialize::CanonicalDeserialize;
+    use rug::Integer;
+
+    #[test]
+    fn test_runtime_backends() {
+        let params = Parameters::from_security_level(128).unwrap();
+        for backend in &["snark_range", "sigma_range"] {
+            let (protocol, typed_proof_bytes) = match *backend {
+                "snark_range" => {
+                    let protocol = Protocol::<
+                        Rsa2048,
+                        G1Projective,
+                        snark_range::Protocol<Bls12_381>,
+                    >::setup_deterministic(&params, [7u8; 32])
+                    .unwrap();
+                    let (statement, witness) = statement_and_witness(&protocol.crs, &params);
+                    let (mut rng1, mut rng2) = seeded_rngs([8u8; 32]);
+                    let proof = protocol
+                        .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"dyn")
+                        .unwrap();
+                    (protocol.into_dyn(), proof.to_canonical_bytes().unwrap())
+                }
+                _ => {
+                    let protocol = Protocol::<
+                        Rsa2048,
+                        G1Projective,
+                        sigma_range::Protocol<G1Projective>,
+                    >::setup_deterministic(&params, [7u8; 32])
+                    .unwrap();
+                    let (statement, witness) = statement_and_witness(&protocol.crs, &params);
+                    let (mut rng1, mut rng2) = seeded_rngs([8u8; 32]);
+                    let proof = protocol
+                        .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"dyn")
+                        .unwrap();
+                    (protocol.into_dyn(), proof.to_canonical_bytes().unwrap())
+                }
+            };
+            let protocol: Protocol<Rsa2048, G1Projective, DynProtocol<G1Projective>> = protocol;
+            assert!(protocol
+                .crs
+                .crs_hash_to_prime
+                .hash_to_prime_parameters
+                .backend()
+                .contains(backend));
+
+            let (statement, witness) = statement_and_witness(&protocol.crs, &params);
+            let (mut rng1, mut rng2) = seeded_rngs([9u8; 32]);
+            let proof = protocol
+                .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"dyn")
+                .unwrap();
+            protocol
+                .verify_noninteractive(&statement, &proof, b"dyn")
+                .unwrap();
+            assert!(protocol
+                .verify_noninteractive(&statement, &proof, b"other")
+                .is_err());
+
+            let typed_proof =
+                MembershipProof::<Rsa2048, G1Projective, DynProtocol<G1Projective>>::deserialize(
+                    &typed_proof_bytes[..],
+                )
+                .unwrap();
+            protocol
+                .verify_noninteractive(&statement, &typed_proof, b"dyn")
+                .unwrap();
+        }
+    }
+
+    fn statement_and_witness<HP: HashToPrimeProtocol<G1Projective>>(
+        crs: &CRS<Rsa2048, G1Projective, HP>,
+        params: &Parameters,
+    ) -> (Statement<Rsa2048, G1Projective>, Witness<Rsa2048>) {
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 245;
+        let randomness = Integer::from(5);
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let commitment = crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        (
+            Statement::new(accum.0.value, commitment).unwrap(),
+            Witness::new(value, randomness, accum.1.witness.0.value).unwrap(),
+        )
+    }
+}
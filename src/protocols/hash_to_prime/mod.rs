@@ -2,79 +2,185 @@
 use crate::{
     commitments::{pedersen::PedersenCommitment, Commitment},
     parameters::Parameters,
-    protocols::{ProofError, SetupError, VerificationError},
-    utils::curve::CurvePointProjective,
+    protocols::{
+        check_point, point_bytes, CRSError, ProofError, SetupError, ValidationError,
+        VerificationError,
+    },
+    utils::zeroize_integer,
+    utils::RedactedInteger,
+    utils::{
+        curve::{CurveError, CurvePointProjective},
+        serialization::point_size,
+    },
 };
+use ark_serialize::CanonicalSerialize;
 use channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel};
 use rand::{CryptoRng, RngCore};
 use rug::Integer;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+use zeroize::Zeroize;
 
 pub mod channel;
+pub mod dynamic;
+pub mod sigma_range;
 pub mod transcript;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "arkworks")] {
+        pub mod r1cs;
+        pub mod recursive;
         pub mod snark_hash;
         pub mod snark_range;
 
-        use ark_ec::{PairingEngine, AffineCurve};
-        use ark_serialize::CanonicalSerialize;
+        use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine, ProjectiveCurve};
+        use ark_ff::{PrimeField, UniformRand, Zero};
+        use core::ops::Sub;
+        use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+
+        /// The number of constraints of a circuit, synthesized without a
+        /// witness.
+        fn count_constraints<F: PrimeField, C: ConstraintSynthesizer<F>>(
+            circuit: C,
+        ) -> Option<usize> {
+            let cs = ConstraintSystem::<F>::new_ref();
+            cs.set_mode(SynthesisMode::Setup);
+            circuit.generate_constraints(cs.clone()).ok()?;
+            Some(cs.num_constraints())
+        }
+
+        fn size<T: CanonicalSerialize>(value: &T, compressed: bool) -> usize {
+            if compressed {
+                value.serialized_size()
+            } else {
+                value.uncompressed_size()
+            }
+        }
 
         impl<E: PairingEngine> CRSSize for legogro16::ProvingKey::<E> {
             fn crs_size(&self) -> (usize, usize) {
-                let g1_serialized_size = E::G1Affine::prime_subgroup_generator().serialized_size();
-                let g2_serialized_size = E::G2Affine::prime_subgroup_generator().serialized_size();
-
-                let mut vk_accum = 0;
-                // Groth16 vk
-                vk_accum += self.vk.alpha_g1.serialized_size();
-                vk_accum += self.vk.beta_g2.serialized_size();
-                vk_accum += self.vk.gamma_g2.serialized_size();
-                vk_accum += self.vk.delta_g2.serialized_size();
-                for g in &self.vk.gamma_abc_g1 {
-                    vk_accum += g.serialized_size();
-                }
-                vk_accum += self.vk.eta_gamma_inv_g1.serialized_size();
-
-                // link
-                vk_accum += 8; // l
-                vk_accum += 8; // t
-                vk_accum += g1_serialized_size;
-                vk_accum += g2_serialized_size;
-
-                for b in &self.vk.link_bases {
-                    vk_accum += b.serialized_size();
-                }
-                vk_accum += g2_serialized_size;
-                for b in &self.vk.link_vk.c {
-                    vk_accum += b.serialized_size();
-                }
-
-                let mut pk_accum = 0;
-                pk_accum += self.beta_g1.serialized_size();
-                pk_accum += self.delta_g1.serialized_size();
-                pk_accum += self.eta_delta_inv_g1.serialized_size();
-                for g in &self.a_query {
-                    pk_accum += g.serialized_size();
-                }
-                for g in &self.b_g1_query {
-                    pk_accum += g.serialized_size();
-                }
-                for g in &self.b_g2_query {
-                    pk_accum += g.serialized_size();
-                }
-                for g in &self.h_query {
-                    pk_accum += g.serialized_size();
-                }
-                for g in &self.l_query {
-                    pk_accum += g.serialized_size();
-                }
-                for g in &self.link_ek.p {
-                    pk_accum += g.serialized_size();
-                }
-
-                (vk_accum, pk_accum)
+                proving_key_size(self, true)
+            }
+
+            fn crs_size_uncompressed(&self) -> (usize, usize) {
+                proving_key_size(self, false)
+            }
+        }
+
+        /// The verifying key is serialized within the proving key, so the size
+        /// of the rest is the difference.
+        fn proving_key_size<E: PairingEngine>(
+            pk: &legogro16::ProvingKey<E>,
+            compressed: bool,
+        ) -> (usize, usize) {
+            let vk_size = size(&pk.vk, compressed);
+            (vk_size, size(pk, compressed) - vk_size)
+        }
+
+        /// Checks a LegoGroth16 key whose link bases are a base committing to
+        /// one followed by the Pedersen bases. Beta and delta have to be the
+        /// same in G1 and G2, the B query too, and eta / delta * delta has
+        /// to be eta / gamma * gamma. The queries are combined with random
+        /// scalars to check them at once.
+        fn validate_snark_key<E: PairingEngine, R: RngCore + CryptoRng>(
+            pk: &legogro16::ProvingKey<E>,
+            pedersen_commitment_parameters: &PedersenCommitment<E::G1Projective>,
+            rng: &mut R,
+        ) -> Result<(), CRSError> {
+            let vk = &pk.vk;
+            if vk.link_bases.len() != 3
+                || vk.link_bases[0].is_zero()
+                || vk.link_bases[1] != pedersen_commitment_parameters.g.into_affine()
+                || vk.link_bases[2] != pedersen_commitment_parameters.h.into_affine()
+            {
+                return Err(CRSError::InvalidCommitmentBases);
+            }
+            if vk.alpha_g1.is_zero()
+                || vk.beta_g2.is_zero()
+                || vk.gamma_g2.is_zero()
+                || vk.delta_g2.is_zero()
+                || pk.b_g1_query.len() != pk.b_g2_query.len()
+            {
+                return Err(CRSError::InvalidKey);
+            }
+            let g1 = E::G1Affine::prime_subgroup_generator();
+            let g2 = E::G2Affine::prime_subgroup_generator();
+            let scalars = (0..pk.b_g1_query.len())
+                .map(|_| E::Fr::rand(rng).into_repr())
+                .collect::<Vec<_>>();
+            let b_g1 = VariableBaseMSM::multi_scalar_mul(&pk.b_g1_query, &scalars);
+            let b_g2 = VariableBaseMSM::multi_scalar_mul(&pk.b_g2_query, &scalars);
+            if E::pairing(pk.beta_g1, g2) != E::pairing(g1, vk.beta_g2)
+                || E::pairing(pk.delta_g1, g2) != E::pairing(g1, vk.delta_g2)
+                || E::pairing(b_g1.into_affine(), g2) != E::pairing(g1, b_g2.into_affine())
+                || E::pairing(pk.eta_delta_inv_g1, vk.delta_g2)
+                    != E::pairing(vk.eta_gamma_inv_g1, vk.gamma_g2)
+            {
+                return Err(CRSError::InvalidKey);
+            }
+            Ok(())
+        }
+
+        /// The elements are committed to in the scalar field, so their size
+        /// has to be below its modulus, e.g., at most 253 bits on BN254,
+        /// whose modulus has 254 bits, instead of the 254 bits of
+        /// `from_security_level(128)`.
+        fn check_field_size<F: PrimeField>(parameters: &Parameters) -> Result<(), SetupError> {
+            if parameters.hash_to_prime_bits as usize >= F::size_in_bits() {
+                return Err(SetupError::CouldNotPerformSetup);
             }
+            Ok(())
+        }
+
+        /// A LegoGroth16 proof with random points, whose link commitment is
+        /// the statement's commitment plus the base committing to one, as
+        /// for an honest proof.
+        #[cfg(feature = "simulator")]
+        fn simulate_snark<E: PairingEngine, R: RngCore + CryptoRng>(
+            rng: &mut R,
+            vk: &legogro16::VerifyingKey<E>,
+            statement: &Statement<E::G1Projective>,
+        ) -> legogro16::Proof<E> {
+            legogro16::Proof {
+                a: <E::G1Projective as UniformRand>::rand(rng).into_affine(),
+                b: <E::G2Projective as UniformRand>::rand(rng).into_affine(),
+                c: <E::G1Projective as UniformRand>::rand(rng).into_affine(),
+                d: <E::G1Projective as UniformRand>::rand(rng).into_affine(),
+                link_d: (statement.c_e_q + vk.link_bases[0].into_projective()).into_affine(),
+                link_pi: <E::G1Projective as UniformRand>::rand(rng).into_affine(),
+            }
+        }
+
+        /// Verifies a LegoGroth16 proof and that its link commitment, without
+        /// the base committing to one, is the statement's commitment.
+        fn verify_snark<
+            E: PairingEngine,
+            HP: HashToPrimeProtocol<E::G1Projective, Proof = legogro16::Proof<E>>,
+            C: HashToPrimeProverChannel<E::G1Projective, HP>,
+        >(
+            pvk: &legogro16::PreparedVerifyingKey<E>,
+            vk: &legogro16::VerifyingKey<E>,
+            prover_channel: &mut C,
+            statement: &Statement<E::G1Projective>,
+        ) -> Result<(), VerificationError> {
+            statement.validate()?;
+            let proof = prover_channel.receive_proof()?;
+            if !legogro16::verify_proof(pvk, &proof)? {
+                return Err(VerificationError::HashToPrimeProofInvalid);
+            }
+            let proof_link_d_without_one = proof
+                .link_d
+                .into_projective()
+                .sub(&vk.link_bases[0].into_projective());
+            if statement.c_e_q != proof_link_d_without_one {
+                return Err(VerificationError::HashToPrimeLinkMismatch {
+                    expected: point_bytes(&statement.c_e_q),
+                    got: point_bytes(&proof_link_d_without_one),
+                });
+            }
+
+            Ok(())
         }
     }
 }
@@ -86,12 +192,20 @@ cfg_if::cfg_if! {
 }
 
 pub trait CRSSize {
+    /// The serialized sizes of the verifying and proving keys.
     fn crs_size(&self) -> (usize, usize);
+    /// Like `crs_size`, with the curve points uncompressed.
+    fn crs_size_uncompressed(&self) -> (usize, usize) {
+        self.crs_size()
+    }
 }
 
 pub trait HashToPrimeProtocol<P: CurvePointProjective> {
     type Proof: Clone;
     type Parameters: Clone;
+    /// The part of the parameters needed to verify, e.g., the SNARK's
+    /// verifying key without the much larger proving key.
+    type VerifyingKey: Clone;
 
     fn from_crs(crs: &CRSHashToPrime<P, Self>) -> Self
     where
@@ -117,15 +231,78 @@ pub trait HashToPrimeProtocol<P: CurvePointProjective> {
         prover_channel: &mut C,
         statement: &Statement<P>,
     ) -> Result<(), VerificationError>
+    where
+        Self: Sized;
+    /// Like `verify`, with the verifying key alone, for verifiers that don't
+    /// hold the parameters.
+    fn verify_with_key<C: HashToPrimeProverChannel<P, Self>>(
+        crs: &CRSHashToPrimeVerifier<P, Self>,
+        prover_channel: &mut C,
+        statement: &Statement<P>,
+    ) -> Result<(), VerificationError>
+    where
+        Self: Sized;
+    fn verifying_key(parameters: &Self::Parameters) -> Self::VerifyingKey;
+    /// The encoding of the verifying key hashed into the CRS fingerprint that
+    /// the transcripts are bound to.
+    fn verifying_key_bytes(verifying_key: &Self::VerifyingKey) -> Vec<u8>;
+    /// Checks the relations between the elements of the parameters that
+    /// hold for an honest setup and can be checked without its trapdoor,
+    /// e.g., the pairing relations between the proving and verifying keys,
+    /// and that they commit to the Pedersen bases.
+    fn validate_parameters<R: RngCore + CryptoRng>(
+        crs: &CRSHashToPrime<P, Self>,
+        rng: &mut R,
+    ) -> Result<(), CRSError>
     where
         Self: Sized;
     fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError>;
+    /// The number of constraints of the proven circuit, for the backends
+    /// that have one.
+    fn num_constraints(&self) -> Option<usize> {
+        None
+    }
+    /// A proof of the right shape for the statement, with random elements,
+    /// that doesn't verify, for the backends that can create one. See
+    /// `membership::simulator`.
+    #[cfg(feature = "simulator")]
+    fn simulate_proof<R: RngCore + CryptoRng>(
+        &self,
+        _: &mut R,
+        _: &Statement<P>,
+    ) -> Option<Self::Proof>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, CurveError>;
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, CurveError>;
+    /// Like `proof_to_bytes`, with the curve points uncompressed where the
+    /// proof system supports it.
+    fn proof_to_bytes_uncompressed(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        Self::proof_to_bytes(proof)
+    }
+    fn proof_from_bytes_uncompressed(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        Self::proof_from_bytes(bytes)
+    }
+    /// The length of `proof_to_bytes` or `proof_to_bytes_uncompressed`.
+    fn proof_size(proof: &Self::Proof, compressed: bool) -> usize {
+        let bytes = if compressed {
+            Self::proof_to_bytes(proof)
+        } else {
+            Self::proof_to_bytes_uncompressed(proof)
+        };
+        bytes.map(|b| b.len()).unwrap_or(0)
+    }
 }
 
 pub struct CRSHashToPrime<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
     pub parameters: Parameters,
     pub pedersen_commitment_parameters: PedersenCommitment<P>,
-    pub hash_to_prime_parameters: HP::Parameters,
+    /// Shared by the clones of the CRS, as the proving keys can be large.
+    pub hash_to_prime_parameters: Arc<HP::Parameters>,
 }
 
 impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone for CRSHashToPrime<P, HP> {
@@ -138,22 +315,128 @@ impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone for CRSHashToPri
     }
 }
 
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRSHashToPrime<P, HP>
+where
+    HP::Parameters: CRSSize,
+{
+    /// The size of the Pedersen bases and of the verifying and proving keys.
+    pub(crate) fn size(&self, compressed: bool) -> usize {
+        let (vk_size, pk_size) = if compressed {
+            self.hash_to_prime_parameters.crs_size()
+        } else {
+            self.hash_to_prime_parameters.crs_size_uncompressed()
+        };
+        point_size(&self.pedersen_commitment_parameters.g, compressed)
+            + point_size(&self.pedersen_commitment_parameters.h, compressed)
+            + vk_size
+            + pk_size
+    }
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRSHashToPrime<P, HP> {
+    pub fn validate<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(), CRSError> {
+        self.parameters
+            .is_valid()
+            .map_err(|_| CRSError::InvalidParameters)?;
+        let pedersen = &self.pedersen_commitment_parameters;
+        if pedersen.g.is_identity() || pedersen.h.is_identity() || pedersen.g == pedersen.h {
+            return Err(CRSError::InvalidCommitmentBases);
+        }
+        HP::validate_parameters(self, rng)
+    }
+
+    pub fn verifier(&self) -> CRSHashToPrimeVerifier<P, HP> {
+        CRSHashToPrimeVerifier {
+            parameters: self.parameters.clone(),
+            pedersen_commitment_parameters: self.pedersen_commitment_parameters.clone(),
+            verifying_key: HP::verifying_key(&self.hash_to_prime_parameters),
+        }
+    }
+}
+
+/// The part of `CRSHashToPrime` that the verifier needs.
+pub struct CRSHashToPrimeVerifier<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
+    pub parameters: Parameters,
+    pub pedersen_commitment_parameters: PedersenCommitment<P>,
+    pub verifying_key: HP::VerifyingKey,
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone for CRSHashToPrimeVerifier<P, HP> {
+    fn clone(&self) -> Self {
+        Self {
+            parameters: self.parameters.clone(),
+            pedersen_commitment_parameters: self.pedersen_commitment_parameters.clone(),
+            verifying_key: self.verifying_key.clone(),
+        }
+    }
+}
+
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRSHashToPrimeVerifier<P, HP>
+where
+    HP::VerifyingKey: CanonicalSerialize,
+{
+    /// The size of the Pedersen bases and of the verifying key.
+    pub(crate) fn size(&self, compressed: bool) -> usize {
+        let vk_size = if compressed {
+            self.verifying_key.serialized_size()
+        } else {
+            self.verifying_key.uncompressed_size()
+        };
+        point_size(&self.pedersen_commitment_parameters.g, compressed)
+            + point_size(&self.pedersen_commitment_parameters.h, compressed)
+            + vk_size
+    }
+}
+
 pub struct Statement<P: CurvePointProjective> {
     pub c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
 }
 
+impl<P: CurvePointProjective> Statement<P> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_point("c_e_q", &self.c_e_q)
+    }
+}
+
 pub struct Witness {
     pub e: Integer,
     pub r_q: Integer,
 }
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum HashToPrimeError {
-        CouldNotFindIndex {}
-        ValueTooBig {}
-        IntegerError(num: Integer) {
-            from()
-        }
+impl Zeroize for Witness {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl fmt::Debug for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .finish()
+    }
+}
+
+impl Drop for Witness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HashToPrimeError {
+    #[error("could not find an index giving a prime")]
+    CouldNotFindIndex,
+    #[error("the value is too big")]
+    ValueTooBig,
+    #[error("could not convert {0}")]
+    IntegerError(Integer),
+}
+
+impl From<Integer> for HashToPrimeError {
+    fn from(num: Integer) -> Self {
+        HashToPrimeError::IntegerError(num)
     }
 }
@@ -0,0 +1,208 @@
+//! Exports the hash-to-prime circuits in the binary `.r1cs` and `.wtns`
+//! formats of circom, so that snarkjs and other tools built around them can
+//! inspect the constraints and reuse the circuits.
+//!
+//! Wire 0 is the constant one, followed by the public inputs and the witness
+//! variables, in the order of arkworks. The public inputs are declared as
+//! inputs rather than outputs, and the witness variables as intermediate
+//! signals, since arkworks doesn't tell private inputs apart from them.
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, SynthesisMode,
+};
+use std::io::Write;
+use thiserror::Error;
+
+const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+const R1CS_VERSION: u32 = 1;
+const WTNS_MAGIC: &[u8; 4] = b"wtns";
+const WTNS_VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub enum R1CSExportError {
+    #[error("the witness doesn't satisfy the circuit")]
+    Unsatisfied,
+    #[error("could not synthesize the circuit")]
+    SynthesisError(#[from] SynthesisError),
+    #[error("could not write the circuit")]
+    IoError(#[from] std::io::Error),
+}
+
+fn field_bytes<F: PrimeField>(value: &F) -> Vec<u8> {
+    value.into_repr().to_bytes_le()
+}
+
+/// The field size and modulus, which start the header sections of both
+/// formats.
+fn field_header<F: PrimeField>() -> Vec<u8> {
+    let modulus = F::Params::MODULUS.to_bytes_le();
+    let mut header = (modulus.len() as u32).to_le_bytes().to_vec();
+    header.extend_from_slice(&modulus);
+    header
+}
+
+fn write_file<W: Write>(
+    writer: &mut W,
+    magic: &[u8; 4],
+    version: u32,
+    sections: &[(u32, Vec<u8>)],
+) -> Result<(), R1CSExportError> {
+    writer.write_all(magic)?;
+    writer.write_all(&version.to_le_bytes())?;
+    writer.write_all(&(sections.len() as u32).to_le_bytes())?;
+    for (section_type, contents) in sections {
+        writer.write_all(&section_type.to_le_bytes())?;
+        writer.write_all(&(contents.len() as u64).to_le_bytes())?;
+        writer.write_all(contents)?;
+    }
+    Ok(())
+}
+
+fn synthesize<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+    mode: SynthesisMode,
+) -> Result<ConstraintSystemRef<F>, R1CSExportError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(mode);
+    circuit.generate_constraints(cs.clone())?;
+    cs.inline_all_lcs();
+    Ok(cs)
+}
+
+/// Writes the constraints of the circuit in the `.r1cs` format. The circuit
+/// doesn't need a value, e.g., `HashToPrimeCircuit::new(&parameters, None)`.
+pub fn write_r1cs<F: PrimeField, C: ConstraintSynthesizer<F>, W: Write>(
+    circuit: C,
+    mut writer: W,
+) -> Result<(), R1CSExportError> {
+    let cs = synthesize(circuit, SynthesisMode::Setup)?;
+    let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+    let wires = matrices.num_instance_variables + matrices.num_witness_variables;
+
+    let mut header = field_header::<F>();
+    header.extend_from_slice(&(wires as u32).to_le_bytes());
+    // public outputs, public inputs and private inputs
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&((matrices.num_instance_variables - 1) as u32).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&(wires as u64).to_le_bytes());
+    header.extend_from_slice(&(matrices.num_constraints as u32).to_le_bytes());
+
+    let mut constraints = vec![];
+    for i in 0..matrices.num_constraints {
+        for matrix in [&matrices.a, &matrices.b, &matrices.c].iter() {
+            constraints.extend_from_slice(&(matrix[i].len() as u32).to_le_bytes());
+            for (coefficient, wire) in &matrix[i] {
+                constraints.extend_from_slice(&(*wire as u32).to_le_bytes());
+                constraints.extend_from_slice(&field_bytes(coefficient));
+            }
+        }
+    }
+
+    // Each wire is its own label.
+    let labels = (0..wires as u64)
+        .flat_map(|wire| wire.to_le_bytes().to_vec())
+        .collect();
+
+    write_file(
+        &mut writer,
+        R1CS_MAGIC,
+        R1CS_VERSION,
+        &[(1, header), (2, constraints), (3, labels)],
+    )
+}
+
+/// Writes the values of the circuit's wires in the `.wtns` format, failing
+/// if they don't satisfy the constraints.
+pub fn write_witness<F: PrimeField, C: ConstraintSynthesizer<F>, W: Write>(
+    circuit: C,
+    mut writer: W,
+) -> Result<(), R1CSExportError> {
+    let cs = synthesize(
+        circuit,
+        SynthesisMode::Prove {
+            construct_matrices: true,
+        },
+    )?;
+    if !cs.is_satisfied()? {
+        return Err(R1CSExportError::Unsatisfied);
+    }
+    let cs = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+    let wires = cs.instance_assignment.len() + cs.witness_assignment.len();
+
+    let mut header = field_header::<F>();
+    header.extend_from_slice(&(wires as u32).to_le_bytes());
+    let values = cs
+        .instance_assignment
+        .iter()
+        .chain(cs.witness_assignment.iter())
+        .flat_map(field_bytes)
+        .collect();
+
+    write_file(
+        &mut writer,
+        WTNS_MAGIC,
+        WTNS_VERSION,
+        &[(1, header), (2, values)],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_r1cs, write_witness, R1CSExportError};
+    use crate::{
+        parameters::Parameters, protocols::hash_to_prime::snark_range::HashToPrimeCircuit,
+        utils::integer_to_bigint_mod_q,
+    };
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rug::Integer;
+    use std::convert::TryInto;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_export() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut r1cs = vec![];
+        write_r1cs(
+            HashToPrimeCircuit::<Bls12_381>::new(&params, None),
+            &mut r1cs,
+        )
+        .unwrap();
+        assert_eq!(&r1cs[..4], b"r1cs");
+        // The header section starts after the file header and the section's
+        // type and size, with the 32-byte modulus of the scalar field.
+        assert_eq!(u32_at(&r1cs, 24), 32);
+        let wires = u32_at(&r1cs, 60);
+        assert_eq!(u32_at(&r1cs, 68), 1);
+        assert!(u32_at(&r1cs, 84) > 0);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let circuit = HashToPrimeCircuit::<Bls12_381>::new(
+            &params,
+            Some(integer_to_bigint_mod_q::<G1Projective>(&value).unwrap()),
+        );
+        let mut wtns = vec![];
+        write_witness(circuit, &mut wtns).unwrap();
+        assert_eq!(&wtns[..4], b"wtns");
+        assert_eq!(u32_at(&wtns, 60), wires);
+        // The values follow the header section and the values section's type
+        // and size, starting with the constant one.
+        let values = &wtns[64 + 12..];
+        assert_eq!(values.len(), 32 * wires as usize);
+        assert_eq!(values[0], 1);
+        assert_eq!(values[1..32], [0u8; 31]);
+
+        let circuit = HashToPrimeCircuit::<Bls12_381>::new(
+            &params,
+            Some(integer_to_bigint_mod_q::<G1Projective>(&Integer::from(12)).unwrap()),
+        );
+        assert!(matches!(
+            write_witness(circuit, &mut Vec::<u8>::new()),
+            Err(R1CSExportError::Unsatisfied)
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+//! Verifies the LegoGroth16 hash-to-prime proof inside a circuit over the
+//! base field of the pairing curve, e.g., a BLS12-377 proof in a circuit over
+//! BW6-761, whose scalar field is the base field of BLS12-377.
+//!
+//! The circuit checks the same equations as `verify_snark` and the Solidity
+//! verifier:
+//! e(a, b) = e(alpha, beta) e(d, gamma) e(c, delta),
+//! e(link_d, c_0) e(d, c_1) = e(link_pi, a_link),
+//! and link_d = c_e_q + link_bases[0], with the verifying key as constants,
+//! the proof as witness and c_e_q as the public input. An aggregation circuit
+//! over BW6-761 can then check many proofs, with the group-of-unknown-order
+//! parts of the membership proofs verified natively.
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar, eq::EqGadget, fields::FieldVar, groups::CurveVar, pairing::PairingVar,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use std::marker::PhantomData;
+
+/// The field the pairing curve is defined over, in which the circuit is.
+pub type ConstraintF<E> = <<E as PairingEngine>::G1Projective as ProjectiveCurve>::BaseField;
+
+/// The proof allocated as witness.
+pub struct ProofVar<E: PairingEngine, P: PairingVar<E, ConstraintF<E>>>
+where
+    ConstraintF<E>: PrimeField,
+{
+    pub a: P::G1Var,
+    pub b: P::G2Var,
+    pub c: P::G1Var,
+    pub d: P::G1Var,
+    pub link_d: P::G1Var,
+    pub link_pi: P::G1Var,
+}
+
+impl<E: PairingEngine, P: PairingVar<E, ConstraintF<E>>> ProofVar<E, P>
+where
+    ConstraintF<E>: PrimeField,
+{
+    /// Allocates the proof, or unassigned variables when only the
+    /// constraints are needed.
+    pub fn new_witness(
+        cs: ConstraintSystemRef<ConstraintF<E>>,
+        proof: Option<&legogro16::Proof<E>>,
+    ) -> Result<ProofVar<E, P>, SynthesisError> {
+        let g1 = |point: Option<E::G1Affine>| {
+            P::G1Var::new_witness(cs.clone(), || {
+                point
+                    .map(|p| p.into_projective())
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        };
+        Ok(ProofVar {
+            a: g1(proof.map(|p| p.a))?,
+            b: P::G2Var::new_witness(cs.clone(), || {
+                proof
+                    .map(|p| p.b.into_projective())
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?,
+            c: g1(proof.map(|p| p.c))?,
+            d: g1(proof.map(|p| p.d))?,
+            link_d: g1(proof.map(|p| p.link_d))?,
+            link_pi: g1(proof.map(|p| p.link_pi))?,
+        })
+    }
+}
+
+/// Enforces that the proof verifies under the verifying key and links to the
+/// commitment. The verifying key has to have two link bases, as the ones of
+/// `snark_range` and `snark_hash` do.
+pub fn enforce_verification<E: PairingEngine, P: PairingVar<E, ConstraintF<E>>>(
+    cs: ConstraintSystemRef<ConstraintF<E>>,
+    vk: &legogro16::VerifyingKey<E>,
+    proof: &ProofVar<E, P>,
+    c_e_q: &P::G1Var,
+) -> Result<(), SynthesisError>
+where
+    ConstraintF<E>: PrimeField,
+{
+    if vk.link_vk.c.len() != 2 || vk.link_bases.is_empty() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let g1 = |point: E::G1Affine| P::G1Var::new_constant(cs.clone(), point.into_projective());
+    let g2 = |point: E::G2Affine| P::G2Var::new_constant(cs.clone(), point.into_projective());
+    let prepared_g1 = |points: &[&P::G1Var]| {
+        points
+            .iter()
+            .map(|p| P::prepare_g1(p))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let prepared_g2 = |points: &[&P::G2Var]| {
+        points
+            .iter()
+            .map(|p| P::prepare_g2(p))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    // e(a, b) e(c, -delta) e(d, -gamma) e(-alpha, beta) = 1
+    let alpha_neg = g1(-vk.alpha_g1)?;
+    let (beta, delta_neg, gamma_neg) = (g2(vk.beta_g2)?, g2(-vk.delta_g2)?, g2(-vk.gamma_g2)?);
+    let product = P::product_of_pairings(
+        &prepared_g1(&[&proof.a, &proof.c, &proof.d, &alpha_neg])?,
+        &prepared_g2(&[&proof.b, &delta_neg, &gamma_neg, &beta])?,
+    )?;
+    product.enforce_equal(&P::GTVar::one())?;
+
+    // e(link_d, c_0) e(d, c_1) e(link_pi, -a_link) = 1
+    let (c_0, c_1, a_neg) = (
+        g2(vk.link_vk.c[0])?,
+        g2(vk.link_vk.c[1])?,
+        g2(-vk.link_vk.a)?,
+    );
+    let link = P::product_of_pairings(
+        &prepared_g1(&[&proof.link_d, &proof.d, &proof.link_pi])?,
+        &prepared_g2(&[&c_0, &c_1, &a_neg])?,
+    )?;
+    link.enforce_equal(&P::GTVar::one())?;
+
+    let expected_link_d = c_e_q.clone() + g1(vk.link_bases[0])?;
+    expected_link_d.enforce_equal(&proof.link_d)
+}
+
+/// Proves that a hash-to-prime proof for the commitment, which is the public
+/// input, verifies under the verifying key.
+pub struct VerifierCircuit<E: PairingEngine, P> {
+    vk: legogro16::VerifyingKey<E>,
+    proof: Option<legogro16::Proof<E>>,
+    c_e_q: Option<E::G1Projective>,
+    _pairing_var: PhantomData<P>,
+}
+
+impl<E: PairingEngine, P> VerifierCircuit<E, P> {
+    /// The circuit for the verifying key, with the proof and commitment or
+    /// none when only the constraints are needed.
+    pub fn new(
+        vk: &legogro16::VerifyingKey<E>,
+        proof_and_commitment: Option<(&legogro16::Proof<E>, &E::G1Projective)>,
+    ) -> VerifierCircuit<E, P> {
+        VerifierCircuit {
+            vk: vk.clone(),
+            proof: proof_and_commitment.map(|(proof, _)| proof.clone()),
+            c_e_q: proof_and_commitment.map(|(_, c_e_q)| *c_e_q),
+            _pairing_var: PhantomData,
+        }
+    }
+}
+
+impl<E: PairingEngine, P: PairingVar<E, ConstraintF<E>>> ConstraintSynthesizer<ConstraintF<E>>
+    for VerifierCircuit<E, P>
+where
+    ConstraintF<E>: PrimeField,
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF<E>>,
+    ) -> Result<(), SynthesisError> {
+        let c_e_q = P::G1Var::new_input(ark_relations::ns!(cs, "c_e_q"), || {
+            self.c_e_q.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let proof = ProofVar::<E, P>::new_witness(cs.clone(), self.proof.as_ref())?;
+        enforce_verification::<E, P>(cs, &self.vk, &proof, &c_e_q)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VerifierCircuit;
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::{
+                snark_range::Protocol as HPProtocol,
+                transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+                HashToPrimeProtocol, Statement, Witness,
+            },
+            membership,
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use ark_bls12_377::{constraints::PairingVar, Bls12_377, G1Projective};
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_verify_in_circuit() {
+        let (params, _) = Parameters::from_curve::<ark_bls12_377::Fr>().unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = membership::Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_377>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs
+        .crs_hash_to_prime;
+        let protocol = HPProtocol::<Bls12_377>::from_crs(&crs);
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(9);
+        let commitment = crs
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let statement = Statement { c_e_q: commitment };
+        let proof_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+        let verification_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        let vk = &crs.hash_to_prime_parameters.vk;
+        let satisfied = |c_e_q: &G1Projective| {
+            let cs = ConstraintSystem::new_ref();
+            VerifierCircuit::<Bls12_377, PairingVar>::new(vk, Some((&proof, c_e_q)))
+                .generate_constraints(cs.clone())
+                .unwrap();
+            cs.is_satisfied().unwrap()
+        };
+        assert!(satisfied(&commitment));
+        let other = crs
+            .pedersen_commitment_parameters
+            .commit(&Integer::from(3), &Integer::from(9))
+            .unwrap();
+        assert!(!satisfied(&other));
+    }
+}
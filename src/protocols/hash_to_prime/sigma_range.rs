@@ -0,0 +1,614 @@
+//! A transparent range proof from sigma protocols, over any prime-order group,
+//! e.g., the Pallas and Vesta curves, with no trusted setup and no pairings.
+//!
+//! The element, whose top bit is set as with the other backends, is written
+//! as its bits b_i, each but the top one committed as C_i = g^{b_i} h^{r_i},
+//! with randomness such that sum 2^i r_i = r_q. The verifier checks that
+//! g^{2^{μ-1}} prod C_i^{2^i} is the commitment c_e_q, and an OR proof for
+//! each C_i shows that it commits to 0 or 1. The OR proofs are made
+//! non-interactive with a Merlin transcript over the statement and share one
+//! challenge, so a proof has μ - 1 points and 3(μ - 1) + 1 scalars: larger
+//! than a Bulletproof, but over any curve implementing `CurvePointProjective`.
+use crate::{
+    commitments::pedersen::PedersenCommitment,
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{
+            channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
+            CRSHashToPrime, CRSHashToPrimeVerifier, CRSSize, HashToPrimeError, HashToPrimeProtocol,
+            Statement, Witness,
+        },
+        point_bytes, CRSError, ProofError, SetupError, VerificationError,
+    },
+    transcript::FiatShamir,
+    utils::{
+        curve::{CurveError, CurvePointProjective, Field},
+        integer_to_bigint_mod_q,
+        serialization::{read_point, read_scalar, write_point, write_scalar},
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::{integer::Order, Integer};
+
+/// The range proof has no parameters besides the Pedersen bases.
+#[derive(Clone, Debug, Default)]
+pub struct SigmaRangeParameters;
+
+impl CRSSize for SigmaRangeParameters {
+    fn crs_size(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+impl CanonicalSerialize for SigmaRangeParameters {
+    fn serialize<W: Write>(&self, _: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        0
+    }
+}
+
+impl CanonicalDeserialize for SigmaRangeParameters {
+    fn deserialize<R: Read>(_: R) -> Result<Self, SerializationError> {
+        Ok(SigmaRangeParameters)
+    }
+}
+
+#[derive(Clone)]
+pub struct SigmaRangeProof<P: CurvePointProjective> {
+    /// The commitments to the bits, without the top one.
+    pub bit_commitments: Vec<P>,
+    pub challenge: P::ScalarField,
+    /// For each bit, the challenge of the branch for 0 and the responses of
+    /// the branches for 0 and 1. The challenge of the branch for 1 is the
+    /// rest of the shared challenge.
+    pub responses: Vec<(P::ScalarField, P::ScalarField, P::ScalarField)>,
+}
+
+pub struct Protocol<P: CurvePointProjective> {
+    pub crs: CRSHashToPrime<P, Self>,
+}
+
+fn scalar<P: CurvePointProjective>(value: u64) -> P::ScalarField {
+    P::ScalarField::from_integer(&Integer::from(value)).unwrap()
+}
+
+/// 2^0, ..., 2^{count - 1} in the scalar field.
+fn powers_of_two<P: CurvePointProjective>(count: usize) -> Vec<P::ScalarField> {
+    let two = scalar::<P>(2);
+    let mut powers = Vec::with_capacity(count);
+    let mut power = scalar::<P>(1);
+    for _ in 0..count {
+        powers.push(power.clone());
+        power = power.mul(&two);
+    }
+    powers
+}
+
+fn challenge<P: CurvePointProjective>(
+    parameters: &Parameters,
+    pedersen_commitment_parameters: &PedersenCommitment<P>,
+    statement: &Statement<P>,
+    bit_commitments: &[P],
+    announcements: &[(P, P)],
+) -> Result<P::ScalarField, CurveError> {
+    // Through `FiatShamir`, rather than Merlin's own methods, so that the
+    // absorptions are traced like those of the channels' transcripts.
+    let mut transcript = Transcript::new(b"sigma_range");
+    let transcript: &mut dyn FiatShamir = &mut transcript;
+    transcript.append_message(b"bits", &parameters.hash_to_prime_bits.to_be_bytes());
+    transcript.append_message(b"g", &pedersen_commitment_parameters.g.to_affine_bytes()?);
+    transcript.append_message(b"h", &pedersen_commitment_parameters.h.to_affine_bytes()?);
+    transcript.append_message(b"c_e_q", &statement.c_e_q.to_affine_bytes()?);
+    for (bit_commitment, (a_0, a_1)) in bit_commitments.iter().zip(announcements) {
+        transcript.append_message(b"c_i", &bit_commitment.to_affine_bytes()?);
+        transcript.append_message(b"a_0", &a_0.to_affine_bytes()?);
+        transcript.append_message(b"a_1", &a_1.to_affine_bytes()?);
+    }
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(b"c", &mut bytes);
+    integer_to_bigint_mod_q::<P>(&Integer::from_digits(&bytes, Order::Msf))
+        .map_err(|_| CurveError::CannotRead)
+}
+
+fn verify_range<P: CurvePointProjective, C: HashToPrimeProverChannel<P, Protocol<P>>>(
+    parameters: &Parameters,
+    pedersen_commitment_parameters: &PedersenCommitment<P>,
+    prover_channel: &mut C,
+    statement: &Statement<P>,
+) -> Result<(), VerificationError> {
+    statement.validate()?;
+    let proof = prover_channel.receive_proof()?;
+    let bits = parameters.hash_to_prime_bits as usize;
+    if bits < 2 || proof.bit_commitments.len() != bits - 1 || proof.responses.len() != bits - 1 {
+        return Err(VerificationError::HashToPrimeProofInvalid);
+    }
+    let (g, h) = (
+        &pedersen_commitment_parameters.g,
+        &pedersen_commitment_parameters.h,
+    );
+
+    let powers = powers_of_two::<P>(bits);
+    let mut bases = proof.bit_commitments.clone();
+    bases.push(g.clone());
+    let committed = P::msm(&bases, &powers);
+    if committed != statement.c_e_q {
+        return Err(VerificationError::HashToPrimeLinkMismatch {
+            expected: point_bytes(&statement.c_e_q),
+            got: point_bytes(&committed),
+        });
+    }
+
+    let neg_g = g.mul(&scalar::<P>(1).neg());
+    let announcements = proof
+        .bit_commitments
+        .iter()
+        .zip(&proof.responses)
+        .map(|(bit_commitment, (c_0, z_0, z_1))| {
+            let c_1 = proof.challenge.sub(c_0);
+            let a_0 = P::msm(
+                &[h.clone(), bit_commitment.clone()],
+                &[z_0.clone(), c_0.neg()],
+            );
+            let a_1 = P::msm(
+                &[h.clone(), bit_commitment.add(&neg_g)],
+                &[z_1.clone(), c_1.neg()],
+            );
+            (a_0, a_1)
+        })
+        .collect::<Vec<_>>();
+    let expected = challenge(
+        parameters,
+        pedersen_commitment_parameters,
+        statement,
+        &proof.bit_commitments,
+        &announcements,
+    )
+    .map_err(|_| VerificationError::HashToPrimeProofInvalid)?;
+    if expected != proof.challenge {
+        return Err(VerificationError::HashToPrimeProofInvalid);
+    }
+    Ok(())
+}
+
+fn write_proof<P: CurvePointProjective, W: Write>(
+    writer: &mut W,
+    proof: &SigmaRangeProof<P>,
+    compressed: bool,
+) -> Result<(), SerializationError> {
+    writer.write_all(&(proof.bit_commitments.len() as u32).to_be_bytes())?;
+    for bit_commitment in &proof.bit_commitments {
+        write_point(writer, bit_commitment, compressed)?;
+    }
+    write_scalar::<P, _>(writer, &proof.challenge)?;
+    for (c_0, z_0, z_1) in &proof.responses {
+        write_scalar::<P, _>(writer, c_0)?;
+        write_scalar::<P, _>(writer, z_0)?;
+        write_scalar::<P, _>(writer, z_1)?;
+    }
+    Ok(())
+}
+
+fn read_proof<P: CurvePointProjective, R: Read>(
+    reader: &mut R,
+    compressed: bool,
+) -> Result<SigmaRangeProof<P>, SerializationError> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length >= P::ScalarField::size_in_bits() {
+        return Err(SerializationError::InvalidData);
+    }
+    let bit_commitments = (0..length)
+        .map(|_| read_point(reader, compressed))
+        .collect::<Result<Vec<_>, _>>()?;
+    let challenge = read_scalar::<P, _>(reader)?;
+    let responses = (0..length)
+        .map(|_| {
+            Ok((
+                read_scalar::<P, _>(reader)?,
+                read_scalar::<P, _>(reader)?,
+                read_scalar::<P, _>(reader)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, SerializationError>>()?;
+    Ok(SigmaRangeProof {
+        bit_commitments,
+        challenge,
+        responses,
+    })
+}
+
+impl<P: CurvePointProjective> HashToPrimeProtocol<P> for Protocol<P> {
+    type Proof = SigmaRangeProof<P>;
+    type Parameters = SigmaRangeParameters;
+    type VerifyingKey = SigmaRangeParameters;
+
+    fn from_crs(crs: &CRSHashToPrime<P, Self>) -> Protocol<P> {
+        Protocol { crs: crs.clone() }
+    }
+
+    /// There's nothing to generate, but the elements have to fit in the
+    /// scalar field, as with the SNARK backends.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "sigma_range::setup", level = "debug", skip_all)
+    )]
+    fn setup<R: RngCore + CryptoRng>(
+        _: &mut R,
+        _: &PedersenCommitment<P>,
+        parameters: &Parameters,
+    ) -> Result<Self::Parameters, SetupError> {
+        let bits = parameters.hash_to_prime_bits as usize;
+        if bits < 2 || bits >= P::ScalarField::size_in_bits() {
+            return Err(SetupError::CouldNotPerformSetup);
+        }
+        Ok(SigmaRangeParameters)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "sigma_range::prove", level = "debug", skip_all)
+    )]
+    fn prove<R: RngCore + CryptoRng, C: HashToPrimeVerifierChannel<P, Self>>(
+        &self,
+        verifier_channel: &mut C,
+        rng: &mut R,
+        statement: &Statement<P>,
+        witness: &Witness,
+    ) -> Result<(), ProofError> {
+        let bits = self.crs.parameters.hash_to_prime_bits as usize;
+        if bits < 2 || witness.e.significant_bits() as usize != bits {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+        let (g, h) = (
+            &self.crs.pedersen_commitment_parameters.g,
+            &self.crs.pedersen_commitment_parameters.h,
+        );
+        let (zero, one) = (scalar::<P>(0), scalar::<P>(1));
+        let neg_g = g.mul(&one.neg());
+
+        // The randomness of the last committed bit makes the sum r_q.
+        let powers = powers_of_two::<P>(bits - 1);
+        let mut randomness = (0..bits - 2)
+            .map(|_| P::ScalarField::rand(rng))
+            .collect::<Vec<_>>();
+        let partial = randomness
+            .iter()
+            .zip(&powers)
+            .fold(zero.clone(), |sum, (r_i, power)| sum.add(&r_i.mul(power)));
+        let r_q = integer_to_bigint_mod_q::<P>(&witness.r_q)?;
+        let last_power_inverse = powers[bits - 2]
+            .inverse()
+            .ok_or(ProofError::CouldNotCreateProof)?;
+        randomness.push(r_q.sub(&partial).mul(&last_power_inverse));
+
+        let mut bit_commitments = Vec::with_capacity(bits - 1);
+        let mut announcements = Vec::with_capacity(bits - 1);
+        // The nonce of the real branch and the challenge and response of the
+        // simulated one.
+        let mut branches = Vec::with_capacity(bits - 1);
+        for (i, r_i) in randomness.iter().enumerate() {
+            let bit = witness.e.get_bit(i as u32);
+            let bit_commitment = P::msm(
+                &[g.clone(), h.clone()],
+                &[if bit { one.clone() } else { zero.clone() }, r_i.clone()],
+            );
+            let nonce = P::ScalarField::rand(rng);
+            let simulated_challenge = P::ScalarField::rand(rng);
+            let simulated_response = P::ScalarField::rand(rng);
+            let real = h.mul(&nonce);
+            // The simulated branch is the commitment for 1 if the bit is 0
+            // and the other way around.
+            let simulated_base = if bit {
+                bit_commitment.clone()
+            } else {
+                bit_commitment.add(&neg_g)
+            };
+            let simulated = P::msm(
+                &[h.clone(), simulated_base],
+                &[simulated_response.clone(), simulated_challenge.neg()],
+            );
+            announcements.push(if bit {
+                (simulated, real)
+            } else {
+                (real, simulated)
+            });
+            bit_commitments.push(bit_commitment);
+            branches.push((nonce, simulated_challenge, simulated_response));
+        }
+
+        let c = challenge(
+            &self.crs.parameters,
+            &self.crs.pedersen_commitment_parameters,
+            statement,
+            &bit_commitments,
+            &announcements,
+        )
+        .map_err(|_| ProofError::CouldNotCreateProof)?;
+        let responses = branches
+            .into_iter()
+            .zip(&randomness)
+            .enumerate()
+            .map(|(i, ((nonce, c_s, z_s), r_i))| {
+                let c_real = c.sub(&c_s);
+                let z_real = nonce.add(&c_real.mul(r_i));
+                if witness.e.get_bit(i as u32) {
+                    (c_s, z_s, z_real)
+                } else {
+                    (c_real, z_real, z_s)
+                }
+            })
+            .collect();
+
+        verifier_channel.send_proof(&SigmaRangeProof {
+            bit_commitments,
+            challenge: c,
+            responses,
+        })?;
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "sigma_range::verify", level = "debug", skip_all)
+    )]
+    fn verify<C: HashToPrimeProverChannel<P, Self>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<P>,
+    ) -> Result<(), VerificationError> {
+        verify_range(
+            &self.crs.parameters,
+            &self.crs.pedersen_commitment_parameters,
+            prover_channel,
+            statement,
+        )
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "sigma_range::verify", level = "debug", skip_all)
+    )]
+    fn verify_with_key<C: HashToPrimeProverChannel<P, Self>>(
+        crs: &CRSHashToPrimeVerifier<P, Self>,
+        prover_channel: &mut C,
+        statement: &Statement<P>,
+    ) -> Result<(), VerificationError> {
+        verify_range(
+            &crs.parameters,
+            &crs.pedersen_commitment_parameters,
+            prover_channel,
+            statement,
+        )
+    }
+
+    fn verifying_key(parameters: &Self::Parameters) -> Self::VerifyingKey {
+        parameters.clone()
+    }
+
+    fn verifying_key_bytes(_: &Self::VerifyingKey) -> Vec<u8> {
+        vec![]
+    }
+
+    /// The Pedersen bases are checked by `CRSHashToPrime::validate`.
+    fn validate_parameters<R: RngCore + CryptoRng>(
+        _: &CRSHashToPrime<P, Self>,
+        _: &mut R,
+    ) -> Result<(), CRSError> {
+        Ok(())
+    }
+
+    fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
+        Ok((e.clone(), 0))
+    }
+
+    #[cfg(feature = "simulator")]
+    fn simulate_proof<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        _: &Statement<P>,
+    ) -> Option<Self::Proof> {
+        let length = self.crs.parameters.hash_to_prime_bits as usize - 1;
+        Some(SigmaRangeProof {
+            bit_commitments: (0..length).map(|_| P::rand(rng)).collect(),
+            challenge: P::ScalarField::rand(rng),
+            responses: (0..length)
+                .map(|_| {
+                    (
+                        P::ScalarField::rand(rng),
+                        P::ScalarField::rand(rng),
+                        P::ScalarField::rand(rng),
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        let mut bytes = vec![];
+        write_proof(&mut bytes, proof, true).map_err(|_| CurveError::CannotWrite)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes(mut bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        read_proof(&mut bytes, true).map_err(|_| CurveError::CannotRead)
+    }
+
+    fn proof_to_bytes_uncompressed(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        let mut bytes = vec![];
+        write_proof(&mut bytes, proof, false).map_err(|_| CurveError::CannotWrite)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes_uncompressed(mut bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        read_proof(&mut bytes, false).map_err(|_| CurveError::CannotRead)
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{Protocol, SigmaRangeProof};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::{
+                transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+                HashToPrimeProtocol, Statement, Witness,
+            },
+            membership,
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_ec::ProjectiveCurve;
+    use ark_pallas::Projective as Pallas;
+    use ark_vesta::Projective as Vesta;
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_proof() {
+        let (params, _) = Parameters::from_curve::<ark_pallas::Fr>().unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = membership::Protocol::<Rsa2048, Pallas, Protocol<Pallas>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs
+        .crs_hash_to_prime;
+        let protocol = Protocol::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(9);
+        let commitment = crs
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let statement = Statement { c_e_q: commitment };
+        let proof_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value.clone(),
+                    r_q: randomness.clone(),
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verify = |proof: &SigmaRangeProof<Pallas>, statement: &Statement<Pallas>| {
+            let verification_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+            let mut prover_channel =
+                TranscriptProverChannel::new(&crs, &verification_transcript, proof);
+            protocol.verify(&mut prover_channel, statement)
+        };
+        verify(&proof, &statement).unwrap();
+
+        let bytes = Protocol::<Pallas>::proof_to_bytes(&proof).unwrap();
+        let read = Protocol::<Pallas>::proof_from_bytes(&bytes).unwrap();
+        verify(&read, &statement).unwrap();
+
+        let mut tampered = proof.clone();
+        tampered.responses.swap(0, 1);
+        assert!(verify(&tampered, &statement).is_err());
+        let other = Statement {
+            c_e_q: crs
+                .pedersen_commitment_parameters
+                .commit(&value, &Integer::from(10))
+                .unwrap(),
+        };
+        assert!(verify(&proof, &other).is_err());
+
+        // Elements without their top bit set aren't proven.
+        let small = Integer::from(245);
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        assert!(protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng2,
+                &Statement {
+                    c_e_q: crs
+                        .pedersen_commitment_parameters
+                        .commit(&small, &randomness)
+                        .unwrap(),
+                },
+                &Witness {
+                    e: small,
+                    r_q: randomness,
+                },
+            )
+            .is_err());
+    }
+
+    fn membership_over_curve<P: ProjectiveCurve>(params: &Parameters) {
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol =
+            membership::Protocol::<Rsa2048, P, Protocol<P>>::setup(params, &mut rng1, &mut rng2)
+                .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = membership::Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &membership::Witness {
+                    e: value,
+                    r_q: randomness,
+                    w: accum.1.witness.0.value,
+                },
+                b"context",
+            )
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_membership_vesta() {
+        let (params, _) = Parameters::from_curve::<ark_vesta::Fr>().unwrap();
+        membership_over_curve::<Vesta>(&params);
+    }
+
+    #[test]
+    fn test_membership_secp256k1() {
+        let (params, _) = Parameters::from_curve::<ark_secp256k1::Fr>().unwrap();
+        membership_over_curve::<ark_secp256k1::Projective>(&params);
+    }
+}
@@ -1,4 +1,9 @@
 //! LegoGroth16-based hash-to-prime proof, with Blake2s as the hash.
+//!
+//! The witness isn't streamed: the constraint system of ark-relations keeps
+//! the whole assignment in memory during synthesis, and legogro16 reads it
+//! all when proving. Only the search for the prime index outside the circuit
+//! runs in constant memory.
 
 use crate::{
     commitments::pedersen::PedersenCommitment,
@@ -6,16 +11,14 @@ use crate::{
     protocols::{
         hash_to_prime::{
             channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
-            CRSHashToPrime, HashToPrimeError, HashToPrimeProtocol, Statement, Witness,
+            CRSHashToPrime, CRSHashToPrimeVerifier, HashToPrimeError, HashToPrimeProtocol,
+            Statement, Witness,
         },
-        ProofError, SetupError, VerificationError,
-    },
-    utils::{
-        bigint_to_integer, bits_big_endian_to_bytes_big_endian,
-        bytes_big_endian_to_bits_big_endian, integer_to_bigint_mod_q, log2,
+        CRSError, ProofError, SetupError, VerificationError,
     },
+    utils::{curve::CurveError, integer_to_bigint_mod_q, log2},
 };
-use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ec::{PairingEngine, ProjectiveCurve};
 use ark_ff::{BigInteger, One, PrimeField, UniformRand};
 
 use ark_crypto_primitives::prf::blake2s::constraints::evaluate_blake2s;
@@ -28,10 +31,11 @@ use ark_r1cs_std::{
     Assignment, R1CSVar,
 };
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use blake2::{Blake2s, Digest};
-use rand::Rng;
-use rug::{integer::IsPrime, Integer};
-use std::ops::{Neg, Sub};
+use core::ops::Neg;
+use rand::{CryptoRng, Rng};
+use rug::{integer::IsPrime, Assign, Integer};
 
 pub trait HashToPrimeHashParameters {
     const MESSAGE_SIZE: u16;
@@ -46,7 +50,26 @@ pub struct HashToPrimeHashCircuit<E: PairingEngine, P: HashToPrimeHashParameters
     required_bit_size: u16,
     value: Option<E::Fr>,
     index: Option<u64>,
-    parameters_type: std::marker::PhantomData<P>,
+    parameters_type: core::marker::PhantomData<P>,
+}
+
+impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeHashCircuit<E, P> {
+    /// The circuit for the parameters, with the value to hash and the index
+    /// that makes its hash prime, or none when only the constraints are
+    /// needed.
+    pub fn new(
+        parameters: &Parameters,
+        value: Option<E::Fr>,
+        index: Option<u64>,
+    ) -> HashToPrimeHashCircuit<E, P> {
+        HashToPrimeHashCircuit {
+            security_level: parameters.security_level,
+            required_bit_size: parameters.hash_to_prime_bits,
+            value,
+            index,
+            parameters_type: core::marker::PhantomData,
+        }
+    }
 }
 
 impl<E: PairingEngine, P: HashToPrimeHashParameters> ConstraintSynthesizer<E::Fr>
@@ -147,7 +170,9 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> ConstraintSynthesizer<E::Fr
 
 pub struct Protocol<E: PairingEngine, P: HashToPrimeHashParameters> {
     pub crs: CRSHashToPrime<E::G1Projective, Self>,
-    parameters_type: std::marker::PhantomData<P>,
+    /// Prepared once, as it's the same for every proof.
+    pvk: legogro16::PreparedVerifyingKey<E>,
+    parameters_type: core::marker::PhantomData<P>,
 }
 
 impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Projective>
@@ -155,26 +180,27 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Pr
 {
     type Proof = legogro16::Proof<E>;
     type Parameters = legogro16::ProvingKey<E>;
+    type VerifyingKey = legogro16::VerifyingKey<E>;
 
     fn from_crs(crs: &CRSHashToPrime<E::G1Projective, Self>) -> Protocol<E, P> {
         Protocol {
             crs: (*crs).clone(),
-            parameters_type: std::marker::PhantomData,
+            pvk: legogro16::prepare_verifying_key(&crs.hash_to_prime_parameters.vk),
+            parameters_type: core::marker::PhantomData,
         }
     }
 
-    fn setup<R: Rng>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_hash::setup", level = "debug", skip_all)
+    )]
+    fn setup<R: Rng + CryptoRng>(
         rng: &mut R,
         pedersen_commitment_parameters: &PedersenCommitment<E::G1Projective>,
         parameters: &Parameters,
     ) -> Result<Self::Parameters, SetupError> {
-        let c = HashToPrimeHashCircuit::<E, P> {
-            security_level: parameters.security_level,
-            required_bit_size: parameters.hash_to_prime_bits,
-            value: None,
-            index: None,
-            parameters_type: std::marker::PhantomData,
-        };
+        super::check_field_size::<E::Fr>(parameters)?;
+        let c = HashToPrimeHashCircuit::<E, P>::new(parameters, None, None);
         let base_one = E::G1Projective::rand(rng);
         let pedersen_bases = vec![
             base_one,
@@ -191,7 +217,11 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Pr
         )?)
     }
 
-    fn prove<R: Rng, C: HashToPrimeVerifierChannel<E::G1Projective, Self>>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_hash::prove", level = "debug", skip_all)
+    )]
+    fn prove<R: Rng + CryptoRng, C: HashToPrimeVerifierChannel<E::G1Projective, Self>>(
         &self,
         verifier_channel: &mut C,
         rng: &mut R,
@@ -199,17 +229,15 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Pr
         witness: &Witness,
     ) -> Result<(), ProofError> {
         let (_, index) = self.hash_to_prime(&witness.e)?;
-        let c = HashToPrimeHashCircuit::<E, P> {
-            security_level: self.crs.parameters.security_level,
-            required_bit_size: self.crs.parameters.hash_to_prime_bits,
-            value: Some(integer_to_bigint_mod_q::<E::G1Projective>(
+        let c = HashToPrimeHashCircuit::<E, P>::new(
+            &self.crs.parameters,
+            Some(integer_to_bigint_mod_q::<E::G1Projective>(
                 &witness.e.clone(),
             )?),
-            index: Some(index),
-            parameters_type: std::marker::PhantomData,
-        };
+            Some(index),
+        );
         let v = E::Fr::rand(rng);
-        let link_v = integer_to_bigint_mod_q::<E::G1Projective>(&witness.r_q.clone())?;
+        let link_v = integer_to_bigint_mod_q::<E::G1Projective>(&witness.r_q)?;
         let proof = legogro16::create_random_proof::<E, _, _>(
             c,
             v,
@@ -221,25 +249,76 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Pr
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_hash::verify", level = "debug", skip_all)
+    )]
     fn verify<C: HashToPrimeProverChannel<E::G1Projective, Self>>(
         &self,
         prover_channel: &mut C,
         statement: &Statement<E::G1Projective>,
     ) -> Result<(), VerificationError> {
-        let proof = prover_channel.receive_proof()?;
-        let pvk = legogro16::prepare_verifying_key(&self.crs.hash_to_prime_parameters.vk);
-        if !legogro16::verify_proof(&pvk, &proof)? {
-            return Err(VerificationError::VerificationFailed);
-        }
-        let proof_link_d_without_one = proof
-            .link_d
-            .into_projective()
-            .sub(&self.crs.hash_to_prime_parameters.vk.link_bases[0].into_projective());
-        if statement.c_e_q != proof_link_d_without_one {
-            return Err(VerificationError::VerificationFailed);
-        }
+        super::verify_snark(
+            &self.pvk,
+            &self.crs.hash_to_prime_parameters.vk,
+            prover_channel,
+            statement,
+        )
+    }
 
-        Ok(())
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_hash::verify", level = "debug", skip_all)
+    )]
+    fn verify_with_key<C: HashToPrimeProverChannel<E::G1Projective, Self>>(
+        crs: &CRSHashToPrimeVerifier<E::G1Projective, Self>,
+        prover_channel: &mut C,
+        statement: &Statement<E::G1Projective>,
+    ) -> Result<(), VerificationError> {
+        let pvk = legogro16::prepare_verifying_key(&crs.verifying_key);
+        super::verify_snark(&pvk, &crs.verifying_key, prover_channel, statement)
+    }
+
+    fn verifying_key(parameters: &Self::Parameters) -> Self::VerifyingKey {
+        parameters.vk.clone()
+    }
+
+    fn verifying_key_bytes(verifying_key: &Self::VerifyingKey) -> Vec<u8> {
+        let mut bytes = vec![];
+        verifying_key.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn validate_parameters<R: Rng + CryptoRng>(
+        crs: &CRSHashToPrime<E::G1Projective, Self>,
+        rng: &mut R,
+    ) -> Result<(), CRSError> {
+        super::validate_snark_key(
+            &crs.hash_to_prime_parameters,
+            &crs.pedersen_commitment_parameters,
+            rng,
+        )
+    }
+
+    fn num_constraints(&self) -> Option<usize> {
+        super::count_constraints(HashToPrimeHashCircuit::<E, P>::new(
+            &self.crs.parameters,
+            None,
+            None,
+        ))
+    }
+
+    #[cfg(feature = "simulator")]
+    fn simulate_proof<R: Rng + CryptoRng>(
+        &self,
+        rng: &mut R,
+        statement: &Statement<E::G1Projective>,
+    ) -> Option<Self::Proof> {
+        Some(super::simulate_snark(
+            rng,
+            &self.crs.hash_to_prime_parameters.vk,
+            statement,
+        ))
     }
 
     fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
@@ -261,43 +340,40 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Pr
             ]
             .concat();
         }
+        // The bits to hash are laid out as in the circuit: the padding to a
+        // whole number of bytes, the index bits and the value bits. Only the
+        // index bits change between candidates, so the buffers are reused and
+        // the search doesn't allocate per candidate.
+        let padding_length = (8 - (index_bit_length as usize + value_bits.len()) % 8) % 8;
+        let index_start = padding_length;
+        let mut bits_to_hash = vec![false; padding_length + index_bit_length as usize];
+        bits_to_hash.extend_from_slice(&value_bits);
+        let mut bytes_to_hash = vec![0u8; bits_to_hash.len() / 8];
+        let prime_bits = self.crs.parameters.hash_to_prime_bits as u32;
+        let mut integer = Integer::new();
         for index in 0..1 << index_bit_length {
-            let mut index_bits = vec![];
             for i in 0..index_bit_length {
-                let mask = 1u64 << i;
-                let bit = mask & index == mask;
-                index_bits.push(bit);
+                bits_to_hash[index_start + i as usize] = (index >> i) & 1 == 1;
             }
-            let bits_to_hash = [index_bits.as_slice(), &value_bits].concat();
-            let bits_to_hash_padded = if bits_to_hash.len() % 8 != 0 {
-                let padding_length = 8 - bits_to_hash.len() % 8;
-                [&vec![false; padding_length][..], bits_to_hash.as_slice()].concat()
-            } else {
-                bits_to_hash
-            };
-            let bits_big_endian = bits_to_hash_padded.into_iter().rev().collect::<Vec<_>>();
-            let bytes_to_hash = bits_big_endian_to_bytes_big_endian(&bits_big_endian)
-                .into_iter()
-                .rev()
-                .collect::<Vec<_>>();
-            let mut hasher = Blake2s::default();
-            hasher.update(&bytes_to_hash);
-            let hash = hasher.finalize();
-            let hash_big_endian = hash.into_iter().rev().collect::<Vec<_>>();
-            let hash_bits = [
-                vec![true].as_slice(),
-                bytes_big_endian_to_bits_big_endian(&hash_big_endian)
-                    .into_iter()
-                    .rev()
-                    .take(self.crs.parameters.hash_to_prime_bits as usize - 1)
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ]
-            .concat();
+            // Byte j packs bits 8j to 8j+7, the first as the least
+            // significant, as evaluate_blake2s reads them.
+            for (byte, bits) in bytes_to_hash.iter_mut().zip(bits_to_hash.chunks(8)) {
+                *byte = bits
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (k, bit)| byte | ((*bit as u8) << k));
+            }
+            let hash = Blake2s::digest(&bytes_to_hash);
 
-            let element =
-                E::Fr::from_repr(<E::Fr as PrimeField>::BigInt::from_bits_be(&hash_bits)).unwrap();
-            let integer = bigint_to_integer::<E::G1Projective>(&element);
+            // The candidate is 1 followed by the first bits of the hash, read
+            // from the least significant bit of its first byte.
+            integer.assign(0);
+            integer.set_bit(prime_bits - 1, true);
+            for k in 0..prime_bits.min(257) - 1 {
+                if (hash[k as usize / 8] >> (k % 8)) & 1 == 1 {
+                    integer.set_bit(prime_bits - 2 - k, true);
+                }
+            }
             // from the gmp documentation: "A composite number will be identified as a prime with an asymptotic probability of less than 4^(-reps)", so we choose reps = security_level/2
             let is_prime = integer.is_probably_prime(self.crs.parameters.security_level as u32 / 2);
             if is_prime == IsPrime::No {
@@ -309,6 +385,34 @@ impl<E: PairingEngine, P: HashToPrimeHashParameters> HashToPrimeProtocol<E::G1Pr
 
         Err(HashToPrimeError::CouldNotFindIndex)
     }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        legogro16::Proof::<E>::deserialize(bytes).map_err(|_| CurveError::CannotRead)
+    }
+
+    fn proof_to_bytes_uncompressed(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        let mut bytes = vec![];
+        proof.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes_uncompressed(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        legogro16::Proof::<E>::deserialize_uncompressed(bytes).map_err(|_| CurveError::CannotRead)
+    }
+
+    fn proof_size(proof: &Self::Proof, compressed: bool) -> usize {
+        if compressed {
+            proof.serialized_size()
+        } else {
+            proof.uncompressed_size()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -363,7 +467,7 @@ mod test {
             required_bit_size: crs.parameters.hash_to_prime_bits,
             value: Some(integer_to_bigint_mod_q::<G1Projective>(&value).unwrap()),
             index: Some(index),
-            parameters_type: std::marker::PhantomData,
+            parameters_type: core::marker::PhantomData,
         };
         c.generate_constraints(cs.clone()).unwrap();
         if !cs.is_satisfied().unwrap() {
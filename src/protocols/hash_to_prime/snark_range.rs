@@ -6,13 +6,14 @@ use crate::{
     protocols::{
         hash_to_prime::{
             channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
-            CRSHashToPrime, HashToPrimeError, HashToPrimeProtocol, Statement, Witness,
+            CRSHashToPrime, CRSHashToPrimeVerifier, HashToPrimeError, HashToPrimeProtocol,
+            Statement, Witness,
         },
-        ProofError, SetupError, VerificationError,
+        CRSError, ProofError, SetupError, VerificationError,
     },
-    utils::integer_to_bigint_mod_q,
+    utils::{curve::CurveError, integer_to_bigint_mod_q},
 };
-use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ec::{PairingEngine, ProjectiveCurve};
 use ark_ff::{PrimeField, UniformRand};
 use ark_r1cs_std::{
     alloc::{AllocVar, AllocationMode},
@@ -23,15 +24,26 @@ use ark_r1cs_std::{
     Assignment,
 };
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use rand::Rng;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{CryptoRng, Rng};
 use rug::Integer;
-use std::ops::Sub;
 
 pub struct HashToPrimeCircuit<E: PairingEngine> {
     required_bit_size: u16,
     value: Option<E::Fr>,
 }
 
+impl<E: PairingEngine> HashToPrimeCircuit<E> {
+    /// The circuit for the parameters, with the value to prove in range or
+    /// none when only the constraints are needed.
+    pub fn new(parameters: &Parameters, value: Option<E::Fr>) -> HashToPrimeCircuit<E> {
+        HashToPrimeCircuit {
+            required_bit_size: parameters.hash_to_prime_bits,
+            value,
+        }
+    }
+}
+
 impl<E: PairingEngine> ConstraintSynthesizer<E::Fr> for HashToPrimeCircuit<E> {
     fn generate_constraints(self, cs: ConstraintSystemRef<E::Fr>) -> Result<(), SynthesisError> {
         let f = FpVar::new_variable(
@@ -54,27 +66,33 @@ impl<E: PairingEngine> ConstraintSynthesizer<E::Fr> for HashToPrimeCircuit<E> {
 
 pub struct Protocol<E: PairingEngine> {
     pub crs: CRSHashToPrime<E::G1Projective, Self>,
+    /// Prepared once, as it's the same for every proof.
+    pvk: legogro16::PreparedVerifyingKey<E>,
 }
 
 impl<E: PairingEngine> HashToPrimeProtocol<E::G1Projective> for Protocol<E> {
     type Proof = legogro16::Proof<E>;
     type Parameters = legogro16::ProvingKey<E>;
+    type VerifyingKey = legogro16::VerifyingKey<E>;
 
     fn from_crs(crs: &CRSHashToPrime<E::G1Projective, Self>) -> Protocol<E> {
         Protocol {
             crs: (*crs).clone(),
+            pvk: legogro16::prepare_verifying_key(&crs.hash_to_prime_parameters.vk),
         }
     }
 
-    fn setup<R: Rng>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_range::setup", level = "debug", skip_all)
+    )]
+    fn setup<R: Rng + CryptoRng>(
         rng: &mut R,
         pedersen_commitment_parameters: &PedersenCommitment<E::G1Projective>,
         parameters: &Parameters,
     ) -> Result<Self::Parameters, SetupError> {
-        let c = HashToPrimeCircuit::<E> {
-            required_bit_size: parameters.hash_to_prime_bits,
-            value: None,
-        };
+        super::check_field_size::<E::Fr>(parameters)?;
+        let c = HashToPrimeCircuit::<E>::new(parameters, None);
         let base_one = E::G1Projective::rand(rng);
         let pedersen_bases = vec![
             base_one,
@@ -91,21 +109,25 @@ impl<E: PairingEngine> HashToPrimeProtocol<E::G1Projective> for Protocol<E> {
         )?)
     }
 
-    fn prove<R: Rng, C: HashToPrimeVerifierChannel<E::G1Projective, Self>>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_range::prove", level = "debug", skip_all)
+    )]
+    fn prove<R: Rng + CryptoRng, C: HashToPrimeVerifierChannel<E::G1Projective, Self>>(
         &self,
         verifier_channel: &mut C,
         rng: &mut R,
         _: &Statement<E::G1Projective>,
         witness: &Witness,
     ) -> Result<(), ProofError> {
-        let c = HashToPrimeCircuit::<E> {
-            required_bit_size: self.crs.parameters.hash_to_prime_bits,
-            value: Some(integer_to_bigint_mod_q::<E::G1Projective>(
+        let c = HashToPrimeCircuit::<E>::new(
+            &self.crs.parameters,
+            Some(integer_to_bigint_mod_q::<E::G1Projective>(
                 &witness.e.clone(),
             )?),
-        };
+        );
         let v = E::Fr::rand(rng);
-        let link_v = integer_to_bigint_mod_q::<E::G1Projective>(&witness.r_q.clone())?;
+        let link_v = integer_to_bigint_mod_q::<E::G1Projective>(&witness.r_q)?;
         let proof = legogro16::create_random_proof::<E, _, _>(
             c,
             v,
@@ -117,47 +139,127 @@ impl<E: PairingEngine> HashToPrimeProtocol<E::G1Projective> for Protocol<E> {
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_range::verify", level = "debug", skip_all)
+    )]
     fn verify<C: HashToPrimeProverChannel<E::G1Projective, Self>>(
         &self,
         prover_channel: &mut C,
         statement: &Statement<E::G1Projective>,
     ) -> Result<(), VerificationError> {
-        let proof = prover_channel.receive_proof()?;
-        let pvk = legogro16::prepare_verifying_key(&self.crs.hash_to_prime_parameters.vk);
-        if !legogro16::verify_proof(&pvk, &proof)? {
-            return Err(VerificationError::VerificationFailed);
-        }
-        let proof_link_d_without_one = proof
-            .link_d
-            .into_projective()
-            .sub(&self.crs.hash_to_prime_parameters.vk.link_bases[0].into_projective());
-        if statement.c_e_q != proof_link_d_without_one {
-            return Err(VerificationError::VerificationFailed);
-        }
+        super::verify_snark(
+            &self.pvk,
+            &self.crs.hash_to_prime_parameters.vk,
+            prover_channel,
+            statement,
+        )
+    }
 
-        Ok(())
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "snark_range::verify", level = "debug", skip_all)
+    )]
+    fn verify_with_key<C: HashToPrimeProverChannel<E::G1Projective, Self>>(
+        crs: &CRSHashToPrimeVerifier<E::G1Projective, Self>,
+        prover_channel: &mut C,
+        statement: &Statement<E::G1Projective>,
+    ) -> Result<(), VerificationError> {
+        let pvk = legogro16::prepare_verifying_key(&crs.verifying_key);
+        super::verify_snark(&pvk, &crs.verifying_key, prover_channel, statement)
+    }
+
+    fn verifying_key(parameters: &Self::Parameters) -> Self::VerifyingKey {
+        parameters.vk.clone()
+    }
+
+    fn verifying_key_bytes(verifying_key: &Self::VerifyingKey) -> Vec<u8> {
+        let mut bytes = vec![];
+        verifying_key.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn validate_parameters<R: Rng + CryptoRng>(
+        crs: &CRSHashToPrime<E::G1Projective, Self>,
+        rng: &mut R,
+    ) -> Result<(), CRSError> {
+        super::validate_snark_key(
+            &crs.hash_to_prime_parameters,
+            &crs.pedersen_commitment_parameters,
+            rng,
+        )
     }
 
     fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
         Ok((e.clone(), 0))
     }
+
+    fn num_constraints(&self) -> Option<usize> {
+        super::count_constraints(HashToPrimeCircuit::<E>::new(&self.crs.parameters, None))
+    }
+
+    #[cfg(feature = "simulator")]
+    fn simulate_proof<R: Rng + CryptoRng>(
+        &self,
+        rng: &mut R,
+        statement: &Statement<E::G1Projective>,
+    ) -> Option<Self::Proof> {
+        Some(super::simulate_snark(
+            rng,
+            &self.crs.hash_to_prime_parameters.vk,
+            statement,
+        ))
+    }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        legogro16::Proof::<E>::deserialize(bytes).map_err(|_| CurveError::CannotRead)
+    }
+
+    fn proof_to_bytes_uncompressed(proof: &Self::Proof) -> Result<Vec<u8>, CurveError> {
+        let mut bytes = vec![];
+        proof.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes_uncompressed(bytes: &[u8]) -> Result<Self::Proof, CurveError> {
+        legogro16::Proof::<E>::deserialize_uncompressed(bytes).map_err(|_| CurveError::CannotRead)
+    }
+
+    fn proof_size(proof: &Self::Proof, compressed: bool) -> usize {
+        if compressed {
+            proof.serialized_size()
+        } else {
+            proof.uncompressed_size()
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::{HashToPrimeCircuit, Protocol, Statement, Witness};
     use crate::{
-        commitments::Commitment,
+        commitments::{pedersen::PedersenCommitment, Commitment},
         parameters::Parameters,
-        protocols::hash_to_prime::{
-            snark_range::Protocol as HPProtocol,
-            transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
-            HashToPrimeProtocol,
+        protocols::{
+            hash_to_prime::{
+                snark_range::Protocol as HPProtocol,
+                transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+                HashToPrimeProtocol,
+            },
+            SetupError, VerificationError,
         },
         utils::integer_to_bigint_mod_q,
     };
     use accumulator::group::Rsa2048;
     use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+    use ark_bn254::Bn254;
+    use ark_ec::PairingEngine;
     use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
     use merlin::Transcript;
     use rand::thread_rng;
@@ -179,22 +281,20 @@ mod test {
         }
     }
 
-    #[test]
-    fn test_proof() {
-        let params = Parameters::from_security_level(128).unwrap();
+    fn prove_and_verify<E: PairingEngine>(params: &Parameters) {
         let mut rng1 = RandState::new();
         rng1.seed(&Integer::from(13));
         let mut rng2 = thread_rng();
 
         let crs = crate::protocols::membership::Protocol::<
             Rsa2048,
-            G1Projective,
-            HPProtocol<Bls12_381>,
-        >::setup(&params, &mut rng1, &mut rng2)
+            E::G1Projective,
+            HPProtocol<E>,
+        >::setup(params, &mut rng1, &mut rng2)
         .unwrap()
         .crs
         .crs_hash_to_prime;
-        let protocol = Protocol::<Bls12_381>::from_crs(&crs);
+        let protocol = Protocol::<E>::from_crs(&crs);
 
         let value = Integer::from(Integer::u_pow_u(
             2,
@@ -228,5 +328,48 @@ mod test {
         let mut prover_channel =
             TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
         protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        // The proof links to the commitment it was created for only.
+        let other = Statement {
+            c_e_q: protocol
+                .crs
+                .pedersen_commitment_parameters
+                .commit(&Integer::from(3), &Integer::from(9))
+                .unwrap(),
+        };
+        let verification_transcript = RefCell::new(Transcript::new(b"hash_to_prime"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        assert!(matches!(
+            protocol.verify(&mut prover_channel, &other),
+            Err(VerificationError::HashToPrimeLinkMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        prove_and_verify::<Bls12_381>(&params);
+    }
+
+    #[test]
+    fn test_proof_bls12_377() {
+        let (params, _) = Parameters::from_curve::<ark_bls12_377::Fr>().unwrap();
+        prove_and_verify::<ark_bls12_377::Bls12_377>(&params);
+    }
+
+    #[test]
+    fn test_proof_bn254() {
+        let (params, _) = Parameters::from_curve::<ark_bn254::Fr>().unwrap();
+        assert_eq!(params.hash_to_prime_bits, 252);
+        prove_and_verify::<Bn254>(&params);
+
+        // 254-bit elements don't fit in the scalar field.
+        let params = Parameters::from_security_level(128).unwrap();
+        let pedersen = PedersenCommitment::<ark_bn254::G1Projective>::setup(&mut thread_rng());
+        assert!(matches!(
+            Protocol::<Bn254>::setup(&mut thread_rng(), &pedersen, &params),
+            Err(SetupError::CouldNotPerformSetup)
+        ));
     }
 }
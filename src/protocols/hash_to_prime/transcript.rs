@@ -2,13 +2,14 @@ use crate::{
     channels::ChannelError,
     protocols::hash_to_prime::{
         channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
-        CRSHashToPrime, HashToPrimeProtocol,
+        CRSHashToPrime, CRSHashToPrimeVerifier, HashToPrimeProtocol,
+    },
+    transcript::{
+        FiatShamir, TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolCurve,
     },
-    transcript::{TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolCurve},
     utils::curve::CurvePointProjective,
 };
-use merlin::Transcript;
-use std::cell::RefCell;
+use core::cell::RefCell;
 
 pub trait TranscriptProtocolHashToPrime<P: CurvePointProjective>:
     TranscriptProtocolCurve<P> + TranscriptProtocolChallenge
@@ -16,7 +17,7 @@ pub trait TranscriptProtocolHashToPrime<P: CurvePointProjective>:
     fn hash_to_prime_domain_sep(&mut self);
 }
 
-impl<P: CurvePointProjective> TranscriptProtocolHashToPrime<P> for Transcript {
+impl<P: CurvePointProjective, T: FiatShamir> TranscriptProtocolHashToPrime<P> for T {
     fn hash_to_prime_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"hash_to_prime");
     }
@@ -29,8 +30,8 @@ pub struct TranscriptVerifierChannel<
     T: TranscriptProtocolHashToPrime<P>,
 > {
     proof: Option<HP::Proof>,
-    crs_type: std::marker::PhantomData<CRSHashToPrime<P, HP>>,
-    transcript_type: std::marker::PhantomData<&'a RefCell<T>>,
+    crs_type: core::marker::PhantomData<CRSHashToPrime<P, HP>>,
+    transcript_type: core::marker::PhantomData<&'a RefCell<T>>,
 }
 
 impl<
@@ -46,8 +47,8 @@ impl<
     ) -> TranscriptVerifierChannel<'a, P, HP, T> {
         TranscriptVerifierChannel {
             proof: None,
-            crs_type: std::marker::PhantomData,
-            transcript_type: std::marker::PhantomData,
+            crs_type: core::marker::PhantomData,
+            transcript_type: core::marker::PhantomData,
         }
     }
 
@@ -80,8 +81,8 @@ pub struct TranscriptProverChannel<
     T: TranscriptProtocolHashToPrime<P>,
 > {
     proof: HP::Proof,
-    crs_type: std::marker::PhantomData<CRSHashToPrime<P, HP>>,
-    transcript_type: std::marker::PhantomData<&'a RefCell<T>>,
+    crs_type: core::marker::PhantomData<CRSHashToPrime<P, HP>>,
+    transcript_type: core::marker::PhantomData<&'a RefCell<T>>,
 }
 
 impl<
@@ -98,8 +99,21 @@ impl<
     ) -> TranscriptProverChannel<'a, P, HP, T> {
         TranscriptProverChannel {
             proof: proof.clone(),
-            crs_type: std::marker::PhantomData,
-            transcript_type: std::marker::PhantomData,
+            crs_type: core::marker::PhantomData,
+            transcript_type: core::marker::PhantomData,
+        }
+    }
+
+    /// Like `new`, for verifiers holding only the verifying key.
+    pub fn with_verifier_crs(
+        _: &CRSHashToPrimeVerifier<P, HP>,
+        _: &'a RefCell<T>,
+        proof: &HP::Proof,
+    ) -> TranscriptProverChannel<'a, P, HP, T> {
+        TranscriptProverChannel {
+            proof: proof.clone(),
+            crs_type: core::marker::PhantomData,
+            transcript_type: core::marker::PhantomData,
         }
     }
 }
@@ -0,0 +1,23 @@
+use crate::{
+    channels::ChannelError,
+    commitments::{integer::IntegerCommitment, Commitment},
+    protocols::intersection::Side,
+    utils::ConvertibleUnknownOrderGroup,
+};
+
+pub trait IntersectionVerifierChannel<G: ConvertibleUnknownOrderGroup> {
+    fn send_c_e(
+        &mut self,
+        c_e: &<IntegerCommitment<G> as Commitment>::Instance,
+    ) -> Result<(), ChannelError>;
+    /// Routes the following root messages to the instance for the given side.
+    fn select_root(&mut self, side: Side) -> Result<(), ChannelError>;
+}
+
+pub trait IntersectionProverChannel<G: ConvertibleUnknownOrderGroup> {
+    fn receive_c_e(
+        &mut self,
+    ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError>;
+    /// Routes the following root messages to the instance for the given side.
+    fn select_root(&mut self, side: Side) -> Result<(), ChannelError>;
+}
@@ -0,0 +1,371 @@
+//! Implements set-intersection membership: shows that a committed element is
+//! present in two accumulators.
+//!
+//! Both root instances share the integer commitment c_e, so a single modeq and
+//! hash_to_prime proof is enough to link them to the Pedersen commitment.
+use crate::{
+    commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment, Commitment},
+    parameters::Parameters,
+    protocols::{
+        check_elem, check_point,
+        hash_to_prime::{
+            channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
+            HashToPrimeError, HashToPrimeProtocol, Statement as HashToPrimeStatement,
+            Witness as HashToPrimeWitness,
+        },
+        membership::{Protocol as MembershipProtocol, CRS},
+        modeq::{
+            channel::{ModEqProverChannel, ModEqVerifierChannel},
+            Proof as ModEqProof, Protocol as ModEqProtocol, Statement as ModEqStatement,
+            Witness as ModEqWitness,
+        },
+        root::{
+            channel::{RootProverChannel, RootVerifierChannel},
+            Proof as RootProof, Protocol as RootProtocol, Statement as RootStatement,
+            Witness as RootWitness,
+        },
+        ProofError, SetupError, ValidationError, VerificationError,
+    },
+    utils::zeroize_integer,
+    utils::ConvertibleUnknownOrderGroup,
+    utils::{curve::CurvePointProjective, random_between},
+    utils::{Redacted, RedactedInteger},
+};
+use channel::{IntersectionProverChannel, IntersectionVerifierChannel};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
+
+pub mod channel;
+pub mod transcript;
+
+/// Selects which of the two root instances the root channel messages belong to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    A,
+    B,
+}
+
+pub struct Protocol<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub crs: CRS<G, P, HP>,
+}
+
+pub struct Statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    pub c_p_a: G::Elem,
+    pub c_p_b: G::Elem,
+    pub c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Statement<G, P> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("c_p_a", &self.c_p_a)?;
+        check_elem::<G>("c_p_b", &self.c_p_b)?;
+        check_point("c_e_q", &self.c_e_q)
+    }
+}
+
+pub struct Witness<G: ConvertibleUnknownOrderGroup> {
+    pub e: Integer,
+    pub r_q: Integer,
+    pub w_a: G::Elem,
+    pub w_b: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for Witness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for Witness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .field("w_a", &Redacted)
+            .field("w_b", &Redacted)
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for Witness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+pub struct Proof<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub c_e: <IntegerCommitment<G> as Commitment>::Instance,
+    pub proof_root_a: RootProof<G>,
+    pub proof_root_b: RootProof<G>,
+    pub proof_modeq: ModEqProof<G, P>,
+    pub proof_hash_to_prime: HP::Proof,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
+    for Proof<G, P, HP>
+{
+    fn clone(&self) -> Self {
+        Self {
+            c_e: self.c_e.clone(),
+            proof_root_a: self.proof_root_a.clone(),
+            proof_root_b: self.proof_root_b.clone(),
+            proof_modeq: self.proof_modeq.clone(),
+            proof_hash_to_prime: self.proof_hash_to_prime.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Protocol<G, P, HP>
+{
+    pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<Protocol<G, P, HP>, SetupError> {
+        let membership = MembershipProtocol::<G, P, HP>::setup(parameters, rng1, rng2)?;
+        Ok(Protocol {
+            crs: membership.crs,
+        })
+    }
+
+    pub fn prove<
+        R1: MutRandState,
+        R2: RngCore + CryptoRng,
+        C: IntersectionVerifierChannel<G>
+            + RootVerifierChannel<G>
+            + ModEqVerifierChannel<G, P>
+            + HashToPrimeVerifierChannel<P, HP>,
+    >(
+        &self,
+        verifier_channel: &mut C,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+    ) -> Result<(), ProofError> {
+        let (hashed_e, _) = self.hash_to_prime(&witness.e)?;
+        let r = random_between(rng1, &Integer::from(0), &G::order_upper_bound());
+        let c_e = self
+            .crs
+            .crs_root
+            .integer_commitment_parameters
+            .commit_secret(&hashed_e, &r, rng2)?;
+        verifier_channel.send_c_e(&c_e)?;
+        let root = RootProtocol::from_crs(&self.crs.crs_root);
+        for (side, acc, w) in [
+            (Side::A, &statement.c_p_a, &witness.w_a),
+            (Side::B, &statement.c_p_b, &witness.w_b),
+        ]
+        .iter()
+        {
+            verifier_channel.select_root(*side)?;
+            root.prove(
+                verifier_channel,
+                rng1,
+                rng2,
+                &RootStatement {
+                    c_e: c_e.clone(),
+                    acc: (*acc).clone(),
+                },
+                &RootWitness {
+                    e: hashed_e.clone(),
+                    r: r.clone(),
+                    w: (*w).clone(),
+                },
+            )?;
+        }
+        let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
+        modeq.prove(
+            verifier_channel,
+            rng1,
+            rng2,
+            &ModEqStatement {
+                c_e,
+                c_e_q: statement.c_e_q.clone(),
+            },
+            &ModEqWitness {
+                e: hashed_e,
+                r,
+                r_q: witness.r_q.clone(),
+            },
+        )?;
+        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
+        hash_to_prime.prove(
+            verifier_channel,
+            rng2,
+            &HashToPrimeStatement {
+                c_e_q: statement.c_e_q.clone(),
+            },
+            &HashToPrimeWitness {
+                e: witness.e.clone(),
+                r_q: witness.r_q.clone(),
+            },
+        )?;
+
+        Ok(())
+    }
+
+    pub fn verify<
+        C: IntersectionProverChannel<G>
+            + RootProverChannel<G>
+            + ModEqProverChannel<G, P>
+            + HashToPrimeProverChannel<P, HP>,
+    >(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G, P>,
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        let c_e = prover_channel.receive_c_e()?;
+        let root = RootProtocol::from_crs(&self.crs.crs_root);
+        for (side, acc) in [(Side::A, &statement.c_p_a), (Side::B, &statement.c_p_b)].iter() {
+            prover_channel.select_root(*side)?;
+            root.verify(
+                prover_channel,
+                &RootStatement {
+                    c_e: c_e.clone(),
+                    acc: (*acc).clone(),
+                },
+            )?;
+        }
+        let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
+        modeq.verify(
+            prover_channel,
+            &ModEqStatement {
+                c_e,
+                c_e_q: statement.c_e_q.clone(),
+            },
+        )?;
+        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
+        hash_to_prime.verify(
+            prover_channel,
+            &HashToPrimeStatement {
+                c_e_q: statement.c_e_q.clone(),
+            },
+        )?;
+
+        Ok(())
+    }
+
+    pub fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
+        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
+        hash_to_prime.hash_to_prime(e)
+    }
+
+    pub fn from_crs(crs: &CRS<G, P, HP>) -> Protocol<G, P, HP> {
+        Protocol { crs: crs.clone() }
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{Protocol, Statement, Witness};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            intersection::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_e2e_prime_rsa() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(
+            2,
+            (crs.parameters.hash_to_prime_bits) as u32,
+        )) - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum_a = accum
+            .clone()
+            .add(&[
+                Integer::from(LARGE_PRIMES[0]),
+                Integer::from(LARGE_PRIMES[1]),
+            ])
+            .add_with_proof(&[value.clone()]);
+        let accum_b = accum
+            .add(&[
+                Integer::from(LARGE_PRIMES[2]),
+                Integer::from(LARGE_PRIMES[3]),
+            ])
+            .add_with_proof(&[value.clone()]);
+
+        let statement = Statement {
+            c_p_a: accum_a.0.value,
+            c_p_b: accum_b.0.value,
+            c_e_q: commitment,
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            w_a: accum_a.1.witness.0.value,
+            w_b: accum_b.1.witness.0.value,
+        };
+
+        let proof_transcript = RefCell::new(Transcript::new(b"intersection"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &witness,
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+        let verification_transcript = RefCell::new(Transcript::new(b"intersection"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+//! Implements set membership where the set is encoded as the roots of the
+//! polynomial P(X) = prod (X - a_i), committed with KZG as C = g^{P(tau)}.
+//!
+//! Membership of e is shown with the quotient opening g^{Q(tau)} where
+//! Q(X) = P(X) / (X - e), so that e(g^{Q(tau)}, h^tau * h^{-e}) = e(C, h). This
+//! is the pairing accumulator relation with the element negated, so the proof
+//! is the pairing protocol on -e, against the commitment -c_e_q, and uses the
+//! pairing channels. The powers of tau come from an existing universal SRS.
+use crate::{
+    commitments::{pedersen::PedersenCommitment, Commitment},
+    parameters::Parameters,
+    protocols::{
+        pairing::{
+            channel::{PairingProverChannel, PairingVerifierChannel},
+            commit_product, CRSPairing, Protocol as PairingProtocol, Statement as PairingStatement,
+            Witness as PairingWitness,
+        },
+        ProofError, VerificationError,
+    },
+    utils::integer_to_bigint_mod_q,
+    utils::zeroize_integer,
+    utils::{Redacted, RedactedInteger},
+};
+use ark_ec::PairingEngine;
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+use std::fmt;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+pub mod ptau;
+
+#[derive(Debug, Error)]
+pub enum KZGError {
+    #[error("the set is larger than the powers of tau")]
+    TooManyElements,
+    #[error("the element is not in the set")]
+    ElementNotInSet,
+    #[error("could not convert {0}")]
+    IntegerError(Integer),
+}
+
+impl From<Integer> for KZGError {
+    fn from(err: Integer) -> Self {
+        KZGError::IntegerError(err)
+    }
+}
+
+/// Powers of tau from a universal SRS, at least as many as the set size plus
+/// one.
+#[derive(Clone)]
+pub struct UniversalSRS<E: PairingEngine> {
+    pub powers_of_g: Vec<E::G1Projective>,
+    pub h: E::G2Projective,
+    pub h_tau: E::G2Projective,
+}
+
+pub struct Statement<E: PairingEngine> {
+    /// The KZG commitment to the set polynomial.
+    pub commitment: E::G1Projective,
+    pub c_e_q: <PedersenCommitment<E::G1Projective> as Commitment>::Instance,
+}
+
+pub struct Witness<E: PairingEngine> {
+    pub e: Integer,
+    pub r_q: Integer,
+    /// The KZG commitment to the quotient P(X) / (X - e).
+    pub quotient: E::G1Projective,
+}
+
+impl<E: PairingEngine> Zeroize for Witness<E> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for Witness<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .field("quotient", &Redacted)
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> Drop for Witness<E> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+pub struct Protocol<E: PairingEngine> {
+    pub crs: CRSPairing<E>,
+}
+
+fn negated_roots<E: PairingEngine>(elements: &[Integer]) -> Result<Vec<E::Fr>, KZGError> {
+    Ok(elements
+        .iter()
+        .map(|a| integer_to_bigint_mod_q::<E::G1Projective>(&Integer::from(-a)))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Commits to the polynomial whose roots are the set elements.
+pub fn commit_set<E: PairingEngine>(
+    srs: &UniversalSRS<E>,
+    elements: &[Integer],
+) -> Result<E::G1Projective, KZGError> {
+    commit_product(&srs.powers_of_g, &negated_roots::<E>(elements)?)
+        .ok_or(KZGError::TooManyElements)
+}
+
+/// Computes the quotient commitment showing that element is a root of the set
+/// polynomial.
+pub fn open_membership<E: PairingEngine>(
+    srs: &UniversalSRS<E>,
+    elements: &[Integer],
+    element: &Integer,
+) -> Result<E::G1Projective, KZGError> {
+    if !elements.contains(element) {
+        return Err(KZGError::ElementNotInSet);
+    }
+    let others = elements
+        .iter()
+        .filter(|a| *a != element)
+        .cloned()
+        .collect::<Vec<_>>();
+    commit_set(srs, &others)
+}
+
+impl<E: PairingEngine> Protocol<E> {
+    pub fn from_srs<R: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        srs: &UniversalSRS<E>,
+        rng: &mut R,
+    ) -> Protocol<E> {
+        Protocol {
+            crs: CRSPairing::<E> {
+                parameters: parameters.clone(),
+                pedersen_commitment_parameters: PedersenCommitment::<E::G1Projective>::setup(rng),
+                powers: srs.powers_of_g.clone(),
+                h: srs.h,
+                h_s: srs.h_tau,
+            },
+        }
+    }
+
+    pub fn from_crs(crs: &CRSPairing<E>) -> Protocol<E> {
+        Protocol { crs: crs.clone() }
+    }
+
+    pub fn prove<R: RngCore + CryptoRng, C: PairingVerifierChannel<E>>(
+        &self,
+        verifier_channel: &mut C,
+        rng: &mut R,
+        statement: &Statement<E>,
+        witness: &Witness<E>,
+    ) -> Result<(), ProofError> {
+        let pairing = PairingProtocol::from_crs(&self.crs);
+        pairing.prove(
+            verifier_channel,
+            rng,
+            &PairingStatement {
+                acc: statement.commitment,
+                c_e_q: -statement.c_e_q,
+            },
+            &PairingWitness {
+                e: Integer::from(-&witness.e),
+                r_q: Integer::from(-&witness.r_q),
+                w: witness.quotient,
+            },
+        )
+    }
+
+    pub fn verify<C: PairingProverChannel<E>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<E>,
+    ) -> Result<(), VerificationError> {
+        let pairing = PairingProtocol::from_crs(&self.crs);
+        pairing.verify(
+            prover_channel,
+            &PairingStatement {
+                acc: statement.commitment,
+                c_e_q: -statement.c_e_q,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{commit_set, open_membership, Protocol, Statement, UniversalSRS, Witness};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::pairing::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+    };
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective, G2Projective};
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::{PrimeField, UniformRand};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    pub(crate) fn srs(size: usize) -> UniversalSRS<Bls12_381> {
+        let mut rng = thread_rng();
+        let tau = Fr::rand(&mut rng);
+        let g = G1Projective::prime_subgroup_generator();
+        let h = G2Projective::prime_subgroup_generator();
+        let mut powers_of_g = vec![g];
+        let mut power = g;
+        for _ in 0..size {
+            power = power.mul(tau.into_repr());
+            powers_of_g.push(power);
+        }
+        UniversalSRS {
+            powers_of_g,
+            h,
+            h_tau: h.mul(tau.into_repr()),
+        }
+    }
+
+    #[test]
+    fn test_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng = thread_rng();
+        let srs = srs(8);
+        let protocol = Protocol::<Bls12_381>::from_srs(&params, &srs, &mut rng);
+
+        let set = LARGE_PRIMES
+            .iter()
+            .map(|p| Integer::from(*p))
+            .collect::<Vec<_>>();
+        let value = set[1].clone();
+        let randomness = Integer::from(5);
+        let c_e_q = protocol
+            .crs
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let statement = Statement::<Bls12_381> {
+            commitment: commit_set(&srs, &set).unwrap(),
+            c_e_q,
+        };
+        let witness = Witness::<Bls12_381> {
+            e: value.clone(),
+            r_q: randomness,
+            quotient: open_membership(&srs, &set, &value).unwrap(),
+        };
+
+        let proof_transcript = RefCell::new(Transcript::new(b"kzg"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&protocol.crs, &proof_transcript);
+        protocol
+            .prove(&mut verifier_channel, &mut rng, &statement, &witness)
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"kzg"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&protocol.crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        assert!(open_membership(&srs, &set[2..], &value).is_err());
+    }
+}
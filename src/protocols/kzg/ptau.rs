@@ -0,0 +1,346 @@
+//! Loads the powers of tau of a universal SRS from the files of public
+//! ceremonies, so that the KZG backend reuses them instead of a fresh setup.
+//!
+//! Two formats are read:
+//! * snarkjs `.ptau` files, e.g., of the Hermez and perpetual powers of tau
+//!   ceremonies, whose sections hold the points as little-endian coordinates
+//!   in Montgomery form.
+//! * the uncompressed `challenge` files of the Zcash powers of tau and its
+//!   forks: a 64-byte hash followed by the points as big-endian coordinates,
+//!   with flags in the top bits of the first byte.
+//!
+//! Only the powers of tau in G1 and the first two in G2 are read. Neither
+//! format is checked against the transcript of its ceremony, so the files have
+//! to come from a trusted source. `check_powers` checks that the powers are
+//! consistent with tau in G2.
+use super::UniversalSRS;
+use crate::utils::curve::CurvePointProjective;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rand::{CryptoRng, RngCore};
+use rug::{integer::Order, Integer};
+use std::io::{Read, Seek, SeekFrom};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PtauError {
+    #[error("invalid file format")]
+    InvalidFormat,
+    #[error("the file is for another curve")]
+    UnsupportedCurve,
+    #[error("the file has {0} powers of tau")]
+    NotEnoughPowers(usize),
+    #[error("a point is not on the curve or not in the subgroup")]
+    InvalidPoint,
+    #[error("could not read the file")]
+    IOError(#[from] std::io::Error),
+    #[error("could not deserialize a point")]
+    SerializationError(#[from] SerializationError),
+}
+
+const SNARKJS_MAGIC: &[u8; 4] = b"ptau";
+const SNARKJS_HEADER: u32 = 1;
+const SNARKJS_TAU_G1: u32 = 2;
+const SNARKJS_TAU_G2: u32 = 3;
+const ZCASH_HASH_SIZE: u64 = 64;
+
+/// The size of a base field element, as written by both formats and by
+/// `CanonicalSerialize`.
+fn fq_size<E: PairingEngine>() -> usize {
+    E::Fq::zero().serialized_size()
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, PtauError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, PtauError> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Rebuilds a point from its base field coordinates, as little-endian bytes
+/// in standard form, through the uncompressed encoding of arkworks, which
+/// checks that it's on the curve and in the subgroup.
+fn point_from_coordinates<A: AffineCurve>(coordinates: &[u8]) -> Result<A, PtauError> {
+    A::deserialize_uncompressed(coordinates).map_err(|_| PtauError::InvalidPoint)
+}
+
+/// Reads points of `coordinates` base field elements each, in Montgomery
+/// form, where all zeros is the point at infinity.
+fn read_snarkjs_points<E: PairingEngine, A: AffineCurve, R: Read>(
+    reader: &mut R,
+    count: usize,
+    coordinates: usize,
+) -> Result<Vec<A>, PtauError> {
+    let size = fq_size::<E>();
+    let r_inverse = E::Fq::from(2u64)
+        .pow(&[8 * size as u64])
+        .inverse()
+        .ok_or(PtauError::UnsupportedCurve)?;
+    let mut point = vec![0u8; size * coordinates];
+    let mut standard = Vec::with_capacity(size * coordinates);
+    (0..count)
+        .map(|_| {
+            reader.read_exact(&mut point)?;
+            if point.iter().all(|b| *b == 0) {
+                return Ok(A::zero());
+            }
+            standard.clear();
+            for coordinate in point.chunks(size) {
+                let montgomery = E::Fq::deserialize(coordinate)?;
+                (montgomery * &r_inverse).serialize(&mut standard)?;
+            }
+            point_from_coordinates(&standard)
+        })
+        .collect()
+}
+
+/// Reads the first `num_powers` powers of tau from a snarkjs `.ptau` file.
+pub fn read_snarkjs<E: PairingEngine, R: Read + Seek>(
+    mut reader: R,
+    num_powers: usize,
+) -> Result<UniversalSRS<E>, PtauError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != SNARKJS_MAGIC {
+        return Err(PtauError::InvalidFormat);
+    }
+    let _version = read_u32(&mut reader)?;
+    let num_sections = read_u32(&mut reader)?;
+    let mut sections = vec![];
+    for _ in 0..num_sections {
+        let section_type = read_u32(&mut reader)?;
+        let size = read_u64(&mut reader)?;
+        let start = reader.seek(SeekFrom::Current(0))?;
+        sections.push((section_type, start, size));
+        reader.seek(SeekFrom::Current(size as i64))?;
+    }
+    let section = |section_type: u32| {
+        sections
+            .iter()
+            .find(|(t, _, _)| *t == section_type)
+            .map(|(_, start, size)| (*start, *size))
+            .ok_or(PtauError::InvalidFormat)
+    };
+
+    let (header, _) = section(SNARKJS_HEADER)?;
+    reader.seek(SeekFrom::Start(header))?;
+    let size = read_u32(&mut reader)? as usize;
+    if size != fq_size::<E>() {
+        return Err(PtauError::UnsupportedCurve);
+    }
+    let mut modulus = vec![0u8; size];
+    reader.read_exact(&mut modulus)?;
+    if Integer::from_digits(&modulus, Order::Lsf)
+        != Integer::from_digits(E::Fq::characteristic(), Order::Lsf)
+    {
+        return Err(PtauError::UnsupportedCurve);
+    }
+    let power = read_u32(&mut reader)?;
+    let available = (1usize << power) * 2 - 1;
+    if num_powers > available {
+        return Err(PtauError::NotEnoughPowers(available));
+    }
+
+    let (tau_g1, _) = section(SNARKJS_TAU_G1)?;
+    reader.seek(SeekFrom::Start(tau_g1))?;
+    let powers_of_g = read_snarkjs_points::<E, E::G1Affine, _>(&mut reader, num_powers, 2)?;
+    let (tau_g2, _) = section(SNARKJS_TAU_G2)?;
+    reader.seek(SeekFrom::Start(tau_g2))?;
+    let powers_of_h = read_snarkjs_points::<E, E::G2Affine, _>(&mut reader, 2, 4)?;
+
+    Ok(UniversalSRS {
+        powers_of_g: powers_of_g.iter().map(|p| p.into_projective()).collect(),
+        h: powers_of_h[0].into_projective(),
+        h_tau: powers_of_h[1].into_projective(),
+    })
+}
+
+/// Reads points of `coordinates` big-endian base field elements each. The
+/// coordinates of G2 are written with the imaginary part first.
+fn read_zcash_points<E: PairingEngine, A: AffineCurve, R: Read>(
+    reader: &mut R,
+    count: usize,
+    coordinates: usize,
+) -> Result<Vec<A>, PtauError> {
+    const INFINITY: u8 = 1 << 6;
+    const FLAGS: u8 = 0b1110_0000;
+    let size = fq_size::<E>();
+    let mut point = vec![0u8; size * coordinates];
+    let mut standard = vec![0u8; size * coordinates];
+    (0..count)
+        .map(|_| {
+            reader.read_exact(&mut point)?;
+            if point[0] & INFINITY != 0 {
+                return Ok(A::zero());
+            }
+            point[0] &= !FLAGS;
+            for (i, coordinate) in point.chunks(size).enumerate() {
+                // c1, c0 in G2 to c0, c1
+                let j = if coordinates == 4 { i ^ 1 } else { i };
+                let target = &mut standard[j * size..(j + 1) * size];
+                target.copy_from_slice(coordinate);
+                target.reverse();
+            }
+            point_from_coordinates(&standard)
+        })
+        .collect()
+}
+
+/// Reads the first `num_powers` powers of tau from a Zcash `challenge` file
+/// of a ceremony of 2^`power` powers.
+pub fn read_zcash_challenge<E: PairingEngine, R: Read + Seek>(
+    mut reader: R,
+    power: u32,
+    num_powers: usize,
+) -> Result<UniversalSRS<E>, PtauError> {
+    let available = (1usize << power) * 2 - 1;
+    if num_powers > available {
+        return Err(PtauError::NotEnoughPowers(available));
+    }
+    let g1_size = 2 * fq_size::<E>() as u64;
+    reader.seek(SeekFrom::Start(ZCASH_HASH_SIZE))?;
+    let powers_of_g = read_zcash_points::<E, E::G1Affine, _>(&mut reader, num_powers, 2)?;
+    reader.seek(SeekFrom::Start(
+        ZCASH_HASH_SIZE + available as u64 * g1_size,
+    ))?;
+    let powers_of_h = read_zcash_points::<E, E::G2Affine, _>(&mut reader, 2, 4)?;
+
+    Ok(UniversalSRS {
+        powers_of_g: powers_of_g.iter().map(|p| p.into_projective()).collect(),
+        h: powers_of_h[0].into_projective(),
+        h_tau: powers_of_h[1].into_projective(),
+    })
+}
+
+/// Checks that each power is the previous one multiplied by tau, as given by
+/// tau in G2, combining the powers with random scalars to check them with two
+/// pairings.
+pub fn check_powers<E: PairingEngine, R: RngCore + CryptoRng>(
+    srs: &UniversalSRS<E>,
+    rng: &mut R,
+) -> bool {
+    let n = srs.powers_of_g.len();
+    if n < 2 || srs.powers_of_g[0].is_zero() || srs.h.is_zero() {
+        return false;
+    }
+    let scalars = (0..n - 1).map(|_| E::Fr::rand(rng)).collect::<Vec<_>>();
+    let lower = E::G1Projective::msm(&srs.powers_of_g[..n - 1], &scalars);
+    let higher = E::G1Projective::msm(&srs.powers_of_g[1..], &scalars);
+    E::pairing(higher.into_affine(), srs.h.into_affine())
+        == E::pairing(lower.into_affine(), srs.h_tau.into_affine())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_powers, read_snarkjs, read_zcash_challenge, PtauError};
+    use crate::protocols::kzg::{test::srs, UniversalSRS};
+    use ark_bls12_381::{Bls12_381, Fq};
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::Field;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use rand::thread_rng;
+    use std::io::Cursor;
+
+    /// The coordinates of a point as written by `serialize_uncompressed`,
+    /// without the flags.
+    fn coordinates<P: ProjectiveCurve>(point: &P) -> Vec<Fq> {
+        let mut bytes = vec![];
+        point
+            .into_affine()
+            .serialize_uncompressed(&mut bytes)
+            .unwrap();
+        bytes
+            .chunks_mut(48)
+            .map(|chunk| {
+                chunk[47] &= 0b0011_1111;
+                Fq::deserialize(&*chunk).unwrap()
+            })
+            .collect()
+    }
+
+    fn write_snarkjs(srs: &UniversalSRS<Bls12_381>, power: u32) -> Vec<u8> {
+        let r = Fq::from(2u64).pow(&[384]);
+        let montgomery = |coordinates: Vec<Fq>, out: &mut Vec<u8>| {
+            for c in coordinates {
+                (c * &r).serialize(&mut *out).unwrap();
+            }
+        };
+        let mut header = 48u32.to_le_bytes().to_vec();
+        Fq::characteristic()
+            .iter()
+            .for_each(|limb| header.extend_from_slice(&limb.to_le_bytes()));
+        header.extend_from_slice(&power.to_le_bytes());
+        header.extend_from_slice(&power.to_le_bytes());
+        let mut tau_g1 = vec![];
+        for p in &srs.powers_of_g {
+            montgomery(coordinates(p), &mut tau_g1);
+        }
+        let mut tau_g2 = vec![];
+        montgomery(coordinates(&srs.h), &mut tau_g2);
+        montgomery(coordinates(&srs.h_tau), &mut tau_g2);
+
+        let mut file = b"ptau".to_vec();
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&3u32.to_le_bytes());
+        for (section_type, section) in [(3u32, tau_g2), (1, header), (2, tau_g1)].iter() {
+            file.extend_from_slice(&section_type.to_le_bytes());
+            file.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            file.extend_from_slice(section);
+        }
+        file
+    }
+
+    fn write_zcash(srs: &UniversalSRS<Bls12_381>) -> Vec<u8> {
+        let big_endian = |coordinates: Vec<Fq>, out: &mut Vec<u8>| {
+            for c in coordinates {
+                let mut bytes = vec![];
+                c.serialize(&mut bytes).unwrap();
+                bytes.reverse();
+                out.extend_from_slice(&bytes);
+            }
+        };
+        let mut file = vec![0u8; 64];
+        for p in &srs.powers_of_g {
+            big_endian(coordinates(p), &mut file);
+        }
+        for h in [srs.h, srs.h_tau].iter() {
+            let c = coordinates(h);
+            big_endian(vec![c[1], c[0], c[3], c[2]], &mut file);
+        }
+        file
+    }
+
+    #[test]
+    fn test_read() {
+        let mut rng = thread_rng();
+        // 2^2 * 2 - 1 powers in G1
+        let srs = srs(6);
+        assert!(check_powers(&srs, &mut rng));
+
+        let snarkjs = write_snarkjs(&srs, 2);
+        let read = read_snarkjs::<Bls12_381, _>(Cursor::new(&snarkjs), 5).unwrap();
+        assert_eq!(read.powers_of_g, srs.powers_of_g[..5].to_vec());
+        assert_eq!(read.h, srs.h);
+        assert_eq!(read.h_tau, srs.h_tau);
+        assert!(check_powers(&read, &mut rng));
+        assert!(matches!(
+            read_snarkjs::<Bls12_381, _>(Cursor::new(&snarkjs), 8),
+            Err(PtauError::NotEnoughPowers(7))
+        ));
+
+        let zcash = write_zcash(&srs);
+        let read = read_zcash_challenge::<Bls12_381, _>(Cursor::new(&zcash), 2, 7).unwrap();
+        assert_eq!(read.powers_of_g, srs.powers_of_g);
+        assert_eq!(read.h_tau, srs.h_tau);
+
+        let mut wrong = read.clone();
+        wrong.powers_of_g.swap(1, 2);
+        assert!(!check_powers(&wrong, &mut rng));
+    }
+}
@@ -0,0 +1,400 @@
+//! Membership over the RSA-2048 group with the curve and hash-to-prime backend
+//! chosen at runtime, e.g., from a configuration file, so that a service
+//! compiles the generic protocol once for each supported instantiation and
+//! dispatches to one of them.
+//!
+//! The statements, proofs and CRSs differ in type between the instantiations,
+//! so they're passed as bytes in the canonical encodings of the rest of the
+//! crate. The Bulletproofs backend is left out, as its parameters can't be
+//! serialized.
+use crate::{
+    commitments::{Commitment, CommitmentError},
+    parameters::{Parameters, ParametersError},
+    protocols::{
+        hash_to_prime::{sigma_range, snark_hash, snark_range, HashToPrimeProtocol},
+        membership::{MembershipProof, Protocol, Statement, Witness, CRS},
+        ProofError, SetupError, VerificationError,
+    },
+    utils::{curve::CurvePointProjective, group::UnknownOrderGroup},
+};
+use accumulator::group::Rsa2048;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AnyMembershipError {
+    #[error("unknown instantiation {0}")]
+    UnknownInstantiation(String),
+    #[error("invalid parameters")]
+    ParametersError(#[from] ParametersError),
+    #[error("setup failed")]
+    SetupError(#[from] SetupError),
+    #[error("could not create the proof")]
+    ProofError(#[from] ProofError),
+    #[error("could not commit")]
+    CommitmentError(#[from] CommitmentError),
+    #[error("could not serialize or deserialize")]
+    SerializationError(#[from] SerializationError),
+}
+
+/// The supported pairs of curve and hash-to-prime backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instantiation {
+    Bls12_381SnarkRange,
+    Bls12_381SnarkHash,
+    Bn254SnarkRange,
+    Bls12_377SnarkRange,
+    JubjubSigmaRange,
+}
+
+impl Instantiation {
+    pub const ALL: [Instantiation; 5] = [
+        Instantiation::Bls12_381SnarkRange,
+        Instantiation::Bls12_381SnarkHash,
+        Instantiation::Bn254SnarkRange,
+        Instantiation::Bls12_377SnarkRange,
+        Instantiation::JubjubSigmaRange,
+    ];
+
+    /// The name of the instantiation in configurations, the curve and the
+    /// backend separated by a slash.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Instantiation::Bls12_381SnarkRange => "bls12_381/snark_range",
+            Instantiation::Bls12_381SnarkHash => "bls12_381/snark_hash",
+            Instantiation::Bn254SnarkRange => "bn254/snark_range",
+            Instantiation::Bls12_377SnarkRange => "bls12_377/snark_range",
+            Instantiation::JubjubSigmaRange => "jubjub/sigma_range",
+        }
+    }
+
+    /// The parameters at 128 bits of security on BLS12-381, and the ones
+    /// derived from the scalar field on the smaller curves.
+    pub fn default_parameters(&self) -> Result<Parameters, ParametersError> {
+        match self {
+            Instantiation::Bls12_381SnarkRange | Instantiation::Bls12_381SnarkHash => {
+                Parameters::from_security_level(128)
+            }
+            Instantiation::Bn254SnarkRange => Ok(Parameters::from_curve::<ark_bn254::Fr>()?.0),
+            Instantiation::Bls12_377SnarkRange => {
+                Ok(Parameters::from_curve::<ark_bls12_377::Fr>()?.0)
+            }
+            Instantiation::JubjubSigmaRange => {
+                Ok(Parameters::from_curve::<ark_ed_on_bls12_381::Fr>()?.0)
+            }
+        }
+    }
+}
+
+impl FromStr for Instantiation {
+    type Err = AnyMembershipError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Instantiation::ALL
+            .iter()
+            .find(|instantiation| instantiation.name() == name)
+            .copied()
+            .ok_or_else(|| AnyMembershipError::UnknownInstantiation(name.to_string()))
+    }
+}
+
+type Membership<P, HP> = Protocol<Rsa2048, P, HP>;
+
+pub enum AnyMembershipProtocol {
+    Bls12_381SnarkRange(
+        Membership<ark_bls12_381::G1Projective, snark_range::Protocol<ark_bls12_381::Bls12_381>>,
+    ),
+    Bls12_381SnarkHash(
+        Membership<ark_bls12_381::G1Projective, snark_hash::Protocol<ark_bls12_381::Bls12_381>>,
+    ),
+    Bn254SnarkRange(Membership<ark_bn254::G1Projective, snark_range::Protocol<ark_bn254::Bn254>>),
+    Bls12_377SnarkRange(
+        Membership<ark_bls12_377::G1Projective, snark_range::Protocol<ark_bls12_377::Bls12_377>>,
+    ),
+    JubjubSigmaRange(
+        Membership<
+            ark_ed_on_bls12_381::EdwardsProjective,
+            sigma_range::Protocol<ark_ed_on_bls12_381::EdwardsProjective>,
+        >,
+    ),
+}
+
+/// Runs the expression with the protocol of whichever instantiation.
+macro_rules! dispatch {
+    ($self:expr, $protocol:ident => $body:expr) => {
+        match $self {
+            AnyMembershipProtocol::Bls12_381SnarkRange($protocol) => $body,
+            AnyMembershipProtocol::Bls12_381SnarkHash($protocol) => $body,
+            AnyMembershipProtocol::Bn254SnarkRange($protocol) => $body,
+            AnyMembershipProtocol::Bls12_377SnarkRange($protocol) => $body,
+            AnyMembershipProtocol::JubjubSigmaRange($protocol) => $body,
+        }
+    };
+}
+
+/// Builds the variant of the instantiation from the expression, whose type
+/// is inferred per variant.
+macro_rules! construct {
+    ($instantiation:expr, $constructor:expr) => {
+        match $instantiation {
+            Instantiation::Bls12_381SnarkRange => {
+                AnyMembershipProtocol::Bls12_381SnarkRange($constructor)
+            }
+            Instantiation::Bls12_381SnarkHash => {
+                AnyMembershipProtocol::Bls12_381SnarkHash($constructor)
+            }
+            Instantiation::Bn254SnarkRange => AnyMembershipProtocol::Bn254SnarkRange($constructor),
+            Instantiation::Bls12_377SnarkRange => {
+                AnyMembershipProtocol::Bls12_377SnarkRange($constructor)
+            }
+            Instantiation::JubjubSigmaRange => {
+                AnyMembershipProtocol::JubjubSigmaRange($constructor)
+            }
+        }
+    };
+}
+
+fn read_crs<P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    bytes: &[u8],
+) -> Result<Membership<P, HP>, SerializationError>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    Ok(Protocol::from_crs(&CRS::deserialize(bytes)?))
+}
+
+fn commit<P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    protocol: &Membership<P, HP>,
+    element: &Integer,
+    randomness: &Integer,
+) -> Result<Vec<u8>, AnyMembershipError> {
+    let commitment = protocol
+        .crs
+        .crs_modeq
+        .pedersen_commitment_parameters
+        .commit(element, randomness)?;
+    commitment
+        .to_affine_bytes()
+        .map_err(|_| SerializationError::InvalidData.into())
+}
+
+fn statement<P: CurvePointProjective>(
+    accumulator: &<Rsa2048 as UnknownOrderGroup>::Elem,
+    commitment: &[u8],
+    epoch: Option<Vec<u8>>,
+) -> Result<Vec<u8>, SerializationError> {
+    Statement::<Rsa2048, P> {
+        c_p: accumulator.clone(),
+        c_e_q: P::from_affine_bytes(commitment).map_err(|_| SerializationError::InvalidData)?,
+        epoch,
+    }
+    .to_canonical_bytes()
+}
+
+fn prove<
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    R1: MutRandState,
+    R2: RngCore + CryptoRng,
+>(
+    protocol: &Membership<P, HP>,
+    rng1: &mut R1,
+    rng2: &mut R2,
+    statement: &[u8],
+    witness: &Witness<Rsa2048>,
+    context: &[u8],
+) -> Result<Vec<u8>, AnyMembershipError> {
+    let statement = Statement::from_canonical_bytes(statement)?;
+    let proof = protocol.prove_noninteractive(rng1, rng2, &statement, witness, context)?;
+    Ok(proof.to_canonical_bytes()?)
+}
+
+fn verify<P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    protocol: &Membership<P, HP>,
+    statement: &[u8],
+    proof: &[u8],
+    context: &[u8],
+) -> Result<(), VerificationError> {
+    let statement = Statement::from_canonical_bytes(statement)?;
+    let mut reader = proof;
+    let proof = MembershipProof::<Rsa2048, P, HP>::deserialize(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    proof.validate(&protocol.crs.parameters)?;
+    protocol.verify_noninteractive(&statement, &proof, context)
+}
+
+impl AnyMembershipProtocol {
+    pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
+        instantiation: Instantiation,
+        parameters: &Parameters,
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<AnyMembershipProtocol, SetupError> {
+        Ok(construct!(
+            instantiation,
+            Protocol::setup(parameters, rng1, rng2)?
+        ))
+    }
+
+    /// Loads a CRS written by `crs_bytes` for the same instantiation.
+    pub fn from_crs_bytes(
+        instantiation: Instantiation,
+        bytes: &[u8],
+    ) -> Result<AnyMembershipProtocol, SerializationError> {
+        Ok(construct!(instantiation, read_crs(bytes)?))
+    }
+
+    pub fn instantiation(&self) -> Instantiation {
+        match self {
+            AnyMembershipProtocol::Bls12_381SnarkRange(_) => Instantiation::Bls12_381SnarkRange,
+            AnyMembershipProtocol::Bls12_381SnarkHash(_) => Instantiation::Bls12_381SnarkHash,
+            AnyMembershipProtocol::Bn254SnarkRange(_) => Instantiation::Bn254SnarkRange,
+            AnyMembershipProtocol::Bls12_377SnarkRange(_) => Instantiation::Bls12_377SnarkRange,
+            AnyMembershipProtocol::JubjubSigmaRange(_) => Instantiation::JubjubSigmaRange,
+        }
+    }
+
+    pub fn parameters(&self) -> &Parameters {
+        dispatch!(self, protocol => &protocol.crs.parameters)
+    }
+
+    /// The compressed serialization of the CRS.
+    pub fn crs_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        dispatch!(self, protocol => protocol.crs.serialize(&mut bytes))?;
+        Ok(bytes)
+    }
+
+    /// Commits to the element, returning the compressed commitment.
+    pub fn commit(
+        &self,
+        element: &Integer,
+        randomness: &Integer,
+    ) -> Result<Vec<u8>, AnyMembershipError> {
+        dispatch!(self, protocol => commit(protocol, element, randomness))
+    }
+
+    /// The canonical encoding of the statement for the accumulator and the
+    /// compressed commitment.
+    pub fn statement(
+        &self,
+        accumulator: &<Rsa2048 as UnknownOrderGroup>::Elem,
+        commitment: &[u8],
+        epoch: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, SerializationError> {
+        match self {
+            AnyMembershipProtocol::Bls12_381SnarkRange(_)
+            | AnyMembershipProtocol::Bls12_381SnarkHash(_) => {
+                statement::<ark_bls12_381::G1Projective>(accumulator, commitment, epoch)
+            }
+            AnyMembershipProtocol::Bn254SnarkRange(_) => {
+                statement::<ark_bn254::G1Projective>(accumulator, commitment, epoch)
+            }
+            AnyMembershipProtocol::Bls12_377SnarkRange(_) => {
+                statement::<ark_bls12_377::G1Projective>(accumulator, commitment, epoch)
+            }
+            AnyMembershipProtocol::JubjubSigmaRange(_) => {
+                statement::<ark_ed_on_bls12_381::EdwardsProjective>(accumulator, commitment, epoch)
+            }
+        }
+    }
+
+    /// Creates a non-interactive proof for the canonical statement, returning
+    /// its canonical encoding.
+    pub fn prove<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &[u8],
+        witness: &Witness<Rsa2048>,
+        context: &[u8],
+    ) -> Result<Vec<u8>, AnyMembershipError> {
+        dispatch!(self, protocol => prove(protocol, rng1, rng2, statement, witness, context))
+    }
+
+    /// Verifies a proof created by `prove` with the same context, rejecting
+    /// malformed statements and proofs.
+    pub fn verify(
+        &self,
+        statement: &[u8],
+        proof: &[u8],
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        dispatch!(self, protocol => verify(protocol, statement, proof, context))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AnyMembershipProtocol, Instantiation};
+    use crate::protocols::membership::Witness;
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    #[test]
+    fn test_names() {
+        for instantiation in Instantiation::ALL.iter() {
+            assert_eq!(
+                instantiation.name().parse::<Instantiation>().unwrap(),
+                *instantiation
+            );
+        }
+        assert!("bls12_381/bp".parse::<Instantiation>().is_err());
+    }
+
+    #[test]
+    fn test_instantiations() {
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        for name in &["bn254/snark_range", "jubjub/sigma_range"] {
+            let instantiation = name.parse::<Instantiation>().unwrap();
+            let params = instantiation.default_parameters().unwrap();
+            let protocol =
+                AnyMembershipProtocol::setup(instantiation, &params, &mut rng1, &mut rng2).unwrap();
+            let protocol = AnyMembershipProtocol::from_crs_bytes(
+                instantiation,
+                &protocol.crs_bytes().unwrap(),
+            )
+            .unwrap();
+            assert_eq!(protocol.instantiation(), instantiation);
+
+            let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+                - &Integer::from(245);
+            let randomness = Integer::from(5);
+            let commitment = protocol.commit(&value, &randomness).unwrap();
+            let accum =
+                accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty(
+                )
+                .add_with_proof(&[value.clone()]);
+            let statement = protocol
+                .statement(&accum.0.value, &commitment, None)
+                .unwrap();
+            let proof = protocol
+                .prove(
+                    &mut rng1,
+                    &mut rng2,
+                    &statement,
+                    &Witness {
+                        e: value,
+                        r_q: randomness,
+                        w: accum.1.witness.0.value,
+                    },
+                    b"context",
+                )
+                .unwrap();
+            protocol.verify(&statement, &proof, b"context").unwrap();
+            assert!(protocol.verify(&statement, &proof, b"other").is_err());
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! The interface shared by the set membership backends: the accumulator-based
+//! `Protocol` of this module and the Merkle tree one of `protocols::merkle`.
+//! An application generic over `MembershipBackend` selects the backend with a
+//! type parameter and proves and verifies the same way with either.
+use crate::{
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        membership::{MembershipProof, Protocol, Statement, Witness},
+        ProofError, VerificationError,
+    },
+    utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
+};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+
+pub trait MembershipBackend {
+    type Statement;
+    type Witness;
+    type Proof;
+
+    /// Proves that the element committed to in the statement is in its set,
+    /// binding the proof to the context.
+    fn prove_noninteractive<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+        context: &[u8],
+    ) -> Result<Self::Proof, ProofError>;
+
+    /// Verifies a proof created by `prove_noninteractive` with the same
+    /// context.
+    fn verify_noninteractive(
+        &self,
+        statement: &Self::Statement,
+        proof: &Self::Proof,
+        context: &[u8],
+    ) -> Result<(), VerificationError>;
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MembershipBackend for Protocol<G, P, HP>
+{
+    type Statement = Statement<G, P>;
+    type Witness = Witness<G>;
+    type Proof = MembershipProof<G, P, HP>;
+
+    fn prove_noninteractive<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+        context: &[u8],
+    ) -> Result<Self::Proof, ProofError> {
+        Protocol::prove_noninteractive(self, rng1, rng2, statement, witness, context)
+    }
+
+    fn verify_noninteractive(
+        &self,
+        statement: &Self::Statement,
+        proof: &Self::Proof,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        Protocol::verify_noninteractive(self, statement, proof, context)
+    }
+}
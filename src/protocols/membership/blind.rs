@@ -0,0 +1,463 @@
+//! Blind issuance of membership witnesses: the accumulator manager inserts
+//! an element it only sees committed, so it can't tell which member later
+//! proves membership.
+//!
+//! The member raises the accumulator `acc` to the hashed element `e` itself
+//! and sends `acc^e` with a commitment `c_e_q` to the hashed element and a
+//! proof that the same `e` is the exponent and the committed value, and that
+//! it's the hash of the element, with the hash-to-prime proof on `c_e_q`. Its witness is then the
+//! accumulator before the insertion. The manager can't update the witnesses
+//! of blinded elements it doesn't know, so it publishes them, and each new
+//! member raises them to its element too, in the same proof:
+//!
+//! ```ignore
+//! let mut issuer = BlindIssuer::new(&crs);
+//! let request = protocol.request_insertion(&mut rng1, &mut rng2, issuer.accumulator(), issuer.witnesses(), &e, &r_q, b"join")?;
+//! let slot = issuer.issue(&request, b"join")?;
+//! // later, with the witnesses updated by the members who joined since
+//! let witness = Witness::new(e, r_q, issuer.witnesses()[slot].clone())?;
+//! ```
+//!
+//! The slots are only known to the manager and the member, and the
+//! membership proofs hide the witness, so they can't be linked to a slot.
+use crate::{
+    commitments::{pedersen::PedersenCommitment, Commitment},
+    protocols::{
+        batch::{CurveEquation, GroupEquation},
+        check_challenge, check_elem, check_masking, check_point, check_response,
+        hash_to_prime::{
+            transcript::{
+                TranscriptProverChannel as HashToPrimeTranscriptProverChannel,
+                TranscriptVerifierChannel as HashToPrimeTranscriptVerifierChannel,
+            },
+            HashToPrimeProtocol, Statement as HashToPrimeStatement, Witness as HashToPrimeWitness,
+        },
+        membership::{Protocol, CRS},
+        s_e_bound, ProofError, VerificationError,
+    },
+    transcript::{TranscriptProtocolChallenge, TranscriptProtocolCurve, TranscriptProtocolInteger},
+    utils::{
+        bigint_to_integer,
+        curve::{CurvePointProjective, Field},
+        integer_mod_q, integer_to_bigint_mod_q, random_symmetric_range,
+        ConvertibleUnknownOrderGroup,
+    },
+};
+use ark_serialize::SerializationError;
+use core::cell::RefCell;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+
+/// A member's request to insert its committed element: the accumulator and
+/// the published witnesses raised to the element, and the proof.
+pub struct BlindRequest<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+    pub accumulator: G::Elem,
+    pub witnesses: Vec<G::Elem>,
+    pub alphas: Vec<G::Elem>,
+    pub alpha_q: P,
+    pub s_e: Integer,
+    pub s_r_q: P::ScalarField,
+    pub proof_hash_to_prime: HP::Proof,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Protocol<G, P, HP>
+{
+    /// Absorbs the values raised to the element, before and after, and the
+    /// commitment to it.
+    fn insertion_transcript(
+        &self,
+        bases: &[G::Elem],
+        results: &[G::Elem],
+        c_e_q: &P,
+        context: &[u8],
+    ) -> Result<Transcript, SerializationError> {
+        let mut transcript = self.transcript(context);
+        transcript.append_message(b"dom-sep", b"blind insertion");
+        for (base, result) in bases.iter().zip(results) {
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"base", base);
+            TranscriptProtocolInteger::<G>::append_integer_point(
+                &mut transcript,
+                b"result",
+                result,
+            );
+        }
+        TranscriptProtocolCurve::<P>::append_curve_point(&mut transcript, b"c_e_q", c_e_q)
+            .map_err(SerializationError::from)?;
+        Ok(transcript)
+    }
+
+    /// Requests the insertion of the element into the accumulator of a
+    /// `BlindIssuer` with the published witnesses, committing to the hashed
+    /// element with the randomness `r_q`, as the membership statements do.
+    /// The witness of the element is the accumulator.
+    pub fn request_insertion<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        accumulator: &G::Elem,
+        witnesses: &[G::Elem],
+        e: &Integer,
+        r_q: &Integer,
+        context: &[u8],
+    ) -> Result<BlindRequest<G, P, HP>, ProofError> {
+        let parameters = &self.crs.parameters;
+        let pedersen = &self.crs.crs_modeq.pedersen_commitment_parameters;
+        let (hashed_e, _) = self.hash_to_prime(e)?;
+        let c_e_q = pedersen.commit(&hashed_e, r_q)?;
+        let bases = std::iter::once(accumulator)
+            .chain(witnesses)
+            .cloned()
+            .collect::<Vec<_>>();
+        let results = bases
+            .iter()
+            .map(|base| G::exp(base, &hashed_e))
+            .collect::<Vec<_>>();
+
+        let r_e_range = Integer::from(Integer::u_pow_u(
+            2,
+            (parameters.security_zk + parameters.security_soundness + parameters.hash_to_prime_bits)
+                as u32,
+        ));
+        check_masking(parameters, "e", &r_e_range, &hashed_e)?;
+        let r_e = random_symmetric_range(rng1, &r_e_range);
+        let r_r_q_field = P::ScalarField::rand(rng2);
+        let alphas = bases
+            .iter()
+            .map(|base| G::exp(base, &r_e))
+            .collect::<Vec<_>>();
+        let alpha_q = pedersen.commit(
+            &integer_mod_q::<P>(&r_e)?,
+            &bigint_to_integer::<P>(&r_r_q_field),
+        )?;
+
+        let mut transcript = self.insertion_transcript(&bases, &results, &c_e_q, context)?;
+        for alpha in &alphas {
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"alpha", alpha);
+        }
+        TranscriptProtocolCurve::<P>::append_curve_point(&mut transcript, b"alpha_q", &alpha_q)
+            .map_err(SerializationError::from)?;
+        let c = transcript.challenge_scalar(b"c", parameters.security_soundness);
+        check_challenge(parameters, &c)?;
+        let s_e = r_e - &c * &hashed_e;
+        let s_r_q = r_r_q_field
+            .sub(&integer_to_bigint_mod_q::<P>(r_q)?.mul(&integer_to_bigint_mod_q::<P>(&c)?));
+
+        let transcript = RefCell::new(transcript);
+        let mut verifier_channel =
+            HashToPrimeTranscriptVerifierChannel::new(&self.crs.crs_hash_to_prime, &transcript);
+        self.hash_to_prime
+            .prove(
+                &mut verifier_channel,
+                rng2,
+                &HashToPrimeStatement {
+                    c_e_q: c_e_q.clone(),
+                },
+                &HashToPrimeWitness {
+                    e: e.clone(),
+                    r_q: r_q.clone(),
+                },
+            )
+            .map_err(|err| err.in_protocol("hash_to_prime"))?;
+        let proof_hash_to_prime = verifier_channel.proof()?;
+
+        let mut results = results.into_iter();
+        Ok(BlindRequest {
+            c_e_q,
+            accumulator: results.next().expect("the accumulator is raised too"),
+            witnesses: results.collect(),
+            alphas,
+            alpha_q,
+            s_e,
+            s_r_q,
+            proof_hash_to_prime,
+        })
+    }
+
+    /// Verifies that the request raises the accumulator and the witnesses
+    /// to the same prime it commits to.
+    pub fn verify_insertion(
+        &self,
+        accumulator: &G::Elem,
+        witnesses: &[G::Elem],
+        request: &BlindRequest<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let parameters = &self.crs.parameters;
+        if request.witnesses.len() != witnesses.len() || request.alphas.len() != witnesses.len() + 1
+        {
+            return Err(VerificationError::BlindInsertionCheckFailed);
+        }
+        check_point("c_e_q", &request.c_e_q)?;
+        check_elem::<G>("accumulator", &request.accumulator)?;
+        check_point("alpha_q", &request.alpha_q)?;
+        for alpha in &request.alphas {
+            check_elem::<G>("alpha", alpha)?;
+        }
+        check_response("s_e", &request.s_e, &s_e_bound(parameters))?;
+
+        let bases = std::iter::once(accumulator)
+            .chain(witnesses)
+            .cloned()
+            .collect::<Vec<_>>();
+        let results = std::iter::once(&request.accumulator)
+            .chain(&request.witnesses)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut transcript =
+            self.insertion_transcript(&bases, &results, &request.c_e_q, context)?;
+        for alpha in &request.alphas {
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"alpha", alpha);
+        }
+        TranscriptProtocolCurve::<P>::append_curve_point(
+            &mut transcript,
+            b"alpha_q",
+            &request.alpha_q,
+        )
+        .map_err(SerializationError::from)?;
+        let c = transcript.challenge_scalar(b"c", parameters.security_soundness);
+
+        for ((base, result), alpha) in bases.iter().zip(&results).zip(&request.alphas) {
+            let equation = GroupEquation::<G> {
+                terms: vec![
+                    (base.clone(), request.s_e.clone()),
+                    (result.clone(), c.clone()),
+                ],
+                target: alpha.clone(),
+            };
+            if !equation.holds(None) {
+                return Err(VerificationError::BlindInsertionCheckFailed);
+            }
+        }
+        let pedersen = &self.crs.crs_modeq.pedersen_commitment_parameters;
+        let equation = CurveEquation {
+            bases: vec![
+                pedersen.g.clone(),
+                pedersen.h.clone(),
+                request.c_e_q.clone(),
+            ],
+            scalars: vec![
+                integer_to_bigint_mod_q::<P>(&request.s_e)?,
+                request.s_r_q.clone(),
+                integer_to_bigint_mod_q::<P>(&c)?,
+            ],
+            target: request.alpha_q.clone(),
+        };
+        if !equation.holds() {
+            return Err(VerificationError::BlindInsertionCheckFailed);
+        }
+
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel = HashToPrimeTranscriptProverChannel::new(
+            &self.crs.crs_hash_to_prime,
+            &transcript,
+            &request.proof_hash_to_prime,
+        );
+        self.hash_to_prime
+            .verify(
+                &mut prover_channel,
+                &HashToPrimeStatement {
+                    c_e_q: request.c_e_q.clone(),
+                },
+            )
+            .map_err(|err| err.in_protocol("hash_to_prime"))
+    }
+}
+
+/// The accumulator manager of blindly inserted elements, with the published
+/// witnesses of the members, by slot.
+pub struct BlindIssuer<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    protocol: Protocol<G, P, HP>,
+    accumulator: G::Elem,
+    witnesses: Vec<G::Elem>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    BlindIssuer<G, P, HP>
+{
+    pub fn new(crs: &CRS<G, P, HP>) -> Self {
+        BlindIssuer {
+            protocol: Protocol::from_crs(crs),
+            accumulator: G::unknown_order_elem(),
+            witnesses: vec![],
+        }
+    }
+
+    pub fn accumulator(&self) -> &G::Elem {
+        &self.accumulator
+    }
+
+    pub fn witnesses(&self) -> &[G::Elem] {
+        &self.witnesses
+    }
+
+    /// Inserts the element of a request made for the current accumulator
+    /// and witnesses, and returns the slot of its witness.
+    pub fn issue(
+        &mut self,
+        request: &BlindRequest<G, P, HP>,
+        context: &[u8],
+    ) -> Result<usize, VerificationError> {
+        self.protocol
+            .verify_insertion(&self.accumulator, &self.witnesses, request, context)?;
+        let witness = std::mem::replace(&mut self.accumulator, request.accumulator.clone());
+        self.witnesses = request.witnesses.clone();
+        self.witnesses.push(witness);
+        Ok(self.witnesses.len() - 1)
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::BlindIssuer;
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::{
+                snark_hash::{HashToPrimeHashParameters, Protocol as HPHashProtocol},
+                snark_range::Protocol as HPProtocol,
+            },
+            membership::{Protocol, Statement, Witness},
+            VerificationError,
+        },
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::{Group, Rsa2048};
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rug::Integer;
+
+    #[test]
+    fn test_blind_issuance() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [51u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([52u8; 32]);
+        let mut issuer = BlindIssuer::new(&protocol.crs);
+
+        let mut members = vec![];
+        let mut e = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32 - 1));
+        for _ in 0..3 {
+            e = e.next_prime();
+            let r_q = Integer::from(members.len() + 7);
+            let request = protocol
+                .request_insertion(
+                    &mut rng1,
+                    &mut rng2,
+                    issuer.accumulator(),
+                    issuer.witnesses(),
+                    &e,
+                    &r_q,
+                    b"join",
+                )
+                .unwrap();
+            let slot = issuer.issue(&request, b"join").unwrap();
+            members.push((e.clone(), slot));
+        }
+
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        for (e, slot) in members {
+            let r_q = Integer::from(3);
+            let statement = Statement::new(
+                issuer.accumulator().clone(),
+                pedersen.commit(&e, &r_q).unwrap(),
+            )
+            .unwrap();
+            let witness = Witness::new(e, r_q, issuer.witnesses()[slot].clone()).unwrap();
+            let proof = protocol
+                .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"member")
+                .unwrap();
+            protocol
+                .verify_noninteractive(&statement, &proof, b"member")
+                .unwrap();
+        }
+
+        // A request that doesn't raise the witnesses by its element, or was
+        // made for another context, is rejected.
+        let e = e.next_prime();
+        let mut request = protocol
+            .request_insertion(
+                &mut rng1,
+                &mut rng2,
+                issuer.accumulator(),
+                issuer.witnesses(),
+                &e,
+                &Integer::from(11),
+                b"join",
+            )
+            .unwrap();
+        assert!(issuer.issue(&request, b"other").is_err());
+        request.witnesses[0] = Rsa2048::exp(&request.witnesses[0], &Integer::from(2));
+        assert!(matches!(
+            issuer.issue(&request, b"join"),
+            Err(VerificationError::BlindInsertionCheckFailed)
+        ));
+        request.witnesses.pop();
+        assert!(issuer.issue(&request, b"join").is_err());
+        assert_eq!(issuer.witnesses().len(), 3);
+    }
+
+    #[test]
+    fn test_blind_issuance_hash() {
+        struct TestHashToPrimeParameters {}
+        impl HashToPrimeHashParameters for TestHashToPrimeParameters {
+            const MESSAGE_SIZE: u16 = 254;
+        }
+
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol = Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPHashProtocol<Bls12_381, TestHashToPrimeParameters>,
+        >::setup_deterministic(&params, [53u8; 32])
+        .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([54u8; 32]);
+        let mut issuer = BlindIssuer::new(&protocol.crs);
+
+        // The element is hashed to the prime accumulated and committed to.
+        let e = Integer::from(24_928_329);
+        let r_q = Integer::from(7);
+        let request = protocol
+            .request_insertion(
+                &mut rng1,
+                &mut rng2,
+                issuer.accumulator(),
+                issuer.witnesses(),
+                &e,
+                &r_q,
+                b"join",
+            )
+            .unwrap();
+        let slot = issuer.issue(&request, b"join").unwrap();
+
+        let (hashed_e, _) = protocol.hash_to_prime(&e).unwrap();
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        assert_eq!(request.c_e_q, pedersen.commit(&hashed_e, &r_q).unwrap());
+        let statement = Statement::new(
+            issuer.accumulator().clone(),
+            pedersen.commit(&hashed_e, &r_q).unwrap(),
+        )
+        .unwrap();
+        let witness = Witness::new(e, r_q, issuer.witnesses()[slot].clone()).unwrap();
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"member")
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"member")
+            .unwrap();
+    }
+}
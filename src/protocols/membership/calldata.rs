@@ -0,0 +1,292 @@
+//! Encodes a membership proof and its statement as EVM calldata.
+//!
+//! The encoding is the ABI encoding of the tuple
+//!
+//! ```text
+//! (bytes32 accumulatorDigest, bytes accumulator, uint256[] commitment,
+//!  bytes epoch, bytes[] elements, bytes[] integers, uint256 signs,
+//!  uint256[] points, uint256 scalar, bytes hashToPrime)
+//! ```
+//!
+//! so a Solidity verifier can take it as the arguments of a function:
+//!
+//! - `accumulatorDigest` is the keccak256 hash of `accumulator`, the
+//!   accumulator as a big-endian integer padded to the width of the group
+//!   elements.
+//! - `commitment` is the Pedersen commitment to the element, as its affine
+//!   coordinates x and y, each left-padded to whole words. For BN254 that's one
+//!   word per coordinate, as expected by the precompiles, and for BLS12-381 two,
+//!   as in EIP-2537.
+//! - `elements` holds c_e, the group elements of the root proof in message
+//!   order and alpha1 of the modeq proof, padded like the accumulator.
+//! - `integers` holds the magnitudes of the root proof's responses followed by
+//!   s_e and s_r of the modeq proof, with bit i of `signs` set if integer i is
+//!   negative.
+//! - `points` holds the coordinates of alpha2 of the modeq proof and `scalar`
+//!   its s_r_q.
+//! - `hashToPrime` is the uncompressed hash-to-prime proof.
+//!
+//! Group elements are encoded as integers, so the encoding is for RSA groups.
+use crate::{
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        membership::{MembershipProof, Statement},
+    },
+    utils::{
+        bigint_to_bytes,
+        curve::{CurveError, CurvePointProjective},
+        group::ElemFromBytes,
+        integer_to_bytes, ConvertibleUnknownOrderGroup,
+    },
+};
+use rug::Integer;
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+pub const WORD_SIZE: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum CalldataError {
+    #[error("an element doesn't fit in a uint256")]
+    ElementTooLarge,
+    #[error("too many integers for the calldata")]
+    TooManyIntegers,
+    #[error("could not encode a curve point")]
+    CurveError(#[from] CurveError),
+}
+
+enum Token {
+    Word([u8; WORD_SIZE]),
+    Bytes(Vec<u8>),
+    Words(Vec<[u8; WORD_SIZE]>),
+    BytesArray(Vec<Vec<u8>>),
+}
+
+fn word(value: usize) -> [u8; WORD_SIZE] {
+    let mut word = [0u8; WORD_SIZE];
+    word[WORD_SIZE - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// Left-pads big-endian bytes to whole words.
+fn words(bytes: &[u8]) -> Vec<[u8; WORD_SIZE]> {
+    let padded = left_pad(bytes, padded_length(bytes.len()));
+    padded
+        .chunks(WORD_SIZE)
+        .map(|chunk| {
+            let mut word = [0u8; WORD_SIZE];
+            word.copy_from_slice(chunk);
+            word
+        })
+        .collect()
+}
+
+fn padded_length(length: usize) -> usize {
+    (length + WORD_SIZE - 1) / WORD_SIZE * WORD_SIZE
+}
+
+fn left_pad(bytes: &[u8], width: usize) -> Vec<u8> {
+    let mut padded = vec![0u8; width - bytes.len()];
+    padded.extend_from_slice(bytes);
+    padded
+}
+
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&word(bytes.len()));
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + padded_length(bytes.len()) - bytes.len(), 0);
+}
+
+fn encode_tail(token: &Token) -> Vec<u8> {
+    let mut out = vec![];
+    match token {
+        Token::Word(_) => {}
+        Token::Bytes(bytes) => encode_bytes(&mut out, bytes),
+        Token::Words(words) => {
+            out.extend_from_slice(&word(words.len()));
+            for w in words {
+                out.extend_from_slice(w);
+            }
+        }
+        Token::BytesArray(array) => {
+            out.extend_from_slice(&word(array.len()));
+            let tails = array
+                .iter()
+                .map(|bytes| {
+                    let mut tail = vec![];
+                    encode_bytes(&mut tail, bytes);
+                    tail
+                })
+                .collect::<Vec<_>>();
+            let mut offset = WORD_SIZE * array.len();
+            for tail in &tails {
+                out.extend_from_slice(&word(offset));
+                offset += tail.len();
+            }
+            for tail in &tails {
+                out.extend_from_slice(tail);
+            }
+        }
+    }
+    out
+}
+
+/// The ABI encoding of a tuple of tokens: a head of one word per token,
+/// holding either the value or the offset of the dynamic data in the tail.
+fn encode_tokens(tokens: &[Token]) -> Vec<u8> {
+    let tails = tokens.iter().map(encode_tail).collect::<Vec<_>>();
+    let mut head = vec![];
+    let mut tail = vec![];
+    for (token, token_tail) in tokens.iter().zip(tails.iter()) {
+        match token {
+            Token::Word(w) => head.extend_from_slice(w),
+            _ => {
+                head.extend_from_slice(&word(WORD_SIZE * tokens.len() + tail.len()));
+                tail.extend_from_slice(token_tail);
+            }
+        }
+    }
+    head.extend_from_slice(&tail);
+    head
+}
+
+/// The width in bytes of the encoded group elements, which are smaller than
+/// twice the order upper bound.
+pub fn element_width<G: ConvertibleUnknownOrderGroup>() -> usize {
+    padded_length((G::order_upper_bound().significant_bits() as usize + 1 + 7) / 8)
+}
+
+fn element<G: ElemFromBytes>(elem: &G::Elem) -> Result<Vec<u8>, CalldataError> {
+    let bytes = G::elem_to_bytes(elem);
+    let width = element_width::<G>();
+    if bytes.len() > width {
+        return Err(CalldataError::ElementTooLarge);
+    }
+    Ok(left_pad(&bytes, width))
+}
+
+fn point<P: CurvePointProjective>(point: &P) -> Result<Vec<[u8; WORD_SIZE]>, CalldataError> {
+    let (x, y) = point.to_affine_coordinates()?;
+    let mut coordinates = words(&x);
+    coordinates.extend(words(&y));
+    Ok(coordinates)
+}
+
+/// The keccak256 hash of the padded accumulator, which a contract can store
+/// instead of the accumulator itself.
+pub fn accumulator_digest<G: ElemFromBytes>(
+    accumulator: &G::Elem,
+) -> Result<[u8; WORD_SIZE], CalldataError> {
+    let mut digest = [0u8; WORD_SIZE];
+    digest.copy_from_slice(&Keccak256::digest(&element::<G>(accumulator)?));
+    Ok(digest)
+}
+
+pub fn encode_calldata<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    statement: &Statement<G, P>,
+    proof: &MembershipProof<G, P, HP>,
+) -> Result<Vec<u8>, CalldataError> {
+    let root = &proof.proof_root;
+    let modeq = &proof.proof_modeq;
+
+    let mut elements = vec![element::<G>(&proof.c_e)?];
+    for elem in &[
+        &root.message1.c_w,
+        &root.message1.c_r,
+        &root.message2.alpha1,
+        &root.message2.alpha2,
+        &root.message2.alpha3,
+        &root.message2.alpha4,
+        &root.message4.q1,
+        &root.message4.q2,
+        &root.message4.q3,
+        &root.message4.q4,
+        &modeq.message1.alpha1,
+    ] {
+        elements.push(element::<G>(elem)?);
+    }
+
+    let integers: Vec<&Integer> = vec![
+        &root.message3.s_e,
+        &root.message3.s_r,
+        &root.message3.s_r_2,
+        &root.message3.s_r_3,
+        &root.message3.s_beta,
+        &root.message3.s_delta,
+        &modeq.message2.s_e,
+        &modeq.message2.s_r,
+    ];
+    if integers.len() > 8 * WORD_SIZE {
+        return Err(CalldataError::TooManyIntegers);
+    }
+    let mut signs = [0u8; WORD_SIZE];
+    for (i, integer) in integers.iter().enumerate() {
+        if **integer < 0 {
+            signs[WORD_SIZE - 1 - i / 8] |= 1 << (i % 8);
+        }
+    }
+    let magnitudes = integers
+        .iter()
+        .map(|integer| integer_to_bytes(&Integer::from(integer.abs_ref())))
+        .collect();
+
+    let scalar = words(&bigint_to_bytes::<P>(&modeq.message2.s_r_q));
+    if scalar.len() != 1 {
+        return Err(CalldataError::ElementTooLarge);
+    }
+
+    Ok(encode_tokens(&[
+        Token::Word(accumulator_digest::<G>(&statement.c_p)?),
+        Token::Bytes(element::<G>(&statement.c_p)?),
+        Token::Words(point(&statement.c_e_q)?),
+        Token::Bytes(statement.epoch.clone().unwrap_or_default()),
+        Token::BytesArray(elements),
+        Token::BytesArray(magnitudes),
+        Token::Word(signs),
+        Token::Words(point(&modeq.message1.alpha2)?),
+        Token::Word(scalar[0]),
+        Token::Bytes(HP::proof_to_bytes_uncompressed(&proof.proof_hash_to_prime)?),
+    ]))
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{encode_tokens, point, word, Token, WORD_SIZE};
+    use ark_bls12_381::G1Projective;
+    use ark_ec::ProjectiveCurve;
+
+    #[test]
+    fn test_abi_encoding() {
+        // abi.encode(uint256(1), bytes("ab"), bytes[]([hex"01"]))
+        let encoded = encode_tokens(&[
+            Token::Word(word(1)),
+            Token::Bytes(b"ab".to_vec()),
+            Token::BytesArray(vec![vec![1]]),
+        ]);
+        let words = encoded.chunks(WORD_SIZE).collect::<Vec<_>>();
+        assert_eq!(words.len(), 10);
+        assert_eq!(words[0], word(1));
+        assert_eq!(words[1], word(0x60));
+        assert_eq!(words[2], word(0xa0));
+        assert_eq!(words[3], word(2));
+        assert_eq!(&words[4][..2], b"ab");
+        assert_eq!(words[5], word(1));
+        assert_eq!(words[6], word(0x20));
+        assert_eq!(words[7], word(1));
+        assert_eq!(words[8][0], 1);
+        assert_eq!(words[9], [0u8; WORD_SIZE]);
+    }
+
+    #[test]
+    fn test_points() {
+        // BLS12-381 coordinates take 48 bytes, padded to two words each.
+        let generator = point(&G1Projective::prime_subgroup_generator()).unwrap();
+        assert_eq!(generator.len(), 4);
+        assert_eq!(generator[0][..16], [0u8; 16]);
+        assert_eq!(
+            generator[0][16..20],
+            [0x17, 0xf1, 0xd3, 0xa7],
+            "x starts with the generator's leading bytes"
+        );
+    }
+}
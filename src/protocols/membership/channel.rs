@@ -5,6 +5,8 @@ use crate::{
 };
 
 pub trait MembershipVerifierChannel<G: ConvertibleUnknownOrderGroup> {
+    /// Binds the proof to the accumulator state identifier.
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError>;
     fn send_c_e(
         &mut self,
         c_e: &<IntegerCommitment<G> as Commitment>::Instance,
@@ -12,7 +14,56 @@ pub trait MembershipVerifierChannel<G: ConvertibleUnknownOrderGroup> {
 }
 
 pub trait MembershipProverChannel<G: ConvertibleUnknownOrderGroup> {
+    /// Binds the proof to the accumulator state identifier.
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError>;
     fn receive_c_e(
         &mut self,
     ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError>;
 }
+
+/// Lends a channel, e.g., to a wrapper such as `InstrumentedChannel`.
+impl<G: ConvertibleUnknownOrderGroup, C: MembershipVerifierChannel<G> + ?Sized>
+    MembershipVerifierChannel<G> for &mut C
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        MembershipVerifierChannel::<G>::bind_epoch(&mut **self, epoch)
+    }
+    fn send_c_e(
+        &mut self,
+        c_e: &<IntegerCommitment<G> as Commitment>::Instance,
+    ) -> Result<(), ChannelError> {
+        (**self).send_c_e(c_e)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: MembershipProverChannel<G> + ?Sized>
+    MembershipProverChannel<G> for &mut C
+{
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        MembershipProverChannel::<G>::bind_epoch(&mut **self, epoch)
+    }
+    fn receive_c_e(
+        &mut self,
+    ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError> {
+        (**self).receive_c_e()
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncMembershipVerifierChannel<G: ConvertibleUnknownOrderGroup> {
+    async fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError>;
+    async fn send_c_e(
+        &mut self,
+        c_e: &<IntegerCommitment<G> as Commitment>::Instance,
+    ) -> Result<(), ChannelError>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncMembershipProverChannel<G: ConvertibleUnknownOrderGroup> {
+    async fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError>;
+    async fn receive_c_e(
+        &mut self,
+    ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError>;
+}
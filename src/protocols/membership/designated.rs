@@ -0,0 +1,437 @@
+//! Designated-verifier membership proofs, which convince the holder of a
+//! verifier secret and nobody else, for disclosures that mustn't be
+//! transferable to third parties.
+//!
+//! The proof shows knowledge of a witness for the statement OR of the
+//! verifier's secret key `x`, for the public key `g^x` on the base `g` of
+//! the Pedersen commitments. Before each Fiat-Shamir challenge, the prover
+//! absorbs the chameleon hash `g^m (g^x)^r` of the messages since the last
+//! challenge, with digest `m` and fresh randomness `r`, instead of the
+//! messages themselves, and adds `r` to the proof. Knowing `x`, the verifier
+//! can open the hashes to any messages (see `VerifierSecret::equivocate`),
+//! so it could have simulated the proof itself, for any statement: a proof
+//! tells the verifier that the prover knows a witness, since it doesn't know
+//! `x`, and tells a third party nothing.
+//!
+//! ```ignore
+//! let secret = VerifierSecret::generate(&crs, &mut rng);
+//! let proof = protocol.prove_designated(&mut rng1, &mut rng2, &statement, &witness, &secret.public_key(), b"context")?;
+//! protocol.verify_designated(&statement, &proof, &secret.public_key(), b"context")?;
+//! ```
+use crate::{
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        membership::{
+            transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+            MembershipProof, Protocol, Statement, VerifierKey, Witness, CRS,
+        },
+        ProofError, VerificationError,
+    },
+    transcript::FiatShamir,
+    utils::{
+        bigint_to_bytes, bytes_to_integer,
+        curve::{CurvePointProjective, Field},
+        group::ElemFromBytes,
+        integer_to_bigint_mod_q,
+        serialization::{read_bytes, write_bytes},
+        ConvertibleUnknownOrderGroup, Redacted,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+use blake2::{Blake2s, Digest};
+use core::cell::RefCell;
+use merlin::Transcript;
+use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+use rug::rand::MutRandState;
+use std::fmt;
+
+/// The verifier's secret key `x`, on the base `g` of the Pedersen
+/// commitments of a CRS.
+pub struct VerifierSecret<P: CurvePointProjective> {
+    base: P,
+    secret: P::ScalarField,
+}
+
+impl<P: CurvePointProjective> VerifierSecret<P> {
+    pub fn generate<
+        G: ConvertibleUnknownOrderGroup,
+        HP: HashToPrimeProtocol<P>,
+        R: RngCore + CryptoRng,
+    >(
+        crs: &CRS<G, P, HP>,
+        rng: &mut R,
+    ) -> Self {
+        VerifierSecret {
+            base: crs.crs_modeq.pedersen_commitment_parameters.g.clone(),
+            secret: P::ScalarField::rand(rng),
+        }
+    }
+
+    /// The public key `g^x` the provers designate the verifier with.
+    pub fn public_key(&self) -> P {
+        self.base.mul(&self.secret)
+    }
+
+    /// The randomness opening the chameleon hash of `digest` with
+    /// randomness `randomness` to `other`: `r + (m - m') / x`. Fails for a
+    /// zero secret key, which can't open the hashes.
+    pub fn equivocate(
+        &self,
+        digest: &P::ScalarField,
+        randomness: &P::ScalarField,
+        other: &P::ScalarField,
+    ) -> Result<P::ScalarField, ProofError> {
+        let inverse = self
+            .secret
+            .inverse()
+            .ok_or(ProofError::CouldNotCreateProof)?;
+        Ok(randomness.add(&digest.sub(other).mul(&inverse)))
+    }
+}
+
+impl<P: CurvePointProjective> fmt::Debug for VerifierSecret<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VerifierSecret")
+            .field("secret", &Redacted)
+            .finish()
+    }
+}
+
+fn chameleon_hash<P: CurvePointProjective>(
+    base: &P,
+    public_key: &P,
+    digest: &P::ScalarField,
+    randomness: &P::ScalarField,
+) -> P {
+    base.mul(digest).add(&public_key.mul(randomness))
+}
+
+/// A Fiat-Shamir transcript absorbing the chameleon hashes of the messages
+/// into the wrapped transcript. The prover draws the randomness of the
+/// hashes, and the verifier reads it from the proof.
+struct DesignatedTranscript<P: CurvePointProjective> {
+    transcript: Transcript,
+    pending: Blake2s,
+    base: P,
+    public_key: P,
+    rng: Option<StdRng>,
+    randomizers: Vec<P::ScalarField>,
+    position: usize,
+    failed: bool,
+}
+
+impl<P: CurvePointProjective> DesignatedTranscript<P> {
+    fn new(
+        base: &P,
+        public_key: &P,
+        rng: Option<StdRng>,
+        randomizers: Vec<P::ScalarField>,
+    ) -> Self {
+        DesignatedTranscript {
+            transcript: Transcript::new(b"cpsnarks-set designated"),
+            pending: Blake2s::default(),
+            base: base.clone(),
+            public_key: public_key.clone(),
+            rng,
+            randomizers,
+            position: 0,
+            failed: false,
+        }
+    }
+
+    fn next_randomizer(&mut self) -> Option<P::ScalarField> {
+        match self.rng.as_mut() {
+            Some(rng) => {
+                let randomizer = P::ScalarField::rand(rng);
+                self.randomizers.push(randomizer.clone());
+                Some(randomizer)
+            }
+            None => {
+                let randomizer = self.randomizers.get(self.position).cloned();
+                self.position += 1;
+                randomizer
+            }
+        }
+    }
+
+    /// Whether the verifier read exactly the randomizers of the proof.
+    fn is_complete(&self) -> bool {
+        !self.failed && (self.rng.is_some() || self.position == self.randomizers.len())
+    }
+}
+
+impl<P: CurvePointProjective> FiatShamir for DesignatedTranscript<P> {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.pending.update(&(label.len() as u64).to_be_bytes());
+        self.pending.update(label);
+        self.pending.update(&(message.len() as u64).to_be_bytes());
+        self.pending.update(message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        let digest = bytes_to_integer(&std::mem::take(&mut self.pending).finalize());
+        let hash = match (
+            integer_to_bigint_mod_q::<P>(&digest),
+            self.next_randomizer(),
+        ) {
+            (Ok(digest), Some(randomizer)) => {
+                chameleon_hash(&self.base, &self.public_key, &digest, &randomizer)
+                    .to_affine_bytes()
+                    .ok()
+            }
+            _ => None,
+        };
+        match hash {
+            Some(hash) => self.transcript.append_message(b"chameleon hash", &hash),
+            None => self.failed = true,
+        }
+        self.transcript.challenge_bytes(label, dest);
+    }
+}
+
+/// A membership proof designated to the holder of a verifier secret, with
+/// the randomness of the chameleon hashes absorbed before each challenge.
+pub struct DesignatedProof<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub proof: MembershipProof<G, P, HP>,
+    pub randomizers: Vec<P::ScalarField>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    DesignatedProof<G, P, HP>
+{
+    /// The compressed proof followed by the randomizers, each prefixed by
+    /// its length.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        write_bytes(&mut bytes, &self.proof.to_canonical_bytes()?)?;
+        for randomizer in &self.randomizers {
+            write_bytes(&mut bytes, &bigint_to_bytes::<P>(randomizer))?;
+        }
+        Ok(bytes)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    DesignatedProof<G, P, HP>
+{
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, SerializationError> {
+        let proof = MembershipProof::deserialize(&read_bytes(&mut bytes)?[..])?;
+        let mut randomizers = vec![];
+        while !bytes.is_empty() {
+            let randomizer = bytes_to_integer(&read_bytes(&mut bytes)?);
+            randomizers.push(
+                P::ScalarField::from_integer(&randomizer).ok_or(SerializationError::InvalidData)?,
+            );
+        }
+        Ok(DesignatedProof { proof, randomizers })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Protocol<G, P, HP>
+{
+    fn designated_transcript(
+        &self,
+        public_key: &P,
+        context: &[u8],
+        rng: Option<StdRng>,
+        randomizers: Vec<P::ScalarField>,
+    ) -> Result<DesignatedTranscript<P>, SerializationError> {
+        let mut transcript = DesignatedTranscript::new(
+            &self.crs.crs_modeq.pedersen_commitment_parameters.g,
+            public_key,
+            rng,
+            randomizers,
+        );
+        self.bind_transcript(&mut transcript, context);
+        transcript.append_message(b"designated verifier", &public_key.to_affine_bytes()?);
+        Ok(transcript)
+    }
+
+    /// Proves membership to the verifier with the public key, as
+    /// `prove_noninteractive` does with the context.
+    pub fn prove_designated<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        public_key: &P,
+        context: &[u8],
+    ) -> Result<DesignatedProof<G, P, HP>, ProofError> {
+        let mut seed = [0u8; 32];
+        rng2.fill_bytes(&mut seed);
+        let transcript = RefCell::new(self.designated_transcript(
+            public_key,
+            context,
+            Some(StdRng::from_seed(seed)),
+            vec![],
+        )?);
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.prove(&mut verifier_channel, rng1, rng2, statement, witness)?;
+        let proof = verifier_channel.proof()?;
+        let transcript = transcript.into_inner();
+        if !transcript.is_complete() {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+        Ok(DesignatedProof {
+            proof,
+            randomizers: transcript.randomizers,
+        })
+    }
+
+    /// Verifies a proof created by `prove_designated` for the public key
+    /// with the same context. Only the public key is needed, but only the
+    /// holder of its secret is convinced.
+    pub fn verify_designated(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &DesignatedProof<G, P, HP>,
+        public_key: &P,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let transcript = RefCell::new(self.designated_transcript(
+            public_key,
+            context,
+            None,
+            proof.randomizers.clone(),
+        )?);
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, &proof.proof);
+        self.verify(&mut prover_channel, statement)?;
+        if !transcript.into_inner().is_complete() {
+            return Err(VerificationError::RandomizerCountMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    VerifierKey<G, P, HP>
+{
+    /// Verifies as `Protocol::verify_designated` does.
+    pub fn verify_designated(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &DesignatedProof<G, P, HP>,
+        public_key: &P,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = DesignatedTranscript::new(
+            &self.crs_modeq.pedersen_commitment_parameters.g,
+            public_key,
+            None,
+            proof.randomizers.clone(),
+        );
+        self.bind_transcript(&mut transcript, context);
+        transcript.append_message(b"designated verifier", &public_key.to_affine_bytes()?);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel =
+            TranscriptProverChannel::with_verifier_key(self, &transcript, &proof.proof);
+        self.verify(&mut prover_channel, statement)?;
+        if !transcript.into_inner().is_complete() {
+            return Err(VerificationError::RandomizerCountMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{chameleon_hash, DesignatedProof, VerifierSecret};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+        utils::{curve::Field, rng::seeded_rngs},
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+    use rug::Integer;
+
+    #[test]
+    fn test_designated_verifier() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [41u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([42u8; 32]);
+        let secret = VerifierSecret::generate(&protocol.crs, &mut rng2);
+        let other = VerifierSecret::generate(&protocol.crs, &mut rng2);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 245;
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement::new(accum.0.value, commitment).unwrap();
+        let witness = Witness::new(value, randomness, accum.1.witness.0.value).unwrap();
+
+        let proof = protocol
+            .prove_designated(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &witness,
+                &secret.public_key(),
+                b"context",
+            )
+            .unwrap();
+        let proof = DesignatedProof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_bytes(
+            &proof.to_bytes().unwrap(),
+        )
+        .unwrap();
+        protocol
+            .verify_designated(&statement, &proof, &secret.public_key(), b"context")
+            .unwrap();
+        protocol
+            .crs
+            .verifier_key()
+            .verify_designated(&statement, &proof, &secret.public_key(), b"context")
+            .unwrap();
+        assert!(protocol
+            .verify_designated(&statement, &proof, &other.public_key(), b"context")
+            .is_err());
+        assert!(protocol
+            .verify_designated(&statement, &proof, &secret.public_key(), b"other")
+            .is_err());
+        let mut truncated = proof;
+        truncated.randomizers.pop();
+        assert!(protocol
+            .verify_designated(&statement, &truncated, &secret.public_key(), b"context")
+            .is_err());
+
+        // The verifier opens the chameleon hashes to any messages, so it
+        // could have created the proof itself.
+        let base = &protocol.crs.crs_modeq.pedersen_commitment_parameters.g;
+        let (digest, randomizer, forged) = (
+            Fr::rand(&mut rng2),
+            Fr::rand(&mut rng2),
+            Fr::rand(&mut rng2),
+        );
+        let opening = secret.equivocate(&digest, &randomizer, &forged).unwrap();
+        assert_eq!(
+            chameleon_hash(base, &secret.public_key(), &digest, &randomizer),
+            chameleon_hash(base, &secret.public_key(), &forged, &opening)
+        );
+        assert_ne!(
+            chameleon_hash(base, &other.public_key(), &digest, &randomizer),
+            chameleon_hash(base, &other.public_key(), &forged, &opening)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! A JSON envelope holding a membership proof together with its statement.
+//!
+//! Each component is named and encoded as URL-safe base64 without padding, so
+//! the envelope can be passed as is through REST APIs and message queues:
+//!
+//! ```json
+//! {"version":1,"statement":{"c_p":"...","c_e_q":"..."},
+//!  "proof":{"c_e":"...","root":"...","modeq":"...","hash_to_prime":"..."}}
+//! ```
+use crate::{
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        membership::{MembershipProof, Statement},
+        modeq::Proof as ModEqProof,
+        root::Proof as RootProof,
+    },
+    utils::{
+        curve::{CurveError, CurvePointProjective},
+        group::ElemFromBytes,
+        ConvertibleUnknownOrderGroup,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const ENVELOPE_VERSION: u16 = 1;
+
+#[derive(Debug, Error)]
+pub enum EnvelopeError {
+    #[error("unsupported envelope version {0}")]
+    UnsupportedVersion(u16),
+    #[error("invalid group element in {0}")]
+    InvalidElement(&'static str),
+    #[error("invalid JSON")]
+    JsonError(#[from] serde_json::Error),
+    #[error("invalid base64")]
+    Base64Error(#[from] base64::DecodeError),
+    #[error("could not serialize or deserialize a component")]
+    SerializationError(#[from] SerializationError),
+    #[error("could not encode or decode a curve point")]
+    CurveError(#[from] CurveError),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementEnvelope {
+    /// The accumulator.
+    pub c_p: String,
+    /// The Pedersen commitment to the element.
+    pub c_e_q: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofEnvelope {
+    pub c_e: String,
+    pub root: String,
+    pub modeq: String,
+    pub hash_to_prime: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u16,
+    pub statement: StatementEnvelope,
+    pub proof: ProofEnvelope,
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode(field: &str) -> Result<Vec<u8>, EnvelopeError> {
+    Ok(base64::decode_config(field, base64::URL_SAFE_NO_PAD)?)
+}
+
+fn to_bytes<T: CanonicalSerialize>(value: &T) -> Result<Vec<u8>, EnvelopeError> {
+    let mut bytes = vec![];
+    value.serialize(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn decode_elem<G: ElemFromBytes>(
+    field: &str,
+    name: &'static str,
+) -> Result<G::Elem, EnvelopeError> {
+    G::elem_from_bytes(&decode(field)?).ok_or(EnvelopeError::InvalidElement(name))
+}
+
+impl Envelope {
+    pub fn new<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+    >(
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+    ) -> Result<Envelope, EnvelopeError> {
+        Ok(Envelope {
+            version: ENVELOPE_VERSION,
+            statement: StatementEnvelope {
+                c_p: encode(&G::elem_to_bytes(&statement.c_p)),
+                c_e_q: encode(&statement.c_e_q.to_affine_bytes()?),
+                epoch: statement.epoch.as_ref().map(|epoch| encode(epoch)),
+            },
+            proof: ProofEnvelope {
+                c_e: encode(&G::elem_to_bytes(&proof.c_e)),
+                root: encode(&to_bytes(&proof.proof_root)?),
+                modeq: encode(&to_bytes(&proof.proof_modeq)?),
+                hash_to_prime: encode(&HP::proof_to_bytes(&proof.proof_hash_to_prime)?),
+            },
+        })
+    }
+
+    pub fn statement<G: ElemFromBytes, P: CurvePointProjective>(
+        &self,
+    ) -> Result<Statement<G, P>, EnvelopeError> {
+        Ok(Statement {
+            c_p: decode_elem::<G>(&self.statement.c_p, "c_p")?,
+            c_e_q: P::from_affine_bytes(&decode(&self.statement.c_e_q)?)?,
+            epoch: self
+                .statement
+                .epoch
+                .as_ref()
+                .map(|epoch| decode(epoch))
+                .transpose()?,
+        })
+    }
+
+    pub fn proof<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+        &self,
+    ) -> Result<MembershipProof<G, P, HP>, EnvelopeError> {
+        Ok(MembershipProof {
+            c_e: decode_elem::<G>(&self.proof.c_e, "c_e")?,
+            proof_root: RootProof::deserialize(&decode(&self.proof.root)?[..])?,
+            proof_modeq: ModEqProof::deserialize(&decode(&self.proof.modeq)?[..])?,
+            proof_hash_to_prime: HP::proof_from_bytes(&decode(&self.proof.hash_to_prime)?)?,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, EnvelopeError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parses an envelope, rejecting other versions.
+    pub fn from_json(json: &str) -> Result<Envelope, EnvelopeError> {
+        let envelope: Envelope = serde_json::from_str(json)?;
+        if envelope.version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(envelope.version));
+        }
+        Ok(envelope)
+    }
+}
+
+/// Encodes a statement and a proof for it as a JSON envelope.
+pub fn encode_envelope<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    statement: &Statement<G, P>,
+    proof: &MembershipProof<G, P, HP>,
+) -> Result<String, EnvelopeError> {
+    Envelope::new(statement, proof)?.to_json()
+}
+
+/// Decodes the statement and the proof from a JSON envelope.
+pub fn decode_envelope<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    json: &str,
+) -> Result<(Statement<G, P>, MembershipProof<G, P, HP>), EnvelopeError> {
+    let envelope = Envelope::from_json(json)?;
+    Ok((envelope.statement()?, envelope.proof()?))
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{decode_envelope, encode_envelope, Envelope, EnvelopeError};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    const LARGE_PRIMES: [u64; 3] = [
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_envelope() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        let proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w: accum.1.witness.0.value,
+                },
+                b"session",
+            )
+            .unwrap();
+
+        let json = encode_envelope(&statement, &proof).unwrap();
+        let (statement, proof) =
+            decode_envelope::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(&json).unwrap();
+        assert_eq!(statement.epoch, Some(b"epoch-1".to_vec()));
+        protocol
+            .verify_noninteractive(&statement, &proof, b"session")
+            .unwrap();
+
+        let mut envelope = Envelope::from_json(&json).unwrap();
+        envelope.version += 1;
+        assert!(matches!(
+            Envelope::from_json(&envelope.to_json().unwrap()),
+            Err(EnvelopeError::UnsupportedVersion(2))
+        ));
+
+        let mut envelope = Envelope::from_json(&json).unwrap();
+        envelope.proof.c_e = "not base64!".to_string();
+        assert!(matches!(
+            envelope.proof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(),
+            Err(EnvelopeError::Base64Error(_))
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+//! A prover and a verifier for the common case of proving that a committed
+//! element is in an accumulator. They load the CRS, commit to the element,
+//! start the transcripts and run the channels, so that proving and verifying
+//! take a few calls:
+//!
+//! ```ignore
+//! let mut prover = MembershipProver::from_crs_bytes(&crs_bytes)?.with_context(b"session");
+//! let (statement, proof) = prover.prove(&accumulator, &element, &witness)?;
+//!
+//! let verifier = MembershipVerifier::from_bytes(&vk_bytes)?.with_context(b"session");
+//! verifier.verify(&statement, &proof)?;
+//! ```
+use crate::{
+    commitments::Commitment,
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        membership::{
+            verify_bytes_with_context, MembershipProof, Protocol, Statement, VerifierKey, Witness,
+            CRS,
+        },
+        ProofError, VerificationError,
+    },
+    utils::{
+        bigint_to_integer,
+        curve::{CurvePointProjective, Field},
+        group::ElemFromBytes,
+        rng::secure_rngs,
+        ConvertibleUnknownOrderGroup,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use rug::rand::{MutRandState, RandState};
+use rug::Integer;
+
+/// Proves membership of elements under a CRS, with the generators seeded
+/// from the operating system.
+pub struct MembershipProver<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    protocol: Protocol<G, P, HP>,
+    context: Vec<u8>,
+    rng1: RandState<'static>,
+    rng2: ChaCha20Rng,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MembershipProver<G, P, HP>
+{
+    pub fn new(crs: &CRS<G, P, HP>) -> Self {
+        let (rng1, rng2) = secure_rngs(&mut OsRng);
+        MembershipProver {
+            protocol: Protocol::from_crs(crs),
+            context: vec![],
+            rng1,
+            rng2,
+        }
+    }
+
+    /// Sets the context the proofs are bound to, e.g., a session or request
+    /// identifier. It's empty by default.
+    pub fn with_context(mut self, context: &[u8]) -> Self {
+        self.context = context.to_vec();
+        self
+    }
+
+    pub fn protocol(&self) -> &Protocol<G, P, HP> {
+        &self.protocol
+    }
+
+    /// Commits to the element with fresh randomness and proves that it's in
+    /// the accumulator, given its membership witness, i.e., the root of the
+    /// accumulator by the element. Returns the statement the proof is for.
+    pub fn prove(
+        &mut self,
+        accumulator: &G::Elem,
+        element: &Integer,
+        witness: &G::Elem,
+    ) -> Result<(Statement<G, P>, MembershipProof<G, P, HP>), ProofError> {
+        let (rng1, rng2) = (&mut self.rng1, &mut self.rng2);
+        prove_with(
+            &self.protocol,
+            &self.context,
+            rng1,
+            rng2,
+            accumulator,
+            element,
+            witness,
+        )
+    }
+
+    /// Like `prove`, with the given generators.
+    pub fn prove_with_rngs<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        accumulator: &G::Elem,
+        element: &Integer,
+        witness: &G::Elem,
+    ) -> Result<(Statement<G, P>, MembershipProof<G, P, HP>), ProofError> {
+        prove_with(
+            &self.protocol,
+            &self.context,
+            rng1,
+            rng2,
+            accumulator,
+            element,
+            witness,
+        )
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MembershipProver<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    /// Loads a CRS written by `CRS::serialize_to`.
+    pub fn from_crs_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(Self::new(&CRS::deserialize_from(bytes)?))
+    }
+}
+
+fn prove_with<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    R1: MutRandState,
+    R2: RngCore + CryptoRng,
+>(
+    protocol: &Protocol<G, P, HP>,
+    context: &[u8],
+    rng1: &mut R1,
+    rng2: &mut R2,
+    accumulator: &G::Elem,
+    element: &Integer,
+    witness: &G::Elem,
+) -> Result<(Statement<G, P>, MembershipProof<G, P, HP>), ProofError> {
+    let r_q = bigint_to_integer::<P>(&P::ScalarField::rand(rng2));
+    let statement = Statement {
+        c_p: accumulator.clone(),
+        c_e_q: protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(element, &r_q)?,
+        epoch: None,
+    };
+    let proof = protocol.prove_noninteractive(
+        rng1,
+        rng2,
+        &statement,
+        &Witness {
+            e: element.clone(),
+            r_q,
+            w: witness.clone(),
+        },
+        context,
+    )?;
+    Ok((statement, proof))
+}
+
+/// Verifies membership proofs with the verifier key only.
+pub struct MembershipVerifier<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    key: VerifierKey<G, P, HP>,
+    context: Vec<u8>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MembershipVerifier<G, P, HP>
+{
+    pub fn new(key: VerifierKey<G, P, HP>) -> Self {
+        MembershipVerifier {
+            key,
+            context: vec![],
+        }
+    }
+
+    pub fn from_crs(crs: &CRS<G, P, HP>) -> Self {
+        Self::new(crs.verifier_key())
+    }
+
+    /// Sets the context the proofs have to be bound to, the one of the
+    /// prover. It's empty by default.
+    pub fn with_context(mut self, context: &[u8]) -> Self {
+        self.context = context.to_vec();
+        self
+    }
+
+    pub fn verify(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+    ) -> Result<(), VerificationError> {
+        self.key
+            .verify_noninteractive(statement, proof, &self.context)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    MembershipVerifier<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    /// Loads a verifier key written by `VerifierKey::serialize_to`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(Self::new(VerifierKey::deserialize_from(bytes)?))
+    }
+
+    /// Verifies a canonical statement and a compressed proof, as written by
+    /// `to_canonical_bytes`, rejecting trailing bytes and proofs that don't
+    /// validate against the parameters.
+    pub fn verify_bytes(
+        &self,
+        statement_bytes: &[u8],
+        proof_bytes: &[u8],
+    ) -> Result<(), VerificationError> {
+        let statement = Statement::<G, P>::from_canonical_bytes(statement_bytes)?;
+        verify_bytes_with_context(&self.key, &statement, proof_bytes, &self.context)
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{MembershipProver, MembershipVerifier};
+    use crate::{
+        parameters::Parameters,
+        protocols::{hash_to_prime::snark_range::Protocol as HPProtocol, membership::Protocol},
+    };
+    use accumulator::{group::Rsa2048, AccumulatorWithoutHashToPrime};
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rug::Integer;
+
+    #[test]
+    fn test_prover_and_verifier() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+            &params, [3u8; 32],
+        )
+        .unwrap()
+        .crs;
+        let mut crs_bytes = vec![];
+        crs.serialize_to(&mut crs_bytes, true).unwrap();
+        let mut vk_bytes = vec![];
+        crs.verifier_key()
+            .serialize_to(&mut vk_bytes, true)
+            .unwrap();
+
+        let element = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 245;
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[element.clone()]);
+
+        let mut prover =
+            MembershipProver::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs_bytes(
+                &crs_bytes,
+            )
+            .unwrap()
+            .with_context(b"session");
+        let (statement, proof) = prover
+            .prove(&accum.0.value, &element, &accum.1.witness.0.value)
+            .unwrap();
+
+        let verifier =
+            MembershipVerifier::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_bytes(
+                &vk_bytes,
+            )
+            .unwrap()
+            .with_context(b"session");
+        verifier.verify(&statement, &proof).unwrap();
+        verifier
+            .verify_bytes(
+                &statement.to_canonical_bytes().unwrap(),
+                &proof.to_canonical_bytes().unwrap(),
+            )
+            .unwrap();
+        let mut trailing = proof.to_canonical_bytes().unwrap();
+        trailing.push(0);
+        assert!(verifier
+            .verify_bytes(&statement.to_canonical_bytes().unwrap(), &trailing)
+            .is_err());
+        assert!(verifier
+            .with_context(b"other session")
+            .verify(&statement, &proof)
+            .is_err());
+    }
+}
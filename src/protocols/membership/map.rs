@@ -0,0 +1,591 @@
+//! Membership proofs for key-value maps, e.g., registries binding DIDs to
+//! keys. The map accumulates a prime for each pair, and a holder proves that
+//! the key and the value in two Pedersen commitments are a pair of the map,
+//! without revealing either:
+//!
+//! ```ignore
+//! let layout = MapLayout::new(&protocol.crs.parameters)?;
+//! let (prime, _) = layout.pair_to_prime(&key, &value)?;
+//! // ... the registry accumulates the prime and gives the holder its witness
+//! let statement = PairStatement { c_p: acc, c_key, c_value };
+//! let proof = protocol.prove_pair(&mut rng1, &mut rng2, &layout, &statement, &witness, b"lookup")?;
+//! protocol.verify_pair(&layout, &statement, &proof, b"lookup")?;
+//! ```
+//!
+//! The prime of a pair is its fields written one after the other, the key,
+//! the value and a nonce, under the top bit of `hash_to_prime_bits` bits,
+//! with the smallest nonce that makes it prime: the commitment to the prime
+//! is `g^{2^{μ-1}} c_key^{2^{V+T}} c_value^{2^T} c_nonce`, for values of V
+//! bits and nonces of T bits, which the verifier computes itself. Range
+//! proofs for the three commitments make the split unique, so the key and
+//! value can't be shifted into each other. The elements are used as they
+//! are, so this needs a range backend, e.g., `sigma_range` or `snark_range`,
+//! whose `hash_to_prime` is the identity.
+//!
+//! Keys and values longer than their fields, e.g., public keys, are rejected
+//! rather than hashed to them: the commitments are to the key and the value
+//! themselves, and the proof would have to show the hash in a circuit. They
+//! can be hashed by the application before committing to them.
+use crate::{
+    commitments::{pedersen::PedersenCommitment, Commitment},
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{
+            sigma_range::{Protocol as SigmaRangeProtocol, SigmaRangeParameters, SigmaRangeProof},
+            transcript::{
+                TranscriptProverChannel as HashToPrimeTranscriptProverChannel,
+                TranscriptVerifierChannel as HashToPrimeTranscriptVerifierChannel,
+            },
+            CRSHashToPrime, HashToPrimeError, HashToPrimeProtocol,
+            Statement as HashToPrimeStatement, Witness as HashToPrimeWitness,
+        },
+        membership::{MembershipProof, Protocol, Statement, Witness},
+        CRSError, ProofError, VerificationError,
+    },
+    utils::{
+        bigint_to_integer,
+        curve::{CurveError, CurvePointProjective, Field},
+        integer_mod_q, integer_to_bigint_mod_q, zeroize_integer, ConvertibleUnknownOrderGroup,
+        Redacted, RedactedInteger,
+    },
+};
+use ark_serialize::SerializationError;
+use core::cell::RefCell;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::integer::IsPrime;
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::fmt;
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+/// Enough nonces to find a prime except with probability about 2^{-30}.
+const NONCE_BITS: u16 = 16;
+
+/// How the key, the value and the nonce of a pair are laid out in its prime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MapLayout {
+    key_bits: u16,
+    value_bits: u16,
+    nonce_bits: u16,
+}
+
+impl MapLayout {
+    /// Splits the bits under the top one of `hash_to_prime_bits` between the
+    /// nonce, the value and the key, the key taking the odd bit. Fails if
+    /// there are fewer than two bits left for the key and the value.
+    pub fn new(parameters: &Parameters) -> Result<MapLayout, CRSError> {
+        let bits = parameters
+            .hash_to_prime_bits
+            .checked_sub(1 + NONCE_BITS)
+            .filter(|bits| *bits >= 2)
+            .ok_or(CRSError::InvalidParameters)?;
+        Ok(MapLayout {
+            key_bits: bits - bits / 2,
+            value_bits: bits / 2,
+            nonce_bits: NONCE_BITS,
+        })
+    }
+
+    pub fn key_bits(&self) -> u16 {
+        self.key_bits
+    }
+
+    pub fn value_bits(&self) -> u16 {
+        self.value_bits
+    }
+
+    fn power_of_two(bits: u16) -> Integer {
+        Integer::from(Integer::u_pow_u(2, bits as u32))
+    }
+
+    fn total_bits(&self) -> u16 {
+        self.key_bits + self.value_bits + self.nonce_bits + 1
+    }
+
+    /// The prime accumulated for the pair, and its nonce. Keys and values
+    /// longer than their bits are rejected with `ValueTooBig`.
+    pub fn pair_to_prime(
+        &self,
+        key: &Integer,
+        value: &Integer,
+    ) -> Result<(Integer, Integer), HashToPrimeError> {
+        if *key < 0
+            || *value < 0
+            || key.significant_bits() > self.key_bits as u32
+            || value.significant_bits() > self.value_bits as u32
+        {
+            return Err(HashToPrimeError::ValueTooBig);
+        }
+        let base = Self::power_of_two(self.total_bits() - 1)
+            + Integer::from(key << (self.value_bits + self.nonce_bits) as u32)
+            + Integer::from(value << self.nonce_bits as u32);
+        let mut nonce = Integer::from(1);
+        while nonce < Self::power_of_two(self.nonce_bits) {
+            let candidate = Integer::from(&base + &nonce);
+            if candidate.is_probably_prime(30) != IsPrime::No {
+                return Ok((candidate, nonce));
+            }
+            nonce += 2;
+        }
+        Err(HashToPrimeError::CouldNotFindIndex)
+    }
+
+    /// The commitment to the prime of the pair in the commitments.
+    fn combine<P: CurvePointProjective>(
+        &self,
+        pedersen: &PedersenCommitment<P>,
+        c_key: &P,
+        c_value: &P,
+        c_nonce: &P,
+    ) -> Result<P, Integer> {
+        let shift = |bits: u16| integer_to_bigint_mod_q::<P>(&Self::power_of_two(bits));
+        Ok(P::msm(
+            &[
+                pedersen.g.clone(),
+                c_key.clone(),
+                c_value.clone(),
+                c_nonce.clone(),
+            ],
+            &[
+                shift(self.total_bits() - 1)?,
+                shift(self.value_bits + self.nonce_bits)?,
+                shift(self.nonce_bits)?,
+                shift(0)?,
+            ],
+        ))
+    }
+}
+
+pub struct PairStatement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    pub c_p: G::Elem,
+    pub c_key: <PedersenCommitment<P> as Commitment>::Instance,
+    pub c_value: <PedersenCommitment<P> as Commitment>::Instance,
+}
+
+/// The openings of the commitments to the key and the value, and the
+/// membership witness of their prime.
+pub struct PairWitness<G: ConvertibleUnknownOrderGroup> {
+    key: Integer,
+    r_key: Integer,
+    value: Integer,
+    r_value: Integer,
+    w: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> PairWitness<G> {
+    pub fn new(
+        key: Integer,
+        r_key: Integer,
+        value: Integer,
+        r_value: Integer,
+        w: G::Elem,
+    ) -> PairWitness<G> {
+        PairWitness {
+            key,
+            r_key,
+            value,
+            r_value,
+            w,
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for PairWitness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.key);
+        zeroize_integer(&mut self.r_key);
+        zeroize_integer(&mut self.value);
+        zeroize_integer(&mut self.r_value);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for PairWitness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PairWitness")
+            .field("key", &RedactedInteger(&self.key))
+            .field("r_key", &RedactedInteger(&self.r_key))
+            .field("value", &RedactedInteger(&self.value))
+            .field("r_value", &RedactedInteger(&self.r_value))
+            .field("w", &Redacted)
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for PairWitness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// A membership proof for the combined commitment, with the commitment to
+/// the nonce and the range proofs of the three fields.
+pub struct PairProof<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub c_nonce: P,
+    pub proof: MembershipProof<G, P, HP>,
+    pub range_key: SigmaRangeProof<P>,
+    pub range_value: SigmaRangeProof<P>,
+    pub range_nonce: SigmaRangeProof<P>,
+}
+
+/// Binds the membership and range proofs to the layout and to the
+/// commitments the pair was combined from.
+fn pair_context<P: CurvePointProjective>(
+    layout: &MapLayout,
+    context: &[u8],
+    commitments: &[&P],
+) -> Result<Vec<u8>, CurveError> {
+    let mut bytes = b"cpsnarks-set key-value pair/".to_vec();
+    for bits in &[layout.key_bits, layout.value_bits, layout.nonce_bits] {
+        bytes.extend_from_slice(&bits.to_be_bytes());
+    }
+    for commitment in commitments {
+        bytes.extend_from_slice(&commitment.to_affine_bytes()?);
+    }
+    bytes.extend_from_slice(context);
+    Ok(bytes)
+}
+
+/// The range proof of a field of `bits` bits, as a sigma range proof that
+/// the field with an extra top bit has `bits + 1` bits.
+fn range_crs<P: CurvePointProjective>(
+    parameters: &Parameters,
+    pedersen: &PedersenCommitment<P>,
+    bits: u16,
+) -> CRSHashToPrime<P, SigmaRangeProtocol<P>> {
+    let mut parameters = parameters.clone();
+    parameters.hash_to_prime_bits = bits + 1;
+    CRSHashToPrime {
+        parameters,
+        pedersen_commitment_parameters: pedersen.clone(),
+        hash_to_prime_parameters: Arc::new(SigmaRangeParameters),
+    }
+}
+
+fn top_bit<P: CurvePointProjective>(
+    pedersen: &PedersenCommitment<P>,
+    commitment: &P,
+    bits: u16,
+) -> Result<P, Integer> {
+    Ok(commitment.add(
+        &pedersen
+            .g
+            .mul(&integer_to_bigint_mod_q::<P>(&MapLayout::power_of_two(
+                bits,
+            ))?),
+    ))
+}
+
+fn prove_range<P: CurvePointProjective, R: RngCore + CryptoRng>(
+    crs: &CRSHashToPrime<P, SigmaRangeProtocol<P>>,
+    transcript: Transcript,
+    rng: &mut R,
+    commitment: &P,
+    field: &Integer,
+    randomness: &Integer,
+) -> Result<SigmaRangeProof<P>, ProofError> {
+    let bits = crs.parameters.hash_to_prime_bits - 1;
+    let transcript = RefCell::new(transcript);
+    let mut verifier_channel = HashToPrimeTranscriptVerifierChannel::new(crs, &transcript);
+    SigmaRangeProtocol::from_crs(crs).prove(
+        &mut verifier_channel,
+        rng,
+        &HashToPrimeStatement {
+            c_e_q: top_bit(&crs.pedersen_commitment_parameters, commitment, bits)?,
+        },
+        &HashToPrimeWitness {
+            e: MapLayout::power_of_two(bits) + field,
+            r_q: randomness.clone(),
+        },
+    )?;
+    Ok(verifier_channel.proof()?)
+}
+
+fn verify_range<P: CurvePointProjective>(
+    crs: &CRSHashToPrime<P, SigmaRangeProtocol<P>>,
+    transcript: Transcript,
+    commitment: &P,
+    proof: &SigmaRangeProof<P>,
+) -> Result<(), VerificationError> {
+    let bits = crs.parameters.hash_to_prime_bits - 1;
+    let transcript = RefCell::new(transcript);
+    let mut prover_channel = HashToPrimeTranscriptProverChannel::new(crs, &transcript, proof);
+    SigmaRangeProtocol::from_crs(crs).verify(
+        &mut prover_channel,
+        &HashToPrimeStatement {
+            c_e_q: top_bit(&crs.pedersen_commitment_parameters, commitment, bits)?,
+        },
+    )
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Protocol<G, P, HP>
+{
+    /// The transcript of the range proof of a field, bound to the CRS and,
+    /// through the pair context, to the layout, the context and the
+    /// commitments.
+    fn range_transcript(&self, pair_context: &[u8], field: &'static [u8]) -> Transcript {
+        let mut transcript = self.transcript(pair_context);
+        transcript.append_message(b"range", field);
+        transcript
+    }
+
+    /// Proves that the committed key and value are a pair of the map in the
+    /// accumulator, non-interactively with the context.
+    pub fn prove_pair<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        layout: &MapLayout,
+        statement: &PairStatement<G, P>,
+        witness: &PairWitness<G>,
+        context: &[u8],
+    ) -> Result<PairProof<G, P, HP>, ProofError> {
+        if layout.total_bits() != self.crs.parameters.hash_to_prime_bits {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+        let (prime, nonce) = layout.pair_to_prime(&witness.key, &witness.value)?;
+        if self.hash_to_prime(&prime)?.0 != prime {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+        let pedersen = &self.crs.crs_modeq.pedersen_commitment_parameters;
+        let r_nonce = bigint_to_integer::<P>(&P::ScalarField::rand(rng2));
+        let c_nonce = pedersen.commit(&nonce, &r_nonce)?;
+        let c_e_q = layout.combine(pedersen, &statement.c_key, &statement.c_value, &c_nonce)?;
+        let r_q = integer_mod_q::<P>(
+            &(Integer::from(&witness.r_key << (layout.value_bits + layout.nonce_bits) as u32)
+                + Integer::from(&witness.r_value << layout.nonce_bits as u32)
+                + &r_nonce),
+        )?;
+
+        let context = pair_context(
+            layout,
+            context,
+            &[&statement.c_key, &statement.c_value, &c_nonce],
+        )
+        .map_err(SerializationError::from)?;
+        let proof = self.prove_noninteractive(
+            rng1,
+            rng2,
+            &Statement::new(statement.c_p.clone(), c_e_q)
+                .map_err(|_| ProofError::CouldNotCreateProof)?,
+            &Witness::new(prime, r_q, witness.w.clone())
+                .map_err(|_| ProofError::CouldNotCreateProof)?,
+            &context,
+        )?;
+
+        let parameters = &self.crs.parameters;
+        Ok(PairProof {
+            range_key: prove_range(
+                &range_crs(parameters, pedersen, layout.key_bits),
+                self.range_transcript(&context, b"key"),
+                rng2,
+                &statement.c_key,
+                &witness.key,
+                &witness.r_key,
+            )?,
+            range_value: prove_range(
+                &range_crs(parameters, pedersen, layout.value_bits),
+                self.range_transcript(&context, b"value"),
+                rng2,
+                &statement.c_value,
+                &witness.value,
+                &witness.r_value,
+            )?,
+            range_nonce: prove_range(
+                &range_crs(parameters, pedersen, layout.nonce_bits),
+                self.range_transcript(&context, b"nonce"),
+                rng2,
+                &c_nonce,
+                &nonce,
+                &r_nonce,
+            )?,
+            c_nonce,
+            proof,
+        })
+    }
+
+    /// Verifies a proof created by `prove_pair` with the same layout and
+    /// context.
+    pub fn verify_pair(
+        &self,
+        layout: &MapLayout,
+        statement: &PairStatement<G, P>,
+        proof: &PairProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        if layout.total_bits() != self.crs.parameters.hash_to_prime_bits {
+            return Err(VerificationError::HashToPrimeProofInvalid);
+        }
+        let pedersen = &self.crs.crs_modeq.pedersen_commitment_parameters;
+        let parameters = &self.crs.parameters;
+        let context = pair_context(
+            layout,
+            context,
+            &[&statement.c_key, &statement.c_value, &proof.c_nonce],
+        )
+        .map_err(SerializationError::from)?;
+        verify_range(
+            &range_crs(parameters, pedersen, layout.key_bits),
+            self.range_transcript(&context, b"key"),
+            &statement.c_key,
+            &proof.range_key,
+        )?;
+        verify_range(
+            &range_crs(parameters, pedersen, layout.value_bits),
+            self.range_transcript(&context, b"value"),
+            &statement.c_value,
+            &proof.range_value,
+        )?;
+        verify_range(
+            &range_crs(parameters, pedersen, layout.nonce_bits),
+            self.range_transcript(&context, b"nonce"),
+            &proof.c_nonce,
+            &proof.range_nonce,
+        )?;
+
+        let c_e_q = layout.combine(
+            pedersen,
+            &statement.c_key,
+            &statement.c_value,
+            &proof.c_nonce,
+        )?;
+        self.verify_noninteractive(
+            &Statement::new(statement.c_p.clone(), c_e_q)?,
+            &proof.proof,
+            &context,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{MapLayout, PairProof, PairStatement, PairWitness};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{hash_to_prime::sigma_range::Protocol as HPProtocol, membership::Protocol},
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::G1Projective;
+    use rug::Integer;
+
+    #[test]
+    fn test_key_value_map() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<G1Projective>>::setup_deterministic(
+                &params, [71u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([72u8; 32]);
+        let layout = MapLayout::new(&params).unwrap();
+        // Parameters leaving no bits for the value are rejected.
+        let mut short = params.clone();
+        short.hash_to_prime_bits = 18;
+        assert!(MapLayout::new(&short).is_err());
+
+        let pairs = [(1001, 77), (1002, 78)];
+        let primes = pairs
+            .iter()
+            .map(|(key, value)| {
+                layout
+                    .pair_to_prime(&Integer::from(*key), &Integer::from(*value))
+                    .unwrap()
+                    .0
+            })
+            .collect::<Vec<_>>();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add(&primes[1..])
+                .add_with_proof(&primes[..1]);
+
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        let commit = |value: i32, randomness: i32| {
+            pedersen
+                .commit(&Integer::from(value), &Integer::from(randomness))
+                .unwrap()
+        };
+        let statement = PairStatement {
+            c_p: accum.0.value.clone(),
+            c_key: commit(1001, 3),
+            c_value: commit(77, 4),
+        };
+        let witness = PairWitness::new(
+            Integer::from(1001),
+            Integer::from(3),
+            Integer::from(77),
+            Integer::from(4),
+            accum.1.witness.0.value.clone(),
+        );
+        let proof = protocol
+            .prove_pair(
+                &mut rng1, &mut rng2, &layout, &statement, &witness, b"lookup",
+            )
+            .unwrap();
+        protocol
+            .verify_pair(&layout, &statement, &proof, b"lookup")
+            .unwrap();
+        assert!(protocol
+            .verify_pair(&layout, &statement, &proof, b"other lookup")
+            .is_err());
+
+        // The proof is for the committed key and value only.
+        let other_value = PairStatement {
+            c_p: accum.0.value.clone(),
+            c_key: commit(1001, 3),
+            c_value: commit(78, 4),
+        };
+        assert!(protocol
+            .verify_pair(&layout, &other_value, &proof, b"lookup")
+            .is_err());
+
+        // The range proofs are bound to the context too, so they can't be
+        // taken from a proof of the same pair in another context.
+        let other_proof = protocol
+            .prove_pair(
+                &mut rng1,
+                &mut rng2,
+                &layout,
+                &statement,
+                &witness,
+                b"other lookup",
+            )
+            .unwrap();
+        let mixed = PairProof {
+            range_key: other_proof.range_key,
+            ..proof
+        };
+        assert!(protocol
+            .verify_pair(&layout, &statement, &mixed, b"lookup")
+            .is_err());
+
+        // A pair that isn't in the map can't be proven.
+        let witness = PairWitness::new(
+            Integer::from(1001),
+            Integer::from(3),
+            Integer::from(78),
+            Integer::from(4),
+            accum.1.witness.0.value,
+        );
+        assert!(protocol
+            .prove_pair(
+                &mut rng1,
+                &mut rng2,
+                &layout,
+                &other_value,
+                &witness,
+                b"lookup",
+            )
+            .map_or(true, |proof| protocol
+                .verify_pair(&layout, &other_value, &proof, b"lookup")
+                .is_err()));
+    }
+}
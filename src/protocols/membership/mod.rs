@@ -1,13 +1,22 @@
 //! Implements CPMemRSA and CPMemRSAPrm.
 use crate::{
-    commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment, Commitment},
+    channels::stats::InstrumentedChannel,
+    commitments::{
+        integer::{IntegerCommitment, IntegerCommitmentTables},
+        pedersen::PedersenCommitment,
+        Commitment,
+    },
     parameters::Parameters,
     protocols::{
+        batch::BatchEquations,
+        check_discriminant, check_elem, check_point,
         hash_to_prime::{
             channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
-            CRSHashToPrime, HashToPrimeError, HashToPrimeProtocol,
+            dynamic::DynProtocol,
+            CRSHashToPrime, CRSHashToPrimeVerifier, CRSSize, HashToPrimeError, HashToPrimeProtocol,
             Statement as HashToPrimeStatement, Witness as HashToPrimeWitness,
         },
+        metrics::ProverMetrics,
         modeq::{
             channel::{ModEqProverChannel, ModEqVerifierChannel},
             CRSModEq, Proof as ModEqProof, Protocol as ModEqProtocol, Statement as ModEqStatement,
@@ -18,18 +27,59 @@ use crate::{
             CRSRoot, Proof as RootProof, Protocol as RootProtocol, Statement as RootStatement,
             Witness as RootWitness,
         },
-        ProofError, SetupError, VerificationError,
+        shared::{CRSBases, SharedCRS},
+        validity::{Validity, ValidityError, ValidityPolicy},
+        CRSError, ProofError, SetupError, ValidationError, VerificationError,
     },
+    transcript::FiatShamir,
+    utils::zeroize_integer,
     utils::ConvertibleUnknownOrderGroup,
-    utils::{curve::CurvePointProjective, random_between},
+    utils::{
+        curve::{CurvePointProjective, Field},
+        group::ElemFromBytes,
+        random_between,
+        rng::{secure_rngs, seeded_rngs},
+        serialization::{
+            bytes_size, crs_fingerprint, elem_size, proof_id, read_bytes, read_checked, read_elem,
+            read_point, read_set_statement, write_bytes, write_checked, write_elem, write_point,
+            write_set_statement,
+        },
+    },
+    utils::{Redacted, RedactedInteger},
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use channel::{MembershipProverChannel, MembershipVerifierChannel};
+use core::cell::RefCell;
+use merlin::Transcript;
+#[cfg(feature = "parallel")]
+use rand::{rngs::StdRng, SeedableRng};
 use rand::{CryptoRng, RngCore};
 use rug::rand::MutRandState;
 use rug::Integer;
+use std::fmt;
+use std::sync::Arc;
+use transcript::{TranscriptProverChannel, TranscriptVerifierChannel};
+use zeroize::Zeroize;
 
+#[cfg(feature = "curves")]
+pub mod any;
+pub mod backend;
+pub mod blind;
+pub mod calldata;
 pub mod channel;
+pub mod designated;
+#[cfg(feature = "json")]
+pub mod envelope;
+pub mod facade;
+pub mod map;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+#[cfg(feature = "arkworks")]
+pub mod solidity;
+pub mod stateless;
 pub mod transcript;
+#[cfg(feature = "json")]
+pub mod vectors;
 
 pub struct CRS<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
 {
@@ -38,6 +88,10 @@ pub struct CRS<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: Has
     pub crs_root: CRSRoot<G>,
     pub crs_modeq: CRSModEq<G, P>,
     pub crs_hash_to_prime: CRSHashToPrime<P, HP>,
+    /// Identifies the application, bound to the transcripts started with
+    /// `Protocol::transcript`, so that proofs aren't accepted across
+    /// applications sharing the same parameters.
+    pub transcript_label: Vec<u8>,
 }
 
 impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
@@ -49,154 +103,404 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimePr
             crs_root: self.crs_root.clone(),
             crs_modeq: self.crs_modeq.clone(),
             crs_hash_to_prime: self.crs_hash_to_prime.clone(),
+            transcript_label: self.transcript_label.clone(),
         }
     }
 }
 
-pub struct Protocol<
-    G: ConvertibleUnknownOrderGroup,
-    P: CurvePointProjective,
-    HP: HashToPrimeProtocol<P>,
-> {
-    pub crs: CRS<G, P, HP>,
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+{
+    /// Builds the CRS from a `SharedCRS`, pointing to its hash-to-prime
+    /// parameters.
+    pub fn from_shared(shared: &SharedCRS<G, P, HP>) -> Self {
+        CRS {
+            parameters: shared.parameters.clone(),
+            crs_modeq: CRSModEq {
+                parameters: shared.parameters.clone(),
+                integer_commitment_parameters: shared.integer_commitment_parameters.clone(),
+                pedersen_commitment_parameters: shared
+                    .crs_hash_to_prime
+                    .pedersen_commitment_parameters
+                    .clone(),
+                fixed_base_tables: None,
+            },
+            crs_root: CRSRoot {
+                parameters: shared.parameters.clone(),
+                integer_commitment_parameters: shared.integer_commitment_parameters.clone(),
+                fixed_base_tables: None,
+            },
+            crs_hash_to_prime: shared.crs_hash_to_prime.clone(),
+            transcript_label: b"membership".to_vec(),
+        }
+    }
+
+    /// Identifies the CRS by what the verifier relies on, so that the
+    /// transcripts bound to it differ across CRSs.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        crs_fingerprint(
+            &self.parameters,
+            &self.crs_root.integer_commitment_parameters,
+            &self.crs_modeq.pedersen_commitment_parameters,
+            &HP::verifying_key_bytes(&HP::verifying_key(
+                &self.crs_hash_to_prime.hash_to_prime_parameters,
+            )),
+        )
+    }
+
+    pub fn with_transcript_label(mut self, label: &[u8]) -> Self {
+        self.transcript_label = label.to_vec();
+        self
+    }
+
+    /// Checks that the CRS is well-formed, e.g., when it's received from an
+    /// untrusted source: that the sub-protocols share the parameters and the
+    /// commitment bases, that the bases are distinct and not the identity,
+    /// and the relations of the hash-to-prime keys that can be checked
+    /// without the trapdoor. A setup whose trapdoor is known still passes,
+    /// so this doesn't replace a trusted setup or ceremony.
+    pub fn validate<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(), CRSError> {
+        let fingerprint = self.parameters.fingerprint();
+        check_discriminant::<G>(&self.parameters)?;
+        if self.crs_root.parameters.fingerprint() != fingerprint
+            || self.crs_modeq.parameters.fingerprint() != fingerprint
+            || self.crs_hash_to_prime.parameters.fingerprint() != fingerprint
+        {
+            return Err(CRSError::InvalidParameters);
+        }
+        let integer_commitment = &self.crs_root.integer_commitment_parameters;
+        let modeq_integer_commitment = &self.crs_modeq.integer_commitment_parameters;
+        if integer_commitment.g == G::id()
+            || integer_commitment.h == G::id()
+            || integer_commitment.g == integer_commitment.h
+            || modeq_integer_commitment.g != integer_commitment.g
+            || modeq_integer_commitment.h != integer_commitment.h
+        {
+            return Err(CRSError::InvalidCommitmentBases);
+        }
+        let pedersen = &self.crs_modeq.pedersen_commitment_parameters;
+        let hash_to_prime_pedersen = &self.crs_hash_to_prime.pedersen_commitment_parameters;
+        if pedersen.g != hash_to_prime_pedersen.g || pedersen.h != hash_to_prime_pedersen.h {
+            return Err(CRSError::InvalidCommitmentBases);
+        }
+        self.crs_hash_to_prime.validate(rng)
+    }
+
+    /// Precomputes fixed-base tables for the integer commitment bases, which
+    /// speed up the root and modeq sub-protocols at the cost of memory.
+    pub fn precompute_fixed_base_tables(&mut self) {
+        let tables = IntegerCommitmentTables::new(
+            &self.crs_root.integer_commitment_parameters,
+            self.parameters
+                .max_response_bits(G::order_upper_bound().significant_bits()),
+        );
+        self.crs_root.fixed_base_tables = Some(tables.clone());
+        self.crs_modeq.fixed_base_tables = Some(tables);
+    }
 }
 
-pub struct Statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
-    pub c_p: G::Elem,
-    pub c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective + 'static,
+        HP: HashToPrimeProtocol<P> + 'static,
+    > CRS<G, P, HP>
+{
+    /// Boxes the hash-to-prime backend, keeping the fingerprint.
+    pub fn into_dyn(self) -> CRS<G, P, DynProtocol<P>> {
+        CRS {
+            parameters: self.parameters,
+            crs_root: self.crs_root,
+            crs_modeq: self.crs_modeq,
+            crs_hash_to_prime: self.crs_hash_to_prime.into_dyn(),
+            transcript_label: self.transcript_label,
+        }
+    }
 }
 
-pub struct Witness<G: ConvertibleUnknownOrderGroup> {
-    pub e: Integer,
-    pub r_q: Integer,
-    pub w: G::Elem,
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+where
+    HP::Parameters: CRSSize,
+{
+    /// The size of the CRS with compressed curve points: the parameters, the
+    /// transcript label, the commitment bases and the hash-to-prime keys.
+    /// The fixed-base tables aren't counted, since they're recomputed from
+    /// the bases.
+    pub fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    pub fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+
+    fn size(&self, compressed: bool) -> usize {
+        Parameters::SERIALIZED_SIZE
+            + bytes_size(self.transcript_label.len())
+            + self.crs_root.size()
+            + self.crs_hash_to_prime.size(compressed)
+    }
 }
 
-pub struct Proof<
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize,
+{
+    fn write<W: Write>(&self, writer: &mut W, compressed: bool) -> Result<(), SerializationError> {
+        write_bases(
+            writer,
+            &self.parameters,
+            &self.transcript_label,
+            &self.crs_modeq,
+            compressed,
+        )?;
+        let hash_to_prime_parameters = &self.crs_hash_to_prime.hash_to_prime_parameters;
+        if compressed {
+            hash_to_prime_parameters.serialize(writer)
+        } else {
+            hash_to_prime_parameters.serialize_uncompressed(writer)
+        }
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    fn read<R: Read>(reader: &mut R, compressed: bool) -> Result<Self, SerializationError> {
+        let (parameters, transcript_label, crs_root, crs_modeq) = read_bases(reader, compressed)?;
+        let hash_to_prime_parameters = Arc::new(if compressed {
+            HP::Parameters::deserialize(reader)?
+        } else {
+            HP::Parameters::deserialize_uncompressed(reader)?
+        });
+        Ok(CRS {
+            parameters: parameters.clone(),
+            crs_hash_to_prime: CRSHashToPrime {
+                parameters,
+                pedersen_commitment_parameters: crs_modeq.pedersen_commitment_parameters.clone(),
+                hash_to_prime_parameters,
+            },
+            crs_root,
+            crs_modeq,
+            transcript_label,
+        })
+    }
+}
+
+/// Writes what the CRS and the verifier key share: the parameters, the
+/// transcript label and the integer and Pedersen commitment bases.
+fn write_bases<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, W: Write>(
+    writer: &mut W,
+    parameters: &Parameters,
+    transcript_label: &[u8],
+    crs_modeq: &CRSModEq<G, P>,
+    compressed: bool,
+) -> Result<(), SerializationError> {
+    parameters.serialize(&mut *writer)?;
+    write_bytes(writer, transcript_label)?;
+    let integer_commitment = &crs_modeq.integer_commitment_parameters;
+    write_elem::<G, _>(writer, &integer_commitment.g)?;
+    write_elem::<G, _>(writer, &integer_commitment.h)?;
+    let pedersen = &crs_modeq.pedersen_commitment_parameters;
+    write_point(writer, &pedersen.g, compressed)?;
+    write_point(writer, &pedersen.h, compressed)
+}
+
+#[allow(clippy::type_complexity)]
+fn read_bases<G: ElemFromBytes, P: CurvePointProjective, R: Read>(
+    reader: &mut R,
+    compressed: bool,
+) -> Result<(Parameters, Vec<u8>, CRSRoot<G>, CRSModEq<G, P>), SerializationError> {
+    let parameters = Parameters::deserialize(&mut *reader)?;
+    let transcript_label = read_bytes(reader)?;
+    let integer_commitment_parameters =
+        IntegerCommitment::<G>::new(&read_elem::<G, _>(reader)?, &read_elem::<G, _>(reader)?);
+    let pedersen_commitment_parameters = PedersenCommitment::<P>::new(
+        &read_point(reader, compressed)?,
+        &read_point(reader, compressed)?,
+    );
+    let crs_modeq = CRSModEq {
+        parameters: parameters.clone(),
+        integer_commitment_parameters: integer_commitment_parameters.clone(),
+        pedersen_commitment_parameters,
+        fixed_base_tables: None,
+    };
+    let crs_root = CRSRoot {
+        parameters: parameters.clone(),
+        integer_commitment_parameters,
+        fixed_base_tables: None,
+    };
+    Ok((parameters, transcript_label, crs_root, crs_modeq))
+}
+
+/// Written as the parameters, the transcript label, the integer and Pedersen
+/// commitment bases, which the sub-protocols share, and the hash-to-prime
+/// parameters. The fixed-base tables aren't written, so they have to be
+/// precomputed again after reading.
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, false)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    /// Writes the CRS followed by a checksum, so that setup runs once and the
+    /// CRS is distributed as a file. Uncompressed curve points make the file
+    /// larger but faster to read.
+    pub fn serialize_to<W: Write>(
+        &self,
+        writer: W,
+        compressed: bool,
+    ) -> Result<(), SerializationError> {
+        write_checked(writer, self, compressed)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    /// Reads a CRS written by `serialize_to`, rejecting it if the checksum
+    /// doesn't match. The fixed-base tables have to be precomputed again.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_checked(reader)
+    }
+}
+
+/// The prover needs all of the CRS, including the hash-to-prime proving key.
+pub type ProverKey<G, P, HP> = CRS<G, P, HP>;
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+{
+    pub fn verifier_key(&self) -> VerifierKey<G, P, HP> {
+        VerifierKey {
+            parameters: self.parameters.clone(),
+            crs_root: self.crs_root.clone(),
+            crs_modeq: self.crs_modeq.clone(),
+            crs_hash_to_prime: self.crs_hash_to_prime.verifier(),
+            transcript_label: self.transcript_label.clone(),
+        }
+    }
+}
+
+/// The part of the CRS that the verifier needs: the commitment bases and the
+/// hash-to-prime verifying key, without the proving key, so that light
+/// verifiers only download a few kilobytes. It's serialized as the CRS is,
+/// with the verifying key in place of the hash-to-prime parameters.
+pub struct VerifierKey<
     G: ConvertibleUnknownOrderGroup,
     P: CurvePointProjective,
     HP: HashToPrimeProtocol<P>,
 > {
-    pub c_e: <IntegerCommitment<G> as Commitment>::Instance,
-    pub proof_root: RootProof<G>,
-    pub proof_modeq: ModEqProof<G, P>,
-    pub proof_hash_to_prime: HP::Proof,
+    pub parameters: Parameters,
+    pub crs_root: CRSRoot<G>,
+    pub crs_modeq: CRSModEq<G, P>,
+    pub crs_hash_to_prime: CRSHashToPrimeVerifier<P, HP>,
+    pub transcript_label: Vec<u8>,
 }
 
 impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
-    for Proof<G, P, HP>
+    for VerifierKey<G, P, HP>
 {
     fn clone(&self) -> Self {
         Self {
-            c_e: self.c_e.clone(),
-            proof_root: self.proof_root.clone(),
-            proof_modeq: self.proof_modeq.clone(),
-            proof_hash_to_prime: self.proof_hash_to_prime.clone(),
+            parameters: self.parameters.clone(),
+            crs_root: self.crs_root.clone(),
+            crs_modeq: self.crs_modeq.clone(),
+            crs_hash_to_prime: self.crs_hash_to_prime.clone(),
+            transcript_label: self.transcript_label.clone(),
         }
     }
 }
 
 impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
-    Protocol<G, P, HP>
+    VerifierKey<G, P, HP>
 {
-    pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
-        parameters: &Parameters,
-        rng1: &mut R1,
-        rng2: &mut R2,
-    ) -> Result<Protocol<G, P, HP>, SetupError> {
-        let integer_commitment_parameters = IntegerCommitment::<G>::setup(rng1);
-        let pedersen_commitment_parameters = PedersenCommitment::<P>::setup(rng2);
-        let hash_to_prime_parameters =
-            HP::setup(rng2, &pedersen_commitment_parameters, parameters)?;
-        Ok(Protocol {
-            crs: CRS::<G, P, HP> {
-                parameters: parameters.clone(),
-                crs_modeq: CRSModEq::<G, P> {
-                    parameters: parameters.clone(),
-                    integer_commitment_parameters: integer_commitment_parameters.clone(),
-                    pedersen_commitment_parameters: pedersen_commitment_parameters.clone(),
-                },
-                crs_root: CRSRoot::<G> {
-                    parameters: parameters.clone(),
-                    integer_commitment_parameters,
-                },
-                crs_hash_to_prime: CRSHashToPrime::<P, HP> {
-                    parameters: parameters.clone(),
-                    pedersen_commitment_parameters,
-                    hash_to_prime_parameters,
-                },
-            },
-        })
-    }
-
-    pub fn prove<
-        R1: MutRandState,
-        R2: RngCore + CryptoRng,
-        C: MembershipVerifierChannel<G>
-            + RootVerifierChannel<G>
-            + ModEqVerifierChannel<G, P>
-            + HashToPrimeVerifierChannel<P, HP>,
-    >(
-        &self,
-        verifier_channel: &mut C,
-        rng1: &mut R1,
-        rng2: &mut R2,
-        statement: &Statement<G, P>,
-        witness: &Witness<G>,
-    ) -> Result<(), ProofError> {
-        let (hashed_e, _) = self.hash_to_prime(&witness.e)?;
-        let r = random_between(rng1, &Integer::from(0), &G::order_upper_bound());
-        let c_e = self
-            .crs
-            .crs_root
-            .integer_commitment_parameters
-            .commit(&hashed_e, &r)?;
-        verifier_channel.send_c_e(&c_e)?;
-        let root = RootProtocol::from_crs(&self.crs.crs_root);
-        root.prove(
-            verifier_channel,
-            rng1,
-            &RootStatement {
-                c_e: c_e.clone(),
-                acc: statement.c_p.clone(),
-            },
-            &RootWitness {
-                e: hashed_e.clone(),
-                r: r.clone(),
-                w: witness.w.clone(),
-            },
-        )?;
-        let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
-        modeq.prove(
-            verifier_channel,
-            rng1,
-            rng2,
-            &ModEqStatement {
-                c_e,
-                c_e_q: statement.c_e_q.clone(),
-            },
-            &ModEqWitness {
-                e: hashed_e,
-                r,
-                r_q: witness.r_q.clone(),
+    /// Builds the key from the commitment bases and the verifying key of
+    /// hash-to-prime parameters generated for them, without the proving key.
+    pub fn from_bases(bases: &CRSBases<G, P>, verifying_key: HP::VerifyingKey) -> Self {
+        VerifierKey {
+            parameters: bases.parameters.clone(),
+            crs_root: CRSRoot {
+                parameters: bases.parameters.clone(),
+                integer_commitment_parameters: bases.integer_commitment_parameters.clone(),
+                fixed_base_tables: None,
             },
-        )?;
-        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
-        hash_to_prime.prove(
-            verifier_channel,
-            rng2,
-            &HashToPrimeStatement {
-                c_e_q: statement.c_e_q.clone(),
+            crs_modeq: CRSModEq {
+                parameters: bases.parameters.clone(),
+                integer_commitment_parameters: bases.integer_commitment_parameters.clone(),
+                pedersen_commitment_parameters: bases.pedersen_commitment_parameters.clone(),
+                fixed_base_tables: None,
             },
-            &HashToPrimeWitness {
-                e: witness.e.clone(),
-                r_q: witness.r_q.clone(),
+            crs_hash_to_prime: CRSHashToPrimeVerifier {
+                parameters: bases.parameters.clone(),
+                pedersen_commitment_parameters: bases.pedersen_commitment_parameters.clone(),
+                verifying_key,
             },
-        )?;
+            transcript_label: b"membership".to_vec(),
+        }
+    }
 
-        Ok(())
+    /// Equal to the fingerprint of the CRS the key was extracted from.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        crs_fingerprint(
+            &self.parameters,
+            &self.crs_root.integer_commitment_parameters,
+            &self.crs_modeq.pedersen_commitment_parameters,
+            &HP::verifying_key_bytes(&self.crs_hash_to_prime.verifying_key),
+        )
+    }
+
+    /// Binds the transcript as `Protocol::bind_transcript` does.
+    pub fn bind_transcript<T: FiatShamir>(&self, transcript: &mut T, context: &[u8]) {
+        transcript.append_message(b"label", &self.transcript_label);
+        transcript.append_message(b"crs", &self.fingerprint());
+        transcript.append_message(b"context", context);
     }
 
+    /// Verifies as `Protocol::verify` does. The SNARK's verifying key is
+    /// prepared on every call.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "membership::verify", level = "debug", skip_all)
+    )]
     pub fn verify<
         C: MembershipProverChannel<G>
             + RootProverChannel<G>
@@ -207,87 +511,1516 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimePr
         prover_channel: &mut C,
         statement: &Statement<G, P>,
     ) -> Result<(), VerificationError> {
-        let c_e = prover_channel.receive_c_e()?;
-        let root = RootProtocol::from_crs(&self.crs.crs_root);
-        root.verify(
-            prover_channel,
-            &RootStatement {
-                c_e: c_e.clone(),
-                acc: statement.c_p.clone(),
-            },
-        )?;
-        let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
-        modeq.verify(
-            prover_channel,
-            &ModEqStatement {
-                c_e,
-                c_e_q: statement.c_e_q.clone(),
-            },
-        )?;
-        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
-        hash_to_prime.verify(
+        verify_root_and_modeq(&self.crs_root, &self.crs_modeq, prover_channel, statement)?;
+        HP::verify_with_key(
+            &self.crs_hash_to_prime,
             prover_channel,
             &HashToPrimeStatement {
                 c_e_q: statement.c_e_q.clone(),
             },
-        )?;
+        )
+    }
 
-        Ok(())
+    /// Verifies a proof created by `prove_noninteractive` with the same
+    /// context.
+    pub fn verify_noninteractive(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_noninteractive_with(Transcript::new(b"cpsnarks-set"), statement, proof, context)
     }
 
-    pub fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
-        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
-        hash_to_prime.hash_to_prime(e)
+    pub fn verify_noninteractive_with<T: FiatShamir>(
+        &self,
+        mut transcript: T,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.bind_transcript(&mut transcript, context);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel =
+            TranscriptProverChannel::with_verifier_key(self, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
     }
 
-    pub fn from_crs(crs: &CRS<G, P, HP>) -> Protocol<G, P, HP> {
-        Protocol { crs: crs.clone() }
+    /// Verifies as `Protocol::verify_signature` does.
+    pub fn verify_signature(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = Transcript::new(b"cpsnarks-set");
+        self.bind_transcript(&mut transcript, context);
+        transcript.append_message(b"message", message);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel =
+            TranscriptProverChannel::with_verifier_key(self, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
     }
-}
 
-#[cfg(all(test, feature = "arkworks"))]
-mod test {
-    use super::{Protocol, Statement, Witness};
-    use crate::{
-        commitments::Commitment,
-        parameters::Parameters,
-        protocols::hash_to_prime::snark_range::Protocol as HPProtocol,
-        protocols::{
-            hash_to_prime::snark_hash::{HashToPrimeHashParameters, Protocol as HPHashProtocol},
-            membership::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
-        },
-    };
-    use accumulator::group::{ClassGroup, Rsa2048};
-    use accumulator::{group::Group, AccumulatorWithoutHashToPrime};
-    use ark_bls12_381::{Bls12_381, G1Projective};
-    use merlin::Transcript;
-    use rand::thread_rng;
-    use rug::rand::RandState;
-    use rug::Integer;
-    use std::cell::RefCell;
+    /// Verifies as `Protocol::verify_with_policy` does.
+    pub fn verify_with_policy(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+        policy: &ValidityPolicy,
+    ) -> Result<(), VerificationError> {
+        policy.check(statement.epoch())?;
+        self.verify_noninteractive(statement, proof, context)
+    }
+}
 
-    const LARGE_PRIMES: [u64; 4] = [
-        553_525_575_239_331_913,
-        12_702_637_924_034_044_211,
-        378_373_571_372_703_133,
-        8_640_171_141_336_142_787,
-    ];
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalSerialize,
+{
+    fn size(&self, compressed: bool) -> usize {
+        Parameters::SERIALIZED_SIZE
+            + bytes_size(self.transcript_label.len())
+            + self.crs_root.size()
+            + self.crs_hash_to_prime.size(compressed)
+    }
 
-    #[test]
-    fn test_e2e_prime_rsa() {
-        let params = Parameters::from_security_level(128).unwrap();
-        let mut rng1 = RandState::new();
-        rng1.seed(&Integer::from(13));
-        let mut rng2 = thread_rng();
+    fn write<W: Write>(&self, writer: &mut W, compressed: bool) -> Result<(), SerializationError> {
+        write_bases(
+            writer,
+            &self.parameters,
+            &self.transcript_label,
+            &self.crs_modeq,
+            compressed,
+        )?;
+        let verifying_key = &self.crs_hash_to_prime.verifying_key;
+        if compressed {
+            verifying_key.serialize(writer)
+        } else {
+            verifying_key.serialize_uncompressed(writer)
+        }
+    }
 
-        let crs = crate::protocols::membership::Protocol::<
-            Rsa2048,
-            G1Projective,
-            HPProtocol<Bls12_381>,
-        >::setup(&params, &mut rng1, &mut rng2)
-        .unwrap()
-        .crs;
-        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+    /// Writes the verifier key followed by a checksum, as
+    /// `CRS::serialize_to` does.
+    pub fn serialize_to<W: Write>(
+        &self,
+        writer: W,
+        compressed: bool,
+    ) -> Result<(), SerializationError> {
+        write_checked(writer, self, compressed)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    fn read<R: Read>(reader: &mut R, compressed: bool) -> Result<Self, SerializationError> {
+        let (parameters, transcript_label, crs_root, crs_modeq) = read_bases(reader, compressed)?;
+        let verifying_key = if compressed {
+            HP::VerifyingKey::deserialize(reader)?
+        } else {
+            HP::VerifyingKey::deserialize_uncompressed(reader)?
+        };
+        Ok(VerifierKey {
+            parameters: parameters.clone(),
+            crs_hash_to_prime: CRSHashToPrimeVerifier {
+                parameters,
+                pedersen_commitment_parameters: crs_modeq.pedersen_commitment_parameters.clone(),
+                verifying_key,
+            },
+            crs_root,
+            crs_modeq,
+            transcript_label,
+        })
+    }
+
+    /// Reads a verifier key written by `serialize_to`, rejecting it if the
+    /// checksum doesn't match.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_checked(reader)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalSerialize,
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, false)
+    }
+}
+
+/// Keeps the hash-to-prime proof created on another thread until it's sent
+/// on the verifier channel.
+#[cfg(feature = "parallel")]
+struct ProofSlot<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> {
+    proof: Option<HP::Proof>,
+    point_type: core::marker::PhantomData<P>,
+}
+
+#[cfg(feature = "parallel")]
+impl<P: CurvePointProjective, HP: HashToPrimeProtocol<P>> HashToPrimeVerifierChannel<P, HP>
+    for ProofSlot<P, HP>
+{
+    fn send_proof(&mut self, proof: &HP::Proof) -> Result<(), crate::channels::ChannelError> {
+        self.proof = Some(proof.clone());
+        Ok(())
+    }
+}
+
+pub struct Protocol<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub crs: CRS<G, P, HP>,
+    /// Built once from the CRS, e.g., preparing the SNARK's verifying key.
+    hash_to_prime: HP,
+}
+
+/// The accumulator and the commitment to the element, built with `new`,
+/// which rejects malformed ones.
+pub struct Statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    pub(crate) c_p: G::Elem,
+    pub(crate) c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+    /// Optional identifier of the accumulator state, e.g., its epoch or a
+    /// digest, bound to the transcript so that the proof is only accepted
+    /// against the same state.
+    pub(crate) epoch: Option<Vec<u8>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Statement<G, P> {
+    /// The statement that the element committed to in `c_e_q` is in the
+    /// accumulator `c_p`, rejected if either is the identity or the
+    /// commitment is outside the prime-order subgroup.
+    pub fn new(
+        c_p: G::Elem,
+        c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+    ) -> Result<Self, ValidationError> {
+        let statement = Statement {
+            c_p,
+            c_e_q,
+            epoch: None,
+        };
+        statement.validate()?;
+        Ok(statement)
+    }
+
+    /// Binds the statement to a state of the accumulator.
+    pub fn with_epoch(mut self, epoch: &[u8]) -> Self {
+        self.epoch = Some(epoch.to_vec());
+        self
+    }
+
+    pub fn c_p(&self) -> &G::Elem {
+        &self.c_p
+    }
+
+    pub fn c_e_q(&self) -> &<PedersenCommitment<P> as Commitment>::Instance {
+        &self.c_e_q
+    }
+
+    pub fn epoch(&self) -> Option<&[u8]> {
+        self.epoch.as_deref()
+    }
+
+    /// Binds the statement to an epoch and an optional expiry time, checked
+    /// by `verify_with_policy`.
+    pub fn with_validity(self, validity: &Validity) -> Self {
+        self.with_epoch(&validity.to_bytes())
+    }
+
+    /// The validity metadata set by `with_validity`, if any.
+    pub fn validity(&self) -> Result<Option<Validity>, ValidityError> {
+        self.epoch.as_deref().map(Validity::from_bytes).transpose()
+    }
+
+    pub fn to_canonical_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        write_set_statement::<G, P, _>(&mut bytes, &self.c_p, &self.c_e_q, &self.epoch)?;
+        Ok(bytes)
+    }
+
+    /// Rejects an accumulator or commitment equal to the identity, or a
+    /// commitment outside the prime-order subgroup.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("c_p", &self.c_p)?;
+        check_point("c_e_q", &self.c_e_q)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective> Statement<G, P> {
+    /// Reads the canonical encoding of a statement, rejecting trailing bytes.
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut reader = bytes;
+        let (c_p, c_e_q, epoch) = read_set_statement::<G, P, _>(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Statement { c_p, c_e_q, epoch })
+    }
+}
+
+/// The element, the randomness of its commitment and its membership
+/// witness, built with `new`.
+pub struct Witness<G: ConvertibleUnknownOrderGroup> {
+    pub(crate) e: Integer,
+    pub(crate) r_q: Integer,
+    pub(crate) w: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Witness<G> {
+    /// Rejects an element that isn't positive, negative randomness and a
+    /// membership witness equal to the identity. Whether the element is in
+    /// the range of the hash-to-prime backend is checked by
+    /// `Protocol::hash_to_prime`.
+    pub fn new(e: Integer, r_q: Integer, w: G::Elem) -> Result<Self, ValidationError> {
+        let witness = Witness { e, r_q, w };
+        if witness.e <= 0 {
+            return Err(ValidationError::WitnessOutOfRange("e"));
+        }
+        if witness.r_q < 0 {
+            return Err(ValidationError::WitnessOutOfRange("r_q"));
+        }
+        check_elem::<G>("w", &witness.w)?;
+        Ok(witness)
+    }
+
+    pub fn e(&self) -> &Integer {
+        &self.e
+    }
+
+    pub fn r_q(&self) -> &Integer {
+        &self.r_q
+    }
+
+    pub fn w(&self) -> &G::Elem {
+        &self.w
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for Witness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for Witness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .field("w", &Redacted)
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for Witness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+pub struct Proof<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub c_e: <IntegerCommitment<G> as Commitment>::Instance,
+    pub proof_root: RootProof<G>,
+    pub proof_modeq: ModEqProof<G, P>,
+    pub proof_hash_to_prime: HP::Proof,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
+    for Proof<G, P, HP>
+{
+    fn clone(&self) -> Self {
+        Self {
+            c_e: self.c_e.clone(),
+            proof_root: self.proof_root.clone(),
+            proof_modeq: self.proof_modeq.clone(),
+            proof_hash_to_prime: self.proof_hash_to_prime.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Proof<G, P, HP>
+{
+    /// Checks the ranges of the responses of the sub-proofs. The group
+    /// elements and curve points are checked when deserializing.
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        self.proof_root.validate(parameters)?;
+        self.proof_modeq.validate(parameters)
+    }
+
+    /// The canonical encoding of the proof, its compressed serialization.
+    pub fn to_canonical_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        self.serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// A stable identifier of the proof and its statement, to reference it
+    /// in caches and audit logs.
+    pub fn proof_id(&self, statement: &Statement<G, P>) -> Result<[u8; 32], SerializationError> {
+        Ok(proof_id(
+            b"membership",
+            &statement.to_canonical_bytes()?,
+            &self.to_canonical_bytes()?,
+        ))
+    }
+}
+
+/// The non-interactive proof, bundling the sub-proofs.
+pub type MembershipProof<G, P, HP> = Proof<G, P, HP>;
+
+/// Written as the commitment to the hashed element, the root and modeq
+/// proofs and the bytes of the hash-to-prime proof.
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for Proof<G, P, HP>
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        write_elem::<G, _>(&mut writer, &self.c_e)?;
+        self.proof_root.serialize(&mut writer)?;
+        self.proof_modeq.serialize(&mut writer)?;
+        write_bytes(&mut writer, &HP::proof_to_bytes(&self.proof_hash_to_prime)?)
+    }
+
+    fn serialized_size(&self) -> usize {
+        elem_size::<G>(&self.c_e)
+            + self.proof_root.serialized_size()
+            + self.proof_modeq.serialized_size()
+            + bytes_size(HP::proof_size(&self.proof_hash_to_prime, true))
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        write_elem::<G, _>(&mut writer, &self.c_e)?;
+        self.proof_root.serialize_uncompressed(&mut writer)?;
+        self.proof_modeq.serialize_uncompressed(&mut writer)?;
+        write_bytes(
+            &mut writer,
+            &HP::proof_to_bytes_uncompressed(&self.proof_hash_to_prime)?,
+        )
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        elem_size::<G>(&self.c_e)
+            + self.proof_root.uncompressed_size()
+            + self.proof_modeq.uncompressed_size()
+            + bytes_size(HP::proof_size(&self.proof_hash_to_prime, false))
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for Proof<G, P, HP>
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Proof {
+            c_e: read_elem::<G, _>(&mut reader)?,
+            proof_root: RootProof::deserialize(&mut reader)?,
+            proof_modeq: ModEqProof::deserialize(&mut reader)?,
+            proof_hash_to_prime: HP::proof_from_bytes(&read_bytes(&mut reader)?)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Proof {
+            c_e: read_elem::<G, _>(&mut reader)?,
+            proof_root: RootProof::deserialize_uncompressed(&mut reader)?,
+            proof_modeq: ModEqProof::deserialize_uncompressed(&mut reader)?,
+            proof_hash_to_prime: HP::proof_from_bytes_uncompressed(&read_bytes(&mut reader)?)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    serde::Serialize for Proof<G, P, HP>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::utils::serialization::serialize_canonical(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    serde::Deserialize<'de> for Proof<G, P, HP>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::utils::serialization::deserialize_canonical(deserializer)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Protocol<G, P, HP>
+{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "membership::setup", level = "debug", skip_all)
+    )]
+    pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<Protocol<G, P, HP>, SetupError> {
+        let crs = CRS::from_shared(&SharedCRS::setup(parameters, rng1, rng2)?);
+        Ok(Protocol {
+            hash_to_prime: HP::from_crs(&crs.crs_hash_to_prime),
+            crs,
+        })
+    }
+
+    /// Like `setup`, deriving all the randomness from the seed, so that the
+    /// same seed gives the same CRS on every machine. The seed has to be
+    /// secret and erased for the CRS to be secure, so this is meant for tests
+    /// and debugging.
+    pub fn setup_deterministic(
+        parameters: &Parameters,
+        seed: [u8; 32],
+    ) -> Result<Protocol<G, P, HP>, SetupError> {
+        let (mut rng1, mut rng2) = seeded_rngs(seed);
+        Self::setup(parameters, &mut rng1, &mut rng2)
+    }
+
+    /// Starts a transcript bound to the label of the CRS and to the context
+    /// of the statement, e.g., a session or request identifier.
+    pub fn transcript(&self, context: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new(b"cpsnarks-set");
+        self.bind_transcript(&mut transcript, context);
+        transcript
+    }
+
+    /// Binds any Fiat-Shamir transcript to the label and the fingerprint of
+    /// the CRS and to the context, before it is used in the transcript
+    /// channels.
+    pub fn bind_transcript<T: FiatShamir>(&self, transcript: &mut T, context: &[u8]) {
+        transcript.append_message(b"label", &self.crs.transcript_label);
+        transcript.append_message(b"crs", &self.crs.fingerprint());
+        transcript.append_message(b"context", context);
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "membership::prove", level = "debug", skip_all)
+    )]
+    pub fn prove<
+        R1: MutRandState,
+        R2: RngCore + CryptoRng,
+        C: MembershipVerifierChannel<G>
+            + RootVerifierChannel<G>
+            + ModEqVerifierChannel<G, P>
+            + HashToPrimeVerifierChannel<P, HP>,
+    >(
+        &self,
+        verifier_channel: &mut C,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+    ) -> Result<(), ProofError> {
+        let mut metrics = ProverMetrics::default();
+        self.prove_root_and_modeq(
+            verifier_channel,
+            rng1,
+            rng2,
+            statement,
+            witness,
+            &mut metrics,
+        )?;
+        self.prove_hash_to_prime(verifier_channel, rng2, statement, witness)
+    }
+
+    /// Like `prove`, but also measures the time of each sub-protocol, the
+    /// bytes sent, the constraints of the hash-to-prime circuit and the
+    /// prover's peak memory. Counting the constraints synthesizes the circuit
+    /// once more, which is small next to proving it.
+    pub fn prove_with_metrics<
+        R1: MutRandState,
+        R2: RngCore + CryptoRng,
+        C: MembershipVerifierChannel<G>
+            + RootVerifierChannel<G>
+            + ModEqVerifierChannel<G, P>
+            + HashToPrimeVerifierChannel<P, HP>,
+    >(
+        &self,
+        verifier_channel: &mut C,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+    ) -> Result<ProverMetrics, ProofError>
+    where
+        HP::Parameters: CRSSize,
+    {
+        let mut channel = InstrumentedChannel::new(verifier_channel);
+        let mut metrics = ProverMetrics::default();
+        self.prove_root_and_modeq(&mut channel, rng1, rng2, statement, witness, &mut metrics)?;
+        metrics.time("hash_to_prime", || {
+            self.prove_hash_to_prime(&mut channel, rng2, statement, witness)
+        })?;
+
+        metrics.bytes = channel.stats().bytes_sent();
+        metrics.constraints = self.hash_to_prime.num_constraints();
+        let field_size = (P::ScalarField::size_in_bits() + 7) / 8;
+        metrics.peak_memory_estimate = self.crs.crs_hash_to_prime.size(false)
+            + metrics.constraints.map_or(0, |constraints| {
+                4 * constraints.next_power_of_two() * field_size
+            });
+        Ok(metrics)
+    }
+
+    /// Like `prove`, but creates the hash-to-prime proof on a rayon thread
+    /// while the root and modeq proofs are created on this one, since it
+    /// doesn't depend on their challenges. It's still sent last, so the
+    /// proofs verify as those of `prove` do.
+    #[cfg(feature = "parallel")]
+    pub fn prove_parallel<
+        R1: MutRandState,
+        R2: RngCore + CryptoRng,
+        C: MembershipVerifierChannel<G>
+            + RootVerifierChannel<G>
+            + ModEqVerifierChannel<G, P>
+            + HashToPrimeVerifierChannel<P, HP>,
+    >(
+        &self,
+        verifier_channel: &mut C,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+    ) -> Result<(), ProofError>
+    where
+        P: Sync,
+        HP: Sync,
+        HP::Proof: Send,
+    {
+        let mut seed = <StdRng as SeedableRng>::Seed::default();
+        rng2.fill_bytes(&mut seed);
+        let mut hash_to_prime_rng = StdRng::from_seed(seed);
+        let hash_to_prime = &self.hash_to_prime;
+        let hash_to_prime_statement = HashToPrimeStatement {
+            c_e_q: statement.c_e_q.clone(),
+        };
+        let hash_to_prime_witness = HashToPrimeWitness {
+            e: witness.e.clone(),
+            r_q: witness.r_q.clone(),
+        };
+        let mut hash_to_prime_proof = Err(ProofError::CouldNotCreateProof);
+        let result = rayon::in_place_scope(|scope| {
+            let hash_to_prime_proof = &mut hash_to_prime_proof;
+            let hash_to_prime_rng = &mut hash_to_prime_rng;
+            let hash_to_prime_statement = &hash_to_prime_statement;
+            let hash_to_prime_witness = &hash_to_prime_witness;
+            scope.spawn(move |_| {
+                let mut proof_slot = ProofSlot::<P, HP> {
+                    proof: None,
+                    point_type: core::marker::PhantomData,
+                };
+                *hash_to_prime_proof = hash_to_prime
+                    .prove(
+                        &mut proof_slot,
+                        hash_to_prime_rng,
+                        hash_to_prime_statement,
+                        hash_to_prime_witness,
+                    )
+                    .and_then(|_| proof_slot.proof.ok_or(ProofError::CouldNotCreateProof));
+            });
+            let mut metrics = ProverMetrics::default();
+            self.prove_root_and_modeq(
+                verifier_channel,
+                rng1,
+                rng2,
+                statement,
+                witness,
+                &mut metrics,
+            )
+        });
+        result?;
+        verifier_channel
+            .send_proof(&hash_to_prime_proof.map_err(|e| e.in_protocol("hash_to_prime"))?)?;
+
+        Ok(())
+    }
+
+    fn prove_root_and_modeq<
+        R1: MutRandState,
+        R2: RngCore + CryptoRng,
+        C: MembershipVerifierChannel<G> + RootVerifierChannel<G> + ModEqVerifierChannel<G, P>,
+    >(
+        &self,
+        verifier_channel: &mut C,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        metrics: &mut ProverMetrics,
+    ) -> Result<(), ProofError> {
+        let (hashed_e, r, c_e) = metrics.time("membership", || {
+            if let Some(epoch) = &statement.epoch {
+                verifier_channel.bind_epoch(epoch)?;
+            }
+            let (hashed_e, _) = self.hash_to_prime(&witness.e)?;
+            let r = random_between(rng1, &Integer::from(0), &G::order_upper_bound());
+            let c_e = self
+                .crs
+                .crs_root
+                .integer_commitment_parameters
+                .commit_secret(&hashed_e, &r, rng2)?;
+            verifier_channel.send_c_e(&c_e)?;
+            Ok::<_, ProofError>((hashed_e, r, c_e))
+        })?;
+        metrics.time("root", || {
+            let root = RootProtocol::from_crs(&self.crs.crs_root);
+            root.prove(
+                verifier_channel,
+                rng1,
+                rng2,
+                &RootStatement {
+                    c_e: c_e.clone(),
+                    acc: statement.c_p.clone(),
+                },
+                &RootWitness {
+                    e: hashed_e.clone(),
+                    r: r.clone(),
+                    w: witness.w.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("root"))
+        })?;
+        metrics.time("modeq", || {
+            let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
+            modeq
+                .prove(
+                    verifier_channel,
+                    rng1,
+                    rng2,
+                    &ModEqStatement {
+                        c_e,
+                        c_e_q: statement.c_e_q.clone(),
+                    },
+                    &ModEqWitness {
+                        e: hashed_e,
+                        r,
+                        r_q: witness.r_q.clone(),
+                    },
+                )
+                .map_err(|e| e.in_protocol("modeq"))
+        })?;
+
+        Ok(())
+    }
+
+    fn prove_hash_to_prime<R: RngCore + CryptoRng, C: HashToPrimeVerifierChannel<P, HP>>(
+        &self,
+        verifier_channel: &mut C,
+        rng: &mut R,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+    ) -> Result<(), ProofError> {
+        self.hash_to_prime
+            .prove(
+                verifier_channel,
+                rng,
+                &HashToPrimeStatement {
+                    c_e_q: statement.c_e_q.clone(),
+                },
+                &HashToPrimeWitness {
+                    e: witness.e.clone(),
+                    r_q: witness.r_q.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("hash_to_prime"))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "membership::verify", level = "debug", skip_all)
+    )]
+    pub fn verify<
+        C: MembershipProverChannel<G>
+            + RootProverChannel<G>
+            + ModEqProverChannel<G, P>
+            + HashToPrimeProverChannel<P, HP>,
+    >(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G, P>,
+    ) -> Result<(), VerificationError> {
+        verify_root_and_modeq(
+            &self.crs.crs_root,
+            &self.crs.crs_modeq,
+            prover_channel,
+            statement,
+        )?;
+        self.hash_to_prime
+            .verify(
+                prover_channel,
+                &HashToPrimeStatement {
+                    c_e_q: statement.c_e_q.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("hash_to_prime"))?;
+
+        Ok(())
+    }
+
+    /// Like `verify`, but only collects the equations of the root and modeq
+    /// proofs, to check them with those of other proofs. The hash-to-prime
+    /// proof is still verified here. The statement is validated before any of
+    /// its equations is collected.
+    pub fn verify_deferred<
+        C: MembershipProverChannel<G>
+            + RootProverChannel<G>
+            + ModEqProverChannel<G, P>
+            + HashToPrimeProverChannel<P, HP>,
+    >(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G, P>,
+        equations: &mut BatchEquations<G, P>,
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        if let Some(epoch) = &statement.epoch {
+            prover_channel.bind_epoch(epoch)?;
+        }
+        let c_e = prover_channel.receive_c_e()?;
+        let root = RootProtocol::from_crs(&self.crs.crs_root);
+        root.verify_deferred(
+            prover_channel,
+            &RootStatement {
+                c_e: c_e.clone(),
+                acc: statement.c_p.clone(),
+            },
+            &mut equations.group,
+        )
+        .map_err(|e| e.in_protocol("root"))?;
+        let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
+        modeq
+            .verify_deferred(
+                prover_channel,
+                &ModEqStatement {
+                    c_e,
+                    c_e_q: statement.c_e_q.clone(),
+                },
+                equations,
+            )
+            .map_err(|e| e.in_protocol("modeq"))?;
+        self.hash_to_prime
+            .verify(
+                prover_channel,
+                &HashToPrimeStatement {
+                    c_e_q: statement.c_e_q.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("hash_to_prime"))?;
+
+        Ok(())
+    }
+
+    /// Verifies many proofs created by `prove_noninteractive` with the same
+    /// context at once. The equations in the group of unknown order and on
+    /// the curve are checked together by random linear combination, so that
+    /// the commitment bases and accumulators shared by the statements are
+    /// exponentiated once. The SNARKs are verified one by one, with the
+    /// prepared verifying key. Fails if any of the proofs is invalid, without
+    /// telling which.
+    pub fn verify_batch<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        batch: &[(Statement<G, P>, MembershipProof<G, P, HP>)],
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut equations = BatchEquations::new();
+        for (statement, proof) in batch {
+            let mut transcript = Transcript::new(b"cpsnarks-set");
+            self.bind_transcript(&mut transcript, context);
+            let transcript = RefCell::new(transcript);
+            let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, proof);
+            self.verify_deferred(&mut prover_channel, statement, &mut equations)?;
+        }
+        if equations.check(rng1, rng2, self.crs.parameters.security_soundness) {
+            Ok(())
+        } else {
+            Err(VerificationError::BatchCheckFailed)
+        }
+    }
+
+    pub fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
+        self.hash_to_prime.hash_to_prime(e)
+    }
+
+    /// Proves with the Fiat-Shamir transform, over the transcript started by
+    /// `transcript(context)`.
+    pub fn prove_noninteractive<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        context: &[u8],
+    ) -> Result<MembershipProof<G, P, HP>, ProofError> {
+        self.prove_noninteractive_with(
+            Transcript::new(b"cpsnarks-set"),
+            rng1,
+            rng2,
+            statement,
+            witness,
+            context,
+        )
+    }
+
+    /// Like `prove_noninteractive`, over another Fiat-Shamir transcript, e.g.,
+    /// `KeccakTranscript::new(b"cpsnarks-set")` for the Solidity verifier.
+    pub fn prove_noninteractive_with<T: FiatShamir, R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        mut transcript: T,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        context: &[u8],
+    ) -> Result<MembershipProof<G, P, HP>, ProofError> {
+        self.bind_transcript(&mut transcript, context);
+        let transcript = RefCell::new(transcript);
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.prove(&mut verifier_channel, rng1, rng2, statement, witness)?;
+        Ok(verifier_channel.proof()?)
+    }
+
+    /// Verifies a proof created by `prove_noninteractive` with the same
+    /// context.
+    pub fn verify_noninteractive(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_noninteractive_with(Transcript::new(b"cpsnarks-set"), statement, proof, context)
+    }
+
+    /// Verifies a proof created by `prove_noninteractive_with` with the same
+    /// kind of transcript and context.
+    pub fn verify_noninteractive_with<T: FiatShamir>(
+        &self,
+        mut transcript: T,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.bind_transcript(&mut transcript, context);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
+    }
+
+    /// Verifies as `verify_noninteractive` does, after checking the
+    /// statement's validity metadata against the policy, so that a proof for
+    /// a past epoch, or one that has expired, is rejected.
+    pub fn verify_with_policy(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+        policy: &ValidityPolicy,
+    ) -> Result<(), VerificationError> {
+        policy.check(statement.epoch())?;
+        self.verify_noninteractive(statement, proof, context)
+    }
+
+    /// Signs the message with a signature of knowledge of the witness, e.g.,
+    /// a session nonce or a transaction hash: a proof as
+    /// `prove_noninteractive`, with the message absorbed into the transcript
+    /// after the context, so that the challenges and the proof are bound to
+    /// it.
+    pub fn sign<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<MembershipProof<G, P, HP>, ProofError> {
+        let mut transcript = self.transcript(context);
+        transcript.append_message(b"message", message);
+        let transcript = RefCell::new(transcript);
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.prove(&mut verifier_channel, rng1, rng2, statement, witness)?;
+        Ok(verifier_channel.proof()?)
+    }
+
+    /// Verifies a signature created by `sign` on the message with the same
+    /// context.
+    pub fn verify_signature(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = self.transcript(context);
+        transcript.append_message(b"message", message);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
+    }
+
+    pub fn from_crs(crs: &CRS<G, P, HP>) -> Protocol<G, P, HP> {
+        Protocol {
+            crs: crs.clone(),
+            hash_to_prime: HP::from_crs(&crs.crs_hash_to_prime),
+        }
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective + 'static,
+        HP: HashToPrimeProtocol<P> + 'static,
+    > Protocol<G, P, HP>
+{
+    /// The protocol over the boxed hash-to-prime backend, so that the
+    /// backend can be chosen at runtime. See `hash_to_prime::dynamic`.
+    pub fn into_dyn(self) -> Protocol<G, P, DynProtocol<P>> {
+        Protocol::from_crs(&self.crs.into_dyn())
+    }
+}
+
+/// Receives the commitment to the element and verifies the root and modeq
+/// proofs, which only need the commitment bases.
+fn verify_root_and_modeq<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    C: MembershipProverChannel<G> + RootProverChannel<G> + ModEqProverChannel<G, P>,
+>(
+    crs_root: &CRSRoot<G>,
+    crs_modeq: &CRSModEq<G, P>,
+    prover_channel: &mut C,
+    statement: &Statement<G, P>,
+) -> Result<(), VerificationError> {
+    statement.validate()?;
+    if let Some(epoch) = &statement.epoch {
+        prover_channel.bind_epoch(epoch)?;
+    }
+    let c_e = prover_channel.receive_c_e()?;
+    let root = RootProtocol::from_crs(crs_root);
+    root.verify(
+        prover_channel,
+        &RootStatement {
+            c_e: c_e.clone(),
+            acc: statement.c_p.clone(),
+        },
+    )
+    .map_err(|e| e.in_protocol("root"))?;
+    let modeq = ModEqProtocol::from_crs(crs_modeq);
+    modeq
+        .verify(
+            prover_channel,
+            &ModEqStatement {
+                c_e,
+                c_e_q: statement.c_e_q.clone(),
+            },
+        )
+        .map_err(|e| e.in_protocol("modeq"))
+}
+
+/// Proves membership non-interactively with an empty context and returns the
+/// compressed proof, so that simple integrations never set up channels or
+/// transcripts. Both generators are seeded from `rng`.
+pub fn prove_to_bytes<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+    R: RngCore + CryptoRng,
+>(
+    crs: &CRS<G, P, HP>,
+    statement: &Statement<G, P>,
+    witness: &Witness<G>,
+    rng: &mut R,
+) -> Result<Vec<u8>, ProofError> {
+    let (mut rng1, mut rng2) = secure_rngs(rng);
+    let proof = Protocol::from_crs(crs).prove_noninteractive(
+        &mut rng1,
+        &mut rng2,
+        statement,
+        witness,
+        &[],
+    )?;
+    Ok(proof.to_canonical_bytes()?)
+}
+
+/// Verifies a compressed proof created by `prove_to_bytes` with the verifier
+/// key, rejecting trailing bytes.
+pub fn verify_bytes<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    crs_vk: &VerifierKey<G, P, HP>,
+    statement: &Statement<G, P>,
+    proof_bytes: &[u8],
+) -> Result<(), VerificationError> {
+    verify_bytes_with_context(crs_vk, statement, proof_bytes, &[])
+}
+
+/// Deserializes a compressed proof, rejecting trailing bytes, validates it
+/// against the parameters and verifies it. All the verifiers taking the proof
+/// as bytes go through it.
+pub(crate) fn verify_bytes_with_context<
+    G: ElemFromBytes,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    crs_vk: &VerifierKey<G, P, HP>,
+    statement: &Statement<G, P>,
+    proof_bytes: &[u8],
+    context: &[u8],
+) -> Result<(), VerificationError> {
+    let mut reader = proof_bytes;
+    let proof = MembershipProof::<G, P, HP>::deserialize(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    proof.validate(&crs_vk.parameters)?;
+    crs_vk.verify_noninteractive(statement, &proof, context)
+}
+
+/// Verifies a non-interactive membership proof from its serialized inputs
+/// alone: the verifier key as written by `CanonicalSerialize`, the canonical
+/// statement and the compressed proof, which was created with an empty
+/// context. Nothing has to be set up by the caller and no randomness is used,
+/// so it can run in deterministic environments such as blockchain runtimes.
+pub fn verify_membership_bytes<
+    G: ElemFromBytes,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+>(
+    crs_vk_bytes: &[u8],
+    statement_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<(), VerificationError>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    verify_membership::<G, P, HP>(crs_vk_bytes, statement_bytes, proof_bytes, &[])
+}
+
+/// Runs the whole verification of a non-interactive membership proof created
+/// with the given context over serialized inputs, as
+/// `verify_membership_bytes` does, for services and pipelines that only pass
+/// bytes around. Trailing bytes after any of the inputs are rejected.
+pub fn verify_membership<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    crs_vk_bytes: &[u8],
+    statement_bytes: &[u8],
+    proof_bytes: &[u8],
+    context: &[u8],
+) -> Result<(), VerificationError>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    let mut reader = crs_vk_bytes;
+    let crs_vk = VerifierKey::<G, P, HP>::deserialize(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    let statement = Statement::<G, P>::from_canonical_bytes(statement_bytes)?;
+    verify_bytes_with_context(&crs_vk, &statement, proof_bytes, context)
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{
+        prove_to_bytes, verify_bytes, verify_membership, verify_membership_bytes, MembershipProof,
+        Protocol, Statement, VerifierKey, Witness, CRS,
+    };
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::hash_to_prime::snark_range::Protocol as HPProtocol,
+        protocols::{
+            hash_to_prime::snark_hash::{HashToPrimeHashParameters, Protocol as HPHashProtocol},
+            membership::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+            CRSError, ValidationError, VerificationError,
+        },
+        transcript::{
+            keccak::KeccakTranscript, poseidon::PoseidonTranscript, shake::ShakeTranscript,
+            FiatShamir,
+        },
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::{ClassGroup, Rsa2048};
+    use accumulator::{group::Group, AccumulatorWithoutHashToPrime};
+    use ark_bls12_377::Bls12_377;
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+    use ark_bn254::Bn254;
+    use ark_ec::PairingEngine;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_witness_debug_is_redacted() {
+        let e = Integer::from(Integer::u_pow_u(2, 252)) - 245;
+        let witness =
+            Witness::<Rsa2048>::new(e.clone(), Integer::from(123_456_789), Rsa2048::elem(3))
+                .unwrap();
+        let debug = format!("{:?}", witness);
+        assert_eq!(
+            debug,
+            "Witness { e: <252 bits>, r_q: <27 bits>, w: <redacted> }"
+        );
+        assert!(!debug.contains(&e.to_string()));
+    }
+
+    #[test]
+    fn test_validated_constructors() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+            &params, [4u8; 32],
+        )
+        .unwrap()
+        .crs;
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 245;
+        let c_e_q = crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &Integer::from(5))
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+
+        let statement =
+            Statement::<Rsa2048, G1Projective>::new(accum.0.value.clone(), c_e_q).unwrap();
+        assert_eq!(statement.c_p(), &accum.0.value);
+        assert!(statement.with_epoch(b"epoch-1").epoch() == Some(&b"epoch-1"[..]));
+        assert!(matches!(
+            Statement::<Rsa2048, G1Projective>::new(Rsa2048::id(), c_e_q),
+            Err(ValidationError::IdentityElement("c_p"))
+        ));
+        assert!(matches!(
+            Statement::<Rsa2048, G1Projective>::new(accum.0.value.clone(), G1Projective::default()),
+            Err(ValidationError::IdentityElement("c_e_q"))
+        ));
+
+        let w = accum.1.witness.0.value;
+        assert!(Witness::<Rsa2048>::new(value.clone(), Integer::from(5), w.clone()).is_ok());
+        assert!(matches!(
+            Witness::<Rsa2048>::new(Integer::from(0), Integer::from(5), w.clone()),
+            Err(ValidationError::WitnessOutOfRange("e"))
+        ));
+        assert!(matches!(
+            Witness::<Rsa2048>::new(value.clone(), Integer::from(-5), w),
+            Err(ValidationError::WitnessOutOfRange("r_q"))
+        ));
+        assert!(matches!(
+            Witness::<Rsa2048>::new(value, Integer::from(5), Rsa2048::id()),
+            Err(ValidationError::IdentityElement("w"))
+        ));
+    }
+
+    #[test]
+    fn test_e2e_prime_rsa() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = crate::protocols::membership::Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPProtocol<Bls12_381>,
+        >::setup(&params, &mut rng1, &mut rng2)
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(
+            2,
+            (crs.parameters.hash_to_prime_bits) as u32,
+        )) - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+        assert_eq!(Rsa2048::exp(&w, &value), acc);
+
+        let proof_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+        let verification_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        let other_epoch_statement = Statement {
+            c_e_q: statement.c_e_q,
+            c_p: statement.c_p.clone(),
+            epoch: Some(b"epoch-2".to_vec()),
+        };
+        let verification_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol
+            .verify(&mut prover_channel, &other_epoch_statement)
+            .unwrap_err();
+    }
+
+    fn prove_and_verify_with<T: FiatShamir, F: Fn() -> T>(new_transcript: F) {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = crate::protocols::membership::Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPProtocol<Bls12_381>,
+        >::setup(&params, &mut rng1, &mut rng2)
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(
+            2,
+            (crs.parameters.hash_to_prime_bits) as u32,
+        )) - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let proof_transcript = RefCell::new(new_transcript());
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: None,
+        };
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+        let verification_transcript = RefCell::new(new_transcript());
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        // The challenges depend on the transform, so a merlin transcript
+        // doesn't accept the proof.
+        let merlin_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut prover_channel = TranscriptProverChannel::new(&crs, &merlin_transcript, &proof);
+        protocol
+            .verify(&mut prover_channel, &statement)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_e2e_transcript_label() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = crate::protocols::membership::Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPProtocol<Bls12_381>,
+        >::setup(&params, &mut rng1, &mut rng2)
+        .unwrap()
+        .crs
+        .with_transcript_label(b"app-1");
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(
+            2,
+            (crs.parameters.hash_to_prime_bits) as u32,
+        )) - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let proof_transcript = RefCell::new(protocol.transcript(b"session-1"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: None,
+        };
+        protocol
+            .prove(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(protocol.transcript(b"session-1"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        let other_context_transcript = RefCell::new(protocol.transcript(b"session-2"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &other_context_transcript, &proof);
+        protocol
+            .verify(&mut prover_channel, &statement)
+            .unwrap_err();
+
+        let other_protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(
+            &crs.clone().with_transcript_label(b"app-2"),
+        );
+        let other_label_transcript = RefCell::new(other_protocol.transcript(b"session-1"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &other_label_transcript, &proof);
+        other_protocol
+            .verify(&mut prover_channel, &statement)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_e2e_noninteractive() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = crate::protocols::membership::Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPProtocol<Bls12_381>,
+        >::setup(&params, &mut rng1, &mut rng2)
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
 
         let value = Integer::from(Integer::u_pow_u(
             2,
@@ -300,30 +2033,723 @@ mod test {
             .pedersen_commitment_parameters
             .commit(&value, &randomness)
             .unwrap();
-
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let accum = accum.add(
+            &LARGE_PRIMES
+                .iter()
+                .skip(1)
+                .map(|p| Integer::from(*p))
+                .collect::<Vec<_>>(),
+        );
+        let accum = accum.add_with_proof(&[value.clone()]);
+        let acc = accum.0.value;
+        let w = accum.1.witness.0.value;
+
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: acc,
+            epoch: None,
+        };
+        let proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w,
+                },
+                b"session-1",
+            )
+            .unwrap();
+
+        let id = proof.proof_id(&statement).unwrap();
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), proof.serialized_size());
+        assert_eq!(bytes, proof.to_canonical_bytes().unwrap());
+        let proof = MembershipProof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(
+            &bytes[..],
+        )
+        .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"session-2")
+            .unwrap_err();
+
+        let mut uncompressed = vec![];
+        proof.serialize_uncompressed(&mut uncompressed).unwrap();
+        assert_eq!(uncompressed.len(), proof.uncompressed_size());
+        assert!(uncompressed.len() > bytes.len());
+        assert!(crs.uncompressed_size() > crs.serialized_size());
+        let mut crs_bytes = vec![];
+        crs.serialize(&mut crs_bytes).unwrap();
+        assert_eq!(crs_bytes.len(), crs.serialized_size());
+        let deserialized_crs =
+            CRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(&crs_bytes[..])
+                .unwrap();
+        Protocol::from_crs(&deserialized_crs)
+            .verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        let mut crs_file = vec![];
+        crs.serialize_to(&mut crs_file, true).unwrap();
+        assert_eq!(crs_file.len(), 1 + crs_bytes.len() + 32);
+        let deserialized_crs =
+            CRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_from(&crs_file[..])
+                .unwrap();
+        Protocol::from_crs(&deserialized_crs)
+            .verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        crs_file[1] ^= 1;
+        assert!(
+            CRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_from(&crs_file[..])
+                .is_err()
+        );
+        assert_eq!(proof.proof_id(&statement).unwrap(), id);
+        let other_statement = Statement {
+            c_e_q: statement.c_e_q,
+            c_p: statement.c_p.clone(),
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        assert_ne!(proof.proof_id(&other_statement).unwrap(), id);
+        let proof =
+            MembershipProof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_uncompressed(
+                &uncompressed[..],
+            )
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&proof).unwrap();
+            let proof: MembershipProof<Rsa2048, G1Projective, HPProtocol<Bls12_381>> =
+                serde_json::from_str(&json).unwrap();
+            protocol
+                .verify_noninteractive(&statement, &proof, b"session-1")
+                .unwrap();
+        }
+        assert!(
+            MembershipProof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(
+                &bytes[..bytes.len() - 1]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_verify_membership_bytes() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: Some(b"epoch-1".to_vec()),
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            w: accum.1.witness.0.value,
+        };
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, &[])
+            .unwrap();
+
+        let mut vk_bytes = vec![];
+        protocol
+            .crs
+            .verifier_key()
+            .serialize(&mut vk_bytes)
+            .unwrap();
+        let statement_bytes = statement.to_canonical_bytes().unwrap();
+        let proof_bytes = proof.to_canonical_bytes().unwrap();
+        verify_membership_bytes::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+            &vk_bytes,
+            &statement_bytes,
+            &proof_bytes,
+        )
+        .unwrap();
+
+        let mut trailing_vk = vk_bytes.clone();
+        trailing_vk.push(0);
+        assert!(
+            verify_membership_bytes::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+                &trailing_vk,
+                &statement_bytes,
+                &proof_bytes,
+            )
+            .is_err()
+        );
+
+        let mut trailing = proof_bytes.clone();
+        trailing.push(0);
+        assert!(
+            verify_membership_bytes::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+                &vk_bytes,
+                &statement_bytes,
+                &trailing,
+            )
+            .is_err()
+        );
+        let other_statement = Statement::<Rsa2048, G1Projective> {
+            epoch: None,
+            ..Statement::from_canonical_bytes(&statement_bytes).unwrap()
+        };
+        assert!(
+            verify_membership_bytes::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+                &vk_bytes,
+                &other_statement.to_canonical_bytes().unwrap(),
+                &proof_bytes,
+            )
+            .is_err()
+        );
+
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"session")
+            .unwrap();
+        let proof_bytes = proof.to_canonical_bytes().unwrap();
+        verify_membership::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+            &vk_bytes,
+            &statement_bytes,
+            &proof_bytes,
+            b"session",
+        )
+        .unwrap();
+        assert!(
+            verify_membership_bytes::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+                &vk_bytes,
+                &statement_bytes,
+                &proof_bytes,
+            )
+            .is_err()
+        );
+
+        let proof_bytes = prove_to_bytes(&protocol.crs, &statement, &witness, &mut rng2).unwrap();
+        verify_bytes(&protocol.crs.verifier_key(), &statement, &proof_bytes).unwrap();
+        verify_membership_bytes::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>(
+            &vk_bytes,
+            &statement_bytes,
+            &proof_bytes,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        let values = [245, 387]
+            .iter()
+            .map(|offset| {
+                Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+                    - &Integer::from(*offset)
+            })
+            .collect::<Vec<_>>();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add(&values);
+        let mut batch = vec![];
+        for (i, value) in values.iter().enumerate() {
+            let randomness = Integer::from(5 + i);
+            let commitment = protocol
+                .crs
+                .crs_modeq
+                .pedersen_commitment_parameters
+                .commit(value, &randomness)
+                .unwrap();
+            let others = values
+                .iter()
+                .filter(|other| *other != value)
+                .cloned()
+                .collect::<Vec<_>>();
+            let w =
+                accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty(
+                )
+                .add(&others)
+                .value;
+            let statement = Statement {
+                c_e_q: commitment,
+                c_p: accum.value.clone(),
+                epoch: None,
+            };
+            let proof = protocol
+                .prove_noninteractive(
+                    &mut rng1,
+                    &mut rng2,
+                    &statement,
+                    &Witness {
+                        e: value.clone(),
+                        r_q: randomness,
+                        w,
+                    },
+                    b"batch",
+                )
+                .unwrap();
+            batch.push((statement, proof));
+        }
+        protocol
+            .verify_batch(&mut rng1, &mut rng2, &batch, b"batch")
+            .unwrap();
+        assert!(protocol
+            .verify_batch(&mut rng1, &mut rng2, &batch, b"other")
+            .is_err());
+
+        let c_e_q = batch[0].0.c_e_q;
+        batch[0].0.c_e_q = batch[1].0.c_e_q;
+        batch[1].0.c_e_q = c_e_q;
+        assert!(protocol
+            .verify_batch(&mut rng1, &mut rng2, &batch, b"batch")
+            .is_err());
+
+        // A batch with an identity accumulator is rejected before it's folded.
+        batch[1].0.c_e_q = batch[0].0.c_e_q;
+        batch[0].0.c_e_q = c_e_q;
+        batch[0].0.c_p = Rsa2048::id();
+        assert!(matches!(
+            protocol.verify_batch(&mut rng1, &mut rng2, &batch, b"batch"),
+            Err(VerificationError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_prove_with_metrics() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
         let accum =
-            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
-        let accum = accum.add(
-            &LARGE_PRIMES
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+
+        let proof_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let metrics = protocol
+            .prove_with_metrics(
+                &mut verifier_channel,
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    w: accum.1.witness.0.value,
+                },
+            )
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+        let verification_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        assert_eq!(
+            metrics
+                .sub_protocols
                 .iter()
-                .skip(1)
-                .map(|p| Integer::from(*p))
+                .map(|(name, _)| *name)
                 .collect::<Vec<_>>(),
+            vec!["membership", "root", "modeq", "hash_to_prime"]
         );
+        assert!(metrics.sub_protocol_elapsed("hash_to_prime") <= metrics.total());
+        assert!(metrics.constraints.unwrap() > 0);
+        assert!(metrics.bytes > 0);
+        assert!(metrics.peak_memory_estimate > crs.crs_hash_to_prime.size(false));
+    }
 
-        let accum = accum.add_with_proof(&[value.clone()]);
-        let acc = accum.0.value;
-        let w = accum.1.witness.0.value;
-        assert_eq!(Rsa2048::exp(&w, &value), acc);
+    #[test]
+    fn test_shared_crs() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
 
-        let proof_transcript = RefCell::new(Transcript::new(b"membership"));
-        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+        assert!(Arc::ptr_eq(
+            &protocol.crs.crs_hash_to_prime.hash_to_prime_parameters,
+            &crs.crs_hash_to_prime.hash_to_prime_parameters
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        crs.validate(&mut rng2).unwrap();
+
+        let mut other_bases = crs.clone();
+        other_bases
+            .crs_hash_to_prime
+            .pedersen_commitment_parameters
+            .h = crs.crs_hash_to_prime.pedersen_commitment_parameters.g;
+        assert!(matches!(
+            other_bases.validate(&mut rng2),
+            Err(CRSError::InvalidCommitmentBases)
+        ));
+
+        let mut other_link_bases = crs.clone();
+        let parameters =
+            Arc::make_mut(&mut other_link_bases.crs_hash_to_prime.hash_to_prime_parameters);
+        parameters.vk.link_bases.swap(1, 2);
+        assert!(matches!(
+            other_link_bases.validate(&mut rng2),
+            Err(CRSError::InvalidCommitmentBases)
+        ));
+
+        let mut other_delta = crs.clone();
+        let parameters = Arc::make_mut(&mut other_delta.crs_hash_to_prime.hash_to_prime_parameters);
+        parameters.delta_g1 = parameters.beta_g1;
+        assert!(matches!(
+            other_delta.validate(&mut rng2),
+            Err(CRSError::InvalidKey)
+        ));
+    }
+
+    fn e2e_over_curve<E: PairingEngine>(params: &Parameters) {
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol = Protocol::<Rsa2048, E::G1Projective, HPProtocol<E>>::setup(
+            params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
         let statement = Statement {
             c_e_q: commitment,
-            c_p: acc,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            w: accum.1.witness.0.value,
         };
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"context")
+            .unwrap();
         protocol
-            .prove(
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_e2e_bn254() {
+        let (params, _) = Parameters::from_curve::<ark_bn254::Fr>().unwrap();
+        e2e_over_curve::<Bn254>(&params);
+    }
+
+    #[test]
+    fn test_e2e_bls12_377() {
+        let (params, _) = Parameters::from_curve::<ark_bls12_377::Fr>().unwrap();
+        e2e_over_curve::<Bls12_377>(&params);
+    }
+
+    #[test]
+    fn test_e2e_bw6_761() {
+        let params = Parameters::from_security_level(128).unwrap();
+        e2e_over_curve::<ark_bw6_761::BW6_761>(&params);
+    }
+
+    #[test]
+    fn test_setup_deterministic() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let crs_bytes = |seed| {
+            let protocol =
+                Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                    &params, seed,
+                )
+                .unwrap();
+            let mut bytes = vec![];
+            protocol.crs.serialize(&mut bytes).unwrap();
+            bytes
+        };
+        assert_eq!(crs_bytes([1u8; 32]), crs_bytes([1u8; 32]));
+        assert_ne!(crs_bytes([1u8; 32]), crs_bytes([2u8; 32]));
+    }
+
+    #[test]
+    fn test_crs_fingerprint() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let setup = |seed| {
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, seed,
+            )
+            .unwrap()
+        };
+        let (protocol, other) = (setup([1u8; 32]), setup([2u8; 32]));
+        assert_ne!(protocol.crs.fingerprint(), other.crs.fingerprint());
+
+        // The same statement and witness, proved under the other CRS with
+        // the same bases, aren't accepted.
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+        let mut crs = other.crs.clone();
+        crs.crs_root = protocol.crs.crs_root.clone();
+        crs.crs_modeq = protocol.crs.crs_modeq.clone();
+        crs.crs_hash_to_prime.pedersen_commitment_parameters = protocol
+            .crs
+            .crs_hash_to_prime
+            .pedersen_commitment_parameters
+            .clone();
+        let other = Protocol::from_crs(&crs);
+        assert_ne!(protocol.crs.fingerprint(), crs.fingerprint());
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            w: accum.1.witness.0.value,
+        };
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"context")
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+        other
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_signature_of_knowledge() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [3u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([4u8; 32]);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement::new(accum.0.value, commitment).unwrap();
+        let witness = Witness::new(value, randomness, accum.1.witness.0.value).unwrap();
+        let signature = protocol
+            .sign(
+                &mut rng1, &mut rng2, &statement, &witness, b"context", b"nonce",
+            )
+            .unwrap();
+        protocol
+            .verify_signature(&statement, &signature, b"context", b"nonce")
+            .unwrap();
+        protocol
+            .crs
+            .verifier_key()
+            .verify_signature(&statement, &signature, b"context", b"nonce")
+            .unwrap();
+
+        // Bound to the message, and apart from the plain proofs.
+        protocol
+            .verify_signature(&statement, &signature, b"context", b"other nonce")
+            .unwrap_err();
+        protocol
+            .verify_noninteractive(&statement, &signature, b"context")
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_verifier_key() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            w: accum.1.witness.0.value,
+        };
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"session-1")
+            .unwrap();
+
+        let vk = protocol.crs.verifier_key();
+        assert_eq!(vk.fingerprint(), protocol.crs.fingerprint());
+        let mut vk_bytes = vec![];
+        vk.serialize(&mut vk_bytes).unwrap();
+        assert_eq!(vk_bytes.len(), vk.serialized_size());
+        assert!(vk_bytes.len() < protocol.crs.serialized_size());
+        let vk =
+            VerifierKey::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(&vk_bytes[..])
+                .unwrap();
+        vk.verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        vk.verify_noninteractive(&statement, &proof, b"session-2")
+            .unwrap_err();
+
+        let mut vk_file = vec![];
+        vk.serialize_to(&mut vk_file, false).unwrap();
+        let vk = VerifierKey::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_from(
+            &vk_file[..],
+        )
+        .unwrap();
+        vk.verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_e2e_parallel() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+
+        let proof_transcript = RefCell::new(Transcript::new(b"membership"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove_parallel(
                 &mut verifier_channel,
                 &mut rng1,
                 &mut rng2,
@@ -331,7 +2757,7 @@ mod test {
                 &Witness {
                     e: value,
                     r_q: randomness,
-                    w,
+                    w: accum.1.witness.0.value,
                 },
             )
             .unwrap();
@@ -342,6 +2768,21 @@ mod test {
         protocol.verify(&mut prover_channel, &statement).unwrap();
     }
 
+    #[test]
+    fn test_e2e_prime_rsa_shake() {
+        prove_and_verify_with(|| ShakeTranscript::new(b"membership"));
+    }
+
+    #[test]
+    fn test_e2e_prime_rsa_keccak() {
+        prove_and_verify_with(|| KeccakTranscript::new(b"membership"));
+    }
+
+    #[test]
+    fn test_e2e_prime_rsa_poseidon() {
+        prove_and_verify_with(|| PoseidonTranscript::<Fr>::new(b"membership"));
+    }
+
     #[test]
     fn test_e2e_prime_class_group() {
         let params = Parameters::from_security_level(128).unwrap();
@@ -390,6 +2831,7 @@ mod test {
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -466,6 +2908,7 @@ mod test {
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -507,6 +2950,7 @@ mod test {
     use rug::rand::RandState;
     use rug::Integer;
     use std::cell::RefCell;
+    use std::sync::Arc;
 
     const LARGE_PRIMES: [u64; 4] = [
         553_525_575_239_331_913,
@@ -558,11 +3002,13 @@ mod test {
         assert_eq!(Rsa2048::exp(&w, &value), acc);
 
         let proof_transcript = RefCell::new(Transcript::new(b"membership"));
-        crs.crs_hash_to_prime.hash_to_prime_parameters.transcript = Some(proof_transcript.clone());
+        Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
+            Some(proof_transcript.clone());
         let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -579,7 +3025,7 @@ mod test {
             .unwrap();
         let proof = verifier_channel.proof().unwrap();
         let verification_transcript = RefCell::new(Transcript::new(b"membership"));
-        crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+        Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
             Some(verification_transcript.clone());
         let mut prover_channel =
             TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
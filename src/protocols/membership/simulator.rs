@@ -0,0 +1,227 @@
+//! Simulated proofs for testing integrations, behind the `simulator`
+//! feature. `Simulator::prove` creates a proof of the right shape for any
+//! statement, without a witness and in milliseconds: it serializes, passes
+//! `Proof::validate` and is stored and sent like a real proof, but its
+//! elements are random and it doesn't verify.
+//!
+//! `Simulator::verify` is a verifier that accepts the simulated proofs for
+//! the statement and context they were simulated for, and verifies any other
+//! proof as usual. The commitment `c_e` of a simulated proof is derived from
+//! the CRS, the statement and the context, which is how it's recognized, so
+//! a simulated proof for another statement or context is still rejected.
+//!
+//! Anyone can simulate a proof, so the simulating verifier must never run
+//! outside of tests: `Protocol::verify_noninteractive` and the verifier
+//! key reject simulated proofs.
+use crate::{
+    protocols::{
+        hash_to_prime::{HashToPrimeProtocol, Statement as HashToPrimeStatement},
+        membership::{MembershipProof, Protocol, Statement, CRS},
+        modeq::{Message1 as ModEqMessage1, Message2 as ModEqMessage2, Proof as ModEqProof},
+        response_bound,
+        root::{
+            Message1 as RootMessage1, Message2 as RootMessage2, Message3 as RootMessage3,
+            Message4 as RootMessage4, Proof as RootProof,
+        },
+        s_e_bound, ProofError, VerificationError,
+    },
+    utils::{
+        bytes_to_integer,
+        curve::{CurvePointProjective, Field},
+        random_symmetric_range, ConvertibleUnknownOrderGroup,
+    },
+};
+use ark_serialize::SerializationError;
+use blake2::{Blake2s, Digest};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+
+pub struct Simulator<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    protocol: Protocol<G, P, HP>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Simulator<G, P, HP>
+{
+    pub fn new(crs: &CRS<G, P, HP>) -> Self {
+        Simulator {
+            protocol: Protocol::from_crs(crs),
+        }
+    }
+
+    /// Simulates a proof for the statement and context, with the responses in
+    /// the ranges of the honest ones. Fails with
+    /// `ProofError::CouldNotCreateProof` for the hash-to-prime backends that
+    /// can't simulate their proofs, i.e., Bulletproofs.
+    pub fn prove<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        context: &[u8],
+    ) -> Result<MembershipProof<G, P, HP>, ProofError> {
+        let parameters = &self.protocol.crs.parameters;
+        let s_e_bound = s_e_bound(parameters);
+        let bound = response_bound::<G>(parameters);
+        let proof_root = RootProof {
+            message1: RootMessage1 {
+                c_w: random_elem::<G, _>(rng1),
+                c_r: random_elem::<G, _>(rng1),
+            },
+            message2: RootMessage2 {
+                alpha1: random_elem::<G, _>(rng1),
+                alpha2: random_elem::<G, _>(rng1),
+                alpha3: random_elem::<G, _>(rng1),
+                alpha4: random_elem::<G, _>(rng1),
+            },
+            message3: RootMessage3 {
+                s_e: random_symmetric_range(rng1, &s_e_bound),
+                s_r: random_symmetric_range(rng1, &bound),
+                s_r_2: random_symmetric_range(rng1, &bound),
+                s_r_3: random_symmetric_range(rng1, &bound),
+                s_beta: random_symmetric_range(rng1, &bound),
+                s_delta: random_symmetric_range(rng1, &bound),
+            },
+            message4: RootMessage4 {
+                q1: random_elem::<G, _>(rng1),
+                q2: random_elem::<G, _>(rng1),
+                q3: random_elem::<G, _>(rng1),
+                q4: random_elem::<G, _>(rng1),
+            },
+        };
+        let proof_modeq = ModEqProof {
+            message1: ModEqMessage1 {
+                alpha1: random_elem::<G, _>(rng1),
+                alpha2: P::rand(rng2),
+            },
+            message2: ModEqMessage2 {
+                s_e: random_symmetric_range(rng1, &s_e_bound),
+                s_r: random_symmetric_range(rng1, &bound),
+                s_r_q: P::ScalarField::rand(rng2),
+            },
+        };
+        let proof_hash_to_prime = self
+            .protocol
+            .hash_to_prime
+            .simulate_proof(
+                rng2,
+                &HashToPrimeStatement {
+                    c_e_q: statement.c_e_q.clone(),
+                },
+            )
+            .ok_or(ProofError::CouldNotCreateProof)?;
+        Ok(MembershipProof {
+            c_e: self.tag(statement, context)?,
+            proof_root,
+            proof_modeq,
+            proof_hash_to_prime,
+        })
+    }
+
+    /// Accepts a proof simulated for the statement and context, and verifies
+    /// any other proof with `Protocol::verify_noninteractive`.
+    pub fn verify(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        proof.validate(&self.protocol.crs.parameters)?;
+        if proof.c_e == self.tag(statement, context)? {
+            return Ok(());
+        }
+        self.protocol
+            .verify_noninteractive(statement, proof, context)
+    }
+
+    /// The commitment `c_e` of the proofs simulated for the statement and
+    /// context.
+    fn tag(
+        &self,
+        statement: &Statement<G, P>,
+        context: &[u8],
+    ) -> Result<G::Elem, SerializationError> {
+        let mut hasher = Blake2s::new();
+        hasher.update(b"cpsnarks-set simulated proof");
+        hasher.update(&self.protocol.crs.fingerprint());
+        hasher.update(&(context.len() as u64).to_be_bytes());
+        hasher.update(context);
+        hasher.update(&statement.to_canonical_bytes()?);
+        let exponent = bytes_to_integer(&hasher.finalize());
+        Ok(G::exp(&G::unknown_order_elem(), &exponent))
+    }
+}
+
+fn random_elem<G: ConvertibleUnknownOrderGroup, R: MutRandState>(rng: &mut R) -> G::Elem {
+    G::exp(
+        &G::unknown_order_elem(),
+        &G::order_upper_bound().random_below(rng),
+    )
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::Simulator;
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{MembershipProof, Protocol, Statement},
+        },
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::{Group, Rsa2048};
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use ark_serialize::CanonicalDeserialize;
+    use rug::Integer;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_simulated_proofs() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [11u8; 32],
+            )
+            .unwrap();
+        let simulator = Simulator::new(&protocol.crs);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&Integer::from(12), &Integer::from(5))
+            .unwrap();
+        let statement = Statement::new(Rsa2048::elem(3), commitment).unwrap();
+
+        let (mut rng1, mut rng2) = seeded_rngs([12u8; 32]);
+        let start = Instant::now();
+        let proof = simulator
+            .prove(&mut rng1, &mut rng2, &statement, b"simulated")
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let bytes = proof.to_canonical_bytes().unwrap();
+        let proof = MembershipProof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(
+            &bytes[..],
+        )
+        .unwrap();
+        proof.validate(&params).unwrap();
+        simulator.verify(&statement, &proof, b"simulated").unwrap();
+
+        assert!(simulator.verify(&statement, &proof, b"other").is_err());
+        assert!(protocol
+            .verify_noninteractive(&statement, &proof, b"simulated")
+            .is_err());
+        assert!(protocol
+            .crs
+            .verifier_key()
+            .verify_noninteractive(&statement, &proof, b"simulated")
+            .is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! Generates a Solidity verifier for non-interactive membership proofs, so
+//! that an accumulator can gate on-chain actions.
+//!
+//! The contract embeds the CRS and verifies proofs over an RSA group and
+//! BN254, as encoded by `calldata::encode_calldata`. The root and modeq checks
+//! use the modexp precompile for the RSA group, moving the factors with
+//! negative exponents to the other side of each equation instead of inverting
+//! them, and the LegoGroth16 proof is checked with the pairing precompile.
+//! Proofs have to be created with `prove_noninteractive_with` over
+//! `KeccakTranscript::new(b"cpsnarks-set")`, whose challenges the contract
+//! recomputes with `keccak256`, and verifying costs several million gas for
+//! 2048-bit moduli.
+use crate::{
+    protocols::{
+        hash_to_prime::snark_range::Protocol as HPProtocol,
+        membership::{calldata::element_width, CRS},
+    },
+    utils::{
+        bytes_to_integer,
+        curve::{CurveError, CurvePointProjective},
+        group::ElemFromBytes,
+        integer_to_bytes,
+        serialization::to_hex,
+    },
+};
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::Field;
+use ark_serialize::{CanonicalSerialize, SerializationError};
+use rug::Integer;
+use thiserror::Error;
+
+const TEMPLATE: &str = include_str!("verifier.sol");
+
+/// The base field modulus of BN254, in little-endian 64-bit limbs.
+const BN254_MODULUS: [u64; 4] = [
+    0x3c20_8c16_d87c_fd47,
+    0x9781_6a91_6871_ca8d,
+    0xb850_45b6_8181_585d,
+    0x3064_4e72_e131_a029,
+];
+
+const WORD_SIZE: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum SolidityError {
+    #[error("the curve has no precompiles")]
+    UnsupportedCurve,
+    #[error("the group is not supported")]
+    UnsupportedGroup,
+    #[error("the parameters are not supported")]
+    UnsupportedParameters,
+    #[error("invalid contract name")]
+    InvalidContractName,
+    #[error("could not encode a curve point")]
+    CurveError(#[from] CurveError),
+    #[error("could not serialize the verifying key")]
+    SerializationError(#[from] SerializationError),
+}
+
+struct Constants(String);
+
+impl Constants {
+    fn uint(&mut self, name: &str, value: &[u8]) -> Result<(), SolidityError> {
+        if value.len() > WORD_SIZE {
+            return Err(SolidityError::UnsupportedCurve);
+        }
+        self.0 += &format!("    uint256 constant {} = 0x{};\n", name, to_hex(value));
+        Ok(())
+    }
+
+    fn bytes(&mut self, name: &str, value: &[u8]) {
+        self.0 += &format!("    bytes constant {} = hex\"{}\";\n", name, to_hex(value));
+    }
+
+    fn g1<P: CurvePointProjective>(&mut self, name: &str, point: &P) -> Result<(), SolidityError> {
+        let (x, y) = point.to_affine_coordinates()?;
+        self.uint(&format!("{}_X", name), &x)?;
+        self.uint(&format!("{}_Y", name), &y)
+    }
+
+    /// G2 points in the order of the pairing precompile: x_1, x_0, y_1, y_0.
+    fn g2<E: PairingEngine>(
+        &mut self,
+        name: &str,
+        point: &E::G2Affine,
+    ) -> Result<(), SolidityError> {
+        // The uncompressed encoding is x_0, x_1, y_0 and y_1 in little-endian,
+        // with the flags in the top bits of y_1.
+        let mut bytes = vec![];
+        point.serialize_uncompressed(&mut bytes)?;
+        if bytes.len() != 4 * WORD_SIZE {
+            return Err(SolidityError::UnsupportedCurve);
+        }
+        let mut coordinates = bytes
+            .chunks(WORD_SIZE)
+            .map(|c| c.to_vec())
+            .collect::<Vec<_>>();
+        coordinates[3][WORD_SIZE - 1] &= 0x3f;
+        for (i, j) in [1, 0, 3, 2].iter().enumerate() {
+            let mut coordinate = coordinates[*j].clone();
+            coordinate.reverse();
+            self.uint(&format!("{}_{}", name, i), &coordinate)?;
+        }
+        Ok(())
+    }
+}
+
+fn left_pad(bytes: &[u8], width: usize) -> Vec<u8> {
+    let mut padded = vec![0u8; width - bytes.len()];
+    padded.extend_from_slice(bytes);
+    padded
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Generates the source of a verifier contract with the given name for the
+/// CRS, which has to be over BN254 with the LegoGroth16 range proof.
+pub fn generate_verifier<G: ElemFromBytes, E: PairingEngine>(
+    crs: &CRS<G, E::G1Projective, HPProtocol<E>>,
+    contract_name: &str,
+) -> Result<String, SolidityError> {
+    if E::Fq::characteristic() != &BN254_MODULUS[..] {
+        return Err(SolidityError::UnsupportedCurve);
+    }
+    if !is_identifier(contract_name) {
+        return Err(SolidityError::InvalidContractName);
+    }
+    let parameters = &crs.parameters;
+    if parameters.security_soundness % 8 != 0
+        || parameters.security_soundness as usize > 8 * WORD_SIZE
+    {
+        return Err(SolidityError::UnsupportedParameters);
+    }
+
+    // The RSA groups are quotients by {1, -1}, whose order upper bound is
+    // (N - 1)/2, and their elements are represented in [1, (N - 1)/2].
+    let max_element = G::order_upper_bound();
+    let modulus = Integer::from(&max_element * 2) + 1;
+    let integer_commitment = &crs.crs_root.integer_commitment_parameters;
+    let g = bytes_to_integer(&G::elem_to_bytes(&integer_commitment.g));
+    let square = g.clone().square() % &modulus;
+    let normalized = square.clone().min(Integer::from(&modulus - &square));
+    if bytes_to_integer(&G::elem_to_bytes(&G::op(
+        &integer_commitment.g,
+        &integer_commitment.g,
+    ))) != normalized
+    {
+        return Err(SolidityError::UnsupportedGroup);
+    }
+
+    let width = element_width::<G>();
+    let element = |bytes: Vec<u8>| -> Result<Vec<u8>, SolidityError> {
+        if bytes.len() > width {
+            return Err(SolidityError::UnsupportedGroup);
+        }
+        Ok(left_pad(&bytes, width))
+    };
+    let mut constants = Constants(String::new());
+    constants.0 += &format!("    uint256 constant ELEMENT_WIDTH = {};\n", width);
+    constants.0 += &format!(
+        "    uint256 constant CHALLENGE_BYTES = {};\n",
+        parameters.security_soundness / 8
+    );
+    constants.bytes("MODULUS", &element(integer_to_bytes(&modulus))?);
+    constants.bytes("MAX_ELEMENT", &element(integer_to_bytes(&max_element))?);
+    constants.bytes("G", &element(G::elem_to_bytes(&integer_commitment.g))?);
+    constants.bytes("H", &element(G::elem_to_bytes(&integer_commitment.h))?);
+    constants.bytes("LABEL", &crs.transcript_label);
+    constants.bytes("CRS_FINGERPRINT", &crs.fingerprint());
+    let s_e_bound = Integer::from(Integer::u_pow_u(
+        2,
+        (parameters.security_zk + parameters.security_soundness + parameters.hash_to_prime_bits + 1)
+            as u32,
+    ));
+    constants.bytes("S_E_BOUND", &integer_to_bytes(&s_e_bound));
+
+    let pedersen = &crs.crs_modeq.pedersen_commitment_parameters;
+    constants.g1("PEDERSEN_G", &pedersen.g)?;
+    constants.g1("PEDERSEN_H", &pedersen.h)?;
+
+    let vk = &crs.crs_hash_to_prime.hash_to_prime_parameters.vk;
+    if vk.link_vk.c.len() != 2 || vk.link_bases.is_empty() {
+        return Err(SolidityError::UnsupportedParameters);
+    }
+    constants.g1("ALPHA_NEG", &(-vk.alpha_g1).into_projective())?;
+    constants.g2::<E>("BETA", &vk.beta_g2)?;
+    constants.g2::<E>("GAMMA_NEG", &-vk.gamma_g2)?;
+    constants.g2::<E>("DELTA_NEG", &-vk.delta_g2)?;
+    constants.g2::<E>("LINK_C0", &vk.link_vk.c[0])?;
+    constants.g2::<E>("LINK_C1", &vk.link_vk.c[1])?;
+    constants.g2::<E>("LINK_A_NEG", &-vk.link_vk.a)?;
+    constants.g1("LINK_BASE", &vk.link_bases[0].into_projective())?;
+
+    Ok(TEMPLATE
+        .replace("{{CONTRACT_NAME}}", contract_name)
+        .replace("{{CONSTANTS}}", &constants.0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generate_verifier, is_identifier, Constants, SolidityError};
+    use crate::{
+        parameters::Parameters,
+        protocols::{hash_to_prime::snark_range::Protocol as HPProtocol, membership::Protocol},
+    };
+    use accumulator::group::Rsa2048;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    #[test]
+    fn test_constants() {
+        let mut constants = Constants(String::new());
+        constants.uint("A", &[1, 2]).unwrap();
+        constants.bytes("B", &[0, 255]);
+        assert_eq!(
+            constants.0,
+            "    uint256 constant A = 0x0102;\n    bytes constant B = hex\"00ff\";\n"
+        );
+        assert!(constants.uint("C", &[0u8; 33]).is_err());
+
+        assert!(is_identifier("MembershipVerifier_2"));
+        assert!(!is_identifier("2Verifier"));
+        assert!(!is_identifier("Verifier {"));
+    }
+
+    #[test]
+    fn test_unsupported_curve() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs;
+        assert!(matches!(
+            generate_verifier(&crs, "MembershipVerifier"),
+            Err(SolidityError::UnsupportedCurve)
+        ));
+    }
+
+    #[test]
+    fn test_bn254() {
+        // The contract draws challenges of whole bytes.
+        let (mut params, _) = Parameters::from_curve::<ark_bn254::Fr>().unwrap();
+        params.security_soundness = 120;
+        params.is_valid().unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs =
+            Protocol::<Rsa2048, ark_bn254::G1Projective, HPProtocol<ark_bn254::Bn254>>::setup(
+                &params, &mut rng1, &mut rng2,
+            )
+            .unwrap()
+            .crs;
+        let contract = generate_verifier(&crs, "MembershipVerifier").unwrap();
+        assert!(contract.contains("contract MembershipVerifier {"));
+    }
+}
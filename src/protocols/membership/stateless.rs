@@ -0,0 +1,203 @@
+//! A verifier for stateless clients and light on-chain logic, which holds the
+//! verifier key and the digest of the latest accumulator, and nothing about
+//! the set: not its elements, the accumulator itself or any witness.
+//!
+//! ```ignore
+//! let mut verifier = StatelessVerifier::from_bytes(&verifier_bytes)?;
+//! verifier.verify(&statement, &proof, b"context")?;
+//! // ... the set changes, and the client learns the new digest
+//! verifier.update_digest(new_digest);
+//! ```
+//!
+//! The digest is `calldata::accumulator_digest`, the one a contract stores,
+//! and a proof is accepted only for a statement whose accumulator hashes to
+//! it, so a proof against an older accumulator is rejected once the digest
+//! moves on. The encoding is the digest followed by the compressed verifier
+//! key.
+use crate::{
+    protocols::{
+        hash_to_prime::HashToPrimeProtocol,
+        membership::{
+            calldata::accumulator_digest, verify_bytes_with_context, MembershipProof, Statement,
+            VerifierKey,
+        },
+        VerificationError,
+    },
+    utils::{curve::CurvePointProjective, group::ElemFromBytes},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+
+pub struct StatelessVerifier<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+{
+    key: VerifierKey<G, P, HP>,
+    digest: [u8; 32],
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    StatelessVerifier<G, P, HP>
+{
+    pub fn new(key: VerifierKey<G, P, HP>, digest: [u8; 32]) -> Self {
+        StatelessVerifier { key, digest }
+    }
+
+    /// A verifier for the accumulator, keeping only its digest.
+    pub fn for_accumulator(
+        key: VerifierKey<G, P, HP>,
+        accumulator: &G::Elem,
+    ) -> Result<Self, VerificationError> {
+        let digest = accumulator_digest::<G>(accumulator)
+            .map_err(|_| VerificationError::AccumulatorDigestMismatch)?;
+        Ok(Self::new(key, digest))
+    }
+
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    /// Replaces the digest with the one of the latest accumulator.
+    pub fn update_digest(&mut self, digest: [u8; 32]) {
+        self.digest = digest;
+    }
+
+    /// Verifies the proof for the statement, whose accumulator has to be the
+    /// latest one, with the context the proof was created with.
+    pub fn verify(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &MembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.check_digest(statement)?;
+        self.key.verify_noninteractive(statement, proof, context)
+    }
+
+    /// Verifies a canonical statement and a compressed proof, rejecting
+    /// trailing bytes and proofs that don't validate against the parameters.
+    pub fn verify_bytes(
+        &self,
+        statement_bytes: &[u8],
+        proof_bytes: &[u8],
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let statement = Statement::<G, P>::from_canonical_bytes(statement_bytes)?;
+        self.check_digest(&statement)?;
+        verify_bytes_with_context(&self.key, &statement, proof_bytes, context)
+    }
+
+    fn check_digest(&self, statement: &Statement<G, P>) -> Result<(), VerificationError> {
+        match accumulator_digest::<G>(&statement.c_p) {
+            Ok(digest) if digest == self.digest => Ok(()),
+            _ => Err(VerificationError::AccumulatorDigestMismatch),
+        }
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    StatelessVerifier<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalSerialize,
+{
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = self.digest.to_vec();
+        self.key.serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    StatelessVerifier<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    /// Reads a verifier written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() < 32 {
+            return Err(SerializationError::InvalidData);
+        }
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&bytes[..32]);
+        let mut reader = &bytes[32..];
+        let key = VerifierKey::deserialize(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Self::new(key, digest))
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::StatelessVerifier;
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{calldata::accumulator_digest, Protocol, Statement, Witness},
+            VerificationError,
+        },
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rug::Integer;
+
+    #[test]
+    fn test_stateless_verifier() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [81u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([82u8; 32]);
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 245;
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement::new(accum.0.value.clone(), commitment).unwrap();
+        let witness = Witness::new(value, randomness, accum.1.witness.0.value).unwrap();
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"context")
+            .unwrap();
+
+        let verifier =
+            StatelessVerifier::for_accumulator(protocol.crs.verifier_key(), &accum.0.value)
+                .unwrap();
+        let mut verifier =
+            StatelessVerifier::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_bytes(
+                &verifier.to_bytes().unwrap(),
+            )
+            .unwrap();
+        verifier.verify(&statement, &proof, b"context").unwrap();
+        assert!(verifier.verify(&statement, &proof, b"other").is_err());
+        let statement_bytes = statement.to_canonical_bytes().unwrap();
+        let mut proof_bytes = proof.to_canonical_bytes().unwrap();
+        verifier
+            .verify_bytes(&statement_bytes, &proof_bytes, b"context")
+            .unwrap();
+        proof_bytes.push(0);
+        assert!(verifier
+            .verify_bytes(&statement_bytes, &proof_bytes, b"context")
+            .is_err());
+
+        // Once the set changes, the proofs against the old accumulator fail.
+        let next = accum
+            .0
+            .add(&[Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32)) - 159]);
+        verifier.update_digest(accumulator_digest::<Rsa2048>(&next.value).unwrap());
+        assert!(matches!(
+            verifier.verify(&statement, &proof, b"context"),
+            Err(VerificationError::AccumulatorDigestMismatch)
+        ));
+    }
+}
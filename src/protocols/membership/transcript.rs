@@ -5,7 +5,7 @@ use crate::{
         hash_to_prime::{
             channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
             transcript::{
-                TranscriptProtocolHashToPrime,
+                FiatShamir, TranscriptProtocolHashToPrime,
                 TranscriptProverChannel as HashToPrimeTranscriptProverChannel,
                 TranscriptVerifierChannel as HashToPrimeTranscriptVerifierChannel,
             },
@@ -13,7 +13,7 @@ use crate::{
         },
         membership::{
             channel::{MembershipProverChannel, MembershipVerifierChannel},
-            Proof, CRS,
+            Proof, VerifierKey, CRS,
         },
         modeq::{
             channel::{ModEqProverChannel, ModEqVerifierChannel},
@@ -30,20 +30,22 @@ use crate::{
             },
         },
     },
-    transcript::{TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolInteger},
+    transcript::{
+        TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolEpoch,
+        TranscriptProtocolInteger,
+    },
     utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
 };
-use merlin::Transcript;
+use core::cell::RefCell;
 use rug::Integer;
-use std::cell::RefCell;
 
 pub trait TranscriptProtocolMembership<G: ConvertibleUnknownOrderGroup>:
-    TranscriptProtocolInteger<G> + TranscriptProtocolChallenge
+    TranscriptProtocolInteger<G> + TranscriptProtocolChallenge + TranscriptProtocolEpoch
 {
     fn membership_domain_sep(&mut self);
 }
 
-impl<G: ConvertibleUnknownOrderGroup> TranscriptProtocolMembership<G> for Transcript {
+impl<G: ConvertibleUnknownOrderGroup, T: FiatShamir> TranscriptProtocolMembership<G> for T {
     fn membership_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"membership");
     }
@@ -144,9 +146,19 @@ impl<
     ) -> Result<(), ChannelError> {
         self.root_transcript_verifier_channel.send_message3(message)
     }
+    fn send_message4(
+        &mut self,
+        message: &crate::protocols::root::Message4<G>,
+    ) -> Result<(), ChannelError> {
+        self.root_transcript_verifier_channel.send_message4(message)
+    }
     fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
         self.root_transcript_verifier_channel.receive_challenge()
     }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.root_transcript_verifier_channel
+            .receive_poe_challenge()
+    }
 }
 
 impl<
@@ -233,10 +245,17 @@ impl<
     fn receive_message3(&mut self) -> Result<crate::protocols::root::Message3, ChannelError> {
         self.root_transcript_prover_channel.receive_message3()
     }
+    fn receive_message4(&mut self) -> Result<crate::protocols::root::Message4<G>, ChannelError> {
+        self.root_transcript_prover_channel.receive_message4()
+    }
     fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
         self.root_transcript_prover_channel
             .generate_and_send_challenge()
     }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.root_transcript_prover_channel
+            .generate_and_send_poe_challenge()
+    }
 }
 
 impl<
@@ -291,6 +310,13 @@ impl<
             + TranscriptProtocolHashToPrime<P>,
     > MembershipVerifierChannel<G> for TranscriptVerifierChannel<'a, G, P, HP, T>
 {
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.membership_domain_sep();
+        transcript.append_epoch(epoch);
+        Ok(())
+    }
+
     fn send_c_e(
         &mut self,
         c_e: &<IntegerCommitment<G> as Commitment>::Instance,
@@ -314,6 +340,13 @@ impl<
             + TranscriptProtocolHashToPrime<P>,
     > MembershipProverChannel<G> for TranscriptProverChannel<'a, G, P, HP, T>
 {
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.membership_domain_sep();
+        transcript.append_epoch(epoch);
+        Ok(())
+    }
+
     fn receive_c_e(
         &mut self,
     ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError> {
@@ -360,4 +393,32 @@ impl<
             proof: proof.clone(),
         }
     }
+
+    /// Like `new`, for verifiers holding only the verifier key.
+    pub fn with_verifier_key(
+        vk: &VerifierKey<G, P, HP>,
+        transcript: &'a RefCell<T>,
+        proof: &Proof<G, P, HP>,
+    ) -> TranscriptProverChannel<'a, G, P, HP, T> {
+        TranscriptProverChannel {
+            transcript,
+            root_transcript_prover_channel: RootTranscriptProverChannel::new(
+                &vk.crs_root,
+                transcript,
+                &proof.proof_root,
+            ),
+            modeq_transcript_prover_channel: ModEqTranscriptProverChannel::new(
+                &vk.crs_modeq,
+                transcript,
+                &proof.proof_modeq,
+            ),
+            hash_to_prime_transcript_prover_channel:
+                HashToPrimeTranscriptProverChannel::with_verifier_crs(
+                    &vk.crs_hash_to_prime,
+                    transcript,
+                    &proof.proof_hash_to_prime,
+                ),
+            proof: proof.clone(),
+        }
+    }
 }
@@ -0,0 +1,374 @@
+//! A JSON format for exchanging test vectors of the membership protocol, to
+//! test independent implementations of the paper's protocols against each
+//! other.
+//!
+//! A file names the suite of backends the vectors are for and lists the
+//! vectors, each holding the parameters, the Blake2s digest of the CRS in its
+//! compressed `CanonicalSerialize` encoding, the context, the canonical
+//! statement, optionally the witness, the compressed proof and whether it's
+//! expected to be accepted:
+//!
+//! ```json
+//! {"version":1,"suite":"rsa2048-bls12_381-legogro16-range","vectors":[
+//!  {"name":"valid","parameters":{"security_level":128,...},"crs_digest":"...",
+//!   "context":"...","statement":"...","witness":{"e":"...","r_q":"...","w":"..."},
+//!   "proof":"...","expected":"accept"}]}
+//! ```
+//!
+//! Byte strings are hex-encoded and the integers of the witness are decimal.
+use crate::{
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::{CRSSize, HashToPrimeProtocol},
+        membership::{MembershipProof, Protocol, Statement, Witness, CRS},
+    },
+    utils::{
+        curve::CurvePointProjective,
+        group::ElemFromBytes,
+        serialization::{from_hex, to_hex},
+        Redacted,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use blake2::{Blake2s, Digest};
+use rug::Integer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+pub const VECTORS_VERSION: u16 = 1;
+
+#[derive(Debug, Error)]
+pub enum VectorError {
+    #[error("unsupported test vectors version {0}")]
+    UnsupportedVersion(u16),
+    #[error("the vectors are for other parameters")]
+    ParametersMismatch,
+    #[error("the vectors are for another CRS")]
+    CRSMismatch,
+    #[error("invalid {0}")]
+    InvalidField(&'static str),
+    #[error("vector {0} doesn't have the expected outcome {1:?}")]
+    UnexpectedOutcome(String, Outcome),
+    #[error("invalid JSON")]
+    JsonError(#[from] serde_json::Error),
+    #[error("could not deserialize a component")]
+    SerializationError(#[from] SerializationError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Accept,
+    Reject,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParametersVector {
+    pub security_level: u16,
+    pub security_zk: u16,
+    pub security_soundness: u16,
+    pub hash_to_prime_bits: u16,
+    pub field_size_bits: u16,
+}
+
+impl From<&Parameters> for ParametersVector {
+    fn from(parameters: &Parameters) -> Self {
+        ParametersVector {
+            security_level: parameters.security_level,
+            security_zk: parameters.security_zk,
+            security_soundness: parameters.security_soundness,
+            hash_to_prime_bits: parameters.hash_to_prime_bits,
+            field_size_bits: parameters.field_size_bits,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessVector {
+    pub e: String,
+    pub r_q: String,
+    pub w: String,
+}
+
+impl fmt::Debug for WitnessVector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WitnessVector")
+            .field("e", &Redacted)
+            .field("r_q", &Redacted)
+            .field("w", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub parameters: ParametersVector,
+    pub crs_digest: String,
+    pub context: String,
+    pub statement: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<WitnessVector>,
+    pub proof: String,
+    pub expected: Outcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub version: u16,
+    /// Names the group, curve and hash-to-prime backends of the vectors.
+    pub suite: String,
+    pub vectors: Vec<TestVector>,
+}
+
+fn decode(field: &str, name: &'static str) -> Result<Vec<u8>, VectorError> {
+    from_hex(field).ok_or(VectorError::InvalidField(name))
+}
+
+fn decode_integer(field: &str, name: &'static str) -> Result<Integer, VectorError> {
+    Integer::from_str_radix(field, 10).map_err(|_| VectorError::InvalidField(name))
+}
+
+/// The Blake2s digest of the compressed CRS, identifying it in the vectors.
+pub fn crs_digest<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+    crs: &CRS<G, P, HP>,
+) -> Result<[u8; 32], SerializationError>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    let mut bytes = vec![];
+    crs.serialize(&mut bytes)?;
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Blake2s::digest(&bytes));
+    Ok(digest)
+}
+
+impl TestVector {
+    pub fn new<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+        name: &str,
+        crs: &CRS<G, P, HP>,
+        context: &[u8],
+        statement: &Statement<G, P>,
+        witness: Option<&Witness<G>>,
+        proof: &MembershipProof<G, P, HP>,
+        expected: Outcome,
+    ) -> Result<TestVector, VectorError>
+    where
+        HP::Parameters: CanonicalSerialize + CRSSize,
+    {
+        Ok(TestVector {
+            name: name.to_string(),
+            parameters: (&crs.parameters).into(),
+            crs_digest: to_hex(&crs_digest(crs)?),
+            context: to_hex(context),
+            statement: to_hex(&statement.to_canonical_bytes()?),
+            witness: witness.map(|witness| WitnessVector {
+                e: witness.e.to_string(),
+                r_q: witness.r_q.to_string(),
+                w: to_hex(&G::elem_to_bytes(&witness.w)),
+            }),
+            proof: to_hex(&proof.to_canonical_bytes()?),
+            expected,
+        })
+    }
+
+    pub fn statement<G: ElemFromBytes, P: CurvePointProjective>(
+        &self,
+    ) -> Result<Statement<G, P>, VectorError> {
+        Ok(Statement::from_canonical_bytes(&decode(
+            &self.statement,
+            "statement",
+        )?)?)
+    }
+
+    pub fn witness<G: ElemFromBytes>(&self) -> Result<Option<Witness<G>>, VectorError> {
+        self.witness
+            .as_ref()
+            .map(|witness| {
+                Ok(Witness {
+                    e: decode_integer(&witness.e, "e")?,
+                    r_q: decode_integer(&witness.r_q, "r_q")?,
+                    w: G::elem_from_bytes(&decode(&witness.w, "w")?)
+                        .ok_or(VectorError::InvalidField("w"))?,
+                })
+            })
+            .transpose()
+    }
+
+    /// Checks that the vector is for the CRS and that verifying its proof
+    /// gives the expected outcome. A proof that can't be decoded counts as
+    /// rejected.
+    pub fn check<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+        &self,
+        crs: &CRS<G, P, HP>,
+    ) -> Result<(), VectorError>
+    where
+        HP::Parameters: CanonicalSerialize + CRSSize,
+    {
+        if self.parameters != (&crs.parameters).into() {
+            return Err(VectorError::ParametersMismatch);
+        }
+        if decode(&self.crs_digest, "crs_digest")? != crs_digest(crs)? {
+            return Err(VectorError::CRSMismatch);
+        }
+        let statement = self.statement::<G, P>()?;
+        let context = decode(&self.context, "context")?;
+        let accepted = decode(&self.proof, "proof")
+            .ok()
+            .and_then(|bytes| MembershipProof::<G, P, HP>::deserialize(&bytes[..]).ok())
+            .map(|proof| {
+                Protocol::from_crs(crs)
+                    .verify_noninteractive(&statement, &proof, &context)
+                    .is_ok()
+            })
+            .unwrap_or(false);
+        let outcome = if accepted {
+            Outcome::Accept
+        } else {
+            Outcome::Reject
+        };
+        if outcome != self.expected {
+            return Err(VectorError::UnexpectedOutcome(
+                self.name.clone(),
+                self.expected,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl TestVectors {
+    pub fn new(suite: &str) -> TestVectors {
+        TestVectors {
+            version: VECTORS_VERSION,
+            suite: suite.to_string(),
+            vectors: vec![],
+        }
+    }
+
+    /// Checks all the vectors against the CRS.
+    pub fn check<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>(
+        &self,
+        crs: &CRS<G, P, HP>,
+    ) -> Result<(), VectorError>
+    where
+        HP::Parameters: CanonicalSerialize + CRSSize,
+    {
+        self.vectors.iter().try_for_each(|vector| vector.check(crs))
+    }
+
+    pub fn to_json(&self) -> Result<String, VectorError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses test vectors, rejecting other versions.
+    pub fn from_json(json: &str) -> Result<TestVectors, VectorError> {
+        let vectors: TestVectors = serde_json::from_str(json)?;
+        if vectors.version != VECTORS_VERSION {
+            return Err(VectorError::UnsupportedVersion(vectors.version));
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{Outcome, TestVector, TestVectors, VectorError};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::snark_range::Protocol as HPProtocol,
+            membership::{Protocol, Statement, Witness},
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    #[test]
+    fn test_vectors() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            w: accum.1.witness.0.value,
+        };
+        let proof = protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, b"vectors")
+            .unwrap();
+
+        let mut vectors = TestVectors::new("rsa2048-bls12_381-legogro16-range");
+        vectors.vectors.push(
+            TestVector::new(
+                "valid",
+                &protocol.crs,
+                b"vectors",
+                &statement,
+                Some(&witness),
+                &proof,
+                Outcome::Accept,
+            )
+            .unwrap(),
+        );
+        vectors.vectors.push(
+            TestVector::new(
+                "wrong context",
+                &protocol.crs,
+                b"other",
+                &statement,
+                None,
+                &proof,
+                Outcome::Reject,
+            )
+            .unwrap(),
+        );
+
+        let vectors = TestVectors::from_json(&vectors.to_json().unwrap()).unwrap();
+        vectors.check(&protocol.crs).unwrap();
+        let decoded = vectors.vectors[0].witness::<Rsa2048>().unwrap().unwrap();
+        assert_eq!(decoded.e, witness.e);
+        assert_eq!(decoded.w, witness.w);
+
+        let mut vectors = vectors;
+        vectors.vectors[1].expected = Outcome::Accept;
+        assert!(matches!(
+            vectors.check(&protocol.crs),
+            Err(VectorError::UnexpectedOutcome(_, Outcome::Accept))
+        ));
+        vectors.vectors[0].crs_digest = "00".repeat(32);
+        assert!(matches!(
+            vectors.vectors[0].check(&protocol.crs),
+            Err(VectorError::CRSMismatch)
+        ));
+    }
+}
@@ -0,0 +1,643 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.0;
+
+/// Verifies non-interactive membership proofs over an RSA group and BN254,
+/// created with `prove_noninteractive_with` over
+/// `KeccakTranscript::new(b"cpsnarks-set")` and encoded by `encode_calldata`.
+///
+/// Generated from a CRS by `cpsnarks_set::protocols::membership::solidity`.
+contract {{CONTRACT_NAME}} {
+{{CONSTANTS}}
+    /// The base field and the scalar field of BN254.
+    uint256 constant P = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+    uint256 constant Q = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+
+    uint256 constant MASK_128 = 0xffffffffffffffffffffffffffffffff;
+
+    /// The length of an encoded LegoGroth16 proof: the points a, b, c, d,
+    /// link_d and link_pi, uncompressed, with b in G2.
+    uint256 constant HASH_TO_PRIME_LENGTH = 448;
+
+    // The positions of the group elements.
+    uint256 constant C_E = 0;
+    uint256 constant C_W = 1;
+    uint256 constant C_R = 2;
+    uint256 constant ALPHA1 = 3;
+    uint256 constant ALPHA2 = 4;
+    uint256 constant ALPHA3 = 5;
+    uint256 constant ALPHA4 = 6;
+    uint256 constant Q1 = 7;
+    uint256 constant Q2 = 8;
+    uint256 constant Q3 = 9;
+    uint256 constant Q4 = 10;
+    uint256 constant MODEQ_ALPHA1 = 11;
+    uint256 constant ELEMENTS = 12;
+
+    // The positions of the integers.
+    uint256 constant S_E = 0;
+    uint256 constant S_R = 1;
+    uint256 constant MODEQ_S_E = 6;
+    uint256 constant MODEQ_S_R = 7;
+    uint256 constant INTEGERS = 8;
+
+    /// The tuple encoded by `encode_calldata`.
+    struct Proof {
+        bytes32 accumulatorDigest;
+        bytes accumulator;
+        uint256[] commitment;
+        bytes epoch;
+        bytes[] elements;
+        bytes[] integers;
+        uint256 signs;
+        uint256[] points;
+        uint256 scalar;
+        bytes hashToPrime;
+    }
+
+    /// A factor base^exponent, or its inverse if negative.
+    struct Term {
+        bytes base;
+        bytes exponent;
+        bool negative;
+    }
+
+    /// Verifies a proof encoded by `encode_calldata` for the given context.
+    /// Malformed curve points make the call revert.
+    function verify(bytes calldata context, bytes calldata data) external view returns (bool) {
+        // The tuple is decoded as a struct, which is encoded after its offset.
+        Proof memory proof = abi.decode(abi.encodePacked(uint256(32), data), (Proof));
+        if (!_isWellFormed(proof)) {
+            return false;
+        }
+        bytes32 state = _start(context, proof);
+        uint256 c;
+        uint256 l;
+        (state, c, l) = _rootChallenges(state, proof);
+        if (!_verifyRoot(proof, c, l)) {
+            return false;
+        }
+        (, c) = _modEqChallenge(state, proof);
+        return _verifyModEq(proof, c) && _verifyHashToPrime(proof);
+    }
+
+    function _isWellFormed(Proof memory proof) private pure returns (bool) {
+        if (
+            keccak256(proof.accumulator) != proof.accumulatorDigest ||
+            !_isElement(proof.accumulator) ||
+            proof.commitment.length != 2 ||
+            proof.elements.length != ELEMENTS ||
+            proof.integers.length != INTEGERS ||
+            proof.signs >> INTEGERS != 0 ||
+            proof.points.length != 2 ||
+            proof.scalar >= Q ||
+            proof.hashToPrime.length != HASH_TO_PRIME_LENGTH
+        ) {
+            return false;
+        }
+        for (uint256 i = 0; i < ELEMENTS; i++) {
+            if (!_isElement(proof.elements[i])) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    /// Elements of the quotient group are represented by integers in
+    /// [1, (N - 1)/2].
+    function _isElement(bytes memory element) private pure returns (bool) {
+        return
+            element.length == ELEMENT_WIDTH &&
+            !_isZero(element) &&
+            _cmp(element, MAX_ELEMENT) <= 0;
+    }
+
+    // The transcript.
+
+    function _append(
+        bytes32 state,
+        bytes memory label,
+        bytes memory message
+    ) private pure returns (bytes32) {
+        return
+            keccak256(
+                abi.encodePacked(state, uint32(label.length), label, uint32(message.length), message)
+            );
+    }
+
+    function _challenge(bytes32 state, bytes memory label) private pure returns (bytes32, uint256) {
+        state = _append(state, label, abi.encodePacked(uint32(CHALLENGE_BYTES)));
+        bytes32 block0 = keccak256(abi.encodePacked(state, uint32(0)));
+        return (state, uint256(block0) >> (256 - 8 * CHALLENGE_BYTES));
+    }
+
+    function _start(bytes calldata context, Proof memory proof) private pure returns (bytes32) {
+        bytes32 state = _append(bytes32(0), "dom-sep", "cpsnarks-set");
+        state = _append(state, "label", LABEL);
+        state = _append(state, "crs", CRS_FINGERPRINT);
+        state = _append(state, "context", context);
+        // An empty epoch stands for no epoch.
+        if (proof.epoch.length > 0) {
+            state = _append(state, "dom-sep", "membership");
+            state = _append(state, "epoch", proof.epoch);
+        }
+        state = _append(state, "dom-sep", "membership");
+        return _append(state, "c_e", _strip(proof.elements[C_E]));
+    }
+
+    function _rootChallenges(bytes32 state, Proof memory proof)
+        private
+        pure
+        returns (
+            bytes32,
+            uint256,
+            uint256
+        )
+    {
+        state = _append(state, "dom-sep", "root");
+        state = _append(state, "c_w", _strip(proof.elements[C_W]));
+        state = _append(state, "c_r", _strip(proof.elements[C_R]));
+        state = _append(state, "dom-sep", "root");
+        state = _append(state, "alpha1", _strip(proof.elements[ALPHA1]));
+        state = _append(state, "alpha2", _strip(proof.elements[ALPHA2]));
+        state = _append(state, "alpha3", _strip(proof.elements[ALPHA3]));
+        state = _append(state, "alpha4", _strip(proof.elements[ALPHA4]));
+        state = _append(state, "dom-sep", "root");
+        uint256 c;
+        (state, c) = _challenge(state, "c");
+        state = _append(state, "dom-sep", "root");
+        state = _append(state, "s_e", proof.integers[S_E]);
+        state = _append(state, "s_r", proof.integers[S_R]);
+        state = _append(state, "s_r_2", proof.integers[S_R + 1]);
+        state = _append(state, "s_r_3", proof.integers[S_R + 2]);
+        state = _append(state, "s_beta", proof.integers[S_R + 3]);
+        state = _append(state, "s_delta", proof.integers[S_R + 4]);
+        state = _append(state, "dom-sep", "root");
+        uint256 l;
+        (state, l) = _challenge(state, "l");
+        return (state, c, _nextPrime(l));
+    }
+
+    function _modEqChallenge(bytes32 state, Proof memory proof)
+        private
+        pure
+        returns (bytes32, uint256)
+    {
+        state = _append(state, "dom-sep", "modeq");
+        state = _append(state, "alpha1", _strip(proof.elements[MODEQ_ALPHA1]));
+        state = _append(state, "alpha2", _compress(proof.points[0], proof.points[1]));
+        state = _append(state, "dom-sep", "modeq");
+        return _challenge(state, "c");
+    }
+
+    /// The compressed arkworks encoding of a point: x in little-endian, with
+    /// the top bit set if y > -y.
+    function _compress(uint256 x, uint256 y) private pure returns (bytes memory) {
+        uint256 flags = y > P - y ? 0x80 : 0;
+        return abi.encodePacked(_reverseBytes(x) | flags);
+    }
+
+    // The root protocol.
+
+    function _verifyRoot(
+        Proof memory proof,
+        uint256 c,
+        uint256 l
+    ) private view returns (bool) {
+        if (_cmp(proof.integers[S_E], S_E_BOUND) > 0) {
+            return false;
+        }
+        bytes memory cBytes = abi.encodePacked(c);
+        bytes memory lBytes = abi.encodePacked(l);
+        // The remainders of s_r, s_r_2, s_r_3, s_beta and s_delta modulo l.
+        bytes[5] memory r;
+        for (uint256 i = 0; i < 5; i++) {
+            r[i] = _remainder(proof, S_R + i, l);
+        }
+        bytes memory sE = proof.integers[S_E];
+        bool negative = _isNegative(proof, S_E);
+
+        Term[] memory terms = new Term[](4);
+        terms[0] = Term(proof.elements[C_E], cBytes, false);
+        terms[1] = Term(proof.elements[Q1], lBytes, false);
+        terms[2] = Term(G, sE, negative);
+        terms[3] = Term(H, r[0], false);
+        if (!_checkEquation(proof.elements[ALPHA1], terms)) {
+            return false;
+        }
+        terms[0] = Term(proof.elements[C_R], cBytes, false);
+        terms[1] = Term(proof.elements[Q2], lBytes, false);
+        terms[2] = Term(G, r[1], false);
+        terms[3] = Term(H, r[2], false);
+        if (!_checkEquation(proof.elements[ALPHA2], terms)) {
+            return false;
+        }
+        terms[0] = Term(proof.accumulator, cBytes, false);
+        terms[1] = Term(proof.elements[Q3], lBytes, false);
+        terms[2] = Term(proof.elements[C_W], sE, negative);
+        terms[3] = Term(H, r[3], true);
+        if (!_checkEquation(proof.elements[ALPHA3], terms)) {
+            return false;
+        }
+        terms[0] = Term(proof.elements[C_R], sE, negative);
+        terms[1] = Term(proof.elements[Q4], lBytes, false);
+        terms[2] = Term(G, r[3], true);
+        terms[3] = Term(H, r[4], true);
+        return _checkEquation(proof.elements[ALPHA4], terms);
+    }
+
+    function _isNegative(Proof memory proof, uint256 i) private pure returns (bool) {
+        return (proof.signs >> i) & 1 == 1;
+    }
+
+    /// The remainder of the floor division of integer i by l.
+    function _remainder(
+        Proof memory proof,
+        uint256 i,
+        uint256 l
+    ) private view returns (bytes memory) {
+        uint256 m = abi.decode(_modexp(proof.integers[i], hex"01", abi.encodePacked(l)), (uint256));
+        if (m != 0 && _isNegative(proof, i)) {
+            m = l - m;
+        }
+        return abi.encodePacked(m);
+    }
+
+    /// Checks that alpha is the product of the terms in the quotient group,
+    /// moving the terms with negative exponents to the left to avoid
+    /// inversions.
+    function _checkEquation(bytes memory alpha, Term[] memory terms) private view returns (bool) {
+        bytes memory left = alpha;
+        bytes memory right = _one();
+        for (uint256 i = 0; i < terms.length; i++) {
+            bytes memory power = _modexp(terms[i].base, terms[i].exponent, MODULUS);
+            if (terms[i].negative) {
+                left = _mulmod(left, power);
+            } else {
+                right = _mulmod(right, power);
+            }
+        }
+        return _eq(left, right) || _eq(left, _sub(MODULUS, right));
+    }
+
+    /// The smallest prime greater than x, as `Integer::next_prime`.
+    function _nextPrime(uint256 x) private pure returns (uint256) {
+        uint256 n = x + 1;
+        if (n <= 2) {
+            return 2;
+        }
+        if (n % 2 == 0) {
+            n++;
+        }
+        while (!_isProbablePrime(n)) {
+            n += 2;
+        }
+        return n;
+    }
+
+    /// Trial division followed by the Miller-Rabin test to the first 20 prime
+    /// bases.
+    function _isProbablePrime(uint256 n) private pure returns (bool) {
+        uint256[20] memory bases = [
+            uint256(2), 3, 5, 7, 11, 13, 17, 19, 23, 29,
+            31, 37, 41, 43, 47, 53, 59, 61, 67, 71
+        ];
+        for (uint256 i = 0; i < bases.length; i++) {
+            if (n == bases[i]) {
+                return true;
+            }
+            if (n % bases[i] == 0) {
+                return false;
+            }
+        }
+        uint256 d = n - 1;
+        uint256 s = 0;
+        while (d % 2 == 0) {
+            d /= 2;
+            s++;
+        }
+        for (uint256 i = 0; i < bases.length; i++) {
+            uint256 y = _powmod(bases[i], d, n);
+            if (y == 1 || y == n - 1) {
+                continue;
+            }
+            bool composite = true;
+            for (uint256 j = 1; j < s; j++) {
+                y = mulmod(y, y, n);
+                if (y == n - 1) {
+                    composite = false;
+                    break;
+                }
+            }
+            if (composite) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    function _powmod(
+        uint256 base,
+        uint256 exponent,
+        uint256 modulus
+    ) private pure returns (uint256 result) {
+        result = 1;
+        base %= modulus;
+        while (exponent > 0) {
+            if (exponent & 1 == 1) {
+                result = mulmod(result, base, modulus);
+            }
+            base = mulmod(base, base, modulus);
+            exponent >>= 1;
+        }
+    }
+
+    // The modeq protocol.
+
+    function _verifyModEq(Proof memory proof, uint256 c) private view returns (bool) {
+        Term[] memory terms = new Term[](3);
+        terms[0] = Term(G, proof.integers[MODEQ_S_E], _isNegative(proof, MODEQ_S_E));
+        terms[1] = Term(H, proof.integers[MODEQ_S_R], _isNegative(proof, MODEQ_S_R));
+        terms[2] = Term(proof.elements[C_E], abi.encodePacked(c), false);
+        if (!_checkEquation(proof.elements[MODEQ_ALPHA1], terms)) {
+            return false;
+        }
+
+        uint256 sE = abi.decode(
+            _modexp(proof.integers[MODEQ_S_E], hex"01", abi.encodePacked(Q)),
+            (uint256)
+        );
+        if (sE != 0 && _isNegative(proof, MODEQ_S_E)) {
+            sE = Q - sE;
+        }
+        uint256[2] memory expected = _ecAdd(
+            _ecMul([PEDERSEN_G_X, PEDERSEN_G_Y], sE),
+            _ecMul([PEDERSEN_H_X, PEDERSEN_H_Y], proof.scalar)
+        );
+        expected = _ecAdd(expected, _ecMul([proof.commitment[0], proof.commitment[1]], c));
+        return expected[0] == proof.points[0] && expected[1] == proof.points[1];
+    }
+
+    // The LegoGroth16 hash-to-prime proof.
+
+    function _verifyHashToPrime(Proof memory proof) private view returns (bool) {
+        bytes memory pi = proof.hashToPrime;
+        uint256[2] memory d = _g1(pi, 256);
+        uint256[2] memory linkD = _g1(pi, 320);
+
+        // e(a, b) = e(alpha, beta) e(d, gamma) e(c, delta)
+        uint256[] memory input = new uint256[](24);
+        _setPair(input, 0, _g1(pi, 0), _g2(pi, 64));
+        _setPair(input, 1, _g1(pi, 192), [DELTA_NEG_0, DELTA_NEG_1, DELTA_NEG_2, DELTA_NEG_3]);
+        _setPair(input, 2, d, [GAMMA_NEG_0, GAMMA_NEG_1, GAMMA_NEG_2, GAMMA_NEG_3]);
+        _setPair(input, 3, [ALPHA_NEG_X, ALPHA_NEG_Y], [BETA_0, BETA_1, BETA_2, BETA_3]);
+        if (!_pairing(input)) {
+            return false;
+        }
+
+        // The link proof: e(link_d, c_0) e(d, c_1) = e(link_pi, a)
+        input = new uint256[](18);
+        _setPair(input, 0, linkD, [LINK_C0_0, LINK_C0_1, LINK_C0_2, LINK_C0_3]);
+        _setPair(input, 1, d, [LINK_C1_0, LINK_C1_1, LINK_C1_2, LINK_C1_3]);
+        _setPair(input, 2, _g1(pi, 384), [LINK_A_NEG_0, LINK_A_NEG_1, LINK_A_NEG_2, LINK_A_NEG_3]);
+        if (!_pairing(input)) {
+            return false;
+        }
+
+        // link_d commits to the element with an extra base for the constant.
+        uint256[2] memory expected = _ecAdd(
+            [proof.commitment[0], proof.commitment[1]],
+            [LINK_BASE_X, LINK_BASE_Y]
+        );
+        return linkD[0] == expected[0] && linkD[1] == expected[1];
+    }
+
+    /// Reads an uncompressed arkworks G1 point, rejecting the point at
+    /// infinity.
+    function _g1(bytes memory data, uint256 offset) private pure returns (uint256[2] memory point) {
+        point[0] = _readLittleEndian(data, offset);
+        point[1] = _readLittleEndian(data, offset + 32);
+        require(point[1] >> 254 == 0, "unexpected point flags");
+    }
+
+    /// Reads an uncompressed arkworks G2 point as x_1, x_0, y_1, y_0, the
+    /// order of the pairing precompile.
+    function _g2(bytes memory data, uint256 offset) private pure returns (uint256[4] memory point) {
+        point[1] = _readLittleEndian(data, offset);
+        point[0] = _readLittleEndian(data, offset + 32);
+        point[3] = _readLittleEndian(data, offset + 64);
+        point[2] = _readLittleEndian(data, offset + 96);
+        require(point[2] >> 254 == 0, "unexpected point flags");
+    }
+
+    function _setPair(
+        uint256[] memory input,
+        uint256 i,
+        uint256[2] memory g1,
+        uint256[4] memory g2
+    ) private pure {
+        input[6 * i] = g1[0];
+        input[6 * i + 1] = g1[1];
+        for (uint256 j = 0; j < 4; j++) {
+            input[6 * i + 2 + j] = g2[j];
+        }
+    }
+
+    function _pairing(uint256[] memory input) private view returns (bool) {
+        uint256[1] memory result;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 8, add(input, 32), mul(mload(input), 32), result, 32)
+        }
+        require(success, "pairing failed");
+        return result[0] == 1;
+    }
+
+    function _ecAdd(uint256[2] memory a, uint256[2] memory b)
+        private
+        view
+        returns (uint256[2] memory result)
+    {
+        uint256[4] memory input = [a[0], a[1], b[0], b[1]];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 6, input, 128, result, 64)
+        }
+        require(success, "ecAdd failed");
+    }
+
+    function _ecMul(uint256[2] memory a, uint256 scalar)
+        private
+        view
+        returns (uint256[2] memory result)
+    {
+        uint256[3] memory input = [a[0], a[1], scalar];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 7, input, 96, result, 64)
+        }
+        require(success, "ecMul failed");
+    }
+
+    // Big integers, as big-endian byte strings.
+
+    function _readLittleEndian(bytes memory data, uint256 offset)
+        private
+        pure
+        returns (uint256 value)
+    {
+        assembly {
+            value := mload(add(add(data, 32), offset))
+        }
+        return _reverseBytes(value);
+    }
+
+    function _reverseBytes(uint256 v) private pure returns (uint256) {
+        v =
+            ((v >> 8) & 0x00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff) |
+            ((v & 0x00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff) << 8);
+        v =
+            ((v >> 16) & 0x0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff) |
+            ((v & 0x0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff) << 16);
+        v =
+            ((v >> 32) & 0x00000000ffffffff00000000ffffffff00000000ffffffff00000000ffffffff) |
+            ((v & 0x00000000ffffffff00000000ffffffff00000000ffffffff00000000ffffffff) << 32);
+        v =
+            ((v >> 64) & 0x0000000000000000ffffffffffffffff0000000000000000ffffffffffffffff) |
+            ((v & 0x0000000000000000ffffffffffffffff0000000000000000ffffffffffffffff) << 64);
+        return (v >> 128) | (v << 128);
+    }
+
+    function _one() private pure returns (bytes memory one) {
+        one = new bytes(ELEMENT_WIDTH);
+        one[ELEMENT_WIDTH - 1] = 0x01;
+    }
+
+    function _modexp(
+        bytes memory base,
+        bytes memory exponent,
+        bytes memory modulus
+    ) private view returns (bytes memory result) {
+        bytes memory input = abi.encodePacked(
+            base.length,
+            exponent.length,
+            modulus.length,
+            base,
+            exponent,
+            modulus
+        );
+        result = new bytes(modulus.length);
+        bool success;
+        assembly {
+            success := staticcall(
+                gas(),
+                5,
+                add(input, 32),
+                mload(input),
+                add(result, 32),
+                mload(modulus)
+            )
+        }
+        require(success, "modexp failed");
+    }
+
+    function _mulmod(bytes memory a, bytes memory b) private view returns (bytes memory) {
+        return _modexp(_mul(a, b), hex"01", MODULUS);
+    }
+
+    /// Schoolbook multiplication of equal-length integers with 128-bit limbs,
+    /// so that the products of limbs fit in a word.
+    function _mul(bytes memory a, bytes memory b) private pure returns (bytes memory result) {
+        uint256 n = a.length / 16;
+        uint256[] memory acc = new uint256[](2 * n);
+        for (uint256 i = 0; i < n; i++) {
+            uint256 ai = _limb(a, i);
+            if (ai == 0) {
+                continue;
+            }
+            for (uint256 j = 0; j < n; j++) {
+                uint256 p = ai * _limb(b, j);
+                acc[i + j] += p >> 128;
+                acc[i + j + 1] += p & MASK_128;
+            }
+        }
+        uint256 carry = 0;
+        for (uint256 k = 2 * n; k > 0; k--) {
+            uint256 v = acc[k - 1] + carry;
+            acc[k - 1] = v & MASK_128;
+            carry = v >> 128;
+        }
+        result = new bytes(32 * n);
+        for (uint256 k = 0; k < n; k++) {
+            uint256 word = (acc[2 * k] << 128) | acc[2 * k + 1];
+            assembly {
+                mstore(add(add(result, 32), mul(k, 32)), word)
+            }
+        }
+    }
+
+    function _limb(bytes memory a, uint256 i) private pure returns (uint256 limb) {
+        assembly {
+            limb := shr(128, mload(add(add(a, 32), mul(i, 16))))
+        }
+    }
+
+    /// a - b for equal-length integers with a >= b.
+    function _sub(bytes memory a, bytes memory b) private pure returns (bytes memory result) {
+        result = new bytes(a.length);
+        uint256 borrow = 0;
+        for (uint256 k = a.length; k > 0; k -= 32) {
+            uint256 x;
+            uint256 y;
+            assembly {
+                x := mload(add(a, k))
+                y := mload(add(b, k))
+            }
+            uint256 d;
+            unchecked {
+                d = x - y - borrow;
+            }
+            borrow = (x < y || (x == y && borrow == 1)) ? 1 : 0;
+            assembly {
+                mstore(add(result, k), d)
+            }
+        }
+    }
+
+    function _strip(bytes memory a) private pure returns (bytes memory stripped) {
+        uint256 start = 0;
+        while (start < a.length && a[start] == 0) {
+            start++;
+        }
+        stripped = new bytes(a.length - start);
+        for (uint256 i = 0; i < stripped.length; i++) {
+            stripped[i] = a[start + i];
+        }
+    }
+
+    function _cmp(bytes memory a, bytes memory b) private pure returns (int256) {
+        a = _strip(a);
+        b = _strip(b);
+        if (a.length != b.length) {
+            return a.length < b.length ? -1 : int256(1);
+        }
+        for (uint256 i = 0; i < a.length; i++) {
+            if (a[i] != b[i]) {
+                return a[i] < b[i] ? -1 : int256(1);
+            }
+        }
+        return 0;
+    }
+
+    function _isZero(bytes memory a) private pure returns (bool) {
+        for (uint256 i = 0; i < a.length; i++) {
+            if (a[i] != 0) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    function _eq(bytes memory a, bytes memory b) private pure returns (bool) {
+        return a.length == b.length && keccak256(a) == keccak256(b);
+    }
+}
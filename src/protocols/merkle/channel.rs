@@ -0,0 +1,20 @@
+use crate::{
+    channels::ChannelError,
+    protocols::merkle::{Message1, Message2},
+};
+use ark_ec::PairingEngine;
+use rug::Integer;
+
+pub trait MerkleVerifierChannel<E: PairingEngine> {
+    fn send_proof(&mut self, proof: &legogro16::Proof<E>) -> Result<(), ChannelError>;
+    fn send_message1(&mut self, message: &Message1<E>) -> Result<(), ChannelError>;
+    fn send_message2(&mut self, message: &Message2<E>) -> Result<(), ChannelError>;
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
+
+pub trait MerkleProverChannel<E: PairingEngine> {
+    fn receive_proof(&mut self) -> Result<legogro16::Proof<E>, ChannelError>;
+    fn receive_message1(&mut self) -> Result<Message1<E>, ChannelError>;
+    fn receive_message2(&mut self) -> Result<Message2<E>, ChannelError>;
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
@@ -0,0 +1,526 @@
+//! Implements set membership with a Merkle tree of the set elements. The
+//! membership is proved by a Merkle path checked in a LegoGroth16 circuit,
+//! linked to the Pedersen commitment c_e_q.
+//!
+//! This is a transparent alternative to the accumulator-based protocols, as no
+//! group of unknown order is needed, and deleting an element only resets its
+//! leaf. The root is a linked input of the circuit, so the verifier removes it
+//! from the link commitment before comparing with c_e_q.
+//!
+//! The SNARK proof doesn't depend on the transcript, so the prover also proves
+//! knowledge of the opening of c_e_q with a sigma protocol whose challenge is
+//! drawn from it. This binds a non-interactive proof to its statement and
+//! context, as for the accumulator-based protocol, and `Protocol` implements
+//! `MembershipBackend` so that applications can use either.
+use crate::{
+    commitments::{pedersen::PedersenCommitment, Commitment},
+    parameters::Parameters,
+    protocols::{
+        check_challenge, check_point, membership::backend::MembershipBackend, point_bytes,
+        ProofError, SetupError, ValidationError, VerificationError,
+    },
+    transcript::TranscriptProtocolCurve,
+    utils::zeroize_integer,
+    utils::{bigint_to_integer, curve::CurvePointProjective, integer_to_bigint_mod_q},
+    utils::{Redacted, RedactedInteger},
+};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{PrimeField, UniformRand};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    boolean::Boolean,
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+    select::CondSelectGadget,
+    Assignment,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use channel::{MerkleProverChannel, MerkleVerifierChannel};
+use core::cell::RefCell;
+use core::ops::Sub;
+use merlin::Transcript;
+use poseidon::{hash_gadget, PoseidonParameters};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+use transcript::{TranscriptProverChannel, TranscriptVerifierChannel};
+use tree::MerklePath;
+use zeroize::Zeroize;
+
+pub mod channel;
+pub mod poseidon;
+pub mod transcript;
+pub mod tree;
+
+#[derive(Debug, Error)]
+pub enum MerkleTreeError {
+    #[error("the index is out of the tree")]
+    IndexOutOfRange,
+}
+
+pub struct MerkleTreeCircuit<E: PairingEngine> {
+    poseidon_parameters: PoseidonParameters<E::Fr>,
+    depth: usize,
+    value: Option<E::Fr>,
+    root: Option<E::Fr>,
+    path: Option<MerklePath<E::Fr>>,
+}
+
+impl<E: PairingEngine> ConstraintSynthesizer<E::Fr> for MerkleTreeCircuit<E> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<E::Fr>) -> Result<(), SynthesisError> {
+        let value = FpVar::new_variable(
+            ark_relations::ns!(cs, "alloc value"),
+            || self.value.get(),
+            AllocationMode::Input,
+        )?;
+        let root = FpVar::new_variable(
+            ark_relations::ns!(cs, "alloc root"),
+            || self.root.get(),
+            AllocationMode::Input,
+        )?;
+        // zero marks an empty leaf
+        value.enforce_not_equal(&FpVar::zero())?;
+
+        let mut current = value;
+        for i in 0..self.depth {
+            let sibling = FpVar::new_variable(
+                ark_relations::ns!(cs, "alloc sibling"),
+                || self.path.as_ref().map(|p| p.siblings[i]).get(),
+                AllocationMode::Witness,
+            )?;
+            let is_right = Boolean::new_variable(
+                ark_relations::ns!(cs, "alloc is right"),
+                || self.path.as_ref().map(|p| p.is_right[i]).get(),
+                AllocationMode::Witness,
+            )?;
+            let left = FpVar::conditionally_select(&is_right, &sibling, &current)?;
+            let right = FpVar::conditionally_select(&is_right, &current, &sibling)?;
+            current = hash_gadget(&self.poseidon_parameters, &left, &right)?;
+        }
+        current.enforce_equal(&root)?;
+
+        Ok(())
+    }
+}
+
+pub struct CRS<E: PairingEngine> {
+    pub parameters: Parameters,
+    pub pedersen_commitment_parameters: PedersenCommitment<E::G1Projective>,
+    pub poseidon_parameters: PoseidonParameters<E::Fr>,
+    pub depth: usize,
+    /// Shared by the clones of the CRS, as the proving key can be large.
+    pub merkle_parameters: Arc<legogro16::ProvingKey<E>>,
+}
+
+impl<E: PairingEngine> Clone for CRS<E> {
+    fn clone(&self) -> Self {
+        Self {
+            parameters: self.parameters.clone(),
+            pedersen_commitment_parameters: self.pedersen_commitment_parameters.clone(),
+            poseidon_parameters: self.poseidon_parameters.clone(),
+            depth: self.depth,
+            merkle_parameters: self.merkle_parameters.clone(),
+        }
+    }
+}
+
+pub struct Protocol<E: PairingEngine> {
+    pub crs: CRS<E>,
+    /// Prepared once, as it's the same for every proof.
+    pvk: legogro16::PreparedVerifyingKey<E>,
+}
+
+pub struct Statement<E: PairingEngine> {
+    pub root: E::Fr,
+    pub c_e_q: <PedersenCommitment<E::G1Projective> as Commitment>::Instance,
+}
+
+impl<E: PairingEngine> Statement<E> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_point("c_e_q", &self.c_e_q)
+    }
+}
+
+pub struct Witness<E: PairingEngine> {
+    pub e: Integer,
+    pub r_q: Integer,
+    pub path: MerklePath<E::Fr>,
+}
+
+impl<E: PairingEngine> Zeroize for Witness<E> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for Witness<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .field("path", &Redacted)
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> Drop for Witness<E> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone)]
+pub struct Message1<E: PairingEngine> {
+    pub alpha: E::G1Projective,
+}
+
+#[derive(Clone)]
+pub struct Message2<E: PairingEngine> {
+    pub s_e: E::Fr,
+    pub s_r_q: E::Fr,
+}
+
+#[derive(Clone)]
+pub struct Proof<E: PairingEngine> {
+    pub snark_proof: legogro16::Proof<E>,
+    pub message1: Message1<E>,
+    pub message2: Message2<E>,
+}
+
+impl<E: PairingEngine> Protocol<E> {
+    pub fn setup<R: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        depth: usize,
+        rng: &mut R,
+    ) -> Result<Protocol<E>, SetupError> {
+        let pedersen_commitment_parameters = PedersenCommitment::<E::G1Projective>::setup(rng);
+        let poseidon_parameters = PoseidonParameters::<E::Fr>::new();
+        let c = MerkleTreeCircuit::<E> {
+            poseidon_parameters: poseidon_parameters.clone(),
+            depth,
+            value: None,
+            root: None,
+            path: None,
+        };
+        let base_one = E::G1Projective::rand(rng);
+        let base_root = E::G1Projective::rand(rng);
+        let pedersen_bases = vec![
+            base_one,
+            pedersen_commitment_parameters.g,
+            base_root,
+            pedersen_commitment_parameters.h,
+        ];
+        let merkle_parameters = legogro16::generate_random_parameters(
+            c,
+            &pedersen_bases
+                .into_iter()
+                .map(|p| p.into_affine())
+                .collect::<Vec<_>>(),
+            rng,
+        )?;
+        Ok(Protocol {
+            pvk: legogro16::prepare_verifying_key(&merkle_parameters.vk),
+            crs: CRS::<E> {
+                parameters: parameters.clone(),
+                pedersen_commitment_parameters,
+                poseidon_parameters,
+                depth,
+                merkle_parameters: Arc::new(merkle_parameters),
+            },
+        })
+    }
+
+    pub fn from_crs(crs: &CRS<E>) -> Protocol<E> {
+        Protocol {
+            crs: crs.clone(),
+            pvk: legogro16::prepare_verifying_key(&crs.merkle_parameters.vk),
+        }
+    }
+
+    pub fn prove<R: RngCore + CryptoRng, C: MerkleVerifierChannel<E>>(
+        &self,
+        verifier_channel: &mut C,
+        rng: &mut R,
+        statement: &Statement<E>,
+        witness: &Witness<E>,
+    ) -> Result<(), ProofError> {
+        if witness.path.siblings.len() != self.crs.depth
+            || witness.path.is_right.len() != self.crs.depth
+        {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+        let c = MerkleTreeCircuit::<E> {
+            poseidon_parameters: self.crs.poseidon_parameters.clone(),
+            depth: self.crs.depth,
+            value: Some(integer_to_bigint_mod_q::<E::G1Projective>(&witness.e)?),
+            root: Some(statement.root),
+            path: Some(witness.path.clone()),
+        };
+        let v = E::Fr::rand(rng);
+        let link_v = integer_to_bigint_mod_q::<E::G1Projective>(&witness.r_q)?;
+        let proof = legogro16::create_random_proof::<E, _, _>(
+            c,
+            v,
+            link_v,
+            &self.crs.merkle_parameters,
+            rng,
+        )?;
+        verifier_channel.send_proof(&proof)?;
+
+        let r_e = E::Fr::rand(rng);
+        let r_r_q = E::Fr::rand(rng);
+        let alpha = self.crs.pedersen_commitment_parameters.commit(
+            &bigint_to_integer::<E::G1Projective>(&r_e),
+            &bigint_to_integer::<E::G1Projective>(&r_r_q),
+        )?;
+        verifier_channel.send_message1(&Message1 { alpha })?;
+
+        let c = verifier_channel.receive_challenge()?;
+        check_challenge(&self.crs.parameters, &c)?;
+        let c = integer_to_bigint_mod_q::<E::G1Projective>(&c)?;
+        let value = integer_to_bigint_mod_q::<E::G1Projective>(&witness.e)?;
+        let message2 = Message2::<E> {
+            s_e: r_e - &(c * &value),
+            s_r_q: r_r_q - &(c * &link_v),
+        };
+        verifier_channel.send_message2(&message2)?;
+
+        Ok(())
+    }
+
+    pub fn verify<C: MerkleProverChannel<E>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<E>,
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        let proof = prover_channel.receive_proof()?;
+        if !legogro16::verify_proof(&self.pvk, &proof)? {
+            return Err(VerificationError::MerkleProofInvalid);
+        }
+        let link_bases = &self.crs.merkle_parameters.vk.link_bases;
+        let proof_link_d_without_one_and_root = proof
+            .link_d
+            .into_projective()
+            .sub(&link_bases[0].into_projective())
+            .sub(&link_bases[2].mul(statement.root.into_repr()));
+        if statement.c_e_q != proof_link_d_without_one_and_root {
+            return Err(VerificationError::MerkleLinkMismatch {
+                expected: point_bytes(&statement.c_e_q),
+                got: point_bytes(&proof_link_d_without_one_and_root),
+            });
+        }
+
+        let message1 = prover_channel.receive_message1()?;
+        check_point("alpha", &message1.alpha)?;
+        let c = prover_channel.generate_and_send_challenge()?;
+        check_challenge(&self.crs.parameters, &c)?;
+        let message2 = prover_channel.receive_message2()?;
+        let c = integer_to_bigint_mod_q::<E::G1Projective>(&c)?;
+        let pedersen = &self.crs.pedersen_commitment_parameters;
+        let expected_alpha = <E::G1Projective as CurvePointProjective>::msm(
+            &[pedersen.g, pedersen.h, statement.c_e_q],
+            &[message2.s_e, message2.s_r_q, c],
+        );
+        if expected_alpha != message1.alpha {
+            return Err(VerificationError::MerkleOpeningMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Absorbs the context and the statement, which the sigma protocol's
+    /// messages don't include.
+    fn bind_transcript(
+        &self,
+        transcript: &mut Transcript,
+        statement: &Statement<E>,
+        context: &[u8],
+    ) {
+        transcript.append_message(b"context", context);
+        TranscriptProtocolCurve::<E::G1Projective>::append_curve_scalar(
+            transcript,
+            b"root",
+            &statement.root,
+        );
+        transcript.append_message(b"c_e_q", &point_bytes(&statement.c_e_q));
+    }
+
+    pub fn prove_noninteractive<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        statement: &Statement<E>,
+        witness: &Witness<E>,
+        context: &[u8],
+    ) -> Result<Proof<E>, ProofError> {
+        let mut transcript = Transcript::new(b"cpsnarks-set-merkle");
+        self.bind_transcript(&mut transcript, statement, context);
+        let transcript = RefCell::new(transcript);
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.prove(&mut verifier_channel, rng, statement, witness)?;
+        Ok(verifier_channel.proof()?)
+    }
+
+    /// Verifies a proof created by `prove_noninteractive` with the same
+    /// context.
+    pub fn verify_noninteractive(
+        &self,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = Transcript::new(b"cpsnarks-set-merkle");
+        self.bind_transcript(&mut transcript, statement, context);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
+    }
+}
+
+/// The Merkle tree backend draws all its randomness from the second
+/// generator, as it has no integer commitments.
+impl<E: PairingEngine> MembershipBackend for Protocol<E> {
+    type Statement = Statement<E>;
+    type Witness = Witness<E>;
+    type Proof = Proof<E>;
+
+    fn prove_noninteractive<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        _: &mut R1,
+        rng2: &mut R2,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+        context: &[u8],
+    ) -> Result<Self::Proof, ProofError> {
+        Protocol::prove_noninteractive(self, rng2, statement, witness, context)
+    }
+
+    fn verify_noninteractive(
+        &self,
+        statement: &Self::Statement,
+        proof: &Self::Proof,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        Protocol::verify_noninteractive(self, statement, proof, context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        poseidon::PoseidonParameters, tree::MerkleTree, MerkleTreeCircuit, Protocol, Statement,
+        Witness,
+    };
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::merkle::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+        utils::integer_to_bigint_mod_q,
+    };
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+    use ark_ff::UniformRand;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::Integer;
+    use std::cell::RefCell;
+
+    const DEPTH: usize = 4;
+
+    #[test]
+    fn test_circuit() {
+        let mut rng = thread_rng();
+        let parameters = PoseidonParameters::<Fr>::new();
+        let mut tree = MerkleTree::new(&parameters, DEPTH);
+        let value = Fr::rand(&mut rng);
+        tree.set(3, value).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let c = MerkleTreeCircuit::<Bls12_381> {
+            poseidon_parameters: parameters,
+            depth: DEPTH,
+            value: Some(value),
+            root: Some(tree.root()),
+            path: Some(tree.path(3).unwrap()),
+        };
+        c.generate_constraints(cs.clone()).unwrap();
+        println!("num constraints: {}", cs.num_constraints());
+        if !cs.is_satisfied().unwrap() {
+            panic!("not satisfied: {:?}", cs.which_is_unsatisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng = thread_rng();
+
+        let crs = Protocol::<Bls12_381>::setup(&params, DEPTH, &mut rng)
+            .unwrap()
+            .crs;
+        let protocol = Protocol::<Bls12_381>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(
+            2,
+            (crs.parameters.hash_to_prime_bits) as u32,
+        )) - &Integer::from(245);
+        let randomness = Integer::from(9);
+        let commitment = protocol
+            .crs
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let mut tree = MerkleTree::new(&crs.poseidon_parameters, DEPTH);
+        tree.set(0, Fr::rand(&mut rng)).unwrap();
+        tree.set(6, integer_to_bigint_mod_q::<G1Projective>(&value).unwrap())
+            .unwrap();
+
+        let statement = Statement::<Bls12_381> {
+            root: tree.root(),
+            c_e_q: commitment,
+        };
+        let witness = Witness {
+            e: value,
+            r_q: randomness,
+            path: tree.path(6).unwrap(),
+        };
+        let proof_transcript = RefCell::new(Transcript::new(b"merkle"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove(&mut verifier_channel, &mut rng, &statement, &witness)
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"merkle"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        let proof = protocol
+            .prove_noninteractive(&mut rng, &statement, &witness, b"context")
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+        assert!(protocol
+            .verify_noninteractive(&statement, &proof, b"other")
+            .is_err());
+
+        tree.delete(6).unwrap();
+        let statement_after_deletion = Statement::<Bls12_381> {
+            root: tree.root(),
+            c_e_q: commitment,
+        };
+        let verification_transcript = RefCell::new(Transcript::new(b"merkle"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        assert!(protocol
+            .verify(&mut prover_channel, &statement_after_deletion)
+            .is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Poseidon permutation with a width of 3 and x^5 S-boxes, used as the 2-to-1
+//! hash of the Merkle tree, natively and in-circuit.
+//!
+//! The round constants and the MDS matrix are the standard ones of the
+//! reference implementation, drawn from the Grain LFSR seeded with the field
+//! size, the width and the numbers of rounds: the constants by rejection
+//! sampling below the modulus and the MDS matrix as the Cauchy matrix
+//! 1/(x_i + y_j) of the next 2 * `WIDTH` distinct elements.
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::fields::{fp::FpVar, FieldVar};
+use ark_relations::r1cs::SynthesisError;
+
+pub const WIDTH: usize = 3;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+
+#[derive(Clone)]
+pub struct PoseidonParameters<F: PrimeField> {
+    pub round_constants: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> PoseidonParameters<F> {
+    pub fn new() -> PoseidonParameters<F> {
+        let mut grain = Grain::new(F::size_in_bits(), WIDTH, FULL_ROUNDS, PARTIAL_ROUNDS);
+        let round_constants = (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+            .map(|_| (0..WIDTH).map(|_| grain.next_field_element()).collect())
+            .collect();
+
+        let xs_ys = loop {
+            let candidates = (0..2 * WIDTH)
+                .map(|_| grain.next_field_element_mod_order())
+                .collect::<Vec<F>>();
+            if candidates
+                .iter()
+                .enumerate()
+                .all(|(i, x)| candidates[..i].iter().all(|y| x != y))
+            {
+                break candidates;
+            }
+        };
+        let (xs, ys) = xs_ys.split_at(WIDTH);
+        let mds = xs
+            .iter()
+            .map(|x| {
+                ys.iter()
+                    // x_i + y_j is nonzero for the standard parameters, which
+                    // the reference implementation checks once per field.
+                    .map(|y| (*x + y).inverse().unwrap())
+                    .collect()
+            })
+            .collect();
+
+        PoseidonParameters {
+            round_constants,
+            mds,
+        }
+    }
+
+    fn is_full_round(r: usize) -> bool {
+        r < FULL_ROUNDS / 2 || r >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS
+    }
+}
+
+impl<F: PrimeField> Default for PoseidonParameters<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The self-shrinking Grain LFSR of the Poseidon reference implementation.
+struct Grain {
+    state: Vec<bool>,
+    field_bits: usize,
+}
+
+impl Grain {
+    fn new(field_bits: u32, width: usize, full_rounds: usize, partial_rounds: usize) -> Grain {
+        let mut state = Vec::with_capacity(80);
+        let mut append = |value: u64, bits: usize| {
+            state.extend((0..bits).rev().map(|i| (value >> i) & 1 == 1));
+        };
+        // A prime field and the x^alpha S-box.
+        append(1, 2);
+        append(0, 4);
+        append(field_bits as u64, 12);
+        append(width as u64, 12);
+        append(full_rounds as u64, 10);
+        append(partial_rounds as u64, 10);
+        append((1 << 30) - 1, 30);
+
+        let mut grain = Grain {
+            state,
+            field_bits: field_bits as usize,
+        };
+        for _ in 0..160 {
+            grain.shift();
+        }
+        grain
+    }
+
+    fn shift(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.remove(0);
+        self.state.push(bit);
+        bit
+    }
+
+    /// Outputs the second bit of each pair whose first bit is set.
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.shift();
+            let bit = self.shift();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    fn next_bits(&mut self) -> Vec<bool> {
+        (0..self.field_bits).map(|_| self.next_bit()).collect()
+    }
+
+    /// The next field-sized integer below the modulus, most significant bit
+    /// first.
+    fn next_field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            if let Some(element) = F::from_repr(F::BigInt::from_bits_be(&self.next_bits())) {
+                return element;
+            }
+        }
+    }
+
+    fn next_field_element_mod_order<F: PrimeField>(&mut self) -> F {
+        let bits = self.next_bits();
+        let mut bytes = vec![0u8; (bits.len() + 7) / 8];
+        let offset = bytes.len() * 8 - bits.len();
+        for (i, bit) in bits.iter().enumerate() {
+            if *bit {
+                bytes[(offset + i) / 8] |= 0x80 >> ((offset + i) % 8);
+            }
+        }
+        F::from_be_bytes_mod_order(&bytes)
+    }
+}
+
+fn sbox<F: PrimeField>(x: F) -> F {
+    let x2 = x.square();
+    x2.square() * x
+}
+
+fn sbox_gadget<F: PrimeField>(x: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+    let x2 = x.square()?;
+    Ok(x2.square()? * x)
+}
+
+pub fn hash<F: PrimeField>(parameters: &PoseidonParameters<F>, left: F, right: F) -> F {
+    let mut state = vec![F::zero(), left, right];
+    permute(parameters, &mut state);
+    state[0]
+}
+
+/// Applies the permutation to a state of `WIDTH` elements.
+pub fn permute<F: PrimeField>(parameters: &PoseidonParameters<F>, state: &mut [F]) {
+    for r in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+        for (s, c) in state.iter_mut().zip(parameters.round_constants[r].iter()) {
+            *s += c;
+        }
+        if PoseidonParameters::<F>::is_full_round(r) {
+            for s in state.iter_mut() {
+                *s = sbox(*s);
+            }
+        } else {
+            state[0] = sbox(state[0]);
+        }
+        let mixed = parameters
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(F::zero(), |sum, (m, s)| sum + *m * s)
+            })
+            .collect::<Vec<_>>();
+        state.copy_from_slice(&mixed);
+    }
+}
+
+pub fn hash_gadget<F: PrimeField>(
+    parameters: &PoseidonParameters<F>,
+    left: &FpVar<F>,
+    right: &FpVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut state = vec![FpVar::zero(), left.clone(), right.clone()];
+    for r in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+        state = state
+            .into_iter()
+            .zip(parameters.round_constants[r].iter())
+            .map(|(s, c)| s + *c)
+            .collect();
+        if PoseidonParameters::<F>::is_full_round(r) {
+            for s in state.iter_mut() {
+                *s = sbox_gadget(s)?;
+            }
+        } else {
+            state[0] = sbox_gadget(&state[0])?;
+        }
+        state = parameters
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(FpVar::zero(), |sum, (m, s)| sum + s.clone() * *m)
+            })
+            .collect();
+    }
+
+    Ok(state[0].clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash, hash_gadget, PoseidonParameters};
+    use ark_bls12_381::Fr;
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, R1CSVar};
+    use ark_relations::r1cs::ConstraintSystem;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_native_and_gadget_agree() {
+        let mut rng = thread_rng();
+        let parameters = PoseidonParameters::<Fr>::new();
+        let left = Fr::rand(&mut rng);
+        let right = Fr::rand(&mut rng);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let left_var = FpVar::new_witness(ark_relations::ns!(cs, "left"), || Ok(left)).unwrap();
+        let right_var = FpVar::new_witness(ark_relations::ns!(cs, "right"), || Ok(right)).unwrap();
+        let result = hash_gadget(&parameters, &left_var, &right_var).unwrap();
+        println!("num constraints: {}", cs.num_constraints());
+
+        assert_eq!(result.value().unwrap(), hash(&parameters, left, right));
+        assert_ne!(
+            hash(&parameters, left, right),
+            hash(&parameters, right, left)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
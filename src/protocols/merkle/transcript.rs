@@ -0,0 +1,124 @@
+use crate::{
+    channels::ChannelError,
+    protocols::merkle::{
+        channel::{MerkleProverChannel, MerkleVerifierChannel},
+        Message1, Message2, Proof, CRS,
+    },
+    transcript::{
+        FiatShamir, TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolCurve,
+    },
+};
+use ark_ec::PairingEngine;
+use core::cell::RefCell;
+use rug::Integer;
+
+pub trait TranscriptProtocolMerkle<E: PairingEngine>:
+    TranscriptProtocolCurve<E::G1Projective> + TranscriptProtocolChallenge
+{
+    fn merkle_domain_sep(&mut self);
+}
+
+impl<E: PairingEngine, T: FiatShamir> TranscriptProtocolMerkle<E> for T {
+    fn merkle_domain_sep(&mut self) {
+        self.append_message(b"dom-sep", b"merkle");
+    }
+}
+
+pub struct TranscriptVerifierChannel<'a, E: PairingEngine, T: TranscriptProtocolMerkle<E>> {
+    crs: CRS<E>,
+    transcript: &'a RefCell<T>,
+    snark_proof: Option<legogro16::Proof<E>>,
+    message1: Option<Message1<E>>,
+    message2: Option<Message2<E>>,
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolMerkle<E>> TranscriptVerifierChannel<'a, E, T> {
+    pub fn new(crs: &CRS<E>, transcript: &'a RefCell<T>) -> TranscriptVerifierChannel<'a, E, T> {
+        TranscriptVerifierChannel {
+            crs: crs.clone(),
+            transcript,
+            snark_proof: None,
+            message1: None,
+            message2: None,
+        }
+    }
+
+    pub fn proof(&self) -> Result<Proof<E>, TranscriptChannelError> {
+        if self.snark_proof.is_some() && self.message1.is_some() && self.message2.is_some() {
+            Ok(Proof {
+                snark_proof: self.snark_proof.as_ref().unwrap().clone(),
+                message1: self.message1.as_ref().unwrap().clone(),
+                message2: self.message2.as_ref().unwrap().clone(),
+            })
+        } else {
+            Err(TranscriptChannelError::Incomplete)
+        }
+    }
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolMerkle<E>> MerkleVerifierChannel<E>
+    for TranscriptVerifierChannel<'a, E, T>
+{
+    fn send_proof(&mut self, proof: &legogro16::Proof<E>) -> Result<(), ChannelError> {
+        self.snark_proof = Some(proof.clone());
+        Ok(())
+    }
+    fn send_message1(&mut self, message: &Message1<E>) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.merkle_domain_sep();
+        transcript.append_curve_point(b"alpha", &message.alpha)?;
+        self.message1 = Some(message.clone());
+        Ok(())
+    }
+    fn send_message2(&mut self, message: &Message2<E>) -> Result<(), ChannelError> {
+        self.message2 = Some(message.clone());
+        Ok(())
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.merkle_domain_sep();
+        Ok(transcript.challenge_scalar(b"c", self.crs.parameters.security_soundness))
+    }
+}
+
+pub struct TranscriptProverChannel<'a, E: PairingEngine, T: TranscriptProtocolMerkle<E>> {
+    crs: CRS<E>,
+    transcript: &'a RefCell<T>,
+    proof: Proof<E>,
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolMerkle<E>> TranscriptProverChannel<'a, E, T> {
+    pub fn new(
+        crs: &CRS<E>,
+        transcript: &'a RefCell<T>,
+        proof: &Proof<E>,
+    ) -> TranscriptProverChannel<'a, E, T> {
+        TranscriptProverChannel {
+            crs: crs.clone(),
+            transcript,
+            proof: proof.clone(),
+        }
+    }
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolMerkle<E>> MerkleProverChannel<E>
+    for TranscriptProverChannel<'a, E, T>
+{
+    fn receive_proof(&mut self) -> Result<legogro16::Proof<E>, ChannelError> {
+        Ok(self.proof.snark_proof.clone())
+    }
+    fn receive_message1(&mut self) -> Result<Message1<E>, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.merkle_domain_sep();
+        transcript.append_curve_point(b"alpha", &self.proof.message1.alpha)?;
+        Ok(self.proof.message1.clone())
+    }
+    fn receive_message2(&mut self) -> Result<Message2<E>, ChannelError> {
+        Ok(self.proof.message2.clone())
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.merkle_domain_sep();
+        Ok(transcript.challenge_scalar(b"c", self.crs.parameters.security_soundness))
+    }
+}
@@ -0,0 +1,137 @@
+//! A fixed-depth Merkle tree with Poseidon as the hash. Empty leaves are zero,
+//! so deleting an element resets its leaf to zero.
+
+use crate::protocols::merkle::{
+    poseidon::{hash, PoseidonParameters},
+    MerkleTreeError,
+};
+use ark_ff::PrimeField;
+
+#[derive(Clone)]
+pub struct MerklePath<F: PrimeField> {
+    /// The siblings from the leaf up to the root.
+    pub siblings: Vec<F>,
+    /// Whether the node on the path at each level is a right child.
+    pub is_right: Vec<bool>,
+}
+
+impl<F: PrimeField> MerklePath<F> {
+    pub fn root(&self, parameters: &PoseidonParameters<F>, leaf: F) -> F {
+        self.siblings
+            .iter()
+            .zip(self.is_right.iter())
+            .fold(leaf, |current, (sibling, is_right)| {
+                if *is_right {
+                    hash(parameters, *sibling, current)
+                } else {
+                    hash(parameters, current, *sibling)
+                }
+            })
+    }
+}
+
+#[derive(Clone)]
+pub struct MerkleTree<F: PrimeField> {
+    parameters: PoseidonParameters<F>,
+    /// layers[0] are the leaves and layers[depth] is the root.
+    layers: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> MerkleTree<F> {
+    pub fn new(parameters: &PoseidonParameters<F>, depth: usize) -> MerkleTree<F> {
+        let mut layers: Vec<Vec<F>> = vec![vec![F::zero(); 1 << depth]];
+        while layers.len() <= depth {
+            let layer = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| hash(parameters, pair[0], pair[1]))
+                .collect();
+            layers.push(layer);
+        }
+
+        MerkleTree {
+            parameters: parameters.clone(),
+            layers,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.layers[self.depth()][0]
+    }
+
+    pub fn leaf(&self, index: usize) -> Result<F, MerkleTreeError> {
+        self.layers[0]
+            .get(index)
+            .copied()
+            .ok_or(MerkleTreeError::IndexOutOfRange)
+    }
+
+    /// Sets the leaf at index and updates the path to the root.
+    pub fn set(&mut self, index: usize, leaf: F) -> Result<(), MerkleTreeError> {
+        if index >= self.layers[0].len() {
+            return Err(MerkleTreeError::IndexOutOfRange);
+        }
+        self.layers[0][index] = leaf;
+        let mut index = index;
+        for level in 0..self.depth() {
+            let left = self.layers[level][index & !1];
+            let right = self.layers[level][index | 1];
+            index >>= 1;
+            self.layers[level + 1][index] = hash(&self.parameters, left, right);
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, index: usize) -> Result<(), MerkleTreeError> {
+        self.set(index, F::zero())
+    }
+
+    pub fn path(&self, index: usize) -> Result<MerklePath<F>, MerkleTreeError> {
+        if index >= self.layers[0].len() {
+            return Err(MerkleTreeError::IndexOutOfRange);
+        }
+        let mut siblings = vec![];
+        let mut is_right = vec![];
+        let mut index = index;
+        for layer in self.layers.iter().take(self.depth()) {
+            siblings.push(layer[index ^ 1]);
+            is_right.push(index & 1 == 1);
+            index >>= 1;
+        }
+
+        Ok(MerklePath { siblings, is_right })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MerkleTree;
+    use crate::protocols::merkle::poseidon::PoseidonParameters;
+    use ark_bls12_381::Fr;
+    use ark_ff::{UniformRand, Zero};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_paths() {
+        let mut rng = thread_rng();
+        let parameters = PoseidonParameters::<Fr>::new();
+        let mut tree = MerkleTree::new(&parameters, 3);
+        let empty_root = tree.root();
+
+        let leaf = Fr::rand(&mut rng);
+        tree.set(5, leaf).unwrap();
+        tree.set(2, Fr::rand(&mut rng)).unwrap();
+        let path = tree.path(5).unwrap();
+        assert_eq!(path.root(&parameters, leaf), tree.root());
+        assert_ne!(path.root(&parameters, Fr::zero()), tree.root());
+
+        tree.delete(5).unwrap();
+        tree.delete(2).unwrap();
+        assert_eq!(tree.root(), empty_root);
+        assert!(tree.set(8, leaf).is_err());
+    }
+}
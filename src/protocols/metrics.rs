@@ -0,0 +1,40 @@
+//! Measurements of a proof's creation, for operators to monitor the provers
+//! and alert on performance regressions.
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Default)]
+pub struct ProverMetrics {
+    /// The time spent in each sub-protocol, in the order they ran, named as
+    /// in `ChannelStats`, e.g., "root" or "hash_to_prime".
+    pub sub_protocols: Vec<(&'static str, Duration)>,
+    /// The constraints of the hash-to-prime circuit, for the SNARK backends.
+    pub constraints: Option<usize>,
+    /// The bytes sent to the verifier, in the wire encoding.
+    pub bytes: usize,
+    /// A rough estimate of the prover's peak memory, in bytes: the size of
+    /// the hash-to-prime CRS in memory, plus for the SNARK backends four
+    /// field elements per constraint, rounded to the FFT domain size.
+    pub peak_memory_estimate: usize,
+}
+
+impl ProverMetrics {
+    pub fn total(&self) -> Duration {
+        self.sub_protocols.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+
+    pub fn sub_protocol_elapsed(&self, sub_protocol: &str) -> Duration {
+        self.sub_protocols
+            .iter()
+            .filter(|(name, _)| *name == sub_protocol)
+            .map(|(_, elapsed)| *elapsed)
+            .sum()
+    }
+
+    /// Runs a step of the prover, recording its time under the sub-protocol.
+    pub(crate) fn time<T, F: FnOnce() -> T>(&mut self, sub_protocol: &'static str, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.sub_protocols.push((sub_protocol, start.elapsed()));
+        result
+    }
+}
@@ -1,96 +1,376 @@
 //! All the protocol implementations.
 
 use crate::{
-    channels::ChannelError, commitments::CommitmentError,
-    protocols::hash_to_prime::HashToPrimeError,
+    channels::ChannelError,
+    commitments::CommitmentError,
+    parameters::Parameters,
+    protocols::{hash_to_prime::HashToPrimeError, validity::ValidityError},
+    transcript::TranscriptChannelError,
+    utils::{curve::CurvePointProjective, serialization::to_hex, ConvertibleUnknownOrderGroup},
 };
 use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
 use rug::Integer;
+use thiserror::Error;
 
+pub mod batch;
+#[cfg(feature = "arkworks")]
+pub mod ceremony;
+pub mod consistency;
 pub mod coprime;
+pub mod disjointness;
+pub mod format;
 pub mod hash_to_prime;
+pub mod intersection;
+#[cfg(feature = "arkworks")]
+pub mod kzg;
 pub mod membership;
+#[cfg(feature = "arkworks")]
+pub mod merkle;
+pub mod metrics;
 pub mod modeq;
 pub mod nonmembership;
+#[cfg(feature = "arkworks")]
+pub mod pairing;
 pub mod root;
+pub mod shared;
+pub mod validity;
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum CRSError {
-        InvalidParameters {}
-    }
+#[derive(Debug, Error)]
+pub enum CRSError {
+    #[error("invalid parameters")]
+    InvalidParameters,
+    #[error("invalid commitment bases")]
+    InvalidCommitmentBases,
+    #[error("invalid key")]
+    InvalidKey,
+    #[error("the discriminant has {0} bits, fewer than the {1} of the security level")]
+    DiscriminantTooSmall(u32, u32),
 }
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum SetupError {
-        CouldNotPerformSetup {}
-        SNARKError(err: SynthesisError) {
-            from()
-        }
-    }
+#[derive(Debug, Error)]
+pub enum SetupError {
+    #[error("could not perform the setup")]
+    CouldNotPerformSetup,
+    #[error("could not generate the SNARK parameters")]
+    SNARKError(#[from] SynthesisError),
+    #[error("unknown hash-to-prime backend {0}")]
+    UnknownBackend(String),
 }
 
 #[cfg(feature = "dalek")]
 type R1CSError = bulletproofs::r1cs::R1CSError;
 
 #[cfg(feature = "arkworks")]
-quick_error! {
-    #[derive(Debug)]
-    pub enum DummyBPError {}
-}
+#[derive(Debug, Error)]
+pub enum DummyBPError {}
 #[cfg(feature = "arkworks")]
 type R1CSError = DummyBPError;
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum ProofError {
-        CouldNotCreateProof {}
-        CommitmentError(err: CommitmentError) {
-            from()
-        }
-        IntegerError(err: Integer) {
-            from()
-        }
-        SNARKError(err: SynthesisError) {
-            from()
-        }
-        VerifierChannelError(err: ChannelError) {
-            from()
-        }
-        PrimeError(err: HashToPrimeError) {
-            from()
-        }
-        BPError(err: R1CSError) {
-            from()
-        }
-        CRSInitError(err: CRSError) {
-            from()
-        }
+#[derive(Debug, Error)]
+pub enum SecurityError {
+    #[error("the challenge is not in [0, 2^{0})")]
+    ChallengeOutOfRange(u16),
+    #[error("the masking randomness of {0} doesn't hide it to {1} bits")]
+    MaskingTooSmall(&'static str, u16),
+}
+
+#[derive(Debug, Error)]
+pub enum ProofError {
+    #[error("could not create the proof")]
+    CouldNotCreateProof,
+    #[error("could not commit")]
+    CommitmentError(#[from] CommitmentError),
+    #[error("could not convert {0}")]
+    IntegerError(Integer),
+    #[error("could not create the SNARK proof")]
+    SNARKError(#[from] SynthesisError),
+    #[error("could not communicate with the verifier")]
+    VerifierChannelError(#[from] ChannelError),
+    #[error("could not hash to a prime")]
+    PrimeError(#[from] HashToPrimeError),
+    #[error("could not create the Bulletproofs proof")]
+    BPError(#[from] R1CSError),
+    #[error("invalid CRS")]
+    CRSInitError(#[from] CRSError),
+    #[error("could not finish the transcript")]
+    TranscriptError(#[from] TranscriptChannelError),
+    #[error("the proof wouldn't be zero-knowledge")]
+    SecurityError(#[from] SecurityError),
+    #[error("could not serialize the proof")]
+    SerializationError(#[from] SerializationError),
+    #[error("the {protocol} proof failed")]
+    InProtocol {
+        protocol: &'static str,
+        #[source]
+        source: Box<ProofError>,
+    },
+}
+
+impl From<Integer> for ProofError {
+    fn from(err: Integer) -> Self {
+        ProofError::IntegerError(err)
     }
 }
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum VerificationError {
-        VerificationFailed {}
-        CommitmentError(err: CommitmentError) {
-            from()
-        }
-        IntegerError(err: Integer) {
-            from()
+impl ProofError {
+    /// Names the sub-protocol the error comes from, unless it already names
+    /// it itself.
+    pub(crate) fn in_protocol(self, protocol: &'static str) -> ProofError {
+        match self {
+            ProofError::InProtocol { .. } | ProofError::SecurityError(_) => self,
+            _ => ProofError::InProtocol {
+                protocol,
+                source: Box::new(self),
+            },
         }
-        SNARKError(err: SynthesisError) {
-            from()
-        }
-        ProverChannelError(err: ChannelError) {
-            from()
-        }
-        BPError(err: R1CSError) {
-            from()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum VerificationError {
+    #[error("the root proof equations do not hold")]
+    RootCheckFailed,
+    #[error("the coprime proof's {0} does not match the responses")]
+    CoprimeCheckFailed(&'static str),
+    #[error("the modeq proof's commitments do not match its challenge and responses")]
+    ModEqChallengeMismatch,
+    #[error("the hash-to-prime proof is invalid")]
+    HashToPrimeProofInvalid,
+    #[error("the hash-to-prime proof commits to {} instead of c_e_q {}", to_hex(.got), to_hex(.expected))]
+    HashToPrimeLinkMismatch { expected: Vec<u8>, got: Vec<u8> },
+    #[error("the Merkle tree proof is invalid")]
+    MerkleProofInvalid,
+    #[error("the Merkle tree proof commits to {} instead of c_e_q {}", to_hex(.got), to_hex(.expected))]
+    MerkleLinkMismatch { expected: Vec<u8>, got: Vec<u8> },
+    #[error("the Merkle tree proof's opening of c_e_q does not match its challenge and responses")]
+    MerkleOpeningMismatch,
+    #[error("the pairing accumulator proof equations do not hold")]
+    PairingCheckFailed,
+    #[error("the consistency proof equations do not hold")]
+    ConsistencyCheckFailed,
+    #[error("the disjointness proof equations do not hold")]
+    DisjointnessCheckFailed,
+    #[error("the combined equations of the batch do not hold")]
+    BatchCheckFailed,
+    #[error("the designated-verifier proof has the wrong number of randomizers")]
+    RandomizerCountMismatch,
+    #[error("the blind insertion proof equations do not hold")]
+    BlindInsertionCheckFailed,
+    #[error("the statement's accumulator doesn't match the latest accumulator digest")]
+    AccumulatorDigestMismatch,
+    #[error("could not recompute a commitment")]
+    CommitmentError(#[from] CommitmentError),
+    #[error("could not convert {0}")]
+    IntegerError(Integer),
+    #[error("could not verify the SNARK proof")]
+    SNARKError(#[from] SynthesisError),
+    #[error("could not receive the proof from the prover")]
+    ProverChannelError(#[from] ChannelError),
+    #[error("could not verify the Bulletproofs proof")]
+    BPError(#[from] R1CSError),
+    #[error("invalid CRS")]
+    CRSInitError(#[from] CRSError),
+    #[error("could not deserialize the proof")]
+    SerializationError(#[from] SerializationError),
+    #[error("the proof or statement is malformed")]
+    ValidationError(#[from] ValidationError),
+    #[error("the challenge is out of range")]
+    SecurityError(#[from] SecurityError),
+    #[error("the proof is not valid under the verifier's policy")]
+    ValidityError(#[from] ValidityError),
+    #[error("the {protocol} proof failed to verify")]
+    InProtocol {
+        protocol: &'static str,
+        #[source]
+        source: Box<VerificationError>,
+    },
+}
+
+impl From<Integer> for VerificationError {
+    fn from(err: Integer) -> Self {
+        VerificationError::IntegerError(err)
+    }
+}
+
+impl VerificationError {
+    /// Names the sub-protocol the error comes from, unless it already names
+    /// it itself, as the failed checks do.
+    pub(crate) fn in_protocol(self, protocol: &'static str) -> VerificationError {
+        match self {
+            VerificationError::CommitmentError(_)
+            | VerificationError::IntegerError(_)
+            | VerificationError::SNARKError(_)
+            | VerificationError::ProverChannelError(_)
+            | VerificationError::BPError(_)
+            | VerificationError::CRSInitError(_)
+            | VerificationError::SerializationError(_)
+            | VerificationError::ValidationError(_) => VerificationError::InProtocol {
+                protocol,
+                source: Box::new(self),
+            },
+            _ => self,
         }
-        CRSInitError(err: CRSError) {
-            from()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    #[error("response {0} is out of range")]
+    ResponseOutOfRange(&'static str),
+    #[error("{0} is the identity")]
+    IdentityElement(&'static str),
+    #[error("{0} is not in the prime-order subgroup")]
+    NotInSubgroup(&'static str),
+    #[error("witness {0} is out of range")]
+    WitnessOutOfRange(&'static str),
+}
+
+/// Checks that the discriminant of a class group is large enough for the
+/// security level. The other groups have none.
+pub(crate) fn check_discriminant<G: ConvertibleUnknownOrderGroup>(
+    parameters: &Parameters,
+) -> Result<(), CRSError> {
+    let required = parameters.class_group_discriminant_bits();
+    match G::discriminant_bits() {
+        Some(bits) if bits < required => Err(CRSError::DiscriminantTooSmall(bits, required)),
+        _ => Ok(()),
+    }
+}
+
+/// The range of s_e checked by the root and coprime verifiers.
+pub(crate) fn s_e_bound(parameters: &Parameters) -> Integer {
+    Integer::from(Integer::u_pow_u(
+        2,
+        (parameters.security_zk + parameters.security_soundness + parameters.hash_to_prime_bits + 1)
+            as u32,
+    ))
+}
+
+/// A bound on the other responses, loose enough for every honest response,
+/// so that proofs with oversized integers are rejected before the verifier
+/// exponentiates by them.
+pub(crate) fn response_bound<G: ConvertibleUnknownOrderGroup>(parameters: &Parameters) -> Integer {
+    G::order_upper_bound()
+        * Integer::from(Integer::u_pow_u(
+            2,
+            (parameters.security_zk
+                + 2 * parameters.security_soundness
+                + parameters.hash_to_prime_bits
+                + 2) as u32,
+        ))
+}
+
+/// Checks that a challenge is in the challenge space of `security_soundness`
+/// bits, which the masking of the responses is sized for, catching a channel
+/// or transcript set up with other parameters.
+pub(crate) fn check_challenge(parameters: &Parameters, c: &Integer) -> Result<(), SecurityError> {
+    if *c < 0 || c.significant_bits() > u32::from(parameters.security_soundness) {
+        return Err(SecurityError::ChallengeOutOfRange(
+            parameters.security_soundness,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that masking randomness drawn from `[-range, range]` hides
+/// `c * secret` for any challenge `c` of `security_soundness` bits, up to a
+/// statistical distance of `2^{1 - security_zk}`, i.e., that
+/// `2^{security_zk + security_soundness} * |secret| <= 2 * range`.
+pub(crate) fn check_masking(
+    parameters: &Parameters,
+    field: &'static str,
+    range: &Integer,
+    secret: &Integer,
+) -> Result<(), SecurityError> {
+    let shift = u32::from(parameters.security_zk + parameters.security_soundness);
+    let mut masked = Integer::from(secret.abs_ref()) << shift;
+    let too_small = masked > Integer::from(range << 1);
+    crate::utils::zeroize_integer(&mut masked);
+    if too_small {
+        return Err(SecurityError::MaskingTooSmall(
+            field,
+            parameters.security_zk,
+        ));
+    }
+    Ok(())
+}
+
+/// The encoding of a point for a `VerificationError`, empty if it has none.
+pub(crate) fn point_bytes<P: CurvePointProjective>(point: &P) -> Vec<u8> {
+    point.to_affine_bytes().unwrap_or_default()
+}
+
+pub(crate) fn check_response(
+    field: &'static str,
+    response: &Integer,
+    bound: &Integer,
+) -> Result<(), ValidationError> {
+    if response.cmp_abs(bound) == core::cmp::Ordering::Greater {
+        return Err(ValidationError::ResponseOutOfRange(field));
+    }
+    Ok(())
+}
+
+/// Rejects the identity, which no honest prover sends, as a commitment,
+/// accumulator or message would then be independent of the secrets.
+pub(crate) fn check_elem<G: ConvertibleUnknownOrderGroup>(
+    field: &'static str,
+    elem: &G::Elem,
+) -> Result<(), ValidationError> {
+    if *elem == G::id() {
+        return Err(ValidationError::IdentityElement(field));
+    }
+    Ok(())
+}
+
+/// Rejects the identity and points outside the prime-order subgroup.
+pub(crate) fn check_point<P: CurvePointProjective>(
+    field: &'static str,
+    point: &P,
+) -> Result<(), ValidationError> {
+    if point.is_identity() {
+        return Err(ValidationError::IdentityElement(field));
+    }
+    if !point.is_in_subgroup() {
+        return Err(ValidationError::NotInSubgroup(field));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_discriminant, CRSError, VerificationError};
+    use crate::{channels::ChannelError, parameters::Parameters};
+    use accumulator::group::{ClassGroup, Rsa2048};
+    use std::error::Error;
+
+    #[test]
+    fn test_error_context() {
+        let err = VerificationError::from(ChannelError::MalformedMessage).in_protocol("root");
+        assert_eq!(err.to_string(), "the root proof failed to verify");
+        let source = err.source().unwrap();
+        assert_eq!(
+            source.to_string(),
+            "could not receive the proof from the prover"
+        );
+        assert!(source.source().unwrap().is::<ChannelError>());
+
+        assert!(matches!(
+            VerificationError::RootCheckFailed.in_protocol("root"),
+            VerificationError::RootCheckFailed
+        ));
+    }
+
+    #[test]
+    fn test_check_discriminant() {
+        let params = Parameters::from_security_level(128).unwrap();
+        check_discriminant::<Rsa2048>(&params).unwrap();
+        check_discriminant::<ClassGroup>(&params).unwrap();
+        let params = Parameters::from_security_level(192).unwrap();
+        match check_discriminant::<ClassGroup>(&params) {
+            Err(CRSError::DiscriminantTooSmall(2048, 3598)) => {}
+            _ => panic!("a 2048-bit discriminant should be too small at 192 bits"),
         }
     }
 }
@@ -16,3 +16,54 @@ pub trait ModEqProverChannel<G: ConvertibleUnknownOrderGroup, P: CurvePointProje
     fn receive_message2(&mut self) -> Result<Message2<P>, ChannelError>;
     fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
 }
+
+/// Lends a channel, e.g., to a wrapper such as `InstrumentedChannel`.
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        C: ModEqVerifierChannel<G, P> + ?Sized,
+    > ModEqVerifierChannel<G, P> for &mut C
+{
+    fn send_message1(&mut self, message: &Message1<G, P>) -> Result<(), ChannelError> {
+        (**self).send_message1(message)
+    }
+    fn send_message2(&mut self, message: &Message2<P>) -> Result<(), ChannelError> {
+        (**self).send_message2(message)
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        (**self).receive_challenge()
+    }
+}
+
+impl<
+        G: ConvertibleUnknownOrderGroup,
+        P: CurvePointProjective,
+        C: ModEqProverChannel<G, P> + ?Sized,
+    > ModEqProverChannel<G, P> for &mut C
+{
+    fn receive_message1(&mut self) -> Result<Message1<G, P>, ChannelError> {
+        (**self).receive_message1()
+    }
+    fn receive_message2(&mut self) -> Result<Message2<P>, ChannelError> {
+        (**self).receive_message2()
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        (**self).generate_and_send_challenge()
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncModEqVerifierChannel<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    async fn send_message1(&mut self, message: &Message1<G, P>) -> Result<(), ChannelError>;
+    async fn send_message2(&mut self, message: &Message2<P>) -> Result<(), ChannelError>;
+    async fn receive_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncModEqProverChannel<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    async fn receive_message1(&mut self) -> Result<Message1<G, P>, ChannelError>;
+    async fn receive_message2(&mut self) -> Result<Message2<P>, ChannelError>;
+    async fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
@@ -1,18 +1,35 @@
 //! Implements ModEq.
-use crate::commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment, Commitment};
+use crate::commitments::{
+    integer::{IntegerCommitment, IntegerCommitmentTables},
+    pedersen::PedersenCommitment,
+    Commitment, CommitmentError,
+};
 use crate::{
     parameters::Parameters,
-    protocols::{ProofError, VerificationError},
+    protocols::{
+        batch::{BatchEquations, CurveEquation, GroupEquation},
+        check_challenge, check_elem, check_masking, check_point, check_response, response_bound,
+        s_e_bound, ProofError, ValidationError, VerificationError,
+    },
+    utils::zeroize_integer,
+    utils::RedactedInteger,
     utils::{
         bigint_to_integer,
         curve::{CurvePointProjective, Field},
+        group::ElemFromBytes,
         integer_mod_q, integer_to_bigint_mod_q, random_symmetric_range,
+        serialization::{
+            elem_size, integer_size, point_size, read_elem, read_integer, read_point, read_scalar,
+            scalar_size, write_elem, write_integer, write_point, write_scalar,
+        },
         ConvertibleUnknownOrderGroup,
     },
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use channel::{ModEqProverChannel, ModEqVerifierChannel};
 use rand::{CryptoRng, RngCore};
 use rug::{rand::MutRandState, Integer};
+use std::fmt;
 
 pub mod channel;
 pub mod transcript;
@@ -23,6 +40,7 @@ pub struct CRSModEq<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
     pub parameters: Parameters,
     pub integer_commitment_parameters: IntegerCommitment<G>, // G, H
     pub pedersen_commitment_parameters: PedersenCommitment<P>, // g, h
+    pub fixed_base_tables: Option<IntegerCommitmentTables<G>>,
 }
 
 pub struct Statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
@@ -30,12 +48,43 @@ pub struct Statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
     pub c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
 }
 
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Statement<G, P> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("c_e", &self.c_e)?;
+        check_point("c_e_q", &self.c_e_q)
+    }
+}
+
 pub struct Witness {
     pub e: Integer,
     pub r: Integer,
     pub r_q: Integer,
 }
 
+impl Zeroize for Witness {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl fmt::Debug for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r", &RedactedInteger(&self.r))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .finish()
+    }
+}
+
+impl Drop for Witness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone)]
 pub struct Message1<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
     pub alpha1: <IntegerCommitment<G> as Commitment>::Instance,
@@ -55,6 +104,99 @@ pub struct Proof<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
     pub message2: Message2<P>,
 }
 
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Proof<G, P> {
+    /// Checks that the integer responses are within the ranges an honest
+    /// prover's are, to reject a malformed proof before verifying it.
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        check_response("s_e", &self.message2.s_e, &s_e_bound(parameters))?;
+        check_response("s_r", &self.message2.s_r, &response_bound::<G>(parameters))
+    }
+
+    fn write<W: Write>(&self, mut writer: W, compressed: bool) -> Result<(), SerializationError> {
+        write_elem::<G, _>(&mut writer, &self.message1.alpha1)?;
+        write_point(&mut writer, &self.message1.alpha2, compressed)?;
+        write_integer(&mut writer, &self.message2.s_e)?;
+        write_integer(&mut writer, &self.message2.s_r)?;
+        write_scalar::<P, _>(&mut writer, &self.message2.s_r_q)
+    }
+
+    fn size(&self, compressed: bool) -> usize {
+        elem_size::<G>(&self.message1.alpha1)
+            + point_size(&self.message1.alpha2, compressed)
+            + integer_size(&self.message2.s_e)
+            + integer_size(&self.message2.s_r)
+            + scalar_size::<P>(&self.message2.s_r_q)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective> Proof<G, P> {
+    fn read<R: Read>(mut reader: R, compressed: bool) -> Result<Self, SerializationError> {
+        Ok(Proof {
+            message1: Message1 {
+                alpha1: read_elem::<G, _>(&mut reader)?,
+                alpha2: read_point(&mut reader, compressed)?,
+            },
+            message2: Message2 {
+                s_e: read_integer(&mut reader)?,
+                s_r: read_integer(&mut reader)?,
+                s_r_q: read_scalar::<P, _>(&mut reader)?,
+            },
+        })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> CanonicalSerialize for Proof<G, P> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.write(writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.write(writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective> CanonicalDeserialize for Proof<G, P> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Proof::read(reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Proof::read(reader, false)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> serde::Serialize for Proof<G, P> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::utils::serialization::serialize_canonical(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, G: ElemFromBytes, P: CurvePointProjective> serde::Deserialize<'de> for Proof<G, P> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::utils::serialization::deserialize_canonical(deserializer)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> CRSModEq<G, P> {
+    /// The size of the integer and Pedersen commitment bases.
+    pub(crate) fn size(&self, compressed: bool) -> usize {
+        elem_size::<G>(&self.integer_commitment_parameters.g)
+            + elem_size::<G>(&self.integer_commitment_parameters.h)
+            + point_size(&self.pedersen_commitment_parameters.g, compressed)
+            + point_size(&self.pedersen_commitment_parameters.h, compressed)
+    }
+}
+
 pub struct Protocol<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
     pub crs: CRSModEq<G, P>,
 }
@@ -64,6 +206,25 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Protocol<G, P> {
         Protocol { crs: crs.clone() }
     }
 
+    fn commit_secret<R: RngCore + CryptoRng>(
+        &self,
+        value: &Integer,
+        randomness: &Integer,
+        rng: &mut R,
+    ) -> Result<G::Elem, CommitmentError> {
+        match &self.crs.fixed_base_tables {
+            Some(tables) => tables.commit_secret(value, randomness, rng),
+            None => self
+                .crs
+                .integer_commitment_parameters
+                .commit_secret(value, randomness, rng),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "modeq::prove", level = "debug", skip_all)
+    )]
     pub fn prove<R1: MutRandState, R2: RngCore + CryptoRng, C: ModEqVerifierChannel<G, P>>(
         &self,
         verifier_channel: &mut C,
@@ -78,18 +239,20 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Protocol<G, P> {
                 + self.crs.parameters.security_soundness
                 + self.crs.parameters.hash_to_prime_bits) as u32,
         ));
+        check_masking(&self.crs.parameters, "e", &r_e_range, &witness.e)?;
         let r_e = random_symmetric_range(rng1, &r_e_range);
         let r_r_range: Integer = G::order_upper_bound() / 2
             * Integer::from(Integer::u_pow_u(
                 2,
                 (self.crs.parameters.security_zk + self.crs.parameters.security_soundness) as u32,
             ));
+        check_masking(&self.crs.parameters, "r", &r_r_range, &witness.r)?;
         let r_r = random_symmetric_range(rng1, &r_r_range);
         assert!(self.crs.parameters.field_size_bits as usize >= P::ScalarField::size_in_bits());
         let r_r_q_field = P::ScalarField::rand(rng2);
         let r_r_q = bigint_to_integer::<P>(&r_r_q_field);
 
-        let alpha1 = self.crs.integer_commitment_parameters.commit(&r_e, &r_r)?;
+        let alpha1 = self.commit_secret(&r_e, &r_r, rng2)?;
         let alpha2 = self
             .crs
             .pedersen_commitment_parameters
@@ -99,9 +262,10 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Protocol<G, P> {
         verifier_channel.send_message1(&message1)?;
 
         let c = verifier_channel.receive_challenge()?;
-        let r_q = integer_to_bigint_mod_q::<P>(&witness.r_q.clone())?;
-        let s_e = r_e - c.clone() * witness.e.clone();
-        let s_r = r_r - c.clone() * witness.r.clone();
+        check_challenge(&self.crs.parameters, &c)?;
+        let r_q = integer_to_bigint_mod_q::<P>(&witness.r_q)?;
+        let s_e = r_e - &c * &witness.e;
+        let s_r = r_r - &c * &witness.r;
         let c_big = integer_to_bigint_mod_q::<P>(&c)?;
         let s_r_q = r_r_q_field.sub(&(r_q.mul(&c_big)));
 
@@ -111,75 +275,107 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Protocol<G, P> {
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "modeq::verify", level = "debug", skip_all)
+    )]
     pub fn verify<C: ModEqProverChannel<G, P>>(
         &self,
         prover_channel: &mut C,
         statement: &Statement<G, P>,
     ) -> Result<(), VerificationError> {
+        let (group_equation, curve_equation) = self.equations(prover_channel, statement)?;
+        if group_equation.holds(self.crs.fixed_base_tables.as_ref()) && curve_equation.holds() {
+            Ok(())
+        } else {
+            Err(VerificationError::ModEqChallengeMismatch)
+        }
+    }
+
+    /// Like `verify`, but only collects the equations, to check them with
+    /// those of other proofs.
+    pub fn verify_deferred<C: ModEqProverChannel<G, P>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G, P>,
+        equations: &mut BatchEquations<G, P>,
+    ) -> Result<(), VerificationError> {
+        let (group_equation, curve_equation) = self.equations(prover_channel, statement)?;
+        equations.group.push(group_equation);
+        equations.curve.push(curve_equation);
+        Ok(())
+    }
+
+    fn equations<C: ModEqProverChannel<G, P>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G, P>,
+    ) -> Result<(GroupEquation<G>, CurveEquation<P>), VerificationError> {
+        statement.validate()?;
         let message1 = prover_channel.receive_message1()?;
         let c = prover_channel.generate_and_send_challenge()?;
+        check_challenge(&self.crs.parameters, &c)?;
         let message2 = prover_channel.receive_message2()?;
+        check_elem::<G>("alpha1", &message1.alpha1)?;
+        check_point("alpha2", &message1.alpha2)?;
 
-        let commitment2 = self
-            .crs
-            .integer_commitment_parameters
-            .commit(&message2.s_e, &message2.s_r)?;
-        let commitment2_extra = G::exp(&statement.c_e, &c);
-        let expected_alpha1 = G::op(&commitment2, &commitment2_extra);
+        let integer_commitment = &self.crs.integer_commitment_parameters;
+        let group_equation = GroupEquation {
+            terms: vec![
+                (integer_commitment.g.clone(), message2.s_e.clone()),
+                (integer_commitment.h.clone(), message2.s_r.clone()),
+                (statement.c_e.clone(), c.clone()),
+            ],
+            target: message1.alpha1,
+        };
 
-        let s_e_mod_q = integer_mod_q::<P>(&message2.s_e)?;
-        let s_r_q_int = bigint_to_integer::<P>(&message2.s_r_q);
-        let commitment1 = self
-            .crs
-            .pedersen_commitment_parameters
-            .commit(&s_e_mod_q, &s_r_q_int)?;
-        let c_big = integer_to_bigint_mod_q::<P>(&c)?;
-        let commitment1_extra = statement.c_e_q.mul(&c_big);
-        let expected_alpha2 = commitment1.add(&commitment1_extra);
+        let pedersen = &self.crs.pedersen_commitment_parameters;
+        let curve_equation = CurveEquation {
+            bases: vec![
+                pedersen.g.clone(),
+                pedersen.h.clone(),
+                statement.c_e_q.clone(),
+            ],
+            scalars: vec![
+                integer_to_bigint_mod_q::<P>(&message2.s_e)?,
+                message2.s_r_q.clone(),
+                integer_to_bigint_mod_q::<P>(&c)?,
+            ],
+            target: message1.alpha2,
+        };
 
-        if expected_alpha1 == message1.alpha1 && expected_alpha2 == message1.alpha2 {
-            Ok(())
-        } else {
-            Err(VerificationError::VerificationFailed)
-        }
+        Ok((group_equation, curve_equation))
     }
 }
 
 #[cfg(all(test, feature = "arkworks"))]
 mod test {
-    use super::{Protocol, Statement, Witness};
+    use super::{CRSModEq, Protocol, Statement, Witness};
     use crate::{
         commitments::Commitment,
         parameters::Parameters,
         protocols::{
-            hash_to_prime::snark_range::Protocol as HPProtocol,
+            hash_to_prime::{sigma_range, snark_range::Protocol as HPProtocol},
+            membership,
             modeq::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
         },
+        utils::curve::CurvePointProjective,
     };
     use accumulator::group::Rsa2048;
     use ark_bls12_381::{Bls12_381, G1Projective};
+    use ark_secp256k1::Projective as Secp256k1;
     use merlin::Transcript;
     use rand::thread_rng;
     use rug::rand::RandState;
     use rug::Integer;
     use std::cell::RefCell;
+    use zeroize::Zeroize;
 
-    #[test]
-    fn test_proof() {
-        let params = Parameters::from_security_level(128).unwrap();
+    fn prove_and_verify<P: CurvePointProjective>(crs: &CRSModEq<Rsa2048, P>) {
         let mut rng1 = RandState::new();
         rng1.seed(&Integer::from(13));
         let mut rng2 = thread_rng();
-
-        let crs = crate::protocols::membership::Protocol::<
-            Rsa2048,
-            G1Projective,
-            HPProtocol<Bls12_381>,
-        >::setup(&params, &mut rng1, &mut rng2)
-        .unwrap()
-        .crs
-        .crs_modeq;
-        let protocol = Protocol::<Rsa2048, G1Projective>::from_crs(&crs);
+        let protocol = Protocol::<Rsa2048, P>::from_crs(crs);
 
         let value1 = Integer::from(2);
         let randomness1 = Integer::from(5);
@@ -200,7 +396,7 @@ mod test {
             c_e: commitment1,
             c_e_q: commitment2,
         };
-        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        let mut verifier_channel = TranscriptVerifierChannel::new(crs, &proof_transcript);
         protocol
             .prove(
                 &mut verifier_channel,
@@ -219,7 +415,40 @@ mod test {
 
         let verification_transcript = RefCell::new(Transcript::new(b"modeq"));
         let mut prover_channel =
-            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+            TranscriptProverChannel::new(crs, &verification_transcript, &proof);
         protocol.verify(&mut prover_channel, &statement).unwrap();
     }
+
+    #[test]
+    fn test_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = membership::Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap()
+        .crs
+        .crs_modeq;
+        prove_and_verify(&crs);
+    }
+
+    #[test]
+    fn test_proof_secp256k1() {
+        let (params, _) = Parameters::from_curve::<ark_secp256k1::Fr>().unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs =
+            membership::Protocol::<Rsa2048, Secp256k1, sigma_range::Protocol<Secp256k1>>::setup(
+                &params, &mut rng1, &mut rng2,
+            )
+            .unwrap()
+            .crs
+            .crs_modeq;
+        prove_and_verify(&crs);
+    }
 }
@@ -5,14 +5,13 @@ use crate::{
         CRSModEq, Message1, Message2, Proof,
     },
     transcript::{
-        TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolCurve,
+        FiatShamir, TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolCurve,
         TranscriptProtocolInteger,
     },
     utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
 };
-use merlin::Transcript;
+use core::cell::RefCell;
 use rug::Integer;
-use std::cell::RefCell;
 
 pub trait TranscriptProtocolModEq<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective>:
     TranscriptProtocolInteger<G> + TranscriptProtocolCurve<P> + TranscriptProtocolChallenge
@@ -20,8 +19,8 @@ pub trait TranscriptProtocolModEq<G: ConvertibleUnknownOrderGroup, P: CurvePoint
     fn modeq_domain_sep(&mut self);
 }
 
-impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> TranscriptProtocolModEq<G, P>
-    for Transcript
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, T: FiatShamir>
+    TranscriptProtocolModEq<G, P> for T
 {
     fn modeq_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"modeq");
@@ -5,6 +5,8 @@ use crate::{
 };
 
 pub trait NonMembershipVerifierChannel<G: ConvertibleUnknownOrderGroup> {
+    /// Binds the proof to the accumulator state identifier.
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError>;
     fn send_c_e(
         &mut self,
         c_e: &<IntegerCommitment<G> as Commitment>::Instance,
@@ -12,6 +14,8 @@ pub trait NonMembershipVerifierChannel<G: ConvertibleUnknownOrderGroup> {
 }
 
 pub trait NonMembershipProverChannel<G: ConvertibleUnknownOrderGroup> {
+    /// Binds the proof to the accumulator state identifier.
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError>;
     fn receive_c_e(
         &mut self,
     ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError>;
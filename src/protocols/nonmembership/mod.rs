@@ -1,8 +1,13 @@
 //! Implements CPNonMemRSA and CPNonMemRSAPrm.
 use crate::{
-    commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment, Commitment},
+    commitments::{
+        integer::{IntegerCommitment, IntegerCommitmentTables},
+        pedersen::PedersenCommitment,
+        Commitment,
+    },
     parameters::Parameters,
     protocols::{
+        check_discriminant, check_elem, check_point,
         coprime::{
             channel::{CoprimeProverChannel, CoprimeVerifierChannel},
             CRSCoprime, Proof as CoprimeProof, Protocol as CoprimeProtocol,
@@ -10,7 +15,7 @@ use crate::{
         },
         hash_to_prime::{
             channel::{HashToPrimeProverChannel, HashToPrimeVerifierChannel},
-            CRSHashToPrime, HashToPrimeError, HashToPrimeProtocol,
+            CRSHashToPrime, CRSHashToPrimeVerifier, CRSSize, HashToPrimeError, HashToPrimeProtocol,
             Statement as HashToPrimeStatement, Witness as HashToPrimeWitness,
         },
         modeq::{
@@ -18,15 +23,36 @@ use crate::{
             CRSModEq, Proof as ModEqProof, Protocol as ModEqProtocol, Statement as ModEqStatement,
             Witness as ModEqWitness,
         },
-        ProofError, SetupError, VerificationError,
+        shared::{CRSBases, SharedCRS},
+        validity::{Validity, ValidityError, ValidityPolicy},
+        CRSError, ProofError, SetupError, ValidationError, VerificationError,
     },
+    transcript::FiatShamir,
+    utils::zeroize_integer,
     utils::ConvertibleUnknownOrderGroup,
-    utils::{curve::CurvePointProjective, random_between},
+    utils::{
+        curve::CurvePointProjective,
+        group::ElemFromBytes,
+        random_between,
+        rng::seeded_rngs,
+        serialization::{
+            bytes_size, crs_fingerprint, elem_size, proof_id, read_bytes, read_checked, read_elem,
+            read_point, write_bytes, write_checked, write_elem, write_point, write_set_statement,
+        },
+    },
+    utils::{Redacted, RedactedInteger},
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use channel::{NonMembershipProverChannel, NonMembershipVerifierChannel};
+use core::cell::RefCell;
+use merlin::Transcript;
 use rand::{CryptoRng, RngCore};
 use rug::rand::MutRandState;
 use rug::Integer;
+use std::fmt;
+use std::sync::Arc;
+use transcript::{TranscriptProverChannel, TranscriptVerifierChannel};
+use zeroize::Zeroize;
 
 pub mod channel;
 pub mod transcript;
@@ -38,6 +64,10 @@ pub struct CRS<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: Has
     pub crs_coprime: CRSCoprime<G>,
     pub crs_modeq: CRSModEq<G, P>,
     pub crs_hash_to_prime: CRSHashToPrime<P, HP>,
+    /// Identifies the application, bound to the transcripts started with
+    /// `Protocol::transcript`, so that proofs aren't accepted across
+    /// applications sharing the same parameters.
+    pub transcript_label: Vec<u8>,
 }
 
 impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
@@ -49,28 +79,707 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimePr
             crs_coprime: self.crs_coprime.clone(),
             crs_modeq: self.crs_modeq.clone(),
             crs_hash_to_prime: self.crs_hash_to_prime.clone(),
+            transcript_label: self.transcript_label.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+{
+    /// Builds the CRS from a `SharedCRS`, pointing to its hash-to-prime
+    /// parameters.
+    pub fn from_shared(shared: &SharedCRS<G, P, HP>) -> Self {
+        CRS {
+            parameters: shared.parameters.clone(),
+            crs_modeq: CRSModEq {
+                parameters: shared.parameters.clone(),
+                integer_commitment_parameters: shared.integer_commitment_parameters.clone(),
+                pedersen_commitment_parameters: shared
+                    .crs_hash_to_prime
+                    .pedersen_commitment_parameters
+                    .clone(),
+                fixed_base_tables: None,
+            },
+            crs_coprime: CRSCoprime {
+                parameters: shared.parameters.clone(),
+                integer_commitment_parameters: shared.integer_commitment_parameters.clone(),
+            },
+            crs_hash_to_prime: shared.crs_hash_to_prime.clone(),
+            transcript_label: b"nonmembership".to_vec(),
+        }
+    }
+
+    /// Identifies the CRS by what the verifier relies on, so that the
+    /// transcripts bound to it differ across CRSs.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        crs_fingerprint(
+            &self.parameters,
+            &self.crs_coprime.integer_commitment_parameters,
+            &self.crs_modeq.pedersen_commitment_parameters,
+            &HP::verifying_key_bytes(&HP::verifying_key(
+                &self.crs_hash_to_prime.hash_to_prime_parameters,
+            )),
+        )
+    }
+
+    pub fn with_transcript_label(mut self, label: &[u8]) -> Self {
+        self.transcript_label = label.to_vec();
+        self
+    }
+
+    /// Checks that the CRS is well-formed, e.g., when it's received from an
+    /// untrusted source: that the sub-protocols share the parameters and the
+    /// commitment bases, that the bases are distinct and not the identity,
+    /// and the relations of the hash-to-prime keys that can be checked
+    /// without the trapdoor. A setup whose trapdoor is known still passes,
+    /// so this doesn't replace a trusted setup or ceremony.
+    pub fn validate<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(), CRSError> {
+        let fingerprint = self.parameters.fingerprint();
+        check_discriminant::<G>(&self.parameters)?;
+        if self.crs_coprime.parameters.fingerprint() != fingerprint
+            || self.crs_modeq.parameters.fingerprint() != fingerprint
+            || self.crs_hash_to_prime.parameters.fingerprint() != fingerprint
+        {
+            return Err(CRSError::InvalidParameters);
+        }
+        let integer_commitment = &self.crs_coprime.integer_commitment_parameters;
+        let modeq_integer_commitment = &self.crs_modeq.integer_commitment_parameters;
+        if integer_commitment.g == G::id()
+            || integer_commitment.h == G::id()
+            || integer_commitment.g == integer_commitment.h
+            || modeq_integer_commitment.g != integer_commitment.g
+            || modeq_integer_commitment.h != integer_commitment.h
+        {
+            return Err(CRSError::InvalidCommitmentBases);
+        }
+        let pedersen = &self.crs_modeq.pedersen_commitment_parameters;
+        let hash_to_prime_pedersen = &self.crs_hash_to_prime.pedersen_commitment_parameters;
+        if pedersen.g != hash_to_prime_pedersen.g || pedersen.h != hash_to_prime_pedersen.h {
+            return Err(CRSError::InvalidCommitmentBases);
+        }
+        self.crs_hash_to_prime.validate(rng)
+    }
+
+    /// Precomputes fixed-base tables for the integer commitment bases, which
+    /// speed up the modeq sub-protocol at the cost of memory.
+    pub fn precompute_fixed_base_tables(&mut self) {
+        let tables = IntegerCommitmentTables::new(
+            &self.crs_modeq.integer_commitment_parameters,
+            self.parameters
+                .max_response_bits(G::order_upper_bound().significant_bits()),
+        );
+        self.crs_modeq.fixed_base_tables = Some(tables);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+where
+    HP::Parameters: CRSSize,
+{
+    /// The size of the CRS with compressed curve points: the parameters, the
+    /// transcript label, the commitment bases and the hash-to-prime keys.
+    /// The fixed-base tables aren't counted, since they're recomputed from
+    /// the bases.
+    pub fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    pub fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+
+    fn size(&self, compressed: bool) -> usize {
+        // The modeq CRS holds the same bases as the coprime and hash-to-prime
+        // ones, so it isn't counted.
+        Parameters::SERIALIZED_SIZE
+            + bytes_size(self.transcript_label.len())
+            + self.crs_coprime.size()
+            + self.crs_hash_to_prime.size(compressed)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize,
+{
+    fn write<W: Write>(&self, writer: &mut W, compressed: bool) -> Result<(), SerializationError> {
+        write_bases(
+            writer,
+            &self.parameters,
+            &self.transcript_label,
+            &self.crs_modeq,
+            compressed,
+        )?;
+        let hash_to_prime_parameters = &self.crs_hash_to_prime.hash_to_prime_parameters;
+        if compressed {
+            hash_to_prime_parameters.serialize(writer)
+        } else {
+            hash_to_prime_parameters.serialize_uncompressed(writer)
+        }
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    fn read<R: Read>(reader: &mut R, compressed: bool) -> Result<Self, SerializationError> {
+        let (parameters, transcript_label, crs_coprime, crs_modeq) =
+            read_bases(reader, compressed)?;
+        let hash_to_prime_parameters = Arc::new(if compressed {
+            HP::Parameters::deserialize(reader)?
+        } else {
+            HP::Parameters::deserialize_uncompressed(reader)?
+        });
+        Ok(CRS {
+            parameters: parameters.clone(),
+            crs_hash_to_prime: CRSHashToPrime {
+                parameters,
+                pedersen_commitment_parameters: crs_modeq.pedersen_commitment_parameters.clone(),
+                hash_to_prime_parameters,
+            },
+            crs_coprime,
+            crs_modeq,
+            transcript_label,
+        })
+    }
+}
+
+/// Writes what the CRS and the verifier key share: the parameters, the
+/// transcript label and the integer and Pedersen commitment bases.
+fn write_bases<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, W: Write>(
+    writer: &mut W,
+    parameters: &Parameters,
+    transcript_label: &[u8],
+    crs_modeq: &CRSModEq<G, P>,
+    compressed: bool,
+) -> Result<(), SerializationError> {
+    parameters.serialize(&mut *writer)?;
+    write_bytes(writer, transcript_label)?;
+    let integer_commitment = &crs_modeq.integer_commitment_parameters;
+    write_elem::<G, _>(writer, &integer_commitment.g)?;
+    write_elem::<G, _>(writer, &integer_commitment.h)?;
+    let pedersen = &crs_modeq.pedersen_commitment_parameters;
+    write_point(writer, &pedersen.g, compressed)?;
+    write_point(writer, &pedersen.h, compressed)
+}
+
+#[allow(clippy::type_complexity)]
+fn read_bases<G: ElemFromBytes, P: CurvePointProjective, R: Read>(
+    reader: &mut R,
+    compressed: bool,
+) -> Result<(Parameters, Vec<u8>, CRSCoprime<G>, CRSModEq<G, P>), SerializationError> {
+    let parameters = Parameters::deserialize(&mut *reader)?;
+    let transcript_label = read_bytes(reader)?;
+    let integer_commitment_parameters =
+        IntegerCommitment::<G>::new(&read_elem::<G, _>(reader)?, &read_elem::<G, _>(reader)?);
+    let pedersen_commitment_parameters = PedersenCommitment::<P>::new(
+        &read_point(reader, compressed)?,
+        &read_point(reader, compressed)?,
+    );
+    let crs_modeq = CRSModEq {
+        parameters: parameters.clone(),
+        integer_commitment_parameters: integer_commitment_parameters.clone(),
+        pedersen_commitment_parameters,
+        fixed_base_tables: None,
+    };
+    let crs_coprime = CRSCoprime {
+        parameters: parameters.clone(),
+        integer_commitment_parameters,
+    };
+    Ok((parameters, transcript_label, crs_coprime, crs_modeq))
+}
+
+/// Written as the parameters, the transcript label, the integer and Pedersen
+/// commitment bases, which the sub-protocols share, and the hash-to-prime
+/// parameters, as the membership CRS is. The fixed-base tables aren't
+/// written, so they have to be precomputed again after reading.
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, false)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    /// Writes the CRS followed by a checksum, so that setup runs once and the
+    /// CRS is distributed as a file. Uncompressed curve points make the file
+    /// larger but faster to read.
+    pub fn serialize_to<W: Write>(
+        &self,
+        writer: W,
+        compressed: bool,
+    ) -> Result<(), SerializationError> {
+        write_checked(writer, self, compressed)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    /// Reads a CRS written by `serialize_to`, rejecting it if the checksum
+    /// doesn't match. The fixed-base tables have to be precomputed again.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_checked(reader)
+    }
+}
+
+/// The prover needs all of the CRS, including the hash-to-prime proving key.
+pub type ProverKey<G, P, HP> = CRS<G, P, HP>;
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CRS<G, P, HP>
+{
+    pub fn verifier_key(&self) -> VerifierKey<G, P, HP> {
+        VerifierKey {
+            parameters: self.parameters.clone(),
+            crs_coprime: self.crs_coprime.clone(),
+            crs_modeq: self.crs_modeq.clone(),
+            crs_hash_to_prime: self.crs_hash_to_prime.verifier(),
+            transcript_label: self.transcript_label.clone(),
+        }
+    }
+}
+
+/// The part of the CRS that the verifier needs, without the hash-to-prime
+/// proving key, serialized as the membership verifier key is.
+pub struct VerifierKey<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub parameters: Parameters,
+    pub crs_coprime: CRSCoprime<G>,
+    pub crs_modeq: CRSModEq<G, P>,
+    pub crs_hash_to_prime: CRSHashToPrimeVerifier<P, HP>,
+    pub transcript_label: Vec<u8>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
+    for VerifierKey<G, P, HP>
+{
+    fn clone(&self) -> Self {
+        Self {
+            parameters: self.parameters.clone(),
+            crs_coprime: self.crs_coprime.clone(),
+            crs_modeq: self.crs_modeq.clone(),
+            crs_hash_to_prime: self.crs_hash_to_prime.clone(),
+            transcript_label: self.transcript_label.clone(),
         }
     }
 }
 
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    VerifierKey<G, P, HP>
+{
+    /// Builds the key from the commitment bases and the verifying key of
+    /// hash-to-prime parameters generated for them, without the proving key.
+    pub fn from_bases(bases: &CRSBases<G, P>, verifying_key: HP::VerifyingKey) -> Self {
+        VerifierKey {
+            parameters: bases.parameters.clone(),
+            crs_coprime: CRSCoprime {
+                parameters: bases.parameters.clone(),
+                integer_commitment_parameters: bases.integer_commitment_parameters.clone(),
+            },
+            crs_modeq: CRSModEq {
+                parameters: bases.parameters.clone(),
+                integer_commitment_parameters: bases.integer_commitment_parameters.clone(),
+                pedersen_commitment_parameters: bases.pedersen_commitment_parameters.clone(),
+                fixed_base_tables: None,
+            },
+            crs_hash_to_prime: CRSHashToPrimeVerifier {
+                parameters: bases.parameters.clone(),
+                pedersen_commitment_parameters: bases.pedersen_commitment_parameters.clone(),
+                verifying_key,
+            },
+            transcript_label: b"nonmembership".to_vec(),
+        }
+    }
+
+    /// Equal to the fingerprint of the CRS the key was extracted from.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        crs_fingerprint(
+            &self.parameters,
+            &self.crs_coprime.integer_commitment_parameters,
+            &self.crs_modeq.pedersen_commitment_parameters,
+            &HP::verifying_key_bytes(&self.crs_hash_to_prime.verifying_key),
+        )
+    }
+
+    /// Binds the transcript as `Protocol::bind_transcript` does.
+    pub fn bind_transcript<T: FiatShamir>(&self, transcript: &mut T, context: &[u8]) {
+        transcript.append_message(b"label", &self.transcript_label);
+        transcript.append_message(b"crs", &self.fingerprint());
+        transcript.append_message(b"context", context);
+    }
+
+    /// Verifies as `Protocol::verify` does. The SNARK's verifying key is
+    /// prepared on every call.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "nonmembership::verify", level = "debug", skip_all)
+    )]
+    pub fn verify<
+        C: NonMembershipProverChannel<G>
+            + CoprimeProverChannel<G>
+            + ModEqProverChannel<G, P>
+            + HashToPrimeProverChannel<P, HP>,
+    >(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G, P>,
+    ) -> Result<(), VerificationError> {
+        verify_coprime_and_modeq(
+            &self.crs_coprime,
+            &self.crs_modeq,
+            prover_channel,
+            statement,
+        )?;
+        HP::verify_with_key(
+            &self.crs_hash_to_prime,
+            prover_channel,
+            &HashToPrimeStatement {
+                c_e_q: statement.c_e_q.clone(),
+            },
+        )
+    }
+
+    /// Verifies a proof created by `prove_noninteractive` with the same
+    /// context.
+    pub fn verify_noninteractive(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &NonMembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = Transcript::new(b"cpsnarks-set");
+        self.bind_transcript(&mut transcript, context);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel =
+            TranscriptProverChannel::with_verifier_key(self, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
+    }
+
+    /// Verifies as `Protocol::verify_signature` does.
+    pub fn verify_signature(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &NonMembershipProof<G, P, HP>,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = Transcript::new(b"cpsnarks-set");
+        self.bind_transcript(&mut transcript, context);
+        transcript.append_message(b"message", message);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel =
+            TranscriptProverChannel::with_verifier_key(self, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
+    }
+
+    /// Verifies as `Protocol::verify_with_policy` does.
+    pub fn verify_with_policy(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &NonMembershipProof<G, P, HP>,
+        context: &[u8],
+        policy: &ValidityPolicy,
+    ) -> Result<(), VerificationError> {
+        policy.check(statement.epoch())?;
+        self.verify_noninteractive(statement, proof, context)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalSerialize,
+{
+    fn size(&self, compressed: bool) -> usize {
+        Parameters::SERIALIZED_SIZE
+            + bytes_size(self.transcript_label.len())
+            + self.crs_coprime.size()
+            + self.crs_hash_to_prime.size(compressed)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, compressed: bool) -> Result<(), SerializationError> {
+        write_bases(
+            writer,
+            &self.parameters,
+            &self.transcript_label,
+            &self.crs_modeq,
+            compressed,
+        )?;
+        let verifying_key = &self.crs_hash_to_prime.verifying_key;
+        if compressed {
+            verifying_key.serialize(writer)
+        } else {
+            verifying_key.serialize_uncompressed(writer)
+        }
+    }
+
+    /// Writes the verifier key followed by a checksum, as
+    /// `CRS::serialize_to` does.
+    pub fn serialize_to<W: Write>(
+        &self,
+        writer: W,
+        compressed: bool,
+    ) -> Result<(), SerializationError> {
+        write_checked(writer, self, compressed)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    fn read<R: Read>(reader: &mut R, compressed: bool) -> Result<Self, SerializationError> {
+        let (parameters, transcript_label, crs_coprime, crs_modeq) =
+            read_bases(reader, compressed)?;
+        let verifying_key = if compressed {
+            HP::VerifyingKey::deserialize(reader)?
+        } else {
+            HP::VerifyingKey::deserialize_uncompressed(reader)?
+        };
+        Ok(VerifierKey {
+            parameters: parameters.clone(),
+            crs_hash_to_prime: CRSHashToPrimeVerifier {
+                parameters,
+                pedersen_commitment_parameters: crs_modeq.pedersen_commitment_parameters.clone(),
+                verifying_key,
+            },
+            crs_coprime,
+            crs_modeq,
+            transcript_label,
+        })
+    }
+
+    /// Reads a verifier key written by `serialize_to`, rejecting it if the
+    /// checksum doesn't match.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_checked(reader)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalSerialize,
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for VerifierKey<G, P, HP>
+where
+    HP::VerifyingKey: CanonicalDeserialize,
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, false)
+    }
+}
+
 pub struct Protocol<
     G: ConvertibleUnknownOrderGroup,
     P: CurvePointProjective,
     HP: HashToPrimeProtocol<P>,
 > {
     pub crs: CRS<G, P, HP>,
+    /// Built once from the CRS, e.g., preparing the SNARK's verifying key.
+    hash_to_prime: HP,
 }
 
+/// The accumulator and the commitment to the element, built with `new`,
+/// which rejects malformed ones.
 pub struct Statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
-    pub c_p: G::Elem,
-    pub c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+    pub(crate) c_p: G::Elem,
+    pub(crate) c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+    /// Optional identifier of the accumulator state, e.g., its epoch or a
+    /// digest, bound to the transcript so that the proof is only accepted
+    /// against the same state.
+    pub(crate) epoch: Option<Vec<u8>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Statement<G, P> {
+    /// The statement that the element committed to in `c_e_q` is not in the
+    /// accumulator `c_p`, rejected if either is the identity or the
+    /// commitment is outside the prime-order subgroup.
+    pub fn new(
+        c_p: G::Elem,
+        c_e_q: <PedersenCommitment<P> as Commitment>::Instance,
+    ) -> Result<Self, ValidationError> {
+        let statement = Statement {
+            c_p,
+            c_e_q,
+            epoch: None,
+        };
+        statement.validate()?;
+        Ok(statement)
+    }
+
+    /// Binds the statement to a state of the accumulator.
+    pub fn with_epoch(mut self, epoch: &[u8]) -> Self {
+        self.epoch = Some(epoch.to_vec());
+        self
+    }
+
+    pub fn c_p(&self) -> &G::Elem {
+        &self.c_p
+    }
+
+    pub fn c_e_q(&self) -> &<PedersenCommitment<P> as Commitment>::Instance {
+        &self.c_e_q
+    }
+
+    pub fn epoch(&self) -> Option<&[u8]> {
+        self.epoch.as_deref()
+    }
+
+    /// Binds the statement to an epoch and an optional expiry time, checked
+    /// by `verify_with_policy`.
+    pub fn with_validity(self, validity: &Validity) -> Self {
+        self.with_epoch(&validity.to_bytes())
+    }
+
+    /// The validity metadata set by `with_validity`, if any.
+    pub fn validity(&self) -> Result<Option<Validity>, ValidityError> {
+        self.epoch.as_deref().map(Validity::from_bytes).transpose()
+    }
+
+    pub fn to_canonical_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        write_set_statement::<G, P, _>(&mut bytes, &self.c_p, &self.c_e_q, &self.epoch)?;
+        Ok(bytes)
+    }
+
+    /// Rejects an accumulator or commitment equal to the identity, or a
+    /// commitment outside the prime-order subgroup.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("c_p", &self.c_p)?;
+        check_point("c_e_q", &self.c_e_q)
+    }
 }
 
+/// The element, the randomness of its commitment and its non-membership
+/// witness, built with `new`.
 pub struct Witness<G: ConvertibleUnknownOrderGroup> {
-    pub e: Integer,
-    pub r_q: Integer,
-    pub d: G::Elem,
-    pub b: Integer,
+    pub(crate) e: Integer,
+    pub(crate) r_q: Integer,
+    pub(crate) d: G::Elem,
+    pub(crate) b: Integer,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Witness<G> {
+    /// Rejects an element that isn't positive and negative randomness. The
+    /// Bezout coefficients `d` and `b` can't be checked without the
+    /// accumulated product, and whether the element is in the range of the
+    /// hash-to-prime backend is checked by `Protocol::hash_to_prime`.
+    pub fn new(e: Integer, r_q: Integer, d: G::Elem, b: Integer) -> Result<Self, ValidationError> {
+        let witness = Witness { e, r_q, d, b };
+        if witness.e <= 0 {
+            return Err(ValidationError::WitnessOutOfRange("e"));
+        }
+        if witness.r_q < 0 {
+            return Err(ValidationError::WitnessOutOfRange("r_q"));
+        }
+        Ok(witness)
+    }
+
+    pub fn e(&self) -> &Integer {
+        &self.e
+    }
+
+    pub fn r_q(&self) -> &Integer {
+        &self.r_q
+    }
+
+    pub fn d(&self) -> &G::Elem {
+        &self.d
+    }
+
+    pub fn b(&self) -> &Integer {
+        &self.b
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for Witness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+        zeroize_integer(&mut self.b);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for Witness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .field("d", &Redacted)
+            .field("b", &RedactedInteger(&self.b))
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for Witness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
 }
 
 pub struct Proof<
@@ -97,39 +806,166 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimePr
     }
 }
 
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    Proof<G, P, HP>
+{
+    /// Checks the ranges of the responses of the sub-proofs. The group
+    /// elements and curve points are checked when deserializing.
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        self.proof_coprime.validate(parameters)?;
+        self.proof_modeq.validate(parameters)
+    }
+
+    /// The canonical encoding of the proof, its compressed serialization.
+    pub fn to_canonical_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = vec![];
+        self.serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// A stable identifier of the proof and its statement, to reference it
+    /// in caches and audit logs.
+    pub fn proof_id(&self, statement: &Statement<G, P>) -> Result<[u8; 32], SerializationError> {
+        Ok(proof_id(
+            b"nonmembership",
+            &statement.to_canonical_bytes()?,
+            &self.to_canonical_bytes()?,
+        ))
+    }
+}
+
+/// The non-interactive proof, bundling the sub-proofs.
+pub type NonMembershipProof<G, P, HP> = Proof<G, P, HP>;
+
+/// Written as the commitment to the hashed element, the coprime and modeq
+/// proofs and the bytes of the hash-to-prime proof.
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for Proof<G, P, HP>
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        write_elem::<G, _>(&mut writer, &self.c_e)?;
+        self.proof_coprime.serialize(&mut writer)?;
+        self.proof_modeq.serialize(&mut writer)?;
+        write_bytes(&mut writer, &HP::proof_to_bytes(&self.proof_hash_to_prime)?)
+    }
+
+    fn serialized_size(&self) -> usize {
+        elem_size::<G>(&self.c_e)
+            + self.proof_coprime.serialized_size()
+            + self.proof_modeq.serialized_size()
+            + bytes_size(HP::proof_size(&self.proof_hash_to_prime, true))
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        write_elem::<G, _>(&mut writer, &self.c_e)?;
+        self.proof_coprime.serialize_uncompressed(&mut writer)?;
+        self.proof_modeq.serialize_uncompressed(&mut writer)?;
+        write_bytes(
+            &mut writer,
+            &HP::proof_to_bytes_uncompressed(&self.proof_hash_to_prime)?,
+        )
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        elem_size::<G>(&self.c_e)
+            + self.proof_coprime.uncompressed_size()
+            + self.proof_modeq.uncompressed_size()
+            + bytes_size(HP::proof_size(&self.proof_hash_to_prime, false))
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for Proof<G, P, HP>
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Proof {
+            c_e: read_elem::<G, _>(&mut reader)?,
+            proof_coprime: CoprimeProof::deserialize(&mut reader)?,
+            proof_modeq: ModEqProof::deserialize(&mut reader)?,
+            proof_hash_to_prime: HP::proof_from_bytes(&read_bytes(&mut reader)?)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Proof {
+            c_e: read_elem::<G, _>(&mut reader)?,
+            proof_coprime: CoprimeProof::deserialize_uncompressed(&mut reader)?,
+            proof_modeq: ModEqProof::deserialize_uncompressed(&mut reader)?,
+            proof_hash_to_prime: HP::proof_from_bytes_uncompressed(&read_bytes(&mut reader)?)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    serde::Serialize for Proof<G, P, HP>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::utils::serialization::serialize_canonical(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    serde::Deserialize<'de> for Proof<G, P, HP>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::utils::serialization::deserialize_canonical(deserializer)
+    }
+}
+
 impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
     Protocol<G, P, HP>
 {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "nonmembership::setup", level = "debug", skip_all)
+    )]
     pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
         parameters: &Parameters,
         rng1: &mut R1,
         rng2: &mut R2,
     ) -> Result<Protocol<G, P, HP>, SetupError> {
-        let integer_commitment_parameters = IntegerCommitment::<G>::setup(rng1);
-        let pedersen_commitment_parameters = PedersenCommitment::<P>::setup(rng2);
-        let hash_to_prime_parameters =
-            HP::setup(rng2, &pedersen_commitment_parameters, parameters)?;
+        let crs = CRS::from_shared(&SharedCRS::setup(parameters, rng1, rng2)?);
         Ok(Protocol {
-            crs: CRS::<G, P, HP> {
-                parameters: parameters.clone(),
-                crs_modeq: CRSModEq::<G, P> {
-                    parameters: parameters.clone(),
-                    integer_commitment_parameters: integer_commitment_parameters.clone(),
-                    pedersen_commitment_parameters: pedersen_commitment_parameters.clone(),
-                },
-                crs_coprime: CRSCoprime::<G> {
-                    parameters: parameters.clone(),
-                    integer_commitment_parameters,
-                },
-                crs_hash_to_prime: CRSHashToPrime::<P, HP> {
-                    parameters: parameters.clone(),
-                    pedersen_commitment_parameters,
-                    hash_to_prime_parameters,
-                },
-            },
+            hash_to_prime: HP::from_crs(&crs.crs_hash_to_prime),
+            crs,
         })
     }
 
+    /// Like `setup`, deriving all the randomness from the seed, so that the
+    /// same seed gives the same CRS on every machine. The seed has to be
+    /// secret and erased for the CRS to be secure, so this is meant for tests
+    /// and debugging.
+    pub fn setup_deterministic(
+        parameters: &Parameters,
+        seed: [u8; 32],
+    ) -> Result<Protocol<G, P, HP>, SetupError> {
+        let (mut rng1, mut rng2) = seeded_rngs(seed);
+        Self::setup(parameters, &mut rng1, &mut rng2)
+    }
+
+    /// Starts a transcript bound to the label of the CRS and to the context
+    /// of the statement, e.g., a session or request identifier.
+    pub fn transcript(&self, context: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new(b"cpsnarks-set");
+        self.bind_transcript(&mut transcript, context);
+        transcript
+    }
+
+    /// Binds any Fiat-Shamir transcript to the label and the fingerprint of
+    /// the CRS and to the context, before it is used in the transcript
+    /// channels.
+    pub fn bind_transcript<T: FiatShamir>(&self, transcript: &mut T, context: &[u8]) {
+        transcript.append_message(b"label", &self.crs.transcript_label);
+        transcript.append_message(b"crs", &self.crs.fingerprint());
+        transcript.append_message(b"context", context);
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "nonmembership::prove", level = "debug", skip_all)
+    )]
     pub fn prove<
         R1: MutRandState,
         R2: RngCore + CryptoRng,
@@ -145,60 +981,73 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimePr
         statement: &Statement<G, P>,
         witness: &Witness<G>,
     ) -> Result<(), ProofError> {
+        if let Some(epoch) = &statement.epoch {
+            verifier_channel.bind_epoch(epoch)?;
+        }
         let (hashed_e, _) = self.hash_to_prime(&witness.e)?;
         let r = random_between(rng1, &Integer::from(0), &G::order_upper_bound());
         let c_e = self
             .crs
             .crs_coprime
             .integer_commitment_parameters
-            .commit(&hashed_e, &r)?;
+            .commit_secret(&hashed_e, &r, rng2)?;
         verifier_channel.send_c_e(&c_e)?;
         let coprime = CoprimeProtocol::from_crs(&self.crs.crs_coprime)?;
-        coprime.prove(
-            verifier_channel,
-            rng1,
-            &CoprimeStatement {
-                c_e: c_e.clone(),
-                acc: statement.c_p.clone(),
-            },
-            &CoprimeWitness {
-                e: hashed_e.clone(),
-                r: r.clone(),
-                d: witness.d.clone(),
-                b: witness.b.clone(),
-            },
-        )?;
+        coprime
+            .prove(
+                verifier_channel,
+                rng1,
+                rng2,
+                &CoprimeStatement {
+                    c_e: c_e.clone(),
+                    acc: statement.c_p.clone(),
+                },
+                &CoprimeWitness {
+                    e: hashed_e.clone(),
+                    r: r.clone(),
+                    d: witness.d.clone(),
+                    b: witness.b.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("coprime"))?;
         let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
-        modeq.prove(
-            verifier_channel,
-            rng1,
-            rng2,
-            &ModEqStatement {
-                c_e,
-                c_e_q: statement.c_e_q.clone(),
-            },
-            &ModEqWitness {
-                e: hashed_e,
-                r,
-                r_q: witness.r_q.clone(),
-            },
-        )?;
-        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
-        hash_to_prime.prove(
-            verifier_channel,
-            rng2,
-            &HashToPrimeStatement {
-                c_e_q: statement.c_e_q.clone(),
-            },
-            &HashToPrimeWitness {
-                e: witness.e.clone(),
-                r_q: witness.r_q.clone(),
-            },
-        )?;
+        modeq
+            .prove(
+                verifier_channel,
+                rng1,
+                rng2,
+                &ModEqStatement {
+                    c_e,
+                    c_e_q: statement.c_e_q.clone(),
+                },
+                &ModEqWitness {
+                    e: hashed_e,
+                    r,
+                    r_q: witness.r_q.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("modeq"))?;
+        self.hash_to_prime
+            .prove(
+                verifier_channel,
+                rng2,
+                &HashToPrimeStatement {
+                    c_e_q: statement.c_e_q.clone(),
+                },
+                &HashToPrimeWitness {
+                    e: witness.e.clone(),
+                    r_q: witness.r_q.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("hash_to_prime"))?;
 
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "nonmembership::verify", level = "debug", skip_all)
+    )]
     pub fn verify<
         C: NonMembershipProverChannel<G>
             + CoprimeProverChannel<G>
@@ -209,47 +1058,156 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimePr
         prover_channel: &mut C,
         statement: &Statement<G, P>,
     ) -> Result<(), VerificationError> {
-        let c_e = prover_channel.receive_c_e()?;
-        let coprime = CoprimeProtocol::from_crs(&self.crs.crs_coprime)?;
-        coprime.verify(
-            prover_channel,
-            &CoprimeStatement {
-                c_e: c_e.clone(),
-                acc: statement.c_p.clone(),
-            },
-        )?;
-        let modeq = ModEqProtocol::from_crs(&self.crs.crs_modeq);
-        modeq.verify(
-            prover_channel,
-            &ModEqStatement {
-                c_e,
-                c_e_q: statement.c_e_q.clone(),
-            },
-        )?;
-        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
-        hash_to_prime.verify(
+        verify_coprime_and_modeq(
+            &self.crs.crs_coprime,
+            &self.crs.crs_modeq,
             prover_channel,
-            &HashToPrimeStatement {
-                c_e_q: statement.c_e_q.clone(),
-            },
+            statement,
         )?;
+        self.hash_to_prime
+            .verify(
+                prover_channel,
+                &HashToPrimeStatement {
+                    c_e_q: statement.c_e_q.clone(),
+                },
+            )
+            .map_err(|e| e.in_protocol("hash_to_prime"))?;
 
         Ok(())
     }
 
     pub fn hash_to_prime(&self, e: &Integer) -> Result<(Integer, u64), HashToPrimeError> {
-        let hash_to_prime = HashToPrimeProtocol::from_crs(&self.crs.crs_hash_to_prime);
-        hash_to_prime.hash_to_prime(e)
+        self.hash_to_prime.hash_to_prime(e)
+    }
+
+    /// Proves with the Fiat-Shamir transform, over the transcript started by
+    /// `transcript(context)`.
+    pub fn prove_noninteractive<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        context: &[u8],
+    ) -> Result<NonMembershipProof<G, P, HP>, ProofError> {
+        let transcript = RefCell::new(self.transcript(context));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.prove(&mut verifier_channel, rng1, rng2, statement, witness)?;
+        Ok(verifier_channel.proof()?)
+    }
+
+    /// Verifies a proof created by `prove_noninteractive` with the same
+    /// context.
+    pub fn verify_noninteractive(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &NonMembershipProof<G, P, HP>,
+        context: &[u8],
+    ) -> Result<(), VerificationError> {
+        let transcript = RefCell::new(self.transcript(context));
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
+    }
+
+    /// Verifies as `verify_noninteractive` does, after checking the
+    /// statement's validity metadata against the policy, so that a proof for
+    /// a past epoch, or one that has expired, is rejected.
+    pub fn verify_with_policy(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &NonMembershipProof<G, P, HP>,
+        context: &[u8],
+        policy: &ValidityPolicy,
+    ) -> Result<(), VerificationError> {
+        policy.check(statement.epoch())?;
+        self.verify_noninteractive(statement, proof, context)
+    }
+
+    /// Signs the message with a signature of knowledge of the witness, as
+    /// `membership::Protocol::sign` does.
+    pub fn sign<R1: MutRandState, R2: RngCore + CryptoRng>(
+        &self,
+        rng1: &mut R1,
+        rng2: &mut R2,
+        statement: &Statement<G, P>,
+        witness: &Witness<G>,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<NonMembershipProof<G, P, HP>, ProofError> {
+        let mut transcript = self.transcript(context);
+        transcript.append_message(b"message", message);
+        let transcript = RefCell::new(transcript);
+        let mut verifier_channel = TranscriptVerifierChannel::new(&self.crs, &transcript);
+        self.prove(&mut verifier_channel, rng1, rng2, statement, witness)?;
+        Ok(verifier_channel.proof()?)
+    }
+
+    /// Verifies a signature created by `sign` on the message with the same
+    /// context.
+    pub fn verify_signature(
+        &self,
+        statement: &Statement<G, P>,
+        proof: &NonMembershipProof<G, P, HP>,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut transcript = self.transcript(context);
+        transcript.append_message(b"message", message);
+        let transcript = RefCell::new(transcript);
+        let mut prover_channel = TranscriptProverChannel::new(&self.crs, &transcript, proof);
+        self.verify(&mut prover_channel, statement)
     }
 
     pub fn from_crs(crs: &CRS<G, P, HP>) -> Protocol<G, P, HP> {
-        Protocol { crs: crs.clone() }
+        Protocol {
+            crs: crs.clone(),
+            hash_to_prime: HP::from_crs(&crs.crs_hash_to_prime),
+        }
     }
 }
 
+/// Receives the commitment to the element and verifies the coprime and modeq
+/// proofs, which only need the commitment bases.
+fn verify_coprime_and_modeq<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    C: NonMembershipProverChannel<G> + CoprimeProverChannel<G> + ModEqProverChannel<G, P>,
+>(
+    crs_coprime: &CRSCoprime<G>,
+    crs_modeq: &CRSModEq<G, P>,
+    prover_channel: &mut C,
+    statement: &Statement<G, P>,
+) -> Result<(), VerificationError> {
+    statement.validate()?;
+    if let Some(epoch) = &statement.epoch {
+        prover_channel.bind_epoch(epoch)?;
+    }
+    let c_e = prover_channel.receive_c_e()?;
+    let coprime = CoprimeProtocol::from_crs(crs_coprime)?;
+    coprime
+        .verify(
+            prover_channel,
+            &CoprimeStatement {
+                c_e: c_e.clone(),
+                acc: statement.c_p.clone(),
+            },
+        )
+        .map_err(|e| e.in_protocol("coprime"))?;
+    let modeq = ModEqProtocol::from_crs(crs_modeq);
+    modeq
+        .verify(
+            prover_channel,
+            &ModEqStatement {
+                c_e,
+                c_e_q: statement.c_e_q.clone(),
+            },
+        )
+        .map_err(|e| e.in_protocol("modeq"))
+}
+
 #[cfg(all(test, feature = "arkworks"))]
 mod test {
-    use super::{Protocol, Statement, Witness};
+    use super::{NonMembershipProof, Protocol, Statement, VerifierKey, Witness, CRS};
     use crate::{
         commitments::Commitment,
         parameters::Parameters,
@@ -264,6 +1222,8 @@ mod test {
     use accumulator::group::{ClassGroup, Rsa2048};
     use accumulator::{group::Group, AccumulatorWithoutHashToPrime};
     use ark_bls12_381::{Bls12_381, G1Projective};
+    use ark_bn254::Bn254;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     use merlin::Transcript;
     use rand::thread_rng;
     use rug::rand::RandState;
@@ -331,6 +1291,7 @@ mod test {
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -353,9 +1314,166 @@ mod test {
         protocol.verify(&mut prover_channel, &statement).unwrap();
     }
 
-    // panics because coprime is not supported for class groups right now
     #[test]
-    #[should_panic]
+    fn test_e2e_bn254() {
+        let (params, _) = Parameters::from_curve::<ark_bn254::Fr>().unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let protocol = Protocol::<Rsa2048, ark_bn254::G1Projective, HPProtocol<Bn254>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let acc_set = LARGE_PRIMES
+            .iter()
+            .map(|p| Integer::from(*p))
+            .collect::<Vec<_>>();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add(&acc_set);
+        let non_mem_proof = accum
+            .prove_nonmembership(&acc_set, &[value.clone()])
+            .unwrap();
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.value,
+            epoch: None,
+        };
+        let proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    d: non_mem_proof.d,
+                    b: non_mem_proof.b,
+                },
+                b"context",
+            )
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_e2e_noninteractive() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let crs = crate::protocols::nonmembership::Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPProtocol<Bls12_381>,
+        >::setup(&params, &mut rng1, &mut rng2)
+        .unwrap()
+        .crs;
+        let protocol = Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::from_crs(&crs);
+
+        let value = Integer::from(Integer::u_pow_u(
+            2,
+            (crs.parameters.hash_to_prime_bits) as u32,
+        )) - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty();
+        let acc_set = LARGE_PRIMES
+            .iter()
+            .skip(1)
+            .map(|p| Integer::from(*p))
+            .collect::<Vec<_>>();
+        let accum = accum.add(&acc_set);
+        let non_mem_proof = accum
+            .prove_nonmembership(&acc_set, &[value.clone()])
+            .unwrap();
+
+        let statement = Statement {
+            c_e_q: commitment,
+            c_p: accum.value,
+            epoch: None,
+        };
+        let proof = protocol
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &Witness {
+                    e: value,
+                    r_q: randomness,
+                    d: non_mem_proof.d,
+                    b: non_mem_proof.b,
+                },
+                b"session-1",
+            )
+            .unwrap();
+
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), proof.serialized_size());
+        let proof =
+            NonMembershipProof::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(
+                &bytes[..],
+            )
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        protocol
+            .verify_noninteractive(&statement, &proof, b"session-2")
+            .unwrap_err();
+
+        let mut crs_bytes = vec![];
+        crs.serialize(&mut crs_bytes).unwrap();
+        assert_eq!(crs_bytes.len(), crs.serialized_size());
+        let mut crs_file = vec![];
+        crs.serialize_to(&mut crs_file, false).unwrap();
+        let deserialized_crs =
+            CRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_from(&crs_file[..])
+                .unwrap();
+        Protocol::from_crs(&deserialized_crs)
+            .verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        let last = crs_file.len() - 1;
+        crs_file[last] ^= 1;
+        assert!(
+            CRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_from(&crs_file[..])
+                .is_err()
+        );
+
+        let mut vk_bytes = vec![];
+        crs.verifier_key().serialize(&mut vk_bytes).unwrap();
+        assert!(vk_bytes.len() < crs_bytes.len());
+        let vk =
+            VerifierKey::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize(&vk_bytes[..])
+                .unwrap();
+        vk.verify_noninteractive(&statement, &proof, b"session-1")
+            .unwrap();
+        vk.verify_noninteractive(&statement, &proof, b"session-2")
+            .unwrap_err();
+    }
+
+    #[test]
     fn test_e2e_prime_class_group() {
         let params = Parameters::from_security_level(128).unwrap();
         let mut rng1 = RandState::new();
@@ -409,6 +1527,7 @@ mod test {
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -492,6 +1611,7 @@ mod test {
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -534,6 +1654,7 @@ mod test {
     use rug::rand::RandState;
     use rug::Integer;
     use std::cell::RefCell;
+    use std::sync::Arc;
 
     const LARGE_PRIMES: [u64; 4] = [
         553_525_575_239_331_913,
@@ -592,11 +1713,13 @@ mod test {
         );
 
         let proof_transcript = RefCell::new(Transcript::new(b"nonmembership"));
-        crs.crs_hash_to_prime.hash_to_prime_parameters.transcript = Some(proof_transcript.clone());
+        Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
+            Some(proof_transcript.clone());
         let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
         let statement = Statement {
             c_e_q: commitment,
             c_p: acc,
+            epoch: None,
         };
         protocol
             .prove(
@@ -614,7 +1737,7 @@ mod test {
             .unwrap();
         let proof = verifier_channel.proof().unwrap();
         let verification_transcript = RefCell::new(Transcript::new(b"nonmembership"));
-        crs.crs_hash_to_prime.hash_to_prime_parameters.transcript =
+        Arc::make_mut(&mut crs.crs_hash_to_prime.hash_to_prime_parameters).transcript =
             Some(verification_transcript.clone());
         let mut prover_channel =
             TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
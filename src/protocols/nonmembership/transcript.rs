@@ -5,7 +5,7 @@ use crate::{
         coprime::{
             channel::{CoprimeProverChannel, CoprimeVerifierChannel},
             transcript::{
-                TranscriptProtocolCoprime,
+                FiatShamir, TranscriptProtocolCoprime,
                 TranscriptProverChannel as CoprimeTranscriptProverChannel,
                 TranscriptVerifierChannel as CoprimeTranscriptVerifierChannel,
             },
@@ -28,23 +28,25 @@ use crate::{
         },
         nonmembership::{
             channel::{NonMembershipProverChannel, NonMembershipVerifierChannel},
-            Proof, CRS,
+            Proof, VerifierKey, CRS,
         },
     },
-    transcript::{TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolInteger},
+    transcript::{
+        TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolEpoch,
+        TranscriptProtocolInteger,
+    },
     utils::{curve::CurvePointProjective, ConvertibleUnknownOrderGroup},
 };
-use merlin::Transcript;
+use core::cell::RefCell;
 use rug::Integer;
-use std::cell::RefCell;
 
 pub trait TranscriptProtocolNonMembership<G: ConvertibleUnknownOrderGroup>:
-    TranscriptProtocolInteger<G> + TranscriptProtocolChallenge
+    TranscriptProtocolInteger<G> + TranscriptProtocolChallenge + TranscriptProtocolEpoch
 {
     fn nonmembership_domain_sep(&mut self);
 }
 
-impl<G: ConvertibleUnknownOrderGroup> TranscriptProtocolNonMembership<G> for Transcript {
+impl<G: ConvertibleUnknownOrderGroup, T: FiatShamir> TranscriptProtocolNonMembership<G> for T {
     fn nonmembership_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"nonmembership");
     }
@@ -148,9 +150,20 @@ impl<
         self.coprime_transcript_verifier_channel
             .send_message3(message)
     }
+    fn send_message4(
+        &mut self,
+        message: &crate::protocols::coprime::Message4<G>,
+    ) -> Result<(), ChannelError> {
+        self.coprime_transcript_verifier_channel
+            .send_message4(message)
+    }
     fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
         self.coprime_transcript_verifier_channel.receive_challenge()
     }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.coprime_transcript_verifier_channel
+            .receive_poe_challenge()
+    }
 }
 
 impl<
@@ -237,10 +250,17 @@ impl<
     fn receive_message3(&mut self) -> Result<crate::protocols::coprime::Message3, ChannelError> {
         self.coprime_transcript_prover_channel.receive_message3()
     }
+    fn receive_message4(&mut self) -> Result<crate::protocols::coprime::Message4<G>, ChannelError> {
+        self.coprime_transcript_prover_channel.receive_message4()
+    }
     fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
         self.coprime_transcript_prover_channel
             .generate_and_send_challenge()
     }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        self.coprime_transcript_prover_channel
+            .generate_and_send_poe_challenge()
+    }
 }
 
 impl<
@@ -295,6 +315,13 @@ impl<
             + TranscriptProtocolHashToPrime<P>,
     > NonMembershipVerifierChannel<G> for TranscriptVerifierChannel<'a, G, P, HP, T>
 {
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.nonmembership_domain_sep();
+        transcript.append_epoch(epoch);
+        Ok(())
+    }
+
     fn send_c_e(
         &mut self,
         c_e: &<IntegerCommitment<G> as Commitment>::Instance,
@@ -318,6 +345,13 @@ impl<
             + TranscriptProtocolHashToPrime<P>,
     > NonMembershipProverChannel<G> for TranscriptProverChannel<'a, G, P, HP, T>
 {
+    fn bind_epoch(&mut self, epoch: &[u8]) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.nonmembership_domain_sep();
+        transcript.append_epoch(epoch);
+        Ok(())
+    }
+
     fn receive_c_e(
         &mut self,
     ) -> Result<<IntegerCommitment<G> as Commitment>::Instance, ChannelError> {
@@ -364,4 +398,32 @@ impl<
             proof: proof.clone(),
         }
     }
+
+    /// Like `new`, for verifiers holding only the verifier key.
+    pub fn with_verifier_key(
+        vk: &VerifierKey<G, P, HP>,
+        transcript: &'a RefCell<T>,
+        proof: &Proof<G, P, HP>,
+    ) -> TranscriptProverChannel<'a, G, P, HP, T> {
+        TranscriptProverChannel {
+            transcript,
+            coprime_transcript_prover_channel: CoprimeTranscriptProverChannel::new(
+                &vk.crs_coprime,
+                transcript,
+                &proof.proof_coprime,
+            ),
+            modeq_transcript_prover_channel: ModEqTranscriptProverChannel::new(
+                &vk.crs_modeq,
+                transcript,
+                &proof.proof_modeq,
+            ),
+            hash_to_prime_transcript_prover_channel:
+                HashToPrimeTranscriptProverChannel::with_verifier_crs(
+                    &vk.crs_hash_to_prime,
+                    transcript,
+                    &proof.proof_hash_to_prime,
+                ),
+            proof: proof.clone(),
+        }
+    }
 }
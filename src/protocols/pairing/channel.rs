@@ -0,0 +1,18 @@
+use crate::{
+    channels::ChannelError,
+    protocols::pairing::{Message1, Message2},
+};
+use ark_ec::PairingEngine;
+use rug::Integer;
+
+pub trait PairingVerifierChannel<E: PairingEngine> {
+    fn send_message1(&mut self, message: &Message1<E>) -> Result<(), ChannelError>;
+    fn send_message2(&mut self, message: &Message2<E>) -> Result<(), ChannelError>;
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
+
+pub trait PairingProverChannel<E: PairingEngine> {
+    fn receive_message1(&mut self) -> Result<Message1<E>, ChannelError>;
+    fn receive_message2(&mut self) -> Result<Message2<E>, ChannelError>;
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
@@ -0,0 +1,380 @@
+//! Implements set membership with a bilinear-map (Nguyen) accumulator.
+//!
+//! The accumulator is acc = g^{prod (s + e_i)} for a secret s, whose powers are
+//! in the CRS, and a witness w satisfies e(w, h^s * h^e) = e(acc, h). The
+//! prover randomizes the witness as w' = w^r and sends a_bar = acc^r * w'^{-e},
+//! which the verifier checks with e(w', h^s) = e(a_bar, h). The prover then
+//! shows knowledge of (e, r) in a_bar and that e is the value committed in
+//! c_e_q. Witnesses are constant-size and no group of unknown order is needed,
+//! at the cost of a trusted setup bounding the set size.
+use crate::{
+    commitments::{pedersen::PedersenCommitment, Commitment},
+    parameters::Parameters,
+    protocols::{check_point, ProofError, ValidationError, VerificationError},
+    utils::zeroize_integer,
+    utils::{
+        curve::CurvePointProjective,
+        integer_to_bigint_mod_q,
+        msm::{CpuMsm, MsmBackend},
+    },
+    utils::{Redacted, RedactedInteger},
+};
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, UniformRand, Zero};
+use channel::{PairingProverChannel, PairingVerifierChannel};
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
+
+pub mod channel;
+pub mod transcript;
+
+#[derive(Clone)]
+pub struct CRSPairing<E: PairingEngine> {
+    pub parameters: Parameters,
+    pub pedersen_commitment_parameters: PedersenCommitment<E::G1Projective>,
+    /// g^{s^i} for i from 0 to the maximal set size.
+    pub powers: Vec<E::G1Projective>,
+    pub h: E::G2Projective,
+    pub h_s: E::G2Projective,
+}
+
+pub struct Statement<E: PairingEngine> {
+    pub acc: E::G1Projective,
+    pub c_e_q: <PedersenCommitment<E::G1Projective> as Commitment>::Instance,
+}
+
+impl<E: PairingEngine> Statement<E> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_point("acc", &self.acc)?;
+        check_point("c_e_q", &self.c_e_q)
+    }
+}
+
+pub struct Witness<E: PairingEngine> {
+    pub e: Integer,
+    pub r_q: Integer,
+    pub w: E::G1Projective,
+}
+
+impl<E: PairingEngine> Zeroize for Witness<E> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r_q);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for Witness<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r_q", &RedactedInteger(&self.r_q))
+            .field("w", &Redacted)
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> Drop for Witness<E> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone)]
+pub struct Message1<E: PairingEngine> {
+    pub w_prime: E::G1Projective,
+    pub a_bar: E::G1Projective,
+    pub t_a: E::G1Projective,
+    pub t_c: E::G1Projective,
+}
+
+#[derive(Clone)]
+pub struct Message2<E: PairingEngine> {
+    pub s_e: E::Fr,
+    pub s_r: E::Fr,
+    pub s_r_q: E::Fr,
+}
+
+#[derive(Clone)]
+pub struct Proof<E: PairingEngine> {
+    pub message1: Message1<E>,
+    pub message2: Message2<E>,
+}
+
+pub struct Protocol<E: PairingEngine> {
+    pub crs: CRSPairing<E>,
+}
+
+/// Computes g^{prod (s + e_i)} from the coefficients of prod (X + e_i) and the
+/// powers g^{s^i}. Returns None if there are not enough powers. Only the
+/// group of the powers is needed, not the pairing.
+pub fn commit_product<P: ProjectiveCurve>(powers: &[P], elements: &[P::ScalarField]) -> Option<P> {
+    commit_product_with(&CpuMsm, powers, elements)
+}
+
+/// Like `commit_product`, computing the MSM over the powers with the backend.
+pub fn commit_product_with<P: ProjectiveCurve, B: MsmBackend<P>>(
+    backend: &B,
+    powers: &[P],
+    elements: &[P::ScalarField],
+) -> Option<P> {
+    if elements.len() >= powers.len() {
+        return None;
+    }
+    let mut coefficients = vec![P::ScalarField::one()];
+    for e in elements {
+        let mut next = vec![P::ScalarField::zero(); coefficients.len() + 1];
+        for (i, c) in coefficients.iter().enumerate() {
+            next[i] += *c * e;
+            next[i + 1] += c;
+        }
+        coefficients = next;
+    }
+
+    Some(backend.msm(&powers[..coefficients.len()], &coefficients))
+}
+
+impl<E: PairingEngine> Protocol<E> {
+    /// Generates the powers of a random s, which is then discarded. Whoever
+    /// runs the setup can forge membership witnesses, so it should be run by a
+    /// trusted party or replaced by a ceremony.
+    pub fn setup<R: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        max_set_size: usize,
+        rng: &mut R,
+    ) -> Protocol<E> {
+        let pedersen_commitment_parameters = PedersenCommitment::<E::G1Projective>::setup(rng);
+        let s = E::Fr::rand(rng);
+        let g = E::G1Projective::prime_subgroup_generator();
+        let h = E::G2Projective::prime_subgroup_generator();
+        let mut powers = vec![g];
+        let mut power = g;
+        for _ in 0..max_set_size {
+            power = power.mul(s.into_repr());
+            powers.push(power);
+        }
+
+        Protocol {
+            crs: CRSPairing::<E> {
+                parameters: parameters.clone(),
+                pedersen_commitment_parameters,
+                powers,
+                h,
+                h_s: h.mul(s.into_repr()),
+            },
+        }
+    }
+
+    pub fn from_crs(crs: &CRSPairing<E>) -> Protocol<E> {
+        Protocol { crs: crs.clone() }
+    }
+
+    pub fn prove<R: RngCore + CryptoRng, C: PairingVerifierChannel<E>>(
+        &self,
+        verifier_channel: &mut C,
+        rng: &mut R,
+        statement: &Statement<E>,
+        witness: &Witness<E>,
+    ) -> Result<(), ProofError> {
+        let e = integer_to_bigint_mod_q::<E::G1Projective>(&witness.e)?;
+        let r_q = integer_to_bigint_mod_q::<E::G1Projective>(&witness.r_q)?;
+        let r = E::Fr::rand(rng);
+        if r.is_zero() {
+            return Err(ProofError::CouldNotCreateProof);
+        }
+        let w_prime = witness.w.mul(r.into_repr());
+        let a_bar = statement.acc.mul(r.into_repr()) - w_prime.mul(e.into_repr());
+
+        let r_e = E::Fr::rand(rng);
+        let r_r = E::Fr::rand(rng);
+        let r_r_q = E::Fr::rand(rng);
+        let t_a = statement.acc.mul(r_r.into_repr()) - w_prime.mul(r_e.into_repr());
+        let t_c = self
+            .crs
+            .pedersen_commitment_parameters
+            .g
+            .mul(r_e.into_repr())
+            + self
+                .crs
+                .pedersen_commitment_parameters
+                .h
+                .mul(r_r_q.into_repr());
+        let message1 = Message1::<E> {
+            w_prime,
+            a_bar,
+            t_a,
+            t_c,
+        };
+        verifier_channel.send_message1(&message1)?;
+
+        let c = integer_to_bigint_mod_q::<E::G1Projective>(&verifier_channel.receive_challenge()?)?;
+        let message2 = Message2::<E> {
+            s_e: r_e + c * e,
+            s_r: r_r + c * r,
+            s_r_q: r_r_q + c * r_q,
+        };
+        verifier_channel.send_message2(&message2)?;
+
+        Ok(())
+    }
+
+    pub fn verify<C: PairingProverChannel<E>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<E>,
+    ) -> Result<(), VerificationError> {
+        statement.validate()?;
+        let message1 = prover_channel.receive_message1()?;
+        let c = integer_to_bigint_mod_q::<E::G1Projective>(
+            &prover_channel.generate_and_send_challenge()?,
+        )?;
+        let message2 = prover_channel.receive_message2()?;
+
+        if message1.w_prime.is_zero() {
+            return Err(VerificationError::PairingCheckFailed);
+        }
+        let is_witness_valid =
+            E::pairing(message1.w_prime.into_affine(), self.crs.h_s.into_affine())
+                == E::pairing(message1.a_bar.into_affine(), self.crs.h.into_affine());
+
+        let expected_t_a = <E::G1Projective as CurvePointProjective>::msm(
+            &[statement.acc, message1.w_prime, message1.a_bar],
+            &[message2.s_r, -message2.s_e, -c],
+        );
+        let pedersen = &self.crs.pedersen_commitment_parameters;
+        let expected_t_c = <E::G1Projective as CurvePointProjective>::msm(
+            &[pedersen.g, pedersen.h, statement.c_e_q],
+            &[message2.s_e, message2.s_r_q, -c],
+        );
+
+        if is_witness_valid && expected_t_a == message1.t_a && expected_t_c == message1.t_c {
+            Ok(())
+        } else {
+            Err(VerificationError::PairingCheckFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{commit_product, commit_product_with, Protocol, Statement};
+    use crate::{
+        accumulators::pairing::PairingAccumulator,
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::pairing::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+        utils::{
+            curve::CurvePointProjective,
+            msm::{CpuMsm, MsmBackend},
+        },
+    };
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+    use merlin::Transcript;
+    use rand::thread_rng;
+    use rug::Integer;
+    use std::cell::Cell;
+    use std::cell::RefCell;
+
+    const LARGE_PRIMES: [u64; 4] = [
+        553_525_575_239_331_913,
+        12_702_637_924_034_044_211,
+        378_373_571_372_703_133,
+        8_640_171_141_336_142_787,
+    ];
+
+    #[test]
+    fn test_proof() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng = thread_rng();
+
+        let crs = Protocol::<Bls12_381>::setup(&params, 8, &mut rng).crs;
+        let protocol = Protocol::<Bls12_381>::from_crs(&crs);
+
+        let mut accum = PairingAccumulator::<Bls12_381>::new(&crs);
+        for p in LARGE_PRIMES.iter() {
+            accum.add(&Integer::from(*p)).unwrap();
+        }
+        let value = Integer::from(LARGE_PRIMES[2]);
+        let randomness = Integer::from(5);
+        let commitment = protocol
+            .crs
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+
+        let statement = Statement::<Bls12_381> {
+            acc: accum.value,
+            c_e_q: commitment,
+        };
+        let witness = accum.membership_witness(&value, &randomness).unwrap();
+
+        let proof_transcript = RefCell::new(Transcript::new(b"pairing"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        protocol
+            .prove(&mut verifier_channel, &mut rng, &statement, &witness)
+            .unwrap();
+        let proof = verifier_channel.proof().unwrap();
+
+        let verification_transcript = RefCell::new(Transcript::new(b"pairing"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        accum.delete(&value).unwrap();
+        let statement_after_deletion = Statement::<Bls12_381> {
+            acc: accum.value,
+            c_e_q: commitment,
+        };
+        let verification_transcript = RefCell::new(Transcript::new(b"pairing"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        assert!(protocol
+            .verify(&mut prover_channel, &statement_after_deletion)
+            .is_err());
+    }
+
+    struct CountingMsm(Cell<usize>);
+
+    impl MsmBackend<G1Projective> for CountingMsm {
+        fn msm(&self, bases: &[G1Projective], scalars: &[Fr]) -> G1Projective {
+            self.0.set(self.0.get() + 1);
+            CpuMsm.msm(bases, scalars)
+        }
+    }
+
+    #[test]
+    fn test_msm_backend() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng = thread_rng();
+        let crs = Protocol::<Bls12_381>::setup(&params, 4, &mut rng).crs;
+        let elements = [Fr::from(3u64), Fr::from(5u64)];
+
+        let backend = CountingMsm(Cell::new(0));
+        let value = commit_product_with(&backend, &crs.powers, &elements).unwrap();
+        assert_eq!(backend.0.get(), 1);
+        assert_eq!(Some(value), commit_product(&crs.powers, &elements));
+        // (s + 3)(s + 5) = s^2 + 8s + 15
+        let expected = CurvePointProjective::msm(
+            &crs.powers[..3],
+            &[Fr::from(15u64), Fr::from(8u64), Fr::from(1u64)],
+        );
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_commit_product_without_pairing() {
+        use ark_ec::ProjectiveCurve;
+        use ark_ed_on_bls12_381::{EdwardsProjective as Jubjub, Fr as JubjubFr};
+        use ark_ff::PrimeField;
+
+        let s = JubjubFr::from(7u64);
+        let g = Jubjub::prime_subgroup_generator();
+        let powers = [g, g.mul(s.into_repr()), g.mul((s * s).into_repr())];
+        let value = commit_product(&powers, &[JubjubFr::from(3u64), JubjubFr::from(5u64)]).unwrap();
+        assert_eq!(value, g.mul(JubjubFr::from(10u64 * 12).into_repr()));
+        assert!(
+            commit_product(&powers[..2], &[JubjubFr::from(3u64), JubjubFr::from(5u64)]).is_none()
+        );
+    }
+}
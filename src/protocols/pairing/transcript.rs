@@ -0,0 +1,127 @@
+use crate::{
+    channels::ChannelError,
+    protocols::pairing::{
+        channel::{PairingProverChannel, PairingVerifierChannel},
+        CRSPairing, Message1, Message2, Proof,
+    },
+    transcript::{
+        FiatShamir, TranscriptChannelError, TranscriptProtocolChallenge, TranscriptProtocolCurve,
+    },
+};
+use ark_ec::PairingEngine;
+use core::cell::RefCell;
+use rug::Integer;
+
+pub trait TranscriptProtocolPairing<E: PairingEngine>:
+    TranscriptProtocolCurve<E::G1Projective> + TranscriptProtocolChallenge
+{
+    fn pairing_domain_sep(&mut self);
+}
+
+impl<E: PairingEngine, T: FiatShamir> TranscriptProtocolPairing<E> for T {
+    fn pairing_domain_sep(&mut self) {
+        self.append_message(b"dom-sep", b"pairing");
+    }
+}
+
+fn append_message1<E: PairingEngine, T: TranscriptProtocolPairing<E>>(
+    transcript: &mut T,
+    message: &Message1<E>,
+) -> Result<(), ChannelError> {
+    transcript.pairing_domain_sep();
+    transcript.append_curve_point(b"w_prime", &message.w_prime)?;
+    transcript.append_curve_point(b"a_bar", &message.a_bar)?;
+    transcript.append_curve_point(b"t_a", &message.t_a)?;
+    transcript.append_curve_point(b"t_c", &message.t_c)?;
+    Ok(())
+}
+
+pub struct TranscriptVerifierChannel<'a, E: PairingEngine, T: TranscriptProtocolPairing<E>> {
+    crs: CRSPairing<E>,
+    transcript: &'a RefCell<T>,
+    message1: Option<Message1<E>>,
+    message2: Option<Message2<E>>,
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolPairing<E>> TranscriptVerifierChannel<'a, E, T> {
+    pub fn new(
+        crs: &CRSPairing<E>,
+        transcript: &'a RefCell<T>,
+    ) -> TranscriptVerifierChannel<'a, E, T> {
+        TranscriptVerifierChannel {
+            crs: crs.clone(),
+            transcript,
+            message1: None,
+            message2: None,
+        }
+    }
+
+    pub fn proof(&self) -> Result<Proof<E>, TranscriptChannelError> {
+        if self.message1.is_some() && self.message2.is_some() {
+            Ok(Proof {
+                message1: self.message1.as_ref().unwrap().clone(),
+                message2: self.message2.as_ref().unwrap().clone(),
+            })
+        } else {
+            Err(TranscriptChannelError::Incomplete)
+        }
+    }
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolPairing<E>> PairingVerifierChannel<E>
+    for TranscriptVerifierChannel<'a, E, T>
+{
+    fn send_message1(&mut self, message: &Message1<E>) -> Result<(), ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        append_message1::<E, T>(&mut *transcript, message)?;
+        self.message1 = Some(message.clone());
+        Ok(())
+    }
+    fn send_message2(&mut self, message: &Message2<E>) -> Result<(), ChannelError> {
+        self.message2 = Some(message.clone());
+        Ok(())
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.pairing_domain_sep();
+        Ok(transcript.challenge_scalar(b"c", self.crs.parameters.security_soundness))
+    }
+}
+
+pub struct TranscriptProverChannel<'a, E: PairingEngine, T: TranscriptProtocolPairing<E>> {
+    crs: CRSPairing<E>,
+    transcript: &'a RefCell<T>,
+    proof: Proof<E>,
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolPairing<E>> TranscriptProverChannel<'a, E, T> {
+    pub fn new(
+        crs: &CRSPairing<E>,
+        transcript: &'a RefCell<T>,
+        proof: &Proof<E>,
+    ) -> TranscriptProverChannel<'a, E, T> {
+        TranscriptProverChannel {
+            crs: crs.clone(),
+            transcript,
+            proof: proof.clone(),
+        }
+    }
+}
+
+impl<'a, E: PairingEngine, T: TranscriptProtocolPairing<E>> PairingProverChannel<E>
+    for TranscriptProverChannel<'a, E, T>
+{
+    fn receive_message1(&mut self) -> Result<Message1<E>, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        append_message1::<E, T>(&mut *transcript, &self.proof.message1)?;
+        Ok(self.proof.message1.clone())
+    }
+    fn receive_message2(&mut self) -> Result<Message2<E>, ChannelError> {
+        Ok(self.proof.message2.clone())
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        let mut transcript = self.transcript.try_borrow_mut()?;
+        transcript.pairing_domain_sep();
+        Ok(transcript.challenge_scalar(b"c", self.crs.parameters.security_soundness))
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     channels::ChannelError,
-    protocols::root::{Message1, Message2, Message3},
+    protocols::root::{Message1, Message2, Message3, Message4},
     utils::ConvertibleUnknownOrderGroup,
 };
 use rug::Integer;
@@ -9,12 +9,85 @@ pub trait RootVerifierChannel<G: ConvertibleUnknownOrderGroup> {
     fn send_message1(&mut self, message: &Message1<G>) -> Result<(), ChannelError>;
     fn send_message2(&mut self, message: &Message2<G>) -> Result<(), ChannelError>;
     fn send_message3(&mut self, message: &Message3) -> Result<(), ChannelError>;
+    fn send_message4(&mut self, message: &Message4<G>) -> Result<(), ChannelError>;
     fn receive_challenge(&mut self) -> Result<Integer, ChannelError>;
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError>;
 }
 
 pub trait RootProverChannel<G: ConvertibleUnknownOrderGroup> {
     fn receive_message1(&mut self) -> Result<Message1<G>, ChannelError>;
     fn receive_message2(&mut self) -> Result<Message2<G>, ChannelError>;
     fn receive_message3(&mut self) -> Result<Message3, ChannelError>;
+    fn receive_message4(&mut self) -> Result<Message4<G>, ChannelError>;
     fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
+
+/// Lends a channel, e.g., to a wrapper such as `InstrumentedChannel`.
+impl<G: ConvertibleUnknownOrderGroup, C: RootVerifierChannel<G> + ?Sized> RootVerifierChannel<G>
+    for &mut C
+{
+    fn send_message1(&mut self, message: &Message1<G>) -> Result<(), ChannelError> {
+        (**self).send_message1(message)
+    }
+    fn send_message2(&mut self, message: &Message2<G>) -> Result<(), ChannelError> {
+        (**self).send_message2(message)
+    }
+    fn send_message3(&mut self, message: &Message3) -> Result<(), ChannelError> {
+        (**self).send_message3(message)
+    }
+    fn send_message4(&mut self, message: &Message4<G>) -> Result<(), ChannelError> {
+        (**self).send_message4(message)
+    }
+    fn receive_challenge(&mut self) -> Result<Integer, ChannelError> {
+        (**self).receive_challenge()
+    }
+    fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        (**self).receive_poe_challenge()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, C: RootProverChannel<G> + ?Sized> RootProverChannel<G>
+    for &mut C
+{
+    fn receive_message1(&mut self) -> Result<Message1<G>, ChannelError> {
+        (**self).receive_message1()
+    }
+    fn receive_message2(&mut self) -> Result<Message2<G>, ChannelError> {
+        (**self).receive_message2()
+    }
+    fn receive_message3(&mut self) -> Result<Message3, ChannelError> {
+        (**self).receive_message3()
+    }
+    fn receive_message4(&mut self) -> Result<Message4<G>, ChannelError> {
+        (**self).receive_message4()
+    }
+    fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError> {
+        (**self).generate_and_send_challenge()
+    }
+    fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError> {
+        (**self).generate_and_send_poe_challenge()
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncRootVerifierChannel<G: ConvertibleUnknownOrderGroup> {
+    async fn send_message1(&mut self, message: &Message1<G>) -> Result<(), ChannelError>;
+    async fn send_message2(&mut self, message: &Message2<G>) -> Result<(), ChannelError>;
+    async fn send_message3(&mut self, message: &Message3) -> Result<(), ChannelError>;
+    async fn send_message4(&mut self, message: &Message4<G>) -> Result<(), ChannelError>;
+    async fn receive_challenge(&mut self) -> Result<Integer, ChannelError>;
+    async fn receive_poe_challenge(&mut self) -> Result<Integer, ChannelError>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait(?Send)]
+pub trait AsyncRootProverChannel<G: ConvertibleUnknownOrderGroup> {
+    async fn receive_message1(&mut self) -> Result<Message1<G>, ChannelError>;
+    async fn receive_message2(&mut self) -> Result<Message2<G>, ChannelError>;
+    async fn receive_message3(&mut self) -> Result<Message3, ChannelError>;
+    async fn receive_message4(&mut self) -> Result<Message4<G>, ChannelError>;
+    async fn generate_and_send_challenge(&mut self) -> Result<Integer, ChannelError>;
+    async fn generate_and_send_poe_challenge(&mut self) -> Result<Integer, ChannelError>;
 }
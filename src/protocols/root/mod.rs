@@ -1,13 +1,37 @@
 //! Implements root, to be used in the membership protocol.
+//!
+//! The large exponentiations of the verifier are replaced by a Wesolowski proof
+//! of exponentiation (PoE): for each check the prover sends Q = u^floor(s/l) for
+//! a prime challenge l, and the verifier only exponentiates by l and s mod l.
 use crate::{
-    commitments::{integer::IntegerCommitment, Commitment},
+    commitments::{
+        integer::{IntegerCommitment, IntegerCommitmentTables},
+        Commitment, CommitmentError,
+    },
     parameters::Parameters,
-    protocols::{ProofError, VerificationError},
-    utils::{random_symmetric_range, ConvertibleUnknownOrderGroup},
+    protocols::{
+        batch::GroupEquation, check_challenge, check_elem, check_masking, check_response,
+        response_bound, s_e_bound, ProofError, ValidationError, VerificationError,
+    },
+    utils::rng::fork_rng,
+    utils::zeroize_integer,
+    utils::{
+        group::ElemFromBytes,
+        join, poe_split, random_symmetric_range,
+        serialization::{
+            elem_size, integer_size, read_elem, read_integer, write_elem, write_integer,
+        },
+        ConvertibleUnknownOrderGroup,
+    },
+    utils::{Redacted, RedactedInteger},
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use channel::{RootProverChannel, RootVerifierChannel};
+use rand::{CryptoRng, RngCore};
 use rug::rand::MutRandState;
 use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
 
 pub mod channel;
 pub mod transcript;
@@ -17,18 +41,49 @@ pub struct CRSRoot<G: ConvertibleUnknownOrderGroup> {
     // G contains the information about Z^*_N
     pub parameters: Parameters,
     pub integer_commitment_parameters: IntegerCommitment<G>, // G, H
+    pub fixed_base_tables: Option<IntegerCommitmentTables<G>>,
 }
 pub struct Statement<G: ConvertibleUnknownOrderGroup> {
     pub c_e: <IntegerCommitment<G> as Commitment>::Instance,
     pub acc: G::Elem,
 }
 
+impl<G: ConvertibleUnknownOrderGroup> Statement<G> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_elem::<G>("c_e", &self.c_e)?;
+        check_elem::<G>("acc", &self.acc)
+    }
+}
+
 pub struct Witness<G: ConvertibleUnknownOrderGroup> {
     pub e: Integer,
     pub r: Integer,
     pub w: G::Elem,
 }
 
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for Witness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.e);
+        zeroize_integer(&mut self.r);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for Witness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("e", &RedactedInteger(&self.e))
+            .field("r", &RedactedInteger(&self.r))
+            .field("w", &Redacted)
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for Witness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone)]
 pub struct Message1<G: ConvertibleUnknownOrderGroup> {
     pub c_w: G::Elem,
@@ -53,11 +108,147 @@ pub struct Message3 {
     pub s_delta: Integer,
 }
 
+/// PoE quotients for the checks of alpha1, alpha2, alpha3 and alpha4.
+#[derive(Clone)]
+pub struct Message4<G: ConvertibleUnknownOrderGroup> {
+    pub q1: G::Elem,
+    pub q2: G::Elem,
+    pub q3: G::Elem,
+    pub q4: G::Elem,
+}
+
 #[derive(Clone)]
 pub struct Proof<G: ConvertibleUnknownOrderGroup> {
     pub message1: Message1<G>,
     pub message2: Message2<G>,
     pub message3: Message3,
+    pub message4: Message4<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Proof<G> {
+    fn elems(&self) -> [&G::Elem; 10] {
+        [
+            &self.message1.c_w,
+            &self.message1.c_r,
+            &self.message2.alpha1,
+            &self.message2.alpha2,
+            &self.message2.alpha3,
+            &self.message2.alpha4,
+            &self.message4.q1,
+            &self.message4.q2,
+            &self.message4.q3,
+            &self.message4.q4,
+        ]
+    }
+
+    fn integers(&self) -> [&Integer; 6] {
+        [
+            &self.message3.s_e,
+            &self.message3.s_r,
+            &self.message3.s_r_2,
+            &self.message3.s_r_3,
+            &self.message3.s_beta,
+            &self.message3.s_delta,
+        ]
+    }
+
+    /// Checks that the responses are within the ranges an honest prover's
+    /// are, to reject a malformed proof before verifying it.
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), ValidationError> {
+        check_response("s_e", &self.message3.s_e, &s_e_bound(parameters))?;
+        let bound = response_bound::<G>(parameters);
+        for (field, response) in [
+            ("s_r", &self.message3.s_r),
+            ("s_r_2", &self.message3.s_r_2),
+            ("s_r_3", &self.message3.s_r_3),
+            ("s_beta", &self.message3.s_beta),
+            ("s_delta", &self.message3.s_delta),
+        ]
+        .iter()
+        {
+            check_response(field, response, &bound)?;
+        }
+        Ok(())
+    }
+}
+
+/// The group elements of the messages 1, 2 and 4 are written first, followed
+/// by the responses of message 3.
+///
+/// There are no curve points, so the uncompressed encoding is the same.
+impl<G: ConvertibleUnknownOrderGroup> CanonicalSerialize for Proof<G> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        for elem in self.elems().iter() {
+            write_elem::<G, _>(&mut writer, elem)?;
+        }
+        for integer in self.integers().iter() {
+            write_integer(&mut writer, integer)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        let elems: usize = self.elems().iter().map(|e| elem_size::<G>(e)).sum();
+        let integers: usize = self.integers().iter().map(|i| integer_size(i)).sum();
+        elems + integers
+    }
+}
+
+impl<G: ElemFromBytes> CanonicalDeserialize for Proof<G> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let message1 = Message1 {
+            c_w: read_elem::<G, _>(&mut reader)?,
+            c_r: read_elem::<G, _>(&mut reader)?,
+        };
+        let message2 = Message2 {
+            alpha1: read_elem::<G, _>(&mut reader)?,
+            alpha2: read_elem::<G, _>(&mut reader)?,
+            alpha3: read_elem::<G, _>(&mut reader)?,
+            alpha4: read_elem::<G, _>(&mut reader)?,
+        };
+        let message4 = Message4 {
+            q1: read_elem::<G, _>(&mut reader)?,
+            q2: read_elem::<G, _>(&mut reader)?,
+            q3: read_elem::<G, _>(&mut reader)?,
+            q4: read_elem::<G, _>(&mut reader)?,
+        };
+        let message3 = Message3 {
+            s_e: read_integer(&mut reader)?,
+            s_r: read_integer(&mut reader)?,
+            s_r_2: read_integer(&mut reader)?,
+            s_r_3: read_integer(&mut reader)?,
+            s_beta: read_integer(&mut reader)?,
+            s_delta: read_integer(&mut reader)?,
+        };
+        Ok(Proof {
+            message1,
+            message2,
+            message3,
+            message4,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<G: ConvertibleUnknownOrderGroup> serde::Serialize for Proof<G> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::utils::serialization::serialize_canonical(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, G: ElemFromBytes> serde::Deserialize<'de> for Proof<G> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::utils::serialization::deserialize_canonical(deserializer)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> CRSRoot<G> {
+    /// The size of the integer commitment bases.
+    pub(crate) fn size(&self) -> usize {
+        elem_size::<G>(&self.integer_commitment_parameters.g)
+            + elem_size::<G>(&self.integer_commitment_parameters.h)
+    }
 }
 
 pub struct Protocol<G: ConvertibleUnknownOrderGroup> {
@@ -69,20 +260,66 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
         Protocol { crs: crs.clone() }
     }
 
-    pub fn prove<R: MutRandState, C: RootVerifierChannel<G>>(
+    fn exp_h(&self, n: &Integer) -> G::Elem {
+        match &self.crs.fixed_base_tables {
+            Some(tables) => tables.h.exp(n),
+            None => G::exp(&self.crs.integer_commitment_parameters.h, n),
+        }
+    }
+
+    fn commit(&self, value: &Integer, randomness: &Integer) -> Result<G::Elem, CommitmentError> {
+        match &self.crs.fixed_base_tables {
+            Some(tables) => Ok(tables.commit(value, randomness)),
+            None => self
+                .crs
+                .integer_commitment_parameters
+                .commit(value, randomness),
+        }
+    }
+
+    fn exp_h_secret<R: RngCore + CryptoRng>(
         &self,
-        verifier_channel: &mut C,
+        n: &Integer,
+        rng: &mut R,
+    ) -> Result<G::Elem, CommitmentError> {
+        match &self.crs.fixed_base_tables {
+            Some(tables) => tables.h.exp_secret(n, rng),
+            None => G::exp_secret(&self.crs.integer_commitment_parameters.h, n, rng),
+        }
+        .ok_or(CommitmentError::NonInvertibleBase)
+    }
+
+    fn commit_secret<R: RngCore + CryptoRng>(
+        &self,
+        value: &Integer,
+        randomness: &Integer,
         rng: &mut R,
+    ) -> Result<G::Elem, CommitmentError> {
+        match &self.crs.fixed_base_tables {
+            Some(tables) => tables.commit_secret(value, randomness, rng),
+            None => self
+                .crs
+                .integer_commitment_parameters
+                .commit_secret(value, randomness, rng),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "root::prove", level = "debug", skip_all)
+    )]
+    pub fn prove<R1: MutRandState, R2: RngCore + CryptoRng, C: RootVerifierChannel<G>>(
+        &self,
+        verifier_channel: &mut C,
+        rng1: &mut R1,
+        rng2: &mut R2,
         _: &Statement<G>,
         witness: &Witness<G>,
     ) -> Result<(), ProofError> {
-        let r_2 = random_symmetric_range(rng, &(G::order_upper_bound() / Integer::from(2)));
-        let r_3 = random_symmetric_range(rng, &(G::order_upper_bound() / Integer::from(2)));
-        let c_w = G::op(
-            &witness.w,
-            &G::exp(&self.crs.integer_commitment_parameters.h, &r_2),
-        );
-        let c_r = self.crs.integer_commitment_parameters.commit(&r_2, &r_3)?;
+        let mut r_2 = random_symmetric_range(rng1, &(G::order_upper_bound() / Integer::from(2)));
+        let mut r_3 = random_symmetric_range(rng1, &(G::order_upper_bound() / Integer::from(2)));
+        let c_w = G::op(&witness.w, &self.exp_h_secret(&r_2, rng2)?);
+        let c_r = self.commit_secret(&r_2, &r_3, rng2)?;
 
         let message1 = Message1::<G> { c_w, c_r };
         verifier_channel.send_message1(&message1)?;
@@ -93,16 +330,18 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
                 + self.crs.parameters.security_soundness
                 + self.crs.parameters.hash_to_prime_bits) as u32,
         ));
-        let r_e = random_symmetric_range(rng, &r_e_range);
+        check_masking(&self.crs.parameters, "e", &r_e_range, &witness.e)?;
+        let r_e = random_symmetric_range(rng1, &r_e_range);
 
         let r_r_range: Integer = G::order_upper_bound() / 2
             * Integer::from(Integer::u_pow_u(
                 2,
                 (self.crs.parameters.security_zk + self.crs.parameters.security_soundness) as u32,
             ));
-        let r_r = random_symmetric_range(rng, &r_r_range);
-        let r_r_2 = random_symmetric_range(rng, &r_r_range);
-        let r_r_3 = random_symmetric_range(rng, &r_r_range);
+        check_masking(&self.crs.parameters, "r", &r_r_range, &witness.r)?;
+        let r_r = random_symmetric_range(rng1, &r_r_range);
+        let r_r_2 = random_symmetric_range(rng1, &r_r_range);
+        let r_r_3 = random_symmetric_range(rng1, &r_r_range);
 
         let r_beta_delta_range: Integer = G::order_upper_bound() / 2
             * Integer::from(Integer::u_pow_u(
@@ -111,27 +350,51 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
                     + self.crs.parameters.security_soundness
                     + self.crs.parameters.hash_to_prime_bits) as u32,
             ));
-        let r_beta = random_symmetric_range(rng, &r_beta_delta_range);
-        let r_delta = random_symmetric_range(rng, &r_beta_delta_range);
+        let r_beta = random_symmetric_range(rng1, &r_beta_delta_range);
+        let r_delta = random_symmetric_range(rng1, &r_beta_delta_range);
 
-        let alpha1 = self.crs.integer_commitment_parameters.commit(&r_e, &r_r)?;
-        let alpha2 = self
-            .crs
-            .integer_commitment_parameters
-            .commit(&r_r_2, &r_r_3)?;
-        let integer_commitment_alpha3 = IntegerCommitment::<G>::new(
-            &message1.c_w,
-            &G::inv(&self.crs.integer_commitment_parameters.h),
+        // The bases h^{-1} and g^{-1} are used through negated exponents. The
+        // exponentiations are independent, so they run side by side with the
+        // `parallel` feature. The exponents are all secret, unlike the ones of
+        // message4, so the fixed-base tables don't apply. Each branch draws
+        // from its own generator, forked from rng2.
+        let (mut rng_alpha1, mut rng_alpha2, mut rng_alpha3, mut rng_alpha4) = (
+            fork_rng(rng2),
+            fork_rng(rng2),
+            fork_rng(rng2),
+            fork_rng(rng2),
         );
-        let alpha3 = integer_commitment_alpha3.commit(&r_e, &r_beta)?;
-        let integer_commitment_alpha4 = IntegerCommitment::<G>::new(
-            &G::inv(&self.crs.integer_commitment_parameters.h),
-            &G::inv(&self.crs.integer_commitment_parameters.g),
-        );
-        let alpha4 = G::op(
-            &G::exp(&message1.c_r, &r_e),
-            &integer_commitment_alpha4.commit(&r_delta, &r_beta)?,
+        let ((alpha1, alpha2), (alpha3, alpha4)) = join(
+            || {
+                join(
+                    || self.commit_secret(&r_e, &r_r, &mut rng_alpha1),
+                    || self.commit_secret(&r_r_2, &r_r_3, &mut rng_alpha2),
+                )
+            },
+            || {
+                join(
+                    || -> Result<_, CommitmentError> {
+                        Ok(G::op(
+                            &G::exp_secret(&message1.c_w, &r_e, &mut rng_alpha3)
+                                .ok_or(CommitmentError::NonInvertibleBase)?,
+                            &self.exp_h_secret(&Integer::from(-&r_beta), &mut rng_alpha3)?,
+                        ))
+                    },
+                    || -> Result<_, CommitmentError> {
+                        Ok(G::op(
+                            &G::exp_secret(&message1.c_r, &r_e, &mut rng_alpha4)
+                                .ok_or(CommitmentError::NonInvertibleBase)?,
+                            &self.commit_secret(
+                                &Integer::from(-&r_beta),
+                                &Integer::from(-&r_delta),
+                                &mut rng_alpha4,
+                            )?,
+                        ))
+                    },
+                )
+            },
         );
+        let (alpha1, alpha2, alpha3, alpha4) = (alpha1?, alpha2?, alpha3?, alpha4?);
         let message2 = Message2::<G> {
             alpha1,
             alpha2,
@@ -141,12 +404,20 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
         verifier_channel.send_message2(&message2)?;
 
         let c = verifier_channel.receive_challenge()?;
-        let s_e = r_e - c.clone() * witness.e.clone();
-        let s_r = r_r - c.clone() * witness.r.clone();
-        let s_r_2 = r_r_2 - c.clone() * r_2.clone();
-        let s_r_3 = r_r_3 - c.clone() * r_3.clone();
-        let s_beta = r_beta - c.clone() * witness.e.clone() * r_2;
-        let s_delta = r_delta - c * witness.e.clone() * r_3;
+        check_challenge(&self.crs.parameters, &c)?;
+        // The responses are computed in place of the nonces, without clones of
+        // the secrets, so that only the values below are left to zeroize.
+        let s_e = r_e - &c * &witness.e;
+        let s_r = r_r - &c * &witness.r;
+        let s_r_2 = r_r_2 - &c * &r_2;
+        let s_r_3 = r_r_3 - &c * &r_3;
+        let mut e_r_2 = Integer::from(&witness.e * &r_2);
+        let mut e_r_3 = Integer::from(&witness.e * &r_3);
+        let s_beta = r_beta - &c * &e_r_2;
+        let s_delta = r_delta - &c * &e_r_3;
+        for secret in [&mut r_2, &mut r_3, &mut e_r_2, &mut e_r_3].iter_mut() {
+            zeroize_integer(secret);
+        }
         let message3 = Message3 {
             s_e,
             s_r,
@@ -157,71 +428,142 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
         };
         verifier_channel.send_message3(&message3)?;
 
+        let l = verifier_channel.receive_poe_challenge()?.next_prime();
+        let (q_r, _) = poe_split(&message3.s_r, &l);
+        let (q_r_2, _) = poe_split(&message3.s_r_2, &l);
+        let (q_r_3, _) = poe_split(&message3.s_r_3, &l);
+        let (q_beta, _) = poe_split(&message3.s_beta, &l);
+        let (q_delta, _) = poe_split(&message3.s_delta, &l);
+        let ((q1, q2), (q3, q4)) = join(
+            || join(|| self.exp_h(&q_r), || self.commit(&q_r_2, &q_r_3)),
+            || {
+                join(
+                    || self.exp_h(&Integer::from(-&q_beta)),
+                    || self.commit(&Integer::from(-&q_beta), &Integer::from(-&q_delta)),
+                )
+            },
+        );
+        let message4 = Message4::<G> {
+            q1,
+            q2: q2?,
+            q3,
+            q4: q4?,
+        };
+        verifier_channel.send_message4(&message4)?;
+
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "root::verify", level = "debug", skip_all)
+    )]
     pub fn verify<C: RootProverChannel<G>>(
         &self,
         prover_channel: &mut C,
         statement: &Statement<G>,
     ) -> Result<(), VerificationError> {
+        let equations = self.equations(prover_channel, statement)?;
+        let tables = self.crs.fixed_base_tables.as_ref();
+        if equations.iter().all(|equation| equation.holds(tables)) {
+            Ok(())
+        } else {
+            Err(VerificationError::RootCheckFailed)
+        }
+    }
+
+    /// Like `verify`, but only collects the group equations, to check them
+    /// with those of other proofs.
+    pub fn verify_deferred<C: RootProverChannel<G>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G>,
+        equations: &mut Vec<GroupEquation<G>>,
+    ) -> Result<(), VerificationError> {
+        equations.extend(self.equations(prover_channel, statement)?);
+        Ok(())
+    }
+
+    /// Receives the proof and checks the range of s_e, returning the
+    /// equations left to check.
+    fn equations<C: RootProverChannel<G>>(
+        &self,
+        prover_channel: &mut C,
+        statement: &Statement<G>,
+    ) -> Result<Vec<GroupEquation<G>>, VerificationError> {
+        statement.validate()?;
         let message1 = prover_channel.receive_message1()?;
         let message2 = prover_channel.receive_message2()?;
         let c = prover_channel.generate_and_send_challenge()?;
+        check_challenge(&self.crs.parameters, &c)?;
         let message3 = prover_channel.receive_message3()?;
-        let expected_alpha1 = G::op(
-            &G::exp(&statement.c_e, &c),
-            &self
-                .crs
-                .integer_commitment_parameters
-                .commit(&message3.s_e, &message3.s_r)?,
-        );
-        let expected_alpha2 = G::op(
-            &G::exp(&message1.c_r, &c),
-            &self
-                .crs
-                .integer_commitment_parameters
-                .commit(&message3.s_r_2, &message3.s_r_3)?,
-        );
-        let integer_commitment_alpha3 = IntegerCommitment::<G>::new(
-            &message1.c_w,
-            &G::inv(&self.crs.integer_commitment_parameters.h),
-        );
-        let expected_alpha3 = G::op(
-            &G::exp(&statement.acc, &c),
-            &integer_commitment_alpha3.commit(&message3.s_e, &message3.s_beta)?,
-        );
-        let integer_commitment_alpha4 = IntegerCommitment::<G>::new(
-            &G::inv(&self.crs.integer_commitment_parameters.h),
-            &G::inv(&self.crs.integer_commitment_parameters.g),
-        );
-        let expected_alpha4 = G::op(
-            &G::exp(&message1.c_r, &message3.s_e),
-            &integer_commitment_alpha4.commit(&message3.s_delta, &message3.s_beta)?,
-        );
-
-        let s_e_expected_right = Integer::from(Integer::u_pow_u(
-            2,
-            (self.crs.parameters.security_zk
-                + self.crs.parameters.security_soundness
-                + self.crs.parameters.hash_to_prime_bits
-                + 1) as u32,
-        ));
-
-        let s_e_expected_left: Integer = -s_e_expected_right.clone();
-        let is_s_e_in_range =
-            message3.s_e >= s_e_expected_left && message3.s_e <= s_e_expected_right;
-
-        if expected_alpha1 == message2.alpha1
-            && expected_alpha2 == message2.alpha2
-            && expected_alpha3 == message2.alpha3
-            && expected_alpha4 == message2.alpha4
-            && is_s_e_in_range
+        let l = prover_channel
+            .generate_and_send_poe_challenge()?
+            .next_prime();
+        let message4 = prover_channel.receive_message4()?;
+        for (field, elem) in [
+            ("c_w", &message1.c_w),
+            ("c_r", &message1.c_r),
+            ("alpha1", &message2.alpha1),
+            ("alpha2", &message2.alpha2),
+            ("alpha3", &message2.alpha3),
+            ("alpha4", &message2.alpha4),
+            ("q1", &message4.q1),
+            ("q2", &message4.q2),
+            ("q3", &message4.q3),
+            ("q4", &message4.q4),
+        ]
+        .iter()
         {
-            Ok(())
-        } else {
-            Err(VerificationError::VerificationFailed)
+            check_elem::<G>(field, elem)?;
         }
+        check_response("s_e", &message3.s_e, &s_e_bound(&self.crs.parameters))?;
+        let (_, r_r) = poe_split(&message3.s_r, &l);
+        let (_, r_r_2) = poe_split(&message3.s_r_2, &l);
+        let (_, r_r_3) = poe_split(&message3.s_r_3, &l);
+        let (_, r_beta) = poe_split(&message3.s_beta, &l);
+        let (_, r_delta) = poe_split(&message3.s_delta, &l);
+
+        let g = &self.crs.integer_commitment_parameters.g;
+        let h = &self.crs.integer_commitment_parameters.h;
+        Ok(vec![
+            GroupEquation {
+                terms: vec![
+                    (statement.c_e.clone(), c.clone()),
+                    (message4.q1, l.clone()),
+                    (g.clone(), message3.s_e.clone()),
+                    (h.clone(), r_r),
+                ],
+                target: message2.alpha1,
+            },
+            GroupEquation {
+                terms: vec![
+                    (message1.c_r.clone(), c.clone()),
+                    (message4.q2, l.clone()),
+                    (g.clone(), r_r_2),
+                    (h.clone(), r_r_3),
+                ],
+                target: message2.alpha2,
+            },
+            GroupEquation {
+                terms: vec![
+                    (statement.acc.clone(), c),
+                    (message4.q3, l.clone()),
+                    (message1.c_w, message3.s_e.clone()),
+                    (h.clone(), Integer::from(-&r_beta)),
+                ],
+                target: message2.alpha3,
+            },
+            GroupEquation {
+                terms: vec![
+                    (message1.c_r, message3.s_e),
+                    (message4.q4, l),
+                    (g.clone(), Integer::from(-&r_beta)),
+                    (h.clone(), Integer::from(-&r_delta)),
+                ],
+                target: message2.alpha4,
+            },
+        ])
     }
 }
 
@@ -234,6 +576,7 @@ mod test {
         protocols::{
             hash_to_prime::snark_range::Protocol as HPProtocol,
             root::transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+            ProofError, SecurityError, ValidationError, VerificationError,
         },
     };
     use accumulator::{
@@ -261,14 +604,14 @@ mod test {
         rng1.seed(&Integer::from(13));
         let mut rng2 = thread_rng();
 
-        let crs = crate::protocols::membership::Protocol::<
+        let mut membership_crs = crate::protocols::membership::Protocol::<
             Rsa2048,
             G1Projective,
             HPProtocol<Bls12_381>,
         >::setup(&params, &mut rng1, &mut rng2)
         .unwrap()
-        .crs
-        .crs_root;
+        .crs;
+        let crs = membership_crs.crs_root.clone();
         let protocol = Protocol::<Rsa2048>::from_crs(&crs);
 
         let value = Integer::from(LARGE_PRIMES[0]);
@@ -304,6 +647,7 @@ mod test {
             .prove(
                 &mut verifier_channel,
                 &mut rng1,
+                &mut rng2,
                 &statement,
                 &Witness {
                     e: value,
@@ -318,5 +662,46 @@ mod test {
         let mut prover_channel =
             TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
         protocol.verify(&mut prover_channel, &statement).unwrap();
+
+        membership_crs.precompute_fixed_base_tables();
+        let crs_with_tables = membership_crs.crs_root;
+        let protocol_with_tables = Protocol::<Rsa2048>::from_crs(&crs_with_tables);
+        let verification_transcript = RefCell::new(Transcript::new(b"root"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs_with_tables, &verification_transcript, &proof);
+        protocol_with_tables
+            .verify(&mut prover_channel, &statement)
+            .unwrap();
+
+        let identity_statement = Statement {
+            c_e: statement.c_e.clone(),
+            acc: Rsa2048::id(),
+        };
+        let verification_transcript = RefCell::new(Transcript::new(b"root"));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&crs, &verification_transcript, &proof);
+        match protocol.verify(&mut prover_channel, &identity_statement) {
+            Err(VerificationError::ValidationError(ValidationError::IdentityElement("acc"))) => {}
+            _ => panic!("the identity accumulator should be rejected"),
+        }
+
+        // An element too large for the masking of its responses is refused.
+        let large_value = Integer::from(LARGE_PRIMES[0]) << params.hash_to_prime_bits as u32;
+        let proof_transcript = RefCell::new(Transcript::new(b"root"));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&crs, &proof_transcript);
+        match protocol.prove(
+            &mut verifier_channel,
+            &mut rng1,
+            &mut rng2,
+            &statement,
+            &Witness {
+                e: large_value,
+                r: Integer::from(5),
+                w: Rsa2048::id(),
+            },
+        ) {
+            Err(ProofError::SecurityError(SecurityError::MaskingTooSmall("e", _))) => {}
+            _ => panic!("the masking of e should be too small"),
+        }
     }
 }
@@ -0,0 +1,425 @@
+//! A CRS shared by the membership and non-membership protocols when they're
+//! instantiated over the same groups and hash-to-prime protocol, so that setup
+//! runs once and the hash-to-prime parameters, the largest part of the CRS,
+//! are stored once and shared by the CRSs built from it.
+//!
+//! Setup can also be split: `CRSBases::setup` generates the commitment bases,
+//! which is cheap, and the hash-to-prime parameters are generated or loaded
+//! later. Verifiers only need the verifying key with the bases, so they never
+//! hold the proving key.
+use crate::{
+    commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment},
+    parameters::Parameters,
+    protocols::{
+        check_discriminant,
+        hash_to_prime::{CRSHashToPrime, CRSSize, HashToPrimeProtocol},
+        membership, nonmembership, CRSError, SetupError,
+    },
+    utils::{
+        curve::CurvePointProjective,
+        group::ElemFromBytes,
+        rng::seeded_rngs,
+        serialization::{
+            elem_size, read_checked, read_elem, read_point, write_checked, write_elem, write_point,
+        },
+        ConvertibleUnknownOrderGroup,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use std::sync::Arc;
+
+/// The commitment bases of a CRS, before its hash-to-prime parameters.
+pub struct CRSBases<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    pub parameters: Parameters,
+    pub integer_commitment_parameters: IntegerCommitment<G>,
+    pub pedersen_commitment_parameters: PedersenCommitment<P>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Clone for CRSBases<G, P> {
+    fn clone(&self) -> Self {
+        Self {
+            parameters: self.parameters.clone(),
+            integer_commitment_parameters: self.integer_commitment_parameters.clone(),
+            pedersen_commitment_parameters: self.pedersen_commitment_parameters.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> CRSBases<G, P> {
+    pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> CRSBases<G, P> {
+        CRSBases {
+            parameters: parameters.clone(),
+            integer_commitment_parameters: IntegerCommitment::<G>::setup(rng1),
+            pedersen_commitment_parameters: PedersenCommitment::<P>::setup(rng2),
+        }
+    }
+
+    /// Generates the hash-to-prime parameters for the Pedersen bases, e.g.,
+    /// the SNARK's proving key, completing the CRS.
+    pub fn setup_hash_to_prime<HP: HashToPrimeProtocol<P>, R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+    ) -> Result<SharedCRS<G, P, HP>, SetupError> {
+        let hash_to_prime_parameters = Arc::new(HP::setup(
+            rng,
+            &self.pedersen_commitment_parameters,
+            &self.parameters,
+        )?);
+        Ok(self.with_hash_to_prime_parameters(hash_to_prime_parameters))
+    }
+
+    /// Completes the CRS with hash-to-prime parameters generated for these
+    /// bases, e.g., read from a file. `SharedCRS::validate` checks that they
+    /// match.
+    pub fn with_hash_to_prime_parameters<HP: HashToPrimeProtocol<P>>(
+        self,
+        hash_to_prime_parameters: Arc<HP::Parameters>,
+    ) -> SharedCRS<G, P, HP> {
+        SharedCRS {
+            parameters: self.parameters.clone(),
+            integer_commitment_parameters: self.integer_commitment_parameters,
+            crs_hash_to_prime: CRSHashToPrime {
+                parameters: self.parameters,
+                pedersen_commitment_parameters: self.pedersen_commitment_parameters,
+                hash_to_prime_parameters,
+            },
+        }
+    }
+}
+
+pub struct SharedCRS<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub parameters: Parameters,
+    pub integer_commitment_parameters: IntegerCommitment<G>,
+    /// Holds the Pedersen commitment bases and the hash-to-prime parameters.
+    pub crs_hash_to_prime: CRSHashToPrime<P, HP>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> Clone
+    for SharedCRS<G, P, HP>
+{
+    fn clone(&self) -> Self {
+        Self {
+            parameters: self.parameters.clone(),
+            integer_commitment_parameters: self.integer_commitment_parameters.clone(),
+            crs_hash_to_prime: self.crs_hash_to_prime.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    SharedCRS<G, P, HP>
+{
+    pub fn setup<R1: MutRandState, R2: RngCore + CryptoRng>(
+        parameters: &Parameters,
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<SharedCRS<G, P, HP>, SetupError> {
+        if check_discriminant::<G>(parameters).is_err() {
+            return Err(SetupError::CouldNotPerformSetup);
+        }
+        CRSBases::setup(parameters, rng1, rng2).setup_hash_to_prime(rng2)
+    }
+
+    /// Like `setup`, deriving all the randomness from the seed, as the
+    /// protocols' `setup_deterministic` does.
+    pub fn setup_deterministic(
+        parameters: &Parameters,
+        seed: [u8; 32],
+    ) -> Result<SharedCRS<G, P, HP>, SetupError> {
+        let (mut rng1, mut rng2) = seeded_rngs(seed);
+        Self::setup(parameters, &mut rng1, &mut rng2)
+    }
+
+    /// The membership CRS, with the default transcript label. It points to
+    /// the same hash-to-prime parameters.
+    pub fn membership_crs(&self) -> membership::CRS<G, P, HP> {
+        membership::CRS::from_shared(self)
+    }
+
+    pub fn nonmembership_crs(&self) -> nonmembership::CRS<G, P, HP> {
+        nonmembership::CRS::from_shared(self)
+    }
+
+    pub fn membership(&self) -> membership::Protocol<G, P, HP> {
+        membership::Protocol::from_crs(&self.membership_crs())
+    }
+
+    pub fn nonmembership(&self) -> nonmembership::Protocol<G, P, HP> {
+        nonmembership::Protocol::from_crs(&self.nonmembership_crs())
+    }
+
+    /// Checks the CRS as `membership::CRS::validate` does, which covers all
+    /// of its elements.
+    pub fn validate<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(), CRSError> {
+        self.membership_crs().validate(rng)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    SharedCRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    fn write<W: Write>(&self, writer: &mut W, compressed: bool) -> Result<(), SerializationError> {
+        self.parameters.serialize(&mut *writer)?;
+        write_elem::<G, _>(writer, &self.integer_commitment_parameters.g)?;
+        write_elem::<G, _>(writer, &self.integer_commitment_parameters.h)?;
+        let pedersen = &self.crs_hash_to_prime.pedersen_commitment_parameters;
+        write_point(writer, &pedersen.g, compressed)?;
+        write_point(writer, &pedersen.h, compressed)?;
+        let hash_to_prime_parameters = &self.crs_hash_to_prime.hash_to_prime_parameters;
+        if compressed {
+            hash_to_prime_parameters.serialize(writer)
+        } else {
+            hash_to_prime_parameters.serialize_uncompressed(writer)
+        }
+    }
+
+    fn size(&self, compressed: bool) -> usize {
+        Parameters::SERIALIZED_SIZE
+            + elem_size::<G>(&self.integer_commitment_parameters.g)
+            + elem_size::<G>(&self.integer_commitment_parameters.h)
+            + self.crs_hash_to_prime.size(compressed)
+    }
+
+    /// Writes the CRS followed by a checksum, as `membership::CRS::serialize_to`
+    /// does.
+    pub fn serialize_to<W: Write>(
+        &self,
+        writer: W,
+        compressed: bool,
+    ) -> Result<(), SerializationError> {
+        write_checked(writer, self, compressed)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> SharedCRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    fn read<R: Read>(reader: &mut R, compressed: bool) -> Result<Self, SerializationError> {
+        let parameters = Parameters::deserialize(&mut *reader)?;
+        let integer_commitment_parameters =
+            IntegerCommitment::<G>::new(&read_elem::<G, _>(reader)?, &read_elem::<G, _>(reader)?);
+        let pedersen_commitment_parameters = PedersenCommitment::<P>::new(
+            &read_point(reader, compressed)?,
+            &read_point(reader, compressed)?,
+        );
+        let hash_to_prime_parameters = Arc::new(if compressed {
+            HP::Parameters::deserialize(reader)?
+        } else {
+            HP::Parameters::deserialize_uncompressed(reader)?
+        });
+        Ok(SharedCRS {
+            parameters: parameters.clone(),
+            integer_commitment_parameters,
+            crs_hash_to_prime: CRSHashToPrime {
+                parameters,
+                pedersen_commitment_parameters,
+                hash_to_prime_parameters,
+            },
+        })
+    }
+
+    /// Reads a CRS written by `serialize_to`, rejecting it if the checksum
+    /// doesn't match.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_checked(reader)
+    }
+}
+
+/// Written as the parameters, the integer and Pedersen commitment bases and
+/// the hash-to-prime parameters, as the CRSs of the protocols are, without a
+/// transcript label.
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    CanonicalSerialize for SharedCRS<G, P, HP>
+where
+    HP::Parameters: CanonicalSerialize + CRSSize,
+{
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.size(true)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.write(&mut writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.size(false)
+    }
+}
+
+impl<G: ElemFromBytes, P: CurvePointProjective, HP: HashToPrimeProtocol<P>> CanonicalDeserialize
+    for SharedCRS<G, P, HP>
+where
+    HP::Parameters: CanonicalDeserialize,
+{
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Self::read(&mut reader, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CRSBases, SharedCRS};
+    use crate::{
+        commitments::Commitment,
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::{snark_range::Protocol as HPProtocol, HashToPrimeProtocol},
+            membership, nonmembership,
+        },
+    };
+    use accumulator::group::Rsa2048;
+    use accumulator::AccumulatorWithoutHashToPrime;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shared_crs() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let shared = SharedCRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup(
+            &params, &mut rng1, &mut rng2,
+        )
+        .unwrap();
+        shared.validate(&mut rng2).unwrap();
+        let membership = shared.membership();
+        let nonmembership = shared.nonmembership();
+        assert!(Arc::ptr_eq(
+            &membership.crs.crs_hash_to_prime.hash_to_prime_parameters,
+            &nonmembership.crs.crs_hash_to_prime.hash_to_prime_parameters,
+        ));
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = shared
+            .crs_hash_to_prime
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = membership::Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let proof = membership
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &membership::Witness {
+                    e: value,
+                    r_q: randomness,
+                    w: accum.1.witness.0.value,
+                },
+                b"context",
+            )
+            .unwrap();
+        shared
+            .membership()
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+
+        let mut bytes = vec![];
+        shared.serialize_to(&mut bytes, true).unwrap();
+        let read =
+            SharedCRS::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::deserialize_from(&bytes[..])
+                .unwrap();
+        assert_eq!(
+            read.integer_commitment_parameters.g,
+            shared.integer_commitment_parameters.g
+        );
+        read.validate(&mut rng2).unwrap();
+        read.membership()
+            .verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_lazy_setup() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let mut rng1 = RandState::new();
+        rng1.seed(&Integer::from(13));
+        let mut rng2 = thread_rng();
+
+        let bases = CRSBases::<Rsa2048, G1Projective>::setup(&params, &mut rng1, &mut rng2);
+        let shared = bases
+            .clone()
+            .setup_hash_to_prime::<HPProtocol<Bls12_381>, _>(&mut rng2)
+            .unwrap();
+        let hash_to_prime_parameters = &shared.crs_hash_to_prime.hash_to_prime_parameters;
+        let loaded = bases
+            .clone()
+            .with_hash_to_prime_parameters::<HPProtocol<Bls12_381>>(
+                hash_to_prime_parameters.clone(),
+            );
+        loaded.validate(&mut rng2).unwrap();
+        // The verifier only receives the verifying key.
+        let vk = membership::VerifierKey::<_, _, HPProtocol<Bls12_381>>::from_bases(
+            &bases,
+            HPProtocol::<Bls12_381>::verifying_key(hash_to_prime_parameters),
+        );
+        assert_eq!(vk.fingerprint(), loaded.membership_crs().fingerprint());
+
+        let value = Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32))
+            - &Integer::from(245);
+        let randomness = Integer::from(5);
+        let commitment = bases
+            .pedersen_commitment_parameters
+            .commit(&value, &randomness)
+            .unwrap();
+        let accum =
+            accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+                .add_with_proof(&[value.clone()]);
+        let statement = membership::Statement {
+            c_e_q: commitment,
+            c_p: accum.0.value,
+            epoch: None,
+        };
+        let proof = loaded
+            .membership()
+            .prove_noninteractive(
+                &mut rng1,
+                &mut rng2,
+                &statement,
+                &membership::Witness {
+                    e: value,
+                    r_q: randomness,
+                    w: accum.1.witness.0.value,
+                },
+                b"context",
+            )
+            .unwrap();
+        vk.verify_noninteractive(&statement, &proof, b"context")
+            .unwrap();
+    }
+}
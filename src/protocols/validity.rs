@@ -0,0 +1,185 @@
+//! Validity metadata for the membership and non-membership statements: the
+//! epoch of the accumulator the proof is for and an optional expiry time.
+//!
+//! The metadata is carried as the statement's epoch bytes, so it's absorbed
+//! into the transcript and a proof can't be moved to other metadata. The
+//! verifiers check it against their `ValidityPolicy` before the proof, so a
+//! proof made before a revocation epoch rolled over, or after its expiry,
+//! is rejected even though its accumulator and equations still check out:
+//!
+//! ```ignore
+//! let statement = Statement::new(acc, c_e_q)?.with_validity(&Validity::at_epoch(7).expiring_at(now + 60));
+//! let policy = ValidityPolicy::default().at_epoch(7).at_current_time();
+//! protocol.verify_with_policy(&statement, &proof, b"context", &policy)?;
+//! ```
+use std::convert::TryInto;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ValidityError {
+    #[error("the statement carries no validity metadata")]
+    Missing,
+    #[error("the validity metadata is malformed")]
+    Malformed,
+    #[error("the proof is for epoch {got} instead of {expected}")]
+    EpochMismatch { expected: u64, got: u64 },
+    #[error("the proof expired at {expires_at}, it is {now}")]
+    Expired { expires_at: u64, now: u64 },
+    #[error("the proof has no expiry time")]
+    NoExpiry,
+}
+
+const VERSION: u8 = 1;
+
+/// The epoch a statement is valid for, and the time, in seconds since the
+/// Unix epoch, it expires at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Validity {
+    pub epoch: u64,
+    pub expires_at: Option<u64>,
+}
+
+impl Validity {
+    pub fn at_epoch(epoch: u64) -> Self {
+        Validity {
+            epoch,
+            expires_at: None,
+        }
+    }
+
+    pub fn expiring_at(mut self, expires_at: u64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// A version byte, the epoch and, if there is one, the expiry time, as
+    /// big-endian u64s.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![VERSION];
+        bytes.extend_from_slice(&self.epoch.to_be_bytes());
+        if let Some(expires_at) = self.expires_at {
+            bytes.extend_from_slice(&expires_at.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ValidityError> {
+        let read_u64 = |bytes: &[u8]| u64::from_be_bytes(bytes.try_into().unwrap());
+        match bytes {
+            [VERSION, epoch @ ..] if epoch.len() == 8 => Ok(Validity::at_epoch(read_u64(epoch))),
+            [VERSION, rest @ ..] if rest.len() == 16 => {
+                Ok(Validity::at_epoch(read_u64(&rest[..8])).expiring_at(read_u64(&rest[8..])))
+            }
+            _ => Err(ValidityError::Malformed),
+        }
+    }
+}
+
+/// What a verifier accepts: the current epoch, if proofs must be for it,
+/// and the current time, if they mustn't have expired.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValidityPolicy {
+    pub epoch: Option<u64>,
+    pub now: Option<u64>,
+    pub require_expiry: bool,
+}
+
+impl ValidityPolicy {
+    pub fn at_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    pub fn at_time(mut self, now: u64) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Checks expiry against the system clock.
+    pub fn at_current_time(self) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        self.at_time(now)
+    }
+
+    /// Rejects the proofs that never expire.
+    pub fn requiring_expiry(mut self) -> Self {
+        self.require_expiry = true;
+        self
+    }
+
+    /// Checks the epoch bytes of a statement. A policy with no constraints
+    /// accepts any statement, with or without metadata.
+    pub fn check(&self, epoch: Option<&[u8]>) -> Result<(), ValidityError> {
+        if self.epoch.is_none() && self.now.is_none() && !self.require_expiry {
+            return Ok(());
+        }
+        let validity = Validity::from_bytes(epoch.ok_or(ValidityError::Missing)?)?;
+        if let Some(expected) = self.epoch {
+            if validity.epoch != expected {
+                return Err(ValidityError::EpochMismatch {
+                    expected,
+                    got: validity.epoch,
+                });
+            }
+        }
+        match (validity.expires_at, self.now) {
+            (Some(expires_at), Some(now)) if now >= expires_at => {
+                Err(ValidityError::Expired { expires_at, now })
+            }
+            (None, _) if self.require_expiry => Err(ValidityError::NoExpiry),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Validity, ValidityError, ValidityPolicy};
+
+    #[test]
+    fn test_validity_policy() {
+        let validity = Validity::at_epoch(7).expiring_at(100);
+        assert_eq!(
+            Validity::from_bytes(&validity.to_bytes()).unwrap(),
+            validity
+        );
+        assert_eq!(
+            Validity::from_bytes(&Validity::at_epoch(7).to_bytes()).unwrap(),
+            Validity::at_epoch(7)
+        );
+        assert!(Validity::from_bytes(b"epoch 7").is_err());
+
+        let bytes = validity.to_bytes();
+        ValidityPolicy::default().check(None).unwrap();
+        ValidityPolicy::default()
+            .at_epoch(7)
+            .at_time(99)
+            .requiring_expiry()
+            .check(Some(&bytes))
+            .unwrap();
+        assert!(matches!(
+            ValidityPolicy::default().at_epoch(8).check(Some(&bytes)),
+            Err(ValidityError::EpochMismatch {
+                expected: 8,
+                got: 7
+            })
+        ));
+        assert!(matches!(
+            ValidityPolicy::default().at_time(100).check(Some(&bytes)),
+            Err(ValidityError::Expired { .. })
+        ));
+        assert!(matches!(
+            ValidityPolicy::default().at_epoch(7).check(None),
+            Err(ValidityError::Missing)
+        ));
+        assert!(matches!(
+            ValidityPolicy::default()
+                .requiring_expiry()
+                .check(Some(&Validity::at_epoch(7).to_bytes())),
+            Err(ValidityError::NoExpiry)
+        ));
+    }
+}
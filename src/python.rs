@@ -0,0 +1,224 @@
+//! Python bindings for the membership protocol over the RSA-2048 group and
+//! BLS12-381 with the LegoGroth16 range proof, built as the `cpsnarks_set`
+//! extension module, e.g., with `maturin develop --features python`.
+//!
+//! Integers are passed as Python ints, and accumulator values, commitments and
+//! proofs as bytes in the encodings used by the rest of the crate, so proofs
+//! created in Python can be verified in Rust and vice versa.
+use crate::{
+    accumulators::universal::UniversalAccumulator,
+    commitments::Commitment,
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::snark_range::Protocol as HPProtocol,
+        membership::{MembershipProof, Protocol, Statement},
+    },
+    utils::{curve::CurvePointProjective, group::ElemFromBytes, rng::secure_rngs},
+};
+use accumulator::group::Rsa2048;
+use ark_bls12_381::{Bls12_381, G1Projective};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyLong},
+};
+use rand::rngs::OsRng;
+use rug::Integer;
+use std::fmt::Debug;
+
+type MembershipProtocol = Protocol<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+type Proof = MembershipProof<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+
+fn value_error<E: Debug>(err: E) -> PyErr {
+    PyValueError::new_err(format!("{:?}", err))
+}
+
+fn to_integer(value: &PyAny) -> PyResult<Integer> {
+    if !value.is_instance::<PyLong>()? {
+        return Err(PyValueError::new_err("expected an int"));
+    }
+    Integer::from_str_radix(value.str()?.to_str()?, 10).map_err(value_error)
+}
+
+fn to_integers(values: Vec<&PyAny>) -> PyResult<Vec<Integer>> {
+    values.into_iter().map(to_integer).collect()
+}
+
+#[pyclass(name = "Parameters")]
+#[derive(Clone)]
+pub struct PyParameters {
+    inner: Parameters,
+}
+
+#[pymethods]
+impl PyParameters {
+    #[new]
+    fn new(security_level: u16) -> PyResult<Self> {
+        Ok(PyParameters {
+            inner: Parameters::from_security_level(security_level).map_err(value_error)?,
+        })
+    }
+
+    #[getter]
+    fn security_level(&self) -> u16 {
+        self.inner.security_level
+    }
+
+    #[getter]
+    fn security_zk(&self) -> u16 {
+        self.inner.security_zk
+    }
+
+    #[getter]
+    fn security_soundness(&self) -> u16 {
+        self.inner.security_soundness
+    }
+
+    #[getter]
+    fn hash_to_prime_bits(&self) -> u16 {
+        self.inner.hash_to_prime_bits
+    }
+
+    #[getter]
+    fn field_size_bits(&self) -> u16 {
+        self.inner.field_size_bits
+    }
+
+    fn __repr__(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+/// The accumulator manager's view of the set, tracking the witnesses of the
+/// elements that will be proven.
+#[pyclass(name = "Accumulator")]
+pub struct PyAccumulator {
+    inner: UniversalAccumulator<Rsa2048>,
+}
+
+#[pymethods]
+impl PyAccumulator {
+    #[new]
+    fn new() -> Self {
+        PyAccumulator {
+            inner: UniversalAccumulator::empty(),
+        }
+    }
+
+    fn add(&mut self, elements: Vec<&PyAny>) -> PyResult<()> {
+        self.inner.add(&to_integers(elements)?).map_err(value_error)
+    }
+
+    fn delete(&mut self, elements: Vec<&PyAny>) -> PyResult<()> {
+        self.inner
+            .delete(&to_integers(elements)?)
+            .map_err(value_error)
+    }
+
+    fn track(&mut self, element: &PyAny) -> PyResult<()> {
+        self.inner.track(&to_integer(element)?).map_err(value_error)
+    }
+
+    /// The accumulator value, as bytes.
+    fn value<'p>(&self, py: Python<'p>) -> &'p PyBytes {
+        PyBytes::new(py, &Rsa2048::elem_to_bytes(&self.inner.value))
+    }
+}
+
+#[pyclass(name = "Membership")]
+pub struct PyMembership {
+    protocol: MembershipProtocol,
+}
+
+#[pymethods]
+impl PyMembership {
+    /// Runs the setup with randomness from the OS.
+    #[staticmethod]
+    fn setup(parameters: &PyParameters) -> PyResult<Self> {
+        let (mut rng1, mut rng2) = secure_rngs(&mut OsRng);
+        let protocol = MembershipProtocol::setup(&parameters.inner, &mut rng1, &mut rng2)
+            .map_err(value_error)?;
+        Ok(PyMembership { protocol })
+    }
+
+    /// Commits to the element with the Pedersen commitment of the CRS.
+    fn commit<'p>(
+        &self,
+        py: Python<'p>,
+        element: &PyAny,
+        randomness: &PyAny,
+    ) -> PyResult<&'p PyBytes> {
+        let commitment = self
+            .protocol
+            .crs
+            .crs_modeq
+            .pedersen_commitment_parameters
+            .commit(&to_integer(element)?, &to_integer(randomness)?)
+            .map_err(value_error)?;
+        Ok(PyBytes::new(
+            py,
+            &commitment.to_affine_bytes().map_err(value_error)?,
+        ))
+    }
+
+    /// Proves that the committed element, tracked by the accumulator, is in
+    /// the accumulated set, and returns the serialized proof.
+    fn prove<'p>(
+        &self,
+        py: Python<'p>,
+        accumulator: &PyAccumulator,
+        element: &PyAny,
+        randomness: &PyAny,
+        commitment: &[u8],
+        context: &[u8],
+    ) -> PyResult<&'p PyBytes> {
+        let witness = accumulator
+            .inner
+            .membership_witness(&to_integer(element)?, &to_integer(randomness)?)
+            .map_err(value_error)?;
+        let statement = Statement {
+            c_p: accumulator.inner.value.clone(),
+            c_e_q: G1Projective::from_affine_bytes(commitment).map_err(value_error)?,
+            epoch: None,
+        };
+        let (mut rng1, mut rng2) = secure_rngs(&mut OsRng);
+        let proof = self
+            .protocol
+            .prove_noninteractive(&mut rng1, &mut rng2, &statement, &witness, context)
+            .map_err(value_error)?;
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes).map_err(value_error)?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Verifies a serialized proof for the accumulator value and commitment,
+    /// raising a ValueError for malformed inputs.
+    fn verify(
+        &self,
+        accumulator_value: &[u8],
+        commitment: &[u8],
+        proof: &[u8],
+        context: &[u8],
+    ) -> PyResult<bool> {
+        let statement = Statement {
+            c_p: Rsa2048::elem_from_bytes(accumulator_value)
+                .ok_or_else(|| PyValueError::new_err("invalid accumulator value"))?,
+            c_e_q: G1Projective::from_affine_bytes(commitment).map_err(value_error)?,
+            epoch: None,
+        };
+        let proof = Proof::deserialize(proof).map_err(value_error)?;
+        Ok(self
+            .protocol
+            .verify_noninteractive(&statement, &proof, context)
+            .is_ok())
+    }
+}
+
+#[pymodule]
+fn cpsnarks_set(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyParameters>()?;
+    m.add_class::<PyAccumulator>()?;
+    m.add_class::<PyMembership>()?;
+    Ok(())
+}
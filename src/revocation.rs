@@ -0,0 +1,407 @@
+//! A revocation registry on the non-membership protocol, in the style of
+//! the revocation accumulators of AnonCreds. The authority accumulates the
+//! revoked serials, one epoch per batch of revocations, and a holder proves
+//! that the serial committed to in its credential isn't revoked at the
+//! current epoch:
+//!
+//! ```ignore
+//! let mut registry = RevocationRegistry::new(&crs);
+//! let mut witness = registry.witness(&serial)?;
+//! registry.subscribe(|update| publish(update));
+//!
+//! let update = registry.revoke(&[other_serial])?;
+//! witness.apply(&update)?;
+//! let proof = witness.prove(&protocol, registry.accumulator(), b"login", &mut rng1, &mut rng2)?;
+//! proof.verify(&verifier_key, registry.accumulator(), registry.epoch(), b"login")?;
+//! ```
+//!
+//! The proofs are bound to their epoch, so a proof made before a revocation
+//! is rejected afterwards. Holders refresh their witnesses from the published
+//! `RevocationUpdate`s, without revealing their serials to the authority
+//! again.
+use crate::{
+    accumulators::{product, universal::UniversalAccumulator, AccumulatorError},
+    commitments::{Commitment, CommitmentError},
+    protocols::{
+        hash_to_prime::{HashToPrimeError, HashToPrimeProtocol},
+        nonmembership::{NonMembershipProof, Protocol, Statement, VerifierKey, Witness, CRS},
+        validity::{Validity, ValidityPolicy},
+        ProofError, ValidationError, VerificationError,
+    },
+    utils::{
+        bigint_to_integer,
+        curve::{CurvePointProjective, Field},
+        zeroize_integer, ConvertibleUnknownOrderGroup, Redacted, RedactedInteger,
+    },
+};
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::fmt;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+#[derive(Debug, Error)]
+pub enum RevocationError {
+    #[error("could not update the accumulator")]
+    AccumulatorError(#[from] AccumulatorError),
+    #[error("could not hash the serial to a prime")]
+    HashToPrimeError(#[from] HashToPrimeError),
+    #[error("could not commit to the serial")]
+    CommitmentError(#[from] CommitmentError),
+    #[error("the statement or witness is malformed")]
+    ValidationError(#[from] ValidationError),
+    #[error("could not prove non-revocation")]
+    ProofError(#[from] ProofError),
+    #[error("the non-revocation proof is invalid")]
+    VerificationError(#[from] VerificationError),
+    #[error("expected the update of epoch {0}, got {1}")]
+    EpochMismatch(u64, u64),
+    #[error("the proof is for another epoch or accumulator")]
+    StaleEpoch,
+    #[error("the serial is revoked")]
+    Revoked,
+}
+
+/// Binds the proofs to their context, apart from other non-membership proofs
+/// created with the same context.
+fn proof_context(context: &[u8]) -> Vec<u8> {
+    let mut bytes = b"cpsnarks-set non-revocation/".to_vec();
+    bytes.extend_from_slice(context);
+    bytes
+}
+
+/// The revocations of an epoch, published for the holders to update their
+/// witnesses.
+#[derive(Clone, Debug)]
+pub struct RevocationUpdate<G: ConvertibleUnknownOrderGroup> {
+    /// The epoch the update starts.
+    pub epoch: u64,
+    /// The primes of the serials revoked in the epoch.
+    pub revoked: Vec<Integer>,
+    /// The accumulator at the epoch.
+    pub accumulator: G::Elem,
+}
+
+/// The authority's registry of revoked serials.
+pub struct RevocationRegistry<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    protocol: Protocol<G, P, HP>,
+    accumulator: UniversalAccumulator<G>,
+    updates: Vec<RevocationUpdate<G>>,
+    hooks: Vec<Box<dyn FnMut(&RevocationUpdate<G>)>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    RevocationRegistry<G, P, HP>
+{
+    /// A registry at epoch 0, with no revoked serials.
+    pub fn new(crs: &CRS<G, P, HP>) -> Self {
+        RevocationRegistry {
+            protocol: Protocol::from_crs(crs),
+            accumulator: UniversalAccumulator::empty(),
+            updates: vec![],
+            hooks: vec![],
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.updates.len() as u64
+    }
+
+    pub fn accumulator(&self) -> &G::Elem {
+        &self.accumulator.value
+    }
+
+    /// The updates after the epoch, for a holder to catch up.
+    pub fn updates_since(&self, epoch: u64) -> &[RevocationUpdate<G>] {
+        &self.updates[(epoch as usize).min(self.updates.len())..]
+    }
+
+    /// Calls the hook with every update, e.g., to publish it.
+    pub fn subscribe<F: FnMut(&RevocationUpdate<G>) + 'static>(&mut self, hook: F) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Revokes the serials, starting a new epoch, and returns its update
+    /// after passing it to the hooks.
+    pub fn revoke(&mut self, serials: &[Integer]) -> Result<RevocationUpdate<G>, RevocationError> {
+        let revoked = serials
+            .iter()
+            .map(|serial| Ok(self.protocol.hash_to_prime(serial)?.0))
+            .collect::<Result<Vec<_>, RevocationError>>()?;
+        self.accumulator.add(&revoked)?;
+        let update = RevocationUpdate {
+            epoch: self.epoch() + 1,
+            revoked,
+            accumulator: self.accumulator.value.clone(),
+        };
+        self.updates.push(update.clone());
+        for hook in self.hooks.iter_mut() {
+            hook(&update);
+        }
+        Ok(update)
+    }
+
+    /// The non-revocation witness of a serial at the current epoch, given to
+    /// the holder at issuance.
+    pub fn witness(
+        &mut self,
+        serial: &Integer,
+    ) -> Result<NonRevocationWitness<G>, RevocationError> {
+        let (prime, _) = self.protocol.hash_to_prime(serial)?;
+        if self.accumulator.contains(&prime) {
+            return Err(RevocationError::Revoked);
+        }
+        self.accumulator.track(&prime)?;
+        let witness = self
+            .accumulator
+            .nonmembership_witness(&prime, &Integer::from(0));
+        self.accumulator.untrack(&prime);
+        let witness = witness?;
+        Ok(NonRevocationWitness {
+            serial: serial.clone(),
+            prime,
+            d: witness.d().clone(),
+            b: witness.b().clone(),
+            epoch: self.epoch(),
+        })
+    }
+}
+
+/// A holder's witness that its serial isn't revoked at an epoch: the Bezout
+/// coefficients `d = g^a` and `b` with `a e + b x = 1`, for the prime `e` of
+/// the serial and the product `x` of the revoked primes.
+pub struct NonRevocationWitness<G: ConvertibleUnknownOrderGroup> {
+    serial: Integer,
+    prime: Integer,
+    d: G::Elem,
+    b: Integer,
+    epoch: u64,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> NonRevocationWitness<G> {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Moves the witness to the epoch of the update, which has to be the
+    /// next one. For the product `y` of the newly revoked primes and
+    /// `u e + v y = 1`, the new coefficients are `b v` and `g^u d^{v y}`,
+    /// with `b v` then reduced modulo `e`.
+    pub fn apply(&mut self, update: &RevocationUpdate<G>) -> Result<(), RevocationError> {
+        if update.epoch != self.epoch + 1 {
+            return Err(RevocationError::EpochMismatch(self.epoch + 1, update.epoch));
+        }
+        let y = product(&update.revoked);
+        let (gcd, u, v) = self.prime.clone().gcd_cofactors(y.clone(), Integer::new());
+        if gcd != 1 {
+            return Err(RevocationError::Revoked);
+        }
+        let b = Integer::from(&self.b * &v);
+        let (k, b) = b.div_rem_euc(self.prime.clone());
+        let d = G::op(
+            &G::op(
+                &G::exp(&G::unknown_order_elem(), &u),
+                &G::exp(&self.d, &Integer::from(&v * &y)),
+            ),
+            &G::exp(&update.accumulator, &k),
+        );
+        self.d = d;
+        self.b = b;
+        self.epoch = update.epoch;
+        Ok(())
+    }
+
+    /// Proves that the serial isn't revoked at the witness's epoch, in the
+    /// accumulator of that epoch, committing to the serial with fresh
+    /// randomness and binding the proof to the context.
+    pub fn prove<
+        P: CurvePointProjective,
+        HP: HashToPrimeProtocol<P>,
+        R1: MutRandState,
+        R2: RngCore + CryptoRng,
+    >(
+        &self,
+        protocol: &Protocol<G, P, HP>,
+        accumulator: &G::Elem,
+        context: &[u8],
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<NonRevocationProof<G, P, HP>, RevocationError> {
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        let randomness = bigint_to_integer::<P>(&P::ScalarField::rand(rng2));
+        let statement = Statement::new(
+            accumulator.clone(),
+            pedersen.commit(&self.serial, &randomness)?,
+        )?
+        .with_validity(&Validity::at_epoch(self.epoch));
+        let witness = Witness::new(
+            self.serial.clone(),
+            randomness,
+            self.d.clone(),
+            self.b.clone(),
+        )?;
+        let proof = protocol.prove_noninteractive(
+            rng1,
+            rng2,
+            &statement,
+            &witness,
+            &proof_context(context),
+        )?;
+        Ok(NonRevocationProof { statement, proof })
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Zeroize for NonRevocationWitness<G> {
+    fn zeroize(&mut self) {
+        zeroize_integer(&mut self.serial);
+        zeroize_integer(&mut self.prime);
+        zeroize_integer(&mut self.b);
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> fmt::Debug for NonRevocationWitness<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NonRevocationWitness")
+            .field("serial", &RedactedInteger(&self.serial))
+            .field("prime", &RedactedInteger(&self.prime))
+            .field("d", &Redacted)
+            .field("b", &RedactedInteger(&self.b))
+            .field("epoch", &self.epoch)
+            .finish()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Drop for NonRevocationWitness<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// A non-membership proof for a fresh commitment to the serial, in a
+/// statement bound to the epoch.
+pub struct NonRevocationProof<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub statement: Statement<G, P>,
+    pub proof: NonMembershipProof<G, P, HP>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    NonRevocationProof<G, P, HP>
+{
+    /// Verifies the proof for the accumulator and the epoch the verifier
+    /// trusts, e.g., the registry's current ones.
+    pub fn verify(
+        &self,
+        key: &VerifierKey<G, P, HP>,
+        accumulator: &G::Elem,
+        epoch: u64,
+        context: &[u8],
+    ) -> Result<(), RevocationError> {
+        if self.statement.c_p() != accumulator {
+            return Err(RevocationError::StaleEpoch);
+        }
+        ValidityPolicy::default()
+            .at_epoch(epoch)
+            .check(self.statement.epoch())
+            .map_err(|_| RevocationError::StaleEpoch)?;
+        key.verify_noninteractive(&self.statement, &self.proof, &proof_context(context))?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{RevocationError, RevocationRegistry};
+    use crate::{
+        parameters::Parameters,
+        protocols::{hash_to_prime::snark_range::Protocol as HPProtocol, nonmembership::Protocol},
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::Rsa2048;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rug::Integer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_revocation() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [31u8; 32],
+            )
+            .unwrap();
+        let key = protocol.crs.verifier_key();
+        let (mut rng1, mut rng2) = seeded_rngs([32u8; 32]);
+        let mut serials =
+            vec![
+                Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32 - 1))
+                    .next_prime(),
+            ];
+        for _ in 0..2 {
+            serials.push(serials[serials.len() - 1].clone().next_prime());
+        }
+
+        let mut registry = RevocationRegistry::new(&protocol.crs);
+        let published = Rc::new(RefCell::new(vec![]));
+        let sink = published.clone();
+        registry.subscribe(move |update| sink.borrow_mut().push(update.epoch));
+        registry.revoke(&serials[2..]).unwrap();
+        let mut witness = registry.witness(&serials[0]).unwrap();
+        assert!(matches!(
+            registry.witness(&serials[2]),
+            Err(RevocationError::Revoked)
+        ));
+
+        let proof = witness
+            .prove(
+                &protocol,
+                registry.accumulator(),
+                b"login",
+                &mut rng1,
+                &mut rng2,
+            )
+            .unwrap();
+        proof
+            .verify(&key, registry.accumulator(), registry.epoch(), b"login")
+            .unwrap();
+
+        let update = registry.revoke(&serials[1..2]).unwrap();
+        assert_eq!(*published.borrow(), vec![1, 2]);
+        assert!(matches!(
+            proof.verify(&key, registry.accumulator(), registry.epoch(), b"login"),
+            Err(RevocationError::StaleEpoch)
+        ));
+        assert!(matches!(
+            witness.apply(&registry.updates_since(0)[0]),
+            Err(RevocationError::EpochMismatch(2, 1))
+        ));
+        witness.apply(&update).unwrap();
+        witness
+            .prove(
+                &protocol,
+                registry.accumulator(),
+                b"login",
+                &mut rng1,
+                &mut rng2,
+            )
+            .unwrap()
+            .verify(&key, registry.accumulator(), registry.epoch(), b"login")
+            .unwrap();
+
+        let mut revoked = registry.witness(&serials[0]).unwrap();
+        let update = registry.revoke(&serials[..1]).unwrap();
+        assert!(matches!(
+            revoked.apply(&update),
+            Err(RevocationError::Revoked)
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+//! Fixtures for testing integrations, behind the `test-utils` feature: a
+//! membership CRS over RSA-2048 and BLS12-381 with the LegoGroth16 range
+//! proof from a fixed seed, valid proofs under it and a library of tampered
+//! proofs with the errors a verifier has to reject them with, so that
+//! downstream code can check that its verification path rejects what it
+//! must.
+//!
+//! The proofs are non-interactive, created with `CONTEXT`.
+use crate::{
+    commitments::Commitment,
+    parameters::Parameters,
+    protocols::{
+        hash_to_prime::snark_range::Protocol as HPProtocol,
+        membership::{MembershipProof, Protocol, Statement, Witness},
+        ValidationError, VerificationError,
+    },
+    utils::rng::seeded_rngs,
+};
+use accumulator::{
+    group::{Group, Rsa2048},
+    AccumulatorWithoutHashToPrime,
+};
+use ark_bls12_381::{Bls12_381, G1Projective};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rug::Integer;
+
+pub type MembershipProtocol = Protocol<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+pub type MembershipStatement = Statement<Rsa2048, G1Projective>;
+pub type Proof = MembershipProof<Rsa2048, G1Projective, HPProtocol<Bls12_381>>;
+
+/// The context the proofs are created with.
+pub const CONTEXT: &[u8] = b"cpsnarks-set test-utils";
+
+/// The protocol for the CRS from the fixed seed, at the 128-bit security
+/// level.
+pub fn protocol() -> MembershipProtocol {
+    let parameters = Parameters::from_security_level(128).unwrap();
+    MembershipProtocol::setup_deterministic(&parameters, [0u8; 32]).unwrap()
+}
+
+/// Two valid statements and proofs under the protocol, for different
+/// elements and accumulators.
+pub fn valid_proofs(protocol: &MembershipProtocol) -> Vec<(MembershipStatement, Proof)> {
+    let bits = protocol.crs.parameters.hash_to_prime_bits as u32;
+    let top = Integer::from(Integer::u_pow_u(2, bits));
+    vec![
+        prove(protocol, Integer::from(&top - 245), Integer::from(5), 1),
+        prove(protocol, Integer::from(&top - 273), Integer::from(7), 2),
+    ]
+}
+
+fn prove(
+    protocol: &MembershipProtocol,
+    value: Integer,
+    randomness: Integer,
+    seed: u8,
+) -> (MembershipStatement, Proof) {
+    let (mut rng1, mut rng2) = seeded_rngs([seed; 32]);
+    let c_e_q = protocol
+        .crs
+        .crs_modeq
+        .pedersen_commitment_parameters
+        .commit(&value, &randomness)
+        .unwrap();
+    let accum =
+        accumulator::Accumulator::<Rsa2048, Integer, AccumulatorWithoutHashToPrime>::empty()
+            .add_with_proof(&[value.clone()]);
+    let statement = Statement {
+        c_p: accum.0.value,
+        c_e_q,
+        epoch: None,
+    };
+    let proof = protocol
+        .prove_noninteractive(
+            &mut rng1,
+            &mut rng2,
+            &statement,
+            &Witness {
+                e: value,
+                r_q: randomness,
+                w: accum.1.witness.0.value,
+            },
+            CONTEXT,
+        )
+        .unwrap();
+    (statement, proof)
+}
+
+/// A proof, or bytes that don't decode to one, that has to be rejected for
+/// the statement.
+pub struct TamperedProof {
+    pub name: &'static str,
+    pub statement: MembershipStatement,
+    pub proof_bytes: Vec<u8>,
+    /// Whether an error is the one the proof has to be rejected with.
+    pub expected: fn(&VerificationError) -> bool,
+}
+
+impl TamperedProof {
+    /// Whether the result of verifying the proof is the expected error.
+    pub fn is_rejected(&self, result: &Result<(), VerificationError>) -> bool {
+        match result {
+            Ok(()) => false,
+            Err(err) => (self.expected)(err),
+        }
+    }
+}
+
+/// The tampered proofs, made from the valid ones:
+/// - `wrong_link_d`, whose hash-to-prime proof has another link commitment;
+/// - `truncated`, cut in half;
+/// - `trailing_bytes`, a valid proof followed by a zero byte;
+/// - `swapped_root`, `swapped_modeq` and `swapped_hash_to_prime`, with a
+///   sub-proof of the other valid proof;
+/// - `other_statement`, a valid proof for the other statement;
+/// - `identity_accumulator`, for an accumulator equal to the identity.
+pub fn tampered_proofs(protocol: &MembershipProtocol) -> Vec<TamperedProof> {
+    let mut valid = valid_proofs(protocol);
+    let (statement_b, proof_b) = valid.pop().unwrap();
+    let (statement_a, proof_a) = valid.pop().unwrap();
+    let statement = |statement: &MembershipStatement| Statement {
+        c_p: statement.c_p.clone(),
+        c_e_q: statement.c_e_q,
+        epoch: None,
+    };
+    let bytes = |proof: &Proof| {
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes).unwrap();
+        bytes
+    };
+
+    let mut wrong_link_d = proof_a.clone();
+    wrong_link_d.proof_hash_to_prime.link_d = proof_a.proof_hash_to_prime.c;
+    let mut swapped_root = proof_a.clone();
+    swapped_root.proof_root = proof_b.proof_root.clone();
+    let mut swapped_modeq = proof_a.clone();
+    swapped_modeq.proof_modeq = proof_b.proof_modeq.clone();
+    let mut swapped_hash_to_prime = proof_a.clone();
+    swapped_hash_to_prime.proof_hash_to_prime = proof_b.proof_hash_to_prime.clone();
+    let valid_bytes = bytes(&proof_a);
+    let mut trailing_bytes = valid_bytes.clone();
+    trailing_bytes.push(0);
+
+    vec![
+        TamperedProof {
+            name: "wrong_link_d",
+            statement: statement(&statement_a),
+            proof_bytes: bytes(&wrong_link_d),
+            expected: |err| matches!(err, VerificationError::HashToPrimeProofInvalid),
+        },
+        TamperedProof {
+            name: "truncated",
+            statement: statement(&statement_a),
+            proof_bytes: valid_bytes[..valid_bytes.len() / 2].to_vec(),
+            expected: |err| matches!(err, VerificationError::SerializationError(_)),
+        },
+        TamperedProof {
+            name: "trailing_bytes",
+            statement: statement(&statement_a),
+            proof_bytes: trailing_bytes,
+            expected: |err| {
+                matches!(
+                    err,
+                    VerificationError::SerializationError(SerializationError::InvalidData)
+                )
+            },
+        },
+        TamperedProof {
+            name: "swapped_root",
+            statement: statement(&statement_a),
+            proof_bytes: bytes(&swapped_root),
+            expected: |err| matches!(err, VerificationError::RootCheckFailed),
+        },
+        TamperedProof {
+            name: "swapped_modeq",
+            statement: statement(&statement_a),
+            proof_bytes: bytes(&swapped_modeq),
+            expected: |err| matches!(err, VerificationError::ModEqChallengeMismatch),
+        },
+        TamperedProof {
+            name: "swapped_hash_to_prime",
+            statement: statement(&statement_a),
+            proof_bytes: bytes(&swapped_hash_to_prime),
+            expected: |err| matches!(err, VerificationError::HashToPrimeLinkMismatch { .. }),
+        },
+        TamperedProof {
+            name: "other_statement",
+            statement: statement_b,
+            proof_bytes: valid_bytes.clone(),
+            expected: |err| matches!(err, VerificationError::RootCheckFailed),
+        },
+        TamperedProof {
+            name: "identity_accumulator",
+            statement: Statement {
+                c_p: Rsa2048::id(),
+                c_e_q: statement_a.c_e_q,
+                epoch: None,
+            },
+            proof_bytes: valid_bytes,
+            expected: |err| {
+                matches!(
+                    err,
+                    VerificationError::ValidationError(ValidationError::IdentityElement("c_p"))
+                )
+            },
+        },
+    ]
+}
+
+/// Decodes a proof, rejecting trailing bytes, and verifies it for the
+/// statement with `CONTEXT`.
+pub fn verify(
+    protocol: &MembershipProtocol,
+    statement: &MembershipStatement,
+    proof_bytes: &[u8],
+) -> Result<(), VerificationError> {
+    let mut reader = proof_bytes;
+    let proof = Proof::deserialize(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    protocol.verify_noninteractive(statement, &proof, CONTEXT)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{protocol, tampered_proofs, valid_proofs, verify};
+    use ark_serialize::CanonicalSerialize;
+
+    #[test]
+    fn test_fixtures() {
+        let protocol = protocol();
+        for (statement, proof) in valid_proofs(&protocol) {
+            let mut bytes = vec![];
+            proof.serialize(&mut bytes).unwrap();
+            verify(&protocol, &statement, &bytes).unwrap();
+        }
+        for tampered in tampered_proofs(&protocol) {
+            let result = verify(&protocol, &tampered.statement, &tampered.proof_bytes);
+            assert!(
+                tampered.is_rejected(&result),
+                "{}: {:?}",
+                tampered.name,
+                result
+            );
+        }
+    }
+}
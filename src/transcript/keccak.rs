@@ -0,0 +1,84 @@
+//! A transcript over keccak256, for verifiers on the EVM.
+//!
+//! The state is a single hash, updated with each label and message prefixed
+//! by their lengths as big-endian u32. A challenge first updates the state with
+//! its label and its length, as a message, then reads block i of the challenge
+//! from the hash of the state and i, so that the verifier contract only needs
+//! `keccak256`.
+use crate::transcript::{trace_absorb, trace_challenge, FiatShamir};
+use sha3::{Digest, Keccak256};
+
+pub const STATE_SIZE: usize = 32;
+
+#[derive(Clone)]
+pub struct KeccakTranscript {
+    state: [u8; STATE_SIZE],
+}
+
+impl KeccakTranscript {
+    pub fn new(label: &'static [u8]) -> KeccakTranscript {
+        let mut transcript = KeccakTranscript {
+            state: [0u8; STATE_SIZE],
+        };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    fn update(&mut self, parts: &[&[u8]]) {
+        let mut hasher = Keccak256::new();
+        hasher.update(&self.state);
+        for part in parts {
+            hasher.update(&(part.len() as u32).to_be_bytes());
+            hasher.update(part);
+        }
+        self.state.copy_from_slice(&hasher.finalize());
+    }
+}
+
+impl FiatShamir for KeccakTranscript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        trace_absorb("keccak", label, message);
+        self.update(&[label, message]);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.update(&[label, &(dest.len() as u32).to_be_bytes()]);
+        for (i, block) in dest.chunks_mut(STATE_SIZE).enumerate() {
+            let mut hasher = Keccak256::new();
+            hasher.update(&self.state);
+            hasher.update(&(i as u32).to_be_bytes());
+            block.copy_from_slice(&hasher.finalize()[..block.len()]);
+        }
+        trace_challenge("keccak", label, dest);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::KeccakTranscript;
+    use crate::transcript::FiatShamir;
+
+    #[test]
+    fn test_challenges() {
+        let mut transcript1 = KeccakTranscript::new(b"test");
+        let mut transcript2 = KeccakTranscript::new(b"test");
+        transcript1.append_message(b"message", b"hello");
+        transcript2.append_message(b"message", b"hello");
+
+        let mut c1 = [0u8; 48];
+        let mut c2 = [0u8; 48];
+        transcript1.challenge_bytes(b"c", &mut c1);
+        transcript2.challenge_bytes(b"c", &mut c2);
+        assert_eq!(c1, c2);
+        assert_ne!(c1[..16], c1[32..]);
+
+        let mut c3 = [0u8; 48];
+        transcript1.challenge_bytes(b"c", &mut c3);
+        assert_ne!(c1, c3);
+
+        let mut c4 = [0u8; 48];
+        transcript2.append_message(b"message", b"world");
+        transcript2.challenge_bytes(b"c", &mut c4);
+        assert_ne!(c3, c4);
+    }
+}
@@ -0,0 +1,151 @@
+//! Transcripts transform the interactive protocols into non-interactive using
+//! the Fiat-Shamir transform.
+//!
+//! Each protocol defines a transcript that defines a domain separator, how to
+//! consume each message in the protocol and how to generate challenge scalars.
+//! The transcripts are implemented for any `FiatShamir` instantiation: the
+//! Merlin transcript, a SHAKE256 transcript, a keccak256 transcript and a
+//! Poseidon sponge.
+#[cfg(feature = "tracing")]
+use crate::utils::serialization::to_hex;
+use crate::{
+    protocols::{
+        hash_to_prime::transcript::TranscriptProtocolHashToPrime,
+        modeq::transcript::TranscriptProtocolModEq, root::transcript::TranscriptProtocolRoot,
+    },
+    utils::{
+        bigint_to_bytes,
+        curve::{CurveError, CurvePointProjective},
+        integer_to_bytes, ConvertibleUnknownOrderGroup,
+    },
+};
+use merlin::Transcript;
+use rug::integer::Order;
+use rug::Integer;
+use thiserror::Error;
+
+pub mod keccak;
+#[cfg(feature = "arkworks")]
+pub mod poseidon;
+pub mod shake;
+
+#[derive(Debug, Error)]
+pub enum TranscriptChannelError {
+    #[error("the proof is incomplete")]
+    Incomplete,
+}
+
+/// The hash underlying the transcripts. Messages are absorbed with a label and
+/// challenges are squeezed into byte buffers.
+pub trait FiatShamir {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]);
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]);
+}
+
+/// With the `tracing` feature, emits an event for each message absorbed
+/// into a transcript, so that what a proof was bound to can be reconstructed
+/// from the logs. Only public values are absorbed.
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_absorb(transcript: &'static str, label: &[u8], message: &[u8]) {
+    tracing::trace!(
+        target: "cpsnarks_set::transcript",
+        transcript,
+        label = %String::from_utf8_lossy(label),
+        message = %to_hex(message),
+        "absorb"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn trace_absorb(_: &'static str, _: &[u8], _: &[u8]) {}
+
+/// Like `trace_absorb`, for each challenge squeezed from a transcript.
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_challenge(transcript: &'static str, label: &[u8], challenge: &[u8]) {
+    tracing::trace!(
+        target: "cpsnarks_set::transcript",
+        transcript,
+        label = %String::from_utf8_lossy(label),
+        challenge = %to_hex(challenge),
+        "challenge"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn trace_challenge(_: &'static str, _: &[u8], _: &[u8]) {}
+
+impl FiatShamir for Transcript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        trace_absorb("merlin", label, message);
+        Transcript::append_message(self, label, message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        Transcript::challenge_bytes(self, label, dest);
+        trace_challenge("merlin", label, dest);
+    }
+}
+
+pub trait TranscriptProtocolMembershipPrime<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+>:
+    TranscriptProtocolRoot<G> + TranscriptProtocolModEq<G, P> + TranscriptProtocolHashToPrime<P>
+{
+}
+
+pub trait TranscriptProtocolChallenge {
+    fn challenge_scalar(&mut self, label: &'static [u8], length_in_bits: u16) -> Integer;
+}
+
+pub trait TranscriptProtocolInteger<G: ConvertibleUnknownOrderGroup> {
+    fn append_integer_scalar(&mut self, label: &'static [u8], scalar: &Integer);
+    fn append_integer_point(&mut self, label: &'static [u8], point: &G::Elem);
+}
+
+pub trait TranscriptProtocolEpoch {
+    fn append_epoch(&mut self, epoch: &[u8]);
+}
+
+pub trait TranscriptProtocolCurve<P: CurvePointProjective> {
+    fn append_curve_scalar(&mut self, label: &'static [u8], scalar: &P::ScalarField);
+    fn append_curve_point(&mut self, label: &'static [u8], point: &P) -> Result<(), CurveError>;
+}
+
+impl<G: ConvertibleUnknownOrderGroup, T: FiatShamir> TranscriptProtocolInteger<G> for T {
+    fn append_integer_scalar(&mut self, label: &'static [u8], scalar: &Integer) {
+        self.append_message(label, &integer_to_bytes(scalar));
+    }
+
+    fn append_integer_point(&mut self, label: &'static [u8], point: &G::Elem) {
+        self.append_message(label, &G::elem_to_bytes(point));
+    }
+}
+
+impl<T: FiatShamir> TranscriptProtocolEpoch for T {
+    fn append_epoch(&mut self, epoch: &[u8]) {
+        self.append_message(b"epoch", epoch);
+    }
+}
+
+impl<P: CurvePointProjective, T: FiatShamir> TranscriptProtocolCurve<P> for T {
+    fn append_curve_scalar(&mut self, label: &'static [u8], scalar: &P::ScalarField) {
+        self.append_message(label, &bigint_to_bytes::<P>(&scalar));
+    }
+
+    fn append_curve_point(&mut self, label: &'static [u8], point: &P) -> Result<(), CurveError> {
+        let bytes = point.to_affine_bytes()?;
+        self.append_message(label, &bytes);
+        Ok(())
+    }
+}
+
+impl<T: FiatShamir> TranscriptProtocolChallenge for T {
+    fn challenge_scalar(&mut self, label: &'static [u8], length_in_bits: u16) -> Integer {
+        let mut buf = vec![0u8; (length_in_bits / 8) as usize];
+        self.challenge_bytes(label, &mut buf);
+        Integer::from_digits(&buf[..], Order::MsfBe)
+    }
+}
@@ -0,0 +1,109 @@
+//! A transcript over a Poseidon sponge, so that the challenges can be
+//! recomputed cheaply inside a SNARK over the same field.
+//!
+//! The sponge uses the permutation of the Merkle tree hash with a rate of 2
+//! and the first element as capacity. Byte strings are absorbed as their
+//! length followed by chunks that fit below the modulus, and each squeezed
+//! element contributes its low 16 bytes to the challenge, keeping the bias
+//! from the modular reduction negligible.
+use crate::{
+    protocols::merkle::poseidon::{permute, PoseidonParameters, WIDTH},
+    transcript::{trace_absorb, trace_challenge, FiatShamir},
+};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+
+const RATE: usize = WIDTH - 1;
+const SQUEEZED_BYTES: usize = 16;
+
+#[derive(Clone)]
+pub struct PoseidonTranscript<F: PrimeField> {
+    parameters: PoseidonParameters<F>,
+    state: Vec<F>,
+    position: usize,
+}
+
+impl<F: PrimeField> PoseidonTranscript<F> {
+    pub fn new(label: &'static [u8]) -> PoseidonTranscript<F> {
+        let mut transcript = PoseidonTranscript {
+            parameters: PoseidonParameters::new(),
+            state: vec![F::zero(); WIDTH],
+            position: 0,
+        };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    pub fn absorb_element(&mut self, element: F) {
+        self.state[1 + self.position] += element;
+        self.position += 1;
+        if self.position == RATE {
+            permute(&self.parameters, &mut self.state);
+            self.position = 0;
+        }
+    }
+
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.absorb_element(F::from(bytes.len() as u64));
+        let chunk_size = (F::size_in_bits() - 1) / 8;
+        for chunk in bytes.chunks(chunk_size) {
+            self.absorb_element(F::from_le_bytes_mod_order(chunk));
+        }
+    }
+
+    pub fn squeeze_element(&mut self) -> F {
+        permute(&self.parameters, &mut self.state);
+        self.position = 0;
+        self.state[1]
+    }
+}
+
+impl<F: PrimeField> FiatShamir for PoseidonTranscript<F> {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        trace_absorb("poseidon", label, message);
+        self.absorb_bytes(label);
+        self.absorb_bytes(message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.absorb_bytes(label);
+        self.absorb_element(F::from(dest.len() as u64));
+        for chunk in dest.chunks_mut(SQUEEZED_BYTES) {
+            let mut bytes = vec![];
+            // Serializing a field element to a vector can't fail.
+            self.squeeze_element().serialize(&mut bytes).unwrap();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        trace_challenge("poseidon", label, dest);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PoseidonTranscript;
+    use crate::transcript::FiatShamir;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_challenges() {
+        let mut transcript1 = PoseidonTranscript::<Fr>::new(b"test");
+        let mut transcript2 = PoseidonTranscript::<Fr>::new(b"test");
+        transcript1.append_message(b"message", b"hello");
+        transcript2.append_message(b"message", b"hello");
+
+        let mut c1 = [0u8; 40];
+        let mut c2 = [0u8; 40];
+        transcript1.challenge_bytes(b"c", &mut c1);
+        transcript2.challenge_bytes(b"c", &mut c2);
+        assert_eq!(c1, c2);
+
+        let mut c3 = [0u8; 40];
+        transcript1.challenge_bytes(b"c", &mut c3);
+        assert_ne!(c1, c3);
+
+        let mut c4 = [0u8; 40];
+        transcript2.append_message(b"message", b"world");
+        transcript2.challenge_bytes(b"c", &mut c4);
+        assert_ne!(c3, c4);
+    }
+}
@@ -0,0 +1,78 @@
+//! A transcript over SHAKE256, for verifiers that only have Keccak available.
+//!
+//! Labels and messages are absorbed with their lengths as big-endian u32, and
+//! each challenge is read from a copy of the sponge after absorbing its label
+//! and length, together with a counter so that repeated challenges differ.
+use crate::transcript::{trace_absorb, trace_challenge, FiatShamir};
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+#[derive(Clone)]
+pub struct ShakeTranscript {
+    state: Shake256,
+    challenges: u32,
+}
+
+impl ShakeTranscript {
+    pub fn new(label: &'static [u8]) -> ShakeTranscript {
+        let mut transcript = ShakeTranscript {
+            state: Shake256::default(),
+            challenges: 0,
+        };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.state.update(&(bytes.len() as u32).to_be_bytes());
+        self.state.update(bytes);
+    }
+}
+
+impl FiatShamir for ShakeTranscript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        trace_absorb("shake", label, message);
+        self.absorb(label);
+        self.absorb(message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.absorb(label);
+        self.absorb(&(dest.len() as u32).to_be_bytes());
+        self.absorb(&self.challenges.to_be_bytes());
+        self.challenges += 1;
+        self.state.clone().finalize_xof().read(dest);
+        trace_challenge("shake", label, dest);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ShakeTranscript;
+    use crate::transcript::FiatShamir;
+
+    #[test]
+    fn test_challenges() {
+        let mut transcript1 = ShakeTranscript::new(b"test");
+        let mut transcript2 = ShakeTranscript::new(b"test");
+        transcript1.append_message(b"message", b"hello");
+        transcript2.append_message(b"message", b"hello");
+
+        let mut c1 = [0u8; 16];
+        let mut c2 = [0u8; 16];
+        transcript1.challenge_bytes(b"c", &mut c1);
+        transcript2.challenge_bytes(b"c", &mut c2);
+        assert_eq!(c1, c2);
+
+        let mut c3 = [0u8; 16];
+        transcript1.challenge_bytes(b"c", &mut c3);
+        assert_ne!(c1, c3);
+
+        let mut c4 = [0u8; 16];
+        transcript2.append_message(b"message", b"world");
+        transcript2.challenge_bytes(b"c", &mut c4);
+        assert_ne!(c3, c4);
+    }
+}
@@ -0,0 +1,633 @@
+//! A privacy-preserving transparency log on the membership and consistency
+//! protocols. The log accumulates the primes of its entries and publishes
+//! signed tree heads, each committing to the size of the log and its
+//! accumulator at that point:
+//!
+//! ```ignore
+//! let mut log = TransparencyLog::new(&crs, LogSigningKey::generate(&crs, &mut rng));
+//! log.append(&entries)?;
+//! let head = log.publish(timestamp, &mut rng)?;
+//!
+//! // A holder of an entry proves its inclusion without revealing it.
+//! let w = log.inclusion_witness(&entry, head.head.size)?;
+//! let proof = InclusionProof::prove(&protocol, &head, &entry, &w, b"audit", &mut rng1, &mut rng2)?;
+//! proof.verify(&verifier_key, &head, &log_key, b"audit")?;
+//!
+//! // An auditor checks that a later head extends an earlier one.
+//! let proof = log.consistency_proof(old.head.size, head.head.size)?;
+//! proof.verify(&crs.parameters, &old, &head, &log_key)?;
+//! ```
+//!
+//! Unlike the Merkle tree heads of Certificate Transparency, neither proof
+//! reveals entries: an inclusion proof commits to the entry with fresh
+//! randomness, and a consistency proof shows that the new accumulator is the
+//! old one raised to the product of the appended primes, with a proof of
+//! knowledge of exponent that hides them. Since old accumulators divide new
+//! ones, a log can't drop an entry from a later head without the consistency
+//! proof failing.
+use crate::{
+    accumulators::product,
+    commitments::{Commitment, CommitmentError},
+    parameters::Parameters,
+    protocols::{
+        consistency::{
+            transcript::{TranscriptProverChannel, TranscriptVerifierChannel},
+            Proof as ConsistencyProofMessages, Protocol as ConsistencyProtocol,
+            Statement as ConsistencyStatement, Witness as ConsistencyWitness,
+        },
+        hash_to_prime::{HashToPrimeError, HashToPrimeProtocol},
+        membership::{MembershipProof, Protocol, Statement, VerifierKey, Witness, CRS},
+        ProofError, ValidationError, VerificationError,
+    },
+    transcript::{TranscriptProtocolChallenge, TranscriptProtocolCurve},
+    utils::{
+        bigint_to_integer,
+        curve::{CurveError, CurvePointProjective, Field},
+        integer_to_bigint_mod_q, ConvertibleUnknownOrderGroup, Redacted,
+    },
+};
+use blake2::{Blake2s, Digest};
+use core::cell::RefCell;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::rand::MutRandState;
+use rug::Integer;
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TransparencyError {
+    #[error("could not hash the entry to a prime")]
+    HashToPrimeError(#[from] HashToPrimeError),
+    #[error("could not commit to the entry")]
+    CommitmentError(#[from] CommitmentError),
+    #[error("the statement or witness is malformed")]
+    ValidationError(#[from] ValidationError),
+    #[error("could not prove inclusion or consistency")]
+    ProofError(#[from] ProofError),
+    #[error("the inclusion or consistency proof is invalid")]
+    VerificationError(#[from] VerificationError),
+    #[error("could not encode a curve point")]
+    CurveError(#[from] CurveError),
+    #[error("the entry is already in the log")]
+    DuplicateEntry,
+    #[error("the entry isn't in the log at that size")]
+    EntryNotFound,
+    #[error("no tree head was published at size {0}")]
+    UnknownTreeHead(u64),
+    #[error("the tree heads are out of order")]
+    TreeHeadsOutOfOrder,
+    #[error("the signature of the tree head is invalid")]
+    InvalidSignature,
+    #[error("the proof is for another tree head")]
+    StaleTreeHead,
+}
+
+/// The log's signing key `x`, on the base `g` of the Pedersen commitments
+/// of a CRS.
+pub struct LogSigningKey<P: CurvePointProjective> {
+    base: P,
+    secret: P::ScalarField,
+}
+
+impl<P: CurvePointProjective> LogSigningKey<P> {
+    pub fn generate<
+        G: ConvertibleUnknownOrderGroup,
+        HP: HashToPrimeProtocol<P>,
+        R: RngCore + CryptoRng,
+    >(
+        crs: &CRS<G, P, HP>,
+        rng: &mut R,
+    ) -> Self {
+        LogSigningKey {
+            base: crs.crs_modeq.pedersen_commitment_parameters.g.clone(),
+            secret: P::ScalarField::rand(rng),
+        }
+    }
+
+    pub fn public_key(&self) -> LogPublicKey<P> {
+        LogPublicKey {
+            base: self.base.clone(),
+            key: self.base.mul(&self.secret),
+        }
+    }
+
+    /// A Schnorr signature on the message.
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<LogSignature<P>, TransparencyError> {
+        let nonce = P::ScalarField::rand(rng);
+        let commitment = self.base.mul(&nonce);
+        let challenge = signature_challenge(&self.public_key(), &commitment, message)?;
+        Ok(LogSignature {
+            commitment,
+            response: nonce.add(&challenge.mul(&self.secret)),
+        })
+    }
+}
+
+impl<P: CurvePointProjective> fmt::Debug for LogSigningKey<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LogSigningKey")
+            .field("secret", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct LogPublicKey<P: CurvePointProjective> {
+    pub base: P,
+    pub key: P,
+}
+
+impl<P: CurvePointProjective> LogPublicKey<P> {
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &LogSignature<P>,
+    ) -> Result<(), TransparencyError> {
+        let challenge = signature_challenge(self, &signature.commitment, message)?;
+        if self.base.mul(&signature.response) == signature.commitment.add(&self.key.mul(&challenge))
+        {
+            Ok(())
+        } else {
+            Err(TransparencyError::InvalidSignature)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LogSignature<P: CurvePointProjective> {
+    pub commitment: P,
+    pub response: P::ScalarField,
+}
+
+fn signature_challenge<P: CurvePointProjective>(
+    public_key: &LogPublicKey<P>,
+    commitment: &P,
+    message: &[u8],
+) -> Result<P::ScalarField, TransparencyError> {
+    let mut transcript = Transcript::new(b"cpsnarks-set tree head");
+    TranscriptProtocolCurve::<P>::append_curve_point(&mut transcript, b"base", &public_key.base)?;
+    TranscriptProtocolCurve::<P>::append_curve_point(&mut transcript, b"key", &public_key.key)?;
+    TranscriptProtocolCurve::<P>::append_curve_point(&mut transcript, b"commitment", commitment)?;
+    transcript.append_message(b"message", message);
+    let challenge = transcript.challenge_scalar(b"c", 128);
+    integer_to_bigint_mod_q::<P>(&challenge).map_err(|_| TransparencyError::InvalidSignature)
+}
+
+/// The state of the log when a head was published: the number of entries,
+/// the time, and the accumulator of their primes.
+pub struct TreeHead<G: ConvertibleUnknownOrderGroup> {
+    pub size: u64,
+    pub timestamp: u64,
+    pub accumulator: G::Elem,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Clone for TreeHead<G> {
+    fn clone(&self) -> Self {
+        TreeHead {
+            size: self.size,
+            timestamp: self.timestamp,
+            accumulator: self.accumulator.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> TreeHead<G> {
+    /// The signed encoding: the size, the timestamp and the accumulator.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.size.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&G::elem_to_bytes(&self.accumulator));
+        bytes
+    }
+
+    /// The Blake2s digest of `to_bytes`, which the inclusion proofs are
+    /// bound to.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&Blake2s::digest(&self.to_bytes()));
+        digest
+    }
+}
+
+pub struct SignedTreeHead<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> {
+    pub head: TreeHead<G>,
+    pub signature: LogSignature<P>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Clone for SignedTreeHead<G, P> {
+    fn clone(&self) -> Self {
+        SignedTreeHead {
+            head: self.head.clone(),
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> SignedTreeHead<G, P> {
+    pub fn verify(&self, log_key: &LogPublicKey<P>) -> Result<(), TransparencyError> {
+        log_key.verify(&self.head.to_bytes(), &self.signature)
+    }
+}
+
+/// An append-only log of entries, held by its operator.
+pub struct TransparencyLog<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    protocol: Protocol<G, P, HP>,
+    key: LogSigningKey<P>,
+    primes: Vec<Integer>,
+    accumulator: G::Elem,
+    heads: Vec<SignedTreeHead<G, P>>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    TransparencyLog<G, P, HP>
+{
+    pub fn new(crs: &CRS<G, P, HP>, key: LogSigningKey<P>) -> Self {
+        TransparencyLog {
+            protocol: Protocol::from_crs(crs),
+            key,
+            primes: vec![],
+            accumulator: G::unknown_order_elem(),
+            heads: vec![],
+        }
+    }
+
+    pub fn public_key(&self) -> LogPublicKey<P> {
+        self.key.public_key()
+    }
+
+    pub fn size(&self) -> u64 {
+        self.primes.len() as u64
+    }
+
+    /// The latest published head, if any.
+    pub fn latest(&self) -> Option<&SignedTreeHead<G, P>> {
+        self.heads.last()
+    }
+
+    /// Appends the entries, which are visible in the next published head.
+    pub fn append(&mut self, entries: &[Integer]) -> Result<(), TransparencyError> {
+        let mut primes = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let (prime, _) = self.protocol.hash_to_prime(entry)?;
+            if self.primes.contains(&prime) || primes.contains(&prime) {
+                return Err(TransparencyError::DuplicateEntry);
+            }
+            primes.push(prime);
+        }
+        self.accumulator = G::exp(&self.accumulator, &product(&primes));
+        self.primes.extend(primes);
+        Ok(())
+    }
+
+    /// Signs and records a head for the current state of the log.
+    pub fn publish<R: RngCore + CryptoRng>(
+        &mut self,
+        timestamp: u64,
+        rng: &mut R,
+    ) -> Result<SignedTreeHead<G, P>, TransparencyError> {
+        let head = TreeHead {
+            size: self.size(),
+            timestamp,
+            accumulator: self.accumulator.clone(),
+        };
+        let signature = self.key.sign(&head.to_bytes(), rng)?;
+        let signed = SignedTreeHead { head, signature };
+        self.heads.push(signed.clone());
+        Ok(signed)
+    }
+
+    fn head(&self, size: u64) -> Result<&TreeHead<G>, TransparencyError> {
+        self.heads
+            .iter()
+            .rev()
+            .map(|signed| &signed.head)
+            .find(|head| head.size == size)
+            .ok_or(TransparencyError::UnknownTreeHead(size))
+    }
+
+    /// The membership witness of the entry in the accumulator of the head
+    /// published at the size, given to its holder.
+    pub fn inclusion_witness(
+        &self,
+        entry: &Integer,
+        size: u64,
+    ) -> Result<G::Elem, TransparencyError> {
+        self.head(size)?;
+        let (prime, _) = self.protocol.hash_to_prime(entry)?;
+        let included = &self.primes[..size as usize];
+        if !included.contains(&prime) {
+            return Err(TransparencyError::EntryNotFound);
+        }
+        let others = included
+            .iter()
+            .filter(|p| **p != prime)
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(G::exp(&G::unknown_order_elem(), &product(&others)))
+    }
+
+    /// Proves that the head published at `new_size` extends the one
+    /// published at `old_size`.
+    pub fn consistency_proof(
+        &self,
+        old_size: u64,
+        new_size: u64,
+    ) -> Result<ConsistencyProof<G>, TransparencyError> {
+        if old_size > new_size {
+            return Err(TransparencyError::TreeHeadsOutOfOrder);
+        }
+        let (old, new) = (self.head(old_size)?, self.head(new_size)?);
+        let protocol = ConsistencyProtocol::<G>::setup(&self.protocol.crs.parameters);
+        let transcript = RefCell::new(consistency_transcript(old, new));
+        let mut verifier_channel = TranscriptVerifierChannel::new(&protocol.crs, &transcript);
+        protocol.prove(
+            &mut verifier_channel,
+            &ConsistencyStatement {
+                acc_old: old.accumulator.clone(),
+                acc_new: new.accumulator.clone(),
+                deleted: vec![],
+            },
+            &ConsistencyWitness {
+                acc_mid: old.accumulator.clone(),
+                added: self.primes[old_size as usize..new_size as usize].to_vec(),
+            },
+        )?;
+        Ok(ConsistencyProof {
+            proof: verifier_channel.proof().map_err(ProofError::from)?,
+        })
+    }
+}
+
+/// Binds a consistency proof to both heads, whose sizes and timestamps
+/// aren't part of the consistency statement.
+fn consistency_transcript<G: ConvertibleUnknownOrderGroup>(
+    old: &TreeHead<G>,
+    new: &TreeHead<G>,
+) -> Transcript {
+    let mut transcript = Transcript::new(b"cpsnarks-set transparency consistency");
+    transcript.append_message(b"old", &old.digest());
+    transcript.append_message(b"new", &new.digest());
+    transcript
+}
+
+/// A proof that a later head of a log extends an earlier one.
+pub struct ConsistencyProof<G: ConvertibleUnknownOrderGroup> {
+    pub proof: ConsistencyProofMessages<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> ConsistencyProof<G> {
+    /// Verifies the proof between two heads signed by the log.
+    pub fn verify<P: CurvePointProjective>(
+        &self,
+        parameters: &Parameters,
+        old: &SignedTreeHead<G, P>,
+        new: &SignedTreeHead<G, P>,
+        log_key: &LogPublicKey<P>,
+    ) -> Result<(), TransparencyError> {
+        old.verify(log_key)?;
+        new.verify(log_key)?;
+        if old.head.size > new.head.size {
+            return Err(TransparencyError::TreeHeadsOutOfOrder);
+        }
+        let protocol = ConsistencyProtocol::<G>::setup(parameters);
+        let transcript = RefCell::new(consistency_transcript(&old.head, &new.head));
+        let mut prover_channel =
+            TranscriptProverChannel::new(&protocol.crs, &transcript, &self.proof);
+        protocol.verify(
+            &mut prover_channel,
+            &ConsistencyStatement {
+                acc_old: old.head.accumulator.clone(),
+                acc_new: new.head.accumulator.clone(),
+                deleted: vec![],
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// A membership proof for a fresh commitment to an entry, in a statement
+/// bound to the digest of a tree head.
+pub struct InclusionProof<
+    G: ConvertibleUnknownOrderGroup,
+    P: CurvePointProjective,
+    HP: HashToPrimeProtocol<P>,
+> {
+    pub statement: Statement<G, P>,
+    pub proof: MembershipProof<G, P, HP>,
+}
+
+/// Binds the inclusion proofs to their context, apart from other membership
+/// proofs created with the same context.
+fn inclusion_context(context: &[u8]) -> Vec<u8> {
+    let mut bytes = b"cpsnarks-set transparency inclusion/".to_vec();
+    bytes.extend_from_slice(context);
+    bytes
+}
+
+impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, HP: HashToPrimeProtocol<P>>
+    InclusionProof<G, P, HP>
+{
+    /// Proves that the entry is in the log at the head, given its witness
+    /// from `TransparencyLog::inclusion_witness`. The statement commits to
+    /// the prime the entry hashes to, which the log accumulates.
+    pub fn prove<R1: MutRandState, R2: RngCore + CryptoRng>(
+        protocol: &Protocol<G, P, HP>,
+        head: &SignedTreeHead<G, P>,
+        entry: &Integer,
+        witness: &G::Elem,
+        context: &[u8],
+        rng1: &mut R1,
+        rng2: &mut R2,
+    ) -> Result<Self, TransparencyError> {
+        let pedersen = &protocol.crs.crs_modeq.pedersen_commitment_parameters;
+        let randomness = bigint_to_integer::<P>(&P::ScalarField::rand(rng2));
+        let (prime, _) = protocol.hash_to_prime(entry)?;
+        let statement = Statement::new(
+            head.head.accumulator.clone(),
+            pedersen.commit(&prime, &randomness)?,
+        )?
+        .with_epoch(&head.head.digest());
+        let witness = Witness::new(entry.clone(), randomness, witness.clone())?;
+        let proof = protocol.prove_noninteractive(
+            rng1,
+            rng2,
+            &statement,
+            &witness,
+            &inclusion_context(context),
+        )?;
+        Ok(InclusionProof { statement, proof })
+    }
+
+    /// Verifies the proof against a head signed by the log.
+    pub fn verify(
+        &self,
+        key: &VerifierKey<G, P, HP>,
+        head: &SignedTreeHead<G, P>,
+        log_key: &LogPublicKey<P>,
+        context: &[u8],
+    ) -> Result<(), TransparencyError> {
+        head.verify(log_key)?;
+        if self.statement.c_p() != &head.head.accumulator
+            || self.statement.epoch() != Some(&head.head.digest()[..])
+        {
+            return Err(TransparencyError::StaleTreeHead);
+        }
+        key.verify_noninteractive(&self.statement, &self.proof, &inclusion_context(context))?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{InclusionProof, LogSigningKey, TransparencyError, TransparencyLog};
+    use crate::{
+        parameters::Parameters,
+        protocols::{
+            hash_to_prime::{
+                snark_hash::{HashToPrimeHashParameters, Protocol as HPHashProtocol},
+                snark_range::Protocol as HPProtocol,
+            },
+            membership::Protocol,
+        },
+        utils::rng::seeded_rngs,
+    };
+    use accumulator::group::Rsa2048;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use rand::thread_rng;
+    use rug::Integer;
+
+    #[test]
+    fn test_transparency_log() {
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol =
+            Protocol::<Rsa2048, G1Projective, HPProtocol<Bls12_381>>::setup_deterministic(
+                &params, [91u8; 32],
+            )
+            .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([92u8; 32]);
+        let mut rng = thread_rng();
+
+        let mut entries =
+            vec![
+                Integer::from(Integer::u_pow_u(2, params.hash_to_prime_bits as u32 - 1))
+                    .next_prime(),
+            ];
+        for _ in 0..3 {
+            entries.push(entries[entries.len() - 1].clone().next_prime());
+        }
+
+        let mut log = TransparencyLog::new(
+            &protocol.crs,
+            LogSigningKey::generate(&protocol.crs, &mut rng),
+        );
+        let log_key = log.public_key();
+        log.append(&entries[..2]).unwrap();
+        let old = log.publish(1, &mut rng).unwrap();
+        log.append(&entries[2..]).unwrap();
+        let new = log.publish(2, &mut rng).unwrap();
+        assert!(matches!(
+            log.append(&entries[..1]),
+            Err(TransparencyError::DuplicateEntry)
+        ));
+
+        let key = protocol.crs.verifier_key();
+        let witness = log.inclusion_witness(&entries[0], new.head.size).unwrap();
+        let proof = InclusionProof::prove(
+            &protocol,
+            &new,
+            &entries[0],
+            &witness,
+            b"audit",
+            &mut rng1,
+            &mut rng2,
+        )
+        .unwrap();
+        proof.verify(&key, &new, &log_key, b"audit").unwrap();
+        assert!(matches!(
+            proof.verify(&key, &old, &log_key, b"audit"),
+            Err(TransparencyError::StaleTreeHead)
+        ));
+        assert!(matches!(
+            log.inclusion_witness(&entries[3], old.head.size),
+            Err(TransparencyError::EntryNotFound)
+        ));
+
+        let consistency = log.consistency_proof(old.head.size, new.head.size).unwrap();
+        consistency.verify(&params, &old, &new, &log_key).unwrap();
+        assert!(consistency.verify(&params, &new, &old, &log_key).is_err());
+
+        // A head whose contents were changed after signing is rejected.
+        let mut forged = new.clone();
+        forged.head.timestamp = 3;
+        assert!(matches!(
+            consistency.verify(&params, &old, &forged, &log_key),
+            Err(TransparencyError::InvalidSignature)
+        ));
+
+        // A head that drops an entry can't be shown to extend the old one,
+        // even when signed by the log.
+        let mut rewritten = TransparencyLog::new(
+            &protocol.crs,
+            LogSigningKey::generate(&protocol.crs, &mut rng),
+        );
+        rewritten.append(&entries[1..]).unwrap();
+        let mut dropped = new.clone();
+        dropped.head.accumulator = rewritten.accumulator;
+        dropped.signature = log.key.sign(&dropped.head.to_bytes(), &mut rng).unwrap();
+        assert!(consistency
+            .verify(&params, &old, &dropped, &log_key)
+            .is_err());
+    }
+
+    #[test]
+    fn test_transparency_log_hash() {
+        struct TestHashToPrimeParameters {}
+        impl HashToPrimeHashParameters for TestHashToPrimeParameters {
+            const MESSAGE_SIZE: u16 = 254;
+        }
+
+        let params = Parameters::from_security_level(128).unwrap();
+        let protocol = Protocol::<
+            Rsa2048,
+            G1Projective,
+            HPHashProtocol<Bls12_381, TestHashToPrimeParameters>,
+        >::setup_deterministic(&params, [93u8; 32])
+        .unwrap();
+        let (mut rng1, mut rng2) = seeded_rngs([94u8; 32]);
+        let mut rng = thread_rng();
+
+        // The entries aren't primes, and the log accumulates their hashes.
+        let entries = vec![Integer::from(24_928_329), Integer::from(24_928_330)];
+        let mut log = TransparencyLog::new(
+            &protocol.crs,
+            LogSigningKey::generate(&protocol.crs, &mut rng),
+        );
+        let log_key = log.public_key();
+        log.append(&entries).unwrap();
+        let head = log.publish(1, &mut rng).unwrap();
+
+        let key = protocol.crs.verifier_key();
+        let witness = log.inclusion_witness(&entries[1], head.head.size).unwrap();
+        let proof = InclusionProof::prove(
+            &protocol,
+            &head,
+            &entries[1],
+            &witness,
+            b"audit",
+            &mut rng1,
+            &mut rng2,
+        )
+        .unwrap();
+        proof.verify(&key, &head, &log_key, b"audit").unwrap();
+        assert!(proof.verify(&key, &head, &log_key, b"other").is_err());
+    }
+}
@@ -2,12 +2,16 @@
 
 use rand::{CryptoRng, RngCore};
 use rug::Integer;
+use thiserror::Error;
 
-quick_error! {
-    #[derive(Debug)]
-    pub enum CurveError {
-        CannotWrite {}
-    }
+#[derive(Debug, Error)]
+pub enum CurveError {
+    #[error("could not write the point")]
+    CannotWrite,
+    #[error("could not read the point")]
+    CannotRead,
+    #[error("the point is not in the prime-order subgroup")]
+    NotInSubgroup,
 }
 
 pub trait Field
@@ -18,6 +22,10 @@ where
     fn size_in_bits() -> usize;
     fn to_bits(&self) -> Vec<bool>;
     fn from_bits(bits: &[bool]) -> Self;
+    /// Converts an integer in [0, modulus) directly from its limbs, or
+    /// returns `None` for an integer out of this range.
+    fn from_integer(num: &Integer) -> Option<Self>;
+    fn to_integer(&self) -> Integer;
     fn add(&self, other: &Self) -> Self;
     fn sub(&self, other: &Self) -> Self;
     fn neg(&self) -> Self;
@@ -34,20 +42,48 @@ where
 
     fn mul(&self, s: &Self::ScalarField) -> Self;
     fn add(&self, other: &Self) -> Self;
+    /// The sum of the bases multiplied by the scalars, computed with a
+    /// multi-scalar multiplication instead of one multiplication per base.
+    fn msm(bases: &[Self], scalars: &[Self::ScalarField]) -> Self;
 
     fn to_affine_bytes(&self) -> Result<Vec<u8>, CurveError>;
+    /// Decodes a point, rejecting points that aren't on the curve or in the
+    /// prime-order subgroup.
+    fn from_affine_bytes(bytes: &[u8]) -> Result<Self, CurveError>;
+    /// Like `to_affine_bytes`, without point compression where the curve
+    /// supports it, trading size for faster decoding.
+    fn to_affine_bytes_uncompressed(&self) -> Result<Vec<u8>, CurveError> {
+        self.to_affine_bytes()
+    }
+    fn from_affine_bytes_uncompressed(bytes: &[u8]) -> Result<Self, CurveError> {
+        Self::from_affine_bytes(bytes)
+    }
+    /// The big-endian affine coordinates x and y, for encodings that need
+    /// them, e.g., for EVM verifiers. Backends that don't expose coordinates
+    /// return `CurveError::CannotWrite`.
+    fn to_affine_coordinates(&self) -> Result<(Vec<u8>, Vec<u8>), CurveError> {
+        Err(CurveError::CannotWrite)
+    }
     fn rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self;
+    fn is_identity(&self) -> bool;
+    /// Whether the point is in the prime-order subgroup, which points built
+    /// in memory rather than decoded by `from_affine_bytes` may not be.
+    /// Prime-order groups like Ristretto keep the default.
+    fn is_in_subgroup(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(feature = "arkworks")]
 mod arkworks {
     use super::{CurvePointProjective, Field};
-    use crate::utils::{bits_big_endian_to_bytes_big_endian, bytes_to_integer, curve::CurveError};
-    use ark_ec::ProjectiveCurve;
-    use ark_ff::{BigInteger, FpParameters, PrimeField};
-    use ark_serialize::{CanonicalSerialize, SerializationError};
+    use crate::utils::curve::CurveError;
+    use ark_ec::{msm::VariableBaseMSM, AffineCurve, ProjectiveCurve};
+    use ark_ff::{BigInteger, FpParameters, PrimeField, Zero};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 
     use rand::{CryptoRng, RngCore};
+    use rug::integer::Order;
     use rug::Integer;
 
     impl From<SerializationError> for CurveError {
@@ -58,10 +94,7 @@ mod arkworks {
 
     impl<F: PrimeField> Field for F {
         fn modulus() -> Integer {
-            let repr = F::Params::MODULUS;
-            let bits = repr.to_bits_be();
-            let bytes = bits_big_endian_to_bytes_big_endian(&bits);
-            bytes_to_integer(&bytes)
+            Integer::from_digits(F::Params::MODULUS.as_ref(), Order::Lsf)
         }
         fn size_in_bits() -> usize {
             F::size_in_bits()
@@ -72,6 +105,18 @@ mod arkworks {
         fn from_bits(bits: &[bool]) -> Self {
             F::from(F::BigInt::from_bits_be(bits))
         }
+        fn from_integer(num: &Integer) -> Option<Self> {
+            let mut repr = F::BigInt::default();
+            let limbs = repr.as_mut();
+            if *num < 0 || num.significant_digits::<u64>() > limbs.len() {
+                return None;
+            }
+            num.write_digits(limbs, Order::Lsf);
+            F::from_repr(repr)
+        }
+        fn to_integer(&self) -> Integer {
+            Integer::from_digits(self.into_repr().as_ref(), Order::Lsf)
+        }
         fn add(&self, other: &Self) -> Self {
             F::add(*self, *other)
         }
@@ -92,6 +137,26 @@ mod arkworks {
         }
     }
 
+    /// The bit size of p for a prime field F_p, or `None` for extension fields.
+    fn prime_base_field_bits<F: ark_ff::Field>() -> Option<usize> {
+        if F::extension_degree() != 1 {
+            return None;
+        }
+        let limbs = F::characteristic();
+        let i = limbs.iter().rposition(|limb| *limb != 0)?;
+        Some(64 * i + 64 - limbs[i].leading_zeros() as usize)
+    }
+
+    /// Deserializing checks that the point is on the curve, this checks that
+    /// it's in the prime-order subgroup.
+    fn in_subgroup<P: ProjectiveCurve>(affine: P::Affine) -> Result<P, CurveError> {
+        let modulus = <P::ScalarField as PrimeField>::Params::MODULUS;
+        if !affine.mul(modulus).is_zero() {
+            return Err(CurveError::NotInSubgroup);
+        }
+        Ok(affine.into_projective())
+    }
+
     impl<P: ProjectiveCurve> CurvePointProjective for P {
         type ScalarField = P::ScalarField;
 
@@ -103,6 +168,12 @@ mod arkworks {
             P::add(*self, *other)
         }
 
+        fn msm(bases: &[Self], scalars: &[Self::ScalarField]) -> Self {
+            let bases = P::batch_normalization_into_affine(bases);
+            let scalars = scalars.iter().map(|s| s.into_repr()).collect::<Vec<_>>();
+            VariableBaseMSM::multi_scalar_mul(&bases, &scalars)
+        }
+
         fn to_affine_bytes(&self) -> Result<Vec<u8>, CurveError> {
             let affine = self.into_affine();
             let mut bytes = vec![];
@@ -110,9 +181,85 @@ mod arkworks {
             Ok(bytes)
         }
 
+        fn from_affine_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+            let affine = P::Affine::deserialize(bytes).map_err(|_| CurveError::CannotRead)?;
+            in_subgroup::<P>(affine)
+        }
+
+        fn to_affine_bytes_uncompressed(&self) -> Result<Vec<u8>, CurveError> {
+            let affine = self.into_affine();
+            let mut bytes = vec![];
+            affine.serialize_uncompressed(&mut bytes)?;
+            Ok(bytes)
+        }
+
+        fn from_affine_bytes_uncompressed(bytes: &[u8]) -> Result<Self, CurveError> {
+            let affine =
+                P::Affine::deserialize_uncompressed(bytes).map_err(|_| CurveError::CannotRead)?;
+            in_subgroup::<P>(affine)
+        }
+
+        fn to_affine_coordinates(&self) -> Result<(Vec<u8>, Vec<u8>), CurveError> {
+            let affine = self.into_affine();
+            let modulus_bits =
+                match prime_base_field_bits::<<P::Affine as AffineCurve>::BaseField>() {
+                    Some(bits) if !affine.is_zero() => bits,
+                    _ => return Err(CurveError::CannotWrite),
+                };
+            // The uncompressed encoding is x and y in little-endian, with the
+            // flags in the unused top bits of y.
+            let mut x = vec![];
+            affine.serialize_uncompressed(&mut x)?;
+            let mut y = x.split_off(x.len() / 2);
+            for (i, byte) in y.iter_mut().enumerate() {
+                if 8 * i >= modulus_bits {
+                    *byte = 0;
+                } else if 8 * (i + 1) > modulus_bits {
+                    *byte &= (1u8 << (modulus_bits - 8 * i)) - 1;
+                }
+            }
+            x.reverse();
+            y.reverse();
+            Ok((x, y))
+        }
+
         fn rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
             P::rand(rng)
         }
+
+        fn is_identity(&self) -> bool {
+            Zero::is_zero(self)
+        }
+
+        fn is_in_subgroup(&self) -> bool {
+            let modulus = <P::ScalarField as PrimeField>::Params::MODULUS;
+            Zero::is_zero(&ProjectiveCurve::mul(*self, modulus))
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::CurvePointProjective;
+        use ark_bls12_381::{Fr, G1Projective};
+        use ark_ff::UniformRand;
+        use rand::thread_rng;
+
+        #[test]
+        fn test_msm() {
+            let mut rng = thread_rng();
+            let bases = (0..5)
+                .map(|_| <G1Projective as UniformRand>::rand(&mut rng))
+                .collect::<Vec<_>>();
+            let scalars = (0..5).map(|_| Fr::rand(&mut rng)).collect::<Vec<_>>();
+            let expected = bases
+                .iter()
+                .zip(scalars.iter())
+                .map(|(base, scalar)| CurvePointProjective::mul(base, scalar))
+                .fold(G1Projective::default(), |sum, point| {
+                    CurvePointProjective::add(&sum, &point)
+                });
+            assert_eq!(G1Projective::msm(&bases, &scalars), expected);
+        }
     }
 }
 
@@ -120,16 +267,21 @@ mod arkworks {
 mod dalek {
     use super::{CurvePointProjective, Field};
     use crate::utils::{
-        bigint_to_integer, bits_big_endian_to_bytes_big_endian,
-        bytes_big_endian_to_bits_big_endian, curve::CurveError,
+        bits_big_endian_to_bytes_big_endian, bytes_big_endian_to_bits_big_endian, curve::CurveError,
+    };
+    use curve25519_dalek::{
+        constants::BASEPOINT_ORDER,
+        ristretto::{CompressedRistretto, RistrettoPoint},
+        scalar::Scalar,
+        traits::{Identity, MultiscalarMul},
     };
-    use curve25519_dalek::{constants::BASEPOINT_ORDER, ristretto::RistrettoPoint, scalar::Scalar};
     use rand::{CryptoRng, RngCore};
+    use rug::integer::Order;
     use rug::Integer;
 
     impl Field for Scalar {
         fn modulus() -> Integer {
-            bigint_to_integer::<RistrettoPoint>(&BASEPOINT_ORDER)
+            BASEPOINT_ORDER.to_integer()
         }
 
         fn size_in_bits() -> usize {
@@ -165,6 +317,17 @@ mod dalek {
             little_endian_fixed_bytes[..].copy_from_slice(little_endian_bytes_padded.as_ref());
             Scalar::from_bits(little_endian_fixed_bytes)
         }
+        fn from_integer(num: &Integer) -> Option<Self> {
+            if *num < 0 || num.significant_digits::<u8>() > 32 {
+                return None;
+            }
+            let mut little_endian_bytes = [0u8; 32];
+            num.write_digits(&mut little_endian_bytes, Order::Lsf);
+            Scalar::from_canonical_bytes(little_endian_bytes)
+        }
+        fn to_integer(&self) -> Integer {
+            Integer::from_digits(self.as_bytes(), Order::Lsf)
+        }
         fn add(&self, other: &Self) -> Self {
             self + other
         }
@@ -198,13 +361,27 @@ mod dalek {
         fn add(&self, other: &Self) -> Self {
             self + other
         }
+        fn msm(bases: &[Self], scalars: &[Self::ScalarField]) -> Self {
+            RistrettoPoint::multiscalar_mul(scalars, bases)
+        }
 
         fn to_affine_bytes(&self) -> Result<Vec<u8>, CurveError> {
             Ok(self.compress().to_bytes()[..].to_vec())
         }
+        fn from_affine_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+            if bytes.len() != 32 {
+                return Err(CurveError::CannotRead);
+            }
+            CompressedRistretto::from_slice(bytes)
+                .decompress()
+                .ok_or(CurveError::CannotRead)
+        }
         fn rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
             RistrettoPoint::random(rng)
         }
+        fn is_identity(&self) -> bool {
+            *self == RistrettoPoint::identity()
+        }
     }
 
     #[cfg(test)]
@@ -0,0 +1,437 @@
+//! A simple abstraction for groups of unknown order, to wrap the RSA and class
+//! groups from the `accumulator` crate or alternative implementations.
+
+use crate::utils::{bytes_to_integer, zeroize_integer};
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+/// Groups whose elements can be decoded from the output of `elem_to_bytes`,
+/// needed to send them over byte-oriented channels. The class groups from the
+/// `accumulator` crate don't expose their forms, so they're not supported.
+pub trait ElemFromBytes: UnknownOrderGroup {
+    fn elem_from_bytes(bytes: &[u8]) -> Option<Self::Elem>;
+}
+
+/// The window size, in bits, of the fixed-base tables.
+const FIXED_BASE_WINDOW: u32 = 4;
+
+pub trait UnknownOrderGroup
+where
+    Self: Clone + Debug + Sized,
+{
+    type Elem: Clone + Debug + Eq + Hash + Send + Sync;
+
+    fn id() -> Self::Elem;
+    fn op(a: &Self::Elem, b: &Self::Elem) -> Self::Elem;
+    /// Exponentiation, where negative exponents use the inverse of the base.
+    fn exp(a: &Self::Elem, n: &Integer) -> Self::Elem;
+    fn inv(a: &Self::Elem) -> Self::Elem;
+    /// Exponentiation by a secret exponent, whose running time should only
+    /// depend on the length of the exponent, or `None` if the base isn't
+    /// invertible. Defaults to `exp`, which is variable-time. The RSA groups
+    /// override it, while the class groups of the `accumulator` crate keep
+    /// `exp`. The generator is for groups that randomize the exponentiation.
+    fn exp_secret<R: RngCore + CryptoRng>(
+        a: &Self::Elem,
+        n: &Integer,
+        _rng: &mut R,
+    ) -> Option<Self::Elem> {
+        Some(Self::exp(a, n))
+    }
+
+    /// An element of the group whose order is unknown, used as the generator.
+    fn unknown_order_elem() -> Self::Elem;
+    /// An upper bound on the order of the group.
+    fn order_upper_bound() -> Integer;
+    fn elem_to_bytes(a: &Self::Elem) -> Vec<u8>;
+    /// The bit size of the discriminant for class groups, which is checked
+    /// against the security level, and `None` for the other groups.
+    fn discriminant_bits() -> Option<u32> {
+        None
+    }
+}
+
+/// Precomputed powers of a fixed base, where row i holds base^{j * 2^{w*i}}
+/// for all w-bit j. Exponents longer than the table fall back to `G::exp`.
+///
+/// With the `parallel` feature, the exponent is split into chunks of rows
+/// whose products are computed on rayon threads and then combined.
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<G: UnknownOrderGroup> {
+    base: G::Elem,
+    rows: Arc<Vec<Vec<G::Elem>>>,
+}
+
+impl<G: UnknownOrderGroup> FixedBaseTable<G> {
+    pub fn new(base: &G::Elem, max_bits: u32) -> FixedBaseTable<G> {
+        let num_rows = (max_bits + FIXED_BASE_WINDOW - 1) / FIXED_BASE_WINDOW;
+        let mut rows = Vec::with_capacity(num_rows as usize);
+        let mut row_base = base.clone();
+        for _ in 0..num_rows {
+            let mut row = vec![G::id()];
+            for j in 1..(1 << FIXED_BASE_WINDOW) {
+                row.push(G::op(&row[j - 1], &row_base));
+            }
+            row_base = G::op(&row[row.len() - 1], &row_base);
+            rows.push(row);
+        }
+
+        FixedBaseTable {
+            base: base.clone(),
+            rows: Arc::new(rows),
+        }
+    }
+
+    pub fn base(&self) -> &G::Elem {
+        &self.base
+    }
+
+    pub fn exp(&self, n: &Integer) -> G::Elem {
+        if *n < 0 {
+            return G::inv(&self.exp(&Integer::from(-n)));
+        }
+        if n.significant_bits() > self.rows.len() as u32 * FIXED_BASE_WINDOW {
+            return G::exp(&self.base, n);
+        }
+
+        self.product(n)
+    }
+
+    /// Exponentiation by a secret exponent. Looking the entries up by the
+    /// digits of the exponent would leak them through the memory accesses, so
+    /// the table isn't used and this is `G::exp_secret` on the base.
+    pub fn exp_secret<R: RngCore + CryptoRng>(&self, n: &Integer, rng: &mut R) -> Option<G::Elem> {
+        G::exp_secret(&self.base, n, rng)
+    }
+
+    /// The i-th w-bit digit of n.
+    fn digit(n: &Integer, i: usize) -> usize {
+        let mut digit = 0;
+        for k in 0..FIXED_BASE_WINDOW {
+            if n.get_bit(i as u32 * FIXED_BASE_WINDOW + k) {
+                digit |= 1 << k;
+            }
+        }
+        digit
+    }
+
+    /// The entry of row i for the i-th w-bit digit of n, if it's not zero.
+    fn row_power<'a>(n: &Integer, i: usize, row: &'a [G::Elem]) -> Option<&'a G::Elem> {
+        let digit = Self::digit(n, i);
+        if digit != 0 {
+            Some(&row[digit])
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn product(&self, n: &Integer) -> G::Elem {
+        use rayon::prelude::*;
+        self.rows
+            .par_iter()
+            .enumerate()
+            .filter_map(|(i, row)| Self::row_power(n, i, row))
+            .fold(G::id, |result, elem| G::op(&result, elem))
+            .reduce(G::id, |a, b| G::op(&a, &b))
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn product(&self, n: &Integer) -> G::Elem {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, row)| Self::row_power(n, i, row))
+            .fold(G::id(), |result, elem| G::op(&result, elem))
+    }
+}
+
+/// The bits by which the blinding of a secret exponent is longer than it.
+const BLINDING_BITS: u32 = 128;
+
+/// Wraps a group to blind the exponents of `exp_secret`, computing g^n as
+/// g^{n + rho} g^{-rho} for a fresh rho from the caller's generator, since the order is unknown
+/// and multiples of it can't be added to n instead. Repeated exponentiations
+/// by the same secret then run on different exponents, at twice the cost.
+/// The elements are those of the wrapped group, e.g., `Blinded<Rsa2048>`
+/// proves about `Rsa2048` accumulators.
+#[derive(Clone, Debug)]
+pub struct Blinded<G: UnknownOrderGroup> {
+    _group: PhantomData<G>,
+}
+
+impl<G: UnknownOrderGroup> UnknownOrderGroup for Blinded<G> {
+    type Elem = G::Elem;
+
+    fn id() -> G::Elem {
+        G::id()
+    }
+
+    fn op(a: &G::Elem, b: &G::Elem) -> G::Elem {
+        G::op(a, b)
+    }
+
+    fn exp(a: &G::Elem, n: &Integer) -> G::Elem {
+        G::exp(a, n)
+    }
+
+    fn inv(a: &G::Elem) -> G::Elem {
+        G::inv(a)
+    }
+
+    fn exp_secret<R: RngCore + CryptoRng>(
+        a: &G::Elem,
+        n: &Integer,
+        rng: &mut R,
+    ) -> Option<G::Elem> {
+        let mut bytes = vec![0u8; ((n.significant_bits() + BLINDING_BITS + 7) / 8) as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut rho = bytes_to_integer(&bytes);
+        bytes.zeroize();
+        let mut blinded = Integer::from(n + &rho);
+        rho = -rho;
+        let (a_blinded, a_rho) = (G::exp_secret(a, &blinded, rng), G::exp_secret(a, &rho, rng));
+        zeroize_integer(&mut blinded);
+        zeroize_integer(&mut rho);
+        Some(G::op(&a_blinded?, &a_rho?))
+    }
+
+    fn unknown_order_elem() -> G::Elem {
+        G::unknown_order_elem()
+    }
+
+    fn order_upper_bound() -> Integer {
+        G::order_upper_bound()
+    }
+
+    fn elem_to_bytes(a: &G::Elem) -> Vec<u8> {
+        G::elem_to_bytes(a)
+    }
+
+    fn discriminant_bits() -> Option<u32> {
+        G::discriminant_bits()
+    }
+}
+
+impl<G: ElemFromBytes> ElemFromBytes for Blinded<G> {
+    fn elem_from_bytes(bytes: &[u8]) -> Option<G::Elem> {
+        G::elem_from_bytes(bytes)
+    }
+}
+
+mod accumulator_groups {
+    use super::{ElemFromBytes, UnknownOrderGroup};
+    use crate::utils::{bytes_to_integer, secure_pow_mod};
+    use accumulator::group::{
+        ClassGroup, ElemFrom, ElemToBytes, Group, Rsa2048, TypeRep,
+        UnknownOrderGroup as AccumulatorUnknownOrderGroup,
+    };
+    use rand::{CryptoRng, RngCore};
+    use rug::Integer;
+
+    /// Exponentiation by a secret exponent for the `accumulator` crate groups,
+    /// which can't be overridden per group in the blanket implementation.
+    pub trait AccumulatorExpSecret: Group {
+        fn exp_secret(a: &Self::Elem, n: &Integer) -> Option<Self::Elem>;
+    }
+
+    impl AccumulatorExpSecret for Rsa2048 {
+        /// Uses `secure_pow_mod` over the modulus of the group.
+        fn exp_secret(a: &Self::Elem, n: &Integer) -> Option<Self::Elem> {
+            match Rsa2048::rsa_modulus() {
+                Ok(modulus) => {
+                    let a = bytes_to_integer(&Rsa2048::elem_to_bytes(a));
+                    secure_pow_mod(&a, n, &modulus).map(Rsa2048::elem)
+                }
+                Err(_) => Some(<Rsa2048 as Group>::exp(a, n)),
+            }
+        }
+    }
+
+    impl AccumulatorExpSecret for ClassGroup {
+        /// GMP has no fixed-time composition of forms, so this is `exp`.
+        fn exp_secret(a: &Self::Elem, n: &Integer) -> Option<Self::Elem> {
+            Some(<ClassGroup as Group>::exp(a, n))
+        }
+    }
+
+    /// The discriminant of the `accumulator` crate groups, for the same reason.
+    pub trait AccumulatorDiscriminant: Group {
+        fn discriminant_bits() -> Option<u32>;
+    }
+
+    impl AccumulatorDiscriminant for Rsa2048 {
+        fn discriminant_bits() -> Option<u32> {
+            None
+        }
+    }
+
+    impl AccumulatorDiscriminant for ClassGroup {
+        /// The group is represented by its discriminant.
+        fn discriminant_bits() -> Option<u32> {
+            Some(<ClassGroup as TypeRep>::rep().significant_bits())
+        }
+    }
+
+    impl<
+            T: AccumulatorUnknownOrderGroup
+                + ElemToBytes
+                + AccumulatorExpSecret
+                + AccumulatorDiscriminant,
+        > UnknownOrderGroup for T
+    {
+        type Elem = <T as Group>::Elem;
+
+        fn id() -> Self::Elem {
+            <T as Group>::id()
+        }
+
+        fn op(a: &Self::Elem, b: &Self::Elem) -> Self::Elem {
+            <T as Group>::op(a, b)
+        }
+
+        fn exp(a: &Self::Elem, n: &Integer) -> Self::Elem {
+            <T as Group>::exp(a, n)
+        }
+
+        fn inv(a: &Self::Elem) -> Self::Elem {
+            <T as Group>::inv(a)
+        }
+
+        fn exp_secret<R: RngCore + CryptoRng>(
+            a: &Self::Elem,
+            n: &Integer,
+            _rng: &mut R,
+        ) -> Option<Self::Elem> {
+            <T as AccumulatorExpSecret>::exp_secret(a, n)
+        }
+
+        fn unknown_order_elem() -> Self::Elem {
+            <T as AccumulatorUnknownOrderGroup>::unknown_order_elem()
+        }
+
+        fn order_upper_bound() -> Integer {
+            <T as AccumulatorUnknownOrderGroup>::order_upper_bound()
+        }
+
+        fn elem_to_bytes(a: &Self::Elem) -> Vec<u8> {
+            <T as ElemToBytes>::elem_to_bytes(a)
+        }
+
+        fn discriminant_bits() -> Option<u32> {
+            <T as AccumulatorDiscriminant>::discriminant_bits()
+        }
+    }
+
+    impl ElemFromBytes for Rsa2048 {
+        fn elem_from_bytes(bytes: &[u8]) -> Option<<Rsa2048 as UnknownOrderGroup>::Elem> {
+            if bytes.is_empty() {
+                return None;
+            }
+            let elem = Rsa2048::elem(&bytes_to_integer(bytes));
+            // Rejects encodings of integers out of range, which would otherwise
+            // be reduced to another element.
+            if Rsa2048::elem_to_bytes(&elem) != bytes {
+                return None;
+            }
+            Some(elem)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Blinded, ElemFromBytes, FixedBaseTable, UnknownOrderGroup};
+    use accumulator::group::Rsa2048;
+    use rand::{thread_rng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use rug::Integer;
+
+    #[test]
+    fn test_accumulator_groups() {
+        let g = Rsa2048::unknown_order_elem();
+        let g_3 = Rsa2048::exp(&g, &Integer::from(3));
+        assert_eq!(
+            Rsa2048::op(&g_3, &Rsa2048::exp(&g, &Integer::from(-3))),
+            Rsa2048::id()
+        );
+        assert_eq!(Rsa2048::inv(&g_3), Rsa2048::exp(&g, &Integer::from(-3)));
+        assert_eq!(
+            Rsa2048::elem_to_bytes(&g_3),
+            Rsa2048::elem_to_bytes(&Rsa2048::op(&g, &Rsa2048::exp(&g, &Integer::from(2))))
+        );
+        assert_eq!(
+            Rsa2048::elem_from_bytes(&Rsa2048::elem_to_bytes(&g_3)).unwrap(),
+            g_3
+        );
+        for n in &[
+            Integer::from(0),
+            Integer::from(1),
+            Integer::from(-17),
+            Integer::from(Integer::u_pow_u(2, 130)) + 7,
+        ] {
+            assert_eq!(
+                Rsa2048::exp_secret(&g, n, &mut thread_rng()).unwrap(),
+                Rsa2048::exp(&g, n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fixed_base_table() {
+        let g = Rsa2048::unknown_order_elem();
+        let table = FixedBaseTable::<Rsa2048>::new(&g, 70);
+        for n in &[
+            Integer::from(0),
+            Integer::from(1),
+            Integer::from(-17),
+            Integer::from(12_702_637_924_034_044_211u64),
+            Integer::from(Integer::u_pow_u(2, 69)),
+            Integer::from(Integer::u_pow_u(2, 100)) + 3,
+        ] {
+            assert_eq!(table.exp(n), Rsa2048::exp(&g, n));
+            assert_eq!(
+                table.exp_secret(n, &mut thread_rng()).unwrap(),
+                Rsa2048::exp(&g, n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_blinded() {
+        let g = Rsa2048::unknown_order_elem();
+        for n in &[
+            Integer::from(0),
+            Integer::from(-17),
+            Integer::from(Integer::u_pow_u(2, 300)) + 3,
+        ] {
+            assert_eq!(
+                Blinded::<Rsa2048>::exp_secret(&g, n, &mut thread_rng()).unwrap(),
+                Rsa2048::exp(&g, n)
+            );
+        }
+        let table = FixedBaseTable::<Blinded<Rsa2048>>::new(&g, 70);
+        let n = Integer::from(Integer::u_pow_u(2, 100)) + 3;
+        assert_eq!(
+            table.exp_secret(&n, &mut thread_rng()).unwrap(),
+            Rsa2048::exp(&g, &n)
+        );
+
+        // The blinding is drawn from the caller's generator, so that seeded
+        // provers stay reproducible.
+        let mut rng1 = ChaCha20Rng::from_seed([3u8; 32]);
+        let mut rng2 = ChaCha20Rng::from_seed([3u8; 32]);
+        Blinded::<Rsa2048>::exp_secret(&g, &n, &mut rng1).unwrap();
+        Blinded::<Rsa2048>::exp_secret(&g, &n, &mut rng2).unwrap();
+        assert_eq!(rng1.next_u64(), rng2.next_u64());
+        assert_ne!(
+            rng1.next_u64(),
+            ChaCha20Rng::from_seed([3u8; 32]).next_u64()
+        );
+    }
+}
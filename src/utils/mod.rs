@@ -1,13 +1,47 @@
-use accumulator::group::{ElemToBytes, UnknownOrderGroup};
 use rug::integer::Order;
 use rug::rand::MutRandState;
 use rug::Integer;
+use std::fmt;
+use zeroize::Zeroize;
 
 pub mod curve;
 use curve::{CurvePointProjective, Field};
+pub mod group;
+use group::UnknownOrderGroup;
+pub mod msm;
+pub mod rng;
+pub mod rsa;
+pub mod serialization;
+
+/// The bound used throughout the protocols. Any implementation of the
+/// crate-local `UnknownOrderGroup` can be used, including the `accumulator`
+/// crate groups.
+pub trait ConvertibleUnknownOrderGroup: UnknownOrderGroup {}
+impl<T: UnknownOrderGroup> ConvertibleUnknownOrderGroup for T {}
+
+/// Runs the closures on two rayon threads.
+#[cfg(feature = "parallel")]
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    rayon::join(a, b)
+}
 
-pub trait ConvertibleUnknownOrderGroup: UnknownOrderGroup + ElemToBytes {}
-impl<T: UnknownOrderGroup + ElemToBytes> ConvertibleUnknownOrderGroup for T {}
+/// Runs the closures one after the other, without the `parallel` feature.
+#[cfg(not(feature = "parallel"))]
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    (a(), b())
+}
 
 pub fn random_between<R: MutRandState>(rng: &mut R, min: &Integer, max: &Integer) -> Integer {
     min + Integer::from(max - min).random_below(rng)
@@ -17,6 +51,12 @@ pub fn random_symmetric_range<R: MutRandState>(rng: &mut R, max: &Integer) -> In
     Integer::from(-max) + Integer::from(2 * max).random_below(rng)
 }
 
+/// Splits x into (q, r) such that x = q*l + r and 0 <= r < l, as used by the
+/// Wesolowski proof of exponentiation.
+pub fn poe_split(x: &Integer, l: &Integer) -> (Integer, Integer) {
+    x.clone().div_rem_floor(l.clone())
+}
+
 pub fn bytes_big_endian_to_bits_big_endian(bytes: &[u8]) -> Vec<bool> {
     let mut bits = vec![];
     for b in bytes {
@@ -49,23 +89,31 @@ pub fn integer_to_bytes(num: &Integer) -> Vec<u8> {
     bytes
 }
 
+/// Converts an integer to a scalar, directly for integers in [0, q) and
+/// through the bits of its absolute value otherwise.
 pub fn integer_to_bigint<P: CurvePointProjective>(num: &Integer) -> P::ScalarField {
-    let bytes = integer_to_bytes(num);
-    let bits = bytes_big_endian_to_bits_big_endian(&bytes);
-    P::ScalarField::from_bits(&bits)
+    P::ScalarField::from_integer(num).unwrap_or_else(|| {
+        let bytes = integer_to_bytes(num);
+        let bits = bytes_big_endian_to_bits_big_endian(&bytes);
+        P::ScalarField::from_bits(&bits)
+    })
 }
 
 pub fn integer_mod_q<P: CurvePointProjective>(num: &Integer) -> Result<Integer, Integer> {
     let q = P::ScalarField::modulus();
-    num.clone().pow_mod(&Integer::from(1), &q)
+    Ok(Integer::from(num.rem_euc_ref(&q)))
 }
 
+/// Reduces an integer modulo q and converts it to a scalar, skipping the
+/// reduction for integers already in [0, q).
 pub fn integer_to_bigint_mod_q<P: CurvePointProjective>(
     num: &Integer,
 ) -> Result<P::ScalarField, Integer> {
-    let bytes = integer_to_bytes(&integer_mod_q::<P>(num)?);
-    let bits = bytes_big_endian_to_bits_big_endian(&bytes);
-    Ok(P::ScalarField::from_bits(&bits))
+    if let Some(scalar) = P::ScalarField::from_integer(num) {
+        return Ok(scalar);
+    }
+    let reduced = integer_mod_q::<P>(num)?;
+    P::ScalarField::from_integer(&reduced).ok_or(reduced)
 }
 
 pub fn bigint_to_bytes<P: CurvePointProjective>(num: &P::ScalarField) -> Vec<u8> {
@@ -80,10 +128,25 @@ pub fn bytes_to_integer(bytes: &[u8]) -> Integer {
 }
 
 pub fn bigint_to_integer<P: CurvePointProjective>(num: &P::ScalarField) -> Integer {
-    let bytes = bigint_to_bytes::<P>(num);
-    let mut big = Integer::from(0);
-    big.assign_digits(&bytes, Order::MsfBe);
-    big
+    num.to_integer()
+}
+
+/// Computes a^n modulo an odd modulus with GMP's `mpz_powm_sec`, whose running
+/// time only depends on the sizes of the operands. It requires a positive
+/// exponent, so a^{|n|+1} is computed and the extra a divided out. The inverse
+/// is computed whatever the sign of n, which only selects the result. Returns
+/// `None` if a isn't invertible modulo the modulus.
+pub(crate) fn secure_pow_mod(a: &Integer, n: &Integer, modulus: &Integer) -> Option<Integer> {
+    let exponent = Integer::from(n.abs_ref()) + 1;
+    let power = Integer::from(a.secure_pow_mod_ref(&exponent, modulus));
+    let a_inv = Integer::from(a.invert_ref(modulus)?);
+    let power = power * a_inv % modulus;
+    let inverse = Integer::from(power.invert_ref(modulus)?);
+    if *n < 0 {
+        Some(inverse)
+    } else {
+        Some(power)
+    }
 }
 
 pub fn log2(x: usize) -> u32 {
@@ -95,10 +158,60 @@ pub fn log2(x: usize) -> u32 {
     core::mem::size_of::<usize>() as u32 * 8 - n
 }
 
+/// Overwrites all the limbs allocated for the integer and sets it to zero.
+/// Copies GMP left behind when it reallocated the integer, or in
+/// temporaries, are not reached.
+pub fn zeroize_integer(integer: &mut Integer) {
+    let limbs = integer.capacity() / gmp_mpfr_sys::gmp::LIMB_BITS as usize;
+    if limbs == 0 {
+        return;
+    }
+    unsafe {
+        let raw = integer.as_raw_mut();
+        let data = gmp_mpfr_sys::gmp::mpz_limbs_write(raw, limbs as gmp_mpfr_sys::gmp::size_t);
+        std::slice::from_raw_parts_mut(data, limbs).zeroize();
+        gmp_mpfr_sys::gmp::mpz_limbs_finish(raw, 0);
+    }
+}
+
+/// Formats a secret integer for `Debug` as its bit length only, so that
+/// witnesses can be logged without leaking them.
+pub(crate) struct RedactedInteger<'a>(pub &'a Integer);
+
+impl fmt::Debug for RedactedInteger<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} bits>", self.0.significant_bits())
+    }
+}
+
+/// Formats secret integers for `Debug` as their number and bit lengths.
+pub(crate) struct RedactedIntegers<'a>(pub &'a [Integer]);
+
+impl fmt::Debug for RedactedIntegers<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(RedactedInteger))
+            .finish()
+    }
+}
+
+/// Formats any other secret, e.g., a group element, for `Debug` as a
+/// placeholder.
+pub(crate) struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
 #[cfg(all(test, feature = "arkworks"))]
 mod test {
-    use crate::utils::{bigint_to_integer, integer_to_bigint};
-    use ark_bls12_381::G1Projective;
+    use crate::utils::{
+        bigint_to_integer, curve::Field, integer_to_bigint, integer_to_bigint_mod_q,
+        zeroize_integer,
+    };
+    use ark_bls12_381::{Fr, G1Projective};
     use rug::Integer;
 
     #[test]
@@ -108,4 +221,27 @@ mod test {
         let int2 = bigint_to_integer::<G1Projective>(&big);
         assert_eq!(int, int2);
     }
+
+    #[test]
+    fn test_mod_q() {
+        let q = Fr::modulus();
+        assert_eq!(
+            integer_to_bigint_mod_q::<G1Projective>(&Integer::from(&q + 5)).unwrap(),
+            integer_to_bigint::<G1Projective>(&Integer::from(5))
+        );
+        let minus_one = integer_to_bigint_mod_q::<G1Projective>(&Integer::from(-1)).unwrap();
+        assert_eq!(bigint_to_integer::<G1Projective>(&minus_one), q - 1);
+        assert!(Fr::from_integer(&Fr::modulus()).is_none());
+    }
+
+    #[test]
+    fn test_zeroize_integer() {
+        let mut int = Integer::from(Integer::u_pow_u(2, 300)) - 245;
+        let capacity = int.capacity();
+        zeroize_integer(&mut int);
+        assert_eq!(int, 0);
+        assert_eq!(int.capacity(), capacity);
+        zeroize_integer(&mut int);
+        assert_eq!(int, 0);
+    }
 }
@@ -0,0 +1,25 @@
+//! Pluggable multi-scalar multiplication for the set commitments of the
+//! bilinear-map accumulator and KZG, so that provers committing to large sets
+//! can offload those MSMs, e.g., to a GPU, by implementing `MsmBackend` over
+//! the library of their choice.
+//!
+//! LegoGroth16 proving isn't covered: its MSMs run inside legogro16, which
+//! doesn't take a backend.
+use crate::utils::curve::CurvePointProjective;
+
+pub trait MsmBackend<P: CurvePointProjective> {
+    /// The sum of the bases multiplied by the scalars, which have the same
+    /// length.
+    fn msm(&self, bases: &[P], scalars: &[P::ScalarField]) -> P;
+}
+
+/// Computes the MSMs on the CPU with `CurvePointProjective::msm`, in
+/// parallel with the `parallel` feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuMsm;
+
+impl<P: CurvePointProjective> MsmBackend<P> for CpuMsm {
+    fn msm(&self, bases: &[P], scalars: &[P::ScalarField]) -> P {
+        P::msm(bases, scalars)
+    }
+}
@@ -0,0 +1,85 @@
+//! Random number generators derived from a seed, for setups that have to be
+//! reproduced bit for bit, e.g., the fixtures of integration tests.
+//!
+//! Both generators are ChaCha20 streams of the seed, on different stream
+//! numbers, so that the randomness of the group of unknown order and of the
+//! curve is independent. Unlike `StdRng`, ChaCha20 is guaranteed to produce
+//! the same stream across versions and platforms.
+//!
+//! `secure_rngs` derives the same pair from a caller-provided secure
+//! generator, so that a single source is responsible for the randomness of
+//! both, and GMP draws from ChaCha20 rather than its default Mersenne
+//! Twister, whose output can be predicted from a few samples.
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rug::rand::{RandGen, RandState};
+use zeroize::Zeroize;
+
+pub const GROUP_STREAM: u64 = 0;
+pub const CURVE_STREAM: u64 = 1;
+
+/// Feeds a ChaCha20 stream to GMP.
+struct ChaChaRandGen(ChaCha20Rng);
+
+impl RandGen for ChaChaRandGen {
+    fn gen(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+}
+
+pub fn chacha_stream(seed: [u8; 32], stream: u64) -> ChaCha20Rng {
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+/// The generators to pass to `setup`: one for the group of unknown order and
+/// one for the curve.
+pub fn seeded_rngs(seed: [u8; 32]) -> (RandState<'static>, ChaCha20Rng) {
+    (
+        RandState::new_custom_boxed(Box::new(ChaChaRandGen(chacha_stream(seed, GROUP_STREAM)))),
+        chacha_stream(seed, CURVE_STREAM),
+    )
+}
+
+/// The generators to pass to `setup` and the provers, seeded from the
+/// caller's generator.
+pub fn secure_rngs<R: RngCore + CryptoRng>(rng: &mut R) -> (RandState<'static>, ChaCha20Rng) {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    let rngs = seeded_rngs(seed);
+    seed.zeroize();
+    rngs
+}
+
+/// A generator seeded from the caller's, for the branches of a `join`, which
+/// can't share it.
+pub fn fork_rng<R: RngCore + CryptoRng>(rng: &mut R) -> ChaCha20Rng {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    let forked = ChaCha20Rng::from_seed(seed);
+    seed.zeroize();
+    forked
+}
+
+#[cfg(test)]
+mod test {
+    use super::{secure_rngs, seeded_rngs};
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use rug::Integer;
+
+    #[test]
+    fn test_secure_rngs() {
+        let mut seed = [0u8; 32];
+        ChaCha20Rng::from_seed([7u8; 32]).fill_bytes(&mut seed);
+        let (mut expected1, mut expected2) = seeded_rngs(seed);
+        let (mut rng1, mut rng2) = secure_rngs(&mut ChaCha20Rng::from_seed([7u8; 32]));
+        let bound = Integer::from(Integer::u_pow_u(2, 256));
+        assert_eq!(
+            Integer::from(bound.random_below_ref(&mut rng1)),
+            Integer::from(bound.random_below_ref(&mut expected1))
+        );
+        assert_eq!(rng2.next_u64(), expected2.next_u64());
+    }
+}
@@ -0,0 +1,349 @@
+//! RSA groups with a modulus produced by a distributed generation ceremony.
+//!
+//! The ceremony (e.g., Boneh-Franklin style) is run outside of this crate. The
+//! participants publish the modulus together with the values of the
+//! distributed biprimality test, where the test bases are derived from the
+//! modulus itself. `validate_ceremony` checks the published transcript, and
+//! once it passes, the modulus can be imported by implementing `RsaModulus`,
+//! making `RsaGroup` usable in any of the protocol setups.
+use crate::utils::{
+    bytes_to_integer,
+    group::{ElemFromBytes, UnknownOrderGroup},
+    integer_to_bytes, secure_pow_mod,
+};
+use blake2::{Blake2s, Digest};
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use rand::{CryptoRng, RngCore};
+use rug::integer::Order;
+use rug::Integer;
+use thiserror::Error;
+
+/// Trial division bound for the ceremony modulus.
+const SMALL_PRIMES_BOUND: u32 = 1 << 16;
+
+#[derive(Debug, Error)]
+pub enum CeremonyError {
+    #[error("invalid modulus")]
+    InvalidModulus,
+    #[error("the modulus has the small factor {0}")]
+    SmallFactor(u32),
+    #[error("not enough rounds of the biprimality test")]
+    NotEnoughRounds,
+    #[error("invalid number of shares")]
+    InvalidNumberOfShares,
+    #[error("the biprimality test failed in round {0}")]
+    BiprimalityTestFailed(usize),
+}
+
+/// The values published by the participants for one round of the distributed
+/// biprimality test with base g. The first participant, holding p_1 = q_1 = 3
+/// mod 4, publishes g^{(N - p_1 - q_1 + 1)/4} and each of the others, holding
+/// p_i = q_i = 0 mod 4, publishes g^{(p_i + q_i)/4}.
+#[derive(Clone, Debug)]
+pub struct BiprimalityRound {
+    pub shares: Vec<Integer>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CeremonyTranscript {
+    pub modulus: Integer,
+    pub participants: usize,
+    pub rounds: Vec<BiprimalityRound>,
+}
+
+/// Derives the base for the given round of the biprimality test, so that the
+/// participants can't choose it after the modulus is fixed.
+pub fn biprimality_base(modulus: &Integer, round: usize) -> Integer {
+    let modulus_bytes = integer_to_bytes(modulus);
+    let mut counter = 0u32;
+    loop {
+        // Expand to twice the modulus length to make the reduction unbiased.
+        let mut expanded = vec![];
+        let mut block = 0u32;
+        while expanded.len() < 2 * modulus_bytes.len() {
+            let mut hasher = Blake2s::default();
+            hasher.update(b"cpsnarks-set rsa ceremony");
+            hasher.update(&modulus_bytes);
+            hasher.update(&(round as u32).to_le_bytes());
+            hasher.update(&counter.to_le_bytes());
+            hasher.update(&block.to_le_bytes());
+            expanded.extend_from_slice(&hasher.finalize());
+            block += 1;
+        }
+        let base = Integer::from_digits(&expanded, Order::MsfBe) % modulus;
+        if base > 1 && base.jacobi(modulus) == 1 {
+            return base;
+        }
+        counter += 1;
+    }
+}
+
+/// Validates a ceremony transcript for a modulus of `modulus_bits` bits. Each
+/// round of the biprimality test is passed by a modulus with more than two
+/// prime factors with probability at most 1/2, so `min_rounds` should be the
+/// soundness security level. This only checks the published values are
+/// consistent; binding them to the participants' shares, e.g., by
+/// commitments, is up to the ceremony.
+pub fn validate_ceremony(
+    transcript: &CeremonyTranscript,
+    modulus_bits: u32,
+    min_rounds: usize,
+) -> Result<(), CeremonyError> {
+    let n = &transcript.modulus;
+    // Since p = q = 3 mod 4, N = 1 mod 4.
+    if n.significant_bits() != modulus_bits
+        || n.mod_u(4) != 1
+        || n.is_perfect_square()
+        || transcript.participants < 2
+    {
+        return Err(CeremonyError::InvalidModulus);
+    }
+
+    let mut p = Integer::from(2);
+    while p < SMALL_PRIMES_BOUND {
+        if n.is_divisible(&p) {
+            return Err(CeremonyError::SmallFactor(p.to_u32().unwrap()));
+        }
+        p.next_prime_mut();
+    }
+
+    if transcript.rounds.len() < min_rounds {
+        return Err(CeremonyError::NotEnoughRounds);
+    }
+    for (i, round) in transcript.rounds.iter().enumerate() {
+        if round.shares.len() != transcript.participants {
+            return Err(CeremonyError::InvalidNumberOfShares);
+        }
+        if round.shares.iter().any(|v| *v <= 0 || v >= n) {
+            return Err(CeremonyError::BiprimalityTestFailed(i));
+        }
+        let others = round.shares[1..]
+            .iter()
+            .fold(Integer::from(1), |product, v| product * v % n);
+        let negated_others = Integer::from(n - &others);
+        if round.shares[0] != others && round.shares[0] != negated_others {
+            return Err(CeremonyError::BiprimalityTestFailed(i));
+        }
+    }
+
+    Ok(())
+}
+
+/// A modulus imported from a validated ceremony.
+pub trait RsaModulus: Clone + Debug {
+    fn modulus() -> Integer;
+}
+
+/// The RSA quotient group Z^*_N / {1, -1}, where each element is represented
+/// by the smaller of x and N - x.
+#[derive(Clone, Debug)]
+pub struct RsaGroup<M: RsaModulus> {
+    _modulus: PhantomData<M>,
+}
+
+impl<M: RsaModulus> RsaGroup<M> {
+    fn reduce(a: Integer) -> Integer {
+        let modulus = M::modulus();
+        let a = a % &modulus + &modulus;
+        let a = a % &modulus;
+        let negated = Integer::from(&modulus - &a);
+        if negated < a {
+            negated
+        } else {
+            a
+        }
+    }
+
+    pub fn elem(a: &Integer) -> Integer {
+        Self::reduce(a.clone())
+    }
+}
+
+impl<M: RsaModulus> UnknownOrderGroup for RsaGroup<M> {
+    type Elem = Integer;
+
+    fn id() -> Integer {
+        Integer::from(1)
+    }
+
+    fn op(a: &Integer, b: &Integer) -> Integer {
+        Self::reduce(Integer::from(a * b))
+    }
+
+    fn exp(a: &Integer, n: &Integer) -> Integer {
+        let modulus = M::modulus();
+        Self::reduce(Integer::from(a.pow_mod_ref(n, &modulus).unwrap()))
+    }
+
+    fn inv(a: &Integer) -> Integer {
+        let modulus = M::modulus();
+        Self::reduce(Integer::from(a.invert_ref(&modulus).unwrap()))
+    }
+
+    /// Uses `secure_pow_mod`, whose running time doesn't depend on the value
+    /// or the sign of the exponent. The modulus is odd, as it requires.
+    fn exp_secret<R: RngCore + CryptoRng>(
+        a: &Integer,
+        n: &Integer,
+        _rng: &mut R,
+    ) -> Option<Integer> {
+        secure_pow_mod(a, n, &M::modulus()).map(Self::reduce)
+    }
+
+    fn unknown_order_elem() -> Integer {
+        Integer::from(2)
+    }
+
+    /// The order of the quotient group is phi(N)/2 < N/2.
+    fn order_upper_bound() -> Integer {
+        M::modulus() / 2
+    }
+
+    fn elem_to_bytes(a: &Integer) -> Vec<u8> {
+        integer_to_bytes(a)
+    }
+}
+
+impl<M: RsaModulus> ElemFromBytes for RsaGroup<M> {
+    fn elem_from_bytes(bytes: &[u8]) -> Option<Integer> {
+        let a = bytes_to_integer(bytes);
+        if a == 0 || a >= M::modulus() {
+            return None;
+        }
+        // Only the representative in [1, N/2] is accepted, so each element
+        // has a single encoding.
+        let reduced = Self::reduce(a.clone());
+        if reduced != a {
+            return None;
+        }
+        Some(reduced)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        biprimality_base, validate_ceremony, BiprimalityRound, CeremonyError, CeremonyTranscript,
+        RsaGroup, RsaModulus,
+    };
+    use crate::utils::{
+        group::{ElemFromBytes, UnknownOrderGroup},
+        integer_to_bytes,
+    };
+    use rand::thread_rng;
+    use rug::rand::RandState;
+    use rug::Integer;
+
+    const P: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639747";
+    const Q: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639579";
+
+    #[derive(Clone, Debug)]
+    struct TestModulus;
+
+    impl RsaModulus for TestModulus {
+        fn modulus() -> Integer {
+            Integer::from_str_radix(P, 10).unwrap() * Integer::from_str_radix(Q, 10).unwrap()
+        }
+    }
+
+    fn ceremony(p: &Integer, q: &Integer, rounds: usize) -> CeremonyTranscript {
+        let mut rng = RandState::new();
+        rng.seed(&Integer::from(13));
+        let modulus = Integer::from(p * q);
+        // The second participant holds shares that are 0 mod 4.
+        let p_2 = Integer::from(p >> 2).random_below(&mut rng) * 4;
+        let q_2 = Integer::from(q >> 2).random_below(&mut rng) * 4;
+        let p_1 = Integer::from(p - &p_2);
+        let q_1 = Integer::from(q - &q_2);
+        let exponent_1 = Integer::from(&modulus - &p_1) - &q_1 + 1;
+        let exponent_2 = p_2 + q_2;
+        let rounds = (0..rounds)
+            .map(|i| {
+                let g = biprimality_base(&modulus, i);
+                BiprimalityRound {
+                    shares: vec![
+                        g.clone()
+                            .pow_mod(&(exponent_1.clone() / 4), &modulus)
+                            .unwrap(),
+                        g.pow_mod(&(exponent_2.clone() / 4), &modulus).unwrap(),
+                    ],
+                }
+            })
+            .collect();
+        CeremonyTranscript {
+            modulus,
+            participants: 2,
+            rounds,
+        }
+    }
+
+    #[test]
+    fn test_validate_ceremony() {
+        let p = Integer::from_str_radix(P, 10).unwrap();
+        let q = Integer::from_str_radix(Q, 10).unwrap();
+        let transcript = ceremony(&p, &q, 16);
+        validate_ceremony(&transcript, 512, 16).unwrap();
+        assert!(matches!(
+            validate_ceremony(&transcript, 512, 17),
+            Err(CeremonyError::NotEnoughRounds)
+        ));
+        assert!(matches!(
+            validate_ceremony(&transcript, 1024, 16),
+            Err(CeremonyError::InvalidModulus)
+        ));
+
+        let mut tampered = transcript;
+        tampered.rounds[3].shares[1] += 1;
+        assert!(matches!(
+            validate_ceremony(&tampered, 512, 16),
+            Err(CeremonyError::BiprimalityTestFailed(3))
+        ));
+    }
+
+    #[test]
+    fn test_rsa_group() {
+        let g = RsaGroup::<TestModulus>::unknown_order_elem();
+        let g_3 = RsaGroup::<TestModulus>::exp(&g, &Integer::from(3));
+        assert_eq!(g_3, Integer::from(8));
+        assert_eq!(
+            RsaGroup::<TestModulus>::op(&g_3, &RsaGroup::<TestModulus>::inv(&g_3)),
+            RsaGroup::<TestModulus>::id()
+        );
+        assert_eq!(
+            RsaGroup::<TestModulus>::exp(&g, &Integer::from(-3)),
+            RsaGroup::<TestModulus>::inv(&g_3)
+        );
+        for n in &[
+            Integer::from(0),
+            Integer::from(3),
+            Integer::from(-3),
+            Integer::from(Integer::u_pow_u(2, 130)) + 7,
+        ] {
+            assert_eq!(
+                RsaGroup::<TestModulus>::exp_secret(&g, n, &mut thread_rng()).unwrap(),
+                RsaGroup::<TestModulus>::exp(&g, n)
+            );
+        }
+        // A base sharing a factor with the modulus isn't invertible.
+        assert!(RsaGroup::<TestModulus>::exp_secret(
+            &Integer::from(0),
+            &Integer::from(3),
+            &mut thread_rng()
+        )
+        .is_none());
+        let minus_one = TestModulus::modulus() - Integer::from(1);
+        assert_eq!(
+            RsaGroup::<TestModulus>::elem(&minus_one),
+            RsaGroup::<TestModulus>::id()
+        );
+        // -1 has to be encoded as its representative 1.
+        assert!(RsaGroup::<TestModulus>::elem_from_bytes(&integer_to_bytes(&minus_one)).is_none());
+        assert_eq!(
+            RsaGroup::<TestModulus>::elem_from_bytes(&integer_to_bytes(&Integer::from(1))).unwrap(),
+            RsaGroup::<TestModulus>::id()
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Helpers to implement `CanonicalSerialize` and `CanonicalDeserialize` for
+//! the proofs, whose fields aren't all arkworks types.
+//!
+//! Byte strings are prefixed by their length as a big-endian u32, integers
+//! additionally carry a sign byte, and group elements, curve points and
+//! scalars are written as the byte strings of their usual encodings. Only the
+//! curve points differ between the compressed and uncompressed modes, so the
+//! proofs without them use the same encoding in both.
+//!
+//! Reading is strict: every value has a single accepted encoding, curve points
+//! have to be in the prime-order subgroup, and scalars have to be reduced, so
+//! a malformed proof is rejected with `SerializationError::InvalidData` before
+//! it's verified. The compressed encoding is therefore canonical, and is what
+//! `proof_id` hashes.
+//!
+//! With the `serde` feature, the proofs also implement serde's traits through
+//! the same encoding, as a byte string or, for human-readable formats such as
+//! JSON, a hex string.
+use crate::{
+    commitments::{integer::IntegerCommitment, pedersen::PedersenCommitment},
+    parameters::Parameters,
+    utils::{
+        bigint_to_bytes, bytes_big_endian_to_bits_big_endian, bytes_to_integer,
+        curve::{CurveError, CurvePointProjective, Field},
+        group::ElemFromBytes,
+        integer_to_bytes, ConvertibleUnknownOrderGroup,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use blake2::{Blake2s, Digest};
+use rug::Integer;
+
+impl From<CurveError> for SerializationError {
+    fn from(_: CurveError) -> Self {
+        SerializationError::InvalidData
+    }
+}
+
+/// Byte strings declaring a longer length are rejected before allocating.
+pub const MAX_BYTES_LENGTH: usize = 1 << 24;
+
+pub fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), SerializationError> {
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+pub fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, SerializationError> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_BYTES_LENGTH {
+        return Err(SerializationError::InvalidData);
+    }
+    let mut bytes = vec![0u8; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn bytes_size(length: usize) -> usize {
+    4 + length
+}
+
+pub fn write_integer<W: Write>(
+    writer: &mut W,
+    integer: &Integer,
+) -> Result<(), SerializationError> {
+    writer.write_all(&[(*integer < 0) as u8])?;
+    write_bytes(writer, &integer_to_bytes(&Integer::from(integer.abs_ref())))
+}
+
+pub fn read_integer<R: Read>(reader: &mut R) -> Result<Integer, SerializationError> {
+    let mut sign = [0u8; 1];
+    reader.read_exact(&mut sign)?;
+    let bytes = read_bytes(reader)?;
+    // Leading zeros and negative zero would be other encodings of the same
+    // integer.
+    if bytes.first() == Some(&0) {
+        return Err(SerializationError::InvalidData);
+    }
+    let magnitude = bytes_to_integer(&bytes);
+    match sign[0] {
+        0 => Ok(magnitude),
+        1 if magnitude != 0 => Ok(-magnitude),
+        _ => Err(SerializationError::InvalidData),
+    }
+}
+
+pub fn integer_size(integer: &Integer) -> usize {
+    1 + bytes_size((integer.significant_bits() as usize + 7) / 8)
+}
+
+pub fn write_elem<G: ConvertibleUnknownOrderGroup, W: Write>(
+    writer: &mut W,
+    elem: &G::Elem,
+) -> Result<(), SerializationError> {
+    write_bytes(writer, &G::elem_to_bytes(elem))
+}
+
+pub fn read_elem<G: ElemFromBytes, R: Read>(reader: &mut R) -> Result<G::Elem, SerializationError> {
+    G::elem_from_bytes(&read_bytes(reader)?).ok_or(SerializationError::InvalidData)
+}
+
+pub fn elem_size<G: ConvertibleUnknownOrderGroup>(elem: &G::Elem) -> usize {
+    bytes_size(G::elem_to_bytes(elem).len())
+}
+
+pub fn write_point<P: CurvePointProjective, W: Write>(
+    writer: &mut W,
+    point: &P,
+    compressed: bool,
+) -> Result<(), SerializationError> {
+    write_bytes(writer, &point_bytes(point, compressed)?)
+}
+
+pub fn read_point<P: CurvePointProjective, R: Read>(
+    reader: &mut R,
+    compressed: bool,
+) -> Result<P, SerializationError> {
+    let bytes = read_bytes(reader)?;
+    if compressed {
+        Ok(P::from_affine_bytes(&bytes)?)
+    } else {
+        Ok(P::from_affine_bytes_uncompressed(&bytes)?)
+    }
+}
+
+pub fn point_size<P: CurvePointProjective>(point: &P, compressed: bool) -> usize {
+    bytes_size(point_bytes(point, compressed).map(|b| b.len()).unwrap_or(0))
+}
+
+fn point_bytes<P: CurvePointProjective>(
+    point: &P,
+    compressed: bool,
+) -> Result<Vec<u8>, CurveError> {
+    if compressed {
+        point.to_affine_bytes()
+    } else {
+        point.to_affine_bytes_uncompressed()
+    }
+}
+
+pub fn write_scalar<P: CurvePointProjective, W: Write>(
+    writer: &mut W,
+    scalar: &P::ScalarField,
+) -> Result<(), SerializationError> {
+    write_bytes(writer, &bigint_to_bytes::<P>(scalar))
+}
+
+pub fn read_scalar<P: CurvePointProjective, R: Read>(
+    reader: &mut R,
+) -> Result<P::ScalarField, SerializationError> {
+    let bytes = read_bytes(reader)?;
+    if bytes.len() * 8 < P::ScalarField::size_in_bits()
+        || bytes_to_integer(&bytes) >= P::ScalarField::modulus()
+    {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(P::ScalarField::from_bits(
+        &bytes_big_endian_to_bits_big_endian(&bytes),
+    ))
+}
+
+pub fn scalar_size<P: CurvePointProjective>(scalar: &P::ScalarField) -> usize {
+    bytes_size(bigint_to_bytes::<P>(scalar).len())
+}
+
+/// Writes the statement of the membership and nonmembership protocols: the
+/// accumulator, the commitment to the element and the optional epoch.
+pub fn write_set_statement<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective, W: Write>(
+    writer: &mut W,
+    c_p: &G::Elem,
+    c_e_q: &P,
+    epoch: &Option<Vec<u8>>,
+) -> Result<(), SerializationError> {
+    write_elem::<G, _>(writer, c_p)?;
+    write_point(writer, c_e_q, true)?;
+    match epoch {
+        Some(epoch) => {
+            writer.write_all(&[1])?;
+            write_bytes(writer, epoch)
+        }
+        None => Ok(writer.write_all(&[0])?),
+    }
+}
+
+/// Reads a statement written by `write_set_statement`.
+pub fn read_set_statement<G: ElemFromBytes, P: CurvePointProjective, R: Read>(
+    reader: &mut R,
+) -> Result<(G::Elem, P, Option<Vec<u8>>), SerializationError> {
+    let c_p = read_elem::<G, _>(reader)?;
+    let c_e_q = read_point(reader, true)?;
+    let mut has_epoch = [0u8; 1];
+    reader.read_exact(&mut has_epoch)?;
+    let epoch = match has_epoch[0] {
+        0 => None,
+        1 => Some(read_bytes(reader)?),
+        _ => return Err(SerializationError::InvalidData),
+    };
+    Ok((c_p, c_e_q, epoch))
+}
+
+/// Hashes the canonical encodings of a statement and a proof for it, under a
+/// label separating the kinds of proofs.
+pub fn proof_id(label: &[u8], statement: &[u8], proof: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2s::default();
+    for bytes in &[label, statement, proof] {
+        hasher.update(&(bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    }
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&hasher.finalize());
+    id
+}
+
+/// Hashes what a verifier relies on in a CRS: the parameters, the integer and
+/// compressed Pedersen commitment bases and the encoding of the hash-to-prime
+/// verifying key.
+pub fn crs_fingerprint<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective>(
+    parameters: &Parameters,
+    integer_commitment: &IntegerCommitment<G>,
+    pedersen_commitment: &PedersenCommitment<P>,
+    verifying_key: &[u8],
+) -> [u8; 32] {
+    let mut parameters_bytes = vec![];
+    parameters.serialize(&mut parameters_bytes).unwrap();
+    let mut hasher = Blake2s::default();
+    for bytes in &[
+        parameters_bytes,
+        G::elem_to_bytes(&integer_commitment.g),
+        G::elem_to_bytes(&integer_commitment.h),
+        point_bytes(&pedersen_commitment.g, true).unwrap_or_default(),
+        point_bytes(&pedersen_commitment.h, true).unwrap_or_default(),
+        verifying_key.to_vec(),
+    ] {
+        hasher.update(&(bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    }
+    let mut fingerprint = [0u8; 32];
+    fingerprint.copy_from_slice(&hasher.finalize());
+    fingerprint
+}
+
+/// Passes the bytes read through a Blake2s hasher.
+struct DigestReader<R: Read> {
+    inner: R,
+    hasher: Blake2s,
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Passes the bytes written through a Blake2s hasher.
+struct DigestWriter<W: Write> {
+    inner: W,
+    hasher: Blake2s,
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes a value for storage: a byte telling whether its curve points are
+/// compressed, its encoding and the Blake2s digest of both, so that corrupted
+/// or truncated files are detected when reading them.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "serialization::write_checked", level = "debug", skip_all)
+)]
+pub fn write_checked<T: CanonicalSerialize, W: Write>(
+    writer: W,
+    value: &T,
+    compressed: bool,
+) -> Result<(), SerializationError> {
+    let mut writer = DigestWriter {
+        inner: writer,
+        hasher: Blake2s::default(),
+    };
+    writer.write_all(&[compressed as u8])?;
+    if compressed {
+        value.serialize(&mut writer)?;
+    } else {
+        value.serialize_uncompressed(&mut writer)?;
+    }
+    let digest = writer.hasher.finalize();
+    writer.inner.write_all(&digest)?;
+    Ok(())
+}
+
+/// Reads a value written by `write_checked`, rejecting it if the digest
+/// doesn't match.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "serialization::read_checked", level = "debug", skip_all)
+)]
+pub fn read_checked<T: CanonicalDeserialize, R: Read>(reader: R) -> Result<T, SerializationError> {
+    let mut reader = DigestReader {
+        inner: reader,
+        hasher: Blake2s::default(),
+    };
+    let mut compressed = [0u8];
+    reader.read_exact(&mut compressed)?;
+    let value = match compressed[0] {
+        0 => T::deserialize_uncompressed(&mut reader)?,
+        1 => T::deserialize(&mut reader)?,
+        _ => return Err(SerializationError::InvalidData),
+    };
+    let expected = reader.hasher.finalize();
+    let mut digest = [0u8; 32];
+    reader.inner.read_exact(&mut digest)?;
+    if digest[..] != expected[..] {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(value)
+}
+
+#[cfg(feature = "serde")]
+pub fn serialize_canonical<T: ark_serialize::CanonicalSerialize, S: serde::Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut bytes = vec![];
+    value
+        .serialize(&mut bytes)
+        .map_err(serde::ser::Error::custom)?;
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&to_hex(&bytes))
+    } else {
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub fn deserialize_canonical<
+    'de,
+    T: ark_serialize::CanonicalDeserialize,
+    D: serde::Deserializer<'de>,
+>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    use serde::de::Error;
+    let bytes = if deserializer.is_human_readable() {
+        let hex = <String as serde::Deserialize>::deserialize(deserializer)?;
+        from_hex(&hex).ok_or_else(|| D::Error::custom("invalid hex string"))?
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)?
+    };
+    T::deserialize(&bytes[..]).map_err(D::Error::custom)
+}
+
+#[cfg(feature = "serde")]
+struct BytesVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = vec![];
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(all(test, feature = "arkworks"))]
+mod test {
+    use super::{from_hex, integer_size, read_integer, to_hex, write_integer};
+    use rug::Integer;
+
+    #[test]
+    fn test_integers() {
+        for integer in &[Integer::from(0), Integer::from(-5), Integer::from(1) << 300] {
+            let mut bytes = vec![];
+            write_integer(&mut bytes, integer).unwrap();
+            assert_eq!(bytes.len(), integer_size(integer));
+            assert_eq!(&read_integer(&mut &bytes[..]).unwrap(), integer);
+        }
+        assert!(read_integer(&mut &[2u8, 0, 0, 0, 0][..]).is_err());
+        // Negative zero and leading zeros are rejected.
+        assert!(read_integer(&mut &[1u8, 0, 0, 0, 0][..]).is_err());
+        assert!(read_integer(&mut &[0u8, 0, 0, 0, 2, 0, 1][..]).is_err());
+        assert!(read_integer(&mut &[0u8, 0xff, 0xff, 0xff, 0xff][..]).is_err());
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex(&[0, 15, 255]), "000fff");
+        assert_eq!(from_hex("000fff").unwrap(), vec![0, 15, 255]);
+        assert!(from_hex("0f0").is_none());
+        assert!(from_hex("zz").is_none());
+    }
+}